@@ -0,0 +1,340 @@
+use crate::{
+    config::{find_model, Config},
+    locale::tr,
+    status::Status,
+};
+use imgui::{Condition, Ui};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+use tracing::{error, info};
+use transcribewizard::update::{self, Asset, Release};
+
+// the optional parts this build was made with
+const FEATURES: [(&str, bool); 7] = [
+    ("tesseract", cfg!(feature = "tesseract")),
+    ("whisper", cfg!(feature = "whisper")),
+    ("dictation", cfg!(feature = "dictation")),
+    ("tts", cfg!(feature = "tts")),
+    ("cloud", cfg!(feature = "cloud")),
+    ("camera", cfg!(feature = "camera")),
+    ("archive", cfg!(feature = "archive")),
+];
+
+// One model file and what tells it apart from other versions of it, as rten
+// files carry no version of their own
+struct Model {
+    label: String,
+    path: PathBuf,
+    // size in bytes, the day it was written and the start of its hash, None
+    // when it is missing
+    found: Option<(u64, String, String)>,
+}
+
+impl Model {
+    fn read(label: String, path: PathBuf) -> Self {
+        let found = fs::File::open(&path).ok().and_then(|file| {
+            let metadata = file.metadata().ok()?;
+            let modified = metadata
+                .modified()
+                .map(|modified| {
+                    chrono::DateTime::<chrono::Local>::from(modified)
+                        .format("%Y-%m-%d")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let hash = blake3::Hasher::new().update_reader(file).ok()?.finalize();
+            Some((metadata.len(), modified, hash.to_hex()[..12].to_string()))
+        });
+        Model { label, path, found }
+    }
+}
+
+// read the model files on a worker thread, hashing them takes a while
+fn read_models(config: &Config) -> Receiver<Vec<Model>> {
+    let paths = model_paths(config);
+    let (sender, job) = mpsc::channel();
+    thread::spawn(move || {
+        let models = paths
+            .into_iter()
+            .map(|(label, path)| Model::read(label, path))
+            .collect();
+        let _ = sender.send(models);
+    });
+    job
+}
+
+// the model files of every language, whether in use or not
+fn model_paths(config: &Config) -> Vec<(String, PathBuf)> {
+    let mut models = vec![(tr("Detection").to_string(), config.detection_model_path())];
+    for language in &config.languages {
+        models.push((language.name.clone(), find_model(&language.model)));
+    }
+    models.push((
+        tr("Handwriting").to_string(),
+        find_model(&config.handwriting_model.model),
+    ));
+    let mut seen = Vec::new();
+    models.retain(|(_, path)| {
+        let new = !seen.contains(path);
+        seen.push(path.clone());
+        new
+    });
+    models
+}
+
+enum Check {
+    Idle,
+    Pending {
+        job: Receiver<Result<Release, String>>,
+        // started from the button rather than at startup
+        asked: bool,
+    },
+    Done {
+        release: Release,
+        updates: Vec<(Asset, PathBuf)>,
+    },
+    Failed(String),
+}
+
+// Window with the version of the app and of the model files, and the newest
+// release on GitHub. Looking for it at startup can be turned off, then
+// nothing is fetched unless asked for
+pub struct AboutPanel {
+    // read when the window is first shown and again after a download
+    models: Option<Vec<Model>>,
+    reading: Option<Receiver<Vec<Model>>>,
+    check: Check,
+    // the number of models downloaded once it is done
+    download: Option<Receiver<Result<usize, String>>>,
+    downloaded: Option<usize>,
+}
+
+impl Default for AboutPanel {
+    fn default() -> Self {
+        AboutPanel {
+            models: None,
+            reading: None,
+            check: Check::Idle,
+            download: None,
+            downloaded: None,
+        }
+    }
+}
+
+impl AboutPanel {
+    // look for a newer release on a worker thread, `asked` when the user did
+    pub fn check(&mut self, asked: bool) {
+        let (sender, job) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(update::latest_release().map_err(|err| err.to_string()));
+        });
+        self.check = Check::Pending { job, asked };
+    }
+
+    // pick up the release once fetched, `open` turns true when a check of its
+    // own found something new
+    pub fn poll(&mut self, config: &Config, status: &mut Status, open: &mut bool) {
+        if let Check::Pending { job, asked } = &self.check {
+            let asked = *asked;
+            self.check = match job.try_recv() {
+                Ok(Ok(release)) => {
+                    let models: Vec<PathBuf> = model_paths(config)
+                        .into_iter()
+                        .map(|(_, path)| path)
+                        .collect();
+                    let updates = release.model_updates(&models);
+                    if !asked && (release.is_newer() || !updates.is_empty()) {
+                        info!("Update available: {}", release.tag);
+                        *open = true;
+                    }
+                    Check::Done { release, updates }
+                }
+                Ok(Err(err)) => {
+                    // a check nobody asked for fails quietly, e.g. offline
+                    if !asked {
+                        error!("Error checking for updates: {}", err);
+                    }
+                    Check::Failed(err)
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => Check::Failed("worker stopped".into()),
+            };
+        }
+        let Some(download) = &self.download else {
+            return;
+        };
+        match download.try_recv() {
+            Ok(Ok(count)) => {
+                self.downloaded = Some(count);
+                self.models = None;
+                if let Check::Done { updates, .. } = &mut self.check {
+                    updates.clear();
+                }
+            }
+            Ok(Err(err)) => status.error("Error downloading models", err),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                status.error("Error downloading models", "worker stopped")
+            }
+        }
+        self.download = None;
+    }
+
+    // download the newer models in place of the old ones, one after the other
+    fn download(&mut self, updates: Vec<(Asset, PathBuf)>) {
+        let (sender, job) = mpsc::channel();
+        thread::spawn(move || {
+            let result = updates
+                .iter()
+                .try_for_each(|(asset, path)| update::download(asset, path))
+                .map(|_| updates.len())
+                .map_err(|err| err.to_string());
+            let _ = sender.send(result);
+        });
+        self.download = Some(job);
+        self.downloaded = None;
+    }
+
+    pub fn draw(&mut self, ui: &Ui, config: &mut Config, status: &mut Status, open: &mut bool) {
+        if self.models.is_none() {
+            let reading = self.reading.get_or_insert_with(|| read_models(config));
+            match reading.try_recv() {
+                Ok(models) => self.models = Some(models),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.models = Some(Vec::new()),
+            }
+            if self.models.is_some() {
+                self.reading = None;
+            }
+        }
+        let mut check = false;
+        let mut download = None;
+        ui.window(format!("{}###About", tr("About")))
+            .size([520.0, 380.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                ui.text(format!("Transcribe Wizard {}", update::VERSION));
+                let features: Vec<&str> = FEATURES
+                    .iter()
+                    .filter(|(_, enabled)| *enabled)
+                    .map(|(name, _)| *name)
+                    .collect();
+                match features.is_empty() {
+                    true => ui.text_disabled(tr("Built without optional features")),
+                    false => ui.text_disabled(format!(
+                        "{} {}",
+                        tr("Built with"),
+                        features.join(", ")
+                    )),
+                }
+
+                ui.separator();
+                ui.text(tr("Models"));
+                let column = ui.current_font_size() * 8.0;
+                if self.models.is_none() {
+                    ui.text_disabled(tr("Reading models..."));
+                }
+                for model in self.models.iter().flatten() {
+                    ui.text(&model.label);
+                    ui.same_line_with_pos(column);
+                    let name = model.path.file_name().unwrap_or_default();
+                    match &model.found {
+                        Some((size, modified, hash)) => ui.text(format!(
+                            "{} - {:.1} MB, {}, {}",
+                            name.to_string_lossy(),
+                            *size as f64 / (1024.0 * 1024.0),
+                            modified,
+                            hash
+                        )),
+                        None => ui.text_disabled(format!(
+                            "{} - {}",
+                            name.to_string_lossy(),
+                            tr("missing")
+                        )),
+                    }
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(model.path.display().to_string());
+                    }
+                }
+
+                ui.separator();
+                if ui.checkbox(
+                    tr("Check for updates at startup"),
+                    &mut config.check_updates,
+                ) {
+                    if let Err(err) = config.save() {
+                        error!("Error saving config: {}", err);
+                    }
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr(
+                        "Asks GitHub for the newest release, nothing about you or your text is sent",
+                    ));
+                }
+                let checking = matches!(self.check, Check::Pending { .. });
+                {
+                    let _disabled = ui.begin_disabled(checking);
+                    check = ui.button(tr("Check now"));
+                }
+                ui.same_line();
+                match &self.check {
+                    Check::Idle => ui.text_disabled(tr("Not checked yet")),
+                    Check::Pending { .. } => ui.text_disabled(tr("Checking...")),
+                    Check::Failed(err) => ui.text_colored(
+                        [0.9, 0.3, 0.3, 1.0],
+                        format!("{}: {}", tr("Error checking for updates"), err),
+                    ),
+                    Check::Done { release, updates } => {
+                        if release.is_newer() {
+                            ui.text(format!("{} {}", tr("A new version is out:"), release.tag));
+                            if ui.button(tr("Open release page")) {
+                                if let Err(err) = open::that_detached(&release.url) {
+                                    status.error("Error opening link", err);
+                                }
+                            }
+                        } else {
+                            ui.text(tr("This is the newest version"));
+                        }
+                        if !updates.is_empty() {
+                            let names: Vec<&str> =
+                                updates.iter().map(|(asset, _)| asset.name.as_str()).collect();
+                            ui.text_wrapped(format!(
+                                "{} {}",
+                                tr("Newer models:"),
+                                names.join(", ")
+                            ));
+                            let _disabled = ui.begin_disabled(self.download.is_some());
+                            if ui.button(format!(
+                                "{} ({})",
+                                tr("Download models"),
+                                updates.len()
+                            )) {
+                                download = Some(updates.clone());
+                            }
+                        }
+                    }
+                }
+                if self.download.is_some() {
+                    ui.text_disabled(tr("Downloading..."));
+                }
+                if let Some(count) = self.downloaded {
+                    ui.text(format!(
+                        "{} {}, {}",
+                        tr("Models downloaded:"),
+                        count,
+                        tr("they load with \"Reload models\" or at the next start")
+                    ));
+                }
+            });
+        if check {
+            self.check(true);
+        }
+        if let Some(updates) = download {
+            self.download(updates);
+        }
+    }
+}
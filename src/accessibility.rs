@@ -0,0 +1,148 @@
+use accesskit::{
+    ActionHandler, ActionRequest, ActivationHandler, Live, Node, NodeId, Role, TreeId, TreeInfo,
+    TreeUpdate,
+};
+use sdl2::video::Window;
+use std::sync::{Arc, Mutex};
+
+const WINDOW: NodeId = NodeId(0);
+const RESULT: NodeId = NodeId(1);
+
+// the whole accessibility tree, the window holding the last result as a live
+// region so that screen readers speak it once it changes
+fn tree(result: &str) -> TreeUpdate {
+    let mut window = Node::new(Role::Window);
+    window.set_label("Transcribe Wizard");
+    window.set_children(vec![RESULT]);
+    let mut text = Node::new(Role::Label);
+    text.set_label("Last result");
+    text.set_value(result);
+    text.set_live(Live::Polite);
+    TreeUpdate {
+        nodes: vec![(WINDOW, window), (RESULT, text)],
+        tree: Some(TreeInfo::new(WINDOW)),
+        tree_id: TreeId::ROOT,
+        focus: WINDOW,
+    }
+}
+
+// Builds the tree when a screen reader first asks for it
+struct Activation {
+    result: Arc<Mutex<String>>,
+}
+
+impl ActivationHandler for Activation {
+    fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+        let result = self.result.lock().ok()?;
+        Some(tree(&result))
+    }
+}
+
+// The result can only be read, there is nothing for a screen reader to do to it
+struct NoActions;
+
+impl ActionHandler for NoActions {
+    fn do_action(&mut self, _request: ActionRequest) {}
+}
+
+#[cfg(target_os = "linux")]
+struct NoDeactivation;
+
+#[cfg(target_os = "linux")]
+impl accesskit::DeactivationHandler for NoDeactivation {
+    fn deactivate_accessibility(&mut self) {}
+}
+
+// Mirrors each new result into the platform's accessibility API through
+// AccessKit, the text imgui draws is only pixels to assistive technology
+pub struct ScreenReader {
+    result: Arc<Mutex<String>>,
+    #[cfg(target_os = "linux")]
+    adapter: accesskit_unix::Adapter,
+    #[cfg(windows)]
+    adapter: accesskit_windows::SubclassingAdapter,
+    #[cfg(target_os = "macos")]
+    adapter: accesskit_macos::SubclassingAdapter,
+}
+
+impl ScreenReader {
+    // must come before the window is shown for the first time, Windows and
+    // macOS only hand out the accessibility of a window that was never shown
+    #[cfg(target_os = "linux")]
+    pub fn new(_window: &Window) -> Result<Self, String> {
+        let result = Arc::new(Mutex::new(String::new()));
+        let activation = Activation {
+            result: result.clone(),
+        };
+        let adapter = accesskit_unix::Adapter::new(activation, NoActions, NoDeactivation);
+        Ok(ScreenReader { result, adapter })
+    }
+
+    #[cfg(windows)]
+    pub fn new(window: &Window) -> Result<Self, String> {
+        use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+        let handle = window.window_handle().map_err(|err| err.to_string())?;
+        let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+            return Err("Not a Win32 window".into());
+        };
+        let hwnd = accesskit_windows::HWND(handle.hwnd.get() as *mut std::ffi::c_void);
+        let result = Arc::new(Mutex::new(String::new()));
+        let activation = Activation {
+            result: result.clone(),
+        };
+        let adapter = accesskit_windows::SubclassingAdapter::new(hwnd, activation, NoActions);
+        Ok(ScreenReader { result, adapter })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn new(window: &Window) -> Result<Self, String> {
+        use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+        let handle = window.window_handle().map_err(|err| err.to_string())?;
+        let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+            return Err("Not an AppKit window".into());
+        };
+        let result = Arc::new(Mutex::new(String::new()));
+        let activation = Activation {
+            result: result.clone(),
+        };
+        // SAFETY: the view belongs to the window, which outlives the adapter
+        let adapter = unsafe {
+            accesskit_macos::SubclassingAdapter::new(handle.ns_view.as_ptr(), activation, NoActions)
+        };
+        Ok(ScreenReader { result, adapter })
+    }
+
+    #[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+    pub fn new(_window: &Window) -> Result<Self, String> {
+        Err("Screen readers are not supported on this platform".into())
+    }
+
+    // put `text` in the live region, read out by a screen reader that is running
+    pub fn announce(&mut self, text: &str) {
+        if let Ok(mut result) = self.result.lock() {
+            *result = text.to_string();
+        }
+        let update = || tree(text);
+        #[cfg(target_os = "linux")]
+        self.adapter.update_if_active(update);
+        #[cfg(any(windows, target_os = "macos"))]
+        if let Some(events) = self.adapter.update_if_active(update) {
+            events.raise();
+        }
+        #[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+        let _ = update;
+    }
+
+    // AT-SPI and macOS are told when the window gains or loses the focus, on
+    // Windows the adapter sees the window messages itself
+    pub fn set_focused(&mut self, focused: bool) {
+        #[cfg(target_os = "linux")]
+        self.adapter.update_window_focus_state(focused);
+        #[cfg(target_os = "macos")]
+        if let Some(events) = self.adapter.update_view_focus_state(focused) {
+            events.raise();
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        let _ = focused;
+    }
+}
@@ -0,0 +1,107 @@
+use crate::ocr::{Bounds, TranscriptionResult};
+use ab_glyph::{FontRef, PxScale};
+use image::{DynamicImage, Pixel, Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_hollow_rect_mut, draw_text_mut, text_size},
+    rect::Rect,
+};
+
+// words read with less confidence than this are worth a second look
+const SUSPICIOUS_CONFIDENCE: f32 = 0.8;
+
+const BOX_COLOR: [u8; 3] = [40, 110, 230];
+const SUSPICIOUS_COLOR: [u8; 3] = [240, 120, 0];
+const BOX_FILL: u8 = 48;
+const LABEL_BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 220]);
+const LABEL_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+// label text height in pixels, following the height of the line it names
+const MIN_LABEL: f32 = 10.0;
+const MAX_LABEL: f32 = 32.0;
+
+/// `image` with a translucent box over every recognized line and the text
+/// read from it written on a label above, to see at a glance what the engine
+/// made of each part.
+///
+/// Lines the engine was less than 80% sure about are boxed in orange instead
+/// of blue. The labels use a font with Latin, Greek and Cyrillic letters,
+/// other scripts come out as empty boxes.
+pub fn render(image: &DynamicImage, transcription: &TranscriptionResult) -> RgbaImage {
+    let font = FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)
+        .expect("the bundled font is valid");
+    let mut canvas = image.to_rgba8();
+    // the boxes are where the recognized image had them, which may have been scaled
+    let scale_x = canvas.width() as f32 / transcription.width.max(1) as f32;
+    let scale_y = canvas.height() as f32 / transcription.height.max(1) as f32;
+    let scaled = |bounds: Bounds| {
+        (
+            (bounds.left as f32 * scale_x) as i32,
+            (bounds.top as f32 * scale_y) as i32,
+            ((bounds.right - bounds.left) as f32 * scale_x).max(1.0) as u32,
+            ((bounds.bottom - bounds.top) as f32 * scale_y).max(1.0) as u32,
+        )
+    };
+
+    for line in &transcription.lines {
+        let (left, top, width, height) = scaled(line.bounds);
+        let suspicious = line
+            .confidence
+            .is_some_and(|confidence| confidence < SUSPICIOUS_CONFIDENCE);
+        let [red, green, blue] = if suspicious {
+            SUSPICIOUS_COLOR
+        } else {
+            BOX_COLOR
+        };
+        fill(
+            &mut canvas,
+            left,
+            top,
+            width,
+            height,
+            Rgba([red, green, blue, BOX_FILL]),
+        );
+        let rect = Rect::at(left, top).of_size(width, height);
+        draw_hollow_rect_mut(&mut canvas, rect, Rgba([red, green, blue, 255]));
+    }
+
+    // labels go on top of every box, so a line's label is never hidden by the next one
+    for line in &transcription.lines {
+        let text = line.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let (left, top, _, height) = scaled(line.bounds);
+        let size = PxScale::from((height as f32 * 0.6).clamp(MIN_LABEL, MAX_LABEL));
+        let (label_width, label_height) = text_size(size, &font, text);
+        let label_top = (top - label_height as i32 - 2).max(0);
+        fill(
+            &mut canvas,
+            left,
+            label_top,
+            label_width + 4,
+            label_height + 2,
+            LABEL_BACKGROUND,
+        );
+        draw_text_mut(
+            &mut canvas,
+            LABEL_COLOR,
+            left + 2,
+            label_top,
+            size,
+            &font,
+            text,
+        );
+    }
+    canvas
+}
+
+// blend `color` over a rectangle of the canvas, clipped to its edges
+fn fill(canvas: &mut RgbaImage, left: i32, top: i32, width: u32, height: u32, color: Rgba<u8>) {
+    let right = (left + width as i32).min(canvas.width() as i32);
+    let bottom = (top + height as i32).min(canvas.height() as i32);
+    for y in top.max(0)..bottom {
+        for x in left.max(0)..right {
+            canvas.get_pixel_mut(x as u32, y as u32).blend(&color);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    locale::tr,
+    status::Status,
+};
+use imgui::{Condition, Ui};
+use transcribewizard::anonymize::{self, Anonymized, Preset};
+
+// Window with a copy of the text that has its names, contacts and numbers
+// replaced by placeholders, to share a transcript for support or as training
+// data. The text itself is left as it is
+#[derive(Default)]
+pub struct AnonymizationPanel {
+    preset: Option<Preset>,
+    // the text the copy was made of, made again once it changed
+    source: String,
+    anonymized: Option<Anonymized>,
+}
+
+impl AnonymizationPanel {
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        text: &str,
+        clipboard: &SystemClipboard,
+        status: &mut Status,
+        open: &mut bool,
+    ) {
+        if let Some(preset) = self.preset {
+            if self.anonymized.is_none() || self.source != text {
+                self.source = text.to_string();
+                self.anonymized = Some(anonymize::anonymize(text, &preset.options()));
+            }
+        }
+        ui.window(format!("{}###Anonymize", tr("Anonymize")))
+            .size([600.0, 420.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                for (index, preset) in Preset::ALL.into_iter().enumerate() {
+                    if index > 0 {
+                        ui.same_line();
+                    }
+                    let picked = self.preset == Some(preset);
+                    if ui.radio_button_bool(tr(preset.label()), picked) && !picked {
+                        self.preset = Some(preset);
+                        self.anonymized = None;
+                    }
+                }
+                let Some(anonymized) = &mut self.anonymized else {
+                    ui.text_disabled(tr("Pick what to replace with placeholders"));
+                    return;
+                };
+                let summary = anonymized.summary();
+                match summary.is_empty() {
+                    true => ui.text_disabled(tr("Nothing to replace")),
+                    false => ui.text(format!("{} {}", tr("Replaced"), summary)),
+                }
+                ui.same_line();
+                if ui.button(tr("Copy")) {
+                    if let Err(err) = clipboard.set_text(anonymized.text.clone()) {
+                        status.error("Error setting text to clipboard", err);
+                    }
+                }
+                ui.text_disabled(tr("Names are guessed, check the copy before sharing it"));
+                ui.input_text_multiline("##anonymized", &mut anonymized.text, [-1.0, -1.0])
+                    .read_only(true)
+                    .build();
+            });
+    }
+}
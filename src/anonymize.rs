@@ -0,0 +1,494 @@
+use crate::{
+    links::{self, regex, LinkKind},
+    secrets::{self, SecretKind},
+};
+use regex::Regex;
+use std::{ops::Range, sync::OnceLock};
+
+// titles a name follows, with or without a dot
+const TITLES: &[&str] = &[
+    "Mr", "Mrs", "Ms", "Miss", "Mx", "Dr", "Prof", "Sir", "Madam", "Herr", "Frau", "Sr", "Sra",
+    "Srta", "Don", "Doña", "Mme", "Mlle", "Monsieur", "Madame",
+];
+
+// words a name follows in greetings, "Hello Mark"
+const GREETINGS: &[&str] = &["Dear", "Hello", "Hi", "Hey"];
+
+// capitalized words that start sentences, greetings and headings far more
+// often than they are part of a name
+const COMMON: &[&str] = &[
+    "A",
+    "About",
+    "After",
+    "All",
+    "Also",
+    "An",
+    "And",
+    "Any",
+    "As",
+    "At",
+    "Best",
+    "But",
+    "By",
+    "Cheers",
+    "Dear",
+    "Do",
+    "For",
+    "From",
+    "Good",
+    "Hello",
+    "Hey",
+    "Hi",
+    "How",
+    "I",
+    "If",
+    "In",
+    "Is",
+    "It",
+    "Kind",
+    "My",
+    "No",
+    "Not",
+    "Of",
+    "On",
+    "Or",
+    "Our",
+    "Please",
+    "Regards",
+    "See",
+    "She",
+    "He",
+    "Sincerely",
+    "So",
+    "Thank",
+    "Thanks",
+    "The",
+    "Then",
+    "There",
+    "These",
+    "They",
+    "This",
+    "To",
+    "Today",
+    "Tomorrow",
+    "We",
+    "What",
+    "When",
+    "Where",
+    "Which",
+    "Who",
+    "Why",
+    "With",
+    "Yes",
+    "Yesterday",
+    "You",
+    "Your",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+// fields of forms and mail headers whose value is a name
+const NAME_FIELDS: &str = r"(?im)^[ \t]*(?:name|full name|first name|last name|surname|from|to|cc|attn|patient|customer|contact|signed)[ \t]*:[ \t]*([^\n<,]+)";
+
+// runs of capitalized words on one line, a middle initial allowed between
+const CAPITALIZED: &str = r"\p{Lu}\p{Ll}+(?:[-'’]\p{Lu}?\p{Ll}+)*(?:[ \t]+(?:\p{Lu}\.[ \t]+)?\p{Lu}\p{Ll}+(?:[-'’]\p{Lu}?\p{Ll}+)*)*";
+
+// numbers of two digits or more with their separators, or one digit standing
+// alone as in a house number
+const NUMBER: &str = r"\b\d(?:[\d.,/:-]*\d)?\b";
+
+/// What [`anonymize`] found and replaced.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    /// API keys, tokens, private keys and card numbers as [`secrets`] finds them.
+    Secret,
+    Email,
+    Phone,
+    Link,
+    Name,
+    Number,
+}
+
+impl Kind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::Secret => "secrets",
+            Kind::Email => "emails",
+            Kind::Phone => "phone numbers",
+            Kind::Link => "links",
+            Kind::Name => "names",
+            Kind::Number => "numbers",
+        }
+    }
+
+    // what the placeholders of the kind are called before their number
+    fn placeholder(self) -> &'static str {
+        match self {
+            Kind::Secret => "SECRET",
+            Kind::Email => "EMAIL",
+            Kind::Phone => "PHONE",
+            Kind::Link => "LINK",
+            Kind::Name => "NAME",
+            Kind::Number => "NUMBER",
+        }
+    }
+}
+
+/// Which kinds of personal data [`anonymize`] replaces.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Options {
+    pub names: bool,
+    pub emails: bool,
+    pub phones: bool,
+    pub links: bool,
+    pub numbers: bool,
+    pub secrets: bool,
+}
+
+/// Ready-made [`Options`] for what a transcript is shared for.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Preset {
+    /// Only how to reach someone, emails and phone numbers.
+    Contacts,
+    /// Names, contacts and secrets, numbers stay as error codes and versions
+    /// matter to whoever helps.
+    Support,
+    /// Everything there is a placeholder for, for training data.
+    Everything,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Contacts, Preset::Support, Preset::Everything];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::Contacts => "Contacts",
+            Preset::Support => "For support",
+            Preset::Everything => "Everything",
+        }
+    }
+
+    pub fn options(self) -> Options {
+        match self {
+            Preset::Contacts => Options {
+                names: false,
+                emails: true,
+                phones: true,
+                links: false,
+                numbers: false,
+                secrets: false,
+            },
+            Preset::Support => Options {
+                names: true,
+                emails: true,
+                phones: true,
+                links: false,
+                numbers: false,
+                secrets: true,
+            },
+            Preset::Everything => Options {
+                names: true,
+                emails: true,
+                phones: true,
+                links: true,
+                numbers: true,
+                secrets: true,
+            },
+        }
+    }
+}
+
+/// A text with its personal data replaced.
+pub struct Anonymized {
+    pub text: String,
+    /// How many different values of each kind were replaced, in the order of
+    /// [`Kind`], kinds with none left out.
+    pub counts: Vec<(Kind, usize)>,
+}
+
+impl Anonymized {
+    /// The counts as "2 names, 1 email", empty when nothing was replaced.
+    pub fn summary(&self) -> String {
+        self.counts
+            .iter()
+            .map(|(kind, count)| match (count, kind.label()) {
+                (1, label) => format!("1 {}", label.strip_suffix('s').unwrap_or(label)),
+                (count, label) => format!("{} {}", count, label),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// whether `word` can be a name or the part of one on its own
+fn is_namelike(word: &str) -> bool {
+    word.chars().count() > 1 && !COMMON.contains(&word) && !TITLES.contains(&word)
+}
+
+// the names in `text` by the heuristics of [`anonymize`], with where they are
+fn find_names(text: &str) -> Vec<(Range<usize>, String)> {
+    static FIELDS: OnceLock<Regex> = OnceLock::new();
+    static RUNS: OnceLock<Regex> = OnceLock::new();
+    let mut names = Vec::new();
+    for captures in regex(&FIELDS, NAME_FIELDS).captures_iter(text) {
+        let Some(value) = captures.get(1) else {
+            continue;
+        };
+        let trimmed = value.as_str().trim_end();
+        // only names, not the addresses, dates and words such as "To: support"
+        // of the same fields
+        let capitalized = trimmed.chars().next().is_some_and(char::is_uppercase);
+        if !capitalized || trimmed.chars().any(|c| c.is_ascii_digit() || c == '@') {
+            continue;
+        }
+        names.push((
+            value.start()..value.start() + trimmed.len(),
+            trimmed.to_string(),
+        ));
+    }
+    for run in regex(&RUNS, CAPITALIZED).find_iter(text) {
+        let words: Vec<(usize, &str)> = run
+            .as_str()
+            .split([' ', '\t'])
+            .scan(run.start(), |at, word| {
+                let start = *at;
+                *at += word.len() + 1;
+                Some((start, word))
+            })
+            .filter(|(_, word)| !word.is_empty())
+            .collect();
+        // a title or greeting before the run makes one word enough, "Dr. Jones"
+        let before = text[..run.start()].trim_end_matches([' ', '\t']);
+        let titled = TITLES.iter().any(|title| {
+            before
+                .strip_suffix('.')
+                .unwrap_or(before)
+                .rsplit([' ', '\t', '\n'])
+                .next()
+                == Some(title)
+        });
+        let mut first = 0;
+        while first < words.len() && !is_namelike(words[first].1) {
+            first += 1;
+        }
+        let mut last = words.len();
+        while last > first && !is_namelike(words[last - 1].1) {
+            last -= 1;
+        }
+        if first == last {
+            continue;
+        }
+        let titled = titled
+            || (first > 0
+                && (TITLES.contains(&words[first - 1].1)
+                    || GREETINGS.contains(&words[first - 1].1)));
+        let namelike = words[first..last]
+            .iter()
+            .filter(|(_, word)| is_namelike(word))
+            .count();
+        if namelike < 2 && !titled {
+            continue;
+        }
+        let start = words[first].0;
+        let end = words[last - 1].0 + words[last - 1].1.len();
+        names.push((start..end, text[start..end].to_string()));
+    }
+    names
+}
+
+// every place in `text` where `found` is, as a whole word: not "Ann" in
+// "Annual" nor a phone number inside a longer one
+fn occurrences(text: &str, found: &str) -> Vec<Range<usize>> {
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    text.match_indices(found)
+        .map(|(start, _)| start..start + found.len())
+        .filter(|range| {
+            let joined_before =
+                word(found.chars().next()) && word(text[..range.start].chars().next_back());
+            let joined_after =
+                word(found.chars().next_back()) && word(text[range.end..].chars().next());
+            !joined_before && !joined_after
+        })
+        .collect()
+}
+
+/// Replace the personal data of `text` that `options` asks for with
+/// placeholders such as `[NAME 1]` and `[EMAIL 2]`, for a copy fit to share.
+/// The same value gets the same placeholder wherever it is, so who wrote to
+/// whom stays readable.
+///
+/// Secrets, emails, phone numbers and links are found as [`secrets`] and
+/// [`links`] find them. Names are guessed: the values of fields such as
+/// `Name:` and `From:`, a capitalized word after a title such as "Dr." or a
+/// greeting such as "Hello", and runs of two or more capitalized words that
+/// are not common words. The words of a name found are replaced wherever
+/// they stand alone too, so "Jane Doe" takes "Doe" with it. Place names and
+/// headings in title case are caught as names, and names written in lower
+/// case or alone without a title are missed, the result is worth a look
+/// before it is shared.
+pub fn anonymize(text: &str, options: &Options) -> Anonymized {
+    // what is replaced, where it is and the value it stands for
+    let mut spans: Vec<(Range<usize>, Kind, String)> = Vec::new();
+    let mut add = |range: Range<usize>, kind: Kind, value: String| {
+        let overlaps = spans
+            .iter()
+            .any(|(other, _, _)| other.start < range.end && range.start < other.end);
+        if !overlaps && !range.is_empty() {
+            spans.push((range, kind, value));
+        }
+    };
+    // emails are emails whichever of the two finds them
+    for secret in secrets::find(text) {
+        let kind = match secret.kind {
+            SecretKind::Email => Kind::Email,
+            _ => Kind::Secret,
+        };
+        let wanted = match kind {
+            Kind::Email => options.emails,
+            _ => options.secrets,
+        };
+        if wanted {
+            let value = text[secret.range.clone()].to_string();
+            add(secret.range, kind, value);
+        }
+    }
+    for link in links::find(text) {
+        let (kind, wanted) = match link.kind {
+            LinkKind::Email => (Kind::Email, options.emails),
+            LinkKind::Phone => (Kind::Phone, options.phones),
+            LinkKind::Url => (Kind::Link, options.links),
+        };
+        if !wanted {
+            continue;
+        }
+        for range in occurrences(text, &link.text) {
+            add(range, kind, link.target.clone());
+        }
+    }
+    if options.names {
+        let names = find_names(text);
+        for (range, name) in &names {
+            add(range.clone(), Kind::Name, name.clone());
+        }
+        // the parts of each name where they stand alone, the same person
+        for (_, name) in &names {
+            for word in name.split_whitespace().filter(|word| is_namelike(word)) {
+                if word.trim_end_matches('.').chars().count() < 3 {
+                    continue;
+                }
+                for range in occurrences(text, word) {
+                    add(range, Kind::Name, name.clone());
+                }
+            }
+        }
+    }
+    if options.numbers {
+        static NUMBERS: OnceLock<Regex> = OnceLock::new();
+        for number in regex(&NUMBERS, NUMBER).find_iter(text) {
+            add(number.range(), Kind::Number, number.as_str().to_string());
+        }
+    }
+    spans.sort_by_key(|(range, _, _)| range.start);
+
+    // placeholders are numbered by kind in the order their values first appear
+    let mut numbered: Vec<(Kind, String)> = Vec::new();
+    let mut anonymized = String::with_capacity(text.len());
+    let mut from = 0;
+    for (range, kind, value) in spans {
+        let index = match numbered
+            .iter()
+            .filter(|(other, _)| *other == kind)
+            .position(|(_, other)| *other == value)
+        {
+            Some(index) => index,
+            None => {
+                numbered.push((kind, value));
+                numbered.iter().filter(|(other, _)| *other == kind).count() - 1
+            }
+        };
+        anonymized.push_str(&text[from..range.start]);
+        anonymized.push_str(&format!("[{} {}]", kind.placeholder(), index + 1));
+        from = range.end;
+    }
+    anonymized.push_str(&text[from..]);
+
+    let mut counts: Vec<(Kind, usize)> = Vec::new();
+    for (kind, _) in &numbered {
+        match counts.iter_mut().find(|(other, _)| other == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*kind, 1)),
+        }
+    }
+    counts.sort();
+    Anonymized {
+        text: anonymized,
+        counts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn everything(text: &str) -> String {
+        anonymize(text, &Preset::Everything.options()).text
+    }
+
+    #[test]
+    fn the_same_value_gets_the_same_placeholder() {
+        assert_eq!(
+            everything("Write to jane@example.com, jane@example.com or bob@example.org"),
+            "Write to [EMAIL 1], [EMAIL 1] or [EMAIL 2]"
+        );
+    }
+
+    #[test]
+    fn the_words_of_a_name_are_replaced_alone_too() {
+        assert_eq!(
+            everything("Jane Doe called, we thanked Doe."),
+            "[NAME 1] called, we thanked [NAME 1]."
+        );
+    }
+
+    #[test]
+    fn words_inside_longer_words_are_left() {
+        let text = "Dr. Ann signed the Annual report";
+        assert_eq!(
+            anonymize(text, &Preset::Support.options()).text,
+            "Dr. [NAME 1] signed the Annual report"
+        );
+    }
+
+    #[test]
+    fn links_inside_longer_words_are_left() {
+        assert_eq!(
+            occurrences("example.com, myexample.com and example.com", "example.com"),
+            [0..11, 31..42]
+        );
+    }
+
+    #[test]
+    fn only_the_kinds_asked_for_are_replaced() {
+        let text = "Jane Doe, +49 30 1234567, jane@example.com";
+        let anonymized = anonymize(text, &Preset::Contacts.options());
+        assert_eq!(anonymized.text, "Jane Doe, [PHONE 1], [EMAIL 1]");
+        assert_eq!(anonymized.summary(), "1 email, 1 phone number");
+    }
+}
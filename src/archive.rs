@@ -0,0 +1,128 @@
+use crate::cache;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use rusqlite::{params, Connection};
+use std::{
+    error::Error,
+    io::Cursor,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transcriptions (
+    id INTEGER PRIMARY KEY,
+    text TEXT NOT NULL,
+    -- seconds since the Unix epoch
+    created INTEGER NOT NULL,
+    -- blake3 of the captured image, the same screenshot twice has the same hash
+    source_hash TEXT,
+    -- PNG
+    thumbnail BLOB
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS transcriptions_search
+    USING fts5(text, content='transcriptions', content_rowid='id');
+CREATE TRIGGER IF NOT EXISTS transcriptions_added AFTER INSERT ON transcriptions BEGIN
+    INSERT INTO transcriptions_search(rowid, text) VALUES (new.id, new.text);
+END;
+CREATE TRIGGER IF NOT EXISTS transcriptions_removed AFTER DELETE ON transcriptions BEGIN
+    INSERT INTO transcriptions_search(transcriptions_search, rowid, text)
+        VALUES ('delete', old.id, old.text);
+END;
+";
+
+/// A transcription found by [`Archive::search`].
+pub struct Archived {
+    pub id: i64,
+    pub text: String,
+    /// Local time it was recorded, e.g. "2024-05-14 09:30".
+    pub created: String,
+    /// The text around the words searched for, with them in brackets.
+    pub snippet: String,
+}
+
+/// Every transcription ever made, kept in an SQLite database with a full-text
+/// index so they can be searched long after they left the history.
+pub struct Archive {
+    connection: Connection,
+}
+
+// every word of the query as a prefix, quoted so that what FTS5 would take for
+// its own syntax is searched for as it is
+fn match_expression(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl Archive {
+    /// Open the database at `path`, creating it and its directory if needed.
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Archive { connection })
+    }
+
+    /// Record a transcription made now, of `source` when it came from an image.
+    pub fn add(
+        &self,
+        text: &str,
+        source: Option<&DynamicImage>,
+        thumbnail: Option<&RgbaImage>,
+    ) -> Result<(), Box<dyn Error>> {
+        let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let source_hash = source.map(|image| cache::image_key(image, "").to_hex().to_string());
+        let thumbnail = match thumbnail {
+            Some(image) => {
+                let mut png = Cursor::new(Vec::new());
+                image.write_to(&mut png, ImageFormat::Png)?;
+                Some(png.into_inner())
+            }
+            None => None,
+        };
+        self.connection.execute(
+            "INSERT INTO transcriptions (text, created, source_hash, thumbnail) VALUES (?1, ?2, ?3, ?4)",
+            params![text, created, source_hash, thumbnail],
+        )?;
+        Ok(())
+    }
+
+    /// The transcriptions holding every word of `query`, words matching as
+    /// prefixes, best matches first.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Archived>, Box<dyn Error>> {
+        let expression = match_expression(query);
+        if expression.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut statement = self.connection.prepare(
+            "SELECT t.id, t.text, strftime('%Y-%m-%d %H:%M', t.created, 'unixepoch', 'localtime'),
+                    snippet(transcriptions_search, 0, '[', ']', '...', 16)
+             FROM transcriptions_search JOIN transcriptions t ON t.id = transcriptions_search.rowid
+             WHERE transcriptions_search MATCH ?1
+             ORDER BY rank
+             LIMIT ?2",
+        )?;
+        let found = statement
+            .query_map(params![expression, limit as i64], |row| {
+                Ok(Archived {
+                    id: row.get(0)?,
+                    text: row.get(1)?,
+                    created: row.get(2)?,
+                    snippet: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<Archived>, rusqlite::Error>>()?;
+        Ok(found)
+    }
+
+    /// Forget transcription `id`.
+    pub fn remove(&self, id: i64) -> Result<(), Box<dyn Error>> {
+        self.connection
+            .execute("DELETE FROM transcriptions WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
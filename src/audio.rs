@@ -0,0 +1,238 @@
+use crate::{
+    job::JobControl,
+    subtitle::{self, Cue},
+};
+use std::{error::Error, fs::File, path::Path, time::Duration};
+use symphonia::core::{
+    codecs::audio::AudioDecoderOptions,
+    errors::Error as SymphoniaError,
+    formats::{probe::Hint, FormatOptions, TrackType},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+#[cfg(feature = "dictation")]
+pub mod dictation;
+pub mod speakers;
+
+/// Sample rate whisper models expect.
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// File extensions [`load_samples`] can decode.
+pub const EXTENSIONS: &[&str] = &["wav", "mp3"];
+
+/// Everything whisper recognized in a recording, segments are in order.
+#[derive(Clone)]
+pub struct AudioTranscript {
+    pub segments: Vec<Cue>,
+}
+
+impl AudioTranscript {
+    /// One line per segment, prefixed with its time range and speaker.
+    pub fn text(&self) -> String {
+        subtitle::timestamped_text(&self.segments)
+    }
+}
+
+/// Whether the file looks like something [`load_samples`] can decode.
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+/// Decode an audio file into mono samples at [`SAMPLE_RATE`].
+pub fn load_samples(path: &Path) -> Result<Vec<f32>, Box<dyn Error>> {
+    let file =
+        File::open(path).map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .probe(
+            &hint,
+            stream,
+            FormatOptions::default(),
+            MetadataOptions::default(),
+        )
+        .map_err(|err| format!("Unsupported audio file {}: {}", path.display(), err))?;
+
+    let track = format
+        .default_track(TrackType::Audio)
+        .ok_or("No audio track found")?;
+    let track_id = track.id;
+    let codec_params = track
+        .codec_params
+        .as_ref()
+        .and_then(|params| params.audio())
+        .ok_or("No audio codec parameters found")?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make_audio_decoder(codec_params, &AudioDecoderOptions::default())
+        .map_err(|err| format!("Unsupported audio codec: {}", err))?;
+
+    let mut mono = Vec::new();
+    let mut rate = SAMPLE_RATE;
+    let mut interleaved: Vec<f32> = Vec::new();
+    while let Some(packet) = format.next_packet()? {
+        if packet.track_id != track_id {
+            continue;
+        }
+        let buffer = match decoder.decode(&packet) {
+            Ok(buffer) => buffer,
+            // a corrupt frame only loses that frame
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        rate = buffer.spec().rate();
+        let channels = buffer.spec().channels().count().max(1);
+        buffer.copy_to_vec_interleaved(&mut interleaved);
+        mono.extend(
+            interleaved
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+
+    Ok(resample(&mono, rate, SAMPLE_RATE))
+}
+
+/// Resample mono audio with linear interpolation, plenty for speech recognition.
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from as f64 / to as f64;
+    let length = (samples.len() as f64 / ratio) as usize;
+    (0..length)
+        .map(|index| {
+            let position = index as f64 * ratio;
+            let before = position as usize;
+            let after = (before + 1).min(samples.len() - 1);
+            let fraction = (position - before as f64) as f32;
+            samples[before] * (1.0 - fraction) + samples[after] * fraction
+        })
+        .collect()
+}
+
+/// Whether `model` is a tinydiarize model, the only kind that marks where the
+/// speaker changes, as told by the `tdrz` its files are published with.
+pub fn diarizes(model: &Path) -> bool {
+    model
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().contains("tdrz"))
+}
+
+/// A loaded ggml whisper model, reusable across recordings.
+pub struct Transcriber {
+    context: WhisperContext,
+    language: String,
+    speakers: bool,
+    // whether the model marks speaker turns, see [`diarizes`]
+    diarizes: bool,
+}
+
+impl Transcriber {
+    /// `language` is a whisper language code such as `"en"`, or `"auto"` to
+    /// detect it.
+    pub fn new(model: &Path, language: &str) -> Result<Self, Box<dyn Error>> {
+        let context = WhisperContext::new_with_params(model, WhisperContextParameters::default())
+            .map_err(|err| {
+            format!("Failed to load whisper model {}: {}", model.display(), err)
+        })?;
+        Ok(Transcriber {
+            context,
+            language: language.to_string(),
+            speakers: false,
+            diarizes: diarizes(model),
+        })
+    }
+
+    /// Label the segments with who speaks them, see [`speakers::label`]. Only
+    /// tinydiarize models such as `ggml-small.en-tdrz.bin` mark where the
+    /// speaker changes, other models leave the segments unlabelled.
+    pub fn set_speaker_labels(&mut self, speakers: bool) {
+        self.speakers = speakers && self.diarizes;
+    }
+
+    /// Transcribe mono samples at [`SAMPLE_RATE`].
+    pub fn transcribe(&self, samples: &[f32]) -> Result<AudioTranscript, Box<dyn Error>> {
+        self.transcribe_controlled(samples, &JobControl::default(), |_| ())
+    }
+
+    /// Transcribe mono samples at [`SAMPLE_RATE`] as a job `control` pauses
+    /// and cancels, `on_progress` gets the fraction done as whisper goes.
+    pub fn transcribe_controlled(
+        &self,
+        samples: &[f32],
+        control: &JobControl,
+        mut on_progress: impl FnMut(f32) + 'static,
+    ) -> Result<AudioTranscript, Box<dyn Error>> {
+        let mut state = self.context.create_state()?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(&self.language));
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        params.set_tdrz_enable(self.speakers);
+        params.set_progress_callback_safe(move |percent: i32| on_progress(percent as f32 / 100.0));
+        // whisper checks in often while it works, so blocking here pauses it.
+        // Boxed, as whisper-rs hands the callback back as a boxed closure
+        let job = control.clone();
+        let abort: Box<dyn FnMut() -> bool> = Box::new(move || job.checkpoint().is_err());
+        params.set_abort_callback_safe(abort);
+        state.full(params, samples)?;
+        control.checkpoint()?;
+
+        let mut segments = Vec::new();
+        // whether the speaker changes after each segment
+        let mut turns = Vec::new();
+        for segment in state.as_iter() {
+            let text = segment.to_str_lossy()?.trim().to_string();
+            if text.is_empty() {
+                // the turn still ends where the empty segment did
+                if let Some(turn) = turns.last_mut() {
+                    *turn |= segment.next_segment_speaker_turn();
+                }
+                continue;
+            }
+            segments.push(Cue {
+                // whisper counts in centiseconds
+                start: Duration::from_millis(segment.start_timestamp().max(0) as u64 * 10),
+                end: Duration::from_millis(segment.end_timestamp().max(0) as u64 * 10),
+                text,
+                speaker: None,
+            });
+            turns.push(segment.next_segment_speaker_turn());
+        }
+        if self.speakers {
+            speakers::label(&mut segments, &turns, samples);
+        }
+        Ok(AudioTranscript { segments })
+    }
+}
+
+/// Decode and transcribe an audio file with a ggml whisper model, labelling
+/// who speaks when `speakers`, see [`Transcriber::transcribe_controlled`].
+pub fn transcribe_file(
+    model: &Path,
+    language: &str,
+    speakers: bool,
+    path: &Path,
+    control: &JobControl,
+    on_progress: impl FnMut(f32) + 'static,
+) -> Result<AudioTranscript, Box<dyn Error>> {
+    let samples = load_samples(path)?;
+    control.checkpoint()?;
+    let mut transcriber = Transcriber::new(model, language)?;
+    transcriber.set_speaker_labels(speakers);
+    transcriber.transcribe_controlled(&samples, control, on_progress)
+}
@@ -0,0 +1,202 @@
+use super::{resample, Transcriber, SAMPLE_RATE};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+use std::{
+    error::Error,
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tracing::error;
+
+// how often the worker looks at the recorded audio
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// speech is cut into chunks at pauses, within these lengths in seconds
+const MIN_CHUNK: f32 = 2.0;
+const MAX_CHUNK: f32 = 10.0;
+
+// length of quiet in seconds that counts as a pause
+const PAUSE: f32 = 0.4;
+
+// RMS level below which audio counts as silence, whisper makes words up
+// when fed only silence
+const SILENCE: f32 = 0.01;
+
+/// Records the default microphone and transcribes it chunk by chunk.
+///
+/// Speech is cut at pauses so each chunk is a few seconds of whole words, the
+/// text of every chunk comes out of [`Dictation::poll`] as soon as whisper is
+/// done with it.
+pub struct Dictation {
+    stream: Option<Stream>,
+    recording: Arc<AtomicBool>,
+    texts: Receiver<Result<String, String>>,
+    worker: JoinHandle<()>,
+}
+
+impl Dictation {
+    /// Start recording the default microphone.
+    pub fn start(transcriber: Transcriber) -> Result<Self, Box<dyn Error>> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("No microphone found")?;
+        let supported = device
+            .default_input_config()
+            .map_err(|err| format!("Failed to get microphone config: {}", err))?;
+        let config = supported.config();
+        let samples = Arc::new(Mutex::new(Vec::new()));
+
+        let stream = match supported.sample_format() {
+            SampleFormat::I8 => input_stream::<i8>(&device, &config, samples.clone()),
+            SampleFormat::I16 => input_stream::<i16>(&device, &config, samples.clone()),
+            SampleFormat::I32 => input_stream::<i32>(&device, &config, samples.clone()),
+            SampleFormat::U8 => input_stream::<u8>(&device, &config, samples.clone()),
+            SampleFormat::U16 => input_stream::<u16>(&device, &config, samples.clone()),
+            SampleFormat::F32 => input_stream::<f32>(&device, &config, samples.clone()),
+            format => return Err(format!("Unsupported microphone sample format {}", format).into()),
+        }
+        .map_err(|err| format!("Failed to open microphone: {}", err))?;
+        stream
+            .play()
+            .map_err(|err| format!("Failed to start recording: {}", err))?;
+
+        let recording = Arc::new(AtomicBool::new(true));
+        let (sender, texts) = mpsc::channel();
+        let worker = thread::spawn({
+            let recording = recording.clone();
+            let rate = config.sample_rate;
+            move || transcribe_chunks(transcriber, samples, rate, recording, sender)
+        });
+
+        Ok(Dictation {
+            stream: Some(stream),
+            recording,
+            texts,
+            worker,
+        })
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Stop recording, what was said so far still gets transcribed.
+    pub fn stop(&mut self) {
+        self.stream = None;
+        self.recording.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether the last chunk is done after [`Dictation::stop`], check this
+    /// before the final [`Dictation::poll`] so no text gets lost.
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// Text of the chunks transcribed since the last call.
+    pub fn poll(&self) -> Vec<Result<String, String>> {
+        self.texts.try_iter().collect()
+    }
+}
+
+impl Drop for Dictation {
+    fn drop(&mut self) {
+        self.recording.store(false, Ordering::Relaxed);
+    }
+}
+
+// open an input stream that downmixes into the shared sample buffer
+fn input_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    samples: Arc<Mutex<Vec<f32>>>,
+) -> Result<Stream, cpal::Error>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = (config.channels as usize).max(1);
+    device.build_input_stream(
+        *config,
+        move |data: &[T], _: &_| {
+            if let Ok(mut samples) = samples.lock() {
+                samples.extend(data.chunks(channels).map(|frame| {
+                    frame
+                        .iter()
+                        .map(|sample| f32::from_sample(*sample))
+                        .sum::<f32>()
+                        / channels as f32
+                }));
+            }
+        },
+        |err| error!("Error recording audio: {}", err),
+        None,
+    )
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+// whether the recorded audio should be transcribed now: long enough and
+// ending in a pause, or too long to wait for one
+fn chunk_ready(samples: &[f32], rate: u32) -> bool {
+    let seconds = samples.len() as f32 / rate as f32;
+    if seconds >= MAX_CHUNK {
+        return true;
+    }
+    let pause = (PAUSE * rate as f32) as usize;
+    seconds >= MIN_CHUNK && rms(&samples[samples.len().saturating_sub(pause)..]) < SILENCE
+}
+
+// worker thread: cut the recording into chunks and transcribe them until
+// recording stops and the rest is done
+fn transcribe_chunks(
+    transcriber: Transcriber,
+    samples: Arc<Mutex<Vec<f32>>>,
+    rate: u32,
+    recording: Arc<AtomicBool>,
+    sender: Sender<Result<String, String>>,
+) {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let stopped = !recording.load(Ordering::Relaxed);
+        let chunk = match samples.lock() {
+            Ok(mut samples) if stopped || chunk_ready(&samples, rate) => mem::take(&mut *samples),
+            Ok(_) => continue,
+            Err(_) => return,
+        };
+
+        let chunk = resample(&chunk, rate, SAMPLE_RATE);
+        if rms(&chunk) >= SILENCE {
+            let result = transcriber
+                .transcribe(&chunk)
+                .map(|transcript| {
+                    transcript
+                        .segments
+                        .iter()
+                        .map(|segment| segment.text.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(" ")
+                })
+                .map_err(|err| err.to_string());
+            if !matches!(&result, Ok(text) if text.is_empty()) && sender.send(result).is_err() {
+                return;
+            }
+        }
+
+        if stopped {
+            return;
+        }
+    }
+}
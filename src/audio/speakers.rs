@@ -0,0 +1,184 @@
+use super::SAMPLE_RATE;
+use crate::subtitle::Cue;
+use std::{f32::consts::PI, time::Duration};
+
+// samples in a frame the voice of a speaker is measured over, 32 ms
+const FRAME: usize = 512;
+
+// frequencies the voice print samples, spread evenly on a log scale over the
+// range that tells voices apart
+const BANDS: usize = 24;
+const LOWEST: f32 = 100.0;
+const HIGHEST: f32 = 4000.0;
+
+// RMS level below which a frame is silence, it says nothing about the voice
+const SILENCE: f32 = 0.01;
+
+// distance between voice prints below which two turns are taken to be the same
+// speaker, in decibels averaged over the bands
+const SAME_SPEAKER: f32 = 4.0;
+
+// The average spectrum of a stretch of speech, in decibels relative to its
+// own mean so a speaker talking louder or quieter still matches
+struct VoicePrint {
+    bands: [f32; BANDS],
+    // frames it was averaged over, a longer turn weighs more in a speaker
+    frames: usize,
+}
+
+// power of `samples` at `frequency` with the Goertzel algorithm, a single bin
+// of a Fourier transform
+fn power(samples: &[f32], frequency: f32) -> f32 {
+    let coefficient = 2.0 * (2.0 * PI * frequency / SAMPLE_RATE as f32).cos();
+    let (mut previous, mut before) = (0.0, 0.0);
+    for &sample in samples {
+        let current = sample + coefficient * previous - before;
+        before = previous;
+        previous = current;
+    }
+    previous * previous + before * before - coefficient * previous * before
+}
+
+fn voice_print(samples: &[f32]) -> Option<VoicePrint> {
+    let frequencies: Vec<f32> = (0..BANDS)
+        .map(|band| LOWEST * (HIGHEST / LOWEST).powf(band as f32 / (BANDS - 1) as f32))
+        .collect();
+    let mut bands = [0.0; BANDS];
+    let mut frames = 0;
+    for frame in samples.chunks_exact(FRAME) {
+        let rms = (frame.iter().map(|sample| sample * sample).sum::<f32>() / FRAME as f32).sqrt();
+        if rms < SILENCE {
+            continue;
+        }
+        for (band, &frequency) in bands.iter_mut().zip(&frequencies) {
+            *band += 10.0 * (power(frame, frequency) + 1e-10).log10();
+        }
+        frames += 1;
+    }
+    if frames == 0 {
+        return None;
+    }
+    let mean = bands.iter().sum::<f32>() / (BANDS * frames) as f32;
+    for band in &mut bands {
+        *band = *band / frames as f32 - mean;
+    }
+    Some(VoicePrint { bands, frames })
+}
+
+impl VoicePrint {
+    fn distance(&self, other: &VoicePrint) -> f32 {
+        self.bands
+            .iter()
+            .zip(&other.bands)
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f32>()
+            / BANDS as f32
+    }
+
+    // fold another turn of the same speaker in
+    fn add(&mut self, other: &VoicePrint) {
+        let total = (self.frames + other.frames) as f32;
+        for (band, added) in self.bands.iter_mut().zip(&other.bands) {
+            *band = (*band * self.frames as f32 + added * other.frames as f32) / total;
+        }
+        self.frames += other.frames;
+    }
+}
+
+/// Number the speakers of `segments`, from 1 in the order they first speak.
+///
+/// `turns` holds, for every segment, whether whisper saw the speaker change
+/// after it. The segments of each turn are given the speaker whose voice is
+/// closest to theirs, or a new one when no voice heard so far is close.
+/// Turns too quiet to tell go to the speaker of the turn before.
+pub fn label(segments: &mut [Cue], turns: &[bool], samples: &[f32]) {
+    let mut speakers: Vec<VoicePrint> = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    while start < segments.len() {
+        let end = (start..segments.len())
+            .find(|&index| turns.get(index).copied().unwrap_or(false))
+            .map_or(segments.len(), |index| index + 1);
+        let sample_at = |time: Duration| {
+            ((time.as_secs_f32() * SAMPLE_RATE as f32) as usize).min(samples.len())
+        };
+        let from = sample_at(segments[start].start);
+        let to = sample_at(segments[end - 1].end).max(from);
+        let speaker = match voice_print(&samples[from..to]) {
+            Some(print) => {
+                let closest = speakers
+                    .iter()
+                    .enumerate()
+                    .map(|(index, speaker)| (index, speaker.distance(&print)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                match closest {
+                    Some((index, distance)) if distance < SAME_SPEAKER => {
+                        speakers[index].add(&print);
+                        index
+                    }
+                    _ => {
+                        speakers.push(print);
+                        speakers.len() - 1
+                    }
+                }
+            }
+            None => previous.unwrap_or(0),
+        };
+        for segment in &mut segments[start..end] {
+            segment.speaker = Some(speaker + 1);
+        }
+        previous = Some(speaker);
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a second of a pure tone, loud enough not to be silence
+    fn tone(frequency: f32, level: f32) -> Vec<f32> {
+        (0..SAMPLE_RATE)
+            .map(|index| level * (2.0 * PI * frequency * index as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn cues(count: u64) -> Vec<Cue> {
+        (0..count)
+            .map(|second| Cue {
+                start: Duration::from_secs(second),
+                end: Duration::from_secs(second + 1),
+                text: format!("segment {}", second),
+                speaker: None,
+            })
+            .collect()
+    }
+
+    fn speakers(segments: &[Cue]) -> Vec<Option<usize>> {
+        segments.iter().map(|cue| cue.speaker).collect()
+    }
+
+    #[test]
+    fn a_voice_heard_before_gets_its_number_back() {
+        let samples = [tone(200.0, 0.5), tone(2000.0, 0.5), tone(200.0, 0.1)].concat();
+        let mut segments = cues(3);
+        label(&mut segments, &[true, true, false], &samples);
+        assert_eq!(speakers(&segments), [Some(1), Some(2), Some(1)]);
+    }
+
+    #[test]
+    fn segments_of_one_turn_share_a_speaker() {
+        let samples = [tone(200.0, 0.5), tone(2000.0, 0.5), tone(200.0, 0.5)].concat();
+        let mut segments = cues(3);
+        label(&mut segments, &[false, false, false], &samples);
+        assert_eq!(speakers(&segments), [Some(1); 3]);
+    }
+
+    #[test]
+    fn a_silent_turn_goes_to_the_speaker_before() {
+        let samples = [tone(200.0, 0.5), tone(2000.0, 0.5), vec![0.0; 16_000]].concat();
+        let mut segments = cues(3);
+        label(&mut segments, &[true, true, false], &samples);
+        assert_eq!(speakers(&segments), [Some(1), Some(2), Some(2)]);
+    }
+}
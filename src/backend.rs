@@ -0,0 +1,254 @@
+use crate::{
+    ocr::{self, Progress, TranscriptionResult},
+    tiling,
+};
+use image::DynamicImage;
+use ocrs::OcrEngine;
+use std::{
+    error::Error,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+mod cloud;
+#[cfg(feature = "tesseract")]
+mod tesseract;
+
+pub use cloud::{cloud_backend, CloudChoice, CloudCredentials, SharedCloud, CLOUD_BACKENDS};
+#[cfg(feature = "tesseract")]
+pub use tesseract::TesseractBackend;
+
+/// Names of the backends compiled in, the first one is the default.
+pub const BACKENDS: &[&str] = &[
+    "ocrs",
+    #[cfg(feature = "tesseract")]
+    "tesseract",
+];
+
+/// An OCR engine turning images into transcriptions.
+pub trait OcrBackend {
+    /// One of [`BACKENDS`] or [`CLOUD_BACKENDS`].
+    fn name(&self) -> &'static str;
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>>;
+
+    /// Like [`OcrBackend::transcribe`], telling `progress` how far it got on
+    /// the way. Engines that cannot tell only return the result.
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let _ = progress;
+        self.transcribe(image)
+    }
+
+    /// Whether the engine reads columns of vertical text by itself. Engines
+    /// that do not get the image turned so that the columns become lines, see
+    /// [`crate::ocr::transcribe_preprocessed`].
+    fn reads_vertical(&self) -> bool {
+        false
+    }
+
+    /// The engine on this machine doing the reading, without any cache or
+    /// filter around it, for quick reads such as guessing the orientation.
+    /// `None` for cloud engines, where every read is paid for.
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        None
+    }
+}
+
+/// A backend shared by several threads, such as the workers of a pool.
+impl<B: OcrBackend + ?Sized> OcrBackend for Arc<B> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        (**self).transcribe(image)
+    }
+
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        (**self).transcribe_streaming(image, progress)
+    }
+
+    fn reads_vertical(&self) -> bool {
+        (**self).reads_vertical()
+    }
+
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        (**self).local()
+    }
+}
+
+/// The ocrs engine with its bundled models, clones share the loaded models.
+#[derive(Clone)]
+pub struct OcrsBackend {
+    engine: Arc<OcrEngine>,
+}
+
+impl OcrsBackend {
+    pub fn new(engine: OcrEngine) -> Self {
+        OcrsBackend {
+            engine: Arc::new(engine),
+        }
+    }
+}
+
+impl OcrBackend for OcrsBackend {
+    fn name(&self) -> &'static str {
+        "ocrs"
+    }
+
+    /// Images too large to read in one go are read in tiles, see [`tiling`].
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        if tiling::needs_tiling(image.width(), image.height()) {
+            return tiling::transcribe_tiled(
+                image,
+                |tile| ocr::transcribe(&self.engine, tile),
+                &mut |_| {},
+            );
+        }
+        ocr::transcribe(&self.engine, image)
+    }
+
+    // large images tell the lines of each tile as it is read
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        if tiling::needs_tiling(image.width(), image.height()) {
+            return tiling::transcribe_tiled(
+                image,
+                |tile| ocr::transcribe(&self.engine, tile),
+                progress,
+            );
+        }
+        ocr::transcribe_streaming(&self.engine, image, progress)
+    }
+
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        Some(self)
+    }
+}
+
+/// Backends tried in turn, the next one reading the image when the one before
+/// found no text, failed, or read it with a confidence below the minimum.
+/// The result says which one read it.
+pub struct FallbackChain {
+    backends: Vec<Box<dyn OcrBackend>>,
+    min_confidence: f32,
+}
+
+impl FallbackChain {
+    /// `backends` in the order they are tried, it must not be empty.
+    /// `min_confidence` is in `0.0..=1.0`, results of engines that report no
+    /// confidence pass as long as they found text.
+    pub fn new(backends: Vec<Box<dyn OcrBackend>>, min_confidence: f32) -> Self {
+        assert!(!backends.is_empty(), "a fallback chain needs a backend");
+        FallbackChain {
+            backends,
+            min_confidence,
+        }
+    }
+
+    fn good_enough(&self, result: &TranscriptionResult) -> bool {
+        !result.lines.is_empty()
+            && result
+                .confidence()
+                .is_none_or(|confidence| confidence >= self.min_confidence)
+    }
+}
+
+impl OcrBackend for FallbackChain {
+    // the first one, whose settings the rest fill in for
+    fn name(&self) -> &'static str {
+        self.backends[0].name()
+    }
+
+    /// The first result that is good enough, or the best of them when none is.
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        self.transcribe_streaming(image, &mut |_| ())
+    }
+
+    /// The progress of the first backend, those after it only step in at the end.
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let mut best: Option<TranscriptionResult> = None;
+        let mut failure = None;
+        for (index, backend) in self.backends.iter().enumerate() {
+            let result = match index {
+                0 => backend.transcribe_streaming(image, progress),
+                _ => backend.transcribe(image),
+            };
+            let mut result = match result {
+                Ok(result) => result,
+                Err(err) => {
+                    warn!(
+                        "{} failed, trying the next backend: {}",
+                        backend.name(),
+                        err
+                    );
+                    failure = Some(err);
+                    continue;
+                }
+            };
+            result
+                .backend
+                .get_or_insert_with(|| backend.name().to_string());
+            if self.good_enough(&result) {
+                return Ok(result);
+            }
+            info!(
+                "{} read too little, trying the next backend",
+                backend.name()
+            );
+            // text beats none, then the more confident
+            let score = |result: &TranscriptionResult| {
+                (!result.lines.is_empty(), result.confidence().unwrap_or(0.0))
+            };
+            if best
+                .as_ref()
+                .is_none_or(|best| score(&result) > score(best))
+            {
+                best = Some(result);
+            }
+        }
+        match (best, failure) {
+            (Some(best), _) => Ok(best),
+            (None, Some(err)) => Err(err),
+            (None, None) => Err("No OCR backend".into()),
+        }
+    }
+
+    fn reads_vertical(&self) -> bool {
+        self.backends[0].reads_vertical()
+    }
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        self.backends.iter().find_map(|backend| backend.local())
+    }
+}
+
+/// Average time `backend` takes per image over `runs` transcriptions, after an
+/// untimed one that warms up whatever the engine sets up lazily.
+pub fn benchmark(
+    backend: &dyn OcrBackend,
+    image: &DynamicImage,
+    runs: u32,
+) -> Result<Duration, Box<dyn Error>> {
+    backend.transcribe(image)?;
+    let start = Instant::now();
+    for _ in 0..runs {
+        backend.transcribe(image)?;
+    }
+    Ok(start.elapsed() / runs.max(1))
+}
@@ -0,0 +1,218 @@
+use super::OcrBackend;
+use crate::ocr::Progress;
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    sync::{Arc, RwLock},
+};
+
+use crate::ocr::TranscriptionResult;
+#[cfg(any(feature = "google-vision", feature = "azure-ocr", feature = "textract"))]
+use crate::ocr::{average_confidence, Bounds, Line, Word};
+use image::DynamicImage;
+#[cfg(any(feature = "google-vision", feature = "azure-ocr", feature = "textract"))]
+use image::ImageFormat;
+
+#[cfg(feature = "azure-ocr")]
+mod azure;
+#[cfg(feature = "google-vision")]
+mod google;
+#[cfg(feature = "textract")]
+mod textract;
+
+/// Names of the cloud backends compiled in.
+pub const CLOUD_BACKENDS: &[&str] = &[
+    #[cfg(feature = "google-vision")]
+    "google-vision",
+    #[cfg(feature = "azure-ocr")]
+    "azure",
+    #[cfg(feature = "textract")]
+    "textract",
+];
+
+/// Keys of the cloud services, only the one of the backend in use is needed.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudCredentials {
+    /// API key of a Google Cloud project with the Vision API enabled.
+    pub google_api_key: String,
+    /// Endpoint of an Azure AI Vision resource, e.g.
+    /// "https://name.cognitiveservices.azure.com".
+    pub azure_endpoint: String,
+    pub azure_key: String,
+    /// AWS region Textract is called in, e.g. "us-east-1".
+    pub aws_region: String,
+    pub aws_access_key_id: String,
+    pub aws_secret_access_key: String,
+}
+
+/// The cloud backend `name`, one of [`CLOUD_BACKENDS`], with its keys checked
+/// to be there but not yet tried.
+pub fn cloud_backend(
+    name: &str,
+    credentials: &CloudCredentials,
+) -> Result<Box<dyn OcrBackend + Send + Sync>, Box<dyn Error>> {
+    let _ = credentials;
+    match name {
+        #[cfg(feature = "google-vision")]
+        "google-vision" => Ok(Box::new(google::GoogleVisionBackend::new(credentials)?)),
+        #[cfg(feature = "azure-ocr")]
+        "azure" => Ok(Box::new(azure::AzureBackend::new(credentials)?)),
+        #[cfg(feature = "textract")]
+        "textract" => Ok(Box::new(textract::TextractBackend::new(credentials)?)),
+        other => Err(format!("Unknown cloud OCR backend: {}", other).into()),
+    }
+}
+
+type Cloud = Arc<dyn OcrBackend + Send + Sync>;
+
+/// The cloud backend the captures go to while one is picked. Clones share it,
+/// so every [`CloudChoice`] made with one switches at once, and picking one
+/// or changing its keys sets up no local backend again.
+#[derive(Clone, Default)]
+pub struct SharedCloud(Arc<RwLock<Option<Cloud>>>);
+
+impl SharedCloud {
+    /// Send the captures to come to `cloud`, or read them locally again with
+    /// `None`.
+    pub fn set(&self, cloud: Option<Box<dyn OcrBackend + Send + Sync>>) {
+        if let Ok(mut current) = self.0.write() {
+            *current = cloud.map(Cloud::from);
+        }
+    }
+
+    /// Whether the captures go to a cloud backend.
+    pub fn is_set(&self) -> bool {
+        self.get().is_some()
+    }
+
+    // a clone, so the lock is not held while the service answers
+    fn get(&self) -> Option<Cloud> {
+        self.0.read().ok().and_then(|cloud| cloud.clone())
+    }
+}
+
+/// A local backend with the cloud one of a [`SharedCloud`] next to it,
+/// reading with the cloud one while it is set. The cloud one goes around
+/// whatever is wrapped in the local one, such as a cache.
+pub struct CloudChoice {
+    local: Box<dyn OcrBackend>,
+    cloud: SharedCloud,
+}
+
+impl CloudChoice {
+    pub fn new(local: Box<dyn OcrBackend>, cloud: SharedCloud) -> Self {
+        CloudChoice { local, cloud }
+    }
+}
+
+impl OcrBackend for CloudChoice {
+    // the local one, what the settings of the main window are for
+    fn name(&self) -> &'static str {
+        self.local.name()
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let Some(cloud) = self.cloud.get() else {
+            return self.local.transcribe(image);
+        };
+        let mut result = cloud.transcribe(image)?;
+        result.backend = Some(cloud.name().to_string());
+        Ok(result)
+    }
+
+    // only the local backends can tell how far they got
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        match self.cloud.is_set() {
+            true => self.transcribe(image),
+            false => self.local.transcribe_streaming(image, progress),
+        }
+    }
+
+    fn reads_vertical(&self) -> bool {
+        match self.cloud.get() {
+            Some(cloud) => cloud.reads_vertical(),
+            None => self.local.reads_vertical(),
+        }
+    }
+
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        self.local.local()
+    }
+}
+
+// the image as the services take it
+#[cfg(any(feature = "google-vision", feature = "azure-ocr", feature = "textract"))]
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+// the box around the corners of a polygon
+#[cfg(any(feature = "google-vision", feature = "azure-ocr", feature = "textract"))]
+fn polygon_bounds(points: &[(f32, f32)]) -> Bounds {
+    let xs = points.iter().map(|(x, _)| *x);
+    let ys = points.iter().map(|(_, y)| *y);
+    Bounds {
+        left: xs.clone().fold(f32::MAX, f32::min).round() as i32,
+        top: ys.clone().fold(f32::MAX, f32::min).round() as i32,
+        right: xs.fold(f32::MIN, f32::max).round() as i32,
+        bottom: ys.fold(f32::MIN, f32::max).round() as i32,
+    }
+}
+
+#[cfg(any(feature = "google-vision", feature = "azure-ocr", feature = "textract"))]
+fn word(text: String, bounds: Bounds, confidence: Option<f32>) -> Word {
+    Word {
+        text,
+        bounds,
+        rotated_bounds: bounds.into(),
+        confidence,
+        alternatives: Vec::new(),
+    }
+}
+
+// a line of the words, around all of them with their average confidence, None
+// for no words
+#[cfg(any(feature = "google-vision", feature = "azure-ocr", feature = "textract"))]
+fn line(words: Vec<Word>) -> Option<Line> {
+    if words.is_empty() {
+        return None;
+    }
+    let bounds = Bounds {
+        left: words.iter().map(|word| word.bounds.left).min()?,
+        top: words.iter().map(|word| word.bounds.top).min()?,
+        right: words.iter().map(|word| word.bounds.right).max()?,
+        bottom: words.iter().map(|word| word.bounds.bottom).max()?,
+    };
+    let confidence = average_confidence(words.iter().map(|word| word.confidence));
+    Some(Line {
+        text: words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<&str>>()
+            .join(" "),
+        bounds,
+        rotated_bounds: bounds.into(),
+        confidence,
+        words,
+    })
+}
+
+#[cfg(any(feature = "google-vision", feature = "azure-ocr", feature = "textract"))]
+fn transcription(image: &DynamicImage, lines: Vec<Line>) -> TranscriptionResult {
+    TranscriptionResult {
+        width: image.width(),
+        height: image.height(),
+        lines,
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    }
+}
@@ -0,0 +1,84 @@
+use super::{encode_png, line, polygon_bounds, transcription, word, CloudCredentials};
+use crate::{backend::OcrBackend, ocr::TranscriptionResult};
+use image::DynamicImage;
+use serde_json::Value;
+use std::error::Error;
+
+const API_VERSION: &str = "2024-02-01";
+
+/// The read feature of Azure AI Vision image analysis.
+pub struct AzureBackend {
+    endpoint: String,
+    key: String,
+}
+
+impl AzureBackend {
+    pub fn new(credentials: &CloudCredentials) -> Result<Self, Box<dyn Error>> {
+        if credentials.azure_endpoint.is_empty() || credentials.azure_key.is_empty() {
+            return Err("Azure AI Vision needs an endpoint and a key".into());
+        }
+        Ok(AzureBackend {
+            endpoint: credentials.azure_endpoint.trim_end_matches('/').to_string(),
+            key: credentials.azure_key.clone(),
+        })
+    }
+}
+
+impl OcrBackend for AzureBackend {
+    fn name(&self) -> &'static str {
+        "azure"
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let url = format!("{}/computervision/imageanalysis:analyze", self.endpoint);
+        let response: Value = ureq::post(&url)
+            .query("api-version", API_VERSION)
+            .query("features", "read")
+            .header("Ocp-Apim-Subscription-Key", &self.key)
+            .header("Content-Type", "application/octet-stream")
+            .send(encode_png(image)?)
+            .map_err(|err| format!("Request to {} failed: {}", url, err))?
+            .into_body()
+            .read_json()
+            .map_err(|err| format!("Unexpected response from {}: {}", url, err))?;
+        let lines = response["readResult"]["blocks"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|block| block["lines"].as_array().into_iter().flatten())
+            .filter_map(|found| {
+                let words = found["words"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|found| {
+                        let text = found["text"].as_str().unwrap_or_default().to_string();
+                        let confidence = found["confidence"].as_f64().map(|value| value as f32);
+                        word(
+                            text,
+                            polygon_bounds(&points(&found["boundingPolygon"])),
+                            confidence,
+                        )
+                    })
+                    .collect();
+                line(words)
+            })
+            .collect();
+        Ok(transcription(image, lines))
+    }
+}
+
+// the corners of a bounding polygon, a list of x and y objects
+fn points(polygon: &Value) -> Vec<(f32, f32)> {
+    polygon
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|point| {
+            (
+                point["x"].as_f64().unwrap_or(0.0) as f32,
+                point["y"].as_f64().unwrap_or(0.0) as f32,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+use super::{encode_png, line, polygon_bounds, transcription, word, CloudCredentials};
+use crate::{backend::OcrBackend, ocr::TranscriptionResult};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::DynamicImage;
+use serde_json::{json, Value};
+use std::error::Error;
+
+const ANNOTATE: &str = "https://vision.googleapis.com/v1/images:annotate";
+
+/// Google Cloud Vision document text detection.
+pub struct GoogleVisionBackend {
+    api_key: String,
+}
+
+impl GoogleVisionBackend {
+    pub fn new(credentials: &CloudCredentials) -> Result<Self, Box<dyn Error>> {
+        if credentials.google_api_key.is_empty() {
+            return Err("Google Cloud Vision needs an API key".into());
+        }
+        Ok(GoogleVisionBackend {
+            api_key: credentials.google_api_key.clone(),
+        })
+    }
+}
+
+impl OcrBackend for GoogleVisionBackend {
+    fn name(&self) -> &'static str {
+        "google-vision"
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let content = STANDARD.encode(encode_png(image)?);
+        let response: Value = ureq::post(ANNOTATE)
+            .query("key", &self.api_key)
+            .send_json(json!({
+                "requests": [{
+                    "image": { "content": content },
+                    "features": [{ "type": "DOCUMENT_TEXT_DETECTION" }],
+                }],
+            }))
+            .map_err(|err| format!("Request to Google Cloud Vision failed: {}", err))?
+            .into_body()
+            .read_json()
+            .map_err(|err| format!("Unexpected response from Google Cloud Vision: {}", err))?;
+        let response = &response["responses"][0];
+        if let Some(message) = response["error"]["message"].as_str() {
+            return Err(format!("Google Cloud Vision: {}", message).into());
+        }
+        Ok(transcription(image, parse(&response["fullTextAnnotation"])))
+    }
+}
+
+// Lines out of the pages, blocks, paragraphs, words and symbols of the
+// annotation, a line ends at a symbol followed by a line break
+fn parse(annotation: &Value) -> Vec<crate::ocr::Line> {
+    let mut lines = Vec::new();
+    let mut words = Vec::new();
+    let all_words = annotation["pages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|page| page["blocks"].as_array().into_iter().flatten())
+        .flat_map(|block| block["paragraphs"].as_array().into_iter().flatten())
+        .flat_map(|paragraph| paragraph["words"].as_array().into_iter().flatten());
+    for found in all_words {
+        let symbols = found["symbols"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        let mut text: String = symbols
+            .iter()
+            .filter_map(|symbol| symbol["text"].as_str())
+            .collect();
+        let breaks = symbols
+            .last()
+            .and_then(|symbol| symbol["property"]["detectedBreak"]["type"].as_str())
+            .unwrap_or("");
+        if breaks == "HYPHEN" {
+            text.push('-');
+        }
+        let points: Vec<(f32, f32)> = found["boundingBox"]["vertices"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|vertex| {
+                (
+                    vertex["x"].as_f64().unwrap_or(0.0) as f32,
+                    vertex["y"].as_f64().unwrap_or(0.0) as f32,
+                )
+            })
+            .collect();
+        let confidence = found["confidence"]
+            .as_f64()
+            .map(|confidence| confidence as f32);
+        words.push(word(text, polygon_bounds(&points), confidence));
+        if matches!(breaks, "EOL_SURE_SPACE" | "LINE_BREAK" | "HYPHEN") {
+            lines.extend(line(std::mem::take(&mut words)));
+        }
+    }
+    lines.extend(line(words));
+    lines
+}
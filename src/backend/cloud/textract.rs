@@ -0,0 +1,155 @@
+use super::{encode_png, line, transcription, word, CloudCredentials};
+use crate::{
+    backend::OcrBackend,
+    ocr::{Bounds, TranscriptionResult},
+    update::hex,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, KeyInit, Mac};
+use image::DynamicImage;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, error::Error};
+
+const TARGET: &str = "Textract.DetectDocumentText";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+/// AWS Textract text detection, with requests signed by AWS Signature Version 4.
+pub struct TextractBackend {
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl TextractBackend {
+    pub fn new(credentials: &CloudCredentials) -> Result<Self, Box<dyn Error>> {
+        if credentials.aws_region.is_empty()
+            || credentials.aws_access_key_id.is_empty()
+            || credentials.aws_secret_access_key.is_empty()
+        {
+            return Err("AWS Textract needs a region, an access key ID and a secret key".into());
+        }
+        Ok(TextractBackend {
+            region: credentials.aws_region.clone(),
+            access_key_id: credentials.aws_access_key_id.clone(),
+            secret_access_key: credentials.aws_secret_access_key.clone(),
+        })
+    }
+
+    // the Authorization header of a request with `body` sent at `time`,
+    // formatted as 20240514T093000Z
+    fn authorization(&self, host: &str, time: &str, body: &str) -> String {
+        let date = &time[..8];
+        let signed_headers = "content-type;host;x-amz-date;x-amz-target";
+        let canonical_request = format!(
+            "POST\n/\n\ncontent-type:{}\nhost:{}\nx-amz-date:{}\nx-amz-target:{}\n\n{}\n{}",
+            CONTENT_TYPE,
+            host,
+            time,
+            TARGET,
+            signed_headers,
+            hex(&Sha256::digest(body.as_bytes()))
+        );
+        let scope = format!("{}/{}/textract/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            time,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = hmac(
+            format!("AWS4{}", self.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "textract", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            scope,
+            signed_headers,
+            hex(&hmac(&key, string_to_sign.as_bytes()))
+        )
+    }
+}
+
+impl OcrBackend for TextractBackend {
+    fn name(&self) -> &'static str {
+        "textract"
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let host = format!("textract.{}.amazonaws.com", self.region);
+        let body =
+            json!({ "Document": { "Bytes": STANDARD.encode(encode_png(image)?) } }).to_string();
+        let time = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let response: Value = ureq::post(&format!("https://{}/", host))
+            .header("Content-Type", CONTENT_TYPE)
+            .header("X-Amz-Date", &time)
+            .header("X-Amz-Target", TARGET)
+            .header("Authorization", &self.authorization(&host, &time, &body))
+            .send(body)
+            .map_err(|err| format!("Request to AWS Textract failed: {}", err))?
+            .into_body()
+            .read_json()
+            .map_err(|err| format!("Unexpected response from AWS Textract: {}", err))?;
+        Ok(transcription(image, parse(&response, image)))
+    }
+}
+
+// LINE blocks with the WORD blocks they list as children, boxes are fractions
+// of the image size
+fn parse(response: &Value, image: &DynamicImage) -> Vec<crate::ocr::Line> {
+    let blocks: Vec<&Value> = response["Blocks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .collect();
+    let by_id: HashMap<&str, &Value> = blocks
+        .iter()
+        .filter_map(|block| Some((block["Id"].as_str()?, *block)))
+        .collect();
+    let (width, height) = (image.width() as f64, image.height() as f64);
+    let bounds = |block: &Value| {
+        let geometry = &block["Geometry"]["BoundingBox"];
+        let number = |name: &str| geometry[name].as_f64().unwrap_or(0.0);
+        Bounds {
+            left: (number("Left") * width).round() as i32,
+            top: (number("Top") * height).round() as i32,
+            right: ((number("Left") + number("Width")) * width).round() as i32,
+            bottom: ((number("Top") + number("Height")) * height).round() as i32,
+        }
+    };
+    blocks
+        .iter()
+        .filter(|block| block["BlockType"] == "LINE")
+        .filter_map(|found| {
+            let words = found["Relationships"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|relationship| relationship["Type"] == "CHILD")
+                .flat_map(|relationship| relationship["Ids"].as_array().into_iter().flatten())
+                .filter_map(|id| by_id.get(id.as_str()?))
+                .filter(|block| block["BlockType"] == "WORD")
+                .map(|block| {
+                    let text = block["Text"].as_str().unwrap_or_default().to_string();
+                    // in percent
+                    let confidence = block["Confidence"]
+                        .as_f64()
+                        .map(|value| value as f32 / 100.0);
+                    word(text, bounds(block), confidence)
+                })
+                .collect();
+            line(words)
+        })
+        .collect()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,283 @@
+use super::OcrBackend;
+use crate::ocr::{Bounds, Line, TranscriptionResult, Word};
+use image::{DynamicImage, ImageFormat};
+use leptess::LepTess;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    io::Cursor,
+};
+
+// resolution assumed for clipboard images, which carry none
+const FALLBACK_DPI: i32 = 300;
+
+// readings kept per word besides the best one
+const MAX_ALTERNATIVES: usize = 5;
+
+// characters considered less likely than this, in percent, are not worth offering
+const MIN_CHOICE_CONFIDENCE: f32 = 5.0;
+
+// word boxes as both outputs report them, left, top, right and bottom
+type BoxKey = (i32, i32, i32, i32);
+
+/// Tesseract through leptess, needs the tesseract and leptonica libraries.
+pub struct TesseractBackend {
+    tess: RefCell<LepTess>,
+    // a vertical model such as jpn_vert is loaded, which reads columns itself
+    vertical: bool,
+}
+
+impl TesseractBackend {
+    /// `data_path` is the tessdata directory, `None` uses the system one.
+    /// Recognition only produces the characters in `allowed_chars`, any
+    /// character when it is empty.
+    pub fn new(
+        data_path: Option<&str>,
+        language: &str,
+        allowed_chars: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut tess = LepTess::new(data_path, language)
+            .map_err(|err| format!("Failed to initialize tesseract for {}: {}", language, err))?;
+        // the hOCR output then lists the characters the LSTM considered at every position
+        tess.set_variable(leptess::Variable::LstmChoiceMode, "2")
+            .map_err(|err| format!("Failed to configure tesseract: {}", err))?;
+        if !allowed_chars.is_empty() {
+            tess.set_variable(leptess::Variable::TesseditCharWhitelist, allowed_chars)
+                .map_err(|err| format!("Failed to configure tesseract: {}", err))?;
+        }
+        Ok(TesseractBackend {
+            tess: RefCell::new(tess),
+            vertical: language.contains("_vert"),
+        })
+    }
+}
+
+impl OcrBackend for TesseractBackend {
+    fn name(&self) -> &'static str {
+        "tesseract"
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        // leptonica can only read tiff from memory on every platform
+        let mut encoded = Vec::new();
+        DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(&mut Cursor::new(&mut encoded), ImageFormat::Tiff)?;
+
+        let mut tess = self.tess.borrow_mut();
+        tess.set_image_from_mem(&encoded)?;
+        tess.set_fallback_source_resolution(FALLBACK_DPI);
+        let tsv = tess.get_tsv_text(0)?;
+        let mut result = parse_tsv(&tsv, image.width(), image.height());
+        // the recognition is reused, asking for another output format is cheap
+        let choices = word_choices(&tess.get_hocr_text(0)?);
+        for word in result.lines.iter_mut().flat_map(|line| &mut line.words) {
+            let key = (
+                word.bounds.left,
+                word.bounds.top,
+                word.bounds.right,
+                word.bounds.bottom,
+            );
+            if let Some(alternatives) = choices.get(&key) {
+                word.alternatives = alternatives
+                    .iter()
+                    .filter(|alternative| **alternative != word.text)
+                    .take(MAX_ALTERNATIVES)
+                    .cloned()
+                    .collect();
+            }
+        }
+        Ok(result)
+    }
+
+    fn reads_vertical(&self) -> bool {
+        self.vertical
+    }
+
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        Some(self)
+    }
+}
+
+// Build a transcription from tesseract's TSV output, which has a row for every
+// page, block, paragraph, line and word:
+// level page block paragraph line word left top width height confidence text
+fn parse_tsv(tsv: &str, width: u32, height: u32) -> TranscriptionResult {
+    // keyed by block, paragraph and line, which sorts them in reading order
+    let mut lines: BTreeMap<(i64, i64, i64), Line> = BTreeMap::new();
+
+    for row in tsv.lines().skip(1) {
+        let fields: Vec<&str> = row.split('\t').collect();
+        if fields.len() < 12 {
+            continue;
+        }
+        let number = |index: usize| fields[index].trim().parse::<i64>().unwrap_or(0);
+        let key = (number(2), number(3), number(4));
+        let bounds = Bounds {
+            left: number(6) as i32,
+            top: number(7) as i32,
+            right: (number(6) + number(8)) as i32,
+            bottom: (number(7) + number(9)) as i32,
+        };
+
+        match number(0) {
+            4 => {
+                lines.insert(
+                    key,
+                    Line {
+                        text: String::new(),
+                        bounds,
+                        rotated_bounds: bounds.into(),
+                        confidence: None,
+                        words: Vec::new(),
+                    },
+                );
+            }
+            5 => {
+                let text = fields[11].trim();
+                if text.is_empty() {
+                    continue;
+                }
+                // tesseract reports -1 for rows that are not real words
+                let confidence = fields[10]
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|confidence| *confidence >= 0.0)
+                    .map(|confidence| confidence / 100.0);
+                if let Some(line) = lines.get_mut(&key) {
+                    line.words.push(Word {
+                        text: text.to_string(),
+                        bounds,
+                        rotated_bounds: bounds.into(),
+                        confidence,
+                        alternatives: Vec::new(),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let lines = lines
+        .into_values()
+        .filter(|line| !line.words.is_empty())
+        .map(|mut line| {
+            line.text = line
+                .words
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<&str>>()
+                .join(" ");
+            line.confidence = line.word_confidence();
+            line
+        })
+        .collect();
+
+    TranscriptionResult {
+        width,
+        height,
+        lines,
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    }
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// the number after `name` in an hOCR title such as "bbox 36 92 96 116; x_wconf 96"
+fn title_numbers(title: &str, name: &str) -> Vec<f32> {
+    title
+        .split(';')
+        .map(str::trim)
+        .find_map(|property| property.strip_prefix(name))
+        .map(|values| {
+            values
+                .split_whitespace()
+                .filter_map(|value| value.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Readings of every word other than its best one, keyed by the word box. With
+// lstm_choice_mode 2 every character position of a word has a span of the
+// characters considered there with their confidence:
+// <span class='ocrx_cinfo' id='lstm_choices_1_1_1'><span id='choice_1_1_1' title='x_confs 98'>T</span>...</span>
+// A reading swaps one character for its runner-up, the likelier swaps first.
+fn word_choices(hocr: &str) -> HashMap<BoxKey, Vec<String>> {
+    let mut choices = HashMap::new();
+    for word in hocr.split("class='ocrx_word'").skip(1) {
+        let title = word
+            .split_once("title='")
+            .and_then(|(_, rest)| rest.split_once('\''))
+            .map_or("", |(title, _)| title);
+        let bounds = title_numbers(title, "bbox");
+        let [left, top, right, bottom] = bounds[..] else {
+            continue;
+        };
+
+        // every position's characters, most confident first
+        let mut positions: Vec<Vec<(String, f32)>> = Vec::new();
+        for position in word.split("id='lstm_choices_").skip(1) {
+            let mut characters = Vec::new();
+            for choice in position.split("<span id='choice_").skip(1) {
+                let Some((_, rest)) = choice.split_once("title='") else {
+                    continue;
+                };
+                let Some((title, rest)) = rest.split_once('\'') else {
+                    continue;
+                };
+                let Some(character) = rest
+                    .strip_prefix('>')
+                    .and_then(|rest| rest.split_once("</span>"))
+                    .map(|(character, _)| unescape_html(character))
+                else {
+                    continue;
+                };
+                let confidence = title_numbers(title, "x_confs").first().copied();
+                characters.push((character, confidence.unwrap_or(0.0)));
+            }
+            characters.sort_by(|a, b| b.1.total_cmp(&a.1));
+            if !characters.is_empty() {
+                positions.push(characters);
+            }
+        }
+
+        let best: Vec<&str> = positions
+            .iter()
+            .map(|characters| characters[0].0.as_str())
+            .collect();
+        let mut readings: Vec<(f32, String)> = Vec::new();
+        for (index, characters) in positions.iter().enumerate() {
+            for (character, confidence) in &characters[1..] {
+                if *confidence < MIN_CHOICE_CONFIDENCE {
+                    continue;
+                }
+                let mut reading = best.clone();
+                reading[index] = character;
+                readings.push((*confidence, reading.concat()));
+            }
+        }
+        readings.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut alternatives: Vec<String> = Vec::new();
+        for (_, reading) in readings {
+            if !reading.trim().is_empty() && !alternatives.contains(&reading) {
+                alternatives.push(reading);
+            }
+        }
+        if !alternatives.is_empty() {
+            let key = (left as i32, top as i32, right as i32, bottom as i32);
+            choices.insert(key, alternatives);
+        }
+    }
+    choices
+}
@@ -0,0 +1,220 @@
+use crate::{backend_factory, config::Config, jobs::JobRow, locale::tr, SharedCache};
+use imgui::{Condition, Ui};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+use transcribewizard::{backend::OcrBackend, files, job::JobControl, pdf, pool::WorkerPool};
+
+// name of the file every transcription goes into when combining them
+const COMBINED_FILE: &str = "transcriptions.txt";
+
+enum FileState {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+}
+
+// A folder of images and PDFs being transcribed by several workers
+pub struct BatchJob {
+    // kept so the workers live as long as the job
+    _pool: WorkerPool,
+    dir: PathBuf,
+    // what the Jobs window calls it
+    name: String,
+    control: JobControl,
+    files: Vec<(PathBuf, FileState)>,
+    // text of every finished file, kept for the combined output
+    texts: Vec<Option<String>>,
+    combined: bool,
+    updates: Receiver<(usize, FileState, Option<String>)>,
+    // outcome of writing the combined file
+    summary: Option<String>,
+}
+
+// where the transcription of a single file is written, next to it with .txt
+// appended so scan.png and scan.pdf do not collide
+pub fn output_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".txt");
+    path.with_file_name(name)
+}
+
+// where the searchable PDF of a file is written, next to it like its .txt
+fn pdf_output_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".ocr.pdf");
+    path.with_file_name(name)
+}
+
+fn transcribe(backend: &dyn OcrBackend, path: &Path, config: &Config) -> Result<String, String> {
+    let pages = files::transcribe_file(backend, path, &config.preprocessing, config.conversion())
+        .map_err(|err| err.to_string())?;
+    if config.batch_pdf {
+        pdf::save(&pdf_output_path(path), &pages, files::PDF_DPI as f32)
+            .map_err(|err| err.to_string())?;
+    }
+    let texts: Vec<String> = pages
+        .iter()
+        .map(|(_, transcription)| config.render(transcription))
+        .collect();
+    Ok(files::join_pages(path, &texts))
+}
+
+// the files not read yet are given up with the window, workers waiting in a
+// pause are let go instead of sleeping on forever
+impl Drop for BatchJob {
+    fn drop(&mut self) {
+        self.control.cancel();
+    }
+}
+
+impl BatchJob {
+    // start transcribing every supported file in `dir` on a worker pool
+    pub fn start(config: &Config, cache: &SharedCache, dir: PathBuf) -> Result<Self, String> {
+        let paths = files::scan_dir(&dir, config.batch_recursive).map_err(|err| err.to_string())?;
+        if paths.is_empty() {
+            return Err(format!("No images or PDFs in {}", dir.display()));
+        }
+        let factory = backend_factory(config, cache).map_err(|err| err.to_string())?;
+        let pool = WorkerPool::new(config.ocr_threads, factory);
+
+        let control = JobControl::default();
+        let (sender, updates) = mpsc::channel();
+        for (index, path) in paths.iter().enumerate() {
+            let (sender, path, config) = (sender.clone(), path.clone(), config.clone());
+            let control = control.clone();
+            pool.submit(move |backend| {
+                // files not started when the job is cancelled fail right away
+                if let Err(err) = control.checkpoint() {
+                    let _ = sender.send((index, FileState::Failed(err.to_string()), None));
+                    return;
+                }
+                // the window was closed, which cancels the rest
+                if sender.send((index, FileState::Running, None)).is_err() {
+                    return;
+                }
+                let result = backend
+                    .map_err(str::to_string)
+                    .and_then(|backend| transcribe(backend, &path, &config))
+                    .and_then(|text| {
+                        if !config.batch_combined {
+                            let output = output_path(&path);
+                            fs::write(&output, &text).map_err(|err| {
+                                format!("Failed to write {}: {}", output.display(), err)
+                            })?;
+                        }
+                        Ok(text)
+                    });
+                let update = match result {
+                    Ok(text) => (index, FileState::Done, Some(text)),
+                    Err(err) => (index, FileState::Failed(err), None),
+                };
+                let _ = sender.send(update);
+            });
+        }
+
+        Ok(BatchJob {
+            _pool: pool,
+            name: format!("Batch {}", dir.display()),
+            control,
+            dir,
+            texts: vec![None; paths.len()],
+            files: paths
+                .into_iter()
+                .map(|path| (path, FileState::Queued))
+                .collect(),
+            combined: config.batch_combined,
+            updates,
+            summary: None,
+        })
+    }
+
+    fn finished(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|(_, state)| matches!(state, FileState::Done | FileState::Failed(_)))
+            .count()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished() == self.files.len()
+    }
+
+    // its row in the Jobs window until every file is finished
+    pub fn row(&self) -> Option<JobRow<'_>> {
+        (!self.is_done()).then(|| JobRow {
+            name: &self.name,
+            progress: Some(self.finished() as f32 / self.files.len() as f32),
+            control: &self.control,
+        })
+    }
+
+    fn poll(&mut self) {
+        let was_done = self.is_done();
+        while let Ok((index, state, text)) = self.updates.try_recv() {
+            self.files[index].1 = state;
+            self.texts[index] = text;
+        }
+        if !was_done && self.is_done() {
+            self.summary = Some(if self.combined {
+                self.write_combined()
+            } else {
+                "Each transcription was saved next to its file".into()
+            });
+        }
+    }
+
+    // every transcription in file order, headed by the path inside the folder
+    fn write_combined(&self) -> String {
+        let sections: Vec<String> = self
+            .files
+            .iter()
+            .zip(&self.texts)
+            .filter_map(|((path, _), text)| {
+                let name = path.strip_prefix(&self.dir).unwrap_or(path);
+                Some(format!("--- {} ---\n{}", name.display(), text.as_ref()?))
+            })
+            .collect();
+        let output = self.dir.join(COMBINED_FILE);
+        match fs::write(&output, sections.join("\n\n")) {
+            Ok(()) => format!("Saved {}", output.display()),
+            Err(err) => format!("Error writing {}: {}", output.display(), err),
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, open: &mut bool) {
+        self.poll();
+        ui.window(format!("{}###Batch", tr("Batch")))
+            .size([520.0, 420.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                ui.text(self.dir.display().to_string());
+                let finished = self.finished();
+                imgui::ProgressBar::new(finished as f32 / self.files.len() as f32)
+                    .overlay_text(format!("{} of {} files", finished, self.files.len()))
+                    .build(ui);
+                if let Some(summary) = &self.summary {
+                    ui.text_wrapped(summary);
+                }
+                ui.child_window("files").border(true).build(|| {
+                    for (path, state) in &self.files {
+                        let name = path.strip_prefix(&self.dir).unwrap_or(path);
+                        let name = name.display().to_string();
+                        match state {
+                            FileState::Queued => ui.text_disabled(&name),
+                            FileState::Running => ui.text(format!("{} - transcribing...", name)),
+                            FileState::Done => {
+                                ui.text_colored([0.3, 0.8, 0.3, 1.0], format!("{} - done", name))
+                            }
+                            FileState::Failed(err) => {
+                                ui.text_colored([0.9, 0.3, 0.3, 1.0], format!("{} - {}", name, err))
+                            }
+                        }
+                    }
+                });
+            });
+    }
+}
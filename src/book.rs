@@ -0,0 +1,296 @@
+use crate::{export::escape_xml, layout::join_line, stats, translate};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::Path};
+
+// what ends a paragraph, a page ending in anything else goes on on the next one
+const PARAGRAPH_ENDS: &[char] = &[
+    '.', '!', '?', ':', '"', '\'', '\u{201D}', '\u{2019}', '\u{00BB}', ')',
+];
+
+/// A book transcribed page by page, kept in a project file between sessions.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Book {
+    pub title: String,
+    pub author: String,
+    /// In reading order.
+    pub pages: Vec<Page>,
+}
+
+/// The text of one page of a [`Book`].
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Page {
+    pub text: String,
+    /// Title of the chapter the page starts, empty when it goes on with the
+    /// one before.
+    pub chapter: String,
+}
+
+/// Pages of a [`Book`] from one chapter marker to the next, split into
+/// paragraphs with paragraphs broken over a page joined again.
+pub struct Chapter {
+    /// Empty for the pages before the first marker.
+    pub title: String,
+    pub paragraphs: Vec<String>,
+}
+
+impl Book {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        serde_json::from_str(&contents)
+            .map_err(|err| format!("Failed to parse {}: {}", path.display(), err).into())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err).into())
+    }
+
+    /// The title, or "Untitled" when there is none.
+    pub fn display_title(&self) -> &str {
+        match self.title.trim() {
+            "" => "Untitled",
+            title => title,
+        }
+    }
+
+    pub fn chapters(&self) -> Vec<Chapter> {
+        let mut chapters: Vec<Chapter> = Vec::new();
+        // whether the last paragraph stopped mid-sentence at the end of a page
+        let mut open = false;
+        for page in &self.pages {
+            let chapter = page.chapter.trim();
+            if !chapter.is_empty() || chapters.is_empty() {
+                chapters.push(Chapter {
+                    title: chapter.to_string(),
+                    paragraphs: Vec::new(),
+                });
+                open = false;
+            }
+            let paragraphs = &mut chapters.last_mut().expect("a chapter was added").paragraphs;
+            for (index, paragraph) in split_paragraphs(&page.text).into_iter().enumerate() {
+                match paragraphs.last_mut() {
+                    Some(last) if index == 0 && open => join_line(last, &paragraph),
+                    _ => paragraphs.push(paragraph),
+                }
+            }
+            open = paragraphs
+                .last()
+                .is_some_and(|last| !last.ends_with(PARAGRAPH_ENDS));
+        }
+        chapters
+    }
+
+    /// The whole book as one Markdown document, the title and every chapter a
+    /// heading.
+    pub fn markdown(&self) -> String {
+        let mut markdown = format!("# {}\n", self.display_title());
+        if !self.author.trim().is_empty() {
+            markdown.push_str(&format!("\n*{}*\n", self.author.trim()));
+        }
+        for chapter in self.chapters() {
+            if !chapter.title.is_empty() {
+                markdown.push_str(&format!("\n## {}\n", chapter.title));
+            }
+            for paragraph in chapter.paragraphs {
+                markdown.push_str(&format!("\n{}\n", paragraph));
+            }
+        }
+        markdown
+    }
+
+    /// The book as an EPUB 3 file, a document for every chapter and a table
+    /// of contents listing them.
+    pub fn epub(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let chapters = self.chapters();
+        let text: String = self.pages.iter().map(|page| page.text.as_str()).collect();
+        let language = stats::detect_language(&text)
+            .and_then(|language| translate::language_code(language.name))
+            .unwrap_or("und");
+        let title = escape_xml(self.display_title());
+        // the same text gets the same identifier however often it is exported
+        let identifier = blake3::hash(serde_json::to_string(self)?.as_bytes()).to_hex();
+
+        let mut zip = StoredZip::default();
+        // readers tell an EPUB by its first entry, uncompressed
+        zip.add("mimetype", b"application/epub+zip");
+        zip.add(
+            "META-INF/container.xml",
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#,
+        );
+
+        let mut manifest = String::new();
+        let mut spine = String::new();
+        let mut contents = String::new();
+        for (index, chapter) in chapters.iter().enumerate() {
+            let name = format!("chapter{}.xhtml", index + 1);
+            let heading = match chapter.title.as_str() {
+                "" if index == 0 => self.display_title().to_string(),
+                "" => format!("Chapter {}", index + 1),
+                title => title.to_string(),
+            };
+            let mut body = format!("<h1>{}</h1>\n", escape_xml(&heading));
+            for paragraph in &chapter.paragraphs {
+                body.push_str(&format!("<p>{}</p>\n", escape_xml(paragraph)));
+            }
+            zip.add(
+                &format!("OEBPS/{}", name),
+                xhtml(language, &escape_xml(&heading), &body).as_bytes(),
+            );
+            manifest.push_str(&format!(
+                "    <item id=\"chapter{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+                index + 1,
+                name
+            ));
+            spine.push_str(&format!("    <itemref idref=\"chapter{}\"/>\n", index + 1));
+            contents.push_str(&format!(
+                "      <li><a href=\"{}\">{}</a></li>\n",
+                name,
+                escape_xml(&heading)
+            ));
+        }
+
+        let nav = format!(
+            "<nav epub:type=\"toc\" id=\"toc\">\n    <h1>Contents</h1>\n    <ol>\n{}    </ol>\n  </nav>\n",
+            contents
+        );
+        zip.add(
+            "OEBPS/nav.xhtml",
+            xhtml(language, "Contents", &nav).as_bytes(),
+        );
+        let creator = match self.author.trim() {
+            "" => String::new(),
+            author => format!("    <dc:creator>{}</dc:creator>\n", escape_xml(author)),
+        };
+        let package = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">urn:blake3:{}</dc:identifier>
+    <dc:title>{}</dc:title>
+{}    <dc:language>{}</dc:language>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+{}  </manifest>
+  <spine>
+{}  </spine>
+</package>
+"#,
+            identifier,
+            title,
+            creator,
+            language,
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            manifest,
+            spine
+        );
+        zip.add("OEBPS/content.opf", package.as_bytes());
+        Ok(zip.finish())
+    }
+}
+
+// the text of a page in paragraphs, which blank lines separate, with the
+// lines of each joined
+fn split_paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph = String::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !paragraph.is_empty() {
+                paragraphs.push(std::mem::take(&mut paragraph));
+            }
+            continue;
+        }
+        join_line(&mut paragraph, line);
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    paragraphs
+}
+
+fn xhtml(language: &str, title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{}" lang="{}">
+<head>
+  <title>{}</title>
+</head>
+<body>
+  {}</body>
+</html>
+"#,
+        language, language, title, body
+    )
+}
+
+// A zip archive of uncompressed files, all an EPUB needs
+#[derive(Default)]
+struct StoredZip {
+    data: Vec<u8>,
+    // the central directory, written after the files
+    directory: Vec<u8>,
+    count: u16,
+}
+
+impl StoredZip {
+    fn add(&mut self, name: &str, contents: &[u8]) {
+        let crc = crc32fast::hash(contents);
+        let offset = self.data.len() as u32;
+        let size = contents.len() as u32;
+        let name = name.as_bytes();
+        // version 2.0, no flags, stored, dated midnight on the first day zip knows
+        let header = |signature: u32| {
+            let mut header = signature.to_le_bytes().to_vec();
+            header.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+            header.extend_from_slice(&crc.to_le_bytes());
+            header.extend_from_slice(&size.to_le_bytes());
+            header.extend_from_slice(&size.to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&[0, 0]);
+            header
+        };
+
+        self.data.extend(header(0x04034b50));
+        self.data.extend_from_slice(name);
+        self.data.extend_from_slice(contents);
+
+        // the central directory entry also names the version that made it
+        self.directory
+            .extend_from_slice(&0x02014b50u32.to_le_bytes());
+        self.directory.extend_from_slice(&[20, 0]);
+        self.directory.extend(&header(0)[4..]);
+        // no comment, on disk 0, no attributes
+        self.directory.extend_from_slice(&[0; 10]);
+        self.directory.extend_from_slice(&offset.to_le_bytes());
+        self.directory.extend_from_slice(name);
+        self.count += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let start = self.data.len() as u32;
+        let length = self.directory.len() as u32;
+        self.data.extend(self.directory);
+        self.data.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        self.data.extend_from_slice(&[0, 0, 0, 0]);
+        self.data.extend_from_slice(&self.count.to_le_bytes());
+        self.data.extend_from_slice(&self.count.to_le_bytes());
+        self.data.extend_from_slice(&length.to_le_bytes());
+        self.data.extend_from_slice(&start.to_le_bytes());
+        self.data.extend_from_slice(&[0, 0]);
+        self.data
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{config::Config, history::History, locale::tr, status::Status};
+use imgui::{Condition, Ui};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::error;
+use transcribewizard::book::{Book, Page};
+
+// characters of a page shown in the list of pages
+const PREVIEW_CHARS: usize = 60;
+
+// Window collecting the transcriptions of a book's pages in order, saved to
+// its project file after every change and exported as one EPUB or Markdown
+// document
+#[derive(Default)]
+pub struct BookPanel {
+    book: Option<(PathBuf, Book)>,
+    // page shown below the list
+    selected: Option<usize>,
+    // title of the chapter the next page added starts
+    chapter: String,
+    // add every new transcription as the next page
+    collect: bool,
+    // history.pushed() when the last transcription was collected
+    seen: usize,
+}
+
+impl BookPanel {
+    // reopen the project that was open last time
+    pub fn restore(config: &Config) -> Self {
+        let mut panel = BookPanel::default();
+        if !config.book_project.as_os_str().is_empty() {
+            match Book::load(&config.book_project) {
+                Ok(book) => panel.book = Some((config.book_project.clone(), book)),
+                Err(err) => error!("Error opening book project: {}", err),
+            }
+        }
+        panel
+    }
+
+    fn open(&mut self, config: &mut Config, path: PathBuf, book: Book) {
+        config.book_project = path.clone();
+        if let Err(err) = config.save() {
+            error!("Error saving config: {}", err);
+        }
+        self.book = Some((path, book));
+        self.selected = None;
+    }
+
+    fn save(&self, status: &mut Status) {
+        if let Some((path, book)) = &self.book {
+            if let Err(err) = book.save(path) {
+                status.error("Error saving book project", err);
+            }
+        }
+    }
+
+    fn add_page(&mut self, text: &str, status: &mut Status) {
+        let Some((_, book)) = &mut self.book else {
+            return;
+        };
+        book.pages.push(Page {
+            text: text.trim().to_string(),
+            chapter: std::mem::take(&mut self.chapter).trim().to_string(),
+        });
+        self.selected = Some(book.pages.len() - 1);
+        self.save(status);
+    }
+
+    // add the newest transcription when collecting, called every frame even
+    // while the window is closed
+    pub fn update(&mut self, history: &History, status: &mut Status) {
+        let pushed = history.pushed();
+        if pushed == self.seen {
+            return;
+        }
+        self.seen = pushed;
+        if !self.collect || self.book.is_none() {
+            return;
+        }
+        if let Some(entry) = history.entries().next() {
+            if !entry.text.trim().is_empty() {
+                let text = entry.text.clone();
+                self.add_page(&text, status);
+            }
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        config: &mut Config,
+        text: &str,
+        status: &mut Status,
+        open: &mut bool,
+    ) {
+        ui.window(format!("{}###Book", tr("Book")))
+            .size([560.0, 480.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                if ui.button(tr("New book...")) {
+                    let path = crate::projects::save_dialog(config, "book", "json", None)
+                        .add_filter("Book project", &["json"])
+                        .save_file();
+                    if let Some(path) = path {
+                        let book = Book::default();
+                        match book.save(&path) {
+                            Ok(()) => self.open(config, path, book),
+                            Err(err) => status.error("Error creating book project", err),
+                        }
+                    }
+                }
+                ui.same_line();
+                if ui.button(tr("Open book...")) {
+                    let path = crate::projects::open_dialog(config)
+                        .add_filter("Book project", &["json"])
+                        .pick_file();
+                    if let Some(path) = path {
+                        match Book::load(&path) {
+                            Ok(book) => self.open(config, path, book),
+                            Err(err) => status.error("Error opening book project", err),
+                        }
+                    }
+                }
+                let Some((path, book)) = &mut self.book else {
+                    ui.text_disabled(tr("Start a book or open one to collect its pages"));
+                    return;
+                };
+                ui.same_line();
+                ui.text_disabled(path.display().to_string());
+
+                // typed fields are saved once left rather than on every key
+                ui.input_text(tr("Title"), &mut book.title).build();
+                let mut changed = ui.is_item_deactivated_after_edit();
+                ui.input_text(tr("Author"), &mut book.author).build();
+                changed |= ui.is_item_deactivated_after_edit();
+
+                ui.separator();
+                ui.input_text(tr("Chapter title"), &mut self.chapter)
+                    .hint(tr("Empty when the page goes on with the chapter before"))
+                    .build();
+                {
+                    let _disabled = ui.begin_disabled(text.trim().is_empty());
+                    if ui.button(tr("Add page")) {
+                        self.add_page(text, status);
+                        return;
+                    }
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr("Add the text as the next page, starting the chapter above if it has a title"));
+                }
+                ui.same_line();
+                ui.checkbox(tr("Add every new transcription"), &mut self.collect);
+
+                ui.child_window("pages")
+                    .size([0.0, 150.0])
+                    .border(true)
+                    .build(|| {
+                        for (index, page) in book.pages.iter().enumerate() {
+                            let preview: String = page
+                                .text
+                                .split_whitespace()
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                                .chars()
+                                .take(PREVIEW_CHARS)
+                                .collect();
+                            let label = match page.chapter.as_str() {
+                                "" => format!("{} {} - {}", tr("Page"), index + 1, preview),
+                                chapter => {
+                                    format!("{} {} [{}] - {}", tr("Page"), index + 1, chapter, preview)
+                                }
+                            };
+                            let selected = self.selected == Some(index);
+                            if ui
+                                .selectable_config(format!("{}##page{}", label, index))
+                                .selected(selected)
+                                .build()
+                            {
+                                self.selected = Some(index);
+                            }
+                        }
+                    });
+
+                if let Some(index) = self.selected.filter(|index| *index < book.pages.len()) {
+                    if ui.small_button(tr("Move up")) && index > 0 {
+                        book.pages.swap(index, index - 1);
+                        self.selected = Some(index - 1);
+                        changed = true;
+                    }
+                    ui.same_line();
+                    if ui.small_button(tr("Move down")) && index + 1 < book.pages.len() {
+                        book.pages.swap(index, index + 1);
+                        self.selected = Some(index + 1);
+                        changed = true;
+                    }
+                    ui.same_line();
+                    if ui.small_button(tr("Replace with the text")) {
+                        book.pages[index].text = text.trim().to_string();
+                        changed = true;
+                    }
+                    ui.same_line();
+                    if ui.small_button(tr("Remove")) {
+                        book.pages.remove(index);
+                        self.selected = None;
+                        changed = true;
+                    }
+                    if let Some(page) = book.pages.get_mut(index) {
+                        ui.input_text(tr("Starts chapter"), &mut page.chapter)
+                            .build();
+                        changed |= ui.is_item_deactivated_after_edit();
+                        ui.input_text_multiline("##page_text", &mut page.text, [-1.0, -40.0])
+                            .build();
+                        changed |= ui.is_item_deactivated_after_edit();
+                    }
+                }
+
+                ui.separator();
+                let _disabled = ui.begin_disabled(book.pages.is_empty());
+                if ui.button(tr("Export EPUB...")) {
+                    if let Some(output) = export_path(config, path, "epub", "EPUB") {
+                        let written = book.epub().and_then(|epub| {
+                            fs::write(&output, epub).map_err(|err| {
+                                format!("Failed to write {}: {}", output.display(), err).into()
+                            })
+                        });
+                        if let Err(err) = written {
+                            status.error("Error exporting book", err);
+                        }
+                    }
+                }
+                ui.same_line();
+                if ui.button(tr("Export Markdown...")) {
+                    if let Some(output) = export_path(config, path, "md", "Markdown") {
+                        if let Err(err) = fs::write(&output, book.markdown()) {
+                            status.error("Error exporting book", err);
+                        }
+                    }
+                }
+                ui.same_line();
+                ui.text_disabled(format!("{} {}", book.pages.len(), tr("pages")));
+
+                if changed {
+                    if let Err(err) = book.save(path) {
+                        status.error("Error saving book project", err);
+                    }
+                }
+            });
+    }
+}
+
+// ask where to export the book, named and placed like every other saved file
+// while a project is open and next to its project file otherwise
+fn export_path(config: &Config, project: &Path, extension: &str, filter: &str) -> Option<PathBuf> {
+    let stem = project.file_stem().unwrap_or_default().to_string_lossy();
+    if config.current_project().is_some() {
+        return crate::projects::save_dialog(config, &stem, extension, Some(&stem))
+            .add_filter(filter, &[extension])
+            .save_file();
+    }
+    let mut dialog = rfd::FileDialog::new()
+        .set_file_name(format!("{}.{}", stem, extension))
+        .add_filter(filter, &[extension]);
+    if let Some(dir) = project.parent() {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog.save_file()
+}
@@ -0,0 +1,182 @@
+use crate::{
+    backend::OcrBackend,
+    ocr::{Progress, Timings},
+    TranscriptionResult,
+};
+use blake3::Hash;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Transcriptions of images seen before, keyed by a hash of their pixels and
+/// the settings they were recognized with. The least recently used results go
+/// once there are more than `capacity`.
+pub struct ResultCache {
+    results: HashMap<Hash, TranscriptionResult>,
+    // most recently used first
+    order: VecDeque<Hash>,
+    capacity: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredResult {
+    key: String,
+    result: TranscriptionResult,
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        ResultCache {
+            results: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Read a cache written by [`ResultCache::save`], an empty one when the file
+    /// does not exist yet.
+    pub fn load(path: &Path, capacity: usize) -> Result<Self, Box<dyn Error>> {
+        let mut cache = ResultCache::new(capacity);
+        if !path.exists() {
+            return Ok(cache);
+        }
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let stored: Vec<StoredResult> = serde_json::from_str(&contents)
+            .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?;
+        // saved newest first, inserting oldest first keeps that order
+        for entry in stored.into_iter().rev() {
+            if let Ok(key) = Hash::from_hex(&entry.key) {
+                cache.insert(key, entry.result);
+            }
+        }
+        Ok(cache)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let stored: Vec<StoredResult> = self
+            .order
+            .iter()
+            .filter_map(|key| {
+                Some(StoredResult {
+                    key: key.to_hex().to_string(),
+                    result: self.results.get(key)?.clone(),
+                })
+            })
+            .collect();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(&stored)?)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err).into())
+    }
+
+    fn touch(&mut self, key: Hash) {
+        if let Some(index) = self.order.iter().position(|known| *known == key) {
+            self.order.remove(index);
+        }
+        self.order.push_front(key);
+    }
+
+    pub fn get(&mut self, key: Hash) -> Option<TranscriptionResult> {
+        let result = self.results.get(&key)?.clone();
+        self.touch(key);
+        Some(result)
+    }
+
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.order.clear();
+    }
+
+    pub fn insert(&mut self, key: Hash, result: TranscriptionResult) {
+        self.results.insert(key, result);
+        self.touch(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_back() {
+                self.results.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Cache key of an image recognized with the given settings, which should
+/// name everything besides the pixels that changes the result.
+pub fn image_key(image: &DynamicImage, settings: &str) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(settings.as_bytes());
+    hasher.update(&image.width().to_le_bytes());
+    hasher.update(&image.height().to_le_bytes());
+    hasher.update(format!("{:?}", image.color()).as_bytes());
+    hasher.update(image.as_bytes());
+    hasher.finalize()
+}
+
+/// A backend answering images it has seen before from a shared cache.
+pub struct CachedBackend {
+    backend: Box<dyn OcrBackend>,
+    cache: Arc<Mutex<ResultCache>>,
+    settings: String,
+}
+
+impl CachedBackend {
+    /// `settings` name the backend and everything else that changes its results,
+    /// such as the language.
+    pub fn new(
+        backend: Box<dyn OcrBackend>,
+        cache: Arc<Mutex<ResultCache>>,
+        settings: String,
+    ) -> Self {
+        CachedBackend {
+            backend,
+            cache,
+            settings,
+        }
+    }
+}
+
+impl OcrBackend for CachedBackend {
+    fn name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        self.transcribe_streaming(image, &mut |_| ())
+    }
+
+    // a cached result comes back whole, without progress
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let key = image_key(image, &self.settings);
+        // a poisoned cache only costs the lookup
+        if let Some(mut result) = self.cache.lock().ok().and_then(|mut cache| cache.get(key)) {
+            result.timings = Some(Timings {
+                cached: true,
+                ..Timings::default()
+            });
+            return Ok(result);
+        }
+        let result = self.backend.transcribe_streaming(image, progress)?;
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(key, result.clone());
+        }
+        Ok(result)
+    }
+
+    fn reads_vertical(&self) -> bool {
+        self.backend.reads_vertical()
+    }
+
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        self.backend.local()
+    }
+}
@@ -0,0 +1,119 @@
+use image::RgbImage;
+use nokhwa::{
+    pixel_format::RgbFormat,
+    query,
+    utils::{ApiBackend, CameraIndex, RequestedFormat, RequestedFormatType},
+    Camera,
+};
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// Names of the cameras found, in the order [`CameraFeed::open`] counts them.
+pub fn cameras() -> Result<Vec<String>, Box<dyn Error>> {
+    let cameras =
+        query(ApiBackend::Auto).map_err(|err| format!("Failed to list cameras: {}", err))?;
+    Ok(cameras
+        .into_iter()
+        .map(|camera| camera.human_name())
+        .collect())
+}
+
+// what the capture thread leaves for the UI, the newest frame not yet taken
+// and why it stopped when it did
+#[derive(Default)]
+struct Shared {
+    frame: Option<RgbImage>,
+    error: Option<String>,
+}
+
+/// A webcam streaming at its highest resolution, to read documents held up
+/// to it.
+///
+/// Frames are captured on a thread of their own so a slow camera does not
+/// hold up the UI, and only the newest one is kept, [`CameraFeed::frame`]
+/// hands it out. Dropping the feed stops the camera.
+pub struct CameraFeed {
+    shared: Arc<Mutex<Shared>>,
+    running: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl CameraFeed {
+    /// Start streaming camera `index`, 0 for the first one found.
+    pub fn open(index: u32) -> Result<Self, Box<dyn Error>> {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let running = Arc::new(AtomicBool::new(true));
+        let (opened, result) = mpsc::channel();
+        // some platforms want the camera used on the thread that opened it
+        let worker = thread::spawn({
+            let (shared, running) = (shared.clone(), running.clone());
+            move || {
+                let format = RequestedFormat::new::<RgbFormat>(
+                    RequestedFormatType::AbsoluteHighestResolution,
+                );
+                let camera = Camera::new(CameraIndex::Index(index), format)
+                    .and_then(|mut camera| camera.open_stream().map(|_| camera));
+                let mut camera = match camera {
+                    Ok(camera) => {
+                        let _ = opened.send(Ok(()));
+                        camera
+                    }
+                    Err(err) => {
+                        let _ = opened.send(Err(format!("Failed to open camera: {}", err)));
+                        return;
+                    }
+                };
+                while running.load(Ordering::Relaxed) {
+                    let frame = camera
+                        .frame()
+                        .and_then(|buffer| buffer.decode_image::<RgbFormat>());
+                    let Ok(mut shared) = shared.lock() else {
+                        break;
+                    };
+                    match frame {
+                        Ok(frame) => shared.frame = Some(frame),
+                        Err(err) => {
+                            shared.error = Some(format!("Failed to capture frame: {}", err));
+                            break;
+                        }
+                    }
+                }
+                let _ = camera.stop_stream();
+            }
+        });
+        result
+            .recv()
+            .map_err(|_| "Camera thread stopped".to_string())??;
+        Ok(CameraFeed {
+            shared,
+            running,
+            worker: Some(worker),
+        })
+    }
+
+    /// The newest frame, None when there was none since the last call.
+    pub fn frame(&self) -> Option<RgbImage> {
+        self.shared.lock().ok()?.frame.take()
+    }
+
+    /// Why the camera stopped streaming, once it did.
+    pub fn error(&self) -> Option<String> {
+        self.shared.lock().ok()?.error.clone()
+    }
+}
+
+impl Drop for CameraFeed {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        // waits for at most the frame being captured
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{evaluate::edit_distance, subtitle::Cue};
+use std::time::Duration;
+
+// one character in this many may be read differently from one capture to
+// the next and still be the same caption
+const CHARS_PER_MISREAD: usize = 6;
+
+// characters the shorter of two readings needs for only its start to be
+// compared with the longer one, a short line such as "No" would otherwise
+// match any caption that starts the same
+const MIN_SHARED_START: usize = 10;
+
+// cues looked back on for a line that was missing from a reading or two, as
+// when the captions flicker or one capture fails to read them
+const RECENT_CUES: usize = 4;
+
+// the characters of a line as compared between captures, OCR of the same
+// caption differs in case and punctuation now and then
+fn normalized(line: &str) -> Vec<char> {
+    let words: Vec<String> = line
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.join(" ").chars().collect()
+}
+
+// whether `a` and `b` are readings of the same text
+fn same(a: &[char], b: &[char]) -> bool {
+    edit_distance(a, b) <= a.len().max(b.len()) / CHARS_PER_MISREAD
+}
+
+// whether `a` and `b` are readings of the same text, one perhaps cut short
+// when it is long enough to tell
+fn same_start(a: &[char], b: &[char]) -> bool {
+    let shorter = a.len().min(b.len());
+    if shorter < MIN_SHARED_START {
+        return same(a, b);
+    }
+    same(&a[..shorter], &b[..shorter])
+}
+
+/// Builds the transcript of live captions out of what a caption region shows
+/// each time it is read, one [`Cue`] for each caption line.
+///
+/// A line still on screen from the reading before extends its cue, lines
+/// scrolling up as new ones come in are not repeated, and a line growing word
+/// by word as it is spoken, as live captions do, stays one cue with the text
+/// of its longest reading. Lines are compared ignoring case and punctuation,
+/// allowing for a misread character now and then, and a line missing from a
+/// reading or two is not repeated when it is back.
+#[derive(Default)]
+pub struct CaptionTracker {
+    cues: Vec<Cue>,
+    // the cues of the lines the last reading showed, with their text as compared
+    visible: Vec<(usize, Vec<char>)>,
+}
+
+impl CaptionTracker {
+    pub fn new() -> Self {
+        CaptionTracker::default()
+    }
+
+    /// Add what the region showed when read at `from`, which lasts until
+    /// `to` when it is read next, both counted from the start of the
+    /// recording. Returns whether a cue was added or its text changed.
+    pub fn add(&mut self, from: Duration, to: Duration, text: &str) -> bool {
+        let mut changed = false;
+        let mut visible = Vec::new();
+        let mut unmatched = std::mem::take(&mut self.visible);
+        for line in text.lines() {
+            let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+            let compared = normalized(&line);
+            if compared.is_empty() {
+                continue;
+            }
+            let matched = unmatched
+                .iter()
+                .position(|(_, cue)| same_start(cue, &compared))
+                .map(|position| unmatched.remove(position))
+                .or_else(|| {
+                    // a line that went missing for a moment and is back
+                    let recent = self.cues.len().saturating_sub(RECENT_CUES)..self.cues.len();
+                    recent
+                        .rev()
+                        .filter(|index| visible.iter().all(|(shown, _)| shown != index))
+                        .map(|index| (index, normalized(&self.cues[index].text)))
+                        .find(|(_, cue)| same(cue, &compared))
+                });
+            match matched {
+                Some((index, cue_text)) => {
+                    let cue = &mut self.cues[index];
+                    cue.end = to;
+                    // the caption grew as it was spoken
+                    let words = |text: &[char]| text.iter().filter(|c| **c == ' ').count();
+                    if words(&compared) > words(&cue_text) {
+                        cue.text = line;
+                        changed = true;
+                        visible.push((index, compared));
+                    } else {
+                        visible.push((index, cue_text));
+                    }
+                }
+                None => {
+                    self.cues.push(Cue {
+                        start: from,
+                        end: to,
+                        text: line,
+                        speaker: None,
+                    });
+                    changed = true;
+                    visible.push((self.cues.len() - 1, compared));
+                }
+            }
+        }
+        self.visible = visible;
+        changed
+    }
+
+    /// The region still shows what it did when read last, until `to`.
+    pub fn hold(&mut self, to: Duration) {
+        for (index, _) in &self.visible {
+            self.cues[*index].end = to;
+        }
+    }
+
+    pub fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    pub fn into_cues(self) -> Vec<Cue> {
+        self.cues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn texts(tracker: &CaptionTracker) -> Vec<&str> {
+        tracker.cues().iter().map(|cue| cue.text.as_str()).collect()
+    }
+
+    #[test]
+    fn growing_line_stays_one_cue() {
+        let mut tracker = CaptionTracker::new();
+        assert!(tracker.add(seconds(0), seconds(1), "We went down to"));
+        assert!(tracker.add(seconds(1), seconds(2), "We went down to the river"));
+        assert!(!tracker.add(seconds(2), seconds(3), "We went down to the river"));
+        assert_eq!(texts(&tracker), ["We went down to the river"]);
+        assert_eq!(tracker.cues()[0].start, seconds(0));
+        assert_eq!(tracker.cues()[0].end, seconds(3));
+    }
+
+    #[test]
+    fn scrolled_lines_are_not_repeated() {
+        let mut tracker = CaptionTracker::new();
+        tracker.add(
+            seconds(0),
+            seconds(1),
+            "the first caption line\nthe second caption line",
+        );
+        tracker.add(
+            seconds(1),
+            seconds(2),
+            "the second caption line\nand a third one",
+        );
+        assert_eq!(
+            texts(&tracker),
+            [
+                "the first caption line",
+                "the second caption line",
+                "and a third one"
+            ]
+        );
+        assert_eq!(tracker.cues()[1].end, seconds(2));
+    }
+
+    #[test]
+    fn misread_character_is_the_same_caption() {
+        let mut tracker = CaptionTracker::new();
+        tracker.add(seconds(0), seconds(1), "Hello there, my friend");
+        tracker.add(seconds(1), seconds(2), "Hel1o there my friend");
+        assert_eq!(texts(&tracker), ["Hello there, my friend"]);
+    }
+
+    #[test]
+    fn short_line_does_not_swallow_the_next_caption() {
+        let mut tracker = CaptionTracker::new();
+        tracker.add(seconds(0), seconds(1), "No");
+        tracker.add(seconds(1), seconds(2), "Nothing else matters");
+        assert_eq!(texts(&tracker), ["No", "Nothing else matters"]);
+    }
+
+    #[test]
+    fn line_back_after_a_flicker_is_not_repeated() {
+        let mut tracker = CaptionTracker::new();
+        tracker.add(seconds(0), seconds(1), "a caption that flickers");
+        tracker.add(seconds(1), seconds(2), "");
+        tracker.add(seconds(2), seconds(3), "a caption that flickers");
+        assert_eq!(texts(&tracker), ["a caption that flickers"]);
+        tracker.hold(seconds(5));
+        assert_eq!(tracker.cues()[0].end, seconds(5));
+    }
+}
@@ -0,0 +1,173 @@
+use image::{imageops, DynamicImage, RgbaImage};
+use std::{
+    error::Error,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+// A rectangle of the virtual desktop, in the coordinates windows are placed in
+#[derive(Clone, Copy)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Area {
+    // the smallest area holding all of them
+    pub fn union(areas: impl IntoIterator<Item = Area>) -> Option<Area> {
+        let mut areas = areas.into_iter();
+        let first = areas.next()?;
+        let (mut left, mut top) = (first.x, first.y);
+        let (mut right, mut bottom) = (first.right(), first.bottom());
+        for area in areas {
+            left = left.min(area.x);
+            top = top.min(area.y);
+            right = right.max(area.right());
+            bottom = bottom.max(area.bottom());
+        }
+        Some(Area {
+            x: left,
+            y: top,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        })
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+}
+
+// A screenshot of one monitor and where the monitor sits on the desktop, the
+// image has the monitor's own resolution which is larger than the area on
+// monitors scaled for high DPI
+pub struct Shot {
+    pub area: Area,
+    pub image: RgbaImage,
+}
+
+// the region of the desktop given as left, top, right and bottom cut from the
+// shot showing most of it, in the monitor's own pixels so high DPI screens
+// keep their detail
+pub fn cut<'a>(
+    shots: impl IntoIterator<Item = &'a Shot>,
+    [left, top, right, bottom]: [f32; 4],
+) -> Option<DynamicImage> {
+    let overlap = |area: &Area| {
+        let width = right.min(area.right() as f32) - left.max(area.x as f32);
+        let height = bottom.min(area.bottom() as f32) - top.max(area.y as f32);
+        width.max(0.0) * height.max(0.0)
+    };
+    let shot = shots
+        .into_iter()
+        .max_by(|a, b| overlap(&a.area).total_cmp(&overlap(&b.area)))?;
+    let area = shot.area;
+    let (image_width, image_height) = shot.image.dimensions();
+    let scale_x = image_width as f32 / area.width as f32;
+    let scale_y = image_height as f32 / area.height as f32;
+    let to_pixels = |value: f32, origin: i32, scale: f32, size: u32| {
+        (((value - origin as f32) * scale).max(0.0) as u32).min(size)
+    };
+    let x0 = to_pixels(left, area.x, scale_x, image_width);
+    let y0 = to_pixels(top, area.y, scale_y, image_height);
+    let x1 = to_pixels(right, area.x, scale_x, image_width);
+    let y1 = to_pixels(bottom, area.y, scale_y, image_height);
+    (x1 > x0 && y1 > y0).then(|| {
+        let region = imageops::crop_imm(&shot.image, x0, y0, x1 - x0, y1 - y0);
+        DynamicImage::ImageRgba8(region.to_image())
+    })
+}
+
+// capture the region of the desktop given as left, top, right and bottom on
+// a worker thread, after `delay`
+pub fn spawn_region(
+    desktop: Area,
+    rect: [f32; 4],
+    delay: Duration,
+) -> Receiver<Result<DynamicImage, String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        let captured = capture_monitors(desktop)
+            .map_err(|err| err.to_string())
+            .and_then(|shots| cut(&shots, rect).ok_or_else(|| "Region is off screen".into()));
+        let _ = sender.send(captured);
+    });
+    receiver
+}
+
+// every monitor, each at its own resolution
+#[cfg(any(windows, target_os = "macos"))]
+pub fn capture_monitors(_desktop: Area) -> Result<Vec<Shot>, Box<dyn Error>> {
+    let mut shots = Vec::new();
+    for monitor in xcap::Monitor::all()? {
+        shots.push(Shot {
+            area: Area {
+                x: monitor.x()?,
+                y: monitor.y()?,
+                width: monitor.width()?,
+                height: monitor.height()?,
+            },
+            image: monitor.capture_image()?,
+        });
+    }
+    Ok(shots)
+}
+
+// the desktop portal takes the whole `desktop` in one image
+#[cfg(target_os = "linux")]
+pub fn capture_monitors(desktop: Area) -> Result<Vec<Shot>, Box<dyn Error>> {
+    let image = crate::screenshot::screenshot(false)?
+        .ok_or("Screenshot cancelled")?
+        .to_rgba8();
+    Ok(vec![Shot {
+        area: desktop,
+        image,
+    }])
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+pub fn capture_monitors(_desktop: Area) -> Result<Vec<Shot>, Box<dyn Error>> {
+    Err("Screen capture is not supported on this platform".into())
+}
+
+// the focused window of another app, or the topmost one when none is focused
+// as happens on macOS right after our window is hidden
+#[cfg(any(windows, target_os = "macos"))]
+pub fn capture_active_window() -> Result<DynamicImage, Box<dyn Error>> {
+    let own = std::process::id();
+    let mut candidates = Vec::new();
+    for window in xcap::Window::all()? {
+        if window.pid()? == own || window.is_minimized()? || window.width()? == 0 {
+            continue;
+        }
+        if window.is_focused()? {
+            return Ok(DynamicImage::ImageRgba8(window.capture_image()?));
+        }
+        candidates.push((window.z()?, window));
+    }
+    let (_, window) = candidates
+        .into_iter()
+        .max_by_key(|(z, _)| *z)
+        .ok_or("No window to capture")?;
+    Ok(DynamicImage::ImageRgba8(window.capture_image()?))
+}
+
+// Wayland tells no app which window has the focus, the desktop portal asks the
+// user instead and its picker has a mode for windows
+#[cfg(target_os = "linux")]
+pub fn capture_active_window() -> Result<DynamicImage, Box<dyn Error>> {
+    Ok(crate::screenshot::screenshot(true)?.ok_or("Screenshot cancelled")?)
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+pub fn capture_active_window() -> Result<DynamicImage, Box<dyn Error>> {
+    Err("Window capture is not supported on this platform".into())
+}
@@ -0,0 +1,131 @@
+use crate::{config::Config, flow::Flow, locale::tr};
+use imgui::{Condition, Ui};
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+use transcribewizard::diff::{self, Change};
+
+const REMOVED: [f32; 4] = [0.9, 0.3, 0.3, 1.0];
+const ADDED: [f32; 4] = [0.3, 0.8, 0.3, 1.0];
+
+// An AI cleanup of the text, from the request until the correction is accepted
+pub enum Cleanup {
+    Pending {
+        original: String,
+        job: Receiver<Result<String, String>>,
+    },
+    Review {
+        corrected: String,
+        changes: Vec<(Change, String)>,
+    },
+    Failed(String),
+}
+
+impl Cleanup {
+    // send the text to the configured model on a worker thread
+    pub fn start(config: &Config, text: &str) -> Self {
+        let client = config.chat_client(&config.llm_model);
+        let prompt = config.llm_prompt.clone();
+        let original = text.to_string();
+        let (sender, job) = mpsc::channel();
+        {
+            let text = original.clone();
+            thread::spawn(move || {
+                let result = client
+                    .complete(&prompt, &text)
+                    .map_err(|err| err.to_string());
+                let _ = sender.send(result);
+            });
+        }
+        Cleanup::Pending { original, job }
+    }
+
+    // pick up the reply once the model is done
+    pub fn poll(&mut self) {
+        let Cleanup::Pending { original, job } = self else {
+            return;
+        };
+        *self = match job.try_recv() {
+            Ok(Ok(corrected)) => {
+                // models tend to add a line break or two, which would show up as changes
+                let corrected = corrected.trim().to_string();
+                let changes = diff::diff(original, &corrected)
+                    .into_iter()
+                    .map(|(change, text)| (change, text.to_string()))
+                    .collect();
+                Cleanup::Review { corrected, changes }
+            }
+            Ok(Err(err)) => Cleanup::Failed(format!("Error cleaning up text: {}", err)),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                Cleanup::Failed("Error cleaning up text: worker stopped".into())
+            }
+        };
+    }
+
+    // draw the review window, `open` turns false once it is dismissed and the
+    // corrected text is returned when accepted
+    pub fn draw(&mut self, ui: &Ui, open: &mut bool) -> Option<String> {
+        let mut accepted = None;
+        let [display_width, display_height] = ui.io().display_size;
+        ui.window(format!("{}###Clean up with AI", tr("Clean up with AI")))
+            .position(
+                [display_width / 2.0, display_height / 2.0],
+                Condition::FirstUseEver,
+            )
+            .position_pivot([0.5, 0.5])
+            .size([600.0, 400.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| match self {
+                Cleanup::Pending { .. } => ui.text(tr("Waiting for the model...")),
+                Cleanup::Failed(err) => ui.text_wrapped(err.as_str()),
+                Cleanup::Review { corrected, changes } => {
+                    if ui.button(tr("Accept")) {
+                        accepted = Some(corrected.clone());
+                    }
+                    ui.same_line();
+                    if changes.iter().all(|(change, _)| *change == Change::Same) {
+                        ui.text_disabled(tr("No changes"));
+                    }
+                    ui.child_window("changes")
+                        .border(true)
+                        .build(|| draw_changes(ui, changes));
+                }
+            });
+        if accepted.is_some() {
+            *open = false;
+        }
+        accepted
+    }
+}
+
+// the corrected text with removed words struck through in red and added ones in green
+pub fn draw_changes(ui: &Ui, changes: &[(Change, String)]) {
+    let mut flow = Flow::new(ui);
+    let draw_list = ui.get_window_draw_list();
+    for (change, text) in changes {
+        for (index, line) in text.split('\n').enumerate() {
+            // removed line breaks are not laid out, only the words around them
+            if index > 0 && *change != Change::Removed {
+                flow.break_line(ui);
+            }
+            for word in line.split_whitespace() {
+                flow.place(ui, word);
+                match change {
+                    Change::Same => ui.text(word),
+                    Change::Added => ui.text_colored(ADDED, word),
+                    Change::Removed => {
+                        ui.text_colored(REMOVED, word);
+                        let [left, top] = ui.item_rect_min();
+                        let [right, bottom] = ui.item_rect_max();
+                        let middle = (top + bottom) / 2.0;
+                        draw_list
+                            .add_line([left, middle], [right, middle], REMOVED)
+                            .build();
+                    }
+                }
+            }
+        }
+    }
+}
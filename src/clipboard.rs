@@ -0,0 +1,711 @@
+use crate::{
+    config::Selection,
+    startup::{self, Subsystem},
+};
+use clipboard_rs::{
+    common::{Result, RustImageData},
+    Clipboard, ClipboardContext, ContentFormat,
+};
+#[cfg(target_os = "linux")]
+use {
+    clipboard_rs::common::RustImage,
+    std::io::Read,
+    tracing::info,
+    wl_clipboard_rs::{copy, paste},
+};
+
+const UNAVAILABLE: &str = "No clipboard available";
+
+// What the app reads from and writes to a clipboard, so the system clipboard
+// and the primary selection can stand in for each other
+pub trait ClipboardProvider {
+    fn available_formats(&self) -> Result<Vec<String>>;
+    fn has(&self, format: ContentFormat) -> bool;
+    fn get_buffer(&self, format: &str) -> Result<Vec<u8>>;
+    fn get_text(&self) -> Result<String>;
+    fn get_rich_text(&self) -> Result<String>;
+    fn get_html(&self) -> Result<String>;
+    fn get_image(&self) -> Result<RustImageData>;
+    fn get_files(&self) -> Result<Vec<String>>;
+    fn set_text(&self, text: String) -> Result<()>;
+    fn set_image(&self, image: RustImageData) -> Result<()>;
+}
+
+impl ClipboardProvider for ClipboardContext {
+    fn available_formats(&self) -> Result<Vec<String>> {
+        Clipboard::available_formats(self)
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        Clipboard::has(self, format)
+    }
+
+    fn get_buffer(&self, format: &str) -> Result<Vec<u8>> {
+        Clipboard::get_buffer(self, format)
+    }
+
+    fn get_text(&self) -> Result<String> {
+        Clipboard::get_text(self)
+    }
+
+    fn get_rich_text(&self) -> Result<String> {
+        Clipboard::get_rich_text(self)
+    }
+
+    fn get_html(&self) -> Result<String> {
+        Clipboard::get_html(self)
+    }
+
+    fn get_image(&self) -> Result<RustImageData> {
+        Clipboard::get_image(self)
+    }
+
+    fn get_files(&self) -> Result<Vec<String>> {
+        Clipboard::get_files(self)
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        Clipboard::set_text(self, text)
+    }
+
+    fn set_image(&self, image: RustImageData) -> Result<()> {
+        Clipboard::set_image(self, image)
+    }
+}
+
+// The selection of X11 and Wayland that holds whatever was last selected,
+// pasted with the middle mouse button
+//
+// It carries plain text, HTML, images and files but no RTF. Which of them it
+// holds is read from the formats on offer, only what is asked for is fetched.
+// On Wayland the compositor has to support the data control protocol.
+#[cfg(target_os = "linux")]
+pub enum PrimarySelection {
+    Wayland,
+    // the thread that owns the window of the app's connection to the X server
+    X11(x11::Selection),
+}
+
+// names the formats go by on X11 and Wayland, the first one is read and all of
+// them are offered
+#[cfg(target_os = "linux")]
+const TEXT_FORMATS: &[&str] = &[
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+#[cfg(target_os = "linux")]
+const HTML_FORMAT: &str = "text/html";
+#[cfg(target_os = "linux")]
+const IMAGE_FORMAT: &str = "image/png";
+#[cfg(target_os = "linux")]
+const FILES_FORMAT: &str = "text/uri-list";
+
+#[cfg(target_os = "linux")]
+impl PrimarySelection {
+    fn open() -> Result<Self> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match wl_clipboard_rs::utils::is_primary_selection_supported() {
+                Ok(true) => return Ok(PrimarySelection::Wayland),
+                Ok(false) => {}
+                Err(err) => info!("No primary selection through Wayland: {}", err),
+            }
+        }
+        Ok(PrimarySelection::X11(x11::Selection::open()?))
+    }
+
+    fn formats(&self) -> Result<Vec<String>> {
+        match self {
+            PrimarySelection::Wayland => {
+                let formats = paste::get_mime_types_ordered(
+                    paste::ClipboardType::Primary,
+                    paste::Seat::Unspecified,
+                );
+                match formats {
+                    Ok(formats) => Ok(formats),
+                    Err(paste::Error::ClipboardEmpty | paste::Error::NoSeats) => Ok(Vec::new()),
+                    Err(err) => Err(format!("Primary selection: {}", err).into()),
+                }
+            }
+            PrimarySelection::X11(selection) => selection.targets(),
+        }
+    }
+
+    fn read(&self, format: &str) -> Result<Vec<u8>> {
+        match self {
+            PrimarySelection::Wayland => {
+                let (mut pipe, _) = paste::get_contents(
+                    paste::ClipboardType::Primary,
+                    paste::Seat::Unspecified,
+                    paste::MimeType::Specific(format),
+                )
+                .map_err(|err| format!("Primary selection: {}", err))?;
+                let mut bytes = Vec::new();
+                pipe.read_to_end(&mut bytes)?;
+                Ok(bytes)
+            }
+            PrimarySelection::X11(selection) => selection.read(format),
+        }
+    }
+
+    // the first of `formats` on offer, read
+    fn read_first(&self, formats: &[&str]) -> Result<Vec<u8>> {
+        let offered = self.formats()?;
+        let format = formats
+            .iter()
+            .find(|format| offered.iter().any(|name| name == *format))
+            .ok_or("The primary selection holds none of this")?;
+        self.read(format)
+    }
+
+    // offer the same bytes under each of `formats`
+    fn write(&self, formats: &[&str], bytes: Vec<u8>) -> Result<()> {
+        match self {
+            PrimarySelection::Wayland => {
+                let bytes: Box<[u8]> = bytes.into();
+                let sources = formats
+                    .iter()
+                    .map(|format| copy::MimeSource {
+                        source: copy::Source::Bytes(bytes.clone()),
+                        mime_type: copy::MimeType::Specific(format.to_string()),
+                    })
+                    .collect();
+                let mut options = copy::Options::new();
+                options.clipboard(copy::ClipboardType::Primary);
+                options
+                    .copy_multi(sources)
+                    .map_err(|err| format!("Primary selection: {}", err).into())
+            }
+            PrimarySelection::X11(selection) => selection.write(formats, bytes.into()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ClipboardProvider for PrimarySelection {
+    fn available_formats(&self) -> Result<Vec<String>> {
+        self.formats()
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        let Ok(offered) = self.formats() else {
+            return false;
+        };
+        offered.iter().any(|name| match &format {
+            ContentFormat::Text => TEXT_FORMATS.contains(&name.as_str()),
+            ContentFormat::Html => name == HTML_FORMAT,
+            ContentFormat::Image => name.starts_with("image/"),
+            ContentFormat::Files => name == FILES_FORMAT,
+            _ => false,
+        })
+    }
+
+    fn get_buffer(&self, format: &str) -> Result<Vec<u8>> {
+        self.read(format)
+    }
+
+    fn get_text(&self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.read_first(TEXT_FORMATS)?).into_owned())
+    }
+
+    fn get_rich_text(&self) -> Result<String> {
+        Err("The primary selection cannot hold RTF".into())
+    }
+
+    fn get_html(&self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.read(HTML_FORMAT)?).into_owned())
+    }
+
+    fn get_image(&self) -> Result<RustImageData> {
+        // PNG first, the image formats of the apps offering others are all read alike
+        let offered = self.formats()?;
+        let format = offered
+            .iter()
+            .find(|name| *name == IMAGE_FORMAT)
+            .or_else(|| offered.iter().find(|name| name.starts_with("image/")))
+            .ok_or("The primary selection holds no image")?;
+        RustImageData::from_bytes(&self.read(format)?)
+    }
+
+    fn get_files(&self) -> Result<Vec<String>> {
+        let list = String::from_utf8_lossy(&self.read(FILES_FORMAT)?).into_owned();
+        Ok(list
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        self.write(TEXT_FORMATS, text.into_bytes())
+    }
+
+    fn set_image(&self, image: RustImageData) -> Result<()> {
+        let png = image.to_png()?;
+        self.write(&[IMAGE_FORMAT], png.get_bytes().to_vec())
+    }
+}
+
+// the primary selection over the app's own connection to the X server, read
+// and offered from a thread that also answers the apps pasting what it holds
+#[cfg(target_os = "linux")]
+mod x11 {
+    use super::Result;
+    use std::{
+        collections::HashMap,
+        sync::{
+            mpsc::{self, RecvTimeoutError, Sender},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+    use x11rb::{
+        connection::{Connection, RequestConnection},
+        protocol::{
+            xproto::{
+                Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, Property,
+                SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass,
+                SELECTION_NOTIFY_EVENT,
+            },
+            Event,
+        },
+        rust_connection::RustConnection,
+        wrapper::ConnectionExt as _,
+        COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE,
+    };
+
+    // how long the owner of the selection gets to answer
+    const ANSWER_TIMEOUT: Duration = Duration::from_secs(2);
+
+    // how often the thread looks for events while it has nothing else to do
+    const EVENT_POLL: Duration = Duration::from_millis(10);
+
+    enum Request {
+        Targets(Sender<Result<Vec<String>>>),
+        Read(String, Sender<Result<Vec<u8>>>),
+        Write(Vec<String>, Arc<[u8]>, Sender<Result<()>>),
+    }
+
+    pub struct Selection(Sender<Request>);
+
+    impl Selection {
+        pub fn open() -> Result<Self> {
+            if std::env::var_os("DISPLAY").is_none() {
+                return Err("Neither Wayland data control nor an X server is available".into());
+            }
+            let mut owner = Owner::connect()?;
+            let (requests, received) = mpsc::channel();
+            thread::spawn(move || loop {
+                match received.recv_timeout(EVENT_POLL) {
+                    Ok(request) => owner.handle(request),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if owner.serve_pending().is_err() {
+                    break;
+                }
+            });
+            Ok(Selection(requests))
+        }
+
+        fn ask<T>(&self, request: impl FnOnce(Sender<Result<T>>) -> Request) -> Result<T> {
+            let (sender, answer) = mpsc::channel();
+            self.0
+                .send(request(sender))
+                .map_err(|_| "The primary selection is gone")?;
+            answer.recv().map_err(|_| "The primary selection is gone")?
+        }
+
+        pub fn targets(&self) -> Result<Vec<String>> {
+            self.ask(Request::Targets)
+        }
+
+        pub fn read(&self, format: &str) -> Result<Vec<u8>> {
+            self.ask(|sender| Request::Read(format.to_string(), sender))
+        }
+
+        pub fn write(&self, formats: &[&str], bytes: Arc<[u8]>) -> Result<()> {
+            let formats = formats.iter().map(|format| format.to_string()).collect();
+            self.ask(|sender| Request::Write(formats, bytes, sender))
+        }
+    }
+
+    // the connection and window of the thread, with what it offers while it
+    // owns the selection
+    struct Owner {
+        connection: RustConnection,
+        window: Window,
+        // the property the selection is converted into, and the atoms naming
+        // TARGETS and INCR
+        property: Atom,
+        targets: Atom,
+        incr: Atom,
+        names: HashMap<Atom, String>,
+        offered: Vec<(Atom, Arc<[u8]>)>,
+    }
+
+    impl Owner {
+        fn connect() -> Result<Self> {
+            let (connection, screen) = x11rb::connect(None)?;
+            let root = connection.setup().roots[screen].root;
+            let window = connection.generate_id()?;
+            connection.create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                COPY_FROM_PARENT,
+                &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            )?;
+            // asked for together, which takes one round trip
+            let cookies = [&b"TRANSCRIBEWIZARD_PRIMARY"[..], b"TARGETS", b"INCR"]
+                .map(|name| connection.intern_atom(false, name));
+            let mut atoms = [NONE; 3];
+            for (atom, cookie) in atoms.iter_mut().zip(cookies) {
+                *atom = cookie?.reply()?.atom;
+            }
+            let [property, targets, incr] = atoms;
+            Ok(Owner {
+                connection,
+                window,
+                property,
+                targets,
+                incr,
+                names: HashMap::new(),
+                offered: Vec::new(),
+            })
+        }
+
+        fn handle(&mut self, request: Request) {
+            match request {
+                Request::Targets(answer) => {
+                    let _ = answer.send(self.targets());
+                }
+                Request::Read(format, answer) => {
+                    let _ = answer.send(self.atom(&format).and_then(|target| self.read(target)));
+                }
+                Request::Write(formats, bytes, answer) => {
+                    let _ = answer.send(self.write(&formats, bytes));
+                }
+            }
+        }
+
+        fn atom(&self, name: &str) -> Result<Atom> {
+            Ok(self
+                .connection
+                .intern_atom(false, name.as_bytes())?
+                .reply()?
+                .atom)
+        }
+
+        // the formats on offer, their names looked up all at once the first
+        // time each is seen
+        fn targets(&mut self) -> Result<Vec<String>> {
+            let reply = self.read(self.targets)?;
+            let atoms: Vec<Atom> = reply
+                .chunks_exact(4)
+                .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+            let unknown: Vec<Atom> = atoms
+                .iter()
+                .copied()
+                .filter(|atom| !self.names.contains_key(atom))
+                .collect();
+            let cookies = unknown
+                .iter()
+                .map(|&atom| self.connection.get_atom_name(atom))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            for (atom, cookie) in unknown.into_iter().zip(cookies) {
+                let name = String::from_utf8_lossy(&cookie.reply()?.name).into_owned();
+                self.names.insert(atom, name);
+            }
+            Ok(atoms
+                .iter()
+                .filter_map(|atom| self.names.get(atom).cloned())
+                .collect())
+        }
+
+        // convert the selection to `target` and read it off the property, in
+        // chunks when the owner sends it incrementally
+        fn read(&mut self, target: Atom) -> Result<Vec<u8>> {
+            self.connection.convert_selection(
+                self.window,
+                AtomEnum::PRIMARY.into(),
+                target,
+                self.property,
+                CURRENT_TIME,
+            )?;
+            self.connection.flush()?;
+            let window = self.window;
+            let converted = self.wait(|event| match event {
+                Event::SelectionNotify(notify) if notify.requestor == window => {
+                    Some(notify.property)
+                }
+                _ => None,
+            })?;
+            if converted == NONE {
+                return Err("The primary selection holds none of this".into());
+            }
+            let reply = self.take_property()?;
+            if reply.type_ != self.incr {
+                return Ok(reply.value);
+            }
+            let mut bytes = Vec::new();
+            loop {
+                let property = self.property;
+                self.wait(|event| match event {
+                    Event::PropertyNotify(notify)
+                        if notify.atom == property && notify.state == Property::NEW_VALUE =>
+                    {
+                        Some(())
+                    }
+                    _ => None,
+                })?;
+                let chunk = self.take_property()?.value;
+                if chunk.is_empty() {
+                    return Ok(bytes);
+                }
+                bytes.extend(chunk);
+            }
+        }
+
+        fn take_property(&self) -> Result<x11rb::protocol::xproto::GetPropertyReply> {
+            let reply = self
+                .connection
+                .get_property(
+                    true,
+                    self.window,
+                    self.property,
+                    AtomEnum::ANY,
+                    0,
+                    u32::MAX / 4,
+                )?
+                .reply()?;
+            self.connection.flush()?;
+            Ok(reply)
+        }
+
+        // the first event `wanted` picks, the apps pasting from us are answered
+        // meanwhile
+        fn wait<T>(&mut self, mut wanted: impl FnMut(&Event) -> Option<T>) -> Result<T> {
+            let deadline = Instant::now() + ANSWER_TIMEOUT;
+            while Instant::now() < deadline {
+                match self.connection.poll_for_event()? {
+                    Some(event) => {
+                        if let Some(found) = wanted(&event) {
+                            return Ok(found);
+                        }
+                        self.serve(event)?;
+                    }
+                    None => thread::sleep(Duration::from_millis(1)),
+                }
+            }
+            Err("The primary selection did not answer".into())
+        }
+
+        fn write(&mut self, formats: &[String], bytes: Arc<[u8]>) -> Result<()> {
+            let cookies = formats
+                .iter()
+                .map(|format| self.connection.intern_atom(false, format.as_bytes()))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let mut offered = Vec::new();
+            for cookie in cookies {
+                offered.push((cookie.reply()?.atom, bytes.clone()));
+            }
+            self.offered = offered;
+            self.connection.set_selection_owner(
+                self.window,
+                AtomEnum::PRIMARY.into(),
+                CURRENT_TIME,
+            )?;
+            self.connection.flush()?;
+            Ok(())
+        }
+
+        // answer the events that came in, until there are none left
+        fn serve_pending(&mut self) -> Result<()> {
+            while let Some(event) = self.connection.poll_for_event()? {
+                self.serve(event)?;
+            }
+            Ok(())
+        }
+
+        fn serve(&mut self, event: Event) -> Result<()> {
+            match event {
+                Event::SelectionRequest(request) => self.answer(request),
+                // someone else selected something
+                Event::SelectionClear(_) => {
+                    self.offered.clear();
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+
+        // put what an app asked for on its property, refused when it is not
+        // offered or too large to send in one piece
+        fn answer(&self, request: SelectionRequestEvent) -> Result<()> {
+            let limit = self.connection.maximum_request_bytes() - 64;
+            let property = match request.property {
+                NONE => request.target,
+                property => property,
+            };
+            let answered = if request.target == self.targets {
+                let mut atoms = vec![self.targets];
+                atoms.extend(self.offered.iter().map(|(atom, _)| *atom));
+                self.connection.change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &atoms,
+                )?;
+                true
+            } else {
+                match self
+                    .offered
+                    .iter()
+                    .find(|(atom, bytes)| *atom == request.target && bytes.len() <= limit)
+                {
+                    Some((atom, bytes)) => {
+                        self.connection.change_property8(
+                            PropMode::REPLACE,
+                            request.requestor,
+                            property,
+                            *atom,
+                            bytes,
+                        )?;
+                        true
+                    }
+                    None => false,
+                }
+            };
+            let notify = SelectionNotifyEvent {
+                response_type: SELECTION_NOTIFY_EVENT,
+                sequence: 0,
+                time: request.time,
+                requestor: request.requestor,
+                selection: request.selection,
+                target: request.target,
+                property: if answered { property } else { NONE },
+            };
+            self.connection
+                .send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+            self.connection.flush()?;
+            Ok(())
+        }
+    }
+}
+
+// The clipboards of the system, read from and written to as the settings pick
+//
+// Either can be missing, reading or writing one that is then fails and it
+// never holds anything.
+pub struct SystemClipboard {
+    clipboard: Option<ClipboardContext>,
+    #[cfg(target_os = "linux")]
+    primary: Option<PrimarySelection>,
+    pub source: Selection,
+    pub target: Selection,
+}
+
+impl SystemClipboard {
+    pub fn open(source: Selection, target: Selection) -> Self {
+        let clipboard = match ClipboardContext::new() {
+            Ok(context) => Some(context),
+            Err(err) => {
+                startup::degraded(Subsystem::Clipboard, err);
+                None
+            }
+        };
+        SystemClipboard {
+            clipboard,
+            #[cfg(target_os = "linux")]
+            primary: match PrimarySelection::open() {
+                Ok(primary) => Some(primary),
+                Err(err) => {
+                    startup::degraded(Subsystem::PrimarySelection, err);
+                    None
+                }
+            },
+            source,
+            target,
+        }
+    }
+
+    fn provider(&self, selection: Selection) -> Result<&dyn ClipboardProvider> {
+        let provider: Option<&dyn ClipboardProvider> = match selection {
+            Selection::Clipboard => self
+                .clipboard
+                .as_ref()
+                .map(|context| context as &dyn ClipboardProvider),
+            #[cfg(target_os = "linux")]
+            Selection::Primary => self
+                .primary
+                .as_ref()
+                .map(|primary| primary as &dyn ClipboardProvider),
+            #[cfg(not(target_os = "linux"))]
+            Selection::Primary => None,
+        };
+        provider.ok_or_else(|| UNAVAILABLE.into())
+    }
+
+    fn source(&self) -> Result<&dyn ClipboardProvider> {
+        self.provider(self.source)
+    }
+
+    fn target(&self) -> Result<&dyn ClipboardProvider> {
+        self.provider(self.target)
+    }
+}
+
+impl ClipboardProvider for SystemClipboard {
+    fn available_formats(&self) -> Result<Vec<String>> {
+        self.source()?.available_formats()
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        self.source().is_ok_and(|source| source.has(format))
+    }
+
+    fn get_buffer(&self, format: &str) -> Result<Vec<u8>> {
+        self.source()?.get_buffer(format)
+    }
+
+    fn get_text(&self) -> Result<String> {
+        self.source()?.get_text()
+    }
+
+    fn get_rich_text(&self) -> Result<String> {
+        self.source()?.get_rich_text()
+    }
+
+    fn get_html(&self) -> Result<String> {
+        self.source()?.get_html()
+    }
+
+    fn get_image(&self) -> Result<RustImageData> {
+        self.source()?.get_image()
+    }
+
+    fn get_files(&self) -> Result<Vec<String>> {
+        self.source()?.get_files()
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        self.target()?.set_text(text)
+    }
+
+    fn set_image(&self, image: RustImageData) -> Result<()> {
+        self.target()?.set_image(image)
+    }
+}
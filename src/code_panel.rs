@@ -0,0 +1,95 @@
+use crate::{
+    clipboard::SystemClipboard, locale::tr, masking::SecretGuard, status::Status, tabs::Editor,
+};
+use imgui::{Condition, Ui};
+use transcribewizard::codes::{self, Check, Code};
+
+// Window listing the IBANs, card numbers, ISBNs and passport zones in the
+// text with whether their checksums pass, kept up to date as the text changes
+#[derive(Default)]
+pub struct CodePanel {
+    // the text the codes were found in, looked over again only once it changed
+    scanned: String,
+    codes: Vec<Code>,
+}
+
+impl CodePanel {
+    pub fn update(&mut self, text: &str) {
+        if self.scanned != text {
+            self.scanned = text.to_string();
+            self.codes = codes::find(text);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.codes.len()
+    }
+
+    // codes whose checksum fails, probably misread
+    pub fn failed(&self) -> usize {
+        self.codes
+            .iter()
+            .filter(|code| code.check == Check::Failed)
+            .count()
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        editor: Editor,
+        clipboard: &SystemClipboard,
+        secret_guard: &mut SecretGuard,
+        status: &mut Status,
+        open: &mut bool,
+    ) {
+        ui.window(format!("{}###Codes", tr("Codes")))
+            .size([420.0, 300.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                if self.codes.is_empty() {
+                    ui.text_disabled(tr(
+                        "No IBANs, card numbers, ISBNs or passport zones in the text",
+                    ));
+                    return;
+                }
+                let mut fix = None;
+                for (index, code) in self.codes.iter().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    // card numbers are secrets like any other copied text
+                    if ui.small_button(tr("Copy")) {
+                        if let Err(err) = secret_guard.copy(clipboard, &code.text) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                    ui.same_line();
+                    ui.text_disabled(tr(code.kind.label()));
+                    ui.same_line();
+                    match &code.check {
+                        Check::Valid => {
+                            ui.text_colored([0.3, 0.8, 0.3, 1.0], tr("valid"));
+                        }
+                        Check::Fixed(fixed) => {
+                            ui.text_colored([0.9, 0.7, 0.2, 1.0], tr("misread"));
+                            ui.same_line();
+                            if ui.small_button(tr("Fix")) {
+                                fix = Some((code.range.clone(), fixed.clone()));
+                            }
+                            if ui.is_item_hovered() {
+                                ui.tooltip_text(fixed);
+                            }
+                        }
+                        Check::Failed => {
+                            ui.text_colored([0.9, 0.3, 0.3, 1.0], tr("checksum fails"));
+                        }
+                    }
+                    ui.text(&code.text);
+                }
+                // a fix is a step of its own to undo
+                if let Some((range, fixed)) = fix {
+                    editor.undo.track(editor.text, false);
+                    editor.text.replace_range(range, &fixed);
+                    editor.undo.track(editor.text, false);
+                }
+            });
+    }
+}
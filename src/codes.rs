@@ -0,0 +1,622 @@
+use crate::links::regex;
+use regex::Regex;
+use std::{ops::Range, sync::OnceLock};
+
+// what OCR reads in place of a digit, and the digit it stands for
+const DIGIT_LOOKALIKES: &[(char, char)] = &[
+    ('O', '0'),
+    ('o', '0'),
+    ('D', '0'),
+    ('Q', '0'),
+    ('I', '1'),
+    ('l', '1'),
+    ('i', '1'),
+    ('|', '1'),
+    ('Z', '2'),
+    ('z', '2'),
+    ('S', '5'),
+    ('s', '5'),
+    ('G', '6'),
+    ('b', '6'),
+    ('B', '8'),
+    ('g', '9'),
+    ('q', '9'),
+];
+
+// the other way round, for codes of letters and digits
+const LETTER_LOOKALIKES: &[(char, char)] = &[
+    ('0', 'O'),
+    ('1', 'I'),
+    ('2', 'Z'),
+    ('5', 'S'),
+    ('6', 'G'),
+    ('8', 'B'),
+];
+
+// digits of a card number, or what OCR made of them, grouped by spaces or dashes
+const CARD: &str = r"\b[2-6][0-9OoDQIliZzSsGbBgq]{3}(?:[ -]?[0-9OoDQIliZzSsGbBgq]){9,15}\b";
+
+// ISBNs after their label, and ISBN-13 without one
+const ISBN: &str = r"(?i:ISBN)(?:-1[03])?:?[ ]?([0-9OoDQIliZzSsGbBgq][0-9OoDQIliZzSsGbBgq -]{8,15}[0-9OoDQIliZzSsGbBgqXx])";
+const BARE_ISBN: &str = r"\b97[89][- ]?(?:[0-9][- ]?){9}[0-9]\b";
+
+// a country, two check digits and the account, up to where its country's
+// length ends it
+const IBAN: &str = r"\b[A-Z]{2}[0-9OIZSB]{2}[ A-Z0-9]{11,40}";
+
+// length of the IBANs of every country that has them, the country code
+// followed by the length
+const IBAN_LENGTHS: &str = concat!(
+    "AD24 AE23 AL28 AT20 AZ28 BA20 BE16 BG22 BH22 BR29 BY28 CH21 CR22 CY28 CZ24 DE22 DK18 ",
+    "DO28 EE20 EG29 ES24 FI18 FO18 FR27 GB22 GE22 GI23 GL18 GR27 GT28 HR21 HU28 IE22 IL23 ",
+    "IQ23 IS26 IT27 JO30 KW30 KZ20 LB28 LC32 LI21 LT20 LU20 LV21 MC27 MD24 ME22 MK19 MR27 ",
+    "MT31 MU30 NL18 NO15 PK24 PL28 PS29 PT25 QA29 RO24 RS22 SA24 SC31 SE24 SI19 SK24 SM27 ",
+    "ST25 SV28 TL23 TN24 TR26 UA29 VA22 VG24 XK20",
+);
+
+// Where the check digits of one kind of machine readable zone are, in its
+// lines joined into one
+struct MrzFormat {
+    width: usize,
+    lines: usize,
+    // the characters each check digit covers, and where it is
+    checks: &'static [(&'static [(usize, usize)], usize)],
+    // birth and expiry dates, digits only like the check digits
+    dates: &'static [Range<usize>],
+    // the document number, letters and digits
+    number: Range<usize>,
+}
+
+// passports, visas and ID cards, in the order their zones are tried
+const MRZ_FORMATS: &[MrzFormat] = &[
+    MrzFormat {
+        width: 44,
+        lines: 2,
+        checks: &[
+            (&[(44, 53)], 53),
+            (&[(57, 63)], 63),
+            (&[(65, 71)], 71),
+            (&[(72, 86)], 86),
+            (&[(44, 54), (57, 64), (65, 87)], 87),
+        ],
+        dates: &[57..63, 65..71],
+        number: 44..53,
+    },
+    MrzFormat {
+        width: 36,
+        lines: 2,
+        checks: &[
+            (&[(36, 45)], 45),
+            (&[(49, 55)], 55),
+            (&[(57, 63)], 63),
+            (&[(36, 46), (49, 56), (57, 71)], 71),
+        ],
+        dates: &[49..55, 57..63],
+        number: 36..45,
+    },
+    MrzFormat {
+        width: 30,
+        lines: 3,
+        checks: &[
+            (&[(5, 14)], 14),
+            (&[(30, 36)], 36),
+            (&[(38, 44)], 44),
+            (&[(5, 30), (30, 37), (38, 45), (48, 59)], 59),
+        ],
+        dates: &[30..36, 38..44],
+        number: 5..14,
+    },
+];
+
+/// What kind of [`Code`] it is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeKind {
+    Iban,
+    Card,
+    Isbn,
+    /// The machine readable zone of a passport or ID card.
+    Mrz,
+}
+
+impl CodeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            CodeKind::Iban => "IBAN",
+            CodeKind::Card => "Card number",
+            CodeKind::Isbn => "ISBN",
+            CodeKind::Mrz => "MRZ",
+        }
+    }
+}
+
+/// How a [`Code`] fares against its checksum.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Check {
+    Valid,
+    /// It passes once the characters OCR mixes up, such as O and 0, are
+    /// swapped as in this reading.
+    Fixed(String),
+    /// It fails either way, some character was probably misread.
+    Failed,
+}
+
+/// An IBAN, card number, ISBN or passport zone found in a text, with whether
+/// its checksum passes.
+#[derive(Clone, PartialEq)]
+pub struct Code {
+    pub kind: CodeKind,
+    /// As it is written in the text.
+    pub text: String,
+    /// Where it is in the text, in bytes.
+    pub range: Range<usize>,
+    pub check: Check,
+}
+
+// The characters of a code without the spaces and dashes that group them,
+// each with where it is in the text
+struct Found {
+    start: usize,
+    chars: Vec<(usize, char)>,
+}
+
+impl Found {
+    fn new(text: &str, range: Range<usize>, keep: impl Fn(char) -> bool) -> Self {
+        let chars = text[range.clone()]
+            .char_indices()
+            .filter(|(_, c)| keep(*c))
+            .map(|(at, c)| (range.start + at, c))
+            .collect();
+        Found {
+            start: range.start,
+            chars,
+        }
+    }
+
+    fn compact(&self) -> String {
+        self.chars.iter().map(|(_, c)| c).collect()
+    }
+
+    fn end(&self) -> usize {
+        self.chars
+            .last()
+            .map_or(self.start, |(at, c)| at + c.len_utf8())
+    }
+
+    // the code in `text` read as `compact`, grouped as it was
+    fn rewrite(&self, text: &str, compact: &str) -> String {
+        let mut rewritten = String::new();
+        let mut from = self.start;
+        for ((at, c), fixed) in self.chars.iter().zip(compact.chars()) {
+            rewritten.push_str(&text[from..*at]);
+            rewritten.push(fixed);
+            from = at + c.len_utf8();
+        }
+        rewritten
+    }
+
+    fn code(&self, text: &str, kind: CodeKind, check: Check) -> Code {
+        let check = match check {
+            Check::Fixed(compact) => Check::Fixed(self.rewrite(text, &compact)),
+            check => check,
+        };
+        Code {
+            kind,
+            text: text[self.start..self.end()].to_string(),
+            range: self.start..self.end(),
+            check,
+        }
+    }
+}
+
+fn as_digit(c: char) -> char {
+    DIGIT_LOOKALIKES
+        .iter()
+        .find(|(lookalike, _)| *lookalike == c)
+        .map_or(c, |(_, digit)| *digit)
+}
+
+fn lookalike(c: char) -> Option<char> {
+    DIGIT_LOOKALIKES
+        .iter()
+        .chain(LETTER_LOOKALIKES)
+        .find(|(from, _)| *from == c)
+        .map(|(_, to)| *to)
+}
+
+// `compact` as it is, or else the one reading with a single character swapped
+// for its lookalike at one of `positions` that passes `valid`
+fn check(
+    compact: &str,
+    original: &str,
+    positions: Range<usize>,
+    valid: impl Fn(&str) -> bool,
+) -> Check {
+    if valid(compact) {
+        return if compact == original {
+            Check::Valid
+        } else {
+            Check::Fixed(compact.to_string())
+        };
+    }
+    let chars: Vec<char> = compact.chars().collect();
+    let mut passing = positions.filter_map(|at| {
+        let swapped = lookalike(chars[at])?;
+        let mut candidate = chars.clone();
+        candidate[at] = swapped;
+        let candidate: String = candidate.into_iter().collect();
+        valid(&candidate).then_some(candidate)
+    });
+    match (passing.next(), passing.next()) {
+        // more than one would be a guess
+        (Some(fixed), None) => Check::Fixed(fixed),
+        _ => Check::Failed,
+    }
+}
+
+fn luhn(digits: &str) -> bool {
+    let mut sum = 0;
+    for (index, c) in digits.chars().rev().enumerate() {
+        let Some(mut digit) = c.to_digit(10) else {
+            return false;
+        };
+        if index % 2 == 1 {
+            digit *= 2;
+            if digit > 9 {
+                digit -= 9;
+            }
+        }
+        sum += digit;
+    }
+    sum % 10 == 0
+}
+
+fn isbn_valid(isbn: &str) -> bool {
+    let chars: Vec<char> = isbn.chars().collect();
+    match chars.len() {
+        10 => {
+            let mut sum = 0;
+            for (index, c) in chars.iter().enumerate() {
+                let value = match c {
+                    'X' if index == 9 => 10,
+                    c => match c.to_digit(10) {
+                        Some(digit) => digit,
+                        None => return false,
+                    },
+                };
+                sum += value * (10 - index as u32);
+            }
+            sum % 11 == 0
+        }
+        13 => {
+            let mut sum = 0;
+            for (index, c) in chars.iter().enumerate() {
+                let Some(digit) = c.to_digit(10) else {
+                    return false;
+                };
+                sum += if index % 2 == 0 { digit } else { digit * 3 };
+            }
+            sum % 10 == 0
+        }
+        _ => false,
+    }
+}
+
+// how long the IBANs of the country `iban` starts with are
+fn iban_length(iban: &str) -> Option<usize> {
+    let country = iban.get(..2)?;
+    IBAN_LENGTHS
+        .split(' ')
+        .find(|entry| entry.starts_with(country))
+        .and_then(|entry| entry[2..].parse().ok())
+}
+
+fn iban_valid(iban: &str) -> bool {
+    if iban_length(iban) != Some(iban.len())
+        || !iban
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        return false;
+    }
+    // the country and check digits go to the end, letters count from 10
+    let mut remainder = 0;
+    for c in iban[4..].chars().chain(iban[..4].chars()) {
+        let value = c.to_digit(36).expect("checked to be a letter or digit");
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+// the weighted sum of ICAO 9303 that every check digit of a zone is
+fn mrz_digit(chars: impl Iterator<Item = char>) -> u32 {
+    let weights = [7, 3, 1];
+    chars
+        .enumerate()
+        .map(|(index, c)| {
+            let value = match c {
+                '<' => 0,
+                c => c.to_digit(36).unwrap_or(0),
+            };
+            value * weights[index % 3]
+        })
+        .sum::<u32>()
+        % 10
+}
+
+fn mrz_valid(format: &MrzFormat, zone: &str) -> bool {
+    let chars: Vec<char> = zone.chars().collect();
+    format.checks.iter().all(|(ranges, at)| {
+        let covered = ranges
+            .iter()
+            .flat_map(|(start, end)| chars[*start..*end].iter().copied());
+        let expected = match chars[*at] {
+            '<' => Some(0),
+            c => c.to_digit(10),
+        };
+        expected == Some(mrz_digit(covered))
+    })
+}
+
+// passport and ID card zones: lines of capitals, digits and <, in twos of 44
+// or 36 characters or threes of 30
+fn find_mrz(text: &str, add: &mut impl FnMut(Code)) {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split('\n') {
+        lines.push(Found::new(text, offset..offset + line.len(), |c| {
+            !c.is_whitespace()
+        }));
+        offset += line.len() + 1;
+    }
+    let is_zone_line = |found: &Found, width: usize| {
+        let compact = found.compact();
+        compact.len() == width
+            && compact.contains('<')
+            && compact
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '<')
+    };
+    let mut index = 0;
+    'lines: while index < lines.len() {
+        for format in MRZ_FORMATS {
+            let Some(zone) = lines.get(index..index + format.lines) else {
+                continue;
+            };
+            if !zone.iter().all(|line| is_zone_line(line, format.width)) {
+                continue;
+            }
+            let found = Found {
+                start: zone[0].start,
+                chars: zone
+                    .iter()
+                    .flat_map(|line| line.chars.iter().copied())
+                    .collect(),
+            };
+            let original = found.compact();
+            let mut digits: Vec<char> = original.chars().collect();
+            let numeric = format
+                .dates
+                .iter()
+                .flat_map(|range| range.clone())
+                .chain(format.checks.iter().map(|(_, at)| *at));
+            for at in numeric {
+                digits[at] = as_digit(digits[at]);
+            }
+            let compact: String = digits.into_iter().collect();
+            let result = check(&compact, &original, format.number.clone(), |zone| {
+                mrz_valid(format, zone)
+            });
+            add(found.code(text, CodeKind::Mrz, result));
+            index += format.lines;
+            continue 'lines;
+        }
+        index += 1;
+    }
+}
+
+/// The IBANs, card numbers, ISBNs and passport zones in `text` in the order
+/// they appear, each checked against its checksum.
+///
+/// Those that fail it are tried again with the characters OCR confuses, such
+/// as O and 0 or I and 1, swapped where only digits belong, then one at a time
+/// elsewhere. A reading that passes is offered as [`Check::Fixed`] when it is
+/// the only one.
+pub fn find(text: &str) -> Vec<Code> {
+    static IBANS: OnceLock<Regex> = OnceLock::new();
+    static ISBNS: OnceLock<Regex> = OnceLock::new();
+    static BARE_ISBNS: OnceLock<Regex> = OnceLock::new();
+    static CARDS: OnceLock<Regex> = OnceLock::new();
+
+    let mut codes: Vec<Code> = Vec::new();
+    let mut add = |code: Code| {
+        let overlaps = codes
+            .iter()
+            .any(|other| other.range.start < code.range.end && code.range.start < other.range.end);
+        if !overlaps {
+            codes.push(code);
+        }
+    };
+
+    find_mrz(text, &mut add);
+
+    for matched in regex(&IBANS, IBAN).find_iter(text) {
+        let Some(length) = iban_length(matched.as_str()) else {
+            continue;
+        };
+        let mut found = Found::new(text, matched.range(), |c| c != ' ');
+        if found.chars.len() < length {
+            continue;
+        }
+        found.chars.truncate(length);
+        let original = found.compact();
+        // capitals in prose can look like a country and an account, the
+        // account of a real one is mostly digits
+        let account = &original[4..];
+        let digits = account.chars().filter(char::is_ascii_digit).count();
+        if digits * 2 < account.len() {
+            continue;
+        }
+        // the check digits are digits, the account mostly too
+        let mut compact: String = original
+            .chars()
+            .enumerate()
+            .map(|(index, c)| {
+                if (2..4).contains(&index) {
+                    as_digit(c)
+                } else {
+                    c
+                }
+            })
+            .collect();
+        let all_digits: String = compact[..4]
+            .chars()
+            .chain(compact[4..].chars().map(as_digit))
+            .collect();
+        if iban_valid(&all_digits) {
+            compact = all_digits;
+        }
+        let result = check(&compact, &original, 4..length, iban_valid);
+        add(found.code(text, CodeKind::Iban, result));
+    }
+
+    let isbns = regex(&ISBNS, ISBN)
+        .captures_iter(text)
+        .filter_map(|captures| captures.get(1))
+        .chain(regex(&BARE_ISBNS, BARE_ISBN).find_iter(text));
+    for matched in isbns {
+        let found = Found::new(text, matched.range(), |c| c != ' ' && c != '-');
+        let original = found.compact();
+        let last = original.chars().count().saturating_sub(1);
+        let compact: String = original
+            .chars()
+            .enumerate()
+            .map(|(index, c)| match c {
+                'X' | 'x' if index == last => 'X',
+                c => as_digit(c),
+            })
+            .collect();
+        if !matches!(compact.len(), 10 | 13) {
+            continue;
+        }
+        let result = check(&compact, &original, 0..0, isbn_valid);
+        add(found.code(text, CodeKind::Isbn, result));
+    }
+
+    for matched in regex(&CARDS, CARD).find_iter(text) {
+        let mut found = Found::new(text, matched.range(), |c| c != ' ' && c != '-');
+        // a number right after it may have been taken in, a shorter card
+        // number that passes ends before it
+        let mut result = Check::Failed;
+        let mut best = None;
+        while (13..=19).contains(&found.chars.len()) {
+            let original = found.compact();
+            let compact: String = original.chars().map(as_digit).collect();
+            let real = original.chars().filter(char::is_ascii_digit).count();
+            // a word with a few digits in it is no card number
+            if real * 4 < compact.len() * 3 {
+                break;
+            }
+            let attempt = check(&compact, &original, 0..0, luhn);
+            if attempt != Check::Failed {
+                result = attempt;
+                best = Some(found.chars.len());
+                break;
+            }
+            best.get_or_insert(found.chars.len());
+            // back to the end of the previous group
+            let end = found.chars.len();
+            let group = (1..end)
+                .rev()
+                .find(|&index| found.chars[index].0 != found.chars[index - 1].0 + 1);
+            match group {
+                Some(index) => found.chars.truncate(index),
+                None => break,
+            }
+        }
+        if let Some(length) = best {
+            let mut found = Found::new(text, matched.range(), |c| c != ' ' && c != '-');
+            found.chars.truncate(length);
+            add(found.code(text, CodeKind::Card, result));
+        }
+    }
+
+    codes.sort_by_key(|code| code.range.start);
+    codes
+}
+
+/// `text` with every code that [`find`] could fix read the fixed way.
+pub fn correct(text: &str) -> String {
+    let mut corrected = text.to_string();
+    for code in find(text).into_iter().rev() {
+        if let Check::Fixed(fixed) = &code.check {
+            corrected.replace_range(code.range.clone(), fixed);
+        }
+    }
+    corrected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checks(text: &str) -> Vec<(CodeKind, Check)> {
+        find(text)
+            .into_iter()
+            .map(|code| (code.kind, code.check))
+            .collect()
+    }
+
+    #[test]
+    fn valid_codes_pass() {
+        let text = "IBAN DE89 3704 0044 0532 0130 00, card 4111 1111 1111 1111, \
+                    ISBN 978-3-16-148410-0";
+        assert_eq!(
+            checks(text),
+            [
+                (CodeKind::Iban, Check::Valid),
+                (CodeKind::Card, Check::Valid),
+                (CodeKind::Isbn, Check::Valid),
+            ]
+        );
+    }
+
+    #[test]
+    fn lookalikes_are_fixed_in_place() {
+        assert_eq!(
+            correct("Pay to DE89 37O4 0044 0532 0130 00 today"),
+            "Pay to DE89 3704 0044 0532 0130 00 today"
+        );
+        assert_eq!(
+            correct("card 4111 1111 1111 111l"),
+            "card 4111 1111 1111 1111"
+        );
+        assert_eq!(correct("ISBN 0-306-4O615-2"), "ISBN 0-306-40615-2");
+    }
+
+    #[test]
+    fn capitals_in_prose_are_no_iban() {
+        let text = "PLEASE NOTE ATOZ BOOKS AND MORE SHOP IS CLOSED";
+        assert!(find(text).is_empty());
+        assert_eq!(correct(text), text);
+    }
+
+    #[test]
+    fn passport_zone() {
+        let zone = "P<UTOERIKSSON<<ANNA<MARIA<<<<<<<<<<<<<<<<<<<\n\
+                    L898902C36UTO7408122F1204159ZE184226B<<<<<10";
+        assert_eq!(checks(zone), [(CodeKind::Mrz, Check::Valid)]);
+    }
+
+    #[test]
+    fn checksums() {
+        assert!(luhn("79927398713"));
+        assert!(!luhn("79927398710"));
+        assert!(isbn_valid("080442957X"));
+        assert!(iban_valid("GB82WEST12345698765432"));
+        assert!(!iban_valid("GB82WEST12345698765433"));
+    }
+}
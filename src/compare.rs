@@ -0,0 +1,524 @@
+use crate::{
+    cleanup::draw_changes, config::Config, history::History, locale::tr, preprocessing,
+    status::Status,
+};
+use image::DynamicImage;
+use imgui::{Condition, TreeNodeFlags, Ui};
+use std::{
+    error::Error,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+use transcribewizard::{
+    backend::{OcrBackend, BACKENDS},
+    diff::{self, Change},
+    files, ocr,
+    preprocess::Preprocessing,
+};
+
+// One of the two texts being compared and where it came from
+#[derive(Default)]
+struct Side {
+    source: String,
+    text: Option<String>,
+    // the file being read, its text or why it could not be read
+    reading: Option<Receiver<Result<String, String>>>,
+    error: Option<String>,
+}
+
+impl Side {
+    // the picker for one side, true once its text changed
+    fn draw(
+        &mut self,
+        ui: &Ui,
+        name: &str,
+        config: &Config,
+        history: &History,
+        current: &str,
+        load: Loader,
+    ) -> bool {
+        let _id = ui.push_id(name);
+        let mut changed = self.poll();
+        ui.text(format!("{}:", name));
+        ui.same_line();
+        let _disabled = ui.begin_disabled(self.reading.is_some());
+        if ui.button(tr("Open image...")) {
+            let path = crate::projects::open_dialog(config)
+                .add_filter(
+                    "Images and PDFs",
+                    &[files::IMAGE_EXTENSIONS, &["pdf"]].concat(),
+                )
+                .pick_file();
+            if let Some(path) = path {
+                self.open(config, path, load);
+                changed = true;
+            }
+        }
+        ui.same_line();
+        if ui.button(tr("From history")) {
+            ui.open_popup("history");
+        }
+        ui.popup("history", || {
+            if history.is_empty() {
+                ui.text_disabled(tr("No transcriptions yet"));
+            }
+            for (index, entry) in history.entries().enumerate() {
+                let first_line = entry.text.lines().next().unwrap_or_default();
+                let label = format!("{}: {}##{}", index + 1, first_line, index);
+                if ui.menu_item(label) {
+                    self.pick(format!("history entry {}", index + 1), entry.text.clone());
+                    changed = true;
+                }
+            }
+        });
+        ui.same_line();
+        if ui.button(tr("Current text")) {
+            self.pick("current text".into(), current.to_string());
+            changed = true;
+        }
+        ui.same_line();
+        match (&self.text, &self.error) {
+            _ if self.reading.is_some() => {
+                ui.text_disabled(format!("{} {}", tr("Reading..."), self.source))
+            }
+            (Some(_), _) => ui.text_disabled(&self.source),
+            (None, Some(err)) => ui.text_colored([0.9, 0.3, 0.3, 1.0], err),
+            (None, None) => ui.text_disabled(tr("nothing picked")),
+        }
+        changed
+    }
+
+    fn pick(&mut self, source: String, text: String) {
+        self.source = source;
+        self.text = Some(text);
+        self.reading = None;
+        self.error = None;
+    }
+
+    // transcribe every page of the file the way the main window would, on a
+    // worker thread. There is no text until it is done
+    fn open(&mut self, config: &Config, path: PathBuf, load: Loader) {
+        self.source = path.file_name().map_or(path.display().to_string(), |name| {
+            name.to_string_lossy().into_owned()
+        });
+        self.text = None;
+        self.error = None;
+        let config = config.clone();
+        let (sender, job) = mpsc::channel();
+        thread::spawn(move || {
+            let result = load(&config)
+                .and_then(|backend| {
+                    files::transcribe_file(
+                        backend.as_ref(),
+                        &path,
+                        &config.preprocessing,
+                        config.conversion(),
+                    )
+                })
+                .map(|pages| {
+                    let texts: Vec<String> = pages
+                        .iter()
+                        .map(|(_, transcription)| config.render(transcription))
+                        .collect();
+                    files::join_pages(&path, &texts)
+                })
+                .map_err(|err| format!("Error: {}", err));
+            let _ = sender.send(result);
+        });
+        self.reading = Some(job);
+    }
+
+    // take the text of the file once read, true when it came in
+    fn poll(&mut self) -> bool {
+        let Some(reading) = &self.reading else {
+            return false;
+        };
+        match reading.try_recv() {
+            Ok(Ok(text)) => self.text = Some(text),
+            Ok(Err(err)) => self.error = Some(err),
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => {
+                self.error = Some(tr("Error: the worker stopped").into())
+            }
+        }
+        self.reading = None;
+        true
+    }
+}
+
+// how a setup gets its backend, the way the main window loads one but without
+// the cache so both sides are read for real
+pub type Loader = fn(&Config) -> Result<Box<dyn OcrBackend>, Box<dyn Error>>;
+
+// What one setup read from the image
+struct Reading {
+    text: String,
+    // how long the reading took, the backend being loaded left out
+    seconds: f64,
+    confidence: Option<f32>,
+    lines: usize,
+}
+
+enum Run {
+    Idle,
+    Pending,
+    Done(Reading),
+    Failed(String),
+}
+
+// One of the two setups being compared: the settings it starts from, with the
+// backend and preprocessing changed on top of them
+struct Setup {
+    // index into the profiles, None for the settings in use
+    profile: Option<usize>,
+    backend: usize,
+    preprocessing: Preprocessing,
+    run: Run,
+}
+
+impl Setup {
+    fn new(config: &Config) -> Self {
+        let mut setup = Setup {
+            profile: None,
+            backend: 0,
+            preprocessing: Preprocessing::default(),
+            run: Run::Idle,
+        };
+        setup.reset(config);
+        setup
+    }
+
+    // the settings this setup reads with
+    fn config(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        if let Some(profile) = self
+            .profile
+            .and_then(|index| config.profiles.get(index).cloned())
+        {
+            profile.apply(&mut config);
+        }
+        config.backend = BACKENDS[self.backend].into();
+        config.preprocessing = self.preprocessing.clone();
+        config
+    }
+
+    // take the backend and preprocessing of the settings picked
+    fn reset(&mut self, config: &Config) {
+        let mut picked = config.clone();
+        if let Some(profile) = self.profile.and_then(|index| config.profiles.get(index)) {
+            profile.apply(&mut picked);
+        }
+        self.backend = BACKENDS
+            .iter()
+            .position(|name| *name == picked.backend)
+            .unwrap_or(0);
+        self.preprocessing = picked.preprocessing;
+    }
+
+    fn draw(&mut self, ui: &Ui, name: &str, config: &Config, changes: &[(Change, String)]) {
+        let _id = ui.push_id(name);
+        ui.text(name);
+        let current = tr("Settings in use");
+        let label = match self.profile.and_then(|index| config.profiles.get(index)) {
+            Some(profile) => profile.name.as_str(),
+            None => current,
+        };
+        if let Some(_combo) = ui.begin_combo(tr("Settings"), label) {
+            if ui
+                .selectable_config(current)
+                .selected(self.profile.is_none())
+                .build()
+            {
+                self.profile = None;
+                self.reset(config);
+            }
+            for (index, profile) in config.profiles.iter().enumerate() {
+                let _id = ui.push_id_usize(index);
+                let picked = self.profile == Some(index);
+                if ui.selectable_config(&profile.name).selected(picked).build() {
+                    self.profile = Some(index);
+                    self.reset(config);
+                }
+            }
+        }
+        if BACKENDS.len() > 1 {
+            ui.combo_simple_string(tr("Backend"), &mut self.backend, BACKENDS);
+        }
+        if ui.collapsing_header(
+            format!("{}###Preprocessing", tr("Preprocessing")),
+            TreeNodeFlags::empty(),
+        ) {
+            preprocessing::draw(ui, &mut self.preprocessing);
+        }
+        ui.separator();
+        match &self.run {
+            Run::Idle => ui.text_disabled(tr("Not read yet")),
+            Run::Pending => ui.text_disabled(tr("Reading...")),
+            Run::Failed(err) => ui.text_colored([0.9, 0.3, 0.3, 1.0], err),
+            Run::Done(reading) => {
+                let confidence = match reading.confidence {
+                    Some(confidence) => format!("{:.0}%", confidence * 100.0),
+                    None => "-".into(),
+                };
+                ui.text(format!(
+                    "{:.0} ms, {} {}, {} {}",
+                    reading.seconds * 1000.0,
+                    reading.lines,
+                    tr("lines"),
+                    tr("confidence"),
+                    confidence
+                ));
+                ui.child_window("text")
+                    .border(true)
+                    .build(|| draw_changes(ui, changes));
+            }
+        }
+    }
+}
+
+// Window comparing two texts word by word. "Texts" compares two captures,
+// to check that two versions of a document read the same. "Engines" reads one
+// image with two setups of backend and preprocessing and shows both texts side
+// by side, to tune the settings for a kind of document. Words only in A are
+// struck through and words only in B are green
+pub struct ComparePanel {
+    first: Side,
+    second: Side,
+    changes: Vec<(Change, String)>,
+    // the image both setups read and where it came from
+    image: Option<(String, DynamicImage)>,
+    setups: [Setup; 2],
+    job: Option<Receiver<(usize, Result<Reading, String>)>>,
+    // the changes from the text of setup A to that of B, split by the side
+    // they are shown on
+    setup_changes: [Vec<(Change, String)>; 2],
+}
+
+impl ComparePanel {
+    pub fn new(config: &Config) -> Self {
+        ComparePanel {
+            first: Side::default(),
+            second: Side::default(),
+            changes: Vec::new(),
+            image: None,
+            setups: [Setup::new(config), Setup::new(config)],
+            job: None,
+            setup_changes: Default::default(),
+        }
+    }
+
+    // read the image with both setups on a worker thread, one after the other
+    // so neither is slowed down by the other
+    fn start(&mut self, config: &Config, load: Loader) {
+        let Some((_, image)) = &self.image else {
+            return;
+        };
+        let image = image.clone();
+        let configs: Vec<Config> = self.setups.iter().map(|side| side.config(config)).collect();
+        for side in &mut self.setups {
+            side.run = Run::Pending;
+        }
+        self.setup_changes = Default::default();
+        let (sender, job) = mpsc::channel();
+        thread::spawn(move || {
+            for (index, config) in configs.into_iter().enumerate() {
+                let reading = load(&config)
+                    .and_then(|backend| {
+                        let result = ocr::transcribe_preprocessed(
+                            backend.as_ref(),
+                            &image,
+                            &config.preprocessing,
+                        )?;
+                        Ok(Reading {
+                            text: config.render(&result),
+                            seconds: result
+                                .timings
+                                .map_or(0.0, |timings| timings.total.as_secs_f64()),
+                            confidence: result.confidence(),
+                            lines: result.lines.len(),
+                        })
+                    })
+                    .map_err(|err| format!("Error: {}", err));
+                if sender.send((index, reading)).is_err() {
+                    return;
+                }
+            }
+        });
+        self.job = Some(job);
+    }
+
+    fn poll(&mut self) {
+        let Some(job) = &self.job else {
+            return;
+        };
+        loop {
+            match job.try_recv() {
+                Ok((index, reading)) => {
+                    self.setups[index].run = match reading {
+                        Ok(reading) => Run::Done(reading),
+                        Err(err) => Run::Failed(err),
+                    }
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        self.job = None;
+        for side in &mut self.setups {
+            if let Run::Pending = side.run {
+                side.run = Run::Failed(tr("Error: the worker stopped").into());
+            }
+        }
+        match [&self.setups[0].run, &self.setups[1].run] {
+            [Run::Done(first), Run::Done(second)] => {
+                let changes = diff::diff(&first.text, &second.text);
+                for (shown, hidden) in [(0, Change::Added), (1, Change::Removed)] {
+                    self.setup_changes[shown] = changes
+                        .iter()
+                        .filter(|(change, _)| *change != hidden)
+                        .map(|(change, text)| (*change, text.to_string()))
+                        .collect();
+                }
+            }
+            // with one side failed the other has nothing to differ from
+            runs => {
+                for (index, run) in runs.into_iter().enumerate() {
+                    if let Run::Done(reading) = run {
+                        self.setup_changes[index] = vec![(Change::Same, reading.text.clone())];
+                    }
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        open: &mut bool,
+        config: &Config,
+        history: &History,
+        current: &str,
+        status: &mut Status,
+        load: Loader,
+    ) {
+        self.poll();
+        ui.window(format!("{}###Compare", tr("Compare")))
+            .size([800.0, 500.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                let Some(_bar) = ui.tab_bar("modes") else {
+                    return;
+                };
+                if let Some(_tab) = ui.tab_item(format!("{}###Texts", tr("Texts"))) {
+                    self.draw_texts(ui, config, history, current, load);
+                }
+                if let Some(_tab) = ui.tab_item(format!("{}###Engines", tr("Engines"))) {
+                    self.draw_engines(ui, config, history, status, load);
+                }
+            });
+    }
+
+    // two texts picked from files, the history or the editor
+    fn draw_texts(
+        &mut self,
+        ui: &Ui,
+        config: &Config,
+        history: &History,
+        current: &str,
+        load: Loader,
+    ) {
+        let first = self.first.draw(ui, "A", config, history, current, load);
+        let second = self.second.draw(ui, "B", config, history, current, load);
+        if first || second {
+            self.changes = match (&self.first.text, &self.second.text) {
+                (Some(old), Some(new)) => diff::diff(old, new)
+                    .into_iter()
+                    .map(|(change, text)| (change, text.to_string()))
+                    .collect(),
+                _ => Vec::new(),
+            };
+        }
+        let (Some(_), Some(_)) = (&self.first.text, &self.second.text) else {
+            ui.text_disabled(tr("Pick two texts to compare"));
+            return;
+        };
+        let differences = self
+            .changes
+            .iter()
+            // line breaks against spaces are not worth reporting
+            .filter(|(change, text)| *change != Change::Same && !text.trim().is_empty())
+            .count();
+        if differences == 0 {
+            ui.text_colored([0.3, 0.8, 0.3, 1.0], "The words are identical");
+        } else {
+            ui.text(format!(
+                "{} differences, words only in A struck through, only in B in green",
+                differences
+            ));
+        }
+        ui.child_window("differences")
+            .border(true)
+            .build(|| draw_changes(ui, &self.changes));
+    }
+
+    // one image read with two setups
+    fn draw_engines(
+        &mut self,
+        ui: &Ui,
+        config: &Config,
+        history: &History,
+        status: &mut Status,
+        load: Loader,
+    ) {
+        let reading = self.job.is_some();
+        {
+            let _disabled = ui.begin_disabled(reading);
+            {
+                let _disabled = ui.begin_disabled(history.last_image().is_none());
+                if ui.button(tr("Last capture")) {
+                    if let Some(image) = history.last_image() {
+                        self.image = Some((tr("last capture").into(), image.clone()));
+                    }
+                }
+            }
+            ui.same_line();
+            if ui.button(tr("Open image...")) {
+                let path = crate::projects::open_dialog(config)
+                    .add_filter(tr("Images"), files::IMAGE_EXTENSIONS)
+                    .pick_file();
+                if let Some(path) = path {
+                    match files::open_image(&path, config.conversion()) {
+                        Ok((image, _)) => {
+                            let name =
+                                path.file_name().map_or(path.display().to_string(), |name| {
+                                    name.to_string_lossy().into_owned()
+                                });
+                            self.image = Some((name, image));
+                        }
+                        Err(err) => status.error("Error opening image", err),
+                    }
+                }
+            }
+            ui.same_line();
+            match &self.image {
+                Some((source, image)) => {
+                    ui.text_disabled(format!("{} ({}x{})", source, image.width(), image.height()))
+                }
+                None => ui.text_disabled(tr("no image picked")),
+            }
+            ui.same_line();
+            let _disabled = ui.begin_disabled(self.image.is_none());
+            if ui.button(tr("Read with both")) {
+                self.start(config, load);
+            }
+        }
+        ui.columns(2, "sides", true);
+        for (index, (side, name)) in self.setups.iter_mut().zip(["A", "B"]).enumerate() {
+            side.draw(ui, name, config, &self.setup_changes[index]);
+            ui.next_column();
+        }
+        ui.columns(1, "sides", false);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    clipboard::SystemClipboard, config::Config, locale::tr, masking::SecretGuard, status::Status,
+};
+use imgui::{Condition, Ui};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use tracing::error;
+use transcribewizard::ocr::TranscriptionResult;
+
+// One region read into the entry, under its own label
+struct Section {
+    label: String,
+    text: String,
+}
+
+pub enum ComposeAction {
+    // snip another region to add as a section
+    AddRegion,
+    // the sections put together, to go into the editor as one entry
+    Finish(String),
+}
+
+// Window collecting the text of several regions snipped one after the other,
+// such as the title, body and footer of a dialog, each under a label, to be
+// put together into one entry instead of each replacing the text
+#[derive(Default)]
+pub struct ComposePanel {
+    sections: Vec<Section>,
+    // the labels as typed, saved to the config once edited
+    labels: Option<String>,
+    // regions still being read, in the order they were snipped
+    reading: Vec<Receiver<Result<TranscriptionResult, String>>>,
+}
+
+impl ComposePanel {
+    // add what a snipped region read as the next section, labeled with the
+    // next of the configured labels
+    pub fn add(&mut self, config: &Config, text: String) {
+        let index = self.sections.len();
+        let label = match config.compose_labels.get(index) {
+            Some(label) => label.clone(),
+            None => format!("{} {}", tr("Section"), index + 1),
+        };
+        self.sections.push(Section {
+            label,
+            text: text.trim().to_string(),
+        });
+    }
+
+    // add what `job` reads as a section once it is done, after the regions
+    // snipped before it
+    pub fn read(&mut self, job: Receiver<Result<TranscriptionResult, String>>) {
+        self.reading.push(job);
+    }
+
+    // take the regions that were read, stopping at the first still pending
+    fn poll(&mut self, config: &Config, status: &mut Status) {
+        while let Some(job) = self.reading.first() {
+            match job.try_recv() {
+                Ok(Ok(result)) => self.add(config, config.render(&result)),
+                Ok(Err(err)) => status.error("Error extracting text from screen", err),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    status.error("Error extracting text from screen", "worker stopped")
+                }
+            }
+            self.reading.remove(0);
+        }
+    }
+
+    // the sections with their labels as headings, Markdown ones when asked for
+    fn compose(&self, config: &Config) -> String {
+        self.sections
+            .iter()
+            .map(
+                |section| match (section.label.trim(), config.compose_markdown) {
+                    ("", _) => section.text.clone(),
+                    (label, true) => format!("## {}\n\n{}", label, section.text),
+                    (label, false) => format!("{}:\n{}", label, section.text),
+                },
+            )
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        open: &mut bool,
+        config: &mut Config,
+        clipboard: &SystemClipboard,
+        secret_guard: &mut SecretGuard,
+        status: &mut Status,
+    ) -> Option<ComposeAction> {
+        self.poll(config, status);
+        let mut action = None;
+        ui.window(format!("{}###Compose", tr("Compose")))
+            .size([460.0, 420.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                if ui.button(tr("Add region")) {
+                    action = Some(ComposeAction::AddRegion);
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr("Snip a region, its text becomes the next section"));
+                }
+                ui.same_line();
+                let mut changed = ui.checkbox(tr("Markdown headings"), &mut config.compose_markdown);
+                let labels = self
+                    .labels
+                    .get_or_insert_with(|| config.compose_labels.join(", "));
+                ui.input_text(tr("Labels"), labels)
+                    .hint(tr("Title, Body, Footer"))
+                    .build();
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr(
+                        "What the sections are labeled in the order they are added, separated by commas",
+                    ));
+                }
+                if ui.is_item_deactivated_after_edit() {
+                    config.compose_labels = labels
+                        .split(',')
+                        .map(|label| label.trim().to_string())
+                        .filter(|label| !label.is_empty())
+                        .collect();
+                    changed = true;
+                }
+                if changed {
+                    if let Err(err) = config.save() {
+                        error!("Error saving config: {}", err);
+                    }
+                }
+                ui.separator();
+
+                if self.sections.is_empty() && self.reading.is_empty() {
+                    ui.text_disabled(tr("No regions added yet"));
+                }
+                let mut removed = None;
+                let mut moved = None;
+                let count = self.sections.len();
+                for (index, section) in self.sections.iter_mut().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    ui.set_next_item_width(160.0);
+                    ui.input_text("##label", &mut section.label)
+                        .hint(tr("Label"))
+                        .build();
+                    ui.same_line();
+                    if index > 0 && ui.small_button(tr("Up")) {
+                        moved = Some((index, index - 1));
+                    }
+                    ui.same_line();
+                    if index + 1 < count && ui.small_button(tr("Down")) {
+                        moved = Some((index, index + 1));
+                    }
+                    ui.same_line();
+                    if ui.small_button("x") {
+                        removed = Some(index);
+                    }
+                    let lines = section.text.lines().count().clamp(2, 8) as f32;
+                    ui.input_text_multiline(
+                        "##text",
+                        &mut section.text,
+                        [-1.0, ui.text_line_height() * (lines + 1.0)],
+                    )
+                    .build();
+                }
+                if let Some((from, to)) = moved {
+                    self.sections.swap(from, to);
+                }
+                if let Some(index) = removed {
+                    self.sections.remove(index);
+                }
+                if !self.reading.is_empty() {
+                    ui.text_disabled(format!("{} ({})", tr("Reading..."), self.reading.len()));
+                }
+
+                ui.separator();
+                let _disabled = ui.begin_disabled(self.sections.is_empty());
+                if ui.button(tr("Add to text")) {
+                    action = Some(ComposeAction::Finish(self.compose(config)));
+                    self.sections.clear();
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr(
+                        "Put the sections into the editor as one entry, as the output mode says",
+                    ));
+                }
+                ui.same_line();
+                if ui.button(tr("Copy")) {
+                    if let Err(err) = secret_guard.copy(clipboard, &self.compose(config)) {
+                        status.error("Error setting text to clipboard", err);
+                    }
+                }
+                ui.same_line();
+                if ui.button(tr("Clear")) {
+                    self.sections.clear();
+                }
+            });
+        action
+    }
+}
@@ -0,0 +1,1108 @@
+use crate::{keymap::Keymap, locale::UiLanguage, logging::LogLevel, plugins::Plugin, theme::Theme};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+use tracing::warn;
+use transcribewizard::{
+    backend::{CloudCredentials, SharedCloud, BACKENDS},
+    codes,
+    extract::Template,
+    files::{Conversion, ImageLimits, MEGABYTE, SVG_DPI},
+    filter::LineFilter,
+    layout::TextLayout,
+    llm::ChatClient,
+    ocr::{EngineOptions, TranscriptionResult},
+    postprocess::{self, default_transforms, Transform},
+    preprocess::Preprocessing,
+    script,
+    send::SendTarget,
+    source_code::{self, CodeLanguage},
+    template::{self, Fields},
+    translate::{TranslationService, AUTO},
+};
+
+// detection model shared by every language
+pub const DETECTION_MODEL: &str = "text-detection.rten";
+
+// directory searched for models before the working directory
+pub const MODEL_DIR_VAR: &str = "TRANSCRIBE_WIZARD_MODEL_DIR";
+
+// where a model file is: relative paths are looked up in $TRANSCRIBE_WIZARD_MODEL_DIR
+// first and the working directory after, the latter when it is in neither
+pub fn find_model(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    std::env::var_os(MODEL_DIR_VAR)
+        .map(|dir| Path::new(&dir).join(path))
+        .filter(|found| found.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+// A recognition model and the alphabet it was trained on
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Language {
+    pub name: String,
+    pub model: PathBuf,
+    // characters of the model output, the stock Latin alphabet when missing
+    #[serde(default)]
+    pub alphabet: Option<String>,
+}
+
+// What a new transcription does to the text already there
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    Replace,
+    // after the existing text, separated by append_separator
+    Append,
+    // at the cursor of the text editor, or at the end if it never had one
+    Insert,
+}
+
+impl OutputMode {
+    pub const ALL: [OutputMode; 3] = [OutputMode::Replace, OutputMode::Append, OutputMode::Insert];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputMode::Replace => "Replace",
+            OutputMode::Append => "Append",
+            OutputMode::Insert => "Insert at cursor",
+        }
+    }
+}
+
+// What is done with the secrets found in a result before it is copied
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretHandling {
+    // hold the text back and ask whether to mask them
+    Ask,
+    Mask,
+    // copy the text as it was recognized
+    Ignore,
+}
+
+impl SecretHandling {
+    pub const ALL: [SecretHandling; 3] = [
+        SecretHandling::Ask,
+        SecretHandling::Mask,
+        SecretHandling::Ignore,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SecretHandling::Ask => "Ask before copying",
+            SecretHandling::Mask => "Mask them",
+            SecretHandling::Ignore => "Copy as they are",
+        }
+    }
+}
+
+// Which of the clipboards of the system text and images are read from or
+// copied to
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Selection {
+    Clipboard,
+    // whatever was last selected, pasted with the middle mouse button on Linux
+    Primary,
+}
+
+impl Selection {
+    pub const ALL: [Selection; 2] = [Selection::Clipboard, Selection::Primary];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Selection::Clipboard => "Clipboard",
+            Selection::Primary => "Primary selection",
+        }
+    }
+}
+
+// A named set of the settings that differ between kinds of images, picked
+// from the main window to switch all of them at once
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub preprocessing: Preprocessing,
+    // which recognized lines are dropped as noise
+    pub line_filter: LineFilter,
+    pub backend: String,
+    pub language: String,
+    pub handwriting: bool,
+    pub tesseract_language: String,
+    pub output_mode: OutputMode,
+    pub text_layout: TextLayout,
+    // what code read with the code layout is checked as
+    pub code_language: CodeLanguage,
+    pub check_codes: bool,
+    pub formulas: bool,
+    pub auto_copy: bool,
+    // None leaves the transforms as they are, for the built-in profiles and
+    // those saved before profiles had them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transforms: Option<Vec<Transform>>,
+    // a Rhai script the text goes through last, none when empty
+    pub script: PathBuf,
+    // the commands of the plugins it runs, None leaves them as they are
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled_plugins: Option<Vec<String>>,
+    // characters recognition is limited to, all when empty
+    pub allowed_chars: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            name: String::new(),
+            preprocessing: Preprocessing::default(),
+            line_filter: LineFilter::default(),
+            backend: BACKENDS[0].into(),
+            language: "Latin".into(),
+            handwriting: false,
+            tesseract_language: "eng".into(),
+            output_mode: OutputMode::Replace,
+            text_layout: TextLayout::Plain,
+            code_language: CodeLanguage::None,
+            check_codes: false,
+            formulas: false,
+            auto_copy: false,
+            transforms: None,
+            script: PathBuf::new(),
+            enabled_plugins: None,
+            allowed_chars: String::new(),
+        }
+    }
+}
+
+impl Profile {
+    // the settings of `config` as they are now, under `name`
+    pub fn capture(name: &str, config: &Config) -> Self {
+        Profile {
+            name: name.to_string(),
+            preprocessing: config.preprocessing.clone(),
+            line_filter: config.line_filter.clone(),
+            backend: config.backend.clone(),
+            language: config.language.clone(),
+            handwriting: config.handwriting,
+            tesseract_language: config.tesseract_language.clone(),
+            output_mode: config.output_mode,
+            text_layout: config.text_layout,
+            code_language: config.code_language,
+            check_codes: config.check_codes,
+            formulas: config.formulas,
+            auto_copy: config.auto_copy,
+            transforms: Some(config.transforms.clone()),
+            script: config.script.clone(),
+            enabled_plugins: Some(config.enabled_plugins.clone()),
+            allowed_chars: config.engine.allowed_chars.clone(),
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        config.preprocessing = self.preprocessing.clone();
+        config.line_filter = self.line_filter.clone();
+        config.backend = self.backend.clone();
+        config.language = self.language.clone();
+        config.handwriting = self.handwriting;
+        config.tesseract_language = self.tesseract_language.clone();
+        config.output_mode = self.output_mode;
+        config.text_layout = self.text_layout;
+        config.code_language = self.code_language;
+        config.check_codes = self.check_codes;
+        config.formulas = self.formulas;
+        config.auto_copy = self.auto_copy;
+        if let Some(transforms) = &self.transforms {
+            config.transforms = transforms.clone();
+        }
+        config.script = self.script.clone();
+        if let Some(enabled) = &self.enabled_plugins {
+            config.enabled_plugins = enabled.clone();
+        }
+        config.engine.allowed_chars = self.allowed_chars.clone();
+        config.profile = self.name.clone();
+    }
+
+    // whether applying this changes nothing the OCR backend is loaded with
+    pub fn same_engine(&self, config: &Config) -> bool {
+        self.backend == config.backend
+            && self.language == config.language
+            && self.handwriting == config.handwriting
+            && self.tesseract_language == config.tesseract_language
+            && self.allowed_chars == config.engine.allowed_chars
+            && self.line_filter == config.line_filter
+    }
+}
+
+// profiles to start from, for screenshots, photographed receipts, scanned
+// multi-column pages, book pages, codes, source code and manga
+fn default_profiles() -> Vec<Profile> {
+    vec![
+        Profile {
+            name: "Screenshots".into(),
+            // UI text at 100% zoom is a handful of pixels high
+            preprocessing: Preprocessing {
+                auto_upscale: true,
+                ..Preprocessing::default()
+            },
+            ..Profile::default()
+        },
+        Profile {
+            name: "Receipts".into(),
+            preprocessing: Preprocessing {
+                auto_rotate: true,
+                flatten_page: true,
+                grayscale: true,
+                contrast_stretch: true,
+                deskew: true,
+                upscale: true,
+                ..Preprocessing::default()
+            },
+            output_mode: OutputMode::Append,
+            ..Profile::default()
+        },
+        Profile {
+            name: "Documents".into(),
+            preprocessing: Preprocessing {
+                deskew: true,
+                columns: true,
+                ..Preprocessing::default()
+            },
+            text_layout: TextLayout::Markdown,
+            ..Profile::default()
+        },
+        Profile {
+            name: "Book pages".into(),
+            preprocessing: Preprocessing {
+                deskew: true,
+                ..Preprocessing::default()
+            },
+            text_layout: TextLayout::Paragraphs,
+            ..Profile::default()
+        },
+        Profile {
+            name: "Codes".into(),
+            // serial numbers, product keys and the like on labels
+            preprocessing: Preprocessing {
+                auto_upscale: true,
+                ..Preprocessing::default()
+            },
+            allowed_chars: "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-/.<".into(),
+            check_codes: true,
+            ..Profile::default()
+        },
+        Profile {
+            name: "Code".into(),
+            // editors and terminals use small text
+            preprocessing: Preprocessing {
+                auto_upscale: true,
+                ..Preprocessing::default()
+            },
+            // a line of dashes, a lone brace or `;` is code too
+            line_filter: LineFilter {
+                junk_patterns: Vec::new(),
+                ..LineFilter::default()
+            },
+            text_layout: TextLayout::Code,
+            ..Profile::default()
+        },
+        // the stock ocrs models only read Latin script
+        #[cfg(feature = "tesseract")]
+        Profile {
+            name: "Manga".into(),
+            preprocessing: Preprocessing {
+                vertical: true,
+                ..Preprocessing::default()
+            },
+            backend: "tesseract".into(),
+            tesseract_language: "jpn_vert".into(),
+            ..Profile::default()
+        },
+    ]
+}
+
+// placeholders a project's file names may have
+pub const FILE_NAME_PLACEHOLDERS: [&str; 4] = ["project", "date", "time", "source"];
+
+// A client or job of its own, picked from the main window: the folder its
+// files are saved in and how they are named, the profile it reads with and a
+// history kept apart from that of the others
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Project {
+    pub name: String,
+    // where save dialogs open and the journal is kept, empty for the usual places
+    pub output_dir: PathBuf,
+    // applied when the project is opened, empty keeps the settings as they are
+    pub profile: String,
+    // what saved files are called before their extension, with the
+    // placeholders of FILE_NAME_PLACEHOLDERS in braces
+    pub file_name: String,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Project {
+            name: String::new(),
+            output_dir: PathBuf::new(),
+            profile: String::new(),
+            file_name: "{project} {date} {time}".into(),
+        }
+    }
+}
+
+impl Project {
+    // the name of a file saved now, without extension. The source is a file
+    // name or "screen" and characters no file system takes are replaced
+    pub fn file_name(&self, source: Option<&str>) -> String {
+        let now = chrono::Local::now();
+        let name = self
+            .file_name
+            .replace("{project}", &self.name)
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{time}", &now.format("%H-%M-%S").to_string())
+            .replace("{source}", source.unwrap_or_default());
+        match file_safe(&name).trim() {
+            "" => self.name.clone(),
+            name => name.to_string(),
+        }
+    }
+}
+
+// `name` with the characters no file system takes in a file name replaced
+pub fn file_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+// Application settings, stored as TOML in the user's config directory
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // system-wide shortcut that transcribes the clipboard and copies the result back
+    pub hotkey: String,
+    // system-wide shortcut that transcribes the focused window and copies the result
+    pub window_hotkey: String,
+    // system-wide shortcut that starts the live overlay, or stops it
+    pub overlay_hotkey: String,
+    // system-wide shortcuts that transcribe a whole monitor, the first one the
+    // first monitor and so on
+    pub monitor_hotkeys: Vec<String>,
+    // shortcuts of the window's own actions
+    pub keymap: Keymap,
+    // OCR backend in use, one of backend::BACKENDS
+    pub backend: String,
+    // one of backend::CLOUD_BACKENDS next to the local one, empty for none
+    pub cloud_backend: String,
+    pub cloud_credentials: CloudCredentials,
+    // where the captures to come go while the cloud backend is picked in the
+    // main window, for this run only. Shared by the clones of the config, so
+    // the backends made from it all switch at once
+    #[serde(skip)]
+    pub cloud: SharedCloud,
+    // have the cloud backend read what the local one fails on or finds nothing
+    // in, after the fallback chain
+    pub cloud_fallback: bool,
+    // backends, local or cloud, tried in turn after the one in use when it
+    // finds nothing or is less confident than chain_min_confidence
+    pub backend_chain: Vec<String>,
+    // in 0..=1, for the backends that report a confidence
+    pub chain_min_confidence: f32,
+    // have the backend in use and those of the chain all read every image
+    // instead, and vote on the words
+    pub ensemble: bool,
+    // ensemble::VARIANTS the image is read as too, with the ensemble on
+    pub ensemble_variants: Vec<String>,
+    // tesseract language codes, e.g. "eng+deu"
+    pub tesseract_language: String,
+    pub preprocessing: Preprocessing,
+    // which recognized lines are dropped as noise
+    pub line_filter: LineFilter,
+    // keep headings, links and lists of HTML on the clipboard as Markdown
+    pub html_to_markdown: bool,
+    // where captures and text are read from and results are copied to, the
+    // primary selection only exists on Linux
+    pub clipboard_source: Selection,
+    pub clipboard_target: Selection,
+    // memory in MB a clipboard image may take once read, larger ones are
+    // refused rather than filling it
+    pub clipboard_max_megabytes: u32,
+    // clipboard images with more megapixels are scaled down to this, 0 keeps them
+    pub clipboard_max_megapixels: u32,
+    // resolution SVG files and clipboard drawings are drawn at to be read
+    pub svg_dpi: u32,
+    pub output_mode: OutputMode,
+    // plain lines or Markdown inferred from their geometry
+    pub text_layout: TextLayout,
+    // what the text is checked as with the code layout, for the misreads that
+    // break it
+    pub code_language: CodeLanguage,
+    // check the IBANs, card numbers, ISBNs and passport zones in every result
+    // and fix the characters read as their lookalikes
+    pub check_codes: bool,
+    // put every result on the clipboard, the hotkey and tray always do
+    pub auto_copy: bool,
+    pub append_separator: String,
+    // what the sections composed from several regions are labeled, in the
+    // order they are snipped
+    pub compose_labels: Vec<String>,
+    // label them with Markdown headings rather than a line ending in a colon
+    pub compose_markdown: bool,
+    // what copied text and text saved as plain text is wrapped in, its
+    // placeholders such as {text} and {source} are filled in
+    pub output_template: String,
+    // a desktop notification for every result read while the window is hidden
+    pub notify_in_background: bool,
+    // join watch mode results onto the text where they repeat its end, as
+    // screenshots of a page being scrolled do, when results are appended
+    pub merge_overlaps: bool,
+    // skip watch mode images that are the same as the last one read, some
+    // apps put the same image on the clipboard again and again
+    pub skip_repeated_images: bool,
+    // seconds between the reads of the clipboard in watch mode, where the
+    // system does not tell when it changed
+    pub clipboard_poll_interval: f32,
+    // API keys, card numbers and emails in results put on the clipboard
+    pub secret_handling: SecretHandling,
+    // cleanups run over every recognized text, in order
+    pub transforms: Vec<Transform>,
+    // a Rhai script that gets the recognized lines and returns the text, run
+    // after the cleanups, none when empty
+    pub script: PathBuf,
+    // commands every new result is handed to
+    pub plugins: Vec<Plugin>,
+    // commands of the plugins the current profile runs, which unlike their
+    // names are what tells them apart
+    pub enabled_plugins: Vec<String>,
+    // destinations of "Send to...", in the order they are listed
+    pub send_targets: Vec<SendTarget>,
+    // every new result is posted here as JSON, empty for none
+    pub webhook_url: String,
+    // the whole Authorization header, e.g. "Bearer <token>", empty for none
+    pub webhook_authorization: String,
+    // ggml whisper model used for audio files
+    pub whisper_model: PathBuf,
+    // whisper language code, "auto" detects it
+    pub whisper_language: String,
+    // number who speaks in audio transcripts, with a tinydiarize model
+    pub speaker_labels: bool,
+    // seconds between the video frames that get transcribed
+    pub video_interval: f32,
+    // characters a line of saved subtitles holds, longer cues are wrapped at
+    // spaces, 0 for no limit
+    pub subtitle_line_length: usize,
+    // webcam the Camera window streams, by its place among those found
+    pub camera_index: u32,
+    // read only the page found in a camera frame, flattened, not the desk around it
+    pub camera_crop: bool,
+    // underline words missing from the dictionary below the editor
+    pub spell_check: bool,
+    // highlight the words the engine was unsure about, to pick another reading
+    pub proofread: bool,
+    // show the image the text was read from next to it, line by line
+    pub side_by_side: bool,
+    // wrap long lines of the text at the width of the editor
+    pub word_wrap: bool,
+    // number the lines of the text in a gutter left of it
+    pub line_numbers: bool,
+    // show the text in a monospace font, for code and terminal screenshots
+    pub monospace: bool,
+    // hunspell .dic file, its .aff file is expected next to it
+    pub dictionary: PathBuf,
+    // OpenAI-compatible API the text is cleaned up with, e.g. a llama.cpp server
+    pub llm_url: String,
+    pub llm_model: String,
+    // empty for servers that want none
+    pub llm_api_key: String,
+    // instructions the model gets along with the text
+    pub llm_prompt: String,
+    // have a vision model read lines that look like formulas as LaTeX, on the
+    // API of the AI cleanup
+    pub formulas: bool,
+    pub formula_model: String,
+    // read aloud speed relative to the normal one of the speech engine
+    pub speech_rate: f32,
+    // "Process folder..." descends into subfolders
+    pub batch_recursive: bool,
+    // write one transcriptions.txt in the folder instead of a .txt per file
+    pub batch_combined: bool,
+    // also write each file as a PDF with an invisible text layer
+    pub batch_pdf: bool,
+    // folder new files are transcribed from as they appear, empty when none is watched
+    pub watched_folder: PathBuf,
+    // book project open in the Book window, empty when none is
+    pub book_project: PathBuf,
+    // threads ocrs runs its models on, 0 is one per physical core, ocrs only
+    // runs on the CPU and this is read once at startup
+    pub inference_threads: usize,
+    // threads transcribing in batch and watch mode, 0 is one per CPU core
+    pub ocr_threads: usize,
+    // read large clipboard images on the pool, showing their lines as they come
+    pub stream_results: bool,
+    // a corner overlay with where the time of the last transcriptions went
+    pub perf_hud: bool,
+    // transcriptions kept to answer repeated images, 0 turns the cache off
+    pub cache_size: usize,
+    // keep the cache across restarts, in the user's cache directory
+    pub persist_cache: bool,
+    // field extraction templates, applied from "Extract fields..."
+    pub templates: Vec<Template>,
+    pub translation_service: TranslationService,
+    // empty for the default URL of the service
+    pub translation_url: String,
+    pub translation_api_key: String,
+    // language codes of translate::LANGUAGES, the source may be "auto"
+    pub translate_from: String,
+    // with translate_from "auto", send the language detected here instead of
+    // leaving it to the service
+    pub translate_detected: bool,
+    pub translate_to: String,
+    // seconds between the captures of the region under the live overlay
+    pub overlay_interval: f32,
+    // show the translation of the text in the live overlay instead of the text
+    pub overlay_translate: bool,
+    // in 0..=1, how much the live overlay hides what is under it
+    pub overlay_opacity: f32,
+    // clicks go through the live overlay to the game or video under it
+    pub overlay_click_through: bool,
+    // seconds between the captures of a pinned region
+    pub pin_interval: f32,
+    // start the text appended from a pinned region with the time it was read
+    pub pin_timestamps: bool,
+    // seconds between the captures of a caption region being recorded
+    pub caption_interval: f32,
+    // ocrs decoding options
+    pub engine: EngineOptions,
+    // reopen the text and history of the last run, unless started with --clean
+    pub restore_session: bool,
+    // hand the command of a second start to the running instance over its
+    // socket instead of opening another window
+    pub single_instance: bool,
+    // what clients of --serve send as "Authorization: Bearer", required before
+    // it listens on more than the local machine
+    pub serve_token: String,
+    // seconds between saving the tabs and history aside to recover them after
+    // a crash, 0 to never
+    pub autosave_interval: u32,
+    // keep every transcription in a database searchable from the history panel
+    pub archive: bool,
+    // append every recognized text to a Markdown file for each day
+    pub journal: bool,
+    // where the journal files go, empty for a folder in the documents directory
+    pub journal_dir: PathBuf,
+    // how much goes to the log file and Log window
+    pub log_level: LogLevel,
+    pub theme: Theme,
+    // language of the labels, settings and errors
+    pub ui_language: UiLanguage,
+    // give each new result to screen readers, read when the window is created
+    pub screen_reader: bool,
+    // size of the text in pixels before ui_scale
+    pub font_size: f32,
+    // factor the whole interface is scaled by, for high DPI screens
+    pub ui_scale: f32,
+    // TTF or OTF file for the interface and text, empty uses the bundled one
+    pub font: PathBuf,
+    // merged in for the scripts the font lacks such as CJK and Arabic, files
+    // that do not exist are skipped
+    pub fallback_fonts: Vec<PathBuf>,
+    // the monospace font of the editor, the first of these that exists
+    pub monospace_fonts: Vec<PathBuf>,
+    // name of the language text is recognized in
+    pub language: String,
+    pub languages: Vec<Language>,
+    // recognize with the handwriting model instead of the language's own, ocrs only
+    pub handwriting: bool,
+    // recognition model trained on handwritten text
+    pub handwriting_model: Language,
+    // text detection model, shared by every language
+    pub detection_model: PathBuf,
+    // models given with --detection-model and --recognition-model, for this run only
+    #[serde(skip)]
+    pub detection_override: Option<PathBuf>,
+    #[serde(skip)]
+    pub recognition_override: Option<PathBuf>,
+    // where the errors of the script go from every clone of the config, for
+    // the status bar. None without a window, they are logged then
+    #[serde(skip)]
+    pub script_errors: Option<Sender<String>>,
+    pub profiles: Vec<Profile>,
+    // name of the profile applied last, empty before any was
+    pub profile: String,
+    pub projects: Vec<Project>,
+    // name of the project open, empty for none
+    pub project: String,
+    // ask GitHub for a newer release and models at startup, off to never
+    // connect unless asked to from the About window
+    pub check_updates: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            hotkey: "Ctrl+Shift+O".into(),
+            window_hotkey: String::new(),
+            overlay_hotkey: String::new(),
+            monitor_hotkeys: Vec::new(),
+            keymap: Keymap::default(),
+            backend: BACKENDS[0].into(),
+            cloud_backend: String::new(),
+            cloud_credentials: CloudCredentials::default(),
+            cloud: SharedCloud::default(),
+            cloud_fallback: false,
+            backend_chain: Vec::new(),
+            chain_min_confidence: 0.6,
+            ensemble: false,
+            ensemble_variants: Vec::new(),
+            tesseract_language: "eng".into(),
+            preprocessing: Preprocessing::default(),
+            line_filter: LineFilter::default(),
+            html_to_markdown: false,
+            clipboard_source: Selection::Clipboard,
+            clipboard_target: Selection::Clipboard,
+            clipboard_max_megabytes: 256,
+            clipboard_max_megapixels: 36,
+            svg_dpi: SVG_DPI,
+            output_mode: OutputMode::Replace,
+            text_layout: TextLayout::Plain,
+            code_language: CodeLanguage::None,
+            check_codes: false,
+            auto_copy: false,
+            append_separator: "\n\n".into(),
+            compose_labels: vec!["Title".into(), "Body".into(), "Footer".into()],
+            compose_markdown: false,
+            output_template: "{text}".into(),
+            notify_in_background: true,
+            merge_overlaps: false,
+            skip_repeated_images: true,
+            clipboard_poll_interval: 1.0,
+            secret_handling: SecretHandling::Ask,
+            transforms: default_transforms(),
+            script: PathBuf::new(),
+            plugins: Vec::new(),
+            enabled_plugins: Vec::new(),
+            send_targets: Vec::new(),
+            webhook_url: String::new(),
+            webhook_authorization: String::new(),
+            whisper_model: "ggml-base.bin".into(),
+            whisper_language: "auto".into(),
+            speaker_labels: false,
+            video_interval: 1.0,
+            subtitle_line_length: 42,
+            camera_index: 0,
+            camera_crop: true,
+            spell_check: false,
+            proofread: false,
+            side_by_side: false,
+            word_wrap: false,
+            line_numbers: false,
+            monospace: false,
+            dictionary: if cfg!(target_os = "linux") {
+                "/usr/share/hunspell/en_US.dic".into()
+            } else {
+                "en_US.dic".into()
+            },
+            llm_url: "http://localhost:8080/v1".into(),
+            llm_model: "gpt-4o-mini".into(),
+            llm_api_key: String::new(),
+            llm_prompt: "The text below was extracted from an image with OCR. Fix recognition \
+                         mistakes such as misread characters, broken words and stray symbols. \
+                         Keep the wording, language and line breaks otherwise unchanged and \
+                         reply with the corrected text only."
+                .into(),
+            formulas: false,
+            formula_model: "gpt-4o".into(),
+            speech_rate: 1.0,
+            batch_recursive: false,
+            batch_combined: false,
+            batch_pdf: false,
+            watched_folder: PathBuf::new(),
+            book_project: PathBuf::new(),
+            inference_threads: 0,
+            ocr_threads: 0,
+            stream_results: true,
+            perf_hud: false,
+            cache_size: 200,
+            persist_cache: false,
+            templates: vec![Template::invoice()],
+            translation_service: TranslationService::LibreTranslate,
+            translation_url: String::new(),
+            translation_api_key: String::new(),
+            translate_from: AUTO.into(),
+            translate_detected: false,
+            translate_to: "en".into(),
+            overlay_interval: 2.0,
+            overlay_translate: true,
+            overlay_opacity: 0.85,
+            overlay_click_through: true,
+            pin_interval: 5.0,
+            pin_timestamps: true,
+            caption_interval: 1.0,
+            engine: EngineOptions::default(),
+            restore_session: true,
+            single_instance: true,
+            serve_token: String::new(),
+            autosave_interval: 30,
+            screen_reader: false,
+            archive: false,
+            journal: false,
+            journal_dir: PathBuf::new(),
+            log_level: LogLevel::Info,
+            theme: Theme::Dark,
+            ui_language: UiLanguage::System,
+            font_size: 16.0,
+            ui_scale: 1.0,
+            font: PathBuf::new(),
+            fallback_fonts: default_fallback_fonts(),
+            monospace_fonts: default_monospace_fonts(),
+            language: "Latin".into(),
+            languages: vec![Language {
+                name: "Latin".into(),
+                model: "text-recognition.rten".into(),
+                alphabet: None,
+            }],
+            handwriting: false,
+            handwriting_model: Language {
+                name: "Handwriting".into(),
+                model: "handwriting-recognition.rten".into(),
+                alphabet: None,
+            },
+            detection_model: DETECTION_MODEL.into(),
+            detection_override: None,
+            recognition_override: None,
+            script_errors: None,
+            profiles: default_profiles(),
+            profile: String::new(),
+            projects: Vec::new(),
+            project: String::new(),
+            check_updates: true,
+        }
+    }
+}
+
+// where the usual CJK and Arabic fonts of each platform are installed
+fn default_fallback_fonts() -> Vec<PathBuf> {
+    let paths: &[&str] = if cfg!(target_os = "windows") {
+        &[r"C:\Windows\Fonts\msyh.ttc", r"C:\Windows\Fonts\arial.ttf"]
+    } else if cfg!(target_os = "macos") {
+        &[
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+        ]
+    } else {
+        &[
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansArabic-Regular.ttf",
+        ]
+    };
+    paths.iter().map(PathBuf::from).collect()
+}
+
+// where a monospace font comes with each platform or is usually installed
+fn default_monospace_fonts() -> Vec<PathBuf> {
+    let paths: &[&str] = if cfg!(target_os = "windows") {
+        &[
+            r"C:\Windows\Fonts\consola.ttf",
+            r"C:\Windows\Fonts\cour.ttf",
+        ]
+    } else if cfg!(target_os = "macos") {
+        &[
+            "/System/Library/Fonts/Menlo.ttc",
+            "/System/Library/Fonts/Monaco.ttf",
+        ]
+    } else {
+        &[
+            "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+            "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+            "/usr/share/fonts/dejavu-sans-mono-fonts/DejaVuSansMono.ttf",
+            "/usr/share/fonts/truetype/liberation/LiberationMono-Regular.ttf",
+            "/usr/share/fonts/liberation-mono/LiberationMono-Regular.ttf",
+        ]
+    };
+    paths.iter().map(PathBuf::from).collect()
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("transcribewizard").join("config.toml"))
+    }
+
+    // where downloaded models go, a directory of the user's as the working
+    // directory may be anywhere
+    pub fn models_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("transcribewizard").join("models"))
+    }
+
+    #[cfg(feature = "archive")]
+    pub fn archive_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("transcribewizard").join("archive.sqlite3"))
+    }
+
+    // the project open, None when there is none
+    pub fn current_project(&self) -> Option<&Project> {
+        self.projects
+            .iter()
+            .find(|project| project.name == self.project)
+    }
+
+    // the directory of the journal when it is kept, the folder of the project
+    // open when it has one
+    pub fn journal_dir(&self) -> Option<PathBuf> {
+        if !self.journal {
+            return None;
+        }
+        if let Some(project) = self
+            .current_project()
+            .filter(|project| !project.output_dir.as_os_str().is_empty())
+        {
+            return Some(project.output_dir.clone());
+        }
+        if !self.journal_dir.as_os_str().is_empty() {
+            return Some(self.journal_dir.clone());
+        }
+        dirs::document_dir().map(|dir| dir.join("Transcribe Wizard journal"))
+    }
+
+    pub fn cache_path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("transcribewizard").join("results.json"))
+    }
+
+    // the text of a transcription in the configured layout, with the transforms
+    // applied and the codes that fail their checksums fixed when checking them.
+    // Code has its typographic characters and lookalikes turned back into ASCII
+    pub fn render(&self, transcription: &TranscriptionResult) -> String {
+        let text = match self.text_layout {
+            TextLayout::Code => source_code::to_ascii(&TextLayout::Code.render(transcription)),
+            layout => layout.render(transcription),
+        };
+        let text = postprocess::apply(&self.transforms, &text);
+        let text = if self.check_codes {
+            codes::correct(&text)
+        } else {
+            text
+        };
+        if self.script.as_os_str().is_empty() {
+            return text;
+        }
+        // a broken script leaves the text as it was rather than losing it
+        match script::run_file(&self.script, transcription, &text) {
+            Ok(scripted) => scripted,
+            Err(err) => {
+                match &self.script_errors {
+                    Some(errors) => {
+                        let _ = errors.send(err.to_string());
+                    }
+                    None => warn!("Error running script: {}", err),
+                }
+                text
+            }
+        }
+    }
+
+    // how large a clipboard image is read
+    pub fn clipboard_limits(&self) -> ImageLimits {
+        ImageLimits {
+            max_bytes: self.clipboard_max_megabytes as u64 * MEGABYTE,
+            max_pixels: self.clipboard_max_megapixels as u64 * 1_000_000,
+        }
+    }
+
+    // how SVG and HEIF files are read, within the memory a clipboard image may take
+    pub fn conversion(&self) -> Conversion {
+        Conversion {
+            svg_dpi: self.svg_dpi,
+            max_bytes: self.clipboard_max_megabytes as u64 * MEGABYTE,
+        }
+    }
+
+    // `text` as it is copied and saved as plain text, put in the output template
+    pub fn templated(
+        &self,
+        text: &str,
+        source: Option<&str>,
+        transcription: Option<&TranscriptionResult>,
+    ) -> String {
+        template::fill(
+            &self.output_template,
+            &Fields {
+                text,
+                source,
+                confidence: transcription.and_then(TranscriptionResult::confidence),
+            },
+        )
+    }
+
+    // the AI cleanup API with `model`
+    pub fn chat_client(&self, model: &str) -> ChatClient {
+        ChatClient {
+            url: self.llm_url.clone(),
+            model: model.to_string(),
+            api_key: (!self.llm_api_key.is_empty()).then(|| self.llm_api_key.clone()),
+        }
+    }
+
+    // the selected language, or the first one if it went missing
+    pub fn selected_language(&self) -> Option<&Language> {
+        self.languages
+            .iter()
+            .find(|language| language.name == self.language)
+            .or(self.languages.first())
+    }
+
+    // the model text is recognized with, the handwriting one in handwriting mode
+    pub fn recognition_language(&self) -> Option<&Language> {
+        if self.handwriting {
+            Some(&self.handwriting_model)
+        } else {
+            self.selected_language()
+        }
+    }
+
+    // the detection model file to load
+    pub fn detection_model_path(&self) -> PathBuf {
+        find_model(
+            self.detection_override
+                .as_deref()
+                .unwrap_or(&self.detection_model),
+        )
+    }
+
+    // the backends tried in turn after the one in use, those of the fallback
+    // chain and then the cloud backend when it reads what they come up short on
+    pub fn chain(&self) -> Vec<&str> {
+        let mut chain: Vec<&str> = self.backend_chain.iter().map(String::as_str).collect();
+        if self.cloud_fallback
+            && !self.cloud_backend.is_empty()
+            && !chain.contains(&self.cloud_backend.as_str())
+        {
+            chain.push(&self.cloud_backend);
+        }
+        chain
+    }
+
+    // the recognition model file to load, with the alphabet of its language
+    pub fn recognition_model_path(&self) -> Option<(PathBuf, Option<String>)> {
+        let language = self.recognition_language()?;
+        let model = self
+            .recognition_override
+            .as_deref()
+            .unwrap_or(&language.model);
+        Some((find_model(model), language.alphabet.clone()))
+    }
+
+    // the model of the language text is recognized in, for picking another file
+    pub fn recognition_language_mut(&mut self) -> Option<&mut Language> {
+        if self.handwriting {
+            return Some(&mut self.handwriting_model);
+        }
+        let index = self
+            .languages
+            .iter()
+            .position(|language| language.name == self.language)
+            .unwrap_or(0);
+        self.languages.get_mut(index)
+    }
+
+    // load the config file, falling back to defaults when there is none yet
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let Some(path) = Self::path() else {
+            return Ok(Config::default());
+        };
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let mut config: Config = toml::from_str(&contents)
+            .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?;
+        config.enable_plugins_by_command();
+        Ok(config)
+    }
+
+    // plugins used to be enabled by name, an entry still naming one gets its command
+    fn enable_plugins_by_command(&mut self) {
+        let plugins = &self.plugins;
+        let by_command = |enabled: &mut Vec<String>| {
+            for entry in enabled.iter_mut() {
+                if plugins.iter().any(|plugin| plugin.command == *entry) {
+                    continue;
+                }
+                if let Some(plugin) = plugins.iter().find(|plugin| plugin.name == *entry) {
+                    *entry = plugin.command.clone();
+                }
+            }
+        };
+        by_command(&mut self.enabled_plugins);
+        for profile in &mut self.profiles {
+            if let Some(enabled) = &mut profile.enabled_plugins {
+                by_command(enabled);
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::path().ok_or("No config directory available")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // it holds API keys and tokens, so it is readable by this user only,
+        // written aside first so a failed write leaves the previous one
+        let partial = path.with_extension("toml.partial");
+        write_private(&partial, toml::to_string_pretty(self)?.as_bytes())
+            .map_err(|err| format!("Failed to write {}: {}", partial.display(), err))?;
+        fs::rename(&partial, &path)
+            .map_err(|err| format!("Failed to move {}: {}", partial.display(), err).into())
+    }
+}
+
+// `contents` in a file at `path` made anew, on Unix with only the owner
+// allowed to read and write it
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => (),
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_replace_readable_ones_and_only_the_owner_reads_them() {
+        use std::os::unix::fs::PermissionsExt;
+        let path =
+            std::env::temp_dir().join(format!("transcribewizard-config-{}", std::process::id()));
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        write_private(&path, b"new").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(contents, "new");
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{jobs::Background, locale::tr};
+use imgui::Ui;
+use tracing::info;
+use transcribewizard::send::{SendKind, SendTarget};
+
+// Delivers the text to a target on a thread of its own, Notion can take a
+// while, and collects what went wrong
+#[derive(Default)]
+pub struct Delivery {
+    background: Background<(String, String)>,
+}
+
+impl Delivery {
+    pub fn deliver(&self, target: &SendTarget, text: &str) {
+        let target = target.clone();
+        let text = text.to_string();
+        self.background.spawn(move || match target.send(&text) {
+            Ok(destination) => {
+                info!("Sent the text to {}", destination);
+                Ok(())
+            }
+            Err(err) => Err((target.name, err.to_string())),
+        });
+    }
+
+    // the targets that failed since the last call, with why
+    pub fn failures(&self) -> Vec<(String, String)> {
+        self.background.failures()
+    }
+}
+
+// rows of the "Send to" targets, returns whether anything changed
+pub fn edit_targets(ui: &Ui, targets: &mut Vec<SendTarget>) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+    for (index, target) in targets.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(index);
+        ui.text(tr(target.kind.label()));
+        ui.same_line();
+        ui.set_next_item_width(160.0);
+        ui.input_text("##name", &mut target.name)
+            .hint(tr("Name"))
+            .build();
+        changed |= ui.is_item_deactivated_after_edit();
+        ui.same_line();
+        if ui.small_button("x") {
+            removed = Some(index);
+        }
+        ui.indent();
+        match target.kind {
+            SendKind::AppendFile => {
+                ui.input_text(format!("{}##path", tr("File")), &mut target.path)
+                    .hint("~/Vault/Inbox.md")
+                    .build();
+                changed |= ui.is_item_deactivated_after_edit();
+                ui.same_line();
+                if ui.small_button(tr("Browse...")) {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Markdown", &["md"])
+                        .pick_file()
+                    {
+                        target.path = path.display().to_string();
+                        changed = true;
+                    }
+                }
+            }
+            SendKind::PathTemplate => {
+                ui.input_text(format!("{}##path", tr("Path")), &mut target.path)
+                    .hint("~/Notes/{date}/{time}.md")
+                    .build();
+                changed |= ui.is_item_deactivated_after_edit();
+                ui.text_disabled("{date} {time} {year} {month} {day} {hour} {minute} {second}");
+            }
+            SendKind::Notion => {
+                ui.input_text(format!("{}##notion", tr("Token")), &mut target.notion_token)
+                    .password(true)
+                    .build();
+                changed |= ui.is_item_deactivated_after_edit();
+                ui.input_text(
+                    format!("{}##notion", tr("Parent page ID")),
+                    &mut target.notion_parent,
+                )
+                .build();
+                changed |= ui.is_item_deactivated_after_edit();
+                ui.text_disabled(tr("The parent page has to be shared with the integration"));
+            }
+        }
+        ui.unindent();
+    }
+    if let Some(index) = removed {
+        targets.remove(index);
+        changed = true;
+    }
+
+    let mut added = None;
+    if let Some(_combo) = ui.begin_combo("##add", tr("Add target")) {
+        for kind in SendKind::ALL {
+            if ui.selectable(tr(kind.label())) {
+                added = Some(kind);
+            }
+        }
+    }
+    if let Some(kind) = added {
+        targets.push(SendTarget {
+            kind,
+            name: tr(kind.label()).to_string(),
+            ..SendTarget::default()
+        });
+        changed = true;
+    }
+    changed
+}
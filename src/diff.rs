@@ -0,0 +1,110 @@
+/// What happened to a run of text between the old and the new version.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+// above this many token pairs the texts are shown as replaced wholesale
+const MAX_TABLE: usize = 4_000_000;
+
+// split into words and the whitespace between them, which concatenate back to `text`
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut whitespace = None;
+    for (index, character) in text.char_indices() {
+        let is_whitespace = character.is_whitespace();
+        if whitespace.is_some_and(|previous| previous != is_whitespace) {
+            tokens.push(&text[start..index]);
+            start = index;
+        }
+        whitespace = Some(is_whitespace);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Word level difference between two texts, as runs that put together give
+/// `old` when skipping the added ones and `new` when skipping the removed ones.
+pub fn diff<'a>(old: &'a str, new: &'a str) -> Vec<(Change, &'a str)> {
+    let old_tokens = tokens(old);
+    let new_tokens = tokens(new);
+
+    // the common start and end need no table, corrections are mostly small
+    let prefix = old_tokens
+        .iter()
+        .zip(&new_tokens)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old_tokens[prefix..]
+        .iter()
+        .rev()
+        .zip(new_tokens[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_middle = &old_tokens[prefix..old_tokens.len() - suffix];
+    let new_middle = &new_tokens[prefix..new_tokens.len() - suffix];
+
+    let mut changes: Vec<(Change, &str)> = old_tokens[..prefix]
+        .iter()
+        .map(|token| (Change::Same, *token))
+        .collect();
+
+    if (old_middle.len() + 1) * (new_middle.len() + 1) > MAX_TABLE {
+        changes.extend(old_middle.iter().map(|token| (Change::Removed, *token)));
+        changes.extend(new_middle.iter().map(|token| (Change::Added, *token)));
+    } else {
+        // longest common subsequence lengths of every pair of suffixes
+        let width = new_middle.len() + 1;
+        let mut table = vec![0u32; (old_middle.len() + 1) * width];
+        for i in (0..old_middle.len()).rev() {
+            for j in (0..new_middle.len()).rev() {
+                table[i * width + j] = if old_middle[i] == new_middle[j] {
+                    table[(i + 1) * width + j + 1] + 1
+                } else {
+                    table[(i + 1) * width + j].max(table[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_middle.len() || j < new_middle.len() {
+            if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+                changes.push((Change::Same, old_middle[i]));
+                i += 1;
+                j += 1;
+            } else if j == new_middle.len()
+                || (i < old_middle.len() && table[(i + 1) * width + j] >= table[i * width + j + 1])
+            {
+                changes.push((Change::Removed, old_middle[i]));
+                i += 1;
+            } else {
+                changes.push((Change::Added, new_middle[j]));
+                j += 1;
+            }
+        }
+    }
+
+    changes.extend(
+        old_tokens[old_tokens.len() - suffix..]
+            .iter()
+            .map(|token| (Change::Same, *token)),
+    );
+
+    // merge neighbouring tokens of the same kind, they are adjacent in their text
+    let mut runs: Vec<(Change, &str)> = Vec::new();
+    for (change, token) in changes {
+        match runs.last_mut() {
+            Some((last_change, last)) if *last_change == change => {
+                let source = if change == Change::Added { new } else { old };
+                let start = last.as_ptr() as usize - source.as_ptr() as usize;
+                *last = &source[start..start + last.len() + token.len()];
+            }
+            _ => runs.push((change, token)),
+        }
+    }
+    runs
+}
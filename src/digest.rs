@@ -0,0 +1,49 @@
+use image::DynamicImage;
+
+/// A digest of the pixels of an image with its size, the same for the same
+/// image however it was encoded, such as a screenshot set on the clipboard
+/// again. Screenshots a few words apart differ in it, unlike in a perceptual
+/// hash.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ImageDigest {
+    width: u32,
+    height: u32,
+    pixels: blake3::Hash,
+}
+
+impl ImageDigest {
+    pub fn of(image: &DynamicImage) -> Self {
+        ImageDigest {
+            width: image.width(),
+            height: image.height(),
+            pixels: blake3::hash(image.as_bytes()),
+        }
+    }
+
+    /// Whether `other` is the same image, pixel for pixel.
+    pub fn is_same(&self, other: &ImageDigest) -> bool {
+        self == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn a_few_changed_pixels_are_not_the_same() {
+        let page = RgbImage::from_pixel(1920, 1080, Rgb([255, 255, 255]));
+        let mut edited = page.clone();
+        for x in 900..910 {
+            edited.put_pixel(x, 500, Rgb([0, 0, 0]));
+        }
+        let (page, edited) = (
+            DynamicImage::ImageRgb8(page),
+            DynamicImage::ImageRgb8(edited),
+        );
+        let digest = ImageDigest::of(&page);
+        assert!(digest.is_same(&ImageDigest::of(&page)));
+        assert!(!digest.is_same(&ImageDigest::of(&edited)));
+    }
+}
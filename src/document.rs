@@ -0,0 +1,164 @@
+use crate::ocr::TranscriptionResult;
+use image::{imageops::FilterType, DynamicImage, Rgb, RgbImage};
+use imageproc::{
+    contours::{find_contours, BorderType},
+    contrast::{otsu_level, threshold, ThresholdType},
+    filter::gaussian_blur_f32,
+    geometric_transformations::{warp_into, Border, Interpolation, Projection},
+    geometry::{approximate_polygon_dp, arc_length, contour_area, convex_hull},
+};
+
+// pages are looked for in a copy of the image this large, enough to find
+// their edges and quick enough for every frame of a camera
+const DETECT_SIZE: u32 = 512;
+
+// blur hiding the text on the page, only its outline is of interest
+const BLUR_SIGMA: f32 = 3.0;
+
+// share of the image a page covers, smaller regions are more likely a bright
+// patch of the background and larger ones the image being the page already
+const MIN_PAGE_AREA: f64 = 0.2;
+const MAX_PAGE_AREA: f64 = 0.95;
+
+/// The corners of a page clockwise from the top left, in image pixels.
+pub type Quad = [(f32, f32); 4];
+
+/// Find the page in a photo of one, as the largest bright region standing out
+/// from a darker background. None when there is no such region or it already
+/// fills the image.
+pub fn find_page(image: &DynamicImage) -> Option<Quad> {
+    let longest = image.width().max(image.height());
+    if longest == 0 {
+        return None;
+    }
+    let small = if longest > DETECT_SIZE {
+        image.resize(DETECT_SIZE, DETECT_SIZE, FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+    let scale = longest as f32 / small.width().max(small.height()) as f32;
+    let gray = gaussian_blur_f32(&small.to_luma8(), BLUR_SIGMA);
+    let page = threshold(&gray, otsu_level(&gray), ThresholdType::Binary);
+
+    let hull = find_contours::<i32>(&page)
+        .into_iter()
+        .filter(|contour| contour.border_type == BorderType::Outer)
+        .map(|contour| convex_hull(contour.points))
+        .max_by(|a, b| contour_area(a).total_cmp(&contour_area(b)))?;
+    let share = contour_area(&hull) / (page.width() as f64 * page.height() as f64);
+    if !(MIN_PAGE_AREA..=MAX_PAGE_AREA).contains(&share) {
+        return None;
+    }
+
+    // the hull is a page when it simplifies to four corners, a page bent or
+    // partly hidden is taken by its outermost points instead
+    let simplified = approximate_polygon_dp(&hull, 0.02 * arc_length(&hull, true), true);
+    let points: Vec<(f32, f32)> = if simplified.len() == 4 {
+        &simplified
+    } else {
+        &hull
+    }
+    .iter()
+    .map(|point| (point.x as f32 * scale, point.y as f32 * scale))
+    .collect();
+    let corners = if points.len() == 4 {
+        [points[0], points[1], points[2], points[3]]
+    } else {
+        outermost(&points)?
+    };
+    Some(clockwise(corners))
+}
+
+// the points furthest towards each corner of the image
+fn outermost(points: &[(f32, f32)]) -> Option<Quad> {
+    let furthest = |key: fn(&(f32, f32)) -> f32| {
+        points
+            .iter()
+            .copied()
+            .max_by(|a, b| key(a).total_cmp(&key(b)))
+    };
+    Some([
+        furthest(|(x, y)| -x - y)?,
+        furthest(|(x, y)| x - y)?,
+        furthest(|(x, y)| x + y)?,
+        furthest(|(x, y)| y - x)?,
+    ])
+}
+
+// corners in clockwise order around their middle, the top left one first
+fn clockwise(mut corners: Quad) -> Quad {
+    let x = corners.iter().map(|corner| corner.0).sum::<f32>() / 4.0;
+    let y = corners.iter().map(|corner| corner.1).sum::<f32>() / 4.0;
+    // y grows downwards, so a growing angle turns clockwise on screen
+    corners.sort_by(|a, b| {
+        (a.1 - y)
+            .atan2(a.0 - x)
+            .total_cmp(&(b.1 - y).atan2(b.0 - x))
+    });
+    let first = (0..4)
+        .min_by(|&a, &b| (corners[a].0 + corners[a].1).total_cmp(&(corners[b].0 + corners[b].1)))
+        .unwrap_or(0);
+    corners.rotate_left(first);
+    corners
+}
+
+fn distance((ax, ay): (f32, f32), (bx, by): (f32, f32)) -> f32 {
+    (ax - bx).hypot(ay - by)
+}
+
+// the corners of the flattened page, as large as its longer opposite edges
+fn flat_corners(corners: Quad) -> Option<Quad> {
+    let [top_left, top_right, bottom_right, bottom_left] = corners;
+    let width = distance(top_left, top_right).max(distance(bottom_left, bottom_right));
+    let height = distance(top_left, bottom_left).max(distance(top_right, bottom_right));
+    (width >= 1.0 && height >= 1.0).then_some([
+        (0.0, 0.0),
+        (width, 0.0),
+        (width, height),
+        (0.0, height),
+    ])
+}
+
+/// The page with `corners` turned into a flat rectangle, as if it had been
+/// scanned instead of photographed at an angle. It is as large as its longer
+/// opposite edges, so no detail is lost on the side nearer to the camera.
+/// None when the corners do not make a page.
+pub fn flatten(image: &DynamicImage, corners: Quad) -> Option<DynamicImage> {
+    let target = flat_corners(corners)?;
+    let projection = Projection::from_control_points(corners, target)?;
+    let (width, height) = target[2];
+    let mut flat = RgbImage::new(width.round() as u32, height.round() as u32);
+    warp_into(
+        &image.to_rgb8(),
+        projection,
+        Interpolation::Bilinear,
+        Border::Constant(Rgb([255, 255, 255])),
+        &mut flat,
+    );
+    Some(DynamicImage::ImageRgb8(flat))
+}
+
+/// Map the boxes of `result`, read from the page with `corners` flattened with
+/// [`flatten`], back onto the photo of `width` by `height` it was found in.
+pub fn unflatten(result: &mut TranscriptionResult, corners: Quad, width: u32, height: u32) {
+    let Some(target) = flat_corners(corners) else {
+        return;
+    };
+    let Some(projection) = Projection::from_control_points(target, corners) else {
+        return;
+    };
+    // the flattened image was rounded to whole pixels
+    let (x, y) = (
+        target[2].0 / result.width as f32,
+        target[2].1 / result.height as f32,
+    );
+    result.map_points(width, height, |px, py| projection * (px * x, py * y));
+}
+
+/// The page found in a photo of one, flattened with [`flatten`], the whole
+/// image when no page is found.
+pub fn flatten_page(image: &DynamicImage) -> DynamicImage {
+    find_page(image)
+        .and_then(|corners| flatten(image, corners))
+        .unwrap_or_else(|| image.clone())
+}
@@ -0,0 +1,506 @@
+use crate::{
+    backend::OcrBackend,
+    ocr::{average_confidence, Bounds, Line, Progress, TranscriptionResult, Word},
+    preprocess::Preprocessing,
+};
+use image::DynamicImage;
+use std::{error::Error, time::Instant};
+use tracing::{info, warn};
+
+/// Names of the preprocessing variants an [`Ensemble`] can read the image
+/// with besides the image itself.
+pub const VARIANTS: &[&str] = &["binarized", "contrast", "upscaled"];
+
+// the weight of a vote from an engine that reports no confidence
+const UNRATED: f32 = 0.5;
+
+/// The preprocessing of the variant `name`, one of [`VARIANTS`].
+pub fn variant(name: &str) -> Option<Preprocessing> {
+    let mut preprocessing = Preprocessing::default();
+    match name {
+        "binarized" => preprocessing.binarize = true,
+        "contrast" => preprocessing.contrast_stretch = true,
+        "upscaled" => preprocessing.upscale = true,
+        _ => return None,
+    }
+    Some(preprocessing)
+}
+
+/// Backends that all read the image, each as it is and as every variant
+/// makes it, their readings merged word by word by [`vote`]. Slower than any
+/// one of them, and better than each on images they misread differently.
+/// Cloud backends, where every read is paid for, only read the image as it
+/// is, see [`reads`].
+pub struct Ensemble {
+    backends: Vec<Box<dyn OcrBackend>>,
+    variants: Vec<Preprocessing>,
+}
+
+impl Ensemble {
+    /// `backends` must not be empty, `variants` are names from [`VARIANTS`],
+    /// others are left out.
+    pub fn new(backends: Vec<Box<dyn OcrBackend>>, variants: &[String]) -> Self {
+        assert!(!backends.is_empty(), "an ensemble needs a backend");
+        Ensemble {
+            backends,
+            variants: variants.iter().filter_map(|name| variant(name)).collect(),
+        }
+    }
+}
+
+/// How many times an [`Ensemble`] of `local` and `paid` backends reads every
+/// image with `variants`, the paid ones reading it once each.
+pub fn reads(local: usize, paid: usize, variants: usize) -> usize {
+    local * (1 + variants) + paid
+}
+
+// whether every read of `backend` is paid for
+fn paid(backend: &dyn OcrBackend) -> bool {
+    backend.local().is_none()
+}
+
+impl OcrBackend for Ensemble {
+    // the first one, whose settings the rest join in with
+    fn name(&self) -> &'static str {
+        self.backends[0].name()
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        self.transcribe_streaming(image, &mut |_| ())
+    }
+
+    /// The progress of the first backend reading the image as it is, the
+    /// vote comes in after every reading.
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        let start = Instant::now();
+        let mut readings = Vec::new();
+        let mut names = Vec::new();
+        let mut failure = None;
+        for (index, backend) in self.backends.iter().enumerate() {
+            let result = match index {
+                0 => backend.transcribe_streaming(image, progress),
+                _ => backend.transcribe(image),
+            };
+            let variants = match paid(backend.as_ref()) {
+                true => &[][..],
+                false => &self.variants[..],
+            };
+            let variants = variants.iter().map(|preprocessing| {
+                let mut result = backend.transcribe(&preprocessing.apply(image))?;
+                result.rescale(image.width(), image.height());
+                Ok(result)
+            });
+            for result in std::iter::once(result).chain(variants) {
+                match result {
+                    Ok(result) => readings.push(result),
+                    Err(err) => {
+                        warn!(
+                            "{} failed, leaving it out of the vote: {}",
+                            backend.name(),
+                            err
+                        );
+                        failure = Some(err);
+                    }
+                }
+            }
+            if !names.contains(&backend.name()) {
+                names.push(backend.name());
+            }
+        }
+        if readings.is_empty() {
+            return Err(failure.unwrap_or_else(|| "No OCR backend".into()));
+        }
+        let mut result = vote(&readings);
+        result.backend = Some(names.join("+"));
+        info!(
+            "{} readings voted into {} lines in {} ms",
+            readings.len(),
+            result.lines.len(),
+            start.elapsed().as_millis()
+        );
+        Ok(result)
+    }
+
+    fn reads_vertical(&self) -> bool {
+        self.backends.iter().all(|backend| backend.reads_vertical())
+    }
+
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        self.backends.iter().find_map(|backend| backend.local())
+    }
+}
+
+// how far the spans `a` and `b` overlap, negative when they are apart
+fn overlap(a: (i32, i32), b: (i32, i32)) -> i32 {
+    a.1.min(b.1) - a.0.max(b.0)
+}
+
+// whether `a` and `b` are boxes of the same line, side by side and mostly at
+// the same height
+fn same_line(a: Bounds, b: Bounds) -> bool {
+    let height = (a.bottom - a.top).min(b.bottom - b.top);
+    overlap((a.top, a.bottom), (b.top, b.bottom)) * 2 > height
+        && overlap((a.left, a.right), (b.left, b.right)) > 0
+}
+
+fn union(a: Bounds, b: Bounds) -> Bounds {
+    Bounds {
+        left: a.left.min(b.left),
+        top: a.top.min(b.top),
+        right: a.right.max(b.right),
+        bottom: a.bottom.max(b.bottom),
+    }
+}
+
+// the words of `line`, the whole line as one for engines that only read lines
+fn words(line: &Line) -> Vec<Word> {
+    if !line.words.is_empty() {
+        return line.words.clone();
+    }
+    vec![Word {
+        text: line.text.clone(),
+        bounds: line.bounds,
+        rotated_bounds: line.rotated_bounds,
+        confidence: line.confidence,
+        alternatives: Vec::new(),
+    }]
+}
+
+// one line as read by the readings that found it
+struct Group<'a> {
+    bounds: Bounds,
+    lines: Vec<(usize, &'a Line)>,
+}
+
+/// Merge several readings of the same image, all mapped onto it, into one.
+///
+/// Lines are matched up by where they are and kept when the readings that
+/// found one outweigh those that did not. Within a line the words at the same
+/// place are voted on, every reading voting for what it read there weighted
+/// by its confidence, and the reading with the most weight behind it wins,
+/// the others becoming its alternatives. A reading that found nothing there
+/// votes against the word. Words one engine read as one and another as two
+/// are voted on together. Lines are in the reading order of the first reading,
+/// with those only others found where they put them.
+pub fn vote(readings: &[TranscriptionResult]) -> TranscriptionResult {
+    let weights: Vec<f32> = readings
+        .iter()
+        .map(|reading| reading.confidence().unwrap_or(UNRATED))
+        .collect();
+    let mut groups: Vec<Group> = Vec::new();
+    for (reading, result) in readings.iter().enumerate() {
+        // lines of this reading no other found go after its line before
+        let mut after = 0;
+        for line in &result.lines {
+            let found = groups.iter().position(|group| {
+                group.lines.iter().all(|(other, _)| *other != reading)
+                    && same_line(group.bounds, line.bounds)
+            });
+            match found {
+                Some(index) => {
+                    let group = &mut groups[index];
+                    group.bounds = union(group.bounds, line.bounds);
+                    group.lines.push((reading, line));
+                    after = index + 1;
+                }
+                None => {
+                    let group = Group {
+                        bounds: line.bounds,
+                        lines: vec![(reading, line)],
+                    };
+                    groups.insert(after, group);
+                    after += 1;
+                }
+            }
+        }
+    }
+    let line_weight = |line: &Line| {
+        line.confidence
+            .or_else(|| line.word_confidence())
+            .unwrap_or(UNRATED)
+    };
+    let mut lines = Vec::new();
+    for group in groups {
+        let found: f32 = group.lines.iter().map(|(_, line)| line_weight(line)).sum();
+        let missed: f32 = (0..readings.len())
+            .filter(|reading| group.lines.iter().all(|(other, _)| other != reading))
+            .map(|reading| weights[reading])
+            .sum();
+        if found < missed {
+            continue;
+        }
+        if let Some(line) = vote_line(&group) {
+            lines.push(line);
+        }
+    }
+    TranscriptionResult {
+        width: readings[0].width,
+        height: readings[0].height,
+        lines,
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    }
+}
+
+// the words voted in for the line of `group`, None when none of them are
+fn vote_line(group: &Group) -> Option<Line> {
+    let mut placed: Vec<(usize, Word)> = group
+        .lines
+        .iter()
+        .flat_map(|(reading, line)| words(line).into_iter().map(|word| (*reading, word)))
+        .collect();
+    placed.sort_by_key(|(_, word)| word.bounds.left);
+    // runs of words overlapping from left to right, what the readings have at
+    // one place in the line
+    let mut places: Vec<Vec<(usize, Word)>> = Vec::new();
+    let mut right = i32::MIN;
+    for (reading, word) in placed {
+        match places.last_mut() {
+            Some(place) if word.bounds.left < right => place.push((reading, word)),
+            _ => places.push(vec![(reading, word)]),
+        }
+        right = right.max(words_right(places.last()));
+    }
+    let mut words = Vec::new();
+    for place in places {
+        words.extend(vote_place(group, place));
+    }
+    if words.is_empty() {
+        return None;
+    }
+    let bounds = words
+        .iter()
+        .map(|word| word.bounds)
+        .reduce(union)
+        .unwrap_or(group.bounds);
+    // the boxes of the surest reading of the line, the words are not turned
+    let (_, surest) = group.lines.iter().max_by(|(_, a), (_, b)| {
+        let weight = |line: &Line| line.confidence.unwrap_or(UNRATED);
+        weight(a).total_cmp(&weight(b))
+    })?;
+    Some(Line {
+        text: words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        bounds,
+        rotated_bounds: surest.rotated_bounds,
+        confidence: average_confidence(words.iter().map(|word| word.confidence)),
+        words,
+    })
+}
+
+fn words_right(place: Option<&Vec<(usize, Word)>>) -> i32 {
+    place
+        .into_iter()
+        .flatten()
+        .map(|(_, word)| word.bounds.right)
+        .max()
+        .unwrap_or(i32::MIN)
+}
+
+// the words winning the vote at one place in the line, none when the readings
+// that found nothing there outweigh the rest
+fn vote_place(group: &Group, mut place: Vec<(usize, Word)>) -> Vec<Word> {
+    // what each reading of the line read there, with its weight
+    let mut candidates: Vec<(usize, String, f32)> = Vec::new();
+    for (reading, line) in &group.lines {
+        let words: Vec<&Word> = place
+            .iter()
+            .filter(|(other, _)| other == reading)
+            .map(|(_, word)| word)
+            .collect();
+        let text = words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let weight = match words.is_empty() {
+            true => line.confidence.unwrap_or(UNRATED),
+            false => {
+                average_confidence(words.iter().map(|word| word.confidence)).unwrap_or(UNRATED)
+            }
+        };
+        candidates.push((*reading, text, weight));
+    }
+    let mut tally: Vec<(String, f32)> = Vec::new();
+    for (_, text, weight) in &candidates {
+        match tally.iter_mut().find(|(voted, _)| voted == text) {
+            Some((_, total)) => *total += weight,
+            None => tally.push((text.clone(), *weight)),
+        }
+    }
+    // ties go to the reading first in line
+    let Some((winner, _)) = tally
+        .iter()
+        .enumerate()
+        .max_by(|(a_index, (_, a)), (b_index, (_, b))| a.total_cmp(b).then(b_index.cmp(a_index)))
+        .map(|(_, winner)| winner.clone())
+    else {
+        return Vec::new();
+    };
+    if winner.is_empty() {
+        return Vec::new();
+    }
+    let Some((reading, _, _)) = candidates
+        .iter()
+        .filter(|(_, text, _)| *text == winner)
+        .max_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+    else {
+        return Vec::new();
+    };
+    let reading = *reading;
+    place.retain(|(other, _)| *other == reading);
+    let mut words: Vec<Word> = place.into_iter().map(|(_, word)| word).collect();
+    if let [word] = words.as_mut_slice() {
+        tally.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        for (text, _) in tally {
+            if !text.is_empty()
+                && text != word.text
+                && !text.contains(' ')
+                && !word.alternatives.contains(&text)
+            {
+                word.alternatives.push(text);
+            }
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::fixtures;
+    use std::{cell::Cell, rc::Rc};
+
+    fn word(text: &str, left: i32, right: i32, confidence: f32) -> Word {
+        Word {
+            confidence: Some(confidence),
+            ..fixtures::word(text, fixtures::bounds(left, 0, right, 20))
+        }
+    }
+
+    // a reading of one line at `top` for every list of words
+    fn reading(lines: Vec<(i32, Vec<Word>)>) -> TranscriptionResult {
+        let lines = lines
+            .into_iter()
+            .map(|(top, mut words)| {
+                for word in &mut words {
+                    word.bounds.top += top;
+                    word.bounds.bottom += top;
+                    word.rotated_bounds = word.bounds.into();
+                }
+                fixtures::line_of(words)
+            })
+            .collect();
+        fixtures::transcription(200, 200, lines)
+    }
+
+    fn hello(second: &str, confidence: f32) -> Vec<Word> {
+        vec![
+            word("hello", 0, 50, confidence),
+            word(second, 60, 110, confidence),
+        ]
+    }
+
+    fn texts(result: &TranscriptionResult) -> Vec<&str> {
+        result.lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn majority_wins_and_the_rest_become_alternatives() {
+        let result = vote(&[
+            reading(vec![(0, hello("wor1d", 0.8))]),
+            reading(vec![(0, hello("world", 0.8))]),
+            reading(vec![(0, hello("world", 0.8))]),
+        ]);
+        assert_eq!(texts(&result), ["hello world"]);
+        assert_eq!(result.lines[0].words[1].alternatives, ["wor1d"]);
+    }
+
+    #[test]
+    fn confidence_outweighs_numbers() {
+        let result = vote(&[
+            reading(vec![(0, hello("world", 0.9))]),
+            reading(vec![(0, hello("wor1d", 0.3))]),
+            reading(vec![(0, hello("wor1d", 0.3))]),
+        ]);
+        assert_eq!(texts(&result), ["hello world"]);
+    }
+
+    #[test]
+    fn lines_only_one_reading_found_are_dropped() {
+        let result = vote(&[
+            reading(vec![(0, hello("world", 0.8)), (100, hello("noise", 0.8))]),
+            reading(vec![(0, hello("world", 0.8))]),
+            reading(vec![(0, hello("world", 0.8))]),
+        ]);
+        assert_eq!(texts(&result), ["hello world"]);
+    }
+
+    #[test]
+    fn words_read_as_one_are_voted_on_together() {
+        let split = || vec![word("New", 0, 40, 0.8), word("York", 50, 100, 0.8)];
+        let result = vote(&[
+            reading(vec![(0, split())]),
+            reading(vec![(0, vec![word("NewYork", 0, 100, 0.8)])]),
+            reading(vec![(0, split())]),
+        ]);
+        assert_eq!(texts(&result), ["New York"]);
+    }
+
+    // counts its reads, local unless it stands in for a cloud backend
+    struct Counting {
+        reads: Rc<Cell<usize>>,
+        local: bool,
+    }
+
+    impl OcrBackend for Counting {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+            self.reads.set(self.reads.get() + 1);
+            Ok(fixtures::transcription(
+                image.width(),
+                image.height(),
+                Vec::new(),
+            ))
+        }
+
+        fn local(&self) -> Option<&dyn OcrBackend> {
+            self.local.then_some(self as &dyn OcrBackend)
+        }
+    }
+
+    #[test]
+    fn paid_backends_skip_the_variants() {
+        let (local, paid) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+        let ensemble = Ensemble::new(
+            vec![
+                Box::new(Counting {
+                    reads: local.clone(),
+                    local: true,
+                }),
+                Box::new(Counting {
+                    reads: paid.clone(),
+                    local: false,
+                }),
+            ],
+            &["binarized".into(), "contrast".into()],
+        );
+        ensemble
+            .transcribe(&DynamicImage::new_rgb8(20, 20))
+            .unwrap();
+        assert_eq!((local.get(), paid.get()), (3, 1));
+        assert_eq!(reads(1, 1, 2), 4);
+    }
+}
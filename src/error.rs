@@ -0,0 +1,59 @@
+use crate::files::{self, MEGABYTE};
+use std::{error::Error, fmt, path::PathBuf};
+
+/// Why text could not be gotten out of the clipboard, a file or an image.
+#[derive(Debug)]
+pub enum TranscribeError {
+    /// A model file does not exist.
+    ModelMissing(PathBuf),
+    /// A model file exists but it or the engine made from it failed to load.
+    Model(String),
+    /// The clipboard holds nothing text can be gotten from.
+    ClipboardEmpty,
+    /// Reading or writing the clipboard failed, with what was being done.
+    Clipboard(String),
+    /// A file or clipboard image in a format that cannot be read.
+    UnsupportedFormat(String),
+    /// An image whose pixels would take more memory than allowed to read.
+    ImageTooLarge {
+        width: u32,
+        height: u32,
+        /// The most memory allowed, in bytes.
+        limit: u64,
+    },
+    /// Recognizing the text of an image failed.
+    Ocr(String),
+}
+
+impl fmt::Display for TranscribeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranscribeError::ModelMissing(path) => {
+                write!(f, "Model file {} not found", path.display())
+            }
+            TranscribeError::Model(message) => write!(f, "{}", message),
+            TranscribeError::ClipboardEmpty => {
+                write!(f, "The clipboard holds no text, image, file, HTML or RTF")
+            }
+            TranscribeError::Clipboard(message) => write!(f, "{}", message),
+            TranscribeError::UnsupportedFormat(what) => write!(f, "Unsupported format: {}", what),
+            TranscribeError::ImageTooLarge {
+                width,
+                height,
+                limit,
+            } => write!(
+                f,
+                "The image is {}x{} pixels and needs {} MB to read, more than the {} MB allowed",
+                width,
+                height,
+                files::rgba_size(*width, *height).div_ceil(MEGABYTE),
+                limit / MEGABYTE
+            ),
+            TranscribeError::Ocr(message) => {
+                write!(f, "Failed to extract text from image: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for TranscribeError {}
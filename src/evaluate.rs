@@ -0,0 +1,269 @@
+use crate::{
+    backend::OcrBackend,
+    files::{self, Conversion},
+    ocr,
+    preprocess::Preprocessing,
+    TranscriptionResult,
+};
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// How one image of a [`run`] went.
+pub struct Sample {
+    pub path: PathBuf,
+    /// Time the pipeline took, preprocessing included.
+    pub latency: Duration,
+    /// Character edits needed to turn the text into the ground truth.
+    pub char_errors: usize,
+    /// Characters of the ground truth.
+    pub chars: usize,
+    pub word_errors: usize,
+    pub words: usize,
+}
+
+impl Sample {
+    /// Character error rate, edits per character of the ground truth.
+    pub fn cer(&self) -> f64 {
+        rate(self.char_errors, self.chars)
+    }
+
+    /// Word error rate, edits per word of the ground truth.
+    pub fn wer(&self) -> f64 {
+        rate(self.word_errors, self.words)
+    }
+}
+
+/// Everything a [`run`] measured.
+#[derive(Default)]
+pub struct Report {
+    pub samples: Vec<Sample>,
+    /// Images without ground truth or that could not be read, with why.
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl Report {
+    pub fn total_latency(&self) -> Duration {
+        self.samples.iter().map(|sample| sample.latency).sum()
+    }
+
+    pub fn mean_latency(&self) -> Duration {
+        self.total_latency() / (self.samples.len() as u32).max(1)
+    }
+
+    pub fn median_latency(&self) -> Duration {
+        let mut latencies: Vec<Duration> =
+            self.samples.iter().map(|sample| sample.latency).collect();
+        latencies.sort();
+        latencies
+            .get(latencies.len() / 2)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Character error rate over all images, so long texts weigh more.
+    pub fn cer(&self) -> f64 {
+        let errors = self.samples.iter().map(|sample| sample.char_errors).sum();
+        rate(errors, self.samples.iter().map(|sample| sample.chars).sum())
+    }
+
+    /// Word error rate over all images.
+    pub fn wer(&self) -> f64 {
+        let errors = self.samples.iter().map(|sample| sample.word_errors).sum();
+        rate(errors, self.samples.iter().map(|sample| sample.words).sum())
+    }
+}
+
+fn rate(errors: usize, total: usize) -> f64 {
+    match total {
+        0 if errors == 0 => 0.0,
+        0 => 1.0,
+        total => errors as f64 / total as f64,
+    }
+}
+
+/// Levenshtein distance, the fewest insertions, deletions and substitutions
+/// turning `a` into `b`.
+pub fn edit_distance<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_item) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_item) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_item != b_item);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// The ground truth of an image, `name.gt.txt` or else `name.txt` next to it.
+pub fn ground_truth_path(image: &Path) -> Option<PathBuf> {
+    ["gt.txt", "txt"]
+        .iter()
+        .map(|extension| image.with_extension(extension))
+        .find(|path| path.is_file())
+}
+
+/// Compare `text` with the ground truth, line breaks and runs of spaces count
+/// as one space.
+pub fn score(path: PathBuf, latency: Duration, text: &str, truth: &str) -> Sample {
+    let normalize = |text: &str| text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let (text, truth) = (normalize(text), normalize(truth));
+    let text_chars: Vec<char> = text.chars().collect();
+    let truth_chars: Vec<char> = truth.chars().collect();
+    let text_words: Vec<&str> = text.split(' ').filter(|word| !word.is_empty()).collect();
+    let truth_words: Vec<&str> = truth.split(' ').filter(|word| !word.is_empty()).collect();
+    Sample {
+        path,
+        latency,
+        char_errors: edit_distance(&text_chars, &truth_chars),
+        chars: truth_chars.len(),
+        word_errors: edit_distance(&text_words, &truth_words),
+        words: truth_words.len(),
+    }
+}
+
+/// Run the pipeline over every image in `dir` and its subdirectories that has
+/// a ground truth text file, `render` turning each result into the text that
+/// is compared, the way the app lays it out.
+pub fn run(
+    backend: &dyn OcrBackend,
+    dir: &Path,
+    preprocessing: &Preprocessing,
+    conversion: Conversion,
+    render: impl Fn(&TranscriptionResult) -> String,
+) -> Result<Report, Box<dyn Error>> {
+    let mut report = Report::default();
+    for path in files::scan_dir(dir, true)? {
+        if !files::is_image(&path) {
+            continue;
+        }
+        let Some(truth_path) = ground_truth_path(&path) else {
+            report.skipped.push((path, "no ground truth".into()));
+            continue;
+        };
+        let truth = match fs::read_to_string(&truth_path) {
+            Ok(truth) => truth,
+            Err(err) => {
+                let why = format!("Failed to read {}: {}", truth_path.display(), err);
+                report.skipped.push((path, why));
+                continue;
+            }
+        };
+        let image = match files::open_image(&path, conversion) {
+            Ok((image, _)) => image,
+            Err(err) => {
+                report.skipped.push((path, err.to_string()));
+                continue;
+            }
+        };
+        let start = Instant::now();
+        let result = ocr::transcribe_preprocessed(backend, &image, preprocessing);
+        let latency = start.elapsed();
+        match result {
+            Ok(result) => report
+                .samples
+                .push(score(path, latency, &render(&result), &truth)),
+            Err(err) => report.skipped.push((path, err.to_string())),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::fixtures;
+    use image::DynamicImage;
+
+    // reads every image as empty, the text compared comes from `render`
+    struct Blank;
+
+    impl OcrBackend for Blank {
+        fn name(&self) -> &'static str {
+            "blank"
+        }
+
+        fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+            Ok(fixtures::transcription(
+                image.width(),
+                image.height(),
+                Vec::new(),
+            ))
+        }
+    }
+
+    #[test]
+    fn edit_distance_counts_the_fewest_edits() {
+        let chars = |text: &str| text.chars().collect::<Vec<char>>();
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(edit_distance(&chars("same"), &chars("same")), 0);
+    }
+
+    #[test]
+    fn line_breaks_and_spaces_do_not_count_as_errors() {
+        let sample = score(
+            PathBuf::from("page.png"),
+            Duration::ZERO,
+            "Hello\n  world",
+            "Hello world",
+        );
+        assert_eq!((sample.char_errors, sample.word_errors), (0, 0));
+        assert_eq!((sample.chars, sample.words), (11, 2));
+    }
+
+    #[test]
+    fn rates_weigh_long_texts_more() {
+        let report = Report {
+            samples: vec![
+                score(PathBuf::from("a.png"), Duration::ZERO, "abcd", "abcx"),
+                score(PathBuf::from("b.png"), Duration::ZERO, "ab", "ab"),
+            ],
+            skipped: Vec::new(),
+        };
+        assert_eq!(report.samples[0].cer(), 0.25);
+        assert_eq!(report.cer(), 1.0 / 6.0);
+        assert_eq!(report.wer(), 0.5);
+        assert_eq!(rate(0, 0), 0.0);
+        assert_eq!(rate(2, 0), 1.0);
+    }
+
+    #[test]
+    fn unreadable_ground_truth_skips_only_that_image() {
+        let dir =
+            std::env::temp_dir().join(format!("transcribewizard-eval-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let blank = DynamicImage::new_rgb8(8, 8);
+        for name in ["good", "bad", "untold"] {
+            blank.save(dir.join(format!("{}.png", name))).unwrap();
+        }
+        fs::write(dir.join("good.gt.txt"), "text").unwrap();
+        fs::write(dir.join("bad.gt.txt"), [0xff, 0xfe, 0xfd]).unwrap();
+
+        let report = run(
+            &Blank,
+            &dir,
+            &Preprocessing::default(),
+            Conversion::default(),
+            |_| "text".into(),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+        let report = report.unwrap();
+
+        assert_eq!(report.samples.len(), 1);
+        assert_eq!(report.samples[0].char_errors, 0);
+        let mut skipped: Vec<String> = report
+            .skipped
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        skipped.sort();
+        assert_eq!(skipped, ["bad.png", "untold.png"]);
+    }
+}
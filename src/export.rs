@@ -0,0 +1,532 @@
+use crate::{
+    diff::{self, Change},
+    layout,
+    ocr::{Bounds, RotatedBounds, TranscriptionResult, Word},
+    table,
+};
+use serde::Serialize;
+use std::{error::Error, fs, path::Path};
+
+/// File formats a transcription can be saved as.
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    Text,
+    Markdown,
+    Json,
+    Hocr,
+    Alto,
+    Csv,
+    MarkdownTable,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 7] = [
+        ExportFormat::Text,
+        ExportFormat::Markdown,
+        ExportFormat::Json,
+        ExportFormat::Hocr,
+        ExportFormat::Alto,
+        ExportFormat::Csv,
+        ExportFormat::MarkdownTable,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "Plain text",
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Hocr => "hOCR",
+            ExportFormat::Alto => "ALTO XML",
+            ExportFormat::Csv => "CSV table",
+            ExportFormat::MarkdownTable => "Markdown table",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Hocr => "hocr",
+            ExportFormat::Alto => "xml",
+            ExportFormat::Csv => "csv",
+            ExportFormat::MarkdownTable => "md",
+        }
+    }
+
+    /// hOCR, ALTO and tables are all about geometry, there is nothing to write without
+    /// a transcription.
+    pub fn needs_transcription(self) -> bool {
+        matches!(
+            self,
+            ExportFormat::Hocr
+                | ExportFormat::Alto
+                | ExportFormat::Csv
+                | ExportFormat::MarkdownTable
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    text: &'a str,
+    #[serde(flatten)]
+    transcription: Option<&'a TranscriptionResult>,
+}
+
+/// Render a result in the given format, `text` is what the user currently sees.
+pub fn render(
+    format: ExportFormat,
+    text: &str,
+    transcription: Option<&TranscriptionResult>,
+) -> Result<String, Box<dyn Error>> {
+    match format {
+        ExportFormat::Text => Ok(text.to_string()),
+        ExportFormat::Markdown => Ok(markdown(text, transcription)),
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(&JsonExport {
+            text,
+            transcription,
+        })?),
+        ExportFormat::Hocr => match transcription {
+            Some(transcription) => Ok(hocr(&corrected(text, transcription))),
+            None => Err("hOCR export needs text recognized from an image".into()),
+        },
+        ExportFormat::Alto => match transcription {
+            Some(transcription) => Ok(alto(&corrected(text, transcription))),
+            None => Err("ALTO export needs text recognized from an image".into()),
+        },
+        ExportFormat::Csv | ExportFormat::MarkdownTable => {
+            let transcription =
+                transcription.ok_or("Table export needs text recognized from an image")?;
+            let table = table::detect(transcription).ok_or("No table found in the image")?;
+            Ok(match format {
+                ExportFormat::Csv => table.to_csv(),
+                _ => table.to_markdown(),
+            })
+        }
+    }
+}
+
+pub fn save(
+    format: ExportFormat,
+    path: &Path,
+    text: &str,
+    transcription: Option<&TranscriptionResult>,
+) -> Result<(), Box<dyn Error>> {
+    let contents = render(format, text, transcription)?;
+    fs::write(path, contents)
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err).into())
+}
+
+/// The transcription with the corrections made in `text` carried over to its
+/// words, so formats that write every word with its box keep the edits. Words
+/// changed in place keep their box, words split or added share the box of the
+/// word they took the place of and removed words are left out. Lines left
+/// without a word are dropped.
+pub fn corrected(text: &str, transcription: &TranscriptionResult) -> TranscriptionResult {
+    // every word in reading order with the line it is on
+    let recognized: Vec<(usize, &Word)> = transcription
+        .lines
+        .iter()
+        .enumerate()
+        .flat_map(|(index, line)| line.words.iter().map(move |word| (index, word)))
+        .filter(|(_, word)| !word.text.trim().is_empty())
+        .collect();
+    let old = transcription
+        .lines
+        .iter()
+        .map(|line| {
+            line.words
+                .iter()
+                .map(|word| word.text.trim())
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut kept: Vec<(usize, Word)> = Vec::new();
+    let mut removed: Vec<(usize, Word)> = Vec::new();
+    let mut added: Vec<&str> = Vec::new();
+    let mut next = recognized.into_iter();
+    for (change, run) in diff::diff(&old, text) {
+        for word in run.split_whitespace() {
+            match change {
+                Change::Added => added.push(word),
+                Change::Removed => {
+                    removed.extend(next.next().map(|(line, word)| (line, word.clone())))
+                }
+                Change::Same => {
+                    let Some((line, recognized)) = next.next() else {
+                        continue;
+                    };
+                    // words added before the first one go into its box
+                    if kept.is_empty()
+                        && removed.is_empty()
+                        && added.iter().any(|word| has_letters(word))
+                    {
+                        removed.push((line, recognized.clone()));
+                        added.push(word);
+                    } else {
+                        replace(&mut kept, &mut removed, &mut added);
+                        kept.push((line, recognized.clone()));
+                    }
+                }
+            }
+        }
+    }
+    replace(&mut kept, &mut removed, &mut added);
+
+    let mut result = transcription.clone();
+    result.lines.iter_mut().for_each(|line| line.words.clear());
+    for (line, word) in kept {
+        result.lines[line].words.push(word);
+    }
+    result.lines.retain(|line| !line.words.is_empty());
+    for line in &mut result.lines {
+        line.text = line
+            .words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<&str>>()
+            .join(" ");
+    }
+    result
+}
+
+// put the words added in place of the removed ones, one to one as far as they go
+// and splitting the box of the last removed word for the rest
+fn replace(kept: &mut Vec<(usize, Word)>, removed: &mut Vec<(usize, Word)>, added: &mut Vec<&str>) {
+    // heading marks and bullets the layout put in front of lines have no box of
+    // their own
+    if removed.is_empty() && !added.iter().any(|word| has_letters(word)) {
+        added.clear();
+    }
+    if added.is_empty() {
+        removed.clear();
+        return;
+    }
+    if removed.is_empty() {
+        // added after a word, which makes room for them in its box
+        let Some(previous) = kept.pop() else {
+            return;
+        };
+        let mut texts = vec![previous.1.text.clone()];
+        texts.extend(added.iter().map(|word| word.to_string()));
+        kept.extend(split(previous, &texts));
+        added.clear();
+        return;
+    }
+    let paired = removed.len().min(added.len()) - 1;
+    let rest: Vec<String> = if added.len() >= removed.len() {
+        added[paired..]
+            .iter()
+            .map(|word| word.to_string())
+            .collect()
+    } else {
+        vec![added[paired].to_string()]
+    };
+    let mut words = removed.drain(..);
+    for text in &added[..paired] {
+        if let Some(word) = words.next() {
+            kept.extend(split(word, &[text.to_string()]));
+        }
+    }
+    if let Some(word) = words.next() {
+        kept.extend(split(word, &rest));
+    }
+    // what is left of the drain is dropped with it
+    drop(words);
+    added.clear();
+}
+
+fn has_letters(word: &str) -> bool {
+    word.chars().any(char::is_alphanumeric)
+}
+
+// a word's box cut along its length into one box per text, as wide as its share of
+// the characters
+fn split((line, word): (usize, Word), texts: &[String]) -> Vec<(usize, Word)> {
+    if texts.len() == 1 {
+        let unchanged = texts[0] == word.text;
+        return vec![(
+            line,
+            Word {
+                text: texts[0].clone(),
+                confidence: word.confidence.filter(|_| unchanged),
+                alternatives: if unchanged {
+                    word.alternatives
+                } else {
+                    Vec::new()
+                },
+                ..word
+            },
+        )];
+    }
+    let total: usize = texts.iter().map(|text| text.chars().count()).sum();
+    let width = (word.bounds.right - word.bounds.left) as f32;
+    let rotated = word.rotated_bounds;
+    let (cos, sin) = (
+        rotated.angle.to_radians().cos(),
+        rotated.angle.to_radians().sin(),
+    );
+    let mut start = 0;
+    texts
+        .iter()
+        .map(|text| {
+            let end = start + text.chars().count();
+            let (from, to) = (start as f32 / total as f32, end as f32 / total as f32);
+            start = end;
+            let offset = ((from + to) / 2.0 - 0.5) * rotated.width;
+            let piece = Word {
+                text: text.clone(),
+                bounds: Bounds {
+                    left: word.bounds.left + (width * from).round() as i32,
+                    right: word.bounds.left + (width * to).round() as i32,
+                    ..word.bounds
+                },
+                rotated_bounds: RotatedBounds {
+                    center_x: rotated.center_x + cos * offset,
+                    center_y: rotated.center_y + sin * offset,
+                    width: rotated.width * (to - from),
+                    ..rotated
+                },
+                confidence: None,
+                alternatives: Vec::new(),
+            };
+            (line, piece)
+        })
+        .collect()
+}
+
+// the structure inferred from the recognized lines, falling back to one plain
+// text line per markdown line
+fn markdown(text: &str, transcription: Option<&TranscriptionResult>) -> String {
+    let mut output = match transcription {
+        Some(transcription) => layout::markdown(transcription),
+        None => text
+            .lines()
+            .map(escape_markdown)
+            .collect::<Vec<String>>()
+            .join("  \n"),
+    };
+    output.push('\n');
+    output
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_{}[]<>()#+-.!|~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn hocr(transcription: &TranscriptionResult) -> String {
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str(
+        "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" \
+         \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n",
+    );
+    output.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"en\" lang=\"en\">\n");
+    output.push_str("<head>\n<title></title>\n");
+    output.push_str("<meta http-equiv=\"Content-Type\" content=\"text/html;charset=utf-8\" />\n");
+    output.push_str(&format!(
+        "<meta name=\"ocr-system\" content=\"{} {}\" />\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    ));
+    output.push_str("<meta name=\"ocr-capabilities\" content=\"ocr_page ocr_line ocrx_word\" />\n");
+    output.push_str("</head>\n<body>\n");
+    output.push_str(&format!(
+        "<div class=\"ocr_page\" id=\"page_1\" title=\"bbox 0 0 {} {}\">\n",
+        transcription.width, transcription.height
+    ));
+    for (line_index, line) in transcription.lines.iter().enumerate() {
+        output.push_str(&format!(
+            "<span class=\"ocr_line\" id=\"line_1_{}\" title=\"{}\">",
+            line_index + 1,
+            hocr_title(line.bounds, line.confidence)
+        ));
+        for (word_index, word) in line.words.iter().enumerate() {
+            if word_index > 0 {
+                output.push(' ');
+            }
+            output.push_str(&format!(
+                "<span class=\"ocrx_word\" id=\"word_1_{}_{}\" title=\"{}\">{}</span>",
+                line_index + 1,
+                word_index + 1,
+                hocr_title(word.bounds, word.confidence),
+                escape_xml(&word.text)
+            ));
+        }
+        output.push_str("</span>\n");
+    }
+    output.push_str("</div>\n</body>\n</html>\n");
+    output
+}
+
+// ALTO 4 in pixels, one block holding every line
+fn alto(transcription: &TranscriptionResult) -> String {
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str("<alto xmlns=\"http://www.loc.gov/standards/alto/ns-v4#\">\n");
+    output.push_str("<Description>\n<MeasurementUnit>pixel</MeasurementUnit>\n");
+    output.push_str(&format!(
+        "<OCRProcessing ID=\"OCR_0\"><ocrProcessingStep><processingSoftware>\
+         <softwareName>{}</softwareName><softwareVersion>{}</softwareVersion>\
+         </processingSoftware></ocrProcessingStep></OCRProcessing>\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    ));
+    output.push_str("</Description>\n<Layout>\n");
+    output.push_str(&format!(
+        "<Page ID=\"page_1\" PHYSICAL_IMG_NR=\"1\" WIDTH=\"{}\" HEIGHT=\"{}\">\n<PrintSpace>\n",
+        transcription.width, transcription.height
+    ));
+    output.push_str("<TextBlock ID=\"block_1\">\n");
+    for (line_index, line) in transcription.lines.iter().enumerate() {
+        output.push_str(&format!(
+            "<TextLine ID=\"line_{}\" {}>",
+            line_index + 1,
+            alto_position(line.bounds)
+        ));
+        for (word_index, word) in line.words.iter().enumerate() {
+            if word_index > 0 {
+                output.push_str("<SP/>");
+            }
+            output.push_str(&format!(
+                "<String ID=\"word_{}_{}\" CONTENT=\"{}\" {}",
+                line_index + 1,
+                word_index + 1,
+                escape_xml(&word.text),
+                alto_position(word.bounds)
+            ));
+            if let Some(confidence) = word.confidence {
+                output.push_str(&format!(" WC=\"{:.2}\"", confidence));
+            }
+            if word.alternatives.is_empty() {
+                output.push_str("/>");
+            } else {
+                output.push('>');
+                for alternative in &word.alternatives {
+                    output.push_str(&format!(
+                        "<ALTERNATIVE>{}</ALTERNATIVE>",
+                        escape_xml(alternative)
+                    ));
+                }
+                output.push_str("</String>");
+            }
+        }
+        output.push_str("</TextLine>\n");
+    }
+    output.push_str("</TextBlock>\n</PrintSpace>\n</Page>\n</Layout>\n</alto>\n");
+    output
+}
+
+fn alto_position(bounds: Bounds) -> String {
+    format!(
+        "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+        bounds.left,
+        bounds.top,
+        bounds.right - bounds.left,
+        bounds.bottom - bounds.top
+    )
+}
+
+fn hocr_title(bounds: Bounds, confidence: Option<f32>) -> String {
+    let mut title = format!(
+        "bbox {} {} {} {}",
+        bounds.left, bounds.top, bounds.right, bounds.bottom
+    );
+    if let Some(confidence) = confidence {
+        title.push_str(&format!("; x_wconf {}", (confidence * 100.0).round()));
+    }
+    title
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::fixtures;
+
+    fn word(text: &str, left: i32, right: i32) -> Word {
+        Word {
+            confidence: Some(0.5),
+            alternatives: vec!["other".to_string()],
+            ..fixtures::word(text, fixtures::bounds(left, 0, right, 10))
+        }
+    }
+
+    fn transcription(lines: Vec<Vec<Word>>) -> TranscriptionResult {
+        let lines = lines.into_iter().map(fixtures::line_of).collect();
+        fixtures::transcription(100, 100, lines)
+    }
+
+    fn texts(result: &TranscriptionResult) -> Vec<Vec<&str>> {
+        result
+            .lines
+            .iter()
+            .map(|line| line.words.iter().map(|word| word.text.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn corrected_words_keep_their_box() {
+        let read = transcription(vec![
+            vec![word("Helo", 0, 40), word("world", 50, 100)],
+            vec![word("again", 0, 50)],
+        ]);
+        let result = corrected("Hello world\nagain", &read);
+        assert_eq!(texts(&result), [vec!["Hello", "world"], vec!["again"]]);
+        let hello = &result.lines[0].words[0];
+        assert_eq!((hello.bounds.left, hello.bounds.right), (0, 40));
+        assert!(hello.confidence.is_none() && hello.alternatives.is_empty());
+        assert_eq!(result.lines[0].words[1].confidence, Some(0.5));
+        assert_eq!(result.lines[0].text, "Hello world");
+    }
+
+    #[test]
+    fn split_and_removed_words() {
+        let read = transcription(vec![
+            vec![word("newyork", 0, 70), word("city", 80, 120)],
+            vec![word("noise", 0, 50)],
+        ]);
+        let result = corrected("new york city", &read);
+        assert_eq!(texts(&result), [vec!["new", "york", "city"]]);
+        let york = &result.lines[0].words[1];
+        assert_eq!((york.bounds.left, york.bounds.right), (30, 70));
+    }
+
+    #[test]
+    fn layout_marks_and_reflowed_lines_change_nothing() {
+        let read = transcription(vec![vec![word("Title", 0, 50)], vec![word("text", 0, 40)]]);
+        let result = corrected("# Title\n\n- text", &read);
+        assert_eq!(texts(&result), [vec!["Title"], vec!["text"]]);
+        let result = corrected("Title text", &read);
+        assert_eq!(texts(&result), [vec!["Title"], vec!["text"]]);
+    }
+
+    #[test]
+    fn added_words_share_a_neighbour_box() {
+        let read = transcription(vec![vec![word("two", 0, 30)]]);
+        let result = corrected("one two three", &read);
+        assert_eq!(texts(&result), [vec!["one", "two", "three"]]);
+        assert!(render(ExportFormat::Hocr, "one two three", Some(&read))
+            .unwrap()
+            .contains(">three</span>"));
+    }
+}
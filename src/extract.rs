@@ -0,0 +1,141 @@
+use regex::{Regex, RegexBuilder};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use std::error::Error;
+
+/// How a field finds its value in the text.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rule {
+    /// `pattern` is a regular expression, the value is its first capture group
+    /// or the whole match without one.
+    Regex,
+    /// `pattern` is a label such as `Total`, the value is the rest of its line
+    /// or the next line when the label stands alone.
+    Anchor,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 2] = [Rule::Anchor, Rule::Regex];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rule::Regex => "Regex",
+            Rule::Anchor => "After label",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Field {
+    pub name: String,
+    pub rule: Rule,
+    pub pattern: String,
+}
+
+/// Named fields to pull out of one kind of document, such as an invoice.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+/// The value found for every field of a template, in template order.
+pub struct Extraction {
+    pub values: Vec<(String, Option<String>)>,
+}
+
+// fields serialize as a JSON object, missing values as null
+impl Serialize for Extraction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.values.len()))?;
+        for (name, value) in &self.values {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Extraction {
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+// what follows a label on its line, past separators like ':' and '#'
+fn after_anchor(text: &str, anchor: &Regex) -> Option<String> {
+    let found = anchor.find(text)?;
+    let rest = &text[found.end()..];
+    let (line, following) = rest.split_once('\n').unwrap_or((rest, ""));
+    let value = line.trim_start_matches(|character: char| {
+        character.is_whitespace() || ":#-=".contains(character)
+    });
+    let value = value.trim();
+    if !value.is_empty() {
+        return Some(value.to_string());
+    }
+    following
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+impl Field {
+    /// The value of the field in `text`, labels are matched ignoring case.
+    pub fn find(&self, text: &str) -> Result<Option<String>, Box<dyn Error>> {
+        match self.rule {
+            Rule::Regex => {
+                let pattern = Regex::new(&self.pattern)
+                    .map_err(|err| format!("Invalid pattern for {}: {}", self.name, err))?;
+                Ok(pattern.captures(text).and_then(|captures| {
+                    captures
+                        .get(1)
+                        .or(captures.get(0))
+                        .map(|value| value.as_str().trim().to_string())
+                }))
+            }
+            Rule::Anchor => {
+                // not preceded by a letter, "Total" should not find "Subtotal"
+                let pattern = format!(r"(?:^|\W){}", regex::escape(self.pattern.trim()));
+                let anchor = RegexBuilder::new(&pattern).case_insensitive(true).build()?;
+                Ok(after_anchor(text, &anchor))
+            }
+        }
+    }
+}
+
+impl Template {
+    pub fn apply(&self, text: &str) -> Result<Extraction, Box<dyn Error>> {
+        let values = self
+            .fields
+            .iter()
+            .map(|field| Ok((field.name.clone(), field.find(text)?)))
+            .collect::<Result<_, Box<dyn Error>>>()?;
+        Ok(Extraction { values })
+    }
+
+    /// Common invoice fields, a starting point to edit.
+    pub fn invoice() -> Self {
+        let field = |name: &str, rule, pattern: &str| Field {
+            name: name.into(),
+            rule,
+            pattern: pattern.into(),
+        };
+        Template {
+            name: "Invoice".into(),
+            fields: vec![
+                field(
+                    "Invoice #",
+                    Rule::Regex,
+                    r"(?i)invoice\s*(?:no\.?|number|#)\s*:?\s*([A-Z0-9-]+)",
+                ),
+                field(
+                    "Date",
+                    Rule::Regex,
+                    r"\b(\d{1,4}[./-]\d{1,2}[./-]\d{1,4})\b",
+                ),
+                field("Total", Rule::Anchor, "Total"),
+            ],
+        }
+    }
+}
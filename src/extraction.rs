@@ -0,0 +1,146 @@
+use crate::{config::Config, locale::tr};
+use imgui::{Condition, Ui};
+use tracing::error;
+use transcribewizard::extract::{Field, Rule, Template};
+
+// Window applying a field template to the text and exporting the values as JSON
+#[derive(Default)]
+pub struct ExtractionPanel {
+    selected: usize,
+    // JSON of the last extraction, or why it failed
+    result: Option<Result<String, String>>,
+}
+
+impl ExtractionPanel {
+    pub fn draw(&mut self, ui: &Ui, config: &mut Config, text: &str, open: &mut bool) {
+        ui.window(format!("{}###Extract fields", tr("Extract fields")))
+            .size([560.0, 480.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                let mut changed = false;
+
+                let names: Vec<&str> = config
+                    .templates
+                    .iter()
+                    .map(|template| template.name.as_str())
+                    .collect();
+                self.selected = self.selected.min(names.len().saturating_sub(1));
+                if ui.combo_simple_string(tr("Template"), &mut self.selected, &names) {
+                    self.result = None;
+                }
+                ui.same_line();
+                if ui.button(tr("New")) {
+                    config.templates.push(Template {
+                        name: format!("Template {}", config.templates.len() + 1),
+                        fields: Vec::new(),
+                    });
+                    self.selected = config.templates.len() - 1;
+                    changed = true;
+                }
+                ui.same_line();
+                if ui.button(tr("Delete")) && !config.templates.is_empty() {
+                    config.templates.remove(self.selected);
+                    self.selected = self.selected.saturating_sub(1);
+                    self.result = None;
+                    changed = true;
+                }
+
+                if let Some(template) = config.templates.get_mut(self.selected) {
+                    changed |= ui.input_text(tr("Name"), &mut template.name).build();
+                    changed |= edit_fields(ui, &mut template.fields);
+
+                    ui.separator();
+                    if ui.button(tr("Extract")) {
+                        self.result = Some(
+                            template
+                                .apply(text)
+                                .and_then(|extraction| extraction.to_json())
+                                .map_err(|err| err.to_string()),
+                        );
+                    }
+                }
+
+                match &mut self.result {
+                    Some(Ok(json)) => {
+                        ui.same_line();
+                        if ui.button(tr("Save JSON...")) {
+                            let path = crate::projects::save_dialog(config, "fields", "json", None)
+                                .add_filter("JSON", &["json"])
+                                .save_file();
+                            if let Some(path) = path {
+                                if let Err(err) = std::fs::write(&path, json.as_bytes()) {
+                                    self.result =
+                                        Some(Err(format!("Error saving fields: {}", err)));
+                                }
+                            }
+                        }
+                    }
+                    Some(Err(err)) => ui.text_wrapped(err.as_str()),
+                    None => (),
+                }
+                if let Some(Ok(json)) = &mut self.result {
+                    ui.input_text_multiline("##fields", json, [-1.0, -1.0])
+                        .read_only(true)
+                        .build();
+                }
+
+                if changed {
+                    if let Err(err) = config.save() {
+                        error!("Error saving config: {}", err);
+                    }
+                }
+            });
+    }
+}
+
+// rows of name, rule and pattern, returns whether anything changed
+fn edit_fields(ui: &Ui, fields: &mut Vec<Field>) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+    let rule_labels = Rule::ALL.map(Rule::label);
+    for (index, field) in fields.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(index);
+        ui.set_next_item_width(120.0);
+        changed |= ui
+            .input_text("##name", &mut field.name)
+            .hint(tr("Field"))
+            .build();
+        ui.same_line();
+        ui.set_next_item_width(110.0);
+        let mut rule_index = Rule::ALL
+            .iter()
+            .position(|rule| *rule == field.rule)
+            .unwrap_or(0);
+        if ui.combo_simple_string("##rule", &mut rule_index, &rule_labels) {
+            field.rule = Rule::ALL[rule_index];
+            changed = true;
+        }
+        ui.same_line();
+        ui.set_next_item_width(-30.0);
+        let hint = match field.rule {
+            Rule::Anchor => "Label, e.g. Total",
+            Rule::Regex => "Pattern, first group is the value",
+        };
+        changed |= ui
+            .input_text("##pattern", &mut field.pattern)
+            .hint(hint)
+            .build();
+        ui.same_line();
+        if ui.small_button("x") {
+            removed = Some(index);
+        }
+    }
+    if let Some(index) = removed {
+        fields.remove(index);
+        changed = true;
+    }
+    if ui.button(tr("Add field")) {
+        fields.push(Field {
+            name: String::new(),
+            rule: Rule::Anchor,
+            pattern: String::new(),
+        });
+        changed = true;
+    }
+    changed
+}
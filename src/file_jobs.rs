@@ -0,0 +1,95 @@
+use crate::{
+    clipboard::SystemClipboard, config::Config, history::History, instance::Reply,
+    masking::SecretGuard, record_capture, status::Status, FileJob, Placement,
+};
+use imgui_glow_renderer::AutoRenderer;
+use std::sync::mpsc::TryRecvError;
+use transcribewizard::TranscribeError;
+
+// A file another instance sent, or the frames of a GIF, being read on the pool
+struct Pending {
+    job: FileJob,
+    // the instance waiting for the text
+    reply: Option<Reply>,
+    // the result goes on the clipboard
+    copy: bool,
+    // the tab it was started from
+    tab: usize,
+}
+
+// The files being read on the pool, recorded in the history once read
+#[derive(Default)]
+pub struct FileJobs {
+    pending: Vec<Pending>,
+}
+
+impl FileJobs {
+    pub fn push(&mut self, job: FileJob, reply: Option<Reply>, copy: bool, tab: usize) {
+        self.pending.push(Pending {
+            job,
+            reply,
+            copy,
+            tab,
+        });
+    }
+
+    // record the files read since the last call, copied when they were to be
+    // and answered to who waits for them. Returns the texts to place in the
+    // tabs they were started from
+    pub fn poll(
+        &mut self,
+        config: &Config,
+        clipboard: &SystemClipboard,
+        renderer: &mut AutoRenderer,
+        history: &mut History,
+        secret_guard: &mut SecretGuard,
+        status: &mut Status,
+    ) -> Vec<Placement> {
+        let mut placements = Vec::new();
+        let mut index = 0;
+        while index < self.pending.len() {
+            let result = match self.pending[index].job.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => {
+                    index += 1;
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => Err("The OCR worker stopped".into()),
+            };
+            let Pending {
+                reply, copy, tab, ..
+            } = self.pending.swap_remove(index);
+            let recorded = result.map_err(TranscribeError::Ocr).and_then(|capture| {
+                record_capture(
+                    capture,
+                    config,
+                    clipboard,
+                    renderer,
+                    history,
+                    copy.then_some(&mut *secret_guard),
+                )
+            });
+            match recorded {
+                Ok((result, result_transcription, result_pages)) => {
+                    if let Some(reply) = reply {
+                        reply.answer(Ok(result.clone()));
+                    }
+                    placements.push(Placement {
+                        tab,
+                        result,
+                        before: None,
+                        read: Some((result_transcription, result_pages)),
+                        merge: false,
+                    });
+                }
+                Err(err) => {
+                    if let Some(reply) = reply {
+                        reply.answer(Err(err.to_string()));
+                    }
+                    status.error("Error extracting text from image", err);
+                }
+            }
+        }
+        placements
+    }
+}
@@ -0,0 +1,855 @@
+use crate::{
+    backend::OcrBackend,
+    metadata::{self, ImageMetadata},
+    ocr,
+    preprocess::Preprocessing,
+    TranscribeError, TranscriptionResult,
+};
+use flate2::read::GzDecoder;
+use image::{
+    codecs::gif::GifDecoder, imageops::FilterType, AnimationDecoder, DynamicImage, ImageDecoder,
+    ImageError, ImageFormat, ImageReader, Limits,
+};
+use quick_xml::{events::Event, Reader};
+use std::{
+    error::Error,
+    fs,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// Extensions of the image files [`load_pages`] reads.
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "tif", "tiff", "webp", "pnm", "tga", "ico", "qoi", "heic",
+    "heif", "avif", "svg", "svgz",
+];
+
+/// Resolution PDF pages are rendered at, enough for small print.
+pub const PDF_DPI: u32 = 300;
+
+/// Resolution SVG images are drawn at by default, twice the size they have on
+/// a screen.
+pub const SVG_DPI: u32 = 192;
+
+/// Bytes in the megabytes [`ImageLimits`] are set in.
+pub const MEGABYTE: u64 = 1024 * 1024;
+
+/// How images of the formats a tool reads are drawn, and how large they may
+/// get. Their size is read from the file before the tool runs, and its output
+/// is decoded within the same limit.
+#[derive(Clone, Copy)]
+pub struct Conversion {
+    /// Resolution SVG images are drawn at, 96 draws them at their own size.
+    pub svg_dpi: u32,
+    /// Memory the decoded pixels may take, larger images are refused.
+    pub max_bytes: u64,
+}
+
+impl Default for Conversion {
+    fn default() -> Self {
+        Conversion {
+            svg_dpi: SVG_DPI,
+            max_bytes: 256 * MEGABYTE,
+        }
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extensions
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+pub fn is_image(path: &Path) -> bool {
+    has_extension(path, IMAGE_EXTENSIONS)
+}
+
+pub fn is_pdf(path: &Path) -> bool {
+    has_extension(path, &["pdf"])
+}
+
+/// Whether [`load_pages`] can read the file.
+pub fn is_supported(path: &Path) -> bool {
+    is_image(path) || is_pdf(path)
+}
+
+/// Supported files in `dir` sorted by path, including those in subdirectories
+/// when `recursive`.
+pub fn scan_dir(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|err| format!("Failed to read {}: {}", dir.display(), err))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                if recursive {
+                    dirs.push(path);
+                }
+            } else if is_supported(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Render every page of a PDF with the `pdftoppm` command from poppler.
+pub fn pdf_pages(path: &Path, dpi: u32) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    in_scratch_dir(|dir| render_pdf(path, dpi, dir))
+}
+
+// run `work` in a directory of its own for the files a tool writes, removed
+// again after
+fn in_scratch_dir<T>(
+    work: impl FnOnce(&Path) -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.subsec_nanos());
+    let dir =
+        std::env::temp_dir().join(format!("transcribewizard-{}-{}", std::process::id(), nanos));
+    fs::create_dir_all(&dir)?;
+    let result = work(&dir);
+    if let Err(err) = fs::remove_dir_all(&dir) {
+        warn!("Error removing {}: {}", dir.display(), err);
+    }
+    result
+}
+
+/// Image formats the image crate has no decoder for, read by a tool instead.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Converted {
+    /// HEIC photos of phones and AVIF, both HEIF files, read by libheif.
+    Heif,
+    /// Drawn by rsvg-convert from librsvg at the resolution of the
+    /// [`Conversion`].
+    Svg,
+}
+
+impl Converted {
+    /// The format of a file of these by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if has_extension(path, &["heic", "heif", "avif"]) {
+            Some(Converted::Heif)
+        } else if has_extension(path, &["svg", "svgz"]) {
+            Some(Converted::Svg)
+        } else {
+            None
+        }
+    }
+
+    /// The format of an encoded image of these by its content.
+    pub fn guess(bytes: &[u8]) -> Option<Self> {
+        // HEIF files start with a box naming the kind of file they are
+        const BRANDS: &[&[u8]] = &[
+            b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1", b"avif",
+            b"avis",
+        ];
+        if bytes.get(4..8) == Some(b"ftyp")
+            && bytes
+                .get(8..12)
+                .is_some_and(|brand| BRANDS.contains(&brand))
+        {
+            return Some(Converted::Heif);
+        }
+        let start = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+        let start = start.trim_start_matches('\u{feff}').trim_start();
+        let xml = start.starts_with("<?xml") || start.starts_with("<!DOCTYPE svg");
+        (start.starts_with("<svg") || xml && start.contains("<svg")).then_some(Converted::Svg)
+    }
+
+    /// Decode an image of this format, as the tool reading it turned it. An
+    /// image larger than the [`Conversion`] allows is refused with
+    /// [`TranscribeError::ImageTooLarge`], before the tool runs where the file
+    /// tells its size.
+    pub fn decode(
+        self,
+        bytes: &[u8],
+        conversion: Conversion,
+    ) -> Result<DynamicImage, Box<dyn Error>> {
+        let limits = ImageLimits {
+            max_bytes: conversion.max_bytes,
+            max_pixels: 0,
+        };
+        let png = match self {
+            Converted::Heif => {
+                if let Some((width, height)) = heif_size(bytes) {
+                    limits.check(width, height)?;
+                }
+                in_scratch_dir(|dir| decode_heif(bytes, dir))?
+            }
+            Converted::Svg => draw_svg(bytes, conversion)?,
+        };
+        Ok(decode_limited(&png, &limits)?)
+    }
+}
+
+// an encoded image refused as `limits` asks by the size in its header, then
+// decoded with no allocation past it
+fn decode_limited(bytes: &[u8], limits: &ImageLimits) -> Result<DynamicImage, TranscribeError> {
+    limits.check_encoded(bytes)?;
+    let mut allowed = Limits::default();
+    allowed.max_alloc = Some(limits.max_bytes);
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|err| TranscribeError::UnsupportedFormat(err.to_string()))?;
+    reader.limits(allowed);
+    reader
+        .decode()
+        .map_err(|err| TranscribeError::UnsupportedFormat(err.to_string()))
+}
+
+// the largest image a HEIF file declares in its ispe properties, the primary
+// image or the grid it is put together from, None when it declares none
+fn heif_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let u32_at = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    // the box type is followed by its version and flags, then the size
+    bytes
+        .windows(4)
+        .enumerate()
+        .filter(|(_, kind)| *kind == b"ispe")
+        .filter_map(|(at, _)| Some((u32_at(at + 8)?, u32_at(at + 12)?)))
+        .max_by_key(|(width, height)| *width as u64 * *height as u64)
+}
+
+// a length of an SVG in CSS pixels, None for relative units such as % and em
+fn css_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+        .unwrap_or(value.len());
+    let number: f64 = value[..split].parse().ok()?;
+    let pixels = match value[split..].trim() {
+        "" | "px" => 1.0,
+        "in" => 96.0,
+        "cm" => 96.0 / 2.54,
+        "mm" => 96.0 / 25.4,
+        "pt" => 96.0 / 72.0,
+        "pc" => 16.0,
+        _ => return None,
+    };
+    Some(number * pixels)
+}
+
+// the size an SVG is drawn at in CSS pixels, from the width and height of its
+// root or else its viewBox, None when it gives neither
+fn svg_size(bytes: &[u8]) -> Option<(f64, f64)> {
+    // svgz files are gzipped, the root is near the start
+    let mut unzipped = Vec::new();
+    let bytes = match bytes.starts_with(&[0x1f, 0x8b]) {
+        true => {
+            GzDecoder::new(bytes)
+                .take(1 << 16)
+                .read_to_end(&mut unzipped)
+                .ok()?;
+            &unzipped[..]
+        }
+        false => bytes,
+    };
+    let text = String::from_utf8_lossy(bytes);
+    let mut reader = Reader::from_str(&text);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(element) | Event::Empty(element)
+                if element.local_name().as_ref() == "svg" =>
+            {
+                let attribute = |name: &str| {
+                    element
+                        .attributes()
+                        .flatten()
+                        .find(|attribute| attribute.key.local_name().as_ref() == name)
+                        .map(|attribute| attribute.value.to_string())
+                };
+                let view_box = attribute("viewBox").and_then(|view_box| {
+                    let numbers: Vec<f64> = view_box
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter_map(|number| number.parse().ok())
+                        .collect();
+                    (numbers.len() == 4 && numbers[2] > 0.0 && numbers[3] > 0.0)
+                        .then(|| (numbers[2], numbers[3]))
+                });
+                let width = attribute("width").and_then(|width| css_length(&width));
+                let height = attribute("height").and_then(|height| css_length(&height));
+                return match (width, height, view_box) {
+                    (Some(width), Some(height), _) => Some((width, height)),
+                    (Some(width), None, Some((box_width, box_height))) => {
+                        Some((width, width * box_height / box_width))
+                    }
+                    (None, Some(height), Some((box_width, box_height))) => {
+                        Some((height * box_width / box_height, height))
+                    }
+                    (_, _, view_box) => view_box,
+                };
+            }
+            Event::Eof => return None,
+            _ => (),
+        }
+    }
+}
+
+// the primary image of a HEIF file as PNG, turned upright by libheif as the
+// file says, with heif-dec or the heif-convert of older versions
+fn decode_heif(bytes: &[u8], dir: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (input, output) = (dir.join("image.heic"), dir.join("image.png"));
+    fs::write(&input, bytes)?;
+    let run = |tool: &str| Command::new(tool).arg(&input).arg(&output).output();
+    let output_of = match run("heif-dec") {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => run("heif-convert"),
+        result => result,
+    }
+    .map_err(|err| format!("Failed to run heif-dec, is libheif installed? {}", err))?;
+    if !output_of.status.success() {
+        return Err(format!(
+            "Failed to decode HEIF image: {}",
+            String::from_utf8_lossy(&output_of.stderr).trim()
+        )
+        .into());
+    }
+    Ok(fs::read(&output)?)
+}
+
+// an SVG drawn as PNG at the resolution of `conversion` with rsvg-convert, on
+// white as the text of most drawings is dark over nothing. The size it is drawn
+// at is checked first and given to the tool, so it cannot draw a larger one
+fn draw_svg(bytes: &[u8], conversion: Conversion) -> Result<Vec<u8>, Box<dyn Error>> {
+    // SVG sizes are in CSS pixels, 96 to the inch
+    let zoom = conversion.svg_dpi.max(1) as f64 / 96.0;
+    let mut command = Command::new("rsvg-convert");
+    command.args(["--format", "png", "--background-color", "white"]);
+    match svg_size(bytes) {
+        Some((width, height)) => {
+            // a size past u32 saturates and is refused as too large
+            let width = (width * zoom).ceil().max(1.0) as u32;
+            let height = (height * zoom).ceil().max(1.0) as u32;
+            ImageLimits {
+                max_bytes: conversion.max_bytes,
+                max_pixels: 0,
+            }
+            .check(width, height)?;
+            command.args([
+                "--width",
+                &width.to_string(),
+                "--height",
+                &height.to_string(),
+            ]);
+        }
+        None => {
+            command.args(["--zoom", &zoom.to_string()]);
+        }
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Failed to run rsvg-convert, is librsvg installed? {}", err))?;
+    // written and read on threads of their own, rsvg-convert may fill either
+    // output before it has read all of its input
+    let mut stdin = child.stdin.take().ok_or("No input to rsvg-convert")?;
+    let svg = bytes.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&svg));
+    let mut stderr = child.stderr.take().ok_or("No errors of rsvg-convert")?;
+    let errors = std::thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+    // a PNG is never much larger than its pixels, so a longer output is cut off
+    let mut png = Vec::new();
+    let read = child
+        .stdout
+        .take()
+        .ok_or("No output of rsvg-convert")?
+        .take(conversion.max_bytes + 1)
+        .read_to_end(&mut png);
+    if read.is_err() || png.len() as u64 > conversion.max_bytes {
+        let _ = child.kill();
+    }
+    let status = child.wait()?;
+    if let Ok(Err(err)) = writer.join() {
+        warn!("Error writing SVG to rsvg-convert: {}", err);
+    }
+    let errors = errors.join().unwrap_or_default();
+    read?;
+    if png.len() as u64 > conversion.max_bytes {
+        return Err(format!(
+            "The drawn SVG image is larger than {} MB",
+            conversion.max_bytes / MEGABYTE
+        )
+        .into());
+    }
+    if !status.success() {
+        return Err(format!("Failed to draw SVG image: {}", errors.trim()).into());
+    }
+    Ok(png)
+}
+
+/// Decode an encoded image of any format [`load_pages`] reads but PDF, by its
+/// content, refusing one larger than `conversion` allows before its pixels
+/// are read.
+pub fn decode(bytes: &[u8], conversion: Conversion) -> Result<DynamicImage, Box<dyn Error>> {
+    match Converted::guess(bytes) {
+        Some(converted) => converted.decode(bytes, conversion),
+        None => Ok(decode_limited(
+            bytes,
+            &ImageLimits {
+                max_bytes: conversion.max_bytes,
+                max_pixels: 0,
+            },
+        )?),
+    }
+}
+
+fn render_pdf(path: &Path, dpi: u32, dir: &Path) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    let output = Command::new("pdftoppm")
+        .args(["-r", &dpi.to_string(), "-png"])
+        .arg(path)
+        .arg(dir.join("page"))
+        .output()
+        .map_err(|err| format!("Failed to run pdftoppm, is poppler installed? {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to render {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    // page numbers are zero padded, so names sort in page order
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .map(|file| image::open(file).map_err(|err| err.into()))
+        .collect()
+}
+
+// where the image directory of each page of a TIFF starts, None for BigTIFF
+// and files that are not TIFF at all
+fn tiff_directories(bytes: &[u8]) -> Option<Vec<u32>> {
+    let little = match bytes.get(..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let bytes = bytes.get(at..at + 2)?.try_into().ok()?;
+        Some(if little {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    };
+    let u32_at = |at: usize| {
+        let bytes = bytes.get(at..at + 4)?.try_into().ok()?;
+        Some(if little {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    };
+    if u16_at(2)? != 42 {
+        return None;
+    }
+    let mut directories = Vec::new();
+    let mut offset = u32_at(4)?;
+    // a broken file could point back at a page already read
+    while offset != 0 && !directories.contains(&offset) {
+        directories.push(offset);
+        let entries = u16_at(offset as usize)? as usize;
+        offset = u32_at(offset as usize + 2 + entries * 12)?;
+    }
+    Some(directories)
+}
+
+// every page of a TIFF, decoded one by one with the header pointing at each of
+// them in turn as the first
+fn tiff_pages(bytes: &[u8]) -> Result<Vec<DynamicImage>, ImageError> {
+    let directories = match tiff_directories(bytes) {
+        Some(directories) if directories.len() > 1 => directories,
+        _ => {
+            return Ok(vec![image::load_from_memory_with_format(
+                bytes,
+                ImageFormat::Tiff,
+            )?])
+        }
+    };
+    let little = bytes.starts_with(b"II");
+    let mut page = bytes.to_vec();
+    directories
+        .into_iter()
+        .map(|offset| {
+            let offset = if little {
+                offset.to_le_bytes()
+            } else {
+                offset.to_be_bytes()
+            };
+            page[4..8].copy_from_slice(&offset);
+            image::load_from_memory_with_format(&page, ImageFormat::Tiff)
+        })
+        .collect()
+}
+
+/// Decode an image turned the way its EXIF orientation says it is meant to be
+/// seen, phones store photos as the sensor took them, along with the rest of
+/// its metadata.
+pub fn decode_oriented(
+    bytes: &[u8],
+    format: ImageFormat,
+) -> Result<(DynamicImage, ImageMetadata), ImageError> {
+    let mut decoder = ImageReader::with_format(Cursor::new(bytes), format).into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let metadata = match decoder.exif_metadata() {
+        Ok(Some(exif)) => metadata::from_exif(&exif),
+        _ => ImageMetadata::default(),
+    };
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok((image, metadata))
+}
+
+/// Frames of an animated GIF read at most, those of a longer animation after
+/// them are left out.
+pub const MAX_FRAMES: usize = 100;
+
+/// Frames of an animation that differ from the one before them read at most,
+/// the ones after them are left out as reading each takes a while.
+pub const MAX_READ_FRAMES: usize = 30;
+
+/// For each frame of an animation that gets read, the index of the frame
+/// whose text it takes: its own, or the one before it when both show the same
+/// pixels, as the text of an animation often holds still. Frames after the
+/// first [`MAX_READ_FRAMES`] to be read are left out.
+pub fn frame_sources(frames: &[DynamicImage]) -> Vec<usize> {
+    let mut sources: Vec<usize> = Vec::new();
+    let mut read = 0;
+    for (index, frame) in frames.iter().enumerate() {
+        let same = sources.last().is_some_and(|&source| {
+            let previous = &frames[source];
+            previous.width() == frame.width()
+                && previous.height() == frame.height()
+                && previous.as_bytes() == frame.as_bytes()
+        });
+        if same {
+            sources.push(sources[index - 1]);
+            continue;
+        }
+        if read == MAX_READ_FRAMES {
+            break;
+        }
+        read += 1;
+        sources.push(index);
+    }
+    sources
+}
+
+/// Bytes the pixels of a `width` by `height` image take once decoded to RGBA.
+pub fn rgba_size(width: u32, height: u32) -> u64 {
+    width as u64 * height as u64 * 4
+}
+
+/// How large a pasted image may be, so a huge bitmap is scaled down or
+/// turned away instead of taking all memory.
+#[derive(Clone, Copy)]
+pub struct ImageLimits {
+    /// Memory the decoded pixels may take, larger images are refused.
+    pub max_bytes: u64,
+    /// Images with more pixels are scaled down to about this many, 0 for no limit.
+    pub max_pixels: u64,
+}
+
+impl ImageLimits {
+    /// Refuse a `width` by `height` image that would take more than [`Self::max_bytes`].
+    pub fn check(&self, width: u32, height: u32) -> Result<(), TranscribeError> {
+        if rgba_size(width, height) > self.max_bytes {
+            return Err(TranscribeError::ImageTooLarge {
+                width,
+                height,
+                limit: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Refuse an encoded image that would be too large, going by the size in
+    /// its header and reading no pixels.
+    pub fn check_encoded(&self, bytes: &[u8]) -> Result<(), TranscribeError> {
+        let (width, height) = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|err| TranscribeError::UnsupportedFormat(err.to_string()))?
+            .into_dimensions()
+            .map_err(|err| TranscribeError::UnsupportedFormat(err.to_string()))?;
+        self.check(width, height)
+    }
+
+    /// Decode an encoded image refused as [`Self::check_encoded`] does, then
+    /// scaled down as [`Self::shrink`] does. SVGs are drawn at `svg_dpi`.
+    pub fn decode(&self, bytes: &[u8], svg_dpi: u32) -> Result<DynamicImage, TranscribeError> {
+        if let Some(converted) = Converted::guess(bytes) {
+            let conversion = Conversion {
+                svg_dpi,
+                max_bytes: self.max_bytes,
+            };
+            let image = converted.decode(bytes, conversion).map_err(|err| match err
+                .downcast::<TranscribeError>()
+            {
+                Ok(err) => *err,
+                Err(err) => TranscribeError::UnsupportedFormat(err.to_string()),
+            })?;
+            return Ok(self.shrink(image));
+        }
+        Ok(self.shrink(decode_limited(bytes, self)?))
+    }
+
+    /// Decode the frames of an animated GIF refused as [`Self::check_encoded`]
+    /// does, each scaled down as [`Self::shrink`] does. Only as many as fit in
+    /// [`Self::max_bytes`] together are decoded, and at most [`MAX_FRAMES`].
+    pub fn decode_gif(&self, bytes: &[u8]) -> Result<Vec<DynamicImage>, TranscribeError> {
+        let unsupported = |err: ImageError| TranscribeError::UnsupportedFormat(err.to_string());
+        self.check_encoded(bytes)?;
+        let decoder = GifDecoder::new(Cursor::new(bytes)).map_err(unsupported)?;
+        let (width, height) = decoder.dimensions();
+        let fitting = (self.max_bytes / rgba_size(width, height).max(1)).max(1);
+        decoder
+            .into_frames()
+            .take(MAX_FRAMES.min(fitting as usize))
+            .map(|frame| {
+                let frame = frame.map_err(unsupported)?.into_buffer();
+                Ok(self.shrink(DynamicImage::ImageRgba8(frame)))
+            })
+            .collect()
+    }
+
+    /// `image` scaled down to at most [`Self::max_pixels`], keeping its aspect
+    /// ratio, or as it is when it is small enough.
+    pub fn shrink(&self, image: DynamicImage) -> DynamicImage {
+        let (width, height) = self.shrunk_size(image.width(), image.height());
+        if (width, height) == (image.width(), image.height()) {
+            return image;
+        }
+        image.resize_exact(width, height, FilterType::Triangle)
+    }
+
+    /// The size [`Self::shrink`] scales a `width` by `height` image to.
+    pub fn shrunk_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let pixels = width as u64 * height as u64;
+        if self.max_pixels == 0 || pixels <= self.max_pixels {
+            return (width, height);
+        }
+        let scale = (self.max_pixels as f64 / pixels as f64).sqrt();
+        (
+            ((width as f64 * scale) as u32).max(1),
+            ((height as f64 * scale) as u32).max(1),
+        )
+    }
+}
+
+/// Every image an encoded file of `format` holds, each frame of an animated
+/// GIF as it is shown up to [`MAX_FRAMES`] and each page of a TIFF, one for
+/// other formats.
+pub fn decode_frames(bytes: &[u8], format: ImageFormat) -> Result<Vec<DynamicImage>, ImageError> {
+    match format {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))?
+            .into_frames()
+            .take(MAX_FRAMES)
+            .map(|frame| Ok(DynamicImage::ImageRgba8(frame?.into_buffer())))
+            .collect(),
+        ImageFormat::Tiff => tiff_pages(bytes),
+        format => Ok(vec![decode_oriented(bytes, format)?.0]),
+    }
+}
+
+/// Read an image file, every frame of an animated GIF or page of a TIFF, or
+/// every page of a PDF.
+pub fn load_pages(
+    path: &Path,
+    conversion: Conversion,
+) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    if is_pdf(path) {
+        return pdf_pages(path, PDF_DPI);
+    }
+    if let Some(image) = read_converted(path, conversion)? {
+        return Ok(vec![image]);
+    }
+    let (bytes, format) = read_image_file(path)?;
+    decode_frames(&bytes, format).map_err(|err| open_error(path, err))
+}
+
+// the contents of an image file and its format, by extension or else content
+fn read_image_file(path: &Path) -> Result<(Vec<u8>, ImageFormat), Box<dyn Error>> {
+    let bytes =
+        fs::read(path).map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+    let format = ImageFormat::from_path(path)
+        .or_else(|_| image::guess_format(&bytes))
+        .map_err(|err| open_error(path, err))?;
+    Ok((bytes, format))
+}
+
+// the image of a file only a tool reads, None for other files
+fn read_converted(
+    path: &Path,
+    conversion: Conversion,
+) -> Result<Option<DynamicImage>, Box<dyn Error>> {
+    let Some(converted) = Converted::from_path(path) else {
+        return Ok(None);
+    };
+    let bytes =
+        fs::read(path).map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+    match converted.decode(&bytes, conversion) {
+        Ok(image) => Ok(Some(image)),
+        Err(err) => Err(format!("Failed to open {}: {}", path.display(), err).into()),
+    }
+}
+
+fn open_error(path: &Path, err: ImageError) -> Box<dyn Error> {
+    match err {
+        ImageError::Unsupported(err) => {
+            TranscribeError::UnsupportedFormat(format!("{}: {}", path.display(), err)).into()
+        }
+        err => format!("Failed to open {}: {}", path.display(), err).into(),
+    }
+}
+
+/// Read an image file upright with its metadata, the first frame of an
+/// animation or page of a TIFF.
+pub fn open_image(
+    path: &Path,
+    conversion: Conversion,
+) -> Result<(DynamicImage, ImageMetadata), Box<dyn Error>> {
+    if let Some(image) = read_converted(path, conversion)? {
+        return Ok((image, ImageMetadata::default()));
+    }
+    let (bytes, format) = read_image_file(path)?;
+    decode_oriented(&bytes, format).map_err(|err| open_error(path, err))
+}
+
+/// Join the texts of the frames of an image of `format`. Frames of an
+/// animated GIF and pages of a TIFF are headed by their number, and frames of
+/// a GIF reading the same as the one before are left out, the text of an
+/// animation often holds still. Pages of other files are joined as they are.
+pub fn join_frames(format: Option<ImageFormat>, texts: &[String]) -> String {
+    let noun = match format {
+        Some(ImageFormat::Gif) => "Frame",
+        Some(ImageFormat::Tiff) => "Page",
+        _ => return texts.join("\n\n"),
+    };
+    if texts.len() < 2 {
+        return texts.join("\n\n");
+    }
+    let mut joined = Vec::new();
+    for (index, text) in texts.iter().enumerate() {
+        let repeated = index > 0 && texts[index - 1].trim() == text.trim();
+        if text.trim().is_empty() || repeated && format == Some(ImageFormat::Gif) {
+            continue;
+        }
+        joined.push(format!("[{} {}]\n{}", noun, index + 1, text));
+    }
+    joined.join("\n\n")
+}
+
+/// Join the texts of the pages of `path`, see [`join_frames`].
+pub fn join_pages(path: &Path, texts: &[String]) -> String {
+    join_frames(ImageFormat::from_path(path).ok(), texts)
+}
+
+/// Transcribe each page of an image or PDF file. Frames of an animated GIF
+/// are read as [`frame_sources`] says.
+pub fn transcribe_file(
+    backend: &dyn OcrBackend,
+    path: &Path,
+    preprocessing: &Preprocessing,
+    conversion: Conversion,
+) -> Result<Vec<(DynamicImage, TranscriptionResult)>, Box<dyn Error>> {
+    let pages = load_pages(path, conversion)?;
+    if ImageFormat::from_path(path).ok() != Some(ImageFormat::Gif) {
+        return pages
+            .into_iter()
+            .map(|page| {
+                let transcription = ocr::transcribe_preprocessed(backend, &page, preprocessing)?;
+                Ok((page, transcription))
+            })
+            .collect();
+    }
+    let sources = frame_sources(&pages);
+    let mut read: Vec<(DynamicImage, TranscriptionResult)> = Vec::new();
+    for (index, (page, source)) in pages.into_iter().zip(sources).enumerate() {
+        let transcription = match source == index {
+            true => ocr::transcribe_preprocessed(backend, &page, preprocessing)?,
+            false => read[source].1.clone(),
+        };
+        read.push((page, transcription));
+    }
+    Ok(read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn still_frames_take_the_text_of_the_one_before() {
+        let frame = |value| {
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(4, 4, image::Luma([value])))
+        };
+        let frames = [frame(0), frame(0), frame(9), frame(9), frame(0)];
+        assert_eq!(frame_sources(&frames), [0, 0, 2, 2, 4]);
+        let frames: Vec<DynamicImage> = (0..=MAX_READ_FRAMES as u8).map(frame).collect();
+        assert_eq!(frame_sources(&frames).len(), MAX_READ_FRAMES);
+    }
+
+    #[test]
+    fn svg_size_reads_width_and_height_in_units() {
+        let svg = br#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" width="1in" height="48pt"/>"#;
+        assert_eq!(svg_size(svg), Some((96.0, 64.0)));
+    }
+
+    #[test]
+    fn svg_size_falls_back_to_the_view_box() {
+        let svg = br#"<svg viewBox="0 0 200 100" width="100%"/>"#;
+        assert_eq!(svg_size(svg), Some((200.0, 100.0)));
+        let svg = br#"<svg viewBox="0,0,200,100" width="50"/>"#;
+        assert_eq!(svg_size(svg), Some((50.0, 25.0)));
+        assert_eq!(svg_size(br#"<svg/>"#), None);
+    }
+
+    #[test]
+    fn huge_svg_is_refused_before_drawing() {
+        let svg = br#"<svg width="100000" height="100000"/>"#;
+        let conversion = Conversion {
+            svg_dpi: 96,
+            max_bytes: 256 * MEGABYTE,
+        };
+        let err = Converted::Svg.decode(svg, conversion).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TranscribeError>(),
+            Some(TranscribeError::ImageTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn heif_size_takes_the_largest_ispe() {
+        let mut bytes = vec![0; 8];
+        for (width, height) in [(512u32, 512u32), (4032, 3024)] {
+            bytes.extend_from_slice(&20u32.to_be_bytes());
+            bytes.extend_from_slice(b"ispe");
+            bytes.extend_from_slice(&[0; 4]);
+            bytes.extend_from_slice(&width.to_be_bytes());
+            bytes.extend_from_slice(&height.to_be_bytes());
+        }
+        assert_eq!(heif_size(&bytes), Some((4032, 3024)));
+        assert_eq!(heif_size(b"nothing here"), None);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    backend::OcrBackend,
+    ocr::{Line, Progress},
+    TranscriptionResult,
+};
+use image::DynamicImage;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tracing::{info, warn};
+
+/// Which recognized lines are dropped as noise.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineFilter {
+    /// Lines with fewer characters than this, not counting spaces, are
+    /// dropped. 1 keeps single characters such as "I", bullets and CJK.
+    pub min_chars: usize,
+    /// Lines read with a lower confidence, in `0.0..=1.0`, are dropped. Lines
+    /// of engines that report no confidence are kept.
+    pub min_confidence: f32,
+    /// Regular expressions, a line any of them matches is dropped.
+    pub junk_patterns: Vec<String>,
+    /// Keep the lines that would be dropped, marked with why, to see what the
+    /// filter takes out.
+    pub show_filtered: bool,
+}
+
+impl Default for LineFilter {
+    fn default() -> Self {
+        LineFilter {
+            min_chars: 1,
+            min_confidence: 0.0,
+            // table borders and rules read as bars, dashes and dots
+            junk_patterns: vec![r"^[\s|_~.,:;'`\-]+$".into()],
+            show_filtered: false,
+        }
+    }
+}
+
+/// A [`LineFilter`] with its patterns compiled, ready to apply.
+pub struct CompiledFilter {
+    min_chars: usize,
+    min_confidence: f32,
+    patterns: Vec<Regex>,
+    show_filtered: bool,
+}
+
+impl CompiledFilter {
+    /// Patterns that are not valid regular expressions are left out.
+    pub fn new(filter: &LineFilter) -> Self {
+        let patterns = filter
+            .junk_patterns
+            .iter()
+            .filter(|pattern| !pattern.trim().is_empty())
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    warn!("Ignoring junk pattern {}: {}", pattern, err);
+                    None
+                }
+            })
+            .collect();
+        CompiledFilter {
+            min_chars: filter.min_chars,
+            min_confidence: filter.min_confidence,
+            patterns,
+            show_filtered: filter.show_filtered,
+        }
+    }
+
+    /// Why `line` is dropped, None when it is kept.
+    pub fn reason(&self, line: &Line) -> Option<String> {
+        let chars = line.text.chars().filter(|c| !c.is_whitespace()).count();
+        if chars < self.min_chars.max(1) {
+            return Some(match chars {
+                0 => "empty".into(),
+                _ => format!("{} characters", chars),
+            });
+        }
+        let confidence = line.confidence.or_else(|| line.word_confidence());
+        if let Some(confidence) = confidence.filter(|confidence| *confidence < self.min_confidence)
+        {
+            return Some(format!("{:.0}% confident", confidence * 100.0));
+        }
+        self.patterns
+            .iter()
+            .find(|pattern| pattern.is_match(&line.text))
+            .map(|pattern| format!("matches {}", pattern.as_str()))
+    }
+
+    /// Drop the lines of `result` the filter catches, or mark them when
+    /// showing filtered lines. Returns how many it caught.
+    pub fn apply(&self, result: &mut TranscriptionResult) -> usize {
+        let mut caught = 0;
+        result.lines.retain_mut(|line| {
+            let Some(reason) = self.reason(line) else {
+                return true;
+            };
+            caught += 1;
+            if self.show_filtered {
+                line.text = format!("[filtered, {}] {}", reason, line.text);
+            }
+            self.show_filtered
+        });
+        caught
+    }
+}
+
+/// A backend whose results go through a [`LineFilter`], progress included.
+pub struct FilteredBackend {
+    backend: Box<dyn OcrBackend>,
+    filter: CompiledFilter,
+}
+
+impl FilteredBackend {
+    pub fn new(backend: Box<dyn OcrBackend>, filter: &LineFilter) -> Self {
+        FilteredBackend {
+            backend,
+            filter: CompiledFilter::new(filter),
+        }
+    }
+}
+
+impl OcrBackend for FilteredBackend {
+    fn name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    fn transcribe(&self, image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        self.transcribe_streaming(image, &mut |_| ())
+    }
+
+    fn transcribe_streaming(
+        &self,
+        image: &DynamicImage,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<TranscriptionResult, Box<dyn Error>> {
+        // the lines found are not read yet, only those read are filtered
+        let mut filtered = |mut update: Progress| {
+            if let Progress::Read(result) = &mut update {
+                self.filter.apply(result);
+            }
+            progress(update);
+        };
+        let mut result = self.backend.transcribe_streaming(image, &mut filtered)?;
+        let lines = result.lines.len();
+        let caught = self.filter.apply(&mut result);
+        if caught > 0 {
+            info!("Filtered out {} of {} lines", caught, lines);
+        }
+        Ok(result)
+    }
+
+    fn reads_vertical(&self) -> bool {
+        self.backend.reads_vertical()
+    }
+
+    fn local(&self) -> Option<&dyn OcrBackend> {
+        self.backend.local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::fixtures;
+
+    fn line(text: &str, confidence: Option<f32>) -> Line {
+        Line {
+            confidence,
+            ..fixtures::line(text, fixtures::bounds(0, 0, 100, 16))
+        }
+    }
+
+    fn filter(min_chars: usize, min_confidence: f32) -> CompiledFilter {
+        CompiledFilter::new(&LineFilter {
+            min_chars,
+            min_confidence,
+            ..LineFilter::default()
+        })
+    }
+
+    #[test]
+    fn blank_lines_are_empty_even_with_no_minimum() {
+        let reason = filter(0, 0.0).reason(&line("  \t ", None));
+        assert_eq!(reason.as_deref(), Some("empty"));
+    }
+
+    #[test]
+    fn short_lines_are_dropped_without_counting_spaces() {
+        let filter = filter(3, 0.0);
+        assert_eq!(
+            filter.reason(&line("a b", None)).as_deref(),
+            Some("2 characters")
+        );
+        assert_eq!(filter.reason(&line("a bc", None)), None);
+    }
+
+    #[test]
+    fn unsure_lines_are_dropped_and_those_without_a_confidence_kept() {
+        let filter = filter(1, 0.5);
+        assert_eq!(
+            filter.reason(&line("maybe", Some(0.25))).as_deref(),
+            Some("25% confident")
+        );
+        assert_eq!(filter.reason(&line("surely", Some(0.75))), None);
+        assert_eq!(filter.reason(&line("unknown", None)), None);
+    }
+
+    #[test]
+    fn rules_and_borders_match_the_default_pattern() {
+        let filter = CompiledFilter::new(&LineFilter::default());
+        let reason = filter.reason(&line("|-----|", None)).unwrap();
+        assert!(reason.starts_with("matches "), "{}", reason);
+        assert_eq!(filter.reason(&line("| Total |", None)), None);
+    }
+
+    #[test]
+    fn invalid_and_blank_patterns_are_left_out() {
+        let filter = CompiledFilter::new(&LineFilter {
+            junk_patterns: vec!["(".into(), " ".into(), "^x+$".into()],
+            ..LineFilter::default()
+        });
+        assert_eq!(filter.patterns.len(), 1);
+        assert!(filter.reason(&line("xxx", None)).is_some());
+    }
+}
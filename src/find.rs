@@ -0,0 +1,135 @@
+use crate::locale::tr;
+use imgui::Ui;
+use regex::{NoExpand, Regex, RegexBuilder};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+// Find and replace over the text, `|` to `I` style fixes in one go
+#[derive(Default)]
+pub struct FindBar {
+    find: String,
+    replace: String,
+    // treat `find` as a regular expression and allow $1 style groups in `replace`
+    regex: bool,
+    match_case: bool,
+    // outcome of the last replace all
+    status: String,
+    // the pattern and its matches, compiled and counted again only once the
+    // query, the flags or the text change
+    counted: Option<Counted>,
+}
+
+struct Counted {
+    // the query, regex and match case flags and a hash of the text
+    key: (String, bool, bool, u64),
+    pattern: Result<Regex, regex::Error>,
+    matches: usize,
+}
+
+impl FindBar {
+    fn pattern(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            self.find.clone()
+        } else {
+            regex::escape(&self.find)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.match_case)
+            .build()
+    }
+
+    fn counted(&mut self, text: &str) -> &Counted {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let key = (
+            self.find.clone(),
+            self.regex,
+            self.match_case,
+            hasher.finish(),
+        );
+        match self.counted.take() {
+            Some(counted) if counted.key == key => self.counted.insert(counted),
+            _ => {
+                let pattern = self.pattern();
+                let matches = pattern
+                    .as_ref()
+                    .map_or(0, |pattern| pattern.find_iter(text).count());
+                self.counted.insert(Counted {
+                    key,
+                    pattern,
+                    matches,
+                })
+            }
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, text: &mut String) {
+        let mut changed = ui.input_text(tr("Find"), &mut self.find).build();
+        ui.input_text(tr("Replace with"), &mut self.replace).build();
+        changed |= ui.checkbox(tr("Regex"), &mut self.regex);
+        ui.same_line();
+        changed |= ui.checkbox(tr("Match case"), &mut self.match_case);
+        if changed {
+            self.status.clear();
+        }
+
+        if self.find.is_empty() {
+            return;
+        }
+        let counted = self.counted(text);
+        let matches = counted.matches;
+        let pattern = match &counted.pattern {
+            Ok(pattern) => pattern.clone(),
+            Err(err) => {
+                ui.text_wrapped(format!("{}: {}", tr("Invalid pattern"), err));
+                return;
+            }
+        };
+        let noun = tr(if matches == 1 { "match" } else { "matches" });
+
+        ui.same_line();
+        let _disabled = ui.begin_disabled(matches == 0);
+        if ui.button(tr("Replace all")) {
+            *text = if self.regex {
+                pattern
+                    .replace_all(text, self.replace.as_str())
+                    .into_owned()
+            } else {
+                pattern
+                    .replace_all(text, NoExpand(&self.replace))
+                    .into_owned()
+            };
+            self.status = format!("{} {} {}", tr("Replaced"), matches, noun);
+        }
+        ui.same_line();
+        if self.status.is_empty() {
+            ui.text(format!("{} {}", matches, noun));
+        } else {
+            ui.text(&self.status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_are_counted_again_once_the_query_or_text_changes() {
+        let mut bar = FindBar {
+            find: "a".to_string(),
+            ..FindBar::default()
+        };
+        assert_eq!(bar.counted("banana").matches, 3);
+        assert_eq!(bar.counted("bandana").matches, 3);
+        assert_eq!(bar.counted("Bandana band").matches, 4);
+        bar.match_case = true;
+        bar.find = "B".to_string();
+        assert_eq!(bar.counted("Bandana band").matches, 1);
+        bar.regex = true;
+        bar.find = "(".to_string();
+        assert!(bar.counted("Bandana band").pattern.is_err());
+    }
+}
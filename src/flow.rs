@@ -0,0 +1,43 @@
+use imgui::Ui;
+
+// Lays out words one after the other like wrapped text, so each can be its
+// own item with a color, underline or context menu
+pub struct Flow {
+    wrap_width: f32,
+    space: f32,
+    line_width: f32,
+    // nothing placed on the current line yet
+    line_empty: bool,
+}
+
+impl Flow {
+    pub fn new(ui: &Ui) -> Self {
+        Flow {
+            wrap_width: ui.content_region_avail()[0],
+            space: ui.calc_text_size(" ")[0],
+            line_width: 0.0,
+            line_empty: true,
+        }
+    }
+
+    // move the cursor to where the next word goes, on a new line if it does not fit
+    pub fn place(&mut self, ui: &Ui, word: &str) {
+        let width = ui.calc_text_size(word)[0];
+        if !self.line_empty && self.line_width + self.space + width <= self.wrap_width {
+            ui.same_line_with_spacing(0.0, self.space);
+            self.line_width += self.space;
+        } else {
+            self.line_width = 0.0;
+        }
+        self.line_empty = false;
+        self.line_width += width;
+    }
+
+    // end the current line, leaving an empty one when nothing was placed on it
+    pub fn break_line(&mut self, ui: &Ui) {
+        if self.line_empty {
+            ui.new_line();
+        }
+        self.line_empty = true;
+    }
+}
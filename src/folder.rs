@@ -0,0 +1,155 @@
+use crate::{backend_factory, batch::output_path, config::Config, SharedCache};
+use image::DynamicImage;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+use transcribewizard::{backend::OcrBackend, files, pool::WorkerPool, TranscriptionResult};
+
+// a file is only read once it has not changed for this long, scanners write
+// their images in several goes
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+// files remembered as queued, the ones touched longest ago are forgotten first
+const SEEN_CAPACITY: usize = 1000;
+
+// A file of the watched folder transcribed, its text already written next to it
+pub struct FolderResult {
+    pub text: String,
+    // the first page, for the history thumbnail
+    pub image: DynamicImage,
+    // only for single page files, boxes of several pages do not add up
+    pub transcription: Option<TranscriptionResult>,
+}
+
+// A folder watched for new images and PDFs, each transcribed on a worker pool
+// into a .txt file next to it
+pub struct FolderWatch {
+    pub dir: PathBuf,
+    // dropping it stops the watching
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    pool: WorkerPool,
+    config: Config,
+    // files that changed and when they last did, waiting to settle
+    pending: HashMap<PathBuf, Instant>,
+    // files already queued, so rewriting one does not transcribe it again,
+    // the most recently touched last
+    seen: VecDeque<PathBuf>,
+    results: Receiver<(PathBuf, Result<FolderResult, String>)>,
+    sender: mpsc::Sender<(PathBuf, Result<FolderResult, String>)>,
+}
+
+fn transcribe(
+    backend: &dyn OcrBackend,
+    path: &Path,
+    config: &Config,
+) -> Result<FolderResult, String> {
+    let pages = files::transcribe_file(backend, path, &config.preprocessing, config.conversion())
+        .map_err(|err| err.to_string())?;
+    let texts: Vec<String> = pages
+        .iter()
+        .map(|(_, transcription)| config.render(transcription))
+        .collect();
+    let text = files::join_pages(path, &texts);
+    let output = output_path(path);
+    fs::write(&output, &text)
+        .map_err(|err| format!("Failed to write {}: {}", output.display(), err))?;
+    let single = pages.len() == 1;
+    let (image, transcription) = pages
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("No pages in {}", path.display()))?;
+    Ok(FolderResult {
+        text,
+        image,
+        transcription: single.then_some(transcription),
+    })
+}
+
+impl FolderWatch {
+    // watch `dir`, files already in it are left alone
+    pub fn start(config: &Config, cache: &SharedCache, dir: PathBuf) -> Result<Self, String> {
+        let factory = backend_factory(config, cache).map_err(|err| err.to_string())?;
+        let (event_sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(event_sender)
+            .map_err(|err| format!("Failed to watch {}: {}", dir.display(), err))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|err| format!("Failed to watch {}: {}", dir.display(), err))?;
+        info!("Watching {} for new files", dir.display());
+        let (sender, results) = mpsc::channel();
+        Ok(FolderWatch {
+            dir,
+            _watcher: watcher,
+            events,
+            pool: WorkerPool::new(config.ocr_threads, factory),
+            config: config.clone(),
+            pending: HashMap::new(),
+            seen: VecDeque::new(),
+            results,
+            sender,
+        })
+    }
+
+    // queue the files that settled and return the transcriptions finished since
+    // the last call
+    pub fn poll(&mut self) -> Vec<(PathBuf, Result<FolderResult, String>)> {
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    for path in event.paths {
+                        if !files::is_supported(&path) {
+                            continue;
+                        }
+                        match self.seen.iter().position(|seen| *seen == path) {
+                            Some(index) => {
+                                // touched again, so forgotten last
+                                if let Some(seen) = self.seen.remove(index) {
+                                    self.seen.push_back(seen);
+                                }
+                            }
+                            None => {
+                                self.pending.insert(path, Instant::now());
+                            }
+                        }
+                    }
+                }
+                Ok(_) => (),
+                Err(err) => warn!("Error watching {}: {}", self.dir.display(), err),
+            }
+        }
+
+        let settled: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in settled {
+            self.pending.remove(&path);
+            // moved away or deleted again before it settled
+            if !path.is_file() {
+                continue;
+            }
+            if self.seen.len() == SEEN_CAPACITY {
+                self.seen.pop_front();
+            }
+            self.seen.push_back(path.clone());
+            let (sender, config) = (self.sender.clone(), self.config.clone());
+            self.pool.submit(move |backend| {
+                let result = backend
+                    .map_err(str::to_string)
+                    .and_then(|backend| transcribe(backend, &path, &config));
+                let _ = sender.send((path, result));
+            });
+        }
+
+        self.results.try_iter().collect()
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{llm::ChatClient, ocr::TranscriptionResult};
+use image::DynamicImage;
+use std::error::Error;
+
+/// System prompt of the formula requests.
+pub const LATEX_PROMPT: &str = "The image shows a mathematical formula. Reply with the LaTeX \
+    for it only, without dollar signs, code fences or explanations.";
+
+// characters that are rare in prose but common in formulas as OCR reads them
+const MATH_SYMBOLS: &str =
+    "=+-*/^_<>|()[]{}√∑∏∫∂∞≈≠≤≥±×÷·∈∉⊂⊆∪∩∀∃→⇒⇔′αβγδεζηθικλμνξπρστυφχψωΓΔΘΛΞΠΣΦΨΩ";
+
+// share of the characters of a line that must be digits, symbols or lone
+// letters for it to count as a formula
+const MATH_SHARE: f32 = 0.5;
+
+// pixels around a line kept when cropping it, models do badly with clipped glyphs
+const PADDING: i32 = 6;
+
+/// Whether a recognized line looks like a formula rather than prose: mostly
+/// symbols, digits and single letter variables, with at least one operator.
+pub fn is_formula(text: &str) -> bool {
+    let mut math = 0;
+    let mut total = 0;
+    let mut operators = 0;
+    for word in text.split_whitespace() {
+        let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+        for c in word.chars() {
+            total += 1;
+            if MATH_SYMBOLS.contains(c) {
+                operators += usize::from("=+^_<>√∑∏∫≈≠≤≥±×÷".contains(c));
+                math += 1;
+            } else if c.is_numeric() || (c.is_alphabetic() && letters <= 2) {
+                math += 1;
+            }
+        }
+    }
+    total > 0 && operators > 0 && math as f32 >= total as f32 * MATH_SHARE
+}
+
+/// Replace every line of `result` that looks like a formula with the LaTeX
+/// the model reads from its part of `image`, between `$` signs. `image` is the
+/// one the boxes of `result` refer to. Returns how many lines were replaced.
+pub fn convert(
+    client: &ChatClient,
+    image: &DynamicImage,
+    result: &mut TranscriptionResult,
+) -> Result<usize, Box<dyn Error>> {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let mut converted = 0;
+    for line in &mut result.lines {
+        if !is_formula(&line.text) {
+            continue;
+        }
+        let left = (line.bounds.left - PADDING).clamp(0, width);
+        let top = (line.bounds.top - PADDING).clamp(0, height);
+        let right = (line.bounds.right + PADDING).clamp(0, width);
+        let bottom = (line.bounds.bottom + PADDING).clamp(0, height);
+        if right <= left || bottom <= top {
+            continue;
+        }
+        let crop = image.crop_imm(
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        );
+        let latex = client.complete_image(LATEX_PROMPT, &crop)?;
+        // models tend to wrap it anyway
+        let latex = latex
+            .trim()
+            .trim_start_matches("```latex")
+            .trim_matches('`')
+            .trim()
+            .trim_matches('$')
+            .trim();
+        if !latex.is_empty() {
+            line.text = format!("${}$", latex);
+            converted += 1;
+        }
+    }
+    Ok(converted)
+}
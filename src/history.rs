@@ -0,0 +1,122 @@
+use crate::texture::Texture;
+use image::{DynamicImage, RgbaImage};
+use std::{collections::VecDeque, path::PathBuf, sync::Arc};
+#[cfg(feature = "archive")]
+use transcribewizard::archive::Archive;
+use transcribewizard::TranscriptionResult;
+
+// number of transcriptions kept in the history panel
+pub const HISTORY_CAPACITY: usize = 20;
+
+// longest side of a history thumbnail in pixels
+const THUMBNAIL_SIZE: u32 = 96;
+
+pub struct HistoryEntry {
+    pub text: String,
+    // without its image, see `kept`
+    pub transcription: Option<TranscriptionResult>,
+    pub thumbnail: Option<Texture>,
+    // pixels of the thumbnail, kept to save the session
+    pub thumbnail_image: Option<RgbaImage>,
+}
+
+// `transcription` as an entry keeps it, without the image it was read from so
+// the history does not hold on to a full screenshot next to each thumbnail
+pub fn kept(transcription: TranscriptionResult) -> TranscriptionResult {
+    TranscriptionResult {
+        image: None,
+        ..transcription
+    }
+}
+
+// The last transcriptions, newest first
+pub struct History {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+    // where every new transcription is kept for good, when enabled
+    #[cfg(feature = "archive")]
+    archive: Option<Archive>,
+    // directory of the daily journal new transcriptions are written to, when enabled
+    journal: Option<PathBuf>,
+    // entries pushed since the start, to tell when a new one came in
+    pushed: usize,
+    // the image the newest text was recognized from, for comparing engines on it
+    last_image: Option<Arc<DynamicImage>>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            #[cfg(feature = "archive")]
+            archive: None,
+            journal: None,
+            pushed: 0,
+            last_image: None,
+        }
+    }
+
+    #[cfg(feature = "archive")]
+    pub fn archive(&self) -> Option<&Archive> {
+        self.archive.as_ref()
+    }
+
+    #[cfg(feature = "archive")]
+    pub fn set_archive(&mut self, archive: Option<Archive>) {
+        self.archive = archive;
+    }
+
+    pub fn journal(&self) -> Option<&PathBuf> {
+        self.journal.as_ref()
+    }
+
+    pub fn set_journal(&mut self, journal: Option<PathBuf>) {
+        self.journal = journal;
+    }
+
+    // record a transcription, returning the oldest entry if it no longer fits
+    pub fn push(&mut self, entry: HistoryEntry) -> Option<HistoryEntry> {
+        self.entries.push_front(entry);
+        self.pushed += 1;
+        if self.entries.len() > self.capacity {
+            return self.entries.pop_back();
+        }
+        None
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter()
+    }
+
+    // the newest entry, for results that are finished after it was recorded
+    pub fn latest_mut(&mut self) -> Option<&mut HistoryEntry> {
+        self.entries.front_mut()
+    }
+
+    pub fn last_image(&self) -> Option<&DynamicImage> {
+        self.last_image.as_deref()
+    }
+
+    pub fn set_last_image(&mut self, image: Option<Arc<DynamicImage>>) {
+        self.last_image = image;
+    }
+
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+
+    // take every entry out, their thumbnails are left to the caller to destroy
+    pub fn clear(&mut self) -> Vec<HistoryEntry> {
+        self.entries.drain(..).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// Scale an image down to fit in the history panel
+pub fn thumbnail(image: &DynamicImage) -> RgbaImage {
+    image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8()
+}
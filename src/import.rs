@@ -0,0 +1,420 @@
+use crate::ocr::{Bounds, Line, RotatedBounds, TranscriptionResult, Word};
+use quick_xml::{
+    escape::resolve_predefined_entity,
+    events::{BytesStart, Event},
+    Reader, XmlVersion,
+};
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Extensions of the hOCR and ALTO files [`load`] reads.
+pub const EXTENSIONS: &[&str] = &["hocr", "html", "htm", "xml"];
+
+// hOCR classes of a line of text, which the words of it are inside
+const HOCR_LINES: [&str; 5] = [
+    "ocr_line",
+    "ocrx_line",
+    "ocr_caption",
+    "ocr_header",
+    "ocr_textfloat",
+];
+
+// elements of HTML without an end
+const HTML_VOID: [&str; 6] = ["br", "hr", "img", "input", "link", "meta"];
+
+/// A transcription read back from a file an OCR run was saved as.
+pub struct Imported {
+    pub transcription: TranscriptionResult,
+    /// The image the file says it was read from, as it names it.
+    pub image: Option<String>,
+}
+
+/// Read an hOCR file, as this app, Tesseract and OCRmyPDF write them, or an
+/// ALTO XML file, as libraries and archives keep scanned pages in.
+///
+/// Only the first page is read. Boxes are in the units the file has them,
+/// ALTO in tenths of a millimetre or twelve-hundredths of an inch has to be
+/// [`TranscriptionResult::rescale`]d onto the image.
+pub fn load(path: &Path) -> Result<Imported, Box<dyn Error>> {
+    let xml = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    parse(&xml).map_err(|err| format!("Failed to read {}: {}", path.display(), err).into())
+}
+
+/// Read hOCR or ALTO XML, told apart by the root element.
+pub fn parse(xml: &str) -> Result<Imported, Box<dyn Error>> {
+    let mut reader = Reader::from_str(xml);
+    // hOCR is HTML, which does not always close what it opens
+    reader.config_mut().check_end_names = false;
+    reader.config_mut().allow_unmatched_ends = true;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => {
+                return match element.local_name().as_ref() {
+                    "alto" => parse_alto(reader),
+                    _ => parse_hocr(reader),
+                };
+            }
+            Event::Eof => return Err("The file is empty".into()),
+            _ => (),
+        }
+    }
+}
+
+/// The image an hOCR or ALTO file names, looked for next to it, then an image
+/// with the same name as the file.
+pub fn find_image(path: &Path, named: Option<&str>) -> Option<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    if let Some(named) = named {
+        let named = Path::new(named);
+        let candidates = [named.to_path_buf(), dir.join(named.file_name()?)];
+        if let Some(found) = candidates.into_iter().find(|path| path.is_file()) {
+            return Some(found);
+        }
+    }
+    crate::files::IMAGE_EXTENSIONS
+        .iter()
+        .map(|extension| path.with_extension(extension))
+        .find(|path| path.is_file())
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    let attribute = element.try_get_attribute(name).ok()??;
+    attribute
+        .normalized_value(XmlVersion::Implicit1_0)
+        .ok()
+        .map(|value| value.into_owned())
+}
+
+// text entities such as &amp; come apart from the text around them
+fn push_text(event: &Event, text: &mut String) {
+    match event {
+        Event::Text(content) => text.push_str(&content.xml10_content()),
+        Event::CData(content) => text.push_str(&content.xml10_content()),
+        Event::GeneralRef(reference) => match reference.resolve_char_ref() {
+            Ok(Some(character)) => text.push(character),
+            _ => text.push_str(resolve_predefined_entity(reference).unwrap_or_default()),
+        },
+        _ => (),
+    }
+}
+
+fn bounds(left: i32, top: i32, right: i32, bottom: i32) -> Bounds {
+    Bounds {
+        left,
+        top,
+        right,
+        bottom,
+    }
+}
+
+// the box around every word, for lines that have none of their own
+fn enclosing(words: &[Word]) -> Option<Bounds> {
+    Some(bounds(
+        words.iter().map(|word| word.bounds.left).min()?,
+        words.iter().map(|word| word.bounds.top).min()?,
+        words.iter().map(|word| word.bounds.right).max()?,
+        words.iter().map(|word| word.bounds.bottom).max()?,
+    ))
+}
+
+fn line(words: Vec<Word>, bounds: Bounds, confidence: Option<f32>, text: String) -> Line {
+    // a line without words of its own is read as the words its text has
+    let text = if words.is_empty() {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    Line {
+        text,
+        bounds,
+        rotated_bounds: RotatedBounds::from(bounds),
+        confidence,
+        words,
+    }
+}
+
+// the properties of an hOCR title such as `bbox 0 0 10 10; x_wconf 95`
+fn hocr_property<'a>(title: &'a str, name: &str) -> Option<&'a str> {
+    title
+        .split(';')
+        .map(str::trim)
+        .find_map(|property| property.strip_prefix(name)?.strip_prefix(' '))
+        .map(str::trim)
+}
+
+fn hocr_bounds(title: &str) -> Option<Bounds> {
+    let numbers: Vec<i32> = hocr_property(title, "bbox")?
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match numbers[..] {
+        [left, top, right, bottom] => Some(bounds(left, top, right, bottom)),
+        _ => None,
+    }
+}
+
+fn hocr_confidence(title: &str) -> Option<f32> {
+    let confidence: f32 = hocr_property(title, "x_wconf")?.parse().ok()?;
+    Some((confidence / 100.0).clamp(0.0, 1.0))
+}
+
+// What an element of hOCR opened
+enum HocrElement {
+    Page,
+    Line,
+    Word,
+    Other,
+}
+
+// a line being read and the word being read in it
+struct HocrLine {
+    title: String,
+    words: Vec<Word>,
+    text: String,
+    word: Option<(String, String)>,
+}
+
+fn parse_hocr(mut reader: Reader<&[u8]>) -> Result<Imported, Box<dyn Error>> {
+    let mut open: Vec<HocrElement> = Vec::new();
+    let mut transcription = TranscriptionResult {
+        width: 0,
+        height: 0,
+        lines: Vec::new(),
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    };
+    let mut image = None;
+    let mut pages = 0;
+    let mut current: Option<HocrLine> = None;
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            // HTML never closes these
+            Event::Start(element) if HTML_VOID.contains(&element.local_name().as_ref()) => (),
+            Event::Start(element) => {
+                let class = attribute(element, "class").unwrap_or_default();
+                let title = attribute(element, "title").unwrap_or_default();
+                let classes: Vec<&str> = class.split_whitespace().collect();
+                let kind = if classes.contains(&"ocr_page") {
+                    pages += 1;
+                    if pages == 1 {
+                        if let Some(page) = hocr_bounds(&title) {
+                            transcription.width = page.right.max(0) as u32;
+                            transcription.height = page.bottom.max(0) as u32;
+                        }
+                        image = hocr_property(&title, "image")
+                            .map(|name| name.trim_matches('"').to_string());
+                    }
+                    HocrElement::Page
+                } else if pages > 1 {
+                    HocrElement::Other
+                } else if HOCR_LINES.iter().any(|line| classes.contains(line)) {
+                    current = Some(HocrLine {
+                        title,
+                        words: Vec::new(),
+                        text: String::new(),
+                        word: None,
+                    });
+                    HocrElement::Line
+                } else if classes.contains(&"ocrx_word") {
+                    if let Some(line) = &mut current {
+                        line.word = Some((title, String::new()));
+                    }
+                    HocrElement::Word
+                } else {
+                    HocrElement::Other
+                };
+                open.push(kind);
+            }
+            Event::End(_) => match open.pop() {
+                Some(HocrElement::Word) => {
+                    let Some(line) = &mut current else {
+                        continue;
+                    };
+                    let Some((title, text)) = line.word.take() else {
+                        continue;
+                    };
+                    let text = text.trim().to_string();
+                    let Some(bounds) = hocr_bounds(&title).filter(|_| !text.is_empty()) else {
+                        continue;
+                    };
+                    line.words.push(Word {
+                        text,
+                        bounds,
+                        rotated_bounds: RotatedBounds::from(bounds),
+                        confidence: hocr_confidence(&title),
+                        alternatives: Vec::new(),
+                    });
+                }
+                Some(HocrElement::Line) => {
+                    let Some(read) = current.take() else {
+                        continue;
+                    };
+                    let confidence = hocr_confidence(&read.title);
+                    let Some(bounds) = hocr_bounds(&read.title).or_else(|| enclosing(&read.words))
+                    else {
+                        continue;
+                    };
+                    let read = line(read.words, bounds, confidence, read.text);
+                    if !read.text.is_empty() {
+                        transcription.lines.push(read);
+                    }
+                }
+                _ => (),
+            },
+            Event::Eof => break,
+            event => {
+                if let Some(line) = &mut current {
+                    match &mut line.word {
+                        Some((_, text)) => push_text(event, text),
+                        None => push_text(event, &mut line.text),
+                    }
+                }
+            }
+        }
+    }
+    if pages == 0 {
+        return Err("No hOCR page found".into());
+    }
+    Ok(Imported {
+        transcription,
+        image,
+    })
+}
+
+fn alto_number(element: &BytesStart, name: &str) -> Option<f32> {
+    attribute(element, name)?.trim().parse().ok()
+}
+
+fn alto_bounds(element: &BytesStart) -> Option<Bounds> {
+    let left = alto_number(element, "HPOS")?;
+    let top = alto_number(element, "VPOS")?;
+    let right = left + alto_number(element, "WIDTH")?;
+    let bottom = top + alto_number(element, "HEIGHT")?;
+    Some(bounds(
+        left.round() as i32,
+        top.round() as i32,
+        right.round() as i32,
+        bottom.round() as i32,
+    ))
+}
+
+// a String of ALTO as a word, its WC confidence is already in 0..=1
+fn alto_word(element: &BytesStart) -> Option<Word> {
+    let text = attribute(element, "CONTENT")?.trim().to_string();
+    let bounds = alto_bounds(element)?;
+    (!text.is_empty()).then(|| Word {
+        text,
+        bounds,
+        rotated_bounds: RotatedBounds::from(bounds),
+        confidence: alto_number(element, "WC").map(|confidence| confidence.clamp(0.0, 1.0)),
+        alternatives: Vec::new(),
+    })
+}
+
+fn parse_alto(mut reader: Reader<&[u8]>) -> Result<Imported, Box<dyn Error>> {
+    let mut transcription = TranscriptionResult {
+        width: 0,
+        height: 0,
+        lines: Vec::new(),
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    };
+    let mut image: Option<String> = None;
+    let mut pages = 0;
+    // the line, word and other reading being read, and the file name of the image
+    let mut current: Option<(Option<Bounds>, Vec<Word>)> = None;
+    let mut word: Option<Word> = None;
+    let mut alternative: Option<String> = None;
+    let mut file_name: Option<String> = None;
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Start(element) | Event::Empty(element) => {
+                let empty = matches!(event, Event::Empty(_));
+                match element.local_name().as_ref() {
+                    "Page" => {
+                        pages += 1;
+                        if pages == 1 {
+                            let size = |name| alto_number(element, name).unwrap_or(0.0);
+                            transcription.width = size("WIDTH").round() as u32;
+                            transcription.height = size("HEIGHT").round() as u32;
+                        }
+                    }
+                    _ if pages > 1 => (),
+                    "fileName" if !empty => file_name = Some(String::new()),
+                    "TextLine" if !empty => current = Some((alto_bounds(element), Vec::new())),
+                    "String" => match (&mut current, alto_word(element)) {
+                        (Some((_, words)), Some(read)) if empty => words.push(read),
+                        (Some(_), read) => word = read,
+                        _ => (),
+                    },
+                    "ALTERNATIVE" if !empty && word.is_some() => alternative = Some(String::new()),
+                    _ => (),
+                }
+            }
+            Event::End(element) => match element.local_name().as_ref() {
+                "fileName" => {
+                    image = file_name.take().map(|name| name.trim().to_string());
+                }
+                "ALTERNATIVE" => {
+                    if let (Some(read), Some(alternative)) = (&mut word, alternative.take()) {
+                        let alternative = alternative.trim().to_string();
+                        if !alternative.is_empty() {
+                            read.alternatives.push(alternative);
+                        }
+                    }
+                }
+                "String" => {
+                    if let (Some((_, words)), Some(read)) = (&mut current, word.take()) {
+                        words.push(read);
+                    }
+                }
+                "TextLine" => {
+                    let Some((bounds, words)) = current.take() else {
+                        continue;
+                    };
+                    let Some(bounds) = bounds.or_else(|| enclosing(&words)) else {
+                        continue;
+                    };
+                    if !words.is_empty() {
+                        transcription
+                            .lines
+                            .push(line(words, bounds, None, String::new()));
+                    }
+                }
+                _ => (),
+            },
+            Event::Eof => break,
+            event => {
+                if let Some(text) = alternative.as_mut().or(file_name.as_mut()) {
+                    push_text(event, text);
+                }
+            }
+        }
+    }
+    if pages == 0 {
+        return Err("No ALTO page found".into());
+    }
+    // lines of ALTO have no confidence of their own, the words' average stands in
+    for read in &mut transcription.lines {
+        read.confidence = read.word_confidence();
+    }
+    Ok(Imported {
+        transcription,
+        image: image.filter(|name| !name.is_empty()),
+    })
+}
@@ -0,0 +1,349 @@
+use crate::tray::TrayCommand;
+use std::{
+    error::Error,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+use tracing::{error, info, warn};
+
+// longest a command waits for the running instance to answer, reading a large
+// PDF takes a while
+const REPLY_TIMEOUT: Duration = Duration::from_secs(300);
+// longest the commands that read nothing wait, an instance that takes longer
+// is busy and the start gives up. Also how long the running instance waits
+// for a command to come in
+const QUICK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// What scripts and desktop shortcuts can have the running instance do, one
+// line each on its socket
+pub enum Command {
+    // transcribe the clipboard, answered with the text
+    OcrClipboard,
+    // transcribe an image or PDF file, answered with the text
+    OcrFile(PathBuf),
+    Show,
+    ToggleWatch,
+    Quit,
+}
+
+impl Command {
+    // `ocr-clipboard`, `ocr-file PATH`, `show`, `watch` or `quit`
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        Ok(match name {
+            "ocr-clipboard" => Command::OcrClipboard,
+            "ocr-file" if argument.is_empty() => return Err("ocr-file needs a path".into()),
+            "ocr-file" => Command::OcrFile(PathBuf::from(argument)),
+            "show" => Command::Show,
+            "watch" => Command::ToggleWatch,
+            "quit" => Command::Quit,
+            other => return Err(format!("Unknown command: {}", other)),
+        })
+    }
+
+    // how long the client waits for the answer, the running instance reads
+    // the text it answers with before it does
+    fn timeout(&self) -> Duration {
+        match self {
+            Command::OcrClipboard | Command::OcrFile(_) => REPLY_TIMEOUT + QUICK_TIMEOUT,
+            Command::Show | Command::ToggleWatch | Command::Quit => QUICK_TIMEOUT,
+        }
+    }
+
+    // the line sent for the command, files by their full path as the running
+    // instance runs in another directory
+    fn line(&self) -> String {
+        match self {
+            Command::OcrClipboard => "ocr-clipboard".into(),
+            Command::OcrFile(path) => {
+                let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+                format!("ocr-file {}", path.display())
+            }
+            Command::Show => "show".into(),
+            Command::ToggleWatch => "watch".into(),
+            Command::Quit => "quit".into(),
+        }
+    }
+}
+
+impl From<TrayCommand> for Command {
+    fn from(command: TrayCommand) -> Self {
+        match command {
+            TrayCommand::Transcribe => Command::OcrClipboard,
+            TrayCommand::ToggleWatch => Command::ToggleWatch,
+            TrayCommand::ShowWindow => Command::Show,
+            TrayCommand::Quit => Command::Quit,
+        }
+    }
+}
+
+// Where the answer to a command that came in on the socket goes, the client
+// waits for it
+pub struct Reply(Sender<Result<String, String>>);
+
+impl Reply {
+    // answer the client with the text or what went wrong
+    pub fn answer(self, result: Result<String, String>) {
+        let _ = self.0.send(result);
+    }
+}
+
+// The socket of the first instance, commands for it arrive through `poll`
+pub struct Instance {
+    requests: Receiver<(Command, Reply)>,
+    #[cfg(unix)]
+    socket: PathBuf,
+    #[cfg(not(unix))]
+    port_file: PathBuf,
+}
+
+enum Claim {
+    // no other instance runs, this one listens for commands
+    First(Instance),
+    // another instance runs and answered the command with this
+    Running(Result<String, String>),
+}
+
+impl Instance {
+    pub fn poll(&self) -> impl Iterator<Item = (Command, Reply)> + '_ {
+        self.requests.try_iter()
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        // the next start finds no socket to send to and becomes the first
+        #[cfg(unix)]
+        let path = &self.socket;
+        #[cfg(not(unix))]
+        let path = &self.port_file;
+        if let Err(err) = std::fs::remove_file(path) {
+            warn!("Error removing {}: {}", path.display(), err);
+        }
+    }
+}
+
+// read one command line off `stream`, hand it to the main loop and write the
+// answer back, `ok` or the text for the commands that read it
+fn serve<S: Read + Write>(stream: S, token: &str, requests: &Sender<(Command, Reply)>) {
+    let mut reader = BufReader::new(stream);
+    let mut read_line = || {
+        let mut line = String::new();
+        reader.read_line(&mut line).map(|_| line)
+    };
+    let answer = (|| {
+        if !token.is_empty() && read_line().map_err(|err| err.to_string())?.trim() != token {
+            return Err("Wrong token".to_string());
+        }
+        let line = read_line().map_err(|err| err.to_string())?;
+        info!("Command from another instance: {}", line.trim());
+        let command = Command::parse(&line)?;
+        let (reply, answer) = mpsc::channel();
+        requests
+            .send((command, Reply(reply)))
+            .map_err(|_| "The window is closing".to_string())?;
+        answer
+            .recv_timeout(REPLY_TIMEOUT)
+            .map_err(|err| format!("No answer: {}", err))?
+    })();
+    let response = match answer {
+        Ok(text) => format!("ok\n{}", text),
+        Err(err) => format!("error\n{}", err),
+    };
+    if let Err(err) = reader.get_mut().write_all(response.as_bytes()) {
+        error!("Error answering command: {}", err);
+    }
+}
+
+// why the running instance took a command and gave no answer
+fn unanswered(err: io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+            "The running instance is busy, try again once it is done".to_string()
+        }
+        _ => format!("The running instance did not answer: {}", err),
+    }
+}
+
+// `ok` or `error`, then the text or the error
+fn parse_response(response: &str) -> Result<String, String> {
+    let (status, text) = response.split_once('\n').unwrap_or((response, ""));
+    match status {
+        "ok" => Ok(text.to_string()),
+        _ => Err(text.to_string()),
+    }
+}
+
+// A directory only this user can get into, so other users can't send commands
+// to the socket in it. Made when missing, refused when someone else owns it or
+// can get in
+#[cfg(unix)]
+fn socket_dir() -> Result<PathBuf, Box<dyn Error>> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    // SAFETY: getuid has no preconditions and can't fail
+    let uid = unsafe { libc::getuid() };
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir.join("transcribewizard"),
+        None => std::env::temp_dir().join(format!("transcribewizard-{}", uid)),
+    };
+    if let Err(err) = std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        if err.kind() != std::io::ErrorKind::AlreadyExists {
+            return Err(format!("Failed to create {}: {}", dir.display(), err).into());
+        }
+    }
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(format!("{} is not private to this user", dir.display()).into());
+    }
+    Ok(dir)
+}
+
+// The instance of this start, or the exit code of a start that had
+// `command` (showing the window without one) run by the instance already
+// running and is done, printing what it answered
+pub fn start(command: Option<&Command>) -> Result<Option<Instance>, ExitCode> {
+    match claim(command.unwrap_or(&Command::Show)) {
+        Ok(Claim::First(instance)) => Ok(Some(instance)),
+        Ok(Claim::Running(Ok(text))) => {
+            if !text.is_empty() {
+                println!("{}", text);
+            }
+            Err(ExitCode::SUCCESS)
+        }
+        Ok(Claim::Running(Err(err))) => {
+            error!("Error running command in the running instance: {}", err);
+            Err(ExitCode::FAILURE)
+        }
+        Err(err) => {
+            error!("Error listening for commands: {}", err);
+            Ok(None)
+        }
+    }
+}
+
+// Become the first instance, or send `command` to the one already running.
+// A socket left behind by an instance that crashed is taken over, an instance
+// that takes the command but doesn't answer in time is busy, and this start
+// fails instead of opening a second window
+#[cfg(unix)]
+fn claim(command: &Command) -> Result<Claim, Box<dyn Error>> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+    let socket = socket_dir()?.join("transcribewizard.sock");
+    if let Ok(mut stream) = UnixStream::connect(&socket) {
+        let mut response = String::new();
+        let sent = stream
+            .set_read_timeout(Some(command.timeout()))
+            .and_then(|_| stream.set_write_timeout(Some(QUICK_TIMEOUT)))
+            .and_then(|_| stream.write_all(format!("{}\n", command.line()).as_bytes()))
+            .and_then(|_| stream.shutdown(std::net::Shutdown::Write))
+            .and_then(|_| stream.read_to_string(&mut response));
+        return Ok(Claim::Running(
+            sent.map_err(unanswered)
+                .and_then(|_| parse_response(&response)),
+        ));
+    }
+    if socket.exists() {
+        std::fs::remove_file(&socket)?;
+    }
+    let listener = UnixListener::bind(&socket)
+        .map_err(|err| format!("Failed to listen on {}: {}", socket.display(), err))?;
+    let (sender, requests) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    // a client that connects and sends nothing would keep
+                    // its thread waiting for good
+                    if let Err(err) = stream
+                        .set_read_timeout(Some(QUICK_TIMEOUT))
+                        .and_then(|_| stream.set_write_timeout(Some(QUICK_TIMEOUT)))
+                    {
+                        error!("Error accepting command: {}", err);
+                        continue;
+                    }
+                    let sender = sender.clone();
+                    thread::spawn(move || serve(stream, "", &sender));
+                }
+                Err(err) => error!("Error accepting command: {}", err),
+            }
+        }
+    });
+    info!("Listening for commands on {}", socket.display());
+    Ok(Claim::First(Instance { requests, socket }))
+}
+
+// Windows has no Unix sockets for std, commands come over loopback TCP on a
+// port written to a file of the user's, with a token only its reader knows
+#[cfg(not(unix))]
+fn claim(command: &Command) -> Result<Claim, Box<dyn Error>> {
+    use std::net::TcpListener;
+    let port_file = dirs::data_local_dir()
+        .ok_or("No local data directory")?
+        .join("transcribewizard")
+        .join("instance");
+    if let Some((mut stream, token)) = connect(&port_file) {
+        let mut response = String::new();
+        let sent = stream
+            .set_read_timeout(Some(command.timeout()))
+            .and_then(|_| stream.set_write_timeout(Some(QUICK_TIMEOUT)))
+            .and_then(|_| stream.write_all(format!("{}\n{}\n", token, command.line()).as_bytes()))
+            .and_then(|_| stream.shutdown(std::net::Shutdown::Write))
+            .and_then(|_| stream.read_to_string(&mut response));
+        return Ok(Claim::Running(
+            sent.map_err(unanswered)
+                .and_then(|_| parse_response(&response)),
+        ));
+    }
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let mut bytes = [0; 16];
+    getrandom::fill(&mut bytes).map_err(|err| format!("Failed to make a token: {}", err))?;
+    let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    if let Some(dir) = port_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let port = listener.local_addr()?.port();
+    std::fs::write(&port_file, format!("{}\n{}", port, token))?;
+    let (sender, requests) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    // a client that connects and sends nothing would keep
+                    // its thread waiting for good
+                    if let Err(err) = stream
+                        .set_read_timeout(Some(QUICK_TIMEOUT))
+                        .and_then(|_| stream.set_write_timeout(Some(QUICK_TIMEOUT)))
+                    {
+                        error!("Error accepting command: {}", err);
+                        continue;
+                    }
+                    let (sender, token) = (sender.clone(), token.clone());
+                    thread::spawn(move || serve(stream, &token, &sender));
+                }
+                Err(err) => error!("Error accepting command: {}", err),
+            }
+        }
+    });
+    info!("Listening for commands on port {}", port);
+    Ok(Claim::First(Instance {
+        requests,
+        port_file,
+    }))
+}
+
+// the running instance and its token, None when there is none
+#[cfg(not(unix))]
+fn connect(port_file: &std::path::Path) -> Option<(std::net::TcpStream, String)> {
+    let contents = std::fs::read_to_string(port_file).ok()?;
+    let (port, token) = contents.split_once('\n')?;
+    let port: u16 = port.trim().parse().ok()?;
+    let address = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let stream = std::net::TcpStream::connect_timeout(&address, QUICK_TIMEOUT).ok()?;
+    Some((stream, token.trim().to_string()))
+}
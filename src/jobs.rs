@@ -0,0 +1,158 @@
+use crate::locale::tr;
+use imgui::{Condition, Ui};
+use std::{
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+use transcribewizard::job::JobControl;
+
+enum JobUpdate<T> {
+    Progress(f32),
+    Done(Result<T, String>),
+}
+
+// A long job on a thread of its own, such as transcribing a video, with how
+// far it got and the control to pause or cancel it from the Jobs window
+pub struct Job<T> {
+    pub name: String,
+    pub control: JobControl,
+    // fraction done, None while the job cannot tell
+    pub progress: Option<f32>,
+    updates: Receiver<JobUpdate<T>>,
+}
+
+impl<T: Send + 'static> Job<T> {
+    // run `work`, which reports the fraction done through the callback it gets
+    pub fn spawn(
+        name: String,
+        work: impl FnOnce(&JobControl, Box<dyn FnMut(f32) + Send>) -> Result<T, String> + Send + 'static,
+    ) -> Self {
+        let control = JobControl::default();
+        let (sender, updates) = mpsc::channel();
+        thread::spawn({
+            let control = control.clone();
+            move || {
+                let progress_sender = sender.clone();
+                let on_progress = Box::new(move |fraction| {
+                    let _ = progress_sender.send(JobUpdate::Progress(fraction));
+                });
+                let result = work(&control, on_progress);
+                let _ = sender.send(JobUpdate::Done(result));
+            }
+        });
+        Job {
+            name,
+            control,
+            progress: None,
+            updates,
+        }
+    }
+
+    // the result once the job is done, the progress is kept up to date meanwhile
+    pub fn poll(&mut self) -> Option<Result<T, String>> {
+        loop {
+            match self.updates.try_recv() {
+                Ok(JobUpdate::Progress(fraction)) => self.progress = Some(fraction),
+                Ok(JobUpdate::Done(result)) => return Some(result),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => return Some(Err("worker stopped".into())),
+            }
+        }
+    }
+
+    pub fn row(&self) -> JobRow<'_> {
+        JobRow {
+            name: &self.name,
+            progress: self.progress,
+            control: &self.control,
+        }
+    }
+}
+
+// Work handed off to threads of their own that nothing waits for, such as
+// posting a result to a webhook, with what went wrong kept until asked for
+pub struct Background<E> {
+    sender: Sender<E>,
+    receiver: Receiver<E>,
+}
+
+impl<E> Default for Background<E> {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Background { sender, receiver }
+    }
+}
+
+impl<E: Send + 'static> Background<E> {
+    // run `work` on a thread of its own, keeping its error
+    pub fn spawn(&self, work: impl FnOnce() -> Result<(), E> + Send + 'static) {
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            if let Err(err) = work() {
+                let _ = sender.send(err);
+            }
+        });
+    }
+
+    // keep an error found before there was anything to run
+    pub fn fail(&self, err: E) {
+        let _ = self.sender.send(err);
+    }
+
+    // where work run elsewhere, on a pool or on a thread of its own, sends its
+    // errors to be kept
+    pub fn sender(&self) -> Sender<E> {
+        self.sender.clone()
+    }
+
+    // the errors since the last call
+    pub fn failures(&self) -> Vec<E> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+// A job as the Jobs window lists it
+pub struct JobRow<'a> {
+    pub name: &'a str,
+    pub progress: Option<f32>,
+    pub control: &'a JobControl,
+}
+
+// list the jobs with their progress and buttons to pause, resume and cancel
+// them, a cancelled job is left to its owner to drop
+pub fn draw_jobs(ui: &Ui, jobs: &[JobRow]) {
+    if jobs.is_empty() {
+        return;
+    }
+    ui.window(format!("{}###Jobs", tr("Jobs")))
+        .size([420.0, 0.0], Condition::FirstUseEver)
+        .always_auto_resize(true)
+        .build(|| {
+            for (index, job) in jobs.iter().enumerate() {
+                let _id = ui.push_id_usize(index);
+                let paused = job.control.is_paused();
+                let overlay = if paused {
+                    format!("{} ({})", job.name, tr("paused"))
+                } else {
+                    job.name.to_string()
+                };
+                // a job that cannot tell how far it got shows a full bar
+                imgui::ProgressBar::new(job.progress.unwrap_or(1.0))
+                    .size([300.0, 0.0])
+                    .overlay_text(overlay)
+                    .build(ui);
+                ui.same_line();
+                if paused {
+                    if ui.button(tr("Resume")) {
+                        job.control.resume();
+                    }
+                } else if ui.button(tr("Pause")) {
+                    job.control.pause();
+                }
+                ui.same_line();
+                if ui.button(tr("Cancel")) {
+                    job.control.cancel();
+                }
+            }
+        });
+}
@@ -0,0 +1,28 @@
+use chrono::Local;
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Append `text` to today's journal in `dir`, a Markdown file named after the
+/// date, under a heading with the time and `note` on where it came from.
+/// Returns the path of the file.
+pub fn append(dir: &Path, text: &str, note: Option<&str>) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    let now = Local::now();
+    let path = dir.join(format!("{}.md", now.format("%Y-%m-%d")));
+    let mut entry = String::new();
+    if !path.exists() {
+        entry.push_str(&format!("# {}\n", now.format("%A, %-d %B %Y")));
+    }
+    entry.push_str(&format!("\n## {}", now.format("%H:%M:%S")));
+    if let Some(note) = note.filter(|note| !note.is_empty()) {
+        entry.push_str(&format!(" - {}", note));
+    }
+    entry.push_str(&format!("\n\n{}\n", text.trim_end()));
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(entry.as_bytes())?;
+    Ok(path)
+}
@@ -0,0 +1,341 @@
+use crate::locale::tr;
+use imgui::{Condition, Key, Ui};
+use serde::{Deserialize, Serialize};
+
+// key names a shortcut may end in, as they are written in the keymap
+const KEYS: &[(&str, Key)] = &[
+    ("A", Key::A),
+    ("B", Key::B),
+    ("C", Key::C),
+    ("D", Key::D),
+    ("E", Key::E),
+    ("F", Key::F),
+    ("G", Key::G),
+    ("H", Key::H),
+    ("I", Key::I),
+    ("J", Key::J),
+    ("K", Key::K),
+    ("L", Key::L),
+    ("M", Key::M),
+    ("N", Key::N),
+    ("O", Key::O),
+    ("P", Key::P),
+    ("Q", Key::Q),
+    ("R", Key::R),
+    ("S", Key::S),
+    ("T", Key::T),
+    ("U", Key::U),
+    ("V", Key::V),
+    ("W", Key::W),
+    ("X", Key::X),
+    ("Y", Key::Y),
+    ("Z", Key::Z),
+    ("0", Key::Alpha0),
+    ("1", Key::Alpha1),
+    ("2", Key::Alpha2),
+    ("3", Key::Alpha3),
+    ("4", Key::Alpha4),
+    ("5", Key::Alpha5),
+    ("6", Key::Alpha6),
+    ("7", Key::Alpha7),
+    ("8", Key::Alpha8),
+    ("9", Key::Alpha9),
+    ("F1", Key::F1),
+    ("F2", Key::F2),
+    ("F3", Key::F3),
+    ("F4", Key::F4),
+    ("F5", Key::F5),
+    ("F6", Key::F6),
+    ("F7", Key::F7),
+    ("F8", Key::F8),
+    ("F9", Key::F9),
+    ("F10", Key::F10),
+    ("F11", Key::F11),
+    ("F12", Key::F12),
+    ("Tab", Key::Tab),
+    ("Enter", Key::Enter),
+    ("Escape", Key::Escape),
+    ("Space", Key::Space),
+    ("Backspace", Key::Backspace),
+    ("Delete", Key::Delete),
+    ("Insert", Key::Insert),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Left", Key::LeftArrow),
+    ("Right", Key::RightArrow),
+    ("Up", Key::UpArrow),
+    ("Down", Key::DownArrow),
+    ("Comma", Key::Comma),
+    ("Period", Key::Period),
+    ("Slash", Key::Slash),
+    ("Minus", Key::Minus),
+    ("Equal", Key::Equal),
+];
+
+// keys a text field uses along with Ctrl, a shortcut on one of them is left to
+// the field while typing
+const EDITING_KEYS: &[Key] = &[
+    Key::A,
+    Key::C,
+    Key::V,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::Backspace,
+    Key::Delete,
+    Key::Home,
+    Key::End,
+    Key::LeftArrow,
+    Key::RightArrow,
+    Key::UpArrow,
+    Key::DownArrow,
+    Key::Enter,
+];
+
+// What a shortcut of the keymap does, the same as the button it stands for
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    GetClipboard,
+    Copy,
+    SaveAs,
+    SnipScreen,
+    ToggleWatch,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    Settings,
+    CheatSheet,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::GetClipboard,
+        Action::Copy,
+        Action::SaveAs,
+        Action::SnipScreen,
+        Action::ToggleWatch,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::Settings,
+        Action::CheatSheet,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::GetClipboard => "Get clipboard",
+            Action::Copy => "Copy",
+            Action::SaveAs => "Save as",
+            Action::SnipScreen => "Snip screen",
+            Action::ToggleWatch => "Watch clipboard",
+            Action::NewTab => "New tab",
+            Action::CloseTab => "Close tab",
+            Action::NextTab => "Next tab",
+            Action::PreviousTab => "Previous tab",
+            Action::Settings => "Settings",
+            Action::CheatSheet => "Keyboard shortcuts",
+        }
+    }
+}
+
+// A key with the modifiers held along with it, written as "Ctrl+Shift+V"
+#[derive(Clone, Copy)]
+pub struct Shortcut {
+    key: Key,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Shortcut {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        let mut key = None;
+        for part in text.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ctrl = true,
+                "shift" => shift = true,
+                "alt" => alt = true,
+                _ if key.is_some() => return Err(format!("More than one key in {}", text)),
+                _ => {
+                    let found = KEYS
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(part))
+                        .ok_or_else(|| format!("Unknown key {}", part))?;
+                    key = Some(found.1);
+                }
+            }
+        }
+        Ok(Shortcut {
+            key: key.ok_or_else(|| format!("No key in {}", text))?,
+            ctrl,
+            shift,
+            alt,
+        })
+    }
+
+    // pressed this frame, plain keys and the editing ones are left to the text
+    // field being typed in
+    fn is_pressed(&self, ui: &Ui) -> bool {
+        let io = ui.io();
+        let function_key = (Key::F1 as u32..=Key::F12 as u32).contains(&(self.key as u32));
+        let plain = !self.ctrl && !self.alt && !function_key;
+        let editing = !self.alt && EDITING_KEYS.contains(&self.key);
+        if io.want_text_input && (plain || editing) {
+            return false;
+        }
+        io.key_ctrl == self.ctrl
+            && io.key_shift == self.shift
+            && io.key_alt == self.alt
+            && ui.is_key_pressed_no_repeat(self.key)
+    }
+}
+
+// The shortcut of every action, empty for none
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keymap {
+    pub get_clipboard: String,
+    pub copy: String,
+    pub save_as: String,
+    pub snip_screen: String,
+    pub toggle_watch: String,
+    pub new_tab: String,
+    pub close_tab: String,
+    pub next_tab: String,
+    pub previous_tab: String,
+    pub settings: String,
+    pub cheat_sheet: String,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            get_clipboard: "Ctrl+Shift+V".into(),
+            copy: "Ctrl+Shift+C".into(),
+            save_as: "Ctrl+S".into(),
+            snip_screen: "Ctrl+Shift+S".into(),
+            toggle_watch: "Ctrl+Shift+L".into(),
+            new_tab: "Ctrl+T".into(),
+            close_tab: "Ctrl+W".into(),
+            next_tab: "Ctrl+PageDown".into(),
+            previous_tab: "Ctrl+PageUp".into(),
+            settings: "Ctrl+Comma".into(),
+            cheat_sheet: "F1".into(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn binding_mut(&mut self, action: Action) -> &mut String {
+        match action {
+            Action::GetClipboard => &mut self.get_clipboard,
+            Action::Copy => &mut self.copy,
+            Action::SaveAs => &mut self.save_as,
+            Action::SnipScreen => &mut self.snip_screen,
+            Action::ToggleWatch => &mut self.toggle_watch,
+            Action::NewTab => &mut self.new_tab,
+            Action::CloseTab => &mut self.close_tab,
+            Action::NextTab => &mut self.next_tab,
+            Action::PreviousTab => &mut self.previous_tab,
+            Action::Settings => &mut self.settings,
+            Action::CheatSheet => &mut self.cheat_sheet,
+        }
+    }
+
+    pub fn binding(&self, action: Action) -> &str {
+        match action {
+            Action::GetClipboard => &self.get_clipboard,
+            Action::Copy => &self.copy,
+            Action::SaveAs => &self.save_as,
+            Action::SnipScreen => &self.snip_screen,
+            Action::ToggleWatch => &self.toggle_watch,
+            Action::NewTab => &self.new_tab,
+            Action::CloseTab => &self.close_tab,
+            Action::NextTab => &self.next_tab,
+            Action::PreviousTab => &self.previous_tab,
+            Action::Settings => &self.settings,
+            Action::CheatSheet => &self.cheat_sheet,
+        }
+    }
+
+    // the valid shortcuts, parsed once rather than every frame
+    pub fn bindings(&self) -> Bindings {
+        Bindings(
+            Action::ALL
+                .into_iter()
+                .filter_map(|action| Some((action, Shortcut::parse(self.binding(action)).ok()?)))
+                .collect(),
+        )
+    }
+
+    // the shortcut editors of the settings window, true once one changed to a
+    // valid shortcut or was cleared
+    pub fn edit(&mut self, ui: &Ui) -> bool {
+        let mut changed = false;
+        for action in Action::ALL {
+            let binding = self.binding_mut(action);
+            if ui.input_text(action.label(), binding).build() {
+                changed |= binding.is_empty() || Shortcut::parse(binding).is_ok();
+            }
+            if !binding.is_empty() {
+                if let Err(err) = Shortcut::parse(binding) {
+                    ui.text_colored([0.9, 0.3, 0.3, 1.0], err);
+                }
+            }
+        }
+        if ui.button(tr("Reset shortcuts")) {
+            *self = Keymap::default();
+            changed = true;
+        }
+        changed
+    }
+}
+
+// The shortcuts of a keymap as they are checked every frame, made again when
+// the keymap changes
+pub struct Bindings(Vec<(Action, Shortcut)>);
+
+impl Bindings {
+    // the action whose shortcut was pressed this frame
+    pub fn pressed(&self, ui: &Ui) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(_, shortcut)| shortcut.is_pressed(ui))
+            .map(|(action, _)| *action)
+    }
+}
+
+// Window listing every shortcut, the keymap's and the fixed ones
+pub fn draw_cheat_sheet(ui: &Ui, keymap: &Keymap, hotkeys: &[(String, String)], open: &mut bool) {
+    ui.window(format!("{}###Keyboard shortcuts", tr("Keyboard shortcuts")))
+        .size([360.0, 0.0], Condition::FirstUseEver)
+        .opened(open)
+        .build(|| {
+            let row = |label: &str, binding: &str| {
+                ui.text(label);
+                ui.same_line_with_pos(200.0);
+                if binding.is_empty() {
+                    ui.text_disabled(tr("none"));
+                } else {
+                    ui.text(binding);
+                }
+            };
+            for action in Action::ALL {
+                row(action.label(), keymap.binding(action));
+            }
+            row("Undo", "Ctrl+Z");
+            row("Redo", "Ctrl+Y or Ctrl+Shift+Z");
+            ui.separator();
+            ui.text_disabled(tr("Anywhere on the desktop"));
+            for (label, binding) in hotkeys {
+                row(label, binding);
+            }
+            ui.separator();
+            ui.text_disabled(tr("Change them under \"Keyboard shortcuts\" in Settings"));
+        });
+}
@@ -0,0 +1,469 @@
+use crate::ocr::{Line, TranscriptionResult};
+use serde::{Deserialize, Serialize};
+use std::mem;
+
+// lines this many times the usual height are headings, larger ones higher level
+const HEADING_SIZES: [(f32, &str); 3] = [(2.0, "#"), (1.6, "##"), (1.3, "###")];
+
+// headings are short, longer lines in a large font are more likely a lead paragraph
+const HEADING_WORDS: usize = 12;
+
+// gaps between lines taller than this share of the usual height start a paragraph
+const PARAGRAPH_GAP: f32 = 0.8;
+
+// lines wider than this share of the text are not in a column and left out
+// when looking for the gaps between columns
+const COLUMN_WIDTH: f32 = 0.5;
+
+// every column needs this many lines, fewer are more likely a stray caption
+const COLUMN_LINES: usize = 3;
+
+// what a word broken over two lines may end in: the hyphen-minus, the Unicode
+// hyphen, a soft hyphen and the not sign OCR often reads a hyphen as
+const HYPHENS: &[char] = &['-', '\u{2010}', '\u{00AD}', '\u{00AC}'];
+
+// code is mostly indented in steps of this many spaces, indents all within a
+// space of them are snapped to them
+const INDENT_STEP: usize = 4;
+
+// marks recognized as the bullet of a list item
+const BULLETS: &[&str] = &["•", "◦", "▪", "‣", "●", "○", "·", "-", "–", "*"];
+
+/// How the recognized lines are turned into text.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextLayout {
+    /// Every line after the other, separated by spaces.
+    Plain,
+    /// Headings, lists and paragraphs inferred from the line geometry.
+    Markdown,
+    /// The lines of every paragraph joined into one, for pasting into a word
+    /// processor.
+    Paragraphs,
+    /// Every line on its own, indented by where it starts and with the blank
+    /// lines kept, for source code.
+    Code,
+}
+
+impl TextLayout {
+    pub const ALL: [TextLayout; 4] = [
+        TextLayout::Plain,
+        TextLayout::Markdown,
+        TextLayout::Paragraphs,
+        TextLayout::Code,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TextLayout::Plain => "Plain text",
+            TextLayout::Markdown => "Markdown",
+            TextLayout::Paragraphs => "Flowing paragraphs",
+            TextLayout::Code => "Code",
+        }
+    }
+
+    pub fn render(self, result: &TranscriptionResult) -> String {
+        match self {
+            TextLayout::Plain => result.text(),
+            TextLayout::Markdown => markdown(result),
+            TextLayout::Paragraphs => paragraphs(result),
+            TextLayout::Code => code(result),
+        }
+    }
+}
+
+fn height(line: &Line) -> f32 {
+    (line.bounds.bottom - line.bounds.top) as f32
+}
+
+// the median line height, None without lines
+fn usual_height(lines: &[Line]) -> Option<f32> {
+    let mut heights: Vec<f32> = lines.iter().map(height).collect();
+    heights.sort_by(f32::total_cmp);
+    heights.get(heights.len() / 2).map(|usual| usual.max(1.0))
+}
+
+// x ranges no line of a column covers, between the columns of the page
+fn gutters(lines: &[Line], usual: f32) -> Vec<(i32, i32)> {
+    let left = lines.iter().map(|line| line.bounds.left).min().unwrap_or(0);
+    let right = lines
+        .iter()
+        .map(|line| line.bounds.right)
+        .max()
+        .unwrap_or(0);
+    let width = right - left;
+    if width <= 0 {
+        return Vec::new();
+    }
+    let narrow: Vec<&Line> = lines
+        .iter()
+        .filter(|line| {
+            ((line.bounds.right - line.bounds.left) as f32) < width as f32 * COLUMN_WIDTH
+        })
+        .collect();
+    let mut covered = vec![false; width as usize];
+    for line in &narrow {
+        let start = (line.bounds.left - left).clamp(0, width) as usize;
+        let end = (line.bounds.right - left).clamp(0, width) as usize;
+        covered[start..end].iter_mut().for_each(|x| *x = true);
+    }
+
+    let mut gutters = Vec::new();
+    let mut x = 0;
+    while x < covered.len() {
+        if covered[x] {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < covered.len() && !covered[x] {
+            x += 1;
+        }
+        let (start, end) = (start as i32 + left, x as i32 + left);
+        let before = narrow.iter().filter(|line| line.bounds.right <= start);
+        let after = narrow.iter().filter(|line| line.bounds.left >= end);
+        if (end - start) as f32 >= usual
+            && before.count() >= COLUMN_LINES
+            && after.count() >= COLUMN_LINES
+        {
+            gutters.push((start, end));
+        }
+    }
+    gutters
+}
+
+/// Put the lines of a multi-column page in reading order, column by column
+/// from the left instead of across the columns from the top. Lines reaching
+/// over a gap between columns, such as a title, split the page into sections
+/// read one after the other. Pages of a single column are left as they are.
+pub fn reading_order(result: &mut TranscriptionResult) {
+    let Some(usual) = usual_height(&result.lines) else {
+        return;
+    };
+    let gutters = gutters(&result.lines, usual);
+    if gutters.is_empty() {
+        return;
+    }
+    let column = |line: &Line| {
+        let center = (line.bounds.left + line.bounds.right) / 2;
+        gutters.iter().filter(|(start, _)| *start < center).count()
+    };
+    let spans = |line: &Line| {
+        gutters
+            .iter()
+            .any(|(start, end)| line.bounds.left < *start && line.bounds.right > *end)
+    };
+
+    let mut lines = mem::take(&mut result.lines);
+    lines.sort_by_key(|line| line.bounds.top);
+    let mut section: Vec<Line> = Vec::new();
+    for line in lines {
+        if spans(&line) {
+            section.sort_by_key(|line| (column(line), line.bounds.top));
+            result.lines.append(&mut section);
+            result.lines.push(line);
+        } else {
+            section.push(line);
+        }
+    }
+    section.sort_by_key(|line| (column(line), line.bounds.top));
+    result.lines.append(&mut section);
+}
+
+// a list item's text without its bullet, numbered items keep their number
+fn list_item(text: &str) -> Option<String> {
+    for bullet in BULLETS {
+        if let Some(rest) = text.strip_prefix(bullet) {
+            if rest.starts_with(char::is_whitespace) {
+                return Some(format!("- {}", escape_inline(rest.trim_start())));
+            }
+        }
+    }
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    let rest = &text[digits..];
+    let numbered = (1..=3).contains(&digits) && (rest.starts_with(". ") || rest.starts_with(") "));
+    numbered.then(|| {
+        format!(
+            "{}. {}",
+            &text[..digits],
+            escape_inline(rest[2..].trim_start())
+        )
+    })
+}
+
+// keep the characters that mark emphasis, links, code and headings within
+// recognized text from taking effect
+fn escape_inline(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<#".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// keep text that happens to start like Markdown syntax from turning into it
+fn escape_start(text: &str) -> String {
+    let special = text.starts_with(['#', '>', '-', '+', '*', '=', '|'])
+        || text.starts_with(char::is_numeric) && list_item(text).is_some();
+    if special {
+        format!("\\{}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+// add a line to a paragraph, joining words hyphenated across lines
+pub(crate) fn join_line(paragraph: &mut String, text: &str) {
+    if paragraph.is_empty() {
+        paragraph.push_str(text);
+    } else if paragraph.ends_with(HYPHENS) && text.starts_with(char::is_lowercase) {
+        paragraph.pop();
+        paragraph.push_str(text);
+    } else {
+        paragraph.push(' ');
+        paragraph.push_str(text);
+    }
+}
+
+enum Block {
+    Heading(String),
+    Paragraph(String),
+    List(Vec<String>),
+}
+
+impl Block {
+    fn render(self) -> String {
+        match self {
+            Block::Heading(heading) => heading,
+            Block::Paragraph(paragraph) => escape_start(&paragraph),
+            Block::List(items) => items.join("\n"),
+        }
+    }
+}
+
+/// Markdown reconstruction of the document: lines much taller than the usual
+/// are headings, lines starting with a bullet or number are list items and
+/// the other lines are joined into paragraphs wherever they are not set apart
+/// by a gap or an indent.
+pub fn markdown(result: &TranscriptionResult) -> String {
+    let Some(usual) = usual_height(&result.lines) else {
+        return String::new();
+    };
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut previous: Option<&Line> = None;
+    // left edge of the last list item's bullet
+    let mut item_left = i32::MAX;
+    for line in &result.lines {
+        let text = line.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let size = height(line) / usual;
+        let heading = (line.words.len() <= HEADING_WORDS)
+            .then(|| HEADING_SIZES.iter().find(|(min, _)| size >= *min))
+            .flatten();
+        let (gap, indent) = previous.map_or((f32::MAX, 0.0), |previous| {
+            (
+                (line.bounds.top - previous.bounds.bottom) as f32,
+                (line.bounds.left - previous.bounds.left) as f32,
+            )
+        });
+        let close = gap <= usual * PARAGRAPH_GAP;
+        previous = Some(line);
+
+        if let Some((_, marks)) = heading {
+            blocks.push(Block::Heading(format!("{} {}", marks, escape_inline(text))));
+            // the text below a heading never continues it
+            previous = None;
+        } else if let Some(item) = list_item(text) {
+            item_left = line.bounds.left;
+            match blocks.last_mut() {
+                Some(Block::List(items)) if close => items.push(item),
+                _ => blocks.push(Block::List(vec![item])),
+            }
+        } else {
+            match blocks.last_mut() {
+                // wrapped text of a list item is indented past its bullet
+                Some(Block::List(items)) if close && line.bounds.left > item_left => {
+                    if let Some(item) = items.last_mut() {
+                        join_line(item, &escape_inline(text));
+                    }
+                }
+                Some(Block::Paragraph(paragraph)) if close && indent <= usual => {
+                    join_line(paragraph, &escape_inline(text))
+                }
+                _ => blocks.push(Block::Paragraph(escape_inline(text))),
+            }
+        }
+    }
+    blocks
+        .into_iter()
+        .map(Block::render)
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+/// The text as flowing paragraphs: lines are joined into one wherever they are
+/// not set apart by a gap or an indent, and so are words hyphenated across
+/// them. Paragraphs are separated by a blank line.
+pub fn paragraphs(result: &TranscriptionResult) -> String {
+    let Some(usual) = usual_height(&result.lines) else {
+        return String::new();
+    };
+
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut previous: Option<&Line> = None;
+    for line in &result.lines {
+        let text = line.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let continues = previous.is_some_and(|previous| {
+            let gap = (line.bounds.top - previous.bounds.bottom) as f32;
+            let indent = (line.bounds.left - previous.bounds.left) as f32;
+            gap <= usual * PARAGRAPH_GAP && indent <= usual
+        });
+        previous = Some(line);
+        match paragraphs.last_mut() {
+            Some(paragraph) if continues => join_line(paragraph, text),
+            _ => paragraphs.push(text.to_string()),
+        }
+    }
+    paragraphs.join("\n\n")
+}
+
+// the median of `values`, None when there are none
+fn median(mut values: Vec<f32>) -> Option<f32> {
+    values.sort_by(f32::total_cmp);
+    values.get(values.len() / 2).copied()
+}
+
+/// The text as source code: each row of the image on a line of its own,
+/// indented by as many spaces as characters fit between its start and that of
+/// the leftmost row, and as many blank lines between rows as their gap holds.
+/// Pieces an engine read as separate lines on the same row are joined with
+/// the spaces between them.
+pub fn code(result: &TranscriptionResult) -> String {
+    let mut lines: Vec<&Line> = result
+        .lines
+        .iter()
+        .filter(|line| !line.text.trim().is_empty())
+        .collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    let center = |line: &Line| (line.bounds.top + line.bounds.bottom) as f32 / 2.0;
+    lines.sort_by(|a, b| center(a).total_cmp(&center(b)));
+
+    // pieces whose middle is within the row above are on it
+    let mut rows: Vec<Vec<&Line>> = Vec::new();
+    for line in lines {
+        match rows.last_mut() {
+            Some(row) if center(line) < row[0].bounds.bottom as f32 => row.push(line),
+            _ => rows.push(vec![line]),
+        }
+    }
+    for row in &mut rows {
+        row.sort_by_key(|line| line.bounds.left);
+    }
+
+    // code is mostly in a monospace font, so one width fits every character
+    let char_width = median(
+        rows.iter()
+            .flatten()
+            .filter(|line| line.text.trim().chars().count() >= 3)
+            .map(|line| {
+                (line.bounds.right - line.bounds.left) as f32
+                    / line.text.trim().chars().count() as f32
+            })
+            .collect(),
+    )
+    .unwrap_or(1.0)
+    .max(1.0);
+    let left = rows.iter().map(|row| row[0].bounds.left).min().unwrap_or(0);
+    let mut indents: Vec<usize> = rows
+        .iter()
+        .map(|row| ((row[0].bounds.left - left) as f32 / char_width).round() as usize)
+        .collect();
+    let stepped = indents
+        .iter()
+        .all(|indent| indent % INDENT_STEP <= 1 || INDENT_STEP - indent % INDENT_STEP <= 1);
+    if stepped {
+        for indent in &mut indents {
+            *indent = (*indent as f32 / INDENT_STEP as f32).round() as usize * INDENT_STEP;
+        }
+    }
+    let pitch = median(
+        rows.windows(2)
+            .map(|pair| (pair[1][0].bounds.top - pair[0][0].bounds.top) as f32)
+            .collect(),
+    );
+
+    let mut text = String::new();
+    for (index, (row, indent)) in rows.iter().zip(indents).enumerate() {
+        if index > 0 {
+            text.push('\n');
+            // the gap to the row above holds this many rows
+            let above = rows[index - 1][0].bounds.top;
+            if let Some(pitch) = pitch.filter(|pitch| *pitch > 0.0) {
+                let blank = ((row[0].bounds.top - above) as f32 / pitch).round() as usize;
+                text.push_str(&"\n".repeat(blank.saturating_sub(1)));
+            }
+        }
+        text.push_str(&" ".repeat(indent));
+        let mut right = None;
+        for line in row {
+            if let Some(right) = right {
+                let gap = ((line.bounds.left - right) as f32 / char_width).round();
+                text.push_str(&" ".repeat(gap.max(1.0) as usize));
+            }
+            text.push_str(line.text.trim());
+            right = Some(line.bounds.right);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::fixtures;
+
+    // a line of `text` at `left` on row `row`, ten pixels per character
+    fn line(text: &str, left: i32, row: i32) -> Line {
+        let right = left + text.chars().count() as i32 * 10;
+        fixtures::line(text, fixtures::bounds(left, row * 20, right, row * 20 + 16))
+    }
+
+    fn transcription(lines: Vec<Line>) -> TranscriptionResult {
+        fixtures::transcription(800, 800, lines)
+    }
+
+    #[test]
+    fn code_keeps_indentation_and_blank_lines() {
+        let result = transcription(vec![
+            line("fn main() {", 100, 0),
+            line("let x = 1;", 141, 1),
+            line("let y = x;", 139, 2),
+            line("}", 100, 4),
+        ]);
+        assert_eq!(
+            code(&result),
+            "fn main() {\n    let x = 1;\n    let y = x;\n\n}"
+        );
+    }
+
+    #[test]
+    fn code_joins_pieces_on_a_row() {
+        let result = transcription(vec![
+            line("x = 1", 0, 0),
+            line("# one", 80, 0),
+            line("y", 0, 1),
+        ]);
+        assert_eq!(code(&result), "x = 1   # one\ny");
+    }
+}
@@ -0,0 +1,72 @@
+//! OCR pipeline behind Transcribe Wizard, usable without the GUI.
+//!
+//! [`transcribe`] runs an [`ocrs::OcrEngine`] over an image and returns a
+//! [`TranscriptionResult`] with every line and word the engine found, their
+//! boxes and orientation, [`files`] does the same for image and PDF files.
+//! What can go wrong on the way is a [`TranscribeError`]. Other engines plug
+//! in through [`backend::OcrBackend`].
+//!
+//! Around that, [`preprocess`] and [`document`] prepare the image, [`filter`]
+//! and [`postprocess`] clean up what was read, and [`export`] and [`pdf`]
+//! turn the results into files. The other modules work on the text once it
+//! is read, each one's own documentation says what it does.
+//!
+//! With the `whisper` feature [`audio`] transcribes speech from audio files,
+//! with `camera` [`camera`] streams a webcam, and with `archive` [`archive`]
+//! keeps every transcription in a database to search later.
+
+pub mod annotate;
+pub mod anonymize;
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "whisper")]
+pub mod audio;
+pub mod backend;
+pub mod book;
+pub mod cache;
+#[cfg(feature = "camera")]
+pub mod camera;
+pub mod captions;
+pub mod codes;
+pub mod diff;
+pub mod digest;
+pub mod document;
+pub mod ensemble;
+pub mod error;
+pub mod evaluate;
+pub mod export;
+pub mod extract;
+pub mod files;
+pub mod filter;
+pub mod formula;
+pub mod import;
+pub mod job;
+pub mod journal;
+pub mod layout;
+pub mod links;
+pub mod llm;
+pub mod merge;
+pub mod metadata;
+pub mod ocr;
+pub mod orientation;
+pub mod pdf;
+pub mod pool;
+pub mod postprocess;
+pub mod preprocess;
+pub mod script;
+pub mod secrets;
+pub mod send;
+pub mod source_code;
+pub mod spelling;
+pub mod stats;
+pub mod stitch;
+pub mod subtitle;
+pub mod table;
+pub mod template;
+pub mod tiling;
+pub mod translate;
+pub mod update;
+pub mod video;
+
+pub use error::TranscribeError;
+pub use ocr::{transcribe, TranscriptionResult};
@@ -0,0 +1,137 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    locale::tr,
+    status::Status,
+};
+use imgui::{Condition, Ui};
+
+// Window listing the lines of the text to pick some of them and copy only
+// those, in the order they have in the text. A click picks one line, Ctrl+click
+// adds or removes one and Shift+click picks the lines up to the one clicked
+#[derive(Default)]
+pub struct LinePanel {
+    // the text the lines are of, split again only once it changed
+    scanned: String,
+    // the lines that are not blank
+    lines: Vec<String>,
+    picked: Vec<bool>,
+    // where Shift+click picks from, the line clicked last
+    anchor: Option<usize>,
+}
+
+impl LinePanel {
+    pub fn update(&mut self, text: &str) {
+        if self.scanned == text {
+            return;
+        }
+        let lines: Vec<String> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+        // an edit elsewhere keeps the picks of lines that stayed where they were
+        self.picked = lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                self.lines.get(index) == Some(line) && self.picked.get(index) == Some(&true)
+            })
+            .collect();
+        self.lines = lines;
+        self.anchor = self.anchor.filter(|anchor| *anchor < self.lines.len());
+        self.scanned = text.to_string();
+    }
+
+    // the picked lines, one per line
+    fn picked_text(&self) -> String {
+        self.lines
+            .iter()
+            .zip(&self.picked)
+            .filter(|(_, picked)| **picked)
+            .map(|(line, _)| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn click(&mut self, ui: &Ui, index: usize) {
+        let io = ui.io();
+        match self.anchor {
+            Some(anchor) if io.key_shift => {
+                if !io.key_ctrl {
+                    self.picked.fill(false);
+                }
+                let range = anchor.min(index)..=anchor.max(index);
+                self.picked[range].fill(true);
+                // the anchor stays, so the range can be moved from it
+                return;
+            }
+            _ if io.key_ctrl => self.picked[index] = !self.picked[index],
+            _ => {
+                self.picked.fill(false);
+                self.picked[index] = true;
+            }
+        }
+        self.anchor = Some(index);
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        clipboard: &SystemClipboard,
+        status: &mut Status,
+        open: &mut bool,
+    ) {
+        ui.window(format!("{}###Lines", tr("Copy lines")))
+            .size([420.0, 360.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                if self.lines.is_empty() {
+                    ui.text_disabled(tr("No lines in the text"));
+                    return;
+                }
+                let count = self.picked.iter().filter(|picked| **picked).count();
+                {
+                    let _disabled = ui.begin_disabled(count == 0);
+                    if ui.button(format!("{} ({})", tr("Copy picked lines"), count)) {
+                        if let Err(err) = clipboard.set_text(self.picked_text()) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                }
+                ui.same_line();
+                if ui.button(tr("Pick all")) {
+                    self.picked.fill(true);
+                }
+                ui.same_line();
+                if ui.button(tr("Clear")) {
+                    self.picked.fill(false);
+                    self.anchor = None;
+                }
+                ui.text_disabled(tr(
+                    "Click picks a line, Ctrl+click adds one, Shift+click picks a range",
+                ));
+                ui.separator();
+                ui.child_window("lines").build(|| {
+                    let mut clicked = None;
+                    for (index, line) in self.lines.iter().enumerate() {
+                        let _id = ui.push_id_usize(index);
+                        // the line is drawn over an empty selectable, text of
+                        // its own such as ## would be taken for an ID
+                        let start = ui.cursor_pos()[0];
+                        if ui
+                            .selectable_config("##line")
+                            .selected(self.picked[index])
+                            .build()
+                        {
+                            clicked = Some(index);
+                        }
+                        ui.same_line_with_pos(start);
+                        ui.text(line);
+                    }
+                    if let Some(index) = clicked {
+                        self.click(ui, index);
+                    }
+                });
+            });
+    }
+}
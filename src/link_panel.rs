@@ -0,0 +1,65 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    locale::tr,
+    status::Status,
+};
+use imgui::{Condition, Ui};
+use transcribewizard::links::{self, Link};
+
+// Window listing the web addresses, emails and phone numbers in the text, to
+// open or copy each one, kept up to date as the text changes
+#[derive(Default)]
+pub struct LinkPanel {
+    // the text the links were found in, looked over again only once it changed
+    scanned: String,
+    links: Vec<Link>,
+}
+
+impl LinkPanel {
+    pub fn update(&mut self, text: &str) {
+        if self.scanned != text {
+            self.scanned = text.to_string();
+            self.links = links::find(text);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        clipboard: &SystemClipboard,
+        status: &mut Status,
+        open: &mut bool,
+    ) {
+        ui.window(format!("{}###Links", tr("Links")))
+            .size([360.0, 300.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                if self.links.is_empty() {
+                    ui.text_disabled(tr("No links, emails or phone numbers in the text"));
+                    return;
+                }
+                for (index, link) in self.links.iter().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    if ui.small_button(tr("Open")) {
+                        if let Err(err) = open::that_detached(&link.target) {
+                            status.error("Error opening link", err);
+                        }
+                    }
+                    ui.same_line();
+                    if ui.small_button(tr("Copy")) {
+                        if let Err(err) = clipboard.set_text(link.text.clone()) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                    ui.same_line();
+                    ui.text_disabled(tr(link.kind.label()));
+                    ui.same_line();
+                    ui.text(&link.text);
+                }
+            });
+    }
+}
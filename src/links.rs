@@ -0,0 +1,150 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+// web addresses with a scheme or starting with www, up to the next space
+const URL: &str = r#"(?i)\b(?:https?://|www\.)[^\s<>"'`]+"#;
+
+// also what the secrets and anonymization find as email addresses
+pub(crate) const EMAIL: &str =
+    r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b";
+
+// digits grouped by spaces, dots, dashes or parentheses on a single line,
+// told apart from other numbers below
+const PHONE: &str = r"\+?\(?\d[\d ().-]{5,}\d";
+
+// numbers written like phone numbers that are dates
+const DATE: &str = r"^(?:\d{4}-\d{1,2}-\d{1,2}|\d{1,2}[./-]\d{1,2}[./-]\d{2,4})$";
+
+// characters that end a sentence rather than the link before them
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"', ')', ']', '}', '>'];
+
+/// What a [`Link`] leads to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    Url,
+    Email,
+    Phone,
+}
+
+impl LinkKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            LinkKind::Url => "Link",
+            LinkKind::Email => "Email",
+            LinkKind::Phone => "Phone",
+        }
+    }
+}
+
+/// A web address, email address or phone number found in a text.
+#[derive(Clone, PartialEq)]
+pub struct Link {
+    pub kind: LinkKind,
+    /// As it is written in the text.
+    pub text: String,
+    /// What opens it, such as `https://...`, `mailto:...` or `tel:...`.
+    pub target: String,
+}
+
+// the pattern compiled once into `cell`, for the fixed patterns of this and
+// the other modules that find things in text
+pub(crate) fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("built-in patterns are valid"))
+}
+
+// `url` without the punctuation the sentence around it put after it, a
+// closing parenthesis stays when the address opened one
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    while let Some(last) = url.chars().last() {
+        let balanced = last == ')' && url.matches('(').count() >= url.matches(')').count();
+        if !TRAILING.contains(&last) || balanced {
+            break;
+        }
+        url = &url[..url.len() - last.len_utf8()];
+    }
+    url
+}
+
+// whether a run of digits reads as a phone number, which has 7 to 15 digits
+// and a country code or some grouping, unlike order numbers, ids and dates
+fn is_phone(number: &str) -> bool {
+    static DATES: OnceLock<Regex> = OnceLock::new();
+    let digits = number.chars().filter(char::is_ascii_digit).count();
+    let grouped = number.starts_with('+') || number.chars().any(|c| !c.is_ascii_digit());
+    (7..=15).contains(&digits) && grouped && !regex(&DATES, DATE).is_match(number)
+}
+
+/// The links in `text` in the order they appear, each target only once.
+/// Addresses win over the numbers and emails inside them.
+pub fn find(text: &str) -> Vec<Link> {
+    static URLS: OnceLock<Regex> = OnceLock::new();
+    static EMAILS: OnceLock<Regex> = OnceLock::new();
+    static PHONES: OnceLock<Regex> = OnceLock::new();
+
+    let mut found: Vec<(usize, usize, Link)> = Vec::new();
+    let mut add = |start: usize, end: usize, link: Link| {
+        let overlaps = found.iter().any(|(from, to, _)| *from < end && start < *to);
+        if !overlaps {
+            found.push((start, end, link));
+        }
+    };
+    for matched in regex(&URLS, URL).find_iter(text) {
+        let url = trim_url(matched.as_str());
+        let target = if url.to_lowercase().starts_with("www.") {
+            format!("https://{}", url)
+        } else {
+            url.to_string()
+        };
+        let end = matched.start() + url.len();
+        add(
+            matched.start(),
+            end,
+            Link {
+                kind: LinkKind::Url,
+                text: url.to_string(),
+                target,
+            },
+        );
+    }
+    for matched in regex(&EMAILS, EMAIL).find_iter(text) {
+        add(
+            matched.start(),
+            matched.end(),
+            Link {
+                kind: LinkKind::Email,
+                text: matched.as_str().to_string(),
+                target: format!("mailto:{}", matched.as_str()),
+            },
+        );
+    }
+    for matched in regex(&PHONES, PHONE).find_iter(text) {
+        let number = matched.as_str().trim_end_matches(['(', '.', '-', ' ']);
+        if !is_phone(number) {
+            continue;
+        }
+        let mut target = String::from("tel:");
+        if number.starts_with('+') {
+            target.push('+');
+        }
+        target.extend(number.chars().filter(char::is_ascii_digit));
+        add(
+            matched.start(),
+            matched.start() + number.len(),
+            Link {
+                kind: LinkKind::Phone,
+                text: number.to_string(),
+                target,
+            },
+        );
+    }
+
+    found.sort_by_key(|(start, _, _)| *start);
+    let mut links: Vec<Link> = Vec::new();
+    for (_, _, link) in found {
+        if !links.iter().any(|other| other.target == link.target) {
+            links.push(link);
+        }
+    }
+    links
+}
@@ -0,0 +1,454 @@
+use crate::{
+    capture::{self, Area},
+    config::Config,
+    locale::tr,
+    translation, PoolSettings,
+};
+use image::DynamicImage;
+use imgui::{Condition, Key, MouseButton, StyleColor, TreeNodeFlags, Ui, WindowFlags};
+use sdl2::video::{Window, WindowPos};
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant},
+};
+use tracing::error;
+use transcribewizard::{digest::ImageDigest, ocr, pool::WorkerPool};
+
+// time the overlay gets to fade out before the region under it is captured,
+// so it does not read its own text
+const FADE_DELAY: Duration = Duration::from_millis(120);
+
+const ERROR_COLOR: [f32; 4] = [1.0, 0.45, 0.4, 1.0];
+
+// A region of the screen read over and over, its text or the translation of
+// it shown in a borderless window on top of the region, for games and videos
+// in another language. The main window becomes the overlay while it runs
+pub struct LiveOverlay {
+    // the region as left, top, right and bottom in desktop coordinates
+    rect: [f32; 4],
+    desktop: Area,
+    capture: Option<Receiver<Result<DynamicImage, String>>>,
+    next_capture: Instant,
+    // the frame read last, the region is not read again until it changes
+    last_frame: Option<ImageDigest>,
+    // the frame being read, the next one is captured once it is in
+    reading: Option<Receiver<Result<String, String>>>,
+    // frames are read on a pool of their own without the result cache, which
+    // the frames of a game or video would only fill up
+    pool: Option<(WorkerPool, PoolSettings)>,
+    translation: Option<Receiver<Result<String, String>>>,
+    // what the overlay shows, the text read or its translation
+    text: String,
+    error: Option<String>,
+    // whether clicks go through the window, only with a hotkey to stop it by
+    click_through: bool,
+    pub open: bool,
+}
+
+impl LiveOverlay {
+    pub fn start(desktop: Area, rect: [f32; 4], click_through: bool) -> Self {
+        LiveOverlay {
+            rect,
+            desktop,
+            capture: None,
+            next_capture: Instant::now(),
+            last_frame: None,
+            reading: None,
+            pool: None,
+            translation: None,
+            text: String::new(),
+            error: None,
+            click_through,
+            open: true,
+        }
+    }
+
+    // where the window goes, over the region
+    pub fn area(&self) -> Area {
+        let [left, top, right, bottom] = self.rect;
+        Area {
+            x: left as i32,
+            y: top as i32,
+            width: (right - left).max(1.0) as u32,
+            height: (bottom - top).max(1.0) as u32,
+        }
+    }
+
+    // capture the region when it is time and read the frames that changed on
+    // the pool, one at a time
+    pub fn poll(&mut self, window: &mut Window, config: &Config) {
+        if let Some(reading) = &self.reading {
+            let result = match reading.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => Err("worker stopped".to_string()),
+            };
+            self.reading = None;
+            self.show(config, result);
+        }
+        let Some(frame) = self.poll_frame(window, config) else {
+            return;
+        };
+        match crate::ensure_pool(&mut self.pool, config, &None) {
+            Ok(pool) => {
+                let (sender, receiver) = mpsc::channel();
+                let config = config.clone();
+                pool.submit(move |backend| {
+                    let result = backend.map_err(str::to_string).and_then(|backend| {
+                        ocr::transcribe_preprocessed(backend, &frame, &config.preprocessing)
+                            .map(|transcription| config.render(&transcription))
+                            .map_err(|err| err.to_string())
+                    });
+                    let _ = sender.send(result);
+                });
+                self.reading = Some(receiver);
+            }
+            Err(err) => {
+                self.error = Some(format!("{}: {}", tr("Error loading OCR models"), err));
+            }
+        }
+    }
+
+    // capture the region when it is time, with the window faded out meanwhile,
+    // returns the frame captured when it changed since the last one
+    fn poll_frame(&mut self, window: &mut Window, config: &Config) -> Option<DynamicImage> {
+        let Some(job) = &self.capture else {
+            if Instant::now() >= self.next_capture {
+                fade(window, 0.0);
+                self.capture = Some(capture::spawn_region(self.desktop, self.rect, FADE_DELAY));
+            }
+            return None;
+        };
+        let captured = match job.try_recv() {
+            Ok(captured) => captured,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("worker stopped".to_string()),
+        };
+        self.capture = None;
+        self.next_capture =
+            Instant::now() + Duration::from_secs_f32(config.overlay_interval.max(0.1));
+        fade(window, config.overlay_opacity);
+        match captured {
+            Ok(frame) => {
+                let digest = ImageDigest::of(&frame);
+                if self
+                    .last_frame
+                    .as_ref()
+                    .is_some_and(|last| last.is_same(&digest))
+                {
+                    return None;
+                }
+                self.last_frame = Some(digest);
+                self.error = None;
+                Some(frame)
+            }
+            Err(err) => {
+                self.error = Some(format!("{}: {}", tr("Error capturing the screen"), err));
+                None
+            }
+        }
+    }
+
+    // show what was read from the latest frame, translated first when asked
+    fn show(&mut self, config: &Config, result: Result<String, String>) {
+        match result {
+            Ok(text) if text.trim().is_empty() => self.text.clear(),
+            Ok(text) if config.overlay_translate => {
+                // the translation before stays up until the new one is in
+                self.translation = Some(translation::spawn(config, &text));
+            }
+            Ok(text) => self.text = text,
+            Err(err) => {
+                self.error = Some(format!(
+                    "{}: {}",
+                    tr("Error extracting text from screen"),
+                    err
+                ))
+            }
+        }
+    }
+
+    fn poll_translation(&mut self) {
+        let Some(job) = &self.translation else {
+            return;
+        };
+        match job.try_recv() {
+            Ok(Ok(translation)) => self.text = translation,
+            Ok(Err(err)) => self.error = Some(format!("{}: {}", tr("Error translating text"), err)),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.error = Some(format!("{}: worker stopped", tr("Error translating text")))
+            }
+        }
+        self.translation = None;
+    }
+
+    // the text across the whole window, which covers the region, Esc or a
+    // right click stop the overlay while it takes clicks
+    pub fn draw(&mut self, ui: &Ui) {
+        self.poll_translation();
+        let _background = ui.push_style_color(StyleColor::WindowBg, [0.0, 0.0, 0.0, 1.0]);
+        ui.window("##live_overlay")
+            .position([0.0, 0.0], Condition::Always)
+            .size(ui.io().display_size, Condition::Always)
+            .flags(
+                WindowFlags::NO_DECORATION | WindowFlags::NO_MOVE | WindowFlags::NO_SAVED_SETTINGS,
+            )
+            .focused(true)
+            .build(|| {
+                match &self.error {
+                    Some(err) => ui.text_colored(ERROR_COLOR, err),
+                    None if self.text.is_empty() => ui.text_disabled(tr("Waiting for text...")),
+                    None => ui.text_wrapped(&self.text),
+                }
+                if ui.is_window_hovered() && ui.is_mouse_clicked(MouseButton::Right)
+                    || ui.is_key_pressed(Key::Escape)
+                {
+                    self.open = false;
+                }
+            });
+    }
+}
+
+// The live overlay being run, there is one at a time as it takes over the
+// main window
+#[derive(Default)]
+pub struct Live {
+    running: Option<LiveOverlay>,
+}
+
+impl Live {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    // the hotkey stops the overlay running, returns true when there is none
+    // and a region is to be picked for one
+    pub fn toggle(&mut self) -> bool {
+        match &mut self.running {
+            Some(live) => {
+                live.open = false;
+                false
+            }
+            None => true,
+        }
+    }
+
+    // read `rect` of `desktop` over and over, clicks only go through the
+    // window when there is a hotkey to stop it by, from the tray alone
+    // an overlay taking no clicks is hard to stop
+    pub fn start(&mut self, desktop: Area, rect: [f32; 4], config: &Config, can_stop: bool) {
+        let click_through = config.overlay_click_through && can_stop;
+        self.running = Some(LiveOverlay::start(desktop, rect, click_through));
+    }
+
+    // once the snipping overlay is closed the window covers the region picked,
+    // returns false when no overlay was started and the window is the caller's
+    // to put back
+    pub fn cover(&self, window: &mut Window, config: &Config) -> bool {
+        match &self.running {
+            Some(live) => {
+                cover(window, live, config);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // whether the overlay was stopped since the last frame, the window is
+    // taken back from it for the caller to put where it was
+    pub fn stopped(&mut self, window: &mut Window) -> bool {
+        match self.running.take_if(|live| !live.open) {
+            Some(live) => {
+                uncover(window, &live);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // read the region again when it is time and show its text, not while the
+    // snipping overlay still covers the window
+    pub fn frame(&mut self, ui: &Ui, window: &mut Window, config: &Config, snipping: bool) {
+        if let Some(live) = self.running.as_mut().filter(|_| !snipping) {
+            live.poll(window, config);
+            live.draw(ui);
+        }
+    }
+}
+
+// the options of the overlay under "Live overlay" in Settings, saved as they change
+pub fn draw_settings(ui: &Ui, config: &mut Config) {
+    if !ui.collapsing_header(
+        format!("{}###Live overlay", tr("Live overlay")),
+        TreeNodeFlags::empty(),
+    ) {
+        return;
+    }
+    let mut changed = false;
+    if ui
+        .input_float(
+            tr("Capture interval (seconds)"),
+            &mut config.overlay_interval,
+        )
+        .step(0.5)
+        .build()
+    {
+        config.overlay_interval = config.overlay_interval.max(0.1);
+        changed = true;
+    }
+    changed |= ui.checkbox(tr("Show the translation"), &mut config.overlay_translate);
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr(
+            "Translates with the service and languages set in the Translate window",
+        ));
+    }
+    ui.slider_config(tr("Opacity"), 0.2, 1.0)
+        .display_format("%.2f")
+        .build(&mut config.overlay_opacity);
+    changed |= ui.is_item_deactivated_after_edit();
+    changed |= ui.checkbox(tr("Let clicks through"), &mut config.overlay_click_through);
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr(
+            "Clicks reach the game under the overlay, stop it with its hotkey instead of Esc",
+        ));
+    }
+    if changed {
+        if let Err(err) = config.save() {
+            error!("Error saving config: {}", err);
+        }
+    }
+}
+
+// set how much of the window shows, hiding it where the window manager cannot
+// make windows see-through
+pub fn fade(window: &mut Window, opacity: f32) {
+    if window.set_opacity(opacity.clamp(0.0, 1.0)).is_ok() {
+        return;
+    }
+    if opacity > 0.0 {
+        window.show();
+    } else {
+        window.hide();
+    }
+}
+
+// let clicks through the window to whatever is under it, or take them again
+#[cfg(target_os = "linux")]
+pub fn set_click_through(window: &Window, click_through: bool) -> Result<(), String> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use x11rb::{
+        protocol::{
+            shape::{self, SK, SO},
+            xproto::ClipOrdering,
+        },
+        NONE,
+    };
+    let handle = window.window_handle().map_err(|err| err.to_string())?;
+    let RawWindowHandle::Xlib(handle) = handle.as_raw() else {
+        return Err("Windows only let clicks through on X11".into());
+    };
+    let (connection, _) = x11rb::connect(None).map_err(|err| err.to_string())?;
+    let window = handle.window as u32;
+    // an empty input shape takes no clicks, none puts the whole window back
+    let request = if click_through {
+        shape::rectangles(
+            &connection,
+            SO::SET,
+            SK::INPUT,
+            ClipOrdering::UNSORTED,
+            window,
+            0,
+            0,
+            &[],
+        )
+    } else {
+        shape::mask(&connection, SO::SET, SK::INPUT, window, 0, 0, NONE)
+    };
+    request
+        .map_err(|err| err.to_string())?
+        .check()
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn set_click_through(window: &Window, click_through: bool) -> Result<(), String> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetWindowLongPtrW, GWL_EXSTYLE, WS_EX_LAYERED, WS_EX_TRANSPARENT,
+    };
+    let handle = window.window_handle().map_err(|err| err.to_string())?;
+    let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+        return Err("Not a Win32 window".into());
+    };
+    let hwnd = handle.hwnd.get() as *mut std::ffi::c_void;
+    // SAFETY: the handle belongs to the window, which is alive. Only layered
+    // windows let clicks through, SDL keeps the window layered for its opacity
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        let style = if click_through {
+            style | (WS_EX_LAYERED | WS_EX_TRANSPARENT) as isize
+        } else {
+            style & !(WS_EX_TRANSPARENT as isize)
+        };
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_click_through(window: &Window, click_through: bool) -> Result<(), String> {
+    use objc2::{msg_send, runtime::AnyObject};
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    let handle = window.window_handle().map_err(|err| err.to_string())?;
+    let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+        return Err("Not an AppKit window".into());
+    };
+    let view = handle.ns_view.as_ptr() as *mut AnyObject;
+    // SAFETY: the view belongs to the window, which is alive
+    unsafe {
+        let ns_window: *mut AnyObject = msg_send![view, window];
+        if ns_window.is_null() {
+            return Err("The view is in no window".into());
+        }
+        let _: () = msg_send![ns_window, setIgnoresMouseEvents: click_through];
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+pub fn set_click_through(_window: &Window, _click_through: bool) -> Result<(), String> {
+    Err("Click-through is not supported on this platform".into())
+}
+
+// make the window the overlay over the region
+fn cover(window: &mut Window, overlay: &LiveOverlay, config: &Config) {
+    let area = overlay.area();
+    window.set_bordered(false);
+    window.set_always_on_top(true);
+    window.set_position(WindowPos::Positioned(area.x), WindowPos::Positioned(area.y));
+    if let Err(err) = window.set_size(area.width, area.height) {
+        error!("Error covering the region: {}", err);
+    }
+    fade(window, config.overlay_opacity);
+    window.show();
+    if overlay.click_through {
+        if let Err(err) = set_click_through(window, true) {
+            error!("Error letting clicks through the overlay: {}", err);
+        }
+    }
+}
+
+// take the window back from being the overlay, its place and size are the
+// caller's to restore
+fn uncover(window: &mut Window, overlay: &LiveOverlay) {
+    if overlay.click_through {
+        if let Err(err) = set_click_through(window, false) {
+            error!("Error taking clicks again: {}", err);
+        }
+    }
+    fade(window, 1.0);
+    window.set_always_on_top(false);
+    window.set_bordered(true);
+}
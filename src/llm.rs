@@ -0,0 +1,82 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{DynamicImage, ImageFormat};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{error::Error, io::Cursor};
+
+/// An OpenAI-compatible chat completions API, such as OpenAI itself or a local
+/// llama.cpp server.
+pub struct ChatClient {
+    /// Base URL the `/chat/completions` path is appended to, e.g.
+    /// `http://localhost:8080/v1`.
+    pub url: String,
+    pub model: String,
+    /// Sent as a bearer token, local servers usually need none.
+    pub api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Completion {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    content: String,
+}
+
+impl ChatClient {
+    /// Send `text` with the `instructions` as system prompt and return the reply.
+    pub fn complete(&self, instructions: &str, text: &str) -> Result<String, Box<dyn Error>> {
+        self.send(instructions, json!(text))
+    }
+
+    /// Send `image` with the `instructions` as system prompt and return the
+    /// reply, the model has to accept images.
+    pub fn complete_image(
+        &self,
+        instructions: &str,
+        image: &DynamicImage,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        let data_url = format!("data:image/png;base64,{}", STANDARD.encode(&png));
+        self.send(
+            instructions,
+            json!([{ "type": "image_url", "image_url": { "url": data_url } }]),
+        )
+    }
+
+    // post a system prompt and one user message of any content
+    fn send(&self, instructions: &str, content: Value) -> Result<String, Box<dyn Error>> {
+        let url = format!("{}/chat/completions", self.url.trim_end_matches('/'));
+        let mut request = ureq::post(&url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", &format!("Bearer {}", api_key));
+        }
+        let completion: Completion = request
+            .send_json(json!({
+                "model": self.model,
+                "temperature": 0,
+                "messages": [
+                    { "role": "system", "content": instructions },
+                    { "role": "user", "content": content },
+                ],
+            }))
+            .map_err(|err| format!("Request to {} failed: {}", url, err))?
+            .into_body()
+            .read_json()
+            .map_err(|err| format!("Unexpected response from {}: {}", url, err))?;
+        let choice = completion
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Empty response from {}", url))?;
+        Ok(choice.message.content)
+    }
+}
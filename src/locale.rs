@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+// the translations of the interface, each mapping the English text to its own
+const CATALOGS: [(&str, &str); 3] = [
+    ("de", include_str!("../locales/de.toml")),
+    ("es", include_str!("../locales/es.toml")),
+    ("fr", include_str!("../locales/fr.toml")),
+];
+
+// index into CATALOGS of the language shown, past the end for English
+static CURRENT: AtomicUsize = AtomicUsize::new(CATALOGS.len());
+
+// Language of the interface, the system's unless one is picked in settings
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UiLanguage {
+    System,
+    English,
+    German,
+    Spanish,
+    French,
+}
+
+impl UiLanguage {
+    pub const ALL: [UiLanguage; 5] = [
+        UiLanguage::System,
+        UiLanguage::English,
+        UiLanguage::German,
+        UiLanguage::Spanish,
+        UiLanguage::French,
+    ];
+
+    // the name of each language in itself, so it can be found when the
+    // interface is in one the user can't read
+    pub fn label(self) -> &'static str {
+        match self {
+            UiLanguage::System => "System",
+            UiLanguage::English => "English",
+            UiLanguage::German => "Deutsch",
+            UiLanguage::Spanish => "Español",
+            UiLanguage::French => "Français",
+        }
+    }
+
+    fn code(self) -> Option<&'static str> {
+        match self {
+            UiLanguage::System => None,
+            UiLanguage::English => Some("en"),
+            UiLanguage::German => Some("de"),
+            UiLanguage::Spanish => Some("es"),
+            UiLanguage::French => Some("fr"),
+        }
+    }
+}
+
+fn catalogs() -> &'static Vec<HashMap<String, String>> {
+    static PARSED: OnceLock<Vec<HashMap<String, String>>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(code, catalog)| {
+                toml::from_str(catalog).unwrap_or_else(|err| {
+                    tracing::error!("Error reading the {} translation: {}", code, err);
+                    HashMap::new()
+                })
+            })
+            .collect()
+    })
+}
+
+// show the interface in `language`, English for a system language without
+// a translation
+pub fn set(language: UiLanguage) {
+    let system = sys_locale::get_locale();
+    let code = match language.code() {
+        Some(code) => code,
+        // "de-DE", "fr_CA.UTF-8" and the like
+        None => system.as_deref().unwrap_or("en").get(..2).unwrap_or("en"),
+    };
+    let index = CATALOGS
+        .iter()
+        .position(|(catalog, _)| code.eq_ignore_ascii_case(catalog))
+        .unwrap_or(CATALOGS.len());
+    CURRENT.store(index, Ordering::Relaxed);
+}
+
+// `text` in the language of the interface, as it is when not translated
+pub fn tr(text: &str) -> &str {
+    let index = CURRENT.load(Ordering::Relaxed);
+    match catalogs().get(index).and_then(|catalog| catalog.get(text)) {
+        Some(translated) => translated,
+        None => text,
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{locale::tr, status::Status};
+use imgui::{Condition, Ui};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    error,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    Event, Level, Subscriber,
+};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    filter::Targets,
+    layer::{Context, SubscriberExt},
+    reload,
+    util::SubscriberInitExt,
+    Layer, Registry,
+};
+
+// lines the Log window keeps, the oldest go first
+const BUFFER_CAPACITY: usize = 1000;
+
+// days of log files kept next to the current one
+const LOG_FILES: usize = 7;
+
+// How much gets logged, other crates only ever log their warnings and errors
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "Errors",
+            LogLevel::Warn => "Warnings",
+            LogLevel::Info => "Info",
+            LogLevel::Debug => "Debug",
+            LogLevel::Trace => "Trace",
+        }
+    }
+
+    fn targets(self) -> Targets {
+        let level = match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        };
+        // the app and its library share the crate name
+        Targets::new()
+            .with_default(LevelFilter::WARN.min(level))
+            .with_target(env!("CARGO_CRATE_NAME"), level)
+    }
+}
+
+struct LogLine {
+    level: Level,
+    text: String,
+}
+
+// Recent log lines, shared with the layer that records them
+type LogBuffer = Arc<Mutex<VecDeque<LogLine>>>;
+
+// Keeps every event in the buffer the Log window shows
+struct BufferLayer(LogBuffer);
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let mut text = String::new();
+        event.record(&mut LineVisitor(&mut text));
+        let Ok(mut lines) = self.0.lock() else {
+            return;
+        };
+        if lines.len() == BUFFER_CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(LogLine {
+            level: *event.metadata().level(),
+            text,
+        });
+    }
+}
+
+// Formats the message of an event followed by its other fields
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+// Logging to stderr, to a daily log file in the user's data directory and to
+// the Log window
+pub struct Logging {
+    buffer: LogBuffer,
+    filter: reload::Handle<Targets, Registry>,
+    // directory of the log files, None when logging to files failed
+    pub dir: Option<PathBuf>,
+    // flushes the file on exit
+    _guard: Option<WorkerGuard>,
+    pub open: bool,
+    // only the errors, when opened from the status bar
+    pub errors_only: bool,
+}
+
+impl Logging {
+    // start logging, once at startup
+    pub fn init(level: LogLevel) -> Self {
+        let buffer = LogBuffer::default();
+        let (filter, handle) = reload::Layer::new(level.targets());
+
+        let dir = dirs::data_dir().map(|dir| dir.join("transcribewizard").join("logs"));
+        let appender = dir.as_ref().map(|dir| {
+            RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix("transcribewizard")
+                .filename_suffix("log")
+                .max_log_files(LOG_FILES)
+                .build(dir)
+        });
+        let (file, guard, file_error) = match appender {
+            Some(Ok(appender)) => {
+                let (writer, guard) = tracing_appender::non_blocking(appender);
+                let layer = tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(writer);
+                (Some(layer), Some(guard), None)
+            }
+            Some(Err(err)) => (None, None, Some(err.to_string())),
+            None => (None, None, Some("No data directory available".into())),
+        };
+
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+            .with(file)
+            .with(BufferLayer(buffer.clone()))
+            .init();
+        if let Some(err) = &file_error {
+            error!("Error opening log file: {}", err);
+        }
+
+        Logging {
+            buffer,
+            filter: handle,
+            dir: dir.filter(|_| file_error.is_none()),
+            _guard: guard,
+            open: false,
+            errors_only: false,
+        }
+    }
+
+    pub fn set_level(&self, level: LogLevel) {
+        if let Err(err) = self.filter.reload(level.targets()) {
+            error!("Error changing log level: {}", err);
+        }
+    }
+
+    // the Log window, returns the new level when it was changed there. Clearing
+    // it clears the errors of the status bar too
+    pub fn draw(&mut self, ui: &Ui, level: LogLevel, status: &mut Status) -> Option<LogLevel> {
+        if !self.open {
+            return None;
+        }
+        let mut changed = None;
+        let mut open = true;
+        ui.window(format!("{}###Log", tr("Log")))
+            .size([640.0, 360.0], Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                let mut level_index = LogLevel::ALL
+                    .iter()
+                    .position(|known| *known == level)
+                    .unwrap_or(0);
+                let level_labels = LogLevel::ALL.map(LogLevel::label);
+                ui.set_next_item_width(120.0);
+                if ui.combo_simple_string(tr("Level"), &mut level_index, &level_labels) {
+                    changed = Some(LogLevel::ALL[level_index]);
+                }
+                ui.same_line();
+                ui.checkbox(tr("Errors only"), &mut self.errors_only);
+                ui.same_line();
+                let Ok(mut lines) = self.buffer.lock() else {
+                    return;
+                };
+                if ui.button(tr("Copy all")) {
+                    let all: Vec<String> = lines
+                        .iter()
+                        .filter(|line| !self.errors_only || line.level == Level::ERROR)
+                        .map(|line| format!("{} {}", line.level, line.text))
+                        .collect();
+                    ui.set_clipboard_text(all.join("\n"));
+                }
+                ui.same_line();
+                if ui.button(tr("Clear")) {
+                    lines.clear();
+                    status.clear();
+                }
+                if let Some(dir) = &self.dir {
+                    ui.same_line();
+                    ui.text_disabled(dir.display().to_string());
+                }
+                ui.separator();
+
+                ui.child_window("lines").build(|| {
+                    // follow new lines unless scrolled up to read older ones
+                    let at_bottom = ui.scroll_y() >= ui.scroll_max_y();
+                    for line in lines
+                        .iter()
+                        .filter(|line| !self.errors_only || line.level == Level::ERROR)
+                    {
+                        let color = match line.level {
+                            Level::ERROR => [1.0, 0.4, 0.4, 1.0],
+                            Level::WARN => [1.0, 0.8, 0.3, 1.0],
+                            Level::INFO => ui.style_color(imgui::StyleColor::Text),
+                            _ => ui.style_color(imgui::StyleColor::TextDisabled),
+                        };
+                        ui.text_colored(color, format!("{:>5} {}", line.level, line.text));
+                    }
+                    if at_bottom {
+                        ui.set_scroll_here_y_with_ratio(1.0);
+                    }
+                });
+            });
+        self.open = open;
+        changed
+    }
+}
@@ -1,209 +1,5587 @@
-use clipboard_rs::{common::RustImage, Clipboard, ClipboardContext, ContentFormat};
-use image::DynamicImage;
-use imgui::Context;
+mod about;
+mod accessibility;
+mod anonymization;
+mod batch;
+mod book_panel;
+mod capture;
+mod cleanup;
+mod clipboard;
+mod code_panel;
+mod compare;
+mod compose;
+mod config;
+mod delivery;
+mod extraction;
+mod file_jobs;
+mod find;
+mod flow;
+mod folder;
+mod history;
+mod instance;
+mod jobs;
+mod keymap;
+mod line_panel;
+mod link_panel;
+mod live_overlay;
+mod locale;
+mod logging;
+mod markup;
+mod masking;
+mod models;
+mod notification;
+mod onboarding;
+mod overlay;
+mod perf_hud;
+mod pinned;
+mod plugins;
+mod preprocessing;
+mod preview;
+mod projects;
+mod proofread;
+#[cfg(feature = "camera")]
+mod scanner;
+#[cfg(target_os = "linux")]
+mod screenshot;
+mod scrolling;
+#[cfg(feature = "archive")]
+mod search;
+mod serve;
+mod session;
+mod side_by_side;
+#[cfg(feature = "tts")]
+mod speech;
+mod spellcheck;
+mod startup;
+mod statistics;
+mod status;
+mod stream;
+mod tabs;
+mod text_area;
+mod texture;
+mod theme;
+mod transforms;
+mod translation;
+mod tray;
+mod undo;
+mod watch;
+mod webhook;
+
+use about::AboutPanel;
+use accessibility::ScreenReader;
+use anonymization::AnonymizationPanel;
+use batch::BatchJob;
+use book_panel::BookPanel;
+use cleanup::Cleanup;
+use clipboard::{ClipboardProvider, SystemClipboard};
+use clipboard_rs::{common::RustImage, ContentFormat};
+use code_panel::CodePanel;
+use compare::ComparePanel;
+use compose::{ComposeAction, ComposePanel};
+use config::{Config, OutputMode, Profile, SecretHandling, Selection};
+use delivery::Delivery;
+use extraction::ExtractionPanel;
+use file_jobs::FileJobs;
+use folder::FolderWatch;
+use global_hotkey::{hotkey::HotKey, GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use history::{History, HistoryEntry, HISTORY_CAPACITY};
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use imgui::{Condition, Context, Key, TreeNodeFlags};
 use imgui_glow_renderer::{
     glow::{self, HasContext},
     AutoRenderer,
 };
 use imgui_sdl2_support::SdlPlatform;
-use ocrs::{ImageSource, OcrEngine, OcrEngineParams};
-use rten::Model;
+use instance::{Command, Reply};
+use jobs::{draw_jobs, Background, Job};
+use keymap::Action;
+use line_panel::LinePanel;
+use link_panel::LinkPanel;
+use live_overlay::Live;
+use locale::{tr, UiLanguage};
+use logging::Logging;
+use masking::SecretGuard;
+use models::{model_row, pick_model, ModelsDialog};
+use notification::Notifier;
+use onboarding::{Onboarding, OnboardingAction};
+use overlay::Overlay;
+use perf_hud::PerfHud;
+use pinned::PinnedRegion;
+use plugins::PluginRunner;
+use preview::Preview;
+use projects::{save_dialog, ProjectSettings};
+use proofread::ProofreadView;
 #[allow(unused)]
 use rten_tensor::prelude::*;
+#[cfg(feature = "camera")]
+use scanner::{Scanner, ScannerAction};
+use scrolling::{ScrollAction, ScrollCapture};
 use sdl2::{
-    event::Event,
-    video::{GLProfile, Window},
+    event::{Event, WindowEvent},
+    video::{GLContext, GLProfile, Window, WindowPos},
+    VideoSubsystem,
 };
-use std::error::Error;
-
-// Convert an image to a string using OCRengine
-fn image_to_str(engine: &OcrEngine, image: &DynamicImage) -> Result<String, Box<dyn Error>> {
-    let image_rgb = image.to_rgb8();
-    let image_source = ImageSource::from_bytes(image_rgb.as_raw(), image_rgb.dimensions())?;
-    let ocr_input = engine.prepare_input(image_source)?;
-    let word_rects = engine.detect_words(&ocr_input)?;
-    let line_rects = engine.find_text_lines(&ocr_input, &word_rects);
-    let line_texts = engine.recognize_text(&ocr_input, &line_rects)?;
-
-    Ok(line_texts
-        .into_iter()
+#[cfg(feature = "archive")]
+use search::ArchiveSearch;
+use session::Session;
+use side_by_side::SideBySide;
+#[cfg(feature = "tts")]
+use speech::Reader;
+use spellcheck::SpellcheckView;
+use startup::Subsystem;
+use statistics::StatisticsPanel;
+use status::Status;
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    error::Error,
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    mem,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use stream::{stream_clipboard, StreamTargets, Streamed, Streaming};
+use tabs::{Editor, Panels, Tabs};
+use text_area::TextArea;
+use texture::Texture;
+use theme::Theme;
+use tracing::{error, info, warn};
+#[cfg(feature = "archive")]
+use transcribewizard::archive::Archive;
+#[cfg(feature = "whisper")]
+use transcribewizard::audio::{self, AudioTranscript};
+#[cfg(feature = "dictation")]
+use transcribewizard::audio::{dictation::Dictation, Transcriber};
+#[cfg(feature = "tesseract")]
+use transcribewizard::backend::TesseractBackend;
+use transcribewizard::{
+    annotate,
+    backend::{
+        self, CloudChoice, FallbackChain, OcrBackend, OcrsBackend, BACKENDS, CLOUD_BACKENDS,
+    },
+    cache::{CachedBackend, ResultCache},
+    digest::ImageDigest,
+    ensemble::{self, Ensemble},
+    evaluate,
+    export::{self, ExportFormat},
+    files,
+    filter::{FilteredBackend, LineFilter},
+    formula, import, journal,
+    layout::TextLayout,
+    merge,
+    metadata::ImageMetadata,
+    ocr, pdf,
+    pool::{BackendFactory, WorkerPool},
+    preprocess::ColorFilter,
+    source_code::CodeLanguage,
+    spelling::SpellChecker,
+    stats::{self, DetectedLanguage},
+    subtitle::{self, Cue},
+    template, video, TranscribeError, TranscriptionResult,
+};
+use tray::Tray;
+use undo::{UndoStack, UNDO_CAPACITY};
+use watch::ClipboardWatch;
+use webhook::Webhook;
+
+// what a GIF is called on the clipboard of X11 and Wayland, Windows and macOS
+const GIF_FORMATS: [&str; 3] = ["image/gif", "GIF", "com.compuserve.gif"];
+
+// encoded images on the clipboard of X11 and Wayland, Windows and macOS, PNG
+// first as every app that offers others offers it too
+const ENCODED_IMAGE_FORMATS: [&str; 10] = [
+    "image/png",
+    "PNG",
+    "public.png",
+    "public.tiff",
+    "image/webp",
+    "org.webmproject.webp",
+    "image/heic",
+    "public.heic",
+    "image/avif",
+    "image/svg+xml",
+];
+
+// OpenGL versions tried for the window, newest first, the renderer works with all of them
+const GL_VERSIONS: [(u8, u8, GLProfile); 3] = [
+    (3, 3, GLProfile::Core),
+    (3, 0, GLProfile::Compatibility),
+    (2, 1, GLProfile::Compatibility),
+];
+
+// longest the loop sleeps while idle, background results show up at least this often
+const IDLE_TIMEOUT: Duration = Duration::from_millis(250);
+
+// frames still drawn after input, hover states and the like take a frame or two to settle
+const SETTLE_FRAMES: u32 = 3;
+
+// time the window gets to disappear before the screen is captured for snipping
+const SNIP_DELAY: Duration = Duration::from_millis(300);
+
+// how long the text has to stay the same before it is checked as code again,
+// so typing in a long snippet does not parse it on every key
+const SYNTAX_DELAY: Duration = Duration::from_millis(500);
+
+// timed transcriptions of the benchmark, averaged
+const BENCHMARK_RUNS: u32 = 3;
+
+// What the region picked in the snipping overlay is for
+#[derive(Clone, Copy, PartialEq)]
+enum SnipPurpose {
+    Transcribe,
+    // captured while it scrolls, the frames stitched into one image
+    Scrolling,
+    // read over and over with the text shown on top
+    LiveOverlay,
+    // read over and over with new text appended to the transcript
+    Pin,
+    // read over and over with every caption line timed as a cue
+    Captions,
+    // read as the next section of the entry being composed
+    Compose,
+}
+
+// Text read from the clipboard, with the image it was recognized from if any
+struct Capture {
+    text: String,
+    image: Option<DynamicImage>,
+    transcription: Option<TranscriptionResult>,
+    // every page with the image it was read from, when the text was read from
+    // several and `transcription` is None
+    pages: Vec<TranscriptionResult>,
+    // where the text was recognized from for the journal, None when it was
+    // copied as text and not recognized at all
+    note: Option<String>,
+}
+
+// the text of a capture as it was recorded, its transcription with the image it
+// was read from and the pages when it was read from several
+type Recorded = (
+    String,
+    Option<TranscriptionResult>,
+    Vec<TranscriptionResult>,
+);
+
+// path of a file as the clipboard lists it, a file:// URI on most platforms
+fn path_from_uri(uri: &str) -> PathBuf {
+    let Some(path) = uri.strip_prefix("file://") else {
+        return PathBuf::from(uri);
+    };
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let path = String::from_utf8_lossy(&bytes).into_owned();
+    // file:///C:/dir names a drive on Windows
+    #[cfg(windows)]
+    let path = path.strip_prefix('/').map(str::to_string).unwrap_or(path);
+    PathBuf::from(path)
+}
+
+// transcribe copied image and PDF files, headed by their names when there are several
+fn files_capture(backend: &dyn OcrBackend, paths: &[PathBuf], config: &Config) -> Capture {
+    let mut sections = Vec::new();
+    let mut image = None;
+    let mut transcriptions = Vec::new();
+    for path in paths {
+        let text =
+            match files::transcribe_file(backend, path, &config.preprocessing, config.conversion())
+            {
+                Ok(pages) => {
+                    let mut texts = Vec::new();
+                    for (page, mut transcription) in pages {
+                        texts.push(config.render(&transcription));
+                        image.get_or_insert_with(|| page.clone());
+                        transcription.image = Some(Arc::new(page));
+                        transcriptions.push(transcription);
+                    }
+                    files::join_pages(path, &texts)
+                }
+                Err(err) => format!("Error: {}", err),
+            };
+        if paths.len() > 1 {
+            let name = path.file_name().map_or(path.display().to_string(), |name| {
+                name.to_string_lossy().into_owned()
+            });
+            sections.push(format!("--- {} ---\n{}", name, text));
+        } else {
+            sections.push(text);
+        }
+    }
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            path.file_name().map_or(path.display().to_string(), |name| {
+                name.to_string_lossy().into_owned()
+            })
+        })
+        .collect();
+    // boxes only make sense for a single page
+    let (transcription, pages) = match transcriptions.len() {
+        1 => (transcriptions.pop(), Vec::new()),
+        _ => (None, transcriptions),
+    };
+    Capture {
+        text: sections.join("\n\n"),
+        image,
+        transcription,
+        pages,
+        note: Some(names.join(", ")),
+    }
+}
+
+// the image to show in the preview, a copied image file upright and with its
+// metadata before the image on the clipboard itself
+fn clipboard_preview_image(
+    clipboard_context: &SystemClipboard,
+    config: &Config,
+) -> Result<(DynamicImage, ImageMetadata), String> {
+    let copied = clipboard_context
+        .has(ContentFormat::Files)
+        .then(|| clipboard_context.get_files().ok())
         .flatten()
-        .filter(|line| line.to_string().len() > 1)
-        .map(|line| line.to_string())
-        .collect::<Vec<String>>()
-        .join(" "))
+        .and_then(|uris| {
+            uris.iter()
+                .map(|uri| path_from_uri(uri))
+                .find(|path| files::is_image(path))
+        });
+    match copied {
+        Some(path) => files::open_image(&path, config.conversion()).map_err(|err| err.to_string()),
+        None => clipboard_image(clipboard_context, config)
+            .map(|image| (image, ImageMetadata::default()))
+            .map_err(|err| err.to_string()),
+    }
+}
+
+// the clipboard format holding a GIF, None when there is none
+fn clipboard_gif_format(clipboard_context: &SystemClipboard) -> Option<&'static str> {
+    let formats = clipboard_context.available_formats().ok()?;
+    GIF_FORMATS
+        .iter()
+        .find(|gif| formats.iter().any(|format| format == *gif))
+        .copied()
+}
+
+// the frames of the image on the clipboard, every one of an animated GIF and
+// the only one of any other image
+fn clipboard_frames(
+    clipboard_context: &SystemClipboard,
+    config: &Config,
+) -> Result<Vec<DynamicImage>, TranscribeError> {
+    let gif = clipboard_gif_format(clipboard_context)
+        .and_then(|format| clipboard_context.get_buffer(format).ok());
+    match gif {
+        Some(bytes) => config.clipboard_limits().decode_gif(&bytes),
+        None => Ok(vec![clipboard_image(clipboard_context, config)?]),
+    }
+}
+
+fn clipboard_image(
+    clipboard_context: &SystemClipboard,
+    config: &Config,
+) -> Result<DynamicImage, TranscribeError> {
+    let limits = config.clipboard_limits();
+    /* the encoded image where the clipboard has one, its size is read before any pixels */
+    let formats = clipboard_context.available_formats().unwrap_or_default();
+    let encoded = ENCODED_IMAGE_FORMATS
+        .iter()
+        .find(|encoded| formats.iter().any(|format| format == *encoded));
+    if let Some(bytes) = encoded.and_then(|format| clipboard_context.get_buffer(format).ok()) {
+        return limits.decode(&bytes, config.svg_dpi);
+    }
+
+    /* bitmaps the platform decodes, checked before they are copied out */
+    let image_data = clipboard_context.get_image().map_err(|err| {
+        TranscribeError::Clipboard(format!("Failed to get image from clipboard: {}", err))
+    })?;
+    let unsupported = |err| TranscribeError::UnsupportedFormat(format!("clipboard image: {}", err));
+    let (width, height) = image_data.get_size();
+    limits.check(width, height)?;
+    /* scaled down before it is copied out, a large bitmap is never copied whole */
+    let (shrunk_width, shrunk_height) = limits.shrunk_size(width, height);
+    let image_data = match (shrunk_width, shrunk_height) == (width, height) {
+        true => image_data,
+        false => image_data
+            .resize(shrunk_width, shrunk_height, FilterType::Triangle)
+            .map_err(unsupported)?,
+    };
+    image_data.get_dynamic_image().map_err(unsupported)
+}
+
+// have the vision model read the formulas of a transcription as LaTeX when
+// turned on, keeping them as recognized when it cannot be reached
+fn recognize_formulas(
+    config: &Config,
+    image: &DynamicImage,
+    transcription: &mut TranscriptionResult,
+) {
+    if !config.formulas {
+        return;
+    }
+    let client = config.chat_client(&config.formula_model);
+    match formula::convert(&client, image, transcription) {
+        Ok(converted) => info!("Read {} formulas as LaTeX", converted),
+        Err(err) => warn!("Error reading formulas: {}", err),
+    }
+}
+
+// read the formulas of a new transcription as LaTeX on a worker thread when
+// turned on, None when there is nothing to read. The job has the transcription
+// with the formulas replaced, None when none was
+fn spawn_formulas(
+    config: &Config,
+    transcription: &TranscriptionResult,
+) -> Option<Job<Option<TranscriptionResult>>> {
+    if !config.formulas {
+        return None;
+    }
+    let image = transcription.image.clone()?;
+    if !transcription
+        .lines
+        .iter()
+        .any(|line| formula::is_formula(&line.text))
+    {
+        return None;
+    }
+    let client = config.chat_client(&config.formula_model);
+    let mut transcription = transcription.clone();
+    Some(Job::spawn(tr("Reading formulas").into(), move |_, _| {
+        let converted =
+            formula::convert(&client, &image, &mut transcription).map_err(|err| err.to_string())?;
+        info!("Read {} formulas as LaTeX", converted);
+        Ok((converted > 0).then_some(transcription))
+    }))
 }
 
-// get and convert content from clipboard
-fn clipboard_str(
-    engine: &OcrEngine,
-    clipboard_context: &ClipboardContext,
-) -> Result<String, Box<dyn std::error::Error>> {
+// get and convert content from clipboard, `read` is its image when that was
+// read already
+fn clipboard_capture(
+    backend: &dyn OcrBackend,
+    clipboard_context: &SystemClipboard,
+    config: &Config,
+    read: Option<DynamicImage>,
+) -> Result<Capture, TranscribeError> {
+    /* files copied in a file manager, checked first as their paths come as text too */
+    if clipboard_context.has(ContentFormat::Files) {
+        let uris = match clipboard_context.get_files() {
+            Ok(uris) => uris,
+            Err(err) => {
+                return Err(TranscribeError::Clipboard(format!(
+                    "Failed to get files from clipboard: {}",
+                    err
+                )))
+            }
+        };
+        let paths: Vec<PathBuf> = uris
+            .iter()
+            .map(|uri| path_from_uri(uri))
+            .filter(|path| files::is_supported(path))
+            .collect();
+        info!(
+            "Clipboard holds {} files, {} of them supported",
+            uris.len(),
+            paths.len()
+        );
+        if !paths.is_empty() {
+            return Ok(files_capture(backend, &paths, config));
+        }
+    }
+
     if clipboard_context.has(ContentFormat::Text) {
         match clipboard_context.get_text() {
-            Ok(text) => return Ok(text),
-            Err(err) => return Err(format!("Failed to get text from clipboard: {}", err).into()),
+            Ok(text) => {
+                info!("Read {} bytes of text from clipboard", text.len());
+                return Ok(Capture {
+                    text,
+                    image: None,
+                    transcription: None,
+                    pages: Vec::new(),
+                    note: None,
+                });
+            }
+            Err(err) => {
+                return Err(TranscribeError::Clipboard(format!(
+                    "Failed to get text from clipboard: {}",
+                    err
+                )))
+            }
         }
     }
 
-    if clipboard_context.has(ContentFormat::Image) {
-        let image_data = match clipboard_context.get_image() {
-            Ok(image) => image,
-            Err(err) => return Err(format!("Failed to get image from clipboard: {}", err).into()),
-        };
-        let image = match image_data.get_dynamic_image() {
-            Ok(image) => image,
+    /* a GIF of several frames is read on the pool, see stream_clipboard */
+    let mut frames = match read {
+        Some(image) => vec![image],
+        None if clipboard_context.has(ContentFormat::Image) => {
+            vec![clipboard_image(clipboard_context, config)?]
+        }
+        None => Vec::new(),
+    };
+    if let Some(image) = frames.pop() {
+        info!(
+            "Read {}x{} image from clipboard",
+            image.width(),
+            image.height()
+        );
+        match ocr::transcribe_preprocessed(backend, &image, &config.preprocessing) {
+            Ok(transcription) => {
+                return Ok(Capture {
+                    text: config.render(&transcription),
+                    image: Some(image),
+                    transcription: Some(transcription),
+                    pages: Vec::new(),
+                    note: Some("clipboard image".into()),
+                });
+            }
+            Err(err) => return Err(TranscribeError::Ocr(err.to_string())),
+        }
+    }
+
+    /* rich text copied without a plain text version, from browsers or word processors */
+    let markup = if clipboard_context.has(ContentFormat::Html) {
+        match clipboard_context.get_html() {
+            Ok(html) => {
+                info!("Read {} bytes of HTML from clipboard", html.len());
+                Some(markup::html_to_text(&html, config.html_to_markdown))
+            }
             Err(err) => {
-                return Err(
-                    format!("Failed to convert image data to dynamic image: {}", err).into(),
-                )
+                return Err(TranscribeError::Clipboard(format!(
+                    "Failed to get HTML from clipboard: {}",
+                    err
+                )))
+            }
+        }
+    } else if clipboard_context.has(ContentFormat::Rtf) {
+        match clipboard_context.get_rich_text() {
+            Ok(rtf) => {
+                info!("Read {} bytes of RTF from clipboard", rtf.len());
+                Some(markup::rtf_to_text(&rtf))
+            }
+            Err(err) => {
+                return Err(TranscribeError::Clipboard(format!(
+                    "Failed to get RTF from clipboard: {}",
+                    err
+                )))
             }
-        };
-        match image_to_str(engine, &image) {
-            Ok(text) => return Ok(text),
-            Err(err) => return Err(format!("Failed to extract text from image: {}", err).into()),
         }
+    } else {
+        None
+    };
+    if let Some(text) = markup {
+        return Ok(Capture {
+            text,
+            image: None,
+            transcription: None,
+            pages: Vec::new(),
+            note: None,
+        });
     }
 
-    Err("Unhandled clipboard content: neither text nor image".into())
+    Err(TranscribeError::ClipboardEmpty)
 }
 
-// Create a new glow context.
-fn glow_context(window: &Window) -> glow::Context {
-    unsafe {
-        glow::Context::from_loader_function(|s| window.subsystem().gl_get_proc_address(s) as _)
+// transcribe the clipboard and record the result in the history, optionally
+// putting the recognized text back on the clipboard, `read` is its image when
+// that was read already
+fn transcribe_clipboard(
+    backend: &dyn OcrBackend,
+    clipboard_context: &SystemClipboard,
+    config: &Config,
+    read: Option<DynamicImage>,
+    renderer: &mut AutoRenderer,
+    history: &mut History,
+    copy_back: Option<&mut SecretGuard>,
+) -> Result<Recorded, TranscribeError> {
+    let capture = clipboard_capture(backend, clipboard_context, config, read)?;
+    record_capture(
+        capture,
+        config,
+        clipboard_context,
+        renderer,
+        history,
+        copy_back,
+    )
+}
+
+// a file or GIF read on the pool, the capture or what went wrong
+type FileJob = Receiver<Result<Capture, String>>;
+
+// a clipboard image read on the pool in watch mode, sent back with what was read
+type WatchJob = Receiver<(DynamicImage, Result<TranscriptionResult, String>)>;
+
+// read the frames of an animated GIF, a frame showing the same as the one
+// before it only once and at most files::MAX_READ_FRAMES of them
+fn frames_capture(
+    backend: &dyn OcrBackend,
+    frames: Vec<DynamicImage>,
+    config: &Config,
+) -> Result<Capture, String> {
+    let sources = files::frame_sources(&frames);
+    if sources.len() < frames.len() {
+        info!(
+            "Reading only the first {} of {} frames",
+            sources.len(),
+            frames.len()
+        );
+    }
+    let mut texts: Vec<String> = Vec::new();
+    for (index, source) in sources.into_iter().enumerate() {
+        if source != index {
+            texts.push(texts[source].clone());
+            continue;
+        }
+        let frame = &frames[index];
+        let mut transcription = ocr::transcribe_preprocessed(backend, frame, &config.preprocessing)
+            .map_err(|err| err.to_string())?;
+        recognize_formulas(config, frame, &mut transcription);
+        texts.push(config.render(&transcription));
     }
+    Ok(Capture {
+        text: files::join_frames(Some(ImageFormat::Gif), &texts),
+        image: frames.into_iter().next(),
+        // boxes only make sense for a single frame
+        transcription: None,
+        pages: Vec::new(),
+        note: Some("clipboard image".into()),
+    })
 }
 
-fn main() {
-    /* initialize SDL and its video subsystem */
-    let sdl = sdl2::init().unwrap();
-    let video_subsystem = sdl.video().unwrap();
+// put a capture in the history, and on the clipboard through `copy_back` when given
+fn record_capture(
+    capture: Capture,
+    config: &Config,
+    clipboard_context: &SystemClipboard,
+    renderer: &mut AutoRenderer,
+    history: &mut History,
+    copy_back: Option<&mut SecretGuard>,
+) -> Result<Recorded, TranscribeError> {
+    if let Some(guard) = copy_back {
+        let copied = config.templated(
+            &capture.text,
+            capture.note.as_deref(),
+            capture.transcription.as_ref(),
+        );
+        if let Err(err) = guard.copy(clipboard_context, &copied) {
+            return Err(TranscribeError::Clipboard(format!(
+                "Failed to set text to clipboard: {}",
+                err
+            )));
+        }
+    }
+    let thumbnail_image = capture.image.as_ref().map(history::thumbnail);
+    // the image and where it came from go with the transcription, wherever it
+    // ends up
+    let image = capture.image.map(Arc::new);
+    let mut transcription = capture.transcription;
+    if let Some(transcription) = &mut transcription {
+        transcription.image.clone_from(&image);
+        transcription.source.clone_from(&capture.note);
+    }
+    let mut pages = capture.pages;
+    for page in &mut pages {
+        page.source.clone_from(&capture.note);
+    }
+    let thumbnail =
+        thumbnail_image
+            .as_ref()
+            .and_then(|image| match Texture::upload(renderer, image) {
+                Ok(texture) => Some(texture),
+                Err(err) => {
+                    error!("Error creating history thumbnail: {}", err);
+                    None
+                }
+            });
+    remember(
+        history,
+        renderer,
+        HistoryEntry {
+            text: capture.text.clone(),
+            transcription: transcription.clone().map(history::kept),
+            thumbnail,
+            thumbnail_image,
+        },
+        image,
+        capture.note.as_deref(),
+    );
+    Ok((capture.text, transcription, pages))
+}
+
+// a transcription saved as hOCR or ALTO with the image it was read from, found
+// next to it or picked, to proofread and export again, one line per line
+fn open_ocr_file(path: &Path, config: &Config) -> Result<Capture, Box<dyn Error>> {
+    let imported = import::load(path)?;
+    let image_path = import::find_image(path, imported.image.as_deref()).or_else(|| {
+        rfd::FileDialog::new()
+            .set_title("Image the OCR file was read from")
+            .add_filter("Images", files::IMAGE_EXTENSIONS)
+            .pick_file()
+    });
+    let image = match image_path {
+        Some(image_path) => Some(files::open_image(&image_path, config.conversion())?.0),
+        None => None,
+    };
+    let mut transcription = imported.transcription;
+    if let Some(image) = &image {
+        if transcription.width == 0 || transcription.height == 0 {
+            transcription.width = image.width();
+            transcription.height = image.height();
+        } else if (transcription.width, transcription.height) != (image.width(), image.height()) {
+            // ALTO measured in millimetres or inches, or a scaled copy of the image
+            transcription.rescale(image.width(), image.height());
+        }
+    }
+    let text = transcription
+        .lines
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(Capture {
+        text,
+        image,
+        transcription: Some(transcription),
+        pages: Vec::new(),
+        note: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned()),
+    })
+}
 
-    /* hint SDL to initialize an OpenGL 3.3 core profile context */
-    let gl_attr = video_subsystem.gl_attr();
+// transcribe a region picked from the preview or the screen and record it in the history
+fn transcribe_region(
+    backend: &dyn OcrBackend,
+    region: DynamicImage,
+    config: &Config,
+    clipboard_context: &SystemClipboard,
+    renderer: &mut AutoRenderer,
+    history: &mut History,
+    secret_guard: &mut SecretGuard,
+) -> Result<Recorded, TranscribeError> {
+    let result_transcription =
+        ocr::transcribe_preprocessed(backend, &region, &config.preprocessing)
+            .map_err(|err| TranscribeError::Ocr(err.to_string()))?;
+    let capture = Capture {
+        text: config.render(&result_transcription),
+        image: Some(region),
+        transcription: Some(result_transcription),
+        pages: Vec::new(),
+        note: Some("screen".into()),
+    };
+    record_capture(
+        capture,
+        config,
+        clipboard_context,
+        renderer,
+        history,
+        config.auto_copy.then_some(secret_guard),
+    )
+}
 
-    gl_attr.set_context_version(3, 3);
-    gl_attr.set_context_profile(GLProfile::Core);
+// where each display sits on the desktop, in the order SDL numbers them
+fn display_areas(video_subsystem: &VideoSubsystem) -> Result<Vec<capture::Area>, String> {
+    let mut areas = Vec::new();
+    for display in 0..video_subsystem.num_video_displays()? {
+        let bounds = video_subsystem.display_bounds(display)?;
+        areas.push(capture::Area {
+            x: bounds.x(),
+            y: bounds.y(),
+            width: bounds.width(),
+            height: bounds.height(),
+        });
+    }
+    Ok(areas)
+}
 
-    /* create a new window, be sure to call opengl method on the builder when using glow! */
-    let window = video_subsystem
-        .window("Hello imgui-rs!", 1280, 720)
-        .allow_highdpi()
-        .opengl()
-        .position_centered()
-        .resizable()
-        .build()
-        .unwrap();
+// the area all displays cover together, where the snipping overlay goes
+fn desktop_area(video_subsystem: &VideoSubsystem) -> Result<capture::Area, String> {
+    capture::Area::union(display_areas(video_subsystem)?).ok_or_else(|| "No displays".to_string())
+}
 
-    /* create a new OpenGL context and make it current */
-    let gl_context = window.gl_create_context().unwrap();
-    window.gl_make_current(&gl_context).unwrap();
+// capture every monitor on a worker thread, once the window had time to get out of the way
+fn spawn_snip(desktop: capture::Area) -> Receiver<Result<Vec<capture::Shot>, String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        thread::sleep(SNIP_DELAY);
+        let _ = sender.send(capture::capture_monitors(desktop).map_err(|err| err.to_string()));
+    });
+    receiver
+}
 
-    /* enable vsync to cap framerate */
-    window.subsystem().gl_set_swap_interval(1).unwrap();
+// a screen capture taken on a worker thread
+type CaptureJob = Receiver<Result<DynamicImage, String>>;
 
-    /* create new glow and imgui contexts */
-    let gl = glow_context(&window);
+// capture the focused window on a worker thread, once ours had time to give the focus back
+fn spawn_window_capture() -> CaptureJob {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        thread::sleep(SNIP_DELAY);
+        let _ = sender.send(capture::capture_active_window().map_err(|err| err.to_string()));
+    });
+    receiver
+}
 
-    /* create context */
-    let mut imgui = Context::create();
+// capture the whole of display `monitor` on a worker thread, after the window got out of the way
+fn spawn_monitor_capture(
+    video_subsystem: &VideoSubsystem,
+    monitor: usize,
+) -> Result<CaptureJob, String> {
+    let areas = display_areas(video_subsystem)?;
+    let area = *areas
+        .get(monitor)
+        .ok_or_else(|| format!("There is no monitor {}", monitor + 1))?;
+    let desktop = capture::Area::union(areas).ok_or("No displays")?;
+    let rect = [
+        area.x as f32,
+        area.y as f32,
+        area.right() as f32,
+        area.bottom() as f32,
+    ];
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        thread::sleep(SNIP_DELAY);
+        let captured = capture::capture_monitors(desktop)
+            .map_err(|err| err.to_string())
+            .and_then(|shots| {
+                capture::cut(&shots, rect).ok_or_else(|| "Monitor is off screen".into())
+            });
+        let _ = sender.send(captured);
+    });
+    Ok(receiver)
+}
 
-    /* disable creation of files on disc */
-    imgui.set_ini_filename(None);
-    imgui.set_log_filename(None);
+// the database every transcription is kept in
+#[cfg(feature = "archive")]
+fn open_archive() -> Result<Archive, Box<dyn Error>> {
+    Archive::open(&Config::archive_path().ok_or("No data directory")?)
+}
 
-    /* setup platform and renderer, and fonts to imgui */
-    imgui
-        .fonts()
-        .add_font(&[imgui::FontSource::DefaultFontData { config: None }]);
+// add an entry to the history, freeing the thumbnail of the one it pushes out,
+// to the archive with the hash of the image it was read from and to the
+// journal with `note` when it was recognized
+fn remember(
+    history: &mut History,
+    renderer: &mut AutoRenderer,
+    entry: HistoryEntry,
+    source: Option<Arc<DynamicImage>>,
+    note: Option<&str>,
+) {
+    #[cfg(feature = "archive")]
+    if let Some(archive) = history.archive() {
+        if let Err(err) = archive.add(
+            &entry.text,
+            source.as_deref(),
+            entry.thumbnail_image.as_ref(),
+        ) {
+            error!("Error archiving transcription: {}", err);
+        }
+    }
+    if let (Some(dir), Some(note)) = (history.journal(), note) {
+        if let Err(err) = journal::append(dir, &entry.text, Some(note)) {
+            error!("Error writing to the journal: {}", err);
+        }
+    }
+    history.set_last_image(source);
+    if let Some(texture) = history.push(entry).and_then(|entry| entry.thumbnail) {
+        texture.destroy(renderer);
+    }
+}
 
-    /* create platform and renderer */
-    let mut platform = SdlPlatform::new(&mut imgui);
-    let mut renderer = AutoRenderer::new(gl, &mut imgui).unwrap();
+// show an image in the preview, replacing the one there before it is drawn
+fn open_preview(
+    preview: &mut Option<Preview>,
+    renderer: &mut AutoRenderer,
+    image: DynamicImage,
+) -> Result<(), String> {
+    let opened = Preview::new(renderer, image)?;
+    if let Some(previous) = preview.replace(opened) {
+        previous.destroy(renderer);
+    }
+    Ok(())
+}
 
-    // /* setup OCR context */
-    // let detection_model_path = file_path("src/text-detection.rten");
-    // let rec_model_path = file_path("src/text-recognition.rten");
+// path of an audio file copied as text, e.g. from a terminal or file manager
+#[cfg(feature = "whisper")]
+fn clipboard_audio_path(clipboard_context: &SystemClipboard) -> Option<PathBuf> {
+    if !clipboard_context.has(ContentFormat::Text) {
+        return None;
+    }
+    let text = clipboard_context.get_text().ok()?;
+    let text = text.trim();
+    let path = path_from_uri(text);
+    (audio::is_audio_file(&path) && path.is_file()).then_some(path)
+}
 
-    let detection_model = match Model::load_file("text-detection.rten") {
-        Ok(model) => model,
-        Err(err) => {
-            eprintln!("Error loading detection model: {}", err);
-            std::process::exit(1)
+// transcribe an audio file as a job, whisper takes a while
+#[cfg(feature = "whisper")]
+fn spawn_audio_transcription(config: &Config, path: PathBuf) -> Job<AudioTranscript> {
+    let model = config.whisper_model.clone();
+    let language = config.whisper_language.clone();
+    let speakers = config.speaker_labels;
+    let name = job_name("Transcribing", &path);
+    Job::spawn(name, move |control, on_progress| {
+        audio::transcribe_file(&model, &language, speakers, &path, control, on_progress)
+            .map_err(|err| err.to_string())
+    })
+}
+
+// what the Jobs window calls a job working on a file
+fn job_name(doing: &str, path: &Path) -> String {
+    let file = path.file_name().unwrap_or(path.as_os_str());
+    format!("{} {}", doing, file.to_string_lossy())
+}
+
+// time the configured backend on an image on a worker thread, without the
+// cache which would answer every run after the first
+fn spawn_benchmark(config: &Config, image: DynamicImage) -> Receiver<Result<String, String>> {
+    let config = config.clone();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let result = load_backend(&config, &None)
+            .and_then(|backend| backend::benchmark(backend.as_ref(), &image, BENCHMARK_RUNS))
+            .map(|time| {
+                format!(
+                    "{} ms per {}x{} image with {}",
+                    time.as_millis(),
+                    image.width(),
+                    image.height(),
+                    config.backend
+                )
+            })
+            .map_err(|err| err.to_string());
+        let _ = sender.send(result);
+    });
+    receiver
+}
+
+// load the models of the configured backend on a worker thread for the setup
+// wizard, handing back what makes the backend as it does for the pool, which
+// for ocrs only clones the loaded engine
+fn spawn_model_load(
+    config: &Config,
+    cache: &SharedCache,
+) -> Receiver<Result<BackendFactory, String>> {
+    let (config, cache) = (config.clone(), cache.clone());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let result = backend_factory(&config, &cache)
+            .map_err(|err| format!("Error loading OCR models: {}", err));
+        let _ = sender.send(result);
+    });
+    receiver
+}
+
+// read the sample image of the setup wizard on a worker thread with a backend
+// of its own, timing only the reading
+fn spawn_sample_test(config: &Config) -> Receiver<Result<(String, Duration), String>> {
+    let config = config.clone();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let result = load_backend(&config, &None)
+            .and_then(|backend| {
+                let start = Instant::now();
+                let result = ocr::transcribe_preprocessed(
+                    backend.as_ref(),
+                    &onboarding::sample_image(),
+                    &config.preprocessing,
+                )?;
+                Ok((config.render(&result), start.elapsed()))
+            })
+            .map_err(|err| err.to_string());
+        let _ = sender.send(result);
+    });
+    receiver
+}
+
+// transcribe a video as a job with its own backend, as the one in use cannot
+// be shared
+fn spawn_video_transcription(config: &Config, cache: &SharedCache, path: PathBuf) -> Job<Vec<Cue>> {
+    let (config, cache) = (config.clone(), cache.clone());
+    let name = job_name("Transcribing", &path);
+    Job::spawn(name, move |control, on_progress| {
+        let interval =
+            Duration::try_from_secs_f32(config.video_interval).unwrap_or(Duration::from_secs(1));
+        load_backend(&config, &cache)
+            .and_then(|backend| {
+                video::transcribe_video(
+                    backend.as_ref(),
+                    &path,
+                    interval,
+                    &config.preprocessing,
+                    control,
+                    on_progress,
+                )
+            })
+            .map_err(|err| err.to_string())
+    })
+}
+
+// put a new result into the text as the output mode says
+fn place_text(text: &mut String, cursor: &mut Option<usize>, config: &Config, result: &str) {
+    match config.output_mode {
+        OutputMode::Replace => *text = result.to_string(),
+        OutputMode::Append => {
+            if !text.is_empty() {
+                text.push_str(&config.append_separator);
+            }
+            text.push_str(result);
         }
-    };
-    let recognition_model = match Model::load_file("text-recognition.rten") {
-        Ok(model) => model,
-        Err(err) => {
-            eprintln!("Error loading recognition model: {}", err);
-            std::process::exit(1)
+        OutputMode::Insert => {
+            let at = cursor
+                .filter(|at| text.is_char_boundary(*at))
+                .unwrap_or(text.len());
+            text.insert_str(at, result);
+            // the next one goes after this one
+            *cursor = Some(at + result.len());
         }
+    }
+}
+
+// A result of a job for the tab it was started from, put there once the jobs of
+// the frame are polled, whichever tab is open by then
+struct Placement {
+    tab: usize,
+    result: String,
+    // the text and cursor of a streamed read from before it started, put back
+    // under each of its partial results
+    before: Option<(String, Option<usize>)>,
+    // what the result was read as, partial results keep what the tab has
+    read: Option<(Option<TranscriptionResult>, Vec<TranscriptionResult>)>,
+    // joined to the end of the text where they overlap, as the clipboard watch does
+    merge: bool,
+}
+
+// put a job's result into the editor of its tab
+fn place(editor: Editor, config: &Config, placement: Placement) {
+    if let Some(before) = placement.before {
+        (*editor.text, *editor.cursor) = before;
+    }
+    // an empty partial result only puts back the text from before
+    if placement.result.is_empty() && placement.read.is_none() {
+        return;
+    }
+    // only text added at the end can overlap the end
+    let merge = config.merge_overlaps && config.output_mode == OutputMode::Append;
+    let merged = (placement.merge && merge)
+        .then(|| merge::merge(editor.text, &placement.result))
+        .flatten();
+    match merged {
+        Some(merged) => *editor.text = merged,
+        None => place_text(editor.text, editor.cursor, config, &placement.result),
+    }
+    if let Some((transcription, pages)) = placement.read {
+        *editor.transcription = transcription;
+        *editor.pages = pages;
+    }
+}
+
+fn load_ocrs(config: &Config) -> Result<OcrsBackend, Box<dyn Error>> {
+    let (recognition_model, alphabet) = config
+        .recognition_model_path()
+        .ok_or("No recognition languages configured")?;
+    let engine = ocr::load_engine(
+        &config.detection_model_path(),
+        &recognition_model,
+        alphabet,
+        &config.engine,
+    )?;
+    Ok(OcrsBackend::new(engine))
+}
+
+// Results shared by every backend, None when caching is turned off
+type SharedCache = Option<Arc<Mutex<ResultCache>>>;
+
+fn load_cache(config: &Config) -> SharedCache {
+    if config.cache_size == 0 {
+        return None;
+    }
+    let cache = match Config::cache_path().filter(|_| config.persist_cache) {
+        Some(path) => ResultCache::load(&path, config.cache_size).unwrap_or_else(|err| {
+            error!("Error loading result cache: {}", err);
+            ResultCache::new(config.cache_size)
+        }),
+        None => ResultCache::new(config.cache_size),
     };
+    Some(Arc::new(Mutex::new(cache)))
+}
 
-    let ocr = match OcrEngine::new(OcrEngineParams {
-        detection_model: Some(detection_model),
-        recognition_model: Some(recognition_model),
-        ..Default::default()
-    }) {
-        Ok(engine) => engine,
-        Err(err) => {
-            eprintln!("Error creating OCR engine: {}", err);
-            std::process::exit(1)
+// answer repeated images from the cache
+fn cached(
+    backend: Box<dyn OcrBackend>,
+    config: &Config,
+    cache: &SharedCache,
+) -> Box<dyn OcrBackend> {
+    match cache {
+        Some(cache) => Box::new(CachedBackend::new(
+            backend,
+            cache.clone(),
+            engine_settings(config),
+        )),
+        None => backend,
+    }
+}
+
+// Stands in for the backend while its models are missing, every transcription says why
+struct Unavailable(String);
+
+impl OcrBackend for Unavailable {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn transcribe(&self, _image: &DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> {
+        Err(self.0.clone().into())
+    }
+}
+
+// the backend `name`, local or cloud, as configured
+fn load_named(name: &str, config: &Config) -> Result<Box<dyn OcrBackend>, Box<dyn Error>> {
+    if CLOUD_BACKENDS.contains(&name) {
+        return backend::cloud_backend(name, &config.cloud_credentials)
+            .map(|cloud| cloud as Box<dyn OcrBackend>);
+    }
+    Ok(match name {
+        "ocrs" => Box::new(load_ocrs(config)?),
+        #[cfg(feature = "tesseract")]
+        "tesseract" => Box::new(TesseractBackend::new(
+            None,
+            &config.tesseract_language,
+            &config.engine.allowed_chars,
+        )?),
+        other => return Err(format!("Unknown OCR backend: {}", other).into()),
+    })
+}
+
+// the backend `name` for the fallback chain, None when it cannot be loaded
+fn load_chained(name: &str, config: &Config) -> Option<Box<dyn OcrBackend>> {
+    load_named(name, config)
+        .map_err(|err| error!("Error loading {} for the fallback chain: {}", name, err))
+        .ok()
+}
+
+// `primary` followed by the backends of the fallback chain as `load` makes
+// them, those that cannot be loaded are left out. With the ensemble on they
+// all read every image
+fn with_chain(
+    primary: Box<dyn OcrBackend>,
+    config: &Config,
+    load: impl Fn(&str) -> Option<Box<dyn OcrBackend>>,
+) -> Box<dyn OcrBackend> {
+    let mut backends = vec![primary];
+    for name in config.chain() {
+        if backends.iter().any(|backend| backend.name() == name) {
+            continue;
         }
-    };
+        backends.extend(load(name));
+    }
+    if config.ensemble && (backends.len() > 1 || !config.ensemble_variants.is_empty()) {
+        return Box::new(Ensemble::new(backends, &config.ensemble_variants));
+    }
+    if backends.len() == 1 {
+        return backends.remove(0);
+    }
+    Box::new(FallbackChain::new(backends, config.chain_min_confidence))
+}
 
-    /* setup clipboard context */
-    let clipboard = ClipboardContext::new().unwrap();
+// load the configured OCR backend with its models
+fn load_backend(
+    config: &Config,
+    cache: &SharedCache,
+) -> Result<Box<dyn OcrBackend>, Box<dyn Error>> {
+    let backend = with_chain(load_named(&config.backend, config)?, config, |name| {
+        load_chained(name, config)
+    });
+    // the cloud backend goes around the cache, so a capture sent to it is not
+    // answered with what the local one read
+    Ok(filtered(
+        with_cloud(cached(backend, config, cache), config),
+        config,
+    ))
+}
 
-    let mut text = String::new();
+// `local` reading with the cloud backend while it is picked, see use_cloud
+fn with_cloud(local: Box<dyn OcrBackend>, config: &Config) -> Box<dyn OcrBackend> {
+    Box::new(CloudChoice::new(local, config.cloud.clone()))
+}
 
-    /* start main loop */
-    let mut event_pump = sdl.event_pump().unwrap();
+// send the captures to come to the cloud backend of the config, with the keys
+// it has now, or read them locally again
+fn use_cloud(config: &Config, cloud: bool) -> Result<(), Box<dyn Error>> {
+    let picked = match cloud && !config.cloud_backend.is_empty() {
+        true => Some(backend::cloud_backend(
+            &config.cloud_backend,
+            &config.cloud_credentials,
+        )?),
+        false => None,
+    };
+    config.cloud.set(picked);
+    Ok(())
+}
 
-    'main: loop {
-        for event in event_pump.poll_iter() {
-            /* pass all events to imgui platfrom */
-            platform.handle_event(&mut imgui, &event);
+// the backend the settings pick with none of the cache or the cloud in front,
+// for reading an image again as it is
+fn load_uncached(config: &Config) -> Result<Box<dyn OcrBackend>, Box<dyn Error>> {
+    let backend = with_chain(load_named(&config.backend, config)?, config, |name| {
+        load_chained(name, config)
+    });
+    Ok(filtered(backend, config))
+}
+
+// `backend` with the lines of its results that are noise dropped, outside the
+// cache so a change to the filter needs no image read again
+fn filtered(backend: Box<dyn OcrBackend>, config: &Config) -> Box<dyn OcrBackend> {
+    Box::new(FilteredBackend::new(backend, &config.line_filter))
+}
 
-            if let Event::Quit { .. } = event {
-                break 'main;
+// backends for pool workers. Those that can be shared are loaded once for all
+// of them, ocrs with its models and the cloud backends, while every tesseract
+// worker needs an instance of its own
+fn backend_factory(config: &Config, cache: &SharedCache) -> Result<BackendFactory, Box<dyn Error>> {
+    let (config, cache) = (config.clone(), cache.clone());
+    let mut shared: Vec<(&str, Arc<dyn OcrBackend + Send + Sync>)> = Vec::new();
+    // of the chain, left out without saying so again for every worker
+    let mut failed: Vec<&str> = Vec::new();
+    for name in std::iter::once(config.backend.as_str()).chain(config.chain()) {
+        if shared.iter().any(|(loaded, _)| *loaded == name) || failed.contains(&name) {
+            continue;
+        }
+        let loaded: Result<Arc<dyn OcrBackend + Send + Sync>, Box<dyn Error>> = match name {
+            "ocrs" => load_ocrs(&config).map(|ocrs| Arc::new(ocrs) as _),
+            name if CLOUD_BACKENDS.contains(&name) => {
+                backend::cloud_backend(name, &config.cloud_credentials).map(Arc::from)
+            }
+            _ => continue,
+        };
+        match loaded {
+            Ok(backend) => shared.push((name, backend)),
+            Err(err) if name == config.backend => return Err(err),
+            Err(err) => {
+                error!("Error loading {} for the fallback chain: {}", name, err);
+                failed.push(name);
             }
         }
+    }
+    let shared: Vec<(String, Arc<dyn OcrBackend + Send + Sync>)> = shared
+        .into_iter()
+        .map(|(name, backend)| (name.to_string(), backend))
+        .collect();
+    let failed: Vec<String> = failed.into_iter().map(str::to_string).collect();
+    Ok(Arc::new(move || {
+        let share = |name: &str| {
+            shared
+                .iter()
+                .find(|(loaded, _)| loaded == name)
+                .map(|(_, backend)| Box::new(backend.clone()) as Box<dyn OcrBackend>)
+        };
+        let primary = match share(&config.backend) {
+            Some(backend) => backend,
+            None => load_named(&config.backend, &config)?,
+        };
+        let chain = with_chain(primary, &config, |name| match share(name) {
+            Some(backend) => Some(backend),
+            None if failed.iter().any(|failed| failed == name) => None,
+            None => load_chained(name, &config),
+        });
+        let backend = with_cloud(cached(chain, &config, &cache), &config);
+        Ok(filtered(backend, &config))
+    }))
+}
 
-        /* call prepare_frame before calling imgui.new_frame() */
-        platform.prepare_frame(&mut imgui, &window, &event_pump);
+// everything besides the image that changes what a backend recognizes
+fn engine_settings(config: &Config) -> String {
+    let recognition_model = config.recognition_model_path().map(|(model, _)| model);
+    format!(
+        "{} {:?} {} {} {:?} {} {} {:?} {:?} {} {}",
+        config.backend,
+        config.chain(),
+        config.chain_min_confidence,
+        config.ensemble,
+        config.ensemble_variants,
+        config.language,
+        config.handwriting,
+        config.detection_model_path(),
+        recognition_model,
+        config.tesseract_language,
+        serde_json::to_string(&config.engine).unwrap_or_default()
+    )
+}
 
-        let ui = imgui.new_frame();
+// the settings a pool made its backends from, it is rebuilt when they change
+type PoolSettings = (String, usize);
 
-        /* create imgui UI here */
+fn pool_settings(config: &Config) -> PoolSettings {
+    let filter = serde_json::to_string(&config.line_filter).unwrap_or_default();
+    (
+        format!("{} {}", engine_settings(config), filter),
+        config.ocr_threads,
+    )
+}
 
-        if ui.button("Get clipboard") {
-            text = match clipboard_str(&ocr, &clipboard) {
-                Ok(text) => text,
-                Err(err) => format!("Error getting text from clipboard: {}", err),
+// the pool watch mode and streaming transcribe on, made when first needed and
+// again once the settings it was made with changed
+fn ensure_pool<'a>(
+    pool: &'a mut Option<(WorkerPool, PoolSettings)>,
+    config: &Config,
+    cache: &SharedCache,
+) -> Result<&'a WorkerPool, Box<dyn Error>> {
+    let settings = pool_settings(config);
+    if pool
+        .as_ref()
+        .is_none_or(|(_, made_with)| *made_with != settings)
+    {
+        // the old models go before the new ones are loaded
+        *pool = None;
+        let factory = backend_factory(config, cache)?;
+        *pool = Some((WorkerPool::new(config.ocr_threads, factory), settings));
+    }
+    Ok(pool.as_ref().map(|(pool, _)| pool).ok_or("No OCR pool")?)
+}
+
+// load the configured dictionary, turning spell checking off when it cannot be read
+fn load_spellcheck(config: &mut Config, status: &mut Status) -> Option<SpellcheckView> {
+    match SpellChecker::load(&config.dictionary) {
+        Ok(checker) => Some(SpellcheckView::new(checker)),
+        Err(err) => {
+            status.error("Error loading dictionary", err);
+            config.spell_check = false;
+            None
+        }
+    }
+}
+
+// reload the backend after a config change, undoing the change if it fails so
+// the previous models stay in use
+fn switch_backend(
+    backend: &mut Box<dyn OcrBackend>,
+    cache: &SharedCache,
+    config: &mut Config,
+    status: &mut Status,
+    undo: impl FnOnce(&mut Config),
+) {
+    match load_backend(config, cache) {
+        Ok(loaded) => {
+            *backend = loaded;
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
             }
         }
+        Err(err) => {
+            undo(config);
+            status.error("Error loading OCR backend", err);
+        }
+    }
+}
 
-        ui.same_line();
+// transcribe the image on standard input, without the cache which a one-off run
+// would only fill
+fn transcribe_stdin(config: &Config) -> Result<String, Box<dyn Error>> {
+    let mut bytes = Vec::new();
+    io::stdin()
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Failed to read standard input: {}", err))?;
+    if bytes.is_empty() {
+        return Err("No image on standard input".into());
+    }
+    let image = files::decode(&bytes, config.conversion())
+        .map_err(|err| TranscribeError::UnsupportedFormat(format!("standard input: {}", err)))?;
+    let backend = load_backend(config, &None)?;
+    let mut transcription =
+        ocr::transcribe_preprocessed(backend.as_ref(), &image, &config.preprocessing)?;
+    recognize_formulas(config, &image, &mut transcription);
+    Ok(config.render(&transcription))
+}
 
-        if ui.button("Copy") {
-            match clipboard.set_text(text.clone()) {
-                Ok(()) => (),
-                Err(err) => {
-                    text = format!("Error setting text to clipboard: {}", err);
+// run the pipeline over the images in `dir` that come with their ground truth
+// and print how fast and how well each was read, without the cache so every
+// image is really transcribed
+fn bench(config: &Config, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let backend = load_backend(config, &None)?;
+    let report = evaluate::run(
+        backend.as_ref(),
+        dir,
+        &config.preprocessing,
+        config.conversion(),
+        |transcription| config.render(transcription),
+    )?;
+    for (path, reason) in &report.skipped {
+        eprintln!("skipped {}: {}", path.display(), reason);
+    }
+    if report.samples.is_empty() {
+        return Err(format!("No images with ground truth in {}", dir.display()).into());
+    }
+    println!("{:>9} {:>7} {:>7}  image", "ms", "CER", "WER");
+    for sample in &report.samples {
+        let path = sample.path.strip_prefix(dir).unwrap_or(&sample.path);
+        println!(
+            "{:>9.1} {:>6.2}% {:>6.2}%  {}",
+            sample.latency.as_secs_f64() * 1000.0,
+            sample.cer() * 100.0,
+            sample.wer() * 100.0,
+            path.display()
+        );
+    }
+    println!(
+        "{} images with {}, {:.1} ms mean, {:.1} ms median, {:.2}% CER, {:.2}% WER",
+        report.samples.len(),
+        backend.name(),
+        report.mean_latency().as_secs_f64() * 1000.0,
+        report.median_latency().as_secs_f64() * 1000.0,
+        report.cer() * 100.0,
+        report.wer() * 100.0
+    );
+    Ok(())
+}
+
+// Create a new glow context.
+// create the window with the newest of the OpenGL versions the driver offers
+fn create_window(video_subsystem: &VideoSubsystem) -> Result<(Window, GLContext), String> {
+    let mut failures = Vec::new();
+    for (major, minor, profile) in GL_VERSIONS {
+        let gl_attr = video_subsystem.gl_attr();
+        gl_attr.set_context_version(major, minor);
+        gl_attr.set_context_profile(profile);
+
+        /* be sure to call opengl method on the builder when using glow! */
+        let window = match video_subsystem
+            .window("Hello imgui-rs!", 1280, 720)
+            .allow_highdpi()
+            .opengl()
+            .position_centered()
+            .resizable()
+            .hidden()
+            .build()
+        {
+            Ok(window) => window,
+            Err(err) => {
+                failures.push(format!("OpenGL {}.{}: {}", major, minor, err));
+                continue;
+            }
+        };
+        let context = window.gl_create_context().and_then(|context| {
+            window.gl_make_current(&context)?;
+            Ok(context)
+        });
+        match context {
+            Ok(context) => {
+                if failures.is_empty() {
+                    info!("Using OpenGL {}.{}", major, minor);
+                } else {
+                    warn!(
+                        "OpenGL {}.{} is not available, falling back to {}.{}",
+                        GL_VERSIONS[0].0, GL_VERSIONS[0].1, major, minor
+                    );
                 }
+                return Ok((window, context));
             }
+            Err(err) => failures.push(format!("OpenGL {}.{}: {}", major, minor, err)),
         }
+    }
+    Err(failures.join("\n"))
+}
+
+fn glow_context(window: &Window) -> glow::Context {
+    unsafe {
+        glow::Context::from_loader_function(|s| window.subsystem().gl_get_proc_address(s) as _)
+    }
+}
 
-        ui.text(text.as_str());
+fn main() -> ExitCode {
+    /* logging is set up as configured, so a broken config is only reported after, the first
+    start is the one without a config file yet */
+    let first_run = Config::path().is_some_and(|path| !path.exists());
+    let (mut config, config_error) = match Config::load() {
+        Ok(config) => (config, None),
+        Err(err) => (Config::default(), Some(err)),
+    };
 
-        /* render */
-        let draw_data = imgui.render();
+    /* rten reads its thread count when the first model runs, set before the logging thread
+    or any other exists, as changing the environment with threads around is unsound */
+    if config.inference_threads > 0 {
+        std::env::set_var("RTEN_NUM_THREADS", config.inference_threads.to_string());
+    }
 
-        unsafe { renderer.gl_context().clear(glow::COLOR_BUFFER_BIT) };
-        renderer.render(draw_data).unwrap();
+    /* --software renders with Mesa on the CPU, Mesa reads this when SDL loads OpenGL, it is
+    set before any thread exists too */
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--software") {
+        std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+        std::env::set_var("GALLIUM_DRIVER", "llvmpipe");
+    }
 
-        window.gl_swap_window();
+    let mut logging = Logging::init(config.log_level);
+    if let Some(err) = config_error {
+        error!("Error loading config, using defaults: {}", err);
+    }
+    locale::set(config.ui_language);
+
+    /* model files from the command line win over the configured ones, for this run only */
+    let flag_value = |flag: &str| {
+        let index = args.iter().position(|arg| arg == flag)?;
+        args.get(index + 1).map(PathBuf::from)
+    };
+    config.detection_override = flag_value("--detection-model");
+    config.recognition_override = flag_value("--recognition-model");
+
+    /* --serve ADDRESS offers the engine over HTTP instead of opening the window, on :8080
+    when the address is left out, even with other flags after it */
+    if let Some(index) = args.iter().position(|arg| arg == "--serve") {
+        let address = args
+            .get(index + 1)
+            .filter(|arg| !arg.starts_with("--"))
+            .map_or(":8080", String::as_str);
+        if let Err(err) = serve::run(address, &config) {
+            error!("Error serving: {}", err);
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
     }
+
+    /* --stdin transcribes an image piped in and prints the text, for shell pipelines */
+    if args.iter().any(|arg| arg == "--stdin") {
+        match transcribe_stdin(&config) {
+            Ok(text) => println!("{}", text),
+            Err(err) => {
+                error!("Error transcribing standard input: {}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    /* --bench DIR measures speed and accuracy on images with ground truth texts */
+    if args.iter().any(|arg| arg == "--bench") {
+        let Some(dir) = flag_value("--bench") else {
+            error!("--bench needs a directory of images");
+            return ExitCode::FAILURE;
+        };
+        if let Err(err) = bench(&config, &dir) {
+            error!("Error benchmarking: {}", err);
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    /* --command runs a command in the running instance if there is one, a start without
+    shows its window, see instance::Command */
+    let startup_command = match args.iter().position(|arg| arg == "--command") {
+        Some(index) => match Command::parse(&args[index + 1..].join(" ")) {
+            Ok(command) => Some(command),
+            Err(err) => {
+                error!("Error parsing --command: {}", err);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+    let instance = match config.single_instance {
+        true => match instance::start(startup_command.as_ref()) {
+            Ok(instance) => instance,
+            Err(code) => return code,
+        },
+        false => None,
+    };
+    // commands from the tray and other instances, a --command of this start first
+    let mut commands: Vec<(Command, Option<Reply>)> = startup_command
+        .map(|command| (command, None))
+        .into_iter()
+        .collect();
+
+    /* closing the window only hides it while the tray icon is around */
+    sdl2::hint::set("SDL_QUIT_ON_LAST_WINDOW_CLOSE", "0");
+
+    /* initialize SDL and its video subsystem */
+    let sdl = match sdl2::init() {
+        Ok(sdl) => sdl,
+        Err(err) => return startup::fail(Subsystem::Sdl, err),
+    };
+    let video_subsystem = match sdl.video() {
+        Ok(video_subsystem) => video_subsystem,
+        Err(err) => return startup::fail(Subsystem::Video, err),
+    };
+
+    /* create a new window with an OpenGL context made current, 3.3 core profile when available,
+    the context has to live as long as the window is drawn */
+    let (mut window, _gl_context) = match create_window(&video_subsystem) {
+        Ok(created) => created,
+        Err(err) => return startup::fail(Subsystem::Window, err),
+    };
+
+    /* enable vsync to cap framerate, without it the frames are paced by the idle wait only */
+    if let Err(err) = window.subsystem().gl_set_swap_interval(1) {
+        warn!("Error enabling vsync: {}", err);
+    }
+
+    /* create new glow and imgui contexts */
+    let gl = glow_context(&window);
+
+    /* create context */
+    let mut imgui = Context::create();
+
+    /* disable creation of files on disc */
+    imgui.set_ini_filename(None);
+    imgui.set_log_filename(None);
+
+    /* setup fonts and style to imgui, the style is rebuilt from imgui's own when the theme changes */
+    let mut monospace_font = theme::add_fonts(imgui.fonts(), &config);
+    let base_style = *imgui.style();
+    theme::apply_style(
+        imgui.style_mut(),
+        &base_style,
+        config.theme,
+        config.ui_scale,
+    );
+    // atlas replacing the one the renderer uploads, once the font size changed
+    let mut font_texture: Option<Texture> = None;
+    let mut appearance_changed = false;
+
+    /* create platform and renderer */
+    let mut platform = SdlPlatform::new(&mut imgui);
+    let mut renderer = match AutoRenderer::new(gl, &mut imgui) {
+        Ok(renderer) => renderer,
+        Err(err) => return startup::fail(Subsystem::Renderer, err),
+    };
+
+    /* setup OCR context */
+    let cache = load_cache(&config);
+    let mut models_error = None;
+    let mut backend = match load_backend(&config, &cache) {
+        Ok(engine) => engine,
+        Err(err) => {
+            error!("Error loading OCR models: {}", err);
+            let reason = format!("Error loading OCR models: {}", err);
+            models_error = Some(reason.clone());
+            Box::new(Unavailable(reason))
+        }
+    };
+
+    /* the first start, or one with --setup, walks through the setup, which asks for the models
+    itself */
+    let mut onboarding = (first_run || args.iter().any(|arg| arg == "--setup"))
+        .then(|| Onboarding::new(&config, models_error.clone()));
+    let mut models_dialog = match onboarding {
+        Some(_) => None,
+        None => models_error.map(ModelsDialog::new),
+    };
+
+    /* setup clipboard context, the app keeps working without one */
+    let mut clipboard = SystemClipboard::open(config.clipboard_source, config.clipboard_target);
+
+    /* register the system-wide hotkeys, the app keeps working without them */
+    let hotkey_manager = GlobalHotKeyManager::new();
+    if let Err(err) = &hotkey_manager {
+        error!("Error creating hotkey manager: {}", err);
+    }
+    let register = |shortcut: &str| {
+        // an empty shortcut is one the user left unset
+        if shortcut.is_empty() {
+            return None;
+        }
+        let manager = hotkey_manager.as_ref().ok()?;
+        match shortcut.parse::<HotKey>() {
+            Ok(hotkey) => match manager.register(hotkey) {
+                Ok(()) => Some(hotkey),
+                Err(err) => {
+                    error!("Error registering hotkey {}: {}", shortcut, err);
+                    None
+                }
+            },
+            Err(err) => {
+                error!("Error parsing hotkey {}: {}", shortcut, err);
+                None
+            }
+        }
+    };
+    let mut hotkey = register(&config.hotkey);
+    let window_hotkey = register(&config.window_hotkey);
+    let overlay_hotkey = register(&config.overlay_hotkey);
+    let monitor_hotkeys: Vec<Option<HotKey>> = config
+        .monitor_hotkeys
+        .iter()
+        .map(|shortcut| register(shortcut))
+        .collect();
+
+    let tray = match Tray::new() {
+        Ok(tray) => Some(tray),
+        Err(err) => {
+            error!("Error creating tray icon: {}", err);
+            None
+        }
+    };
+
+    /* the clipboard is only watched in watch mode, and watched anew when its source or the
+    poll interval change */
+    let mut clipboard_watch: Option<(ClipboardWatch, Selection, f32)> = None;
+    let mut watching = false;
+    // the last image watch mode read, to skip it when it comes again
+    let mut last_watched: Option<ImageDigest> = None;
+    let mut window_hidden = false;
+
+    let mut text = String::new();
+    let mut status = Status::default();
+    // byte offset of the editor cursor, for the insert output mode
+    let mut cursor: Option<usize> = None;
+    let mut transcription: Option<TranscriptionResult> = None;
+    // the pages of a read of several, when there is no one transcription
+    let mut pages: Vec<TranscriptionResult> = Vec::new();
+    let mut history = History::new(HISTORY_CAPACITY);
+    #[cfg(feature = "archive")]
+    if config.archive {
+        match open_archive() {
+            Ok(archive) => history.set_archive(Some(archive)),
+            Err(err) => status.error("Error opening the archive", err),
+        }
+    }
+    history.set_journal(config.journal_dir());
+    #[cfg(feature = "archive")]
+    let mut archive_search = ArchiveSearch::default();
+    // the audio file being transcribed, for the tab it was picked in
+    #[cfg(feature = "whisper")]
+    let mut audio_job: Option<(usize, Job<AudioTranscript>)> = None;
+    #[cfg(feature = "dictation")]
+    let mut dictation: Option<(usize, Dictation)> = None;
+    // the whisper model being loaded for dictation, it takes a few seconds, and
+    // the tab the speech goes to
+    #[cfg(feature = "dictation")]
+    let mut dictation_model: Option<(usize, Receiver<Result<Transcriber, String>>)> = None;
+    #[cfg(feature = "camera")]
+    let mut camera_open = false;
+    #[cfg(feature = "camera")]
+    let mut scanner = Scanner::default();
+    // the video being transcribed, for the tab it was picked in
+    let mut video_job: Option<(usize, Job<Vec<Cue>>)> = None;
+    // the formulas of the history entry pushed as the given one, being read for
+    // the tab it was placed in
+    let mut formula_job: Option<(usize, usize, Job<Option<TranscriptionResult>>)> = None;
+    // the timed text of the last video or audio file, for "Save subtitles..."
+    let mut subtitle_cues: Option<(Vec<Cue>, &str)> = None;
+    let mut settings_open = false;
+    let mut cheat_sheet_open = false;
+    let mut bindings = config.keymap.bindings();
+    // results with secrets in them wait here to be copied
+    let mut secret_guard = SecretGuard::new(config.secret_handling);
+    // name the current settings are saved as a profile under, in Settings
+    let mut profile_name = String::new();
+    let mut project_settings = ProjectSettings::default();
+    let mut preview: Option<Preview> = None;
+    #[cfg(target_os = "linux")]
+    let mut screenshot_job: Option<Receiver<Result<Option<DynamicImage>, String>>> = None;
+    let mut snip_job: Option<Receiver<Result<Vec<capture::Shot>, String>>> = None;
+    let mut overlay: Option<Overlay> = None;
+    // where the window was before it covered the desktop for snipping
+    let mut snip_window: Option<((i32, i32), (u32, u32))> = None;
+    let mut snip_purpose = SnipPurpose::Transcribe;
+    let mut scroll_capture: Option<ScrollCapture> = None;
+    let mut live_overlay = Live::default();
+    let mut pinned: Option<PinnedRegion> = None;
+    // the focused window or a monitor being captured, whether a hotkey asked for
+    // it so the result is copied without showing the window, and the tab it is for
+    let mut capture_job: Option<(CaptureJob, bool, usize)> = None;
+    let mut batch: Option<BatchJob> = None;
+    let mut folder_watch: Option<FolderWatch> = None;
+    if !config.watched_folder.as_os_str().is_empty() {
+        match FolderWatch::start(&config, &cache, config.watched_folder.clone()) {
+            Ok(watch) => folder_watch = Some(watch),
+            Err(err) => status.error("Error watching folder", err),
+        }
+    }
+    let mut benchmark_job: Option<Receiver<Result<String, String>>> = None;
+    let mut benchmark_result = String::new();
+    // transcribes the images watch mode picks up and the ones streamed
+    let mut worker_pool: Option<(WorkerPool, PoolSettings)> = None;
+    let mut streaming = Streaming::default();
+    // files other instances sent to be read, and the frames of GIFs
+    let mut file_jobs = FileJobs::default();
+    // results of the jobs done this frame, for the tabs they were started from
+    let mut placements: Vec<Placement> = Vec::new();
+    // watch mode images being read, oldest first, with the tab each goes to
+    let mut watch_jobs: VecDeque<(usize, WatchJob)> = VecDeque::new();
+    #[cfg(feature = "tts")]
+    let mut reader: Option<Reader> = None;
+    // the find bar, translation and cleanup of the active tab
+    let mut panels = Panels::default();
+    let mut translating = false;
+    let mut statistics_open = false;
+    let mut anonymizing = false;
+    let mut links_open = false;
+    let mut line_panel = LinePanel::default();
+    let mut lines_open = false;
+    let mut link_panel = LinkPanel::default();
+    let mut codes_open = false;
+    let mut code_panel = CodePanel::default();
+    let mut text_area = TextArea::default();
+    // the text last checked as code, what as and the result
+    let mut syntax_check: Option<(String, CodeLanguage, Result<(), String>)> = None;
+    // the text as it was last changed to and when, once it differs from the
+    // one checked
+    let mut syntax_changed: Option<(String, Instant)> = None;
+    let mut statistics = StatisticsPanel::default();
+    let mut anonymization = AnonymizationPanel::default();
+    let mut compare_open = false;
+    let mut compare = ComparePanel::new(&config);
+    let mut compose = ComposePanel::default();
+    let mut compose_open = false;
+    // the compose window asked for another region, snipped next frame
+    let mut compose_region = false;
+    let mut about_open = false;
+    let mut about = AboutPanel::default();
+    if config.check_updates {
+        about.check(false);
+    }
+    let mut book_open = false;
+    let mut book = BookPanel::restore(&config);
+    // the lines of the last transcription summed up and the language they are in
+    let mut recognized_language: Option<(u64, Option<DetectedLanguage>)> = None;
+    let mut extracting = false;
+    let mut extraction = ExtractionPanel::default();
+    let mut proofread = ProofreadView::default();
+    let mut side_by_side = SideBySide::default();
+    let mut spellcheck = None;
+    if config.spell_check {
+        spellcheck = load_spellcheck(&mut config, &mut status);
+    }
+
+    let mut undo = UndoStack::new(UNDO_CAPACITY, &text);
+    let mut tabs = Tabs::default();
+
+    /* pick up where the last run left off */
+    let start_clean = args.iter().any(|arg| arg == "--clean");
+    if config.restore_session && !start_clean {
+        match Session::load() {
+            Ok(Some(mut session)) => {
+                session.restore_history(&mut history, &mut renderer);
+                imgui.load_ini_settings(&session.layout);
+                if let Some((x, y, width, height)) = session.window {
+                    window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+                    if let Err(err) = window.set_size(width, height) {
+                        error!("Error restoring window size: {}", err);
+                    }
+                }
+                tabs = Tabs::restore(
+                    mem::take(&mut session.tabs),
+                    session.active,
+                    Editor {
+                        text: &mut text,
+                        cursor: &mut cursor,
+                        transcription: &mut transcription,
+                        pages: &mut pages,
+                        undo: &mut undo,
+                        panels: &mut panels,
+                    },
+                );
+            }
+            Ok(None) => (),
+            Err(err) => status.error("Error restoring session", err),
+        }
+    }
+
+    /* an autosave left behind means the last run crashed, it is offered once the window is up */
+    let mut recovery: Option<(Session, SystemTime)> = None;
+    if !start_clean {
+        match Session::load_autosave() {
+            Ok(found) => recovery = found,
+            Err(err) => status.error("Error reading the autosave", err),
+        }
+    }
+    let mut recovery_offered = false;
+    let mut recovered_layout: Option<String> = None;
+    let mut autosaved_at = Instant::now();
+    let mut autosaved = None;
+    // the fingerprint of what is being autosaved and the answer of the thread writing it
+    let mut autosaving: Option<(u64, Receiver<Result<(), String>>)> = None;
+
+    /* screen readers have to be hooked up before the window is first shown */
+    let mut screen_reader = None;
+    if config.screen_reader {
+        match ScreenReader::new(&window) {
+            Ok(reader) => screen_reader = Some(reader),
+            Err(err) => error!("Error setting up screen reader support: {}", err),
+        }
+    }
+    window.show();
+    // results from before, such as the restored session, are not read out
+    let mut announced = history.pushed();
+    let plugin_runner = PluginRunner::default();
+    let webhook = Webhook::default();
+    let mut notifier = Notifier::default();
+    let mut perf_hud = PerfHud::default();
+    let delivery = Delivery::default();
+    // errors of the script, from wherever a text is rendered
+    let script_errors = Background::default();
+    config.script_errors = Some(script_errors.sender());
+
+    /* start main loop */
+    let mut event_pump = match sdl.event_pump() {
+        Ok(event_pump) => event_pump,
+        Err(err) => return startup::fail(Subsystem::Events, err),
+    };
+    let mut settle_frames = SETTLE_FRAMES;
+
+    'main: loop {
+        /* only draw at vsync while something changes, otherwise sleep until an event or the timeout */
+        #[cfg(feature = "tts")]
+        let reading = reader.as_ref().is_some_and(Reader::is_reading);
+        #[cfg(not(feature = "tts"))]
+        let reading = false;
+        let busy = reading
+            || video_job.is_some()
+            || formula_job.is_some()
+            || overlay.is_some()
+            || scroll_capture.is_some()
+            || live_overlay.is_running()
+            || pinned.is_some()
+            || batch.as_ref().is_some_and(|job| !job.is_done());
+        let mut woken_by = None;
+        if settle_frames > 0 {
+            settle_frames -= 1;
+        } else if !busy {
+            woken_by = event_pump.wait_event_timeout(IDLE_TIMEOUT.as_millis() as u32);
+        }
+
+        for event in woken_by.into_iter().chain(event_pump.poll_iter()) {
+            settle_frames = SETTLE_FRAMES;
+
+            /* pass all events to imgui platfrom */
+            platform.handle_event(&mut imgui, &event);
+
+            match event {
+                Event::Quit { .. } => break 'main,
+                Event::Window {
+                    win_event: WindowEvent::Close,
+                    ..
+                } => {
+                    if tray.is_none() {
+                        break 'main;
+                    }
+                    window.hide();
+                    window_hidden = true;
+                }
+                Event::Window {
+                    win_event: win_event @ (WindowEvent::FocusGained | WindowEvent::FocusLost),
+                    ..
+                } => {
+                    if let Some(reader) = &mut screen_reader {
+                        reader.set_focused(win_event == WindowEvent::FocusGained);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        /* the tray menu and other instances share their commands, the answer goes back to
+        the instance that sent one */
+        if let Some(tray) = &tray {
+            while let Some(command) = tray.poll() {
+                commands.push((command.into(), None));
+            }
+        }
+        if let Some(instance) = &instance {
+            commands.extend(
+                instance
+                    .poll()
+                    .map(|(command, reply)| (command, Some(reply))),
+            );
+        }
+        for (command, reply) in std::mem::take(&mut commands) {
+            let answer = |reply: Option<Reply>, result: Result<String, String>| {
+                if let Some(reply) = reply {
+                    reply.answer(result);
+                }
+            };
+            match command {
+                Command::OcrClipboard if streaming.is_reading() => {
+                    status.error(
+                        "Error getting text from clipboard",
+                        "Still reading the last image",
+                    );
+                    answer(reply, Err("Still reading the last image".into()));
+                }
+                Command::OcrClipboard => {
+                    let streamed = stream_clipboard(
+                        &clipboard,
+                        &config,
+                        &cache,
+                        &mut worker_pool,
+                        true,
+                        tabs.active_id(),
+                        (&text, cursor),
+                    );
+                    match streamed {
+                        Ok(Streamed::Job(job)) => streaming.start(job, reply),
+                        Ok(Streamed::Frames(job)) => {
+                            file_jobs.push(job, reply, true, tabs.active_id())
+                        }
+                        Ok(Streamed::Unstreamed(read)) => match transcribe_clipboard(
+                            backend.as_ref(),
+                            &clipboard,
+                            &config,
+                            read,
+                            &mut renderer,
+                            &mut history,
+                            Some(&mut secret_guard),
+                        ) {
+                            Ok((result, result_transcription, result_pages)) => {
+                                place_text(&mut text, &mut cursor, &config, &result);
+                                transcription = result_transcription;
+                                pages = result_pages;
+                                answer(reply, Ok(result));
+                            }
+                            Err(err) => {
+                                answer(reply, Err(err.to_string()));
+                                status.error("Error getting text from clipboard", err);
+                            }
+                        },
+                        Err(err) => {
+                            answer(reply, Err(err.to_string()));
+                            status.error("Error getting text from clipboard", err);
+                        }
+                    }
+                }
+                Command::OcrFile(path) => match ensure_pool(&mut worker_pool, &config, &cache) {
+                    Ok(pool) => {
+                        let (sender, job) = mpsc::channel();
+                        let job_config = config.clone();
+                        pool.submit(move |backend| {
+                            let _ = sender.send(
+                                backend
+                                    .map_err(str::to_string)
+                                    .map(|backend| files_capture(backend, &[path], &job_config)),
+                            );
+                        });
+                        file_jobs.push(job, reply, config.auto_copy, tabs.active_id());
+                    }
+                    Err(err) => {
+                        answer(reply, Err(err.to_string()));
+                        status.error("Error loading OCR models", err);
+                    }
+                },
+                Command::ToggleWatch => {
+                    watching = !watching;
+                    if let Some(tray) = &tray {
+                        tray.set_watching(watching);
+                    }
+                    answer(reply, Ok(String::new()));
+                }
+                Command::Show => {
+                    window.show();
+                    window.raise();
+                    window_hidden = false;
+                    answer(reply, Ok(String::new()));
+                }
+                Command::Quit => {
+                    answer(reply, Ok(String::new()));
+                    break 'main;
+                }
+            }
+        }
+
+        /* files sent by other instances and GIFs are recorded once the pool has read them,
+        copied when they were to be */
+        placements.extend(file_jobs.poll(
+            &config,
+            &clipboard,
+            &mut renderer,
+            &mut history,
+            &mut secret_guard,
+            &mut status,
+        ));
+
+        /* in watch mode every new image on the clipboard is queued on the pool, unless it
+        is the same as the last one */
+        if !watching {
+            last_watched = None;
+            clipboard_watch = None;
+        } else if clipboard_watch
+            .as_ref()
+            .is_none_or(|(watch, source, interval)| {
+                *source != clipboard.source
+                    || (watch.polls() && *interval != config.clipboard_poll_interval)
+            })
+        {
+            let interval = Duration::from_secs_f32(config.clipboard_poll_interval.max(0.1));
+            clipboard_watch = Some((
+                ClipboardWatch::start(clipboard.source, interval),
+                clipboard.source,
+                config.clipboard_poll_interval,
+            ));
+        }
+        let clipboard_changed = clipboard_watch
+            .as_ref()
+            .is_some_and(|(watch, ..)| watch.changed());
+        if watching && clipboard_changed && clipboard.has(ContentFormat::Image) {
+            match clipboard_image(&clipboard, &config) {
+                Ok(image) => {
+                    let digest = ImageDigest::of(&image);
+                    let repeated = config.skip_repeated_images
+                        && last_watched
+                            .as_ref()
+                            .is_some_and(|last| last.is_same(&digest));
+                    if repeated {
+                        info!("Skipping clipboard image that is the same as the last one");
+                    } else {
+                        match ensure_pool(&mut worker_pool, &config, &cache) {
+                            Ok(pool) => {
+                                last_watched = Some(digest);
+                                let (sender, job) = mpsc::channel();
+                                let preprocessing = config.preprocessing.clone();
+                                pool.submit(move |backend| {
+                                    let result =
+                                        backend.map_err(str::to_string).and_then(|backend| {
+                                            ocr::transcribe_preprocessed(
+                                                backend,
+                                                &image,
+                                                &preprocessing,
+                                            )
+                                            .map_err(|err| err.to_string())
+                                        });
+                                    let _ = sender.send((image, result));
+                                });
+                                watch_jobs.push_back((tabs.active_id(), job));
+                            }
+                            Err(err) => status.error("Error loading OCR models", err),
+                        }
+                    }
+                }
+                Err(err) => status.error("Error getting text from clipboard", err),
+            }
+        }
+
+        /* watch mode results are placed in the order the images were copied, however the
+        pool gets through them */
+        while let Some((tab, job)) = watch_jobs.front() {
+            let (tab, result) = match job.try_recv() {
+                Ok((image, result)) => (*tab, result.map(|read| (image, read))),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => (*tab, Err("The OCR worker stopped".into())),
+            };
+            watch_jobs.pop_front();
+            let result =
+                result
+                    .map_err(TranscribeError::Ocr)
+                    .and_then(|(image, result_transcription)| {
+                        let capture = Capture {
+                            text: config.render(&result_transcription),
+                            image: Some(image),
+                            transcription: Some(result_transcription),
+                            pages: Vec::new(),
+                            note: Some("clipboard image".into()),
+                        };
+                        record_capture(
+                            capture,
+                            &config,
+                            &clipboard,
+                            &mut renderer,
+                            &mut history,
+                            config.auto_copy.then_some(&mut secret_guard),
+                        )
+                    });
+            match result {
+                Ok((result, result_transcription, result_pages)) => placements.push(Placement {
+                    tab,
+                    result,
+                    before: None,
+                    read: Some((result_transcription, result_pages)),
+                    merge: true,
+                }),
+                Err(err) => status.error("Error extracting text from image", err),
+            }
+        }
+
+        /* files dropped into the watched folder end up in the history */
+        if let Some(watch) = &mut folder_watch {
+            for (path, result) in watch.poll() {
+                let result = result.map_err(TranscribeError::Ocr).and_then(|result| {
+                    let note = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned());
+                    let capture = Capture {
+                        text: result.text,
+                        image: Some(result.image),
+                        transcription: result.transcription,
+                        pages: Vec::new(),
+                        note: note.or(Some("watched folder".into())),
+                    };
+                    record_capture(
+                        capture,
+                        &config,
+                        &clipboard,
+                        &mut renderer,
+                        &mut history,
+                        None,
+                    )
+                });
+                if let Err(err) = result {
+                    status.error(&format!("Error transcribing {}", path.display()), err);
+                }
+            }
+        }
+
+        /* the hotkey transcribes and copies the result back without touching the window */
+        while let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
+            if event.state() != HotKeyState::Pressed {
+                continue;
+            }
+            if Some(event.id()) == overlay_hotkey.map(|hotkey| hotkey.id()) {
+                if live_overlay.toggle() && snip_job.is_none() && overlay.is_none() {
+                    match desktop_area(&video_subsystem) {
+                        Ok(desktop) => {
+                            snip_window = Some((window.position(), window.size()));
+                            snip_purpose = SnipPurpose::LiveOverlay;
+                            window.hide();
+                            snip_job = Some(spawn_snip(desktop));
+                        }
+                        Err(err) => status.error("Error capturing the screen", err),
+                    }
+                }
+                continue;
+            }
+            if Some(event.id()) == window_hotkey.map(|hotkey| hotkey.id()) {
+                if capture_job.is_none() {
+                    capture_job = Some((spawn_window_capture(), true, tabs.active_id()));
+                }
+                continue;
+            }
+            let monitor = monitor_hotkeys.iter().position(|monitor_hotkey| {
+                monitor_hotkey.map(|hotkey| hotkey.id()) == Some(event.id())
+            });
+            if let Some(monitor) = monitor {
+                if capture_job.is_none() {
+                    match spawn_monitor_capture(&video_subsystem, monitor) {
+                        Ok(job) => capture_job = Some((job, true, tabs.active_id())),
+                        Err(err) => status.error("Error capturing the screen", err),
+                    }
+                }
+                continue;
+            }
+            if Some(event.id()) != hotkey.map(|hotkey| hotkey.id()) {
+                continue;
+            }
+            if streaming.is_reading() {
+                status.error(
+                    "Error getting text from clipboard",
+                    "Still reading the last image",
+                );
+                continue;
+            }
+            let streamed = stream_clipboard(
+                &clipboard,
+                &config,
+                &cache,
+                &mut worker_pool,
+                true,
+                tabs.active_id(),
+                (&text, cursor),
+            );
+            match streamed {
+                Ok(Streamed::Job(job)) => streaming.start(job, None),
+                Ok(Streamed::Frames(job)) => file_jobs.push(job, None, true, tabs.active_id()),
+                Ok(Streamed::Unstreamed(read)) => match transcribe_clipboard(
+                    backend.as_ref(),
+                    &clipboard,
+                    &config,
+                    read,
+                    &mut renderer,
+                    &mut history,
+                    Some(&mut secret_guard),
+                ) {
+                    Ok((result, result_transcription, result_pages)) => {
+                        place_text(&mut text, &mut cursor, &config, &result);
+                        transcription = result_transcription;
+                        pages = result_pages;
+                    }
+                    Err(err) => status.error("Error getting text from clipboard", err),
+                },
+                Err(err) => status.error("Error getting text from clipboard", err),
+            }
+        }
+
+        /* a streamed image shows its lines as they are read, the result is
+        recorded like any other once it is done */
+        placements.extend(streaming.poll(StreamTargets {
+            config: &config,
+            clipboard: &clipboard,
+            renderer: &mut renderer,
+            history: &mut history,
+            preview: &mut preview,
+            secret_guard: &mut secret_guard,
+            status: &mut status,
+            window_hidden,
+        }));
+
+        /* a result held back for its secrets needs the window to ask about it */
+        if secret_guard.is_pending() && window_hidden {
+            window.show();
+            window.raise();
+            window_hidden = false;
+        }
+
+        /* pick up the audio transcript once whisper is done */
+        #[cfg(feature = "whisper")]
+        if let Some((tab, result)) = audio_job
+            .as_mut()
+            .and_then(|(tab, job)| Some((*tab, job.poll()?)))
+        {
+            audio_job = None;
+            match result {
+                Ok(transcript) => {
+                    let result = transcript.text();
+                    subtitle_cues = Some((transcript.segments, "audio"));
+                    placements.push(Placement {
+                        tab,
+                        result: result.clone(),
+                        before: None,
+                        read: Some((None, Vec::new())),
+                        merge: false,
+                    });
+                    if config.auto_copy {
+                        let copied = config.templated(&result, Some("audio"), None);
+                        if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                    remember(
+                        &mut history,
+                        &mut renderer,
+                        HistoryEntry {
+                            text: result,
+                            transcription: None,
+                            thumbnail: None,
+                            thumbnail_image: None,
+                        },
+                        None,
+                        Some("audio"),
+                    );
+                }
+                Err(err) => status.error("Error transcribing audio", err),
+            }
+        }
+
+        /* open the screenshot once the user picked the area */
+        #[cfg(target_os = "linux")]
+        if let Some(job) = &screenshot_job {
+            match job.try_recv() {
+                Ok(Ok(Some(image))) => {
+                    if let Err(err) = open_preview(&mut preview, &mut renderer, image) {
+                        status.error("Error opening screenshot", err);
+                    }
+                    screenshot_job = None;
+                }
+                Ok(Ok(None)) => screenshot_job = None,
+                Ok(Err(err)) => {
+                    status.error("Error taking screenshot", err);
+                    screenshot_job = None;
+                }
+                Err(TryRecvError::Empty) => (),
+                Err(TryRecvError::Disconnected) => {
+                    status.error("Error taking screenshot", "worker stopped");
+                    screenshot_job = None;
+                }
+            }
+        }
+
+        /* cover the desktop with the screenshots once every monitor is captured */
+        if let Some(job) = &snip_job {
+            let shots = match job.try_recv() {
+                Ok(Ok(shots)) => Some(Overlay::new(&mut renderer, shots)),
+                Ok(Err(err)) => Some(Err(err)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err("worker stopped".to_string())),
+            };
+            match shots {
+                Some(Ok(opened)) => {
+                    let desktop = opened.desktop;
+                    window.set_bordered(false);
+                    window.set_always_on_top(true);
+                    window.set_position(
+                        WindowPos::Positioned(desktop.x),
+                        WindowPos::Positioned(desktop.y),
+                    );
+                    if let Err(err) = window.set_size(desktop.width, desktop.height) {
+                        error!("Error covering the desktop: {}", err);
+                    }
+                    window.show();
+                    window.raise();
+                    overlay = Some(opened);
+                    snip_job = None;
+                }
+                Some(Err(err)) => {
+                    status.error("Error capturing the screen", err);
+                    // the window was only hidden, it is still where it was
+                    snip_window = None;
+                    window.show();
+                    snip_job = None;
+                }
+                None => (),
+            }
+        }
+
+        /* transcribe the focused window or monitor once it is captured */
+        if let Some((job, from_hotkey, tab)) = &capture_job {
+            let (from_hotkey, tab) = (*from_hotkey, *tab);
+            let captured = match job.try_recv() {
+                Ok(captured) => Some(captured),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err("worker stopped".to_string())),
+            };
+            if let Some(captured) = captured {
+                capture_job = None;
+                if !from_hotkey {
+                    window.show();
+                }
+                match captured.map(|image| {
+                    transcribe_region(
+                        backend.as_ref(),
+                        image,
+                        &config,
+                        &clipboard,
+                        &mut renderer,
+                        &mut history,
+                        &mut secret_guard,
+                    )
+                }) {
+                    Ok(Ok((result, result_transcription, result_pages))) => {
+                        // the hotkey always copies, as the clipboard one does
+                        if from_hotkey && !config.auto_copy {
+                            let source = result_transcription
+                                .as_ref()
+                                .and_then(|read| read.source.as_deref());
+                            let copied =
+                                config.templated(&result, source, result_transcription.as_ref());
+                            if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                                status.error("Error setting text to clipboard", err);
+                            }
+                        }
+                        placements.push(Placement {
+                            tab,
+                            result,
+                            before: None,
+                            read: Some((result_transcription, result_pages)),
+                            merge: false,
+                        });
+                    }
+                    Ok(Err(err)) => status.error("Error extracting text from screen", err),
+                    Err(err) => status.error("Error capturing the screen", err),
+                }
+            }
+        }
+
+        /* put the window back once the region is picked or the snip cancelled */
+        if overlay.as_ref().is_some_and(|overlay| !overlay.open) {
+            if let Some(closed) = overlay.take() {
+                closed.destroy(&renderer);
+            }
+            if !live_overlay.cover(&mut window, &config) {
+                window.set_always_on_top(false);
+                window.set_bordered(true);
+                if let Some(((x, y), (width, height))) = snip_window.take() {
+                    window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+                    if let Err(err) = window.set_size(width, height) {
+                        error!("Error restoring window size: {}", err);
+                    }
+                }
+            }
+        }
+
+        /* put the window back once the live overlay is stopped */
+        if live_overlay.stopped(&mut window) {
+            if let Some(((x, y), (width, height))) = snip_window.take() {
+                window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+                if let Err(err) = window.set_size(width, height) {
+                    error!("Error restoring window size: {}", err);
+                }
+            }
+        }
+
+        /* follow the video being transcribed */
+        let finished = video_job
+            .as_mut()
+            .and_then(|(tab, job)| Some((*tab, job.poll()?)));
+        if let Some((tab, result)) = finished {
+            video_job = None;
+            match result {
+                Ok(cues) => {
+                    let result = subtitle::timestamped_text(&cues);
+                    placements.push(Placement {
+                        tab,
+                        result: result.clone(),
+                        before: None,
+                        read: Some((None, Vec::new())),
+                        merge: false,
+                    });
+                    if config.auto_copy {
+                        let copied = config.templated(&result, Some("video"), None);
+                        if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                    remember(
+                        &mut history,
+                        &mut renderer,
+                        HistoryEntry {
+                            text: result,
+                            transcription: None,
+                            thumbnail: None,
+                            thumbnail_image: None,
+                        },
+                        None,
+                        Some("video"),
+                    );
+                    subtitle_cues = Some((cues, "video"));
+                }
+                Err(err) => status.error("Error transcribing video", err),
+            }
+        }
+
+        /* append dictated speech as whisper gets through it */
+        #[cfg(feature = "dictation")]
+        if let Some((tab, session)) = &dictation {
+            // checked before polling, a chunk finishing in between is still picked up
+            let finished = session.is_finished();
+            for result in session.poll() {
+                match result {
+                    Ok(spoken) => {
+                        let active = Editor {
+                            text: &mut text,
+                            cursor: &mut cursor,
+                            transcription: &mut transcription,
+                            pages: &mut pages,
+                            undo: &mut undo,
+                            panels: &mut panels,
+                        };
+                        if let Some(Editor { text, .. }) = tabs.editor(*tab, active) {
+                            if !text.is_empty() && !text.ends_with(char::is_whitespace) {
+                                text.push(' ');
+                            }
+                            text.push_str(&spoken);
+                        }
+                    }
+                    Err(err) => status.error("Error transcribing dictation", err),
+                }
+            }
+            if finished {
+                dictation = None;
+            }
+        }
+
+        /* the results of jobs go to the tabs they were started from, those of tabs closed
+        meanwhile are only kept in the history */
+        // the tab the last of them goes to, where its formulas are read into
+        let placed = placements.last().map(|placement| placement.tab);
+        for placement in placements.drain(..) {
+            let active = Editor {
+                text: &mut text,
+                cursor: &mut cursor,
+                transcription: &mut transcription,
+                pages: &mut pages,
+                undo: &mut undo,
+                panels: &mut panels,
+            };
+            if let Some(editor) = tabs.editor(placement.tab, active) {
+                place(editor, &config, placement);
+            }
+        }
+
+        #[cfg(feature = "tts")]
+        if let Some(reader) = &mut reader {
+            if let Err(err) = reader.update() {
+                status.error("Error reading aloud", err);
+                reader.stop();
+            }
+        }
+
+        /* no need to draw while living in the tray, vsync does not pace a hidden window */
+        if window_hidden {
+            thread::sleep(Duration::from_millis(50));
+            continue;
+        }
+
+        /* textures can only be freed while no frame is drawing them */
+        if preview.as_ref().is_some_and(|preview| !preview.open) {
+            if let Some(closed) = preview.take() {
+                closed.destroy(&renderer);
+            }
+        }
+
+        /* fonts can only be rebuilt while no frame is in progress */
+        if appearance_changed {
+            appearance_changed = false;
+            match theme::rebuild_fonts(&mut imgui, &mut renderer, &mut font_texture, &config) {
+                Ok(monospace) => monospace_font = monospace,
+                Err(err) => status.error("Error rebuilding fonts", err),
+            }
+            theme::apply_style(
+                imgui.style_mut(),
+                &base_style,
+                config.theme,
+                config.ui_scale,
+            );
+        }
+
+        /* the layout of a recovered autosave, when it had one */
+        if let Some(layout) = recovered_layout.take().filter(|layout| !layout.is_empty()) {
+            imgui.load_ini_settings(&layout);
+        }
+
+        /* call prepare_frame before calling imgui.new_frame() */
+        platform.prepare_frame(&mut imgui, &window, &event_pump);
+
+        let ui = imgui.new_frame();
+
+        /* create imgui UI here */
+
+        /* every button with a shortcut also checks whether it was pressed */
+        let pressed = bindings.pressed(ui);
+
+        let get_clipboard = {
+            let _disabled = ui.begin_disabled(streaming.is_reading());
+            ui.button(tr("Get clipboard"))
+        };
+        if (get_clipboard || pressed == Some(Action::GetClipboard)) && !streaming.is_reading() {
+            let streamed = stream_clipboard(
+                &clipboard,
+                &config,
+                &cache,
+                &mut worker_pool,
+                config.auto_copy,
+                tabs.active_id(),
+                (&text, cursor),
+            );
+            match streamed {
+                Ok(Streamed::Job(job)) => streaming.start(job, None),
+                Ok(Streamed::Frames(job)) => {
+                    file_jobs.push(job, None, config.auto_copy, tabs.active_id())
+                }
+                Ok(Streamed::Unstreamed(read)) => match transcribe_clipboard(
+                    backend.as_ref(),
+                    &clipboard,
+                    &config,
+                    read,
+                    &mut renderer,
+                    &mut history,
+                    config.auto_copy.then_some(&mut secret_guard),
+                ) {
+                    Ok((result, result_transcription, result_pages)) => {
+                        place_text(&mut text, &mut cursor, &config, &result);
+                        transcription = result_transcription;
+                        pages = result_pages;
+                    }
+                    Err(err) => status.error("Error getting text from clipboard", err),
+                },
+                Err(err) => status.error("Error getting text from clipboard", err),
+            }
+        }
+
+        ui.same_line();
+
+        /* the previewed image can be cropped to the part worth transcribing */
+        if ui.button(tr("Select region...")) {
+            let opened =
+                clipboard_preview_image(&clipboard, &config).and_then(|(image, metadata)| {
+                    open_preview(&mut preview, &mut renderer, image)?;
+                    if let Some(preview) = &mut preview {
+                        preview.set_metadata(metadata);
+                    }
+                    Ok(())
+                });
+            if let Err(err) = opened {
+                status.error("Error previewing clipboard image", err);
+            }
+        }
+
+        /* the desktop lets the user pick the area, the shot then opens in the preview */
+        #[cfg(target_os = "linux")]
+        {
+            ui.same_line();
+            let _disabled = ui.begin_disabled(screenshot_job.is_some());
+            if ui.button(tr("Capture region")) {
+                screenshot_job = Some(screenshot::spawn());
+            }
+        }
+
+        ui.same_line();
+
+        /* hide the window, capture every monitor and pick the region over the screenshots */
+        {
+            let _disabled = ui.begin_disabled(
+                snip_job.is_some()
+                    || overlay.is_some()
+                    || scroll_capture.is_some()
+                    || live_overlay.is_running(),
+            );
+            let snip = ui.button(tr("Snip screen")) || pressed == Some(Action::SnipScreen);
+            ui.same_line();
+            let scrolling = ui.button(tr("Scrolling capture"));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr(
+                    "Pick a region, then scroll it to capture more than fits",
+                ));
+            }
+            ui.same_line();
+            let live = ui.button(tr("Live overlay"));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr(
+                    "Pick a region of a game or video to read again and again, with its text or translation shown on top",
+                ));
+            }
+            ui.same_line();
+            let pinning = ui.begin_disabled(pinned.is_some());
+            let pin = ui.button(tr("Pin region"));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr(
+                    "Pick a region to read again every few seconds, adding its new text to the transcript",
+                ));
+            }
+            ui.same_line();
+            let captions = ui.button(tr("Record captions"));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr(
+                    "Pick the region live captions show in to record them as timed subtitles",
+                ));
+            }
+            pinning.end();
+            ui.same_line();
+            if ui.button(tr("Compose...")) {
+                compose_open = !compose_open;
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr(
+                    "Snip several regions one after the other and put them together under labels",
+                ));
+            }
+            let busy = snip_job.is_some() || overlay.is_some();
+            // a region asked for while busy is snipped once it is free
+            let purpose = if compose_region && !busy {
+                compose_region = false;
+                Some(SnipPurpose::Compose)
+            } else if scrolling {
+                Some(SnipPurpose::Scrolling)
+            } else if live {
+                Some(SnipPurpose::LiveOverlay)
+            } else if pin {
+                Some(SnipPurpose::Pin)
+            } else if captions {
+                Some(SnipPurpose::Captions)
+            } else {
+                snip.then_some(SnipPurpose::Transcribe)
+            };
+            if let Some(purpose) = purpose {
+                match desktop_area(&video_subsystem) {
+                    Ok(desktop) => {
+                        snip_window = Some((window.position(), window.size()));
+                        snip_purpose = purpose;
+                        window.hide();
+                        snip_job = Some(spawn_snip(desktop));
+                    }
+                    Err(err) => status.error("Error capturing the screen", err),
+                }
+            }
+        }
+
+        ui.same_line();
+
+        /* hide the window so the one behind it gets the focus back, then capture that */
+        {
+            let _disabled = ui.begin_disabled(capture_job.is_some() || overlay.is_some());
+            if ui.button(tr("Capture window")) {
+                window.hide();
+                capture_job = Some((spawn_window_capture(), false, tabs.active_id()));
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("Transcribe the window that was active before this one"));
+            }
+            ui.same_line();
+            if ui.button(tr("Capture monitor")) {
+                ui.open_popup("monitors");
+            }
+            ui.popup("monitors", || {
+                let areas = display_areas(&video_subsystem).unwrap_or_default();
+                for (monitor, area) in areas.iter().enumerate() {
+                    let label = format!(
+                        "Monitor {} ({}x{} at {}, {})",
+                        monitor + 1,
+                        area.width,
+                        area.height,
+                        area.x,
+                        area.y
+                    );
+                    if ui.menu_item(label) {
+                        match spawn_monitor_capture(&video_subsystem, monitor) {
+                            Ok(job) => {
+                                window.hide();
+                                capture_job = Some((job, false, tabs.active_id()));
+                            }
+                            Err(err) => status.error("Error capturing the screen", err),
+                        }
+                    }
+                }
+            });
+        }
+
+        ui.same_line();
+
+        // where what the editor holds was read from, for the templates and the names of the
+        // files saved of it
+        let source = transcription
+            .as_ref()
+            .or(pages.first())
+            .and_then(|read| read.source.clone());
+
+        if ui.button(tr("Copy")) || pressed == Some(Action::Copy) {
+            let copied = config.templated(&text, source.as_deref(), transcription.as_ref());
+            if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                status.error("Error setting text to clipboard", err);
+            }
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Save as...")) || pressed == Some(Action::SaveAs) {
+            ui.open_popup("save_as");
+        }
+
+        ui.popup("save_as", || {
+            for format in ExportFormat::ALL {
+                let enabled = transcription.is_some() || !format.needs_transcription();
+                if ui.menu_item_config(format.label()).enabled(enabled).build() {
+                    let path = save_dialog(
+                        &config,
+                        "transcription",
+                        format.extension(),
+                        source.as_deref(),
+                    )
+                    .add_filter(format.label(), &[format.extension()])
+                    .save_file();
+                    if let Some(path) = path {
+                        // only plain text goes through the template, the other formats
+                        // have a structure of their own
+                        let text = match format {
+                            ExportFormat::Text => {
+                                config.templated(&text, source.as_deref(), transcription.as_ref())
+                            }
+                            _ => text.clone(),
+                        };
+                        if let Err(err) = export::save(format, &path, &text, transcription.as_ref())
+                        {
+                            status.error("Error saving transcription", err);
+                        }
+                    }
+                }
+            }
+            ui.separator();
+            // every page with the image it was read from, one for a transcription and
+            // each page of a read of several
+            let read: Vec<(&TranscriptionResult, &DynamicImage)> = transcription
+                .iter()
+                .chain(&pages)
+                .filter_map(|page| page.image.as_deref().map(|image| (page, image)))
+                .collect();
+            let annotated = transcription
+                .as_ref()
+                .and_then(|transcription| Some((transcription, transcription.image.as_deref()?)));
+            if ui
+                .menu_item_config(tr("Annotated image"))
+                .enabled(annotated.is_some())
+                .build()
+            {
+                let path = save_dialog(&config, "annotated", "png", source.as_deref())
+                    .add_filter("PNG", &["png"])
+                    .save_file();
+                if let (Some(path), Some((transcription, image))) = (path, annotated) {
+                    if let Err(err) = annotate::render(image, transcription).save(&path) {
+                        status.error("Error saving annotated image", err);
+                    }
+                }
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr(
+                    "The image with a box and the recognized text over every line, as PNG",
+                ));
+            }
+            if ui
+                .menu_item_config(tr("Searchable PDF"))
+                .enabled(!read.is_empty())
+                .build()
+            {
+                let path = save_dialog(&config, "transcription", "pdf", source.as_deref())
+                    .add_filter("PDF", &["pdf"])
+                    .save_file();
+                if let Some(path) = path {
+                    let pages: Vec<(DynamicImage, TranscriptionResult)> = read
+                        .iter()
+                        .map(|(page, image)| ((*image).clone(), (*page).clone()))
+                        .collect();
+                    if let Err(err) = pdf::save(&path, &pages, files::PDF_DPI as f32) {
+                        status.error("Error saving searchable PDF", err);
+                    }
+                }
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr(
+                    "The image as a PDF with the recognized text over it, invisible but searchable",
+                ));
+            }
+        });
+
+        ui.same_line();
+        if ui.button(tr("Send to...")) {
+            ui.open_popup("send_to");
+        }
+        ui.popup("send_to", || {
+            if config.send_targets.is_empty() {
+                ui.text_disabled(tr("Add targets under \"Send to\" in Settings"));
+            }
+            for (index, target) in config.send_targets.iter().enumerate() {
+                if ui.menu_item(format!("{}##target{}", target.name, index)) {
+                    delivery.deliver(target, &text);
+                }
+            }
+        });
+
+        /* audio files come from the clipboard when it holds a path to one */
+        #[cfg(feature = "whisper")]
+        {
+            ui.same_line();
+            let _disabled = ui.begin_disabled(audio_job.is_some());
+            if ui.button(tr("Transcribe audio...")) {
+                let path = clipboard_audio_path(&clipboard).or_else(|| {
+                    rfd::FileDialog::new()
+                        .add_filter("Audio", audio::EXTENSIONS)
+                        .pick_file()
+                });
+                if let Some(path) = path {
+                    audio_job = Some((tabs.active_id(), spawn_audio_transcription(&config, path)));
+                }
+            }
+        }
+
+        ui.same_line();
+
+        {
+            let _disabled = ui.begin_disabled(video_job.is_some());
+            if ui.button(tr("OCR video...")) {
+                let path = rfd::FileDialog::new()
+                    .add_filter("Video", video::EXTENSIONS)
+                    .pick_file();
+                if let Some(path) = path {
+                    video_job = Some((
+                        tabs.active_id(),
+                        spawn_video_transcription(&config, &cache, path),
+                    ));
+                }
+            }
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Open OCR file...")) {
+            let path = rfd::FileDialog::new()
+                .add_filter("hOCR and ALTO", import::EXTENSIONS)
+                .pick_file();
+            if let Some(path) = path {
+                let result = open_ocr_file(&path, &config)
+                    .map_err(|err| TranscribeError::UnsupportedFormat(err.to_string()))
+                    .and_then(|capture| {
+                        record_capture(
+                            capture,
+                            &config,
+                            &clipboard,
+                            &mut renderer,
+                            &mut history,
+                            None,
+                        )
+                    });
+                match result {
+                    Ok((result, result_transcription, result_pages)) => {
+                        place_text(&mut text, &mut cursor, &config, &result);
+                        transcription = result_transcription;
+                        pages = result_pages;
+                    }
+                    Err(err) => status.error("Error opening OCR file", err),
+                }
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr(
+                "An hOCR or ALTO file of an earlier OCR run, with its image, to correct and export again",
+            ));
+        }
+
+        ui.same_line();
+
+        /* closing the batch window cancels the files not started yet */
+        {
+            let _disabled = ui.begin_disabled(batch.is_some());
+            if ui.button(tr("Process folder...")) {
+                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                    match BatchJob::start(&config, &cache, dir) {
+                        Ok(job) => batch = Some(job),
+                        Err(err) => status.error("Error processing folder", err),
+                    }
+                }
+            }
+        }
+
+        ui.same_line();
+
+        /* the folder stays watched across restarts until stopped */
+        if let Some(watch) = &folder_watch {
+            if ui.button(tr("Stop watching folder")) {
+                folder_watch = None;
+                config.watched_folder = PathBuf::new();
+                if let Err(err) = config.save() {
+                    error!("Error saving config: {}", err);
+                }
+            } else if ui.is_item_hovered() {
+                ui.tooltip_text(watch.dir.display().to_string());
+            }
+        } else if ui.button(tr("Watch folder...")) {
+            if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                match FolderWatch::start(&config, &cache, dir.clone()) {
+                    Ok(watch) => {
+                        folder_watch = Some(watch);
+                        config.watched_folder = dir;
+                        if let Err(err) = config.save() {
+                            error!("Error saving config: {}", err);
+                        }
+                    }
+                    Err(err) => status.error("Error watching folder", err),
+                }
+            }
+        }
+
+        if let Some((cues, source)) = &subtitle_cues {
+            ui.same_line();
+            if ui.button(tr("Save subtitles...")) {
+                let path = save_dialog(&config, "subtitles", "srt", Some(source))
+                    .add_filter("SubRip", &["srt"])
+                    .add_filter("WebVTT", &["vtt"])
+                    .add_filter("Text", &["txt"])
+                    .add_filter("JSON", &["json"])
+                    .save_file();
+                if let Some(path) = path {
+                    let subtitles = subtitle::render_for(&path, cues, config.subtitle_line_length);
+                    if let Err(err) = std::fs::write(&path, subtitles) {
+                        status.error("Error saving subtitles", err);
+                    }
+                }
+            }
+        }
+
+        /* the last words are still transcribed after stopping, hence disabled until done,
+        and the model is loaded on a worker before recording starts */
+        #[cfg(feature = "dictation")]
+        {
+            if let Some((tab, job)) = &dictation_model {
+                let tab = *tab;
+                let loaded = match job.try_recv() {
+                    Ok(loaded) => Some(loaded),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => Some(Err("worker stopped".into())),
+                };
+                if let Some(loaded) = loaded {
+                    dictation_model = None;
+                    match loaded.and_then(|transcriber| {
+                        Dictation::start(transcriber).map_err(|err| err.to_string())
+                    }) {
+                        Ok(session) => dictation = Some((tab, session)),
+                        Err(err) => status.error("Error starting dictation", err),
+                    }
+                }
+            }
+            ui.same_line();
+            let recording = dictation
+                .as_ref()
+                .is_some_and(|(_, session)| session.is_recording());
+            if recording {
+                if ui.button(tr("Stop dictation")) {
+                    if let Some((_, session)) = &mut dictation {
+                        session.stop();
+                    }
+                }
+            } else if dictation_model.is_some() {
+                let _disabled = ui.begin_disabled(true);
+                ui.button(tr("Loading model..."));
+            } else {
+                let _disabled = ui.begin_disabled(dictation.is_some());
+                if ui.button(tr("Dictate")) {
+                    let (sender, job) = mpsc::channel();
+                    let (model, language) = (
+                        config.whisper_model.clone(),
+                        config.whisper_language.clone(),
+                    );
+                    thread::spawn(move || {
+                        let _ = sender.send(
+                            Transcriber::new(&model, &language).map_err(|err| err.to_string()),
+                        );
+                    });
+                    dictation_model = Some((tabs.active_id(), job));
+                }
+            }
+        }
+
+        #[cfg(feature = "camera")]
+        {
+            ui.same_line();
+            if ui.button(tr("Camera")) {
+                camera_open = !camera_open;
+                if !camera_open {
+                    scanner.close(&renderer);
+                }
+            }
+        }
+
+        /* the speech engine is only started when first needed */
+        #[cfg(feature = "tts")]
+        {
+            ui.same_line();
+            match &mut reader {
+                Some(session) if session.is_reading() => {
+                    if session.is_paused() {
+                        if ui.button(tr("Resume")) {
+                            if let Err(err) = session.resume() {
+                                status.error("Error reading aloud", err);
+                                session.stop();
+                            }
+                        }
+                    } else if ui.button(tr("Pause")) {
+                        session.pause();
+                    }
+                    ui.same_line();
+                    if ui.button(tr("Stop reading")) {
+                        session.stop();
+                    }
+                }
+                _ => {
+                    let _disabled = ui.begin_disabled(text.trim().is_empty());
+                    if ui.button(tr("Read aloud")) {
+                        if reader.is_none() {
+                            match Reader::new() {
+                                Ok(session) => reader = Some(session),
+                                Err(err) => status.error("Error starting text to speech", err),
+                            }
+                        }
+                        if let Some(session) = &mut reader {
+                            session.set_rate(config.speech_rate);
+                            if let Err(err) = session.read(&text) {
+                                status.error("Error reading aloud", err);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        ui.same_line();
+
+        {
+            let pending = matches!(panels.cleanup, Some(Cleanup::Pending { .. }));
+            let _disabled = ui.begin_disabled(pending || text.trim().is_empty());
+            if ui.button(tr("Clean up with AI")) {
+                panels.cleanup = Some(Cleanup::start(&config, &text));
+            }
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Translate...")) {
+            translating = true;
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Extract fields...")) {
+            extracting = true;
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Anonymize...")) {
+            anonymizing = !anonymizing;
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Statistics")) {
+            statistics_open = !statistics_open;
+        }
+
+        ui.same_line();
+
+        // counts what is in the text as it is now, such as a new result
+        link_panel.update(&text);
+        if ui.button(format!("{} ({})###links", tr("Links"), link_panel.count())) {
+            links_open = !links_open;
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Copy lines...")) {
+            lines_open = !lines_open;
+        }
+
+        if config.check_codes {
+            ui.same_line();
+            code_panel.update(&text);
+            let label = match code_panel.failed() {
+                0 => format!("{} ({})###codes", tr("Codes"), code_panel.count()),
+                failed => format!(
+                    "{} ({}, {} {})###codes",
+                    tr("Codes"),
+                    code_panel.count(),
+                    failed,
+                    tr("failed")
+                ),
+            };
+            if ui.button(label) {
+                codes_open = !codes_open;
+            }
+        }
+
+        /* code is checked again once the text changed and then stayed the same for a moment */
+        if config.text_layout == TextLayout::Code && config.code_language != CodeLanguage::None {
+            if syntax_check.as_ref().is_none_or(|(checked, language, _)| {
+                *checked != text || *language != config.code_language
+            }) {
+                if syntax_changed
+                    .as_ref()
+                    .is_none_or(|(changed, _)| *changed != text)
+                {
+                    syntax_changed = Some((text.clone(), Instant::now()));
+                }
+                let settled = syntax_changed
+                    .as_ref()
+                    .is_some_and(|(_, since)| since.elapsed() >= SYNTAX_DELAY);
+                if syntax_check.is_none() || settled {
+                    let result = config.code_language.check(&text);
+                    syntax_check = Some((text.clone(), config.code_language, result));
+                    syntax_changed = None;
+                }
+            }
+            if let Some((_, language, result)) = &syntax_check {
+                ui.same_line();
+                match result {
+                    Ok(()) => ui.text_disabled(format!("{} {}", language.label(), tr("is valid"))),
+                    Err(err) => {
+                        let first = err.lines().next().unwrap_or_default();
+                        ui.text_colored(
+                            [0.9, 0.3, 0.3, 1.0],
+                            format!("{}: {}", language.label(), first),
+                        );
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(err);
+                        }
+                    }
+                }
+            }
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Compare")) {
+            compare_open = !compare_open;
+        }
+
+        ui.same_line();
+
+        book.update(&history, &mut status);
+        if ui.button(tr("Book...")) {
+            book_open = !book_open;
+        }
+
+        ui.same_line();
+
+        let toggle_watch = pressed == Some(Action::ToggleWatch);
+        if toggle_watch {
+            watching = !watching;
+        }
+        if ui.checkbox(tr("Watch clipboard"), &mut watching) || toggle_watch {
+            if let Some(tray) = &tray {
+                tray.set_watching(watching);
+            }
+        }
+
+        ui.same_line();
+
+        if ui.checkbox(tr("Copy result automatically"), &mut config.auto_copy) {
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Settings...")) || pressed == Some(Action::Settings) {
+            settings_open = true;
+        }
+        if pressed == Some(Action::CheatSheet) {
+            cheat_sheet_open = !cheat_sheet_open;
+        }
+
+        ui.same_line();
+
+        if ui.button(tr("Log...")) {
+            logging.open = true;
+            logging.errors_only = false;
+        }
+
+        ui.same_line();
+
+        about.poll(&config, &mut status, &mut about_open);
+        if ui.button(tr("About...")) {
+            about_open = !about_open;
+        }
+
+        let mut picked = None;
+        if let Some(name) = projects::pick(ui, &config) {
+            picked = projects::open(name, &mut config, &mut history, &mut renderer, &mut status);
+            announced = history.pushed();
+        }
+
+        if !config.profiles.is_empty() {
+            let preview_value = if config.profile.is_empty() {
+                "Custom"
+            } else {
+                config.profile.as_str()
+            };
+            if let Some(_combo) = ui.begin_combo(tr("Profile"), preview_value) {
+                for profile in &config.profiles {
+                    if ui
+                        .selectable_config(&profile.name)
+                        .selected(profile.name == config.profile)
+                        .build()
+                    {
+                        picked = Some(profile.clone());
+                    }
+                }
+            }
+        }
+        if let Some(profile) = picked {
+            let reload = !profile.same_engine(&config);
+            let previous = config.clone();
+            profile.apply(&mut config);
+            if reload {
+                switch_backend(&mut backend, &cache, &mut config, &mut status, |config| {
+                    *config = previous
+                });
+            } else if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+
+        if BACKENDS.len() > 1 {
+            let mut backend_index = BACKENDS
+                .iter()
+                .position(|name| *name == backend.name())
+                .unwrap_or(0);
+            if ui.combo_simple_string(tr("Backend"), &mut backend_index, BACKENDS) {
+                let previous =
+                    std::mem::replace(&mut config.backend, BACKENDS[backend_index].into());
+                switch_backend(&mut backend, &cache, &mut config, &mut status, |config| {
+                    config.backend = previous
+                });
+            }
+        }
+
+        /* local or cloud is picked for the captures to come, and starts out local */
+        if !config.cloud_backend.is_empty() {
+            let mut cloud = config.cloud.is_set();
+            if ui.checkbox(tr("Cloud OCR"), &mut cloud) {
+                if let Err(err) = use_cloud(&config, cloud) {
+                    status.error("Error setting up cloud OCR", err);
+                }
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(format!(
+                    "{} {}",
+                    tr("Send the next captures to"),
+                    config.cloud_backend
+                ));
+            }
+            ui.same_line();
+        }
+
+        if backend.name() == "ocrs" {
+            let mut language_index = config
+                .languages
+                .iter()
+                .position(|language| language.name == config.language)
+                .unwrap_or(0);
+            let language_names: Vec<&str> = config
+                .languages
+                .iter()
+                .map(|language| language.name.as_str())
+                .collect();
+            if ui.combo_simple_string(tr("Language"), &mut language_index, &language_names) {
+                let previous = std::mem::replace(
+                    &mut config.language,
+                    config.languages[language_index].name.clone(),
+                );
+                switch_backend(&mut backend, &cache, &mut config, &mut status, |config| {
+                    config.language = previous
+                });
+            }
+            ui.same_line();
+            // printed text models make nothing of handwriting, it needs a model of its own
+            if ui.checkbox(tr("Handwriting"), &mut config.handwriting) {
+                switch_backend(&mut backend, &cache, &mut config, &mut status, |config| {
+                    config.handwriting = !config.handwriting
+                });
+            }
+        } else if ui
+            .input_text(tr("Tesseract language"), &mut config.tesseract_language)
+            .enter_returns_true(true)
+            .build()
+        {
+            switch_backend(&mut backend, &cache, &mut config, &mut status, |_| ());
+        }
+
+        ui.same_line();
+        // columns read top to bottom and right to left, as in manga
+        if ui.checkbox(tr("Vertical text"), &mut config.preprocessing.vertical) {
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+
+        /* options changed once in a while live in their own window */
+        if settings_open {
+            ui.window(format!("{}###Settings", tr("Settings")))
+                .size([460.0, 520.0], Condition::FirstUseEver)
+                .opened(&mut settings_open)
+                .build(|| {
+                    if ui.button(tr("Setup wizard...")) {
+                        let error = (backend.name() == "none")
+                            .then(|| "The OCR models are not loaded".to_string());
+                        onboarding = Some(Onboarding::new(&config, error));
+                    }
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(tr(
+                            "Walk through the language, models, hotkey and a test read again",
+                        ));
+                    }
+                    let tunable = matches!(backend.name(), "ocrs" | "tesseract");
+                    if tunable && ui.collapsing_header(format!("{}###OCR engine", tr("OCR engine")), TreeNodeFlags::empty()) {
+                        let previous = config.engine.clone();
+                        let engine = &mut config.engine;
+                        let ocrs = backend.name() == "ocrs";
+                        let mut changed = false;
+                        let mut beam_search = engine.beam_width > 0;
+                        if ocrs && ui.checkbox(tr("Beam search decoding"), &mut beam_search) {
+                            engine.beam_width = if beam_search { 5 } else { 0 };
+                            changed = true;
+                        }
+                        if ocrs && beam_search {
+                            let mut width = engine.beam_width as i32;
+                            if ui.input_int(tr("Beam width"), &mut width).build() {
+                                engine.beam_width = width.clamp(1, 100) as u32;
+                                changed = true;
+                            }
+                        }
+                        // custom when the allowed characters are none of the sets
+                        let sets = ocr::CHARACTER_SETS;
+                        let custom = sets.len() + 1;
+                        let mut set_index = match engine.allowed_chars.as_str() {
+                            "" => 0,
+                            chars => sets
+                                .iter()
+                                .position(|(_, set)| *set == chars)
+                                .map_or(custom, |index| index + 1),
+                        };
+                        let set_labels: Vec<&str> = [tr("All characters")]
+                            .into_iter()
+                            .chain(sets.iter().map(|(name, _)| tr(name)))
+                            .chain([tr("Custom")])
+                            .collect();
+                        if ui.combo_simple_string(tr("Character set"), &mut set_index, &set_labels)
+                            && set_index != custom
+                        {
+                            engine.allowed_chars = match set_index {
+                                0 => String::new(),
+                                index => sets[index - 1].1.to_string(),
+                            };
+                            changed = true;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Only recognize these characters, saved with the profile",
+                            ));
+                        }
+                        // rebuilding the engine on every keystroke would stall typing
+                        changed |= ui
+                            .input_text(tr("Allowed characters"), &mut engine.allowed_chars)
+                            .hint(tr("All"))
+                            .enter_returns_true(true)
+                            .build();
+                        if ocrs {
+                            changed |= ui.checkbox(tr("Debug output"), &mut engine.debug);
+                        }
+                        if changed {
+                            switch_backend(
+                                &mut backend,
+                                &cache,
+                                &mut config,
+                                &mut status,
+                                |config| config.engine = previous,
+                            );
+                        }
+
+                        /* other model files are tried right away, the ones in use stay on failure */
+                        ui.separator();
+                        let previous_detection = config.detection_model.clone();
+                        let previous_override = config.detection_override.clone();
+                        let picked = model_row(ui, tr("Detection model"), &config.detection_model_path())
+                            .then(pick_model)
+                            .flatten();
+                        if let Some(path) = picked {
+                            config.detection_model = path;
+                            config.detection_override = None;
+                            switch_backend(
+                                &mut backend,
+                                &cache,
+                                &mut config,
+                                &mut status,
+                                |config| {
+                                    config.detection_model = previous_detection;
+                                    config.detection_override = previous_override;
+                                },
+                            );
+                        }
+
+                        if let Some((model, _)) = config.recognition_model_path() {
+                            let previous_model = config
+                                .recognition_language()
+                                .map(|language| language.model.clone())
+                                .unwrap_or_default();
+                            let previous_override = config.recognition_override.clone();
+                            let picked = model_row(ui, tr("Recognition model"), &model)
+                                .then(pick_model)
+                                .flatten();
+                            if let Some(path) = picked {
+                                if let Some(language) = config.recognition_language_mut() {
+                                    language.model = path;
+                                }
+                                config.recognition_override = None;
+                                switch_backend(
+                                    &mut backend,
+                                    &cache,
+                                    &mut config,
+                                    &mut status,
+                                    |config| {
+                                        if let Some(language) = config.recognition_language_mut() {
+                                            language.model = previous_model;
+                                        }
+                                        config.recognition_override = previous_override;
+                                    },
+                                );
+                            }
+                        }
+
+                        // the files may have been retrained in place, so the cached results are stale
+                        if ui.button(tr("Reload models")) {
+                            switch_backend(&mut backend, &cache, &mut config, &mut status, |_| ());
+                            if let Some(cache) = &cache {
+                                if let Ok(mut cache) = cache.lock() {
+                                    cache.clear();
+                                }
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Appearance", tr("Appearance")), TreeNodeFlags::empty()) {
+                        let mut theme_index = Theme::ALL
+                            .iter()
+                            .position(|theme| *theme == config.theme)
+                            .unwrap_or(0);
+                        let theme_labels = Theme::ALL.map(Theme::label);
+                        let mut changed =
+                            ui.combo_simple_string(tr("Theme"), &mut theme_index, &theme_labels);
+                        config.theme = Theme::ALL[theme_index];
+                        let mut language_index = UiLanguage::ALL
+                            .iter()
+                            .position(|language| *language == config.ui_language)
+                            .unwrap_or(0);
+                        let language_labels = UiLanguage::ALL.map(UiLanguage::label);
+                        if ui.combo_simple_string(
+                            tr("Interface language"),
+                            &mut language_index,
+                            &language_labels,
+                        ) {
+                            config.ui_language = UiLanguage::ALL[language_index];
+                            locale::set(config.ui_language);
+                            changed = true;
+                        }
+                        // rebuilding the font atlas while a slider is dragged would stutter
+                        ui.slider_config(tr("UI scale"), 0.5, 3.0)
+                            .display_format("%.2fx")
+                            .build(&mut config.ui_scale);
+                        changed |= ui.is_item_deactivated_after_edit();
+                        ui.slider_config(tr("Font size"), 10.0, 32.0)
+                            .display_format("%.0f px")
+                            .build(&mut config.font_size);
+                        changed |= ui.is_item_deactivated_after_edit();
+                        // reading the file on every keystroke would stall typing
+                        let mut font = config.font.display().to_string();
+                        if ui
+                            .input_text(tr("Font file"), &mut font)
+                            .hint(tr("Bundled"))
+                            .enter_returns_true(true)
+                            .build()
+                        {
+                            config.font = font.into();
+                            changed = true;
+                        }
+                        ui.same_line();
+                        if ui.button(tr("Browse...")) {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("Fonts", &["ttf", "otf", "ttc"])
+                                .pick_file()
+                            {
+                                config.font = path;
+                                changed = true;
+                            }
+                        }
+                        if changed {
+                            appearance_changed = true;
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Accessibility", tr("Accessibility")), TreeNodeFlags::empty()) {
+                        if ui.checkbox(
+                            tr("Give new results to screen readers"),
+                            &mut config.screen_reader,
+                        ) {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                        if config.screen_reader != screen_reader.is_some() {
+                            ui.text_disabled(tr("Takes effect after a restart"));
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Keyboard shortcuts", tr("Keyboard shortcuts")), TreeNodeFlags::empty()) {
+                        if ui.button(tr("Show all shortcuts")) {
+                            cheat_sheet_open = true;
+                        }
+                        if config.keymap.edit(ui) {
+                            bindings = config.keymap.bindings();
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Session", tr("Session")), TreeNodeFlags::empty()) {
+                        let mut changed =
+                            ui.checkbox(tr("Restore the last session"), &mut config.restore_session);
+                        changed |= ui.checkbox(
+                            tr("Hand later starts to the running window"),
+                            &mut config.single_instance,
+                        );
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr("Takes effect after a restart"));
+                        }
+                        let mut interval = config.autosave_interval as i32;
+                        if ui
+                            .input_int(tr("Autosave every (seconds, 0 for never)"), &mut interval)
+                            .build()
+                        {
+                            config.autosave_interval = interval.max(0) as u32;
+                            changed = true;
+                        }
+                        #[cfg(feature = "archive")]
+                        if ui.checkbox(
+                            tr("Keep every transcription in a searchable archive"),
+                            &mut config.archive,
+                        ) {
+                            changed = true;
+                            history.set_archive(None);
+                            if config.archive {
+                                match open_archive() {
+                                    Ok(archive) => history.set_archive(Some(archive)),
+                                    Err(err) => status.error("Error opening the archive", err),
+                                }
+                            }
+                        }
+                        changed |= ui.checkbox(
+                            tr("Write every recognized text to a daily journal"),
+                            &mut config.journal,
+                        );
+                        if config.journal {
+                            let mut dir = config.journal_dir.display().to_string();
+                            if ui
+                                .input_text(tr("Journal folder"), &mut dir)
+                                .hint(tr("Documents"))
+                                .enter_returns_true(true)
+                                .build()
+                            {
+                                config.journal_dir = dir.into();
+                                changed = true;
+                            }
+                            ui.same_line();
+                            if ui.button(format!("{}##journal", tr("Browse..."))) {
+                                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                                    config.journal_dir = dir;
+                                    changed = true;
+                                }
+                            }
+                        }
+                        if changed {
+                            history.set_journal(config.journal_dir());
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Clipboard", tr("Clipboard")), TreeNodeFlags::empty()) {
+                        let mut changed =
+                            ui.checkbox(tr("Convert HTML to Markdown"), &mut config.html_to_markdown);
+                        // X11 and Wayland also keep what was last selected
+                        #[cfg(target_os = "linux")]
+                        {
+                            let labels = Selection::ALL.map(|selection| tr(selection.label()));
+                            let index_of = |selection| {
+                                Selection::ALL.iter().position(|kind| *kind == selection).unwrap_or(0)
+                            };
+                            let mut source = index_of(config.clipboard_source);
+                            if ui.combo_simple_string(tr("Read from"), &mut source, &labels) {
+                                config.clipboard_source = Selection::ALL[source];
+                                clipboard.source = config.clipboard_source;
+                                changed = true;
+                            }
+                            let mut target = index_of(config.clipboard_target);
+                            if ui.combo_simple_string(tr("Copy to"), &mut target, &labels) {
+                                config.clipboard_target = Selection::ALL[target];
+                                clipboard.target = config.clipboard_target;
+                                changed = true;
+                            }
+                        }
+                        let mut megabytes = config.clipboard_max_megabytes as i32;
+                        if ui
+                            .input_int(tr("Largest image to read (MB)"), &mut megabytes)
+                            .build()
+                        {
+                            config.clipboard_max_megabytes = megabytes.max(1) as u32;
+                            changed = true;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Memory a pasted image may take once read, larger ones are refused",
+                            ));
+                        }
+                        let mut megapixels = config.clipboard_max_megapixels as i32;
+                        if ui
+                            .input_int(tr("Scale images down to (megapixels, 0 for never)"), &mut megapixels)
+                            .build()
+                        {
+                            config.clipboard_max_megapixels = megapixels.max(0) as u32;
+                            changed = true;
+                        }
+                        changed |= ui.checkbox(
+                            tr("Merge overlapping captures when watching"),
+                            &mut config.merge_overlaps,
+                        );
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr("Only when new results are appended"));
+                        }
+                        changed |= ui.checkbox(
+                            tr("Skip repeated images when watching"),
+                            &mut config.skip_repeated_images,
+                        );
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Images the same as the last one, pixel for pixel, are not read again",
+                            ));
+                        }
+                        ui.slider_config(tr("Clipboard check interval (s)"), 0.2, 10.0)
+                            .display_format("%.1f")
+                            .build(&mut config.clipboard_poll_interval);
+                        if ui.is_item_deactivated_after_edit() {
+                            changed = true;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "How often watch mode reads the clipboard where the system does not tell of changes",
+                            ));
+                        }
+                        let mut handling_index = SecretHandling::ALL
+                            .iter()
+                            .position(|handling| *handling == config.secret_handling)
+                            .unwrap_or(0);
+                        let handling_labels = SecretHandling::ALL.map(SecretHandling::label);
+                        if ui.combo_simple_string(
+                            tr("Secrets in results"),
+                            &mut handling_index,
+                            &handling_labels,
+                        ) {
+                            config.secret_handling = SecretHandling::ALL[handling_index];
+                            secret_guard.handling = config.secret_handling;
+                            changed = true;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "API keys, access tokens, card numbers and email addresses \
+                                 found in results about to be copied",
+                            ));
+                        }
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Preprocessing", tr("Preprocessing")), TreeNodeFlags::empty()) {
+                        let mut changed = preprocessing::draw(ui, &mut config.preprocessing);
+                        let mut dpi = config.svg_dpi as i32;
+                        if ui.input_int(tr("SVG resolution (DPI)"), &mut dpi).build() {
+                            config.svg_dpi = dpi.clamp(24, 1200) as u32;
+                            changed = true;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "SVG images are drawn at this resolution before they are read, 96 is their size on screen",
+                            ));
+                        }
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Batch", tr("Batch")), TreeNodeFlags::empty()) {
+                        let mut changed =
+                            ui.checkbox(tr("Include subfolders"), &mut config.batch_recursive);
+                        changed |= ui.checkbox(tr("Combine into one file"), &mut config.batch_combined);
+                        changed |= ui.checkbox(tr("Also save searchable PDFs"), &mut config.batch_pdf);
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Next to each file, a PDF of its pages with the recognized text over them",
+                            ));
+                        }
+                        // shared with watch mode, 0 runs one per core
+                        let mut threads = config.ocr_threads as i32;
+                        if ui
+                            .input_int(tr("OCR threads (0 for one per core)"), &mut threads)
+                            .build()
+                        {
+                            config.ocr_threads = threads.max(0) as usize;
+                            changed = true;
+                        }
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Performance", tr("Performance")), TreeNodeFlags::empty()) {
+                        // ocrs runs on rten, which has no GPU support to offer
+                        ui.text_disabled(tr("Inference runs on the CPU"));
+                        let mut threads = config.inference_threads as i32;
+                        if ui
+                            .input_int(tr("Inference threads (0 for one per core)"), &mut threads)
+                            .build()
+                        {
+                            config.inference_threads = threads.max(0) as usize;
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr("Applies after a restart"));
+                        }
+                        if ui.checkbox(tr("Stream large images"), &mut config.stream_results) {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Show the lines of big pages in the preview and the text as they are read",
+                            ));
+                        }
+                        if ui.checkbox(tr("Show timings"), &mut config.perf_hud) {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Show how long the last capture took to read, step by step, and the averages of the last 20",
+                            ));
+                        }
+
+                        if let Some(job) = &benchmark_job {
+                            match job.try_recv() {
+                                Ok(result) => {
+                                    benchmark_result =
+                                        result.unwrap_or_else(|err| format!("Error: {}", err));
+                                    benchmark_job = None;
+                                }
+                                Err(TryRecvError::Empty) => (),
+                                Err(TryRecvError::Disconnected) => benchmark_job = None,
+                            }
+                        }
+                        let _disabled = ui.begin_disabled(benchmark_job.is_some());
+                        if ui.button(tr("Benchmark clipboard image")) {
+                            match clipboard_image(&clipboard, &config) {
+                                Ok(image) => {
+                                    benchmark_result = "Running...".into();
+                                    benchmark_job = Some(spawn_benchmark(&config, image));
+                                }
+                                Err(err) => benchmark_result = format!("Error: {}", err),
+                            }
+                        }
+                        ui.same_line();
+                        ui.text(&benchmark_result);
+                    }
+
+                    if let Some(cache) = &cache {
+                        if ui.collapsing_header(format!("{}###Cache", tr("Cache")), TreeNodeFlags::empty()) {
+                            if ui
+                                .checkbox(tr("Keep results across restarts"), &mut config.persist_cache)
+                            {
+                                if let Err(err) = config.save() {
+                                    error!("Error saving config: {}", err);
+                                }
+                            }
+                            if ui.button(tr("Clear cache")) {
+                                if let Ok(mut cache) = cache.lock() {
+                                    cache.clear();
+                                }
+                            }
+                        }
+                    }
+
+                    #[cfg(feature = "whisper")]
+                    if ui.collapsing_header(format!("{}###Audio", tr("Audio")), TreeNodeFlags::empty()) {
+                        if ui.checkbox(tr("Label speakers"), &mut config.speaker_labels) {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Needs a tinydiarize whisper model such as ggml-small.en-tdrz.bin",
+                            ));
+                        }
+                        if config.speaker_labels && !audio::diarizes(&config.whisper_model) {
+                            ui.text_disabled(tr(
+                                "The whisper model does not mark where the speaker changes",
+                            ));
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Video", tr("Video")), TreeNodeFlags::empty()) {
+                        let mut changed = false;
+                        if ui
+                            .input_float(tr("Frame interval (seconds)"), &mut config.video_interval)
+                            .step(0.5)
+                            .build()
+                        {
+                            config.video_interval = config.video_interval.max(0.1);
+                            changed = true;
+                        }
+                        // of saved subtitles, for audio files as much as videos
+                        let mut length = config.subtitle_line_length as i32;
+                        if ui
+                            .input_int(tr("Subtitle line length (0 for no limit)"), &mut length)
+                            .build()
+                        {
+                            config.subtitle_line_length = length.max(0) as usize;
+                            changed = true;
+                        }
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    live_overlay::draw_settings(ui, &mut config);
+
+                    if ui.collapsing_header(format!("{}###Profiles", tr("Profiles")), TreeNodeFlags::empty()) {
+                        let mut changed = false;
+                        let mut removed = None;
+                        for (index, profile) in config.profiles.iter().enumerate() {
+                            ui.text(&profile.name);
+                            ui.same_line();
+                            if ui.small_button(format!("{}##profile{}", tr("Delete"), index)) {
+                                removed = Some(index);
+                            }
+                        }
+                        if let Some(index) = removed {
+                            let profile = config.profiles.remove(index);
+                            if profile.name == config.profile {
+                                config.profile.clear();
+                            }
+                            changed = true;
+                        }
+                        ui.input_text(format!("{}##profile", tr("Name")), &mut profile_name).build();
+                        ui.same_line();
+                        let name = profile_name.trim();
+                        let _disabled = ui.begin_disabled(name.is_empty());
+                        // a profile of the same name is overwritten
+                        if ui.button(tr("Save current settings")) {
+                            let profile = Profile::capture(name, &config);
+                            match config.profiles.iter_mut().find(|p| p.name == name) {
+                                Some(existing) => *existing = profile,
+                                None => config.profiles.push(profile),
+                            }
+                            config.profile = name.to_string();
+                            profile_name.clear();
+                            changed = true;
+                        }
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Projects", tr("Projects")), TreeNodeFlags::empty())
+                        && project_settings.draw(ui, &mut config)
+                    {
+                        history.set_journal(config.journal_dir());
+                        if let Err(err) = config.save() {
+                            error!("Error saving config: {}", err);
+                        }
+                    }
+
+                    if !CLOUD_BACKENDS.is_empty()
+                        && ui.collapsing_header(format!("{}###Cloud OCR", tr("Cloud OCR")), TreeNodeFlags::empty())
+                    {
+                        // whether a cloud backend is in the chain, before what is changed here
+                        let chained = |config: &Config| {
+                            std::iter::once(config.backend.as_str())
+                                .chain(config.chain())
+                                .any(|name| CLOUD_BACKENDS.contains(&name))
+                        };
+                        let was_chained = chained(&config);
+                        let mut names = vec!["none"];
+                        names.extend_from_slice(CLOUD_BACKENDS);
+                        let mut cloud_index = names
+                            .iter()
+                            .position(|name| *name == config.cloud_backend)
+                            .unwrap_or(0);
+                        let mut changed =
+                            ui.combo_simple_string(tr("Service"), &mut cloud_index, &names);
+                        config.cloud_backend = match cloud_index {
+                            0 => String::new(),
+                            index => names[index].to_string(),
+                        };
+                        // the backend is set up again once a field is left, not on every key
+                        let field = |label: &str, value: &mut String, hint: &str, password: bool| {
+                            ui.input_text(label, value)
+                                .hint(hint)
+                                .password(password)
+                                .build();
+                            ui.is_item_deactivated_after_edit()
+                        };
+                        let credentials = &mut config.cloud_credentials;
+                        match config.cloud_backend.as_str() {
+                            "google-vision" => {
+                                changed |=
+                                    field("API key##google", &mut credentials.google_api_key, "", true);
+                            }
+                            "azure" => {
+                                changed |= field(
+                                    "Endpoint##azure",
+                                    &mut credentials.azure_endpoint,
+                                    "https://name.cognitiveservices.azure.com",
+                                    false,
+                                );
+                                changed |= field("Key##azure", &mut credentials.azure_key, "", true);
+                            }
+                            "textract" => {
+                                changed |=
+                                    field("Region##aws", &mut credentials.aws_region, "us-east-1", false);
+                                changed |= field(
+                                    "Access key ID##aws",
+                                    &mut credentials.aws_access_key_id,
+                                    "",
+                                    false,
+                                );
+                                changed |= field(
+                                    "Secret access key##aws",
+                                    &mut credentials.aws_secret_access_key,
+                                    "",
+                                    true,
+                                );
+                            }
+                            _ => (),
+                        }
+                        if !config.cloud_backend.is_empty() {
+                            if let Err(err) = backend::cloud_backend(
+                                &config.cloud_backend,
+                                &config.cloud_credentials,
+                            ) {
+                                ui.text_colored([0.9, 0.3, 0.3, 1.0], err.to_string());
+                            }
+                            changed |= ui.checkbox(
+                                tr("Also when local OCR fails or finds nothing"),
+                                &mut config.cloud_fallback,
+                            );
+                            ui.text_disabled(tr(
+                                "Images read in the cloud are uploaded to the service",
+                            ));
+                        }
+                        // only a chain with a cloud backend in it is set up again, the
+                        // local models stay loaded
+                        if changed {
+                            if let Err(err) = use_cloud(&config, config.cloud.is_set()) {
+                                status.error("Error setting up cloud OCR", err);
+                            }
+                            match was_chained || chained(&config) {
+                                true => switch_backend(
+                                    &mut backend,
+                                    &cache,
+                                    &mut config,
+                                    &mut status,
+                                    |_| {},
+                                ),
+                                false => {
+                                    if let Err(err) = config.save() {
+                                        error!("Error saving config: {}", err);
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Fallback chain", tr("Fallback chain")), TreeNodeFlags::empty()) {
+                        ui.text_disabled(tr(
+                            "Tried in turn when the backend in use finds nothing or is unsure",
+                        ));
+                        let mut changed = false;
+                        let mut removed = None;
+                        let mut moved = None;
+                        let count = config.backend_chain.len();
+                        for (index, name) in config.backend_chain.iter().enumerate() {
+                            let _id = ui.push_id_usize(index);
+                            ui.text(format!("{}. {}", index + 1, name));
+                            ui.same_line();
+                            if index > 0 && ui.small_button(tr("Up")) {
+                                moved = Some((index, index - 1));
+                            }
+                            ui.same_line();
+                            if index + 1 < count && ui.small_button(tr("Down")) {
+                                moved = Some((index, index + 1));
+                            }
+                            ui.same_line();
+                            if ui.small_button("x") {
+                                removed = Some(index);
+                            }
+                        }
+                        if let Some((from, to)) = moved {
+                            config.backend_chain.swap(from, to);
+                            changed = true;
+                        }
+                        if let Some(index) = removed {
+                            config.backend_chain.remove(index);
+                            changed = true;
+                        }
+                        let mut added = None;
+                        if let Some(_combo) = ui.begin_combo("##chain", tr("Add backend")) {
+                            for name in BACKENDS.iter().chain(CLOUD_BACKENDS) {
+                                if *name != config.backend
+                                    && !config.backend_chain.iter().any(|added| added == name)
+                                    && ui.selectable(name)
+                                {
+                                    added = Some(name.to_string());
+                                }
+                            }
+                        }
+                        if let Some(name) = added {
+                            config.backend_chain.push(name);
+                            changed = true;
+                        }
+                        {
+                            let _voting = ui.begin_disabled(config.ensemble);
+                            ui.slider_config(tr("Minimum confidence"), 0.0, 1.0)
+                                .display_format("%.2f")
+                                .build(&mut config.chain_min_confidence);
+                            changed |= ui.is_item_deactivated_after_edit();
+                        }
+                        changed |= ui.checkbox(tr("Ensemble"), &mut config.ensemble);
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Every backend reads every image and the words are voted on, slower",
+                            ));
+                        }
+                        if config.ensemble {
+                            ui.text_disabled(tr("Also read the image"));
+                            for name in ensemble::VARIANTS {
+                                let mut read = config.ensemble_variants.iter().any(|v| v == name);
+                                if ui.checkbox(tr(name), &mut read) {
+                                    config.ensemble_variants.retain(|v| v != name);
+                                    if read {
+                                        config.ensemble_variants.push(name.to_string());
+                                    }
+                                    changed = true;
+                                }
+                            }
+                            // the cloud backends are paid for every image they read
+                            let mut names = vec![config.backend.as_str()];
+                            for name in config.chain() {
+                                if !names.contains(&name) {
+                                    names.push(name);
+                                }
+                            }
+                            let paid = names
+                                .iter()
+                                .filter(|name| CLOUD_BACKENDS.contains(name))
+                                .count();
+                            let variants = config
+                                .ensemble_variants
+                                .iter()
+                                .filter(|name| ensemble::variant(name).is_some())
+                                .count();
+                            let reads = ensemble::reads(names.len() - paid, paid, variants);
+                            ui.text_disabled(format!("{} {}", tr("Reads per image:"), reads));
+                            if paid > 0 {
+                                ui.text_colored(
+                                    [0.9, 0.6, 0.2, 1.0],
+                                    format!(
+                                        "{} {}",
+                                        paid,
+                                        tr("of them by paid cloud backends, which skip the variants")
+                                    ),
+                                );
+                            }
+                        }
+                        if changed {
+                            switch_backend(&mut backend, &cache, &mut config, &mut status, |_| {});
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Send to", tr("Send to")), TreeNodeFlags::empty())
+                        && delivery::edit_targets(ui, &mut config.send_targets)
+                    {
+                        if let Err(err) = config.save() {
+                            error!("Error saving config: {}", err);
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Plugins", tr("Plugins")), TreeNodeFlags::empty()) {
+                        ui.text_disabled(tr(
+                            "Checked plugins get every new result as JSON, saved with the profile",
+                        ));
+                        if plugins::edit(ui, &mut config.plugins, &mut config.enabled_plugins) {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Webhook", tr("Webhook")), TreeNodeFlags::empty()) {
+                        ui.input_text(format!("{}##webhook", tr("URL")), &mut config.webhook_url)
+                            .hint(tr("Every new result is posted here as JSON"))
+                            .build();
+                        let mut changed = ui.is_item_deactivated_after_edit();
+                        ui.input_text(
+                            format!("{}##webhook", tr("Authorization")),
+                            &mut config.webhook_authorization,
+                        )
+                        .hint("Bearer <token>")
+                        .password(true)
+                        .build();
+                        changed |= ui.is_item_deactivated_after_edit();
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Output", tr("Output")), TreeNodeFlags::empty()) {
+                        let mut mode_index = OutputMode::ALL
+                            .iter()
+                            .position(|mode| *mode == config.output_mode)
+                            .unwrap_or(0);
+                        let mode_labels = OutputMode::ALL.map(OutputMode::label);
+                        let mut changed =
+                            ui.combo_simple_string(tr("New text"), &mut mode_index, &mode_labels);
+                        config.output_mode = OutputMode::ALL[mode_index];
+                        let mut layout_index = TextLayout::ALL
+                            .iter()
+                            .position(|layout| *layout == config.text_layout)
+                            .unwrap_or(0);
+                        let layout_labels = TextLayout::ALL.map(TextLayout::label);
+                        changed |= ui.combo_simple_string(
+                            tr("Recognized text"),
+                            &mut layout_index,
+                            &layout_labels,
+                        );
+                        config.text_layout = TextLayout::ALL[layout_index];
+                        if config.text_layout == TextLayout::Code {
+                            let mut language_index = CodeLanguage::ALL
+                                .iter()
+                                .position(|language| *language == config.code_language)
+                                .unwrap_or(0);
+                            let language_labels = CodeLanguage::ALL.map(CodeLanguage::label);
+                            changed |= ui.combo_simple_string(
+                                tr("Check code as"),
+                                &mut language_index,
+                                &language_labels,
+                            );
+                            config.code_language = CodeLanguage::ALL[language_index];
+                            if ui.is_item_hovered() {
+                                ui.tooltip_text(tr(
+                                    "Shows next to the buttons whether the text is valid code, to spot what was misread",
+                                ));
+                            }
+                        }
+                        if config.output_mode == OutputMode::Append {
+                            // edited with escaped line breaks, a single line input cannot hold them
+                            let mut separator = config.append_separator.replace('\n', "\\n");
+                            if ui.input_text(tr("Separator"), &mut separator).build() {
+                                config.append_separator = separator.replace("\\n", "\n");
+                                changed = true;
+                            }
+                        }
+                        ui.input_text_multiline(
+                            tr("Output template"),
+                            &mut config.output_template,
+                            [0.0, ui.text_line_height() * 4.0],
+                        )
+                        .build();
+                        changed |= ui.is_item_deactivated_after_edit();
+                        if ui.is_item_hovered() {
+                            let placeholders: Vec<String> = template::PLACEHOLDERS
+                                .iter()
+                                .map(|name| format!("{{{}}}", name))
+                                .collect();
+                            ui.tooltip_text(format!(
+                                "{} {}",
+                                tr("Wraps copied text and text saved as plain text, with"),
+                                placeholders.join(", ")
+                            ));
+                        }
+                        changed |= ui.checkbox(
+                            tr("Notify of results read while the window is hidden"),
+                            &mut config.notify_in_background,
+                        );
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Line filter", tr("Line filter")), TreeNodeFlags::empty()) {
+                        let filter = &mut config.line_filter;
+                        let mut changed = false;
+                        let mut min_chars = filter.min_chars as i32;
+                        if ui
+                            .input_int(tr("Minimum characters per line"), &mut min_chars)
+                            .build()
+                        {
+                            filter.min_chars = min_chars.max(1) as usize;
+                            changed = true;
+                        }
+                        ui.slider_config(tr("Minimum line confidence"), 0.0, 1.0)
+                            .display_format("%.2f")
+                            .build(&mut filter.min_confidence);
+                        changed |= ui.is_item_deactivated_after_edit();
+                        let mut patterns = filter.junk_patterns.join("\n");
+                        ui.input_text_multiline(
+                            tr("Junk patterns"),
+                            &mut patterns,
+                            [0.0, ui.text_line_height() * 4.0],
+                        )
+                        .build();
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Regular expressions, one per line, lines they match are dropped",
+                            ));
+                        }
+                        if ui.is_item_deactivated_after_edit() {
+                            filter.junk_patterns = patterns
+                                .lines()
+                                .filter(|pattern| !pattern.trim().is_empty())
+                                .map(str::to_string)
+                                .collect();
+                            changed = true;
+                        }
+                        changed |= ui.checkbox(tr("Show filtered lines"), &mut filter.show_filtered);
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr("Keep them in the text, marked with why they would go"));
+                        }
+                        if ui.button(tr("Reset filter")) {
+                            *filter = LineFilter::default();
+                            changed = true;
+                        }
+                        if changed {
+                            switch_backend(&mut backend, &cache, &mut config, &mut status, |_| {});
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Post-processing", tr("Post-processing")), TreeNodeFlags::empty()) {
+                        let mut changed = ui.checkbox(tr("Check codes"), &mut config.check_codes);
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "Check the checksums of IBANs, card numbers, ISBNs and passport zones and fix lookalike misreads",
+                            ));
+                        }
+                        changed |= transforms::edit_transforms(ui, &mut config.transforms);
+                        let mut script = config.script.display().to_string();
+                        if ui
+                            .input_text(tr("Script"), &mut script)
+                            .hint(tr("None"))
+                            .enter_returns_true(true)
+                            .build()
+                        {
+                            config.script = script.trim().into();
+                            changed = true;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(tr(
+                                "A Rhai script that gets the recognized lines with their boxes and confidences and returns the text",
+                            ));
+                        }
+                        ui.same_line();
+                        if ui.button(format!("{}##script", tr("Browse..."))) {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("Rhai", &["rhai"])
+                                .pick_file()
+                            {
+                                config.script = path;
+                                changed = true;
+                            }
+                        }
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    #[cfg(feature = "tts")]
+                    if ui.collapsing_header(format!("{}###Read aloud", tr("Read aloud")), TreeNodeFlags::empty())
+                        && ui
+                            .slider_config("Speech rate", 0.5, 2.0)
+                            .display_format("%.2fx")
+                            .build(&mut config.speech_rate)
+                    {
+                        // applies right away to the next sentence
+                        if let Some(session) = &mut reader {
+                            session.set_rate(config.speech_rate);
+                        }
+                        if let Err(err) = config.save() {
+                            error!("Error saving config: {}", err);
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###Formulas", tr("Formulas")), TreeNodeFlags::empty()) {
+                        let mut changed =
+                            ui.checkbox(tr("Read formulas as LaTeX"), &mut config.formulas);
+                        changed |= ui
+                            .input_text(tr("Vision model"), &mut config.formula_model)
+                            .build();
+                        ui.text_disabled(tr("Sent to the API URL of AI cleanup"));
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+
+                    if ui.collapsing_header(format!("{}###AI cleanup", tr("AI cleanup")), TreeNodeFlags::empty()) {
+                        let mut changed = ui.input_text(tr("API URL"), &mut config.llm_url).build();
+                        changed |= ui.input_text(tr("Model"), &mut config.llm_model).build();
+                        changed |= ui
+                            .input_text(tr("API key"), &mut config.llm_api_key)
+                            .password(true)
+                            .build();
+                        changed |= ui
+                            .input_text_multiline(tr("Prompt"), &mut config.llm_prompt, [0.0, 80.0])
+                            .build();
+                        if changed {
+                            if let Err(err) = config.save() {
+                                error!("Error saving config: {}", err);
+                            }
+                        }
+                    }
+                });
+        }
+
+        /* long jobs can be paused and cancelled, a cancelled one is dropped right away */
+        {
+            let mut rows = Vec::new();
+            #[cfg(feature = "whisper")]
+            rows.extend(audio_job.as_ref().map(|(_, job)| job.row()));
+            rows.extend(video_job.as_ref().map(|(_, job)| job.row()));
+            rows.extend(formula_job.as_ref().map(|(.., job)| job.row()));
+            rows.extend(batch.as_ref().and_then(BatchJob::row));
+            draw_jobs(ui, &rows);
+        }
+        #[cfg(feature = "whisper")]
+        if audio_job
+            .as_ref()
+            .is_some_and(|(_, job)| job.control.is_cancelled())
+        {
+            audio_job = None;
+        }
+        if video_job
+            .as_ref()
+            .is_some_and(|(_, job)| job.control.is_cancelled())
+        {
+            video_job = None;
+        }
+        if formula_job
+            .as_ref()
+            .is_some_and(|(.., job)| job.control.is_cancelled())
+        {
+            formula_job = None;
+        }
+
+        /* each tab is a document of its own, the active one is in the editor */
+        let mut editor = Editor {
+            text: &mut text,
+            cursor: &mut cursor,
+            transcription: &mut transcription,
+            pages: &mut pages,
+            undo: &mut undo,
+            panels: &mut panels,
+        };
+        match pressed {
+            Some(Action::NewTab) => tabs.add(&mut editor),
+            Some(Action::CloseTab) => tabs.close_active(&mut editor),
+            Some(Action::NextTab) => tabs.cycle(1, &mut editor),
+            Some(Action::PreviousTab) => tabs.cycle(-1, &mut editor),
+            _ => (),
+        }
+        tabs.draw(ui, editor);
+
+        if ui.collapsing_header(
+            format!("{}###Find and replace", tr("Find and replace")),
+            TreeNodeFlags::empty(),
+        ) {
+            panels.find.draw(ui, &mut text);
+        }
+
+        if ui.checkbox(tr("Spell check"), &mut config.spell_check) {
+            spellcheck = if config.spell_check {
+                load_spellcheck(&mut config, &mut status)
+            } else {
+                None
+            };
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+
+        ui.same_line();
+        if ui.checkbox(tr("Proofread"), &mut config.proofread) {
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr(
+                "Click the highlighted words the engine was unsure about for other readings",
+            ));
+        }
+        ui.same_line();
+        if ui.checkbox(tr("Side by side"), &mut config.side_by_side) {
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr(
+                "The image next to the text line by line, click a line on the image to edit it",
+            ));
+        }
+        ui.same_line();
+        if ui.button(tr("View")) {
+            ui.open_popup("view");
+        }
+        ui.popup("view", || {
+            let mut changed = ui.checkbox(tr("Word wrap"), &mut config.word_wrap);
+            changed |= ui.checkbox(tr("Line numbers"), &mut config.line_numbers);
+            if ui.checkbox(tr("Monospace font"), &mut config.monospace) {
+                // the font is only loaded while it is in use
+                appearance_changed = true;
+                changed = true;
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("Lines up the columns of code and terminal screenshots"));
+            }
+            if changed {
+                if let Err(err) = config.save() {
+                    error!("Error saving config: {}", err);
+                }
+            }
+        });
+
+        /* whatever changed the text since the last frame becomes a step to undo */
+        undo.track(&text, false);
+        ui.same_line();
+        {
+            let _disabled = ui.begin_disabled(!undo.can_undo());
+            if ui.button(tr("Undo")) {
+                undo.undo(&mut text);
+            }
+        }
+        ui.same_line();
+        {
+            let _disabled = ui.begin_disabled(!undo.can_redo());
+            if ui.button(tr("Redo")) {
+                undo.redo(&mut text);
+            }
+        }
+        // other text fields undo their own typing, the editor leaves it to the app
+        let io = ui.io();
+        let shortcuts = io.key_ctrl && (!io.want_text_input || text_area.has_focus());
+        if shortcuts && ui.is_key_pressed(Key::Z) {
+            if io.key_shift {
+                undo.redo(&mut text);
+            } else {
+                undo.undo(&mut text);
+            }
+        }
+        if shortcuts && ui.is_key_pressed(Key::Y) {
+            undo.redo(&mut text);
+        }
+
+        /* the language of the last transcription, detected again only once it changed */
+        if let Some(result) = &transcription {
+            // the lines summed up, cheaper every frame than joining them into the text
+            let mut hasher = DefaultHasher::new();
+            for line in &result.lines {
+                line.text.hash(&mut hasher);
+            }
+            let recognized = hasher.finish();
+            if recognized_language
+                .as_ref()
+                .is_none_or(|(detected, _)| *detected != recognized)
+            {
+                let language = stats::detect_language(&result.text());
+                recognized_language = Some((recognized, language));
+            }
+            if let Some((_, Some(language))) = &recognized_language {
+                ui.same_line();
+                ui.text_disabled(format!("{} {}", tr("Recognized"), language.name));
+            }
+        }
+
+        /* the spell check and proofread views share the space left with the editor, above the status bar */
+        let status_height = ui.frame_height_with_spacing();
+        let proofreading = transcription.as_ref().filter(|_| config.proofread);
+        let panes = 1 + spellcheck.is_some() as usize + proofreading.is_some() as usize;
+        let pane_height = (ui.content_region_avail()[1] - status_height) / panes as f32;
+        let editor_height = match panes {
+            1 => -status_height,
+            _ => pane_height,
+        };
+        side_by_side.update(&mut renderer, transcription.as_ref());
+        let beside = transcription
+            .as_ref()
+            .filter(|_| config.side_by_side && side_by_side.is_ready() && !streaming.is_reading());
+        let typed = match beside {
+            Some(result) => side_by_side.draw(ui, &mut text, result, editor_height),
+            None => text_area.draw(
+                ui,
+                &mut text,
+                &mut cursor,
+                editor_height,
+                // the lines of a streamed image are still coming in
+                streaming.is_reading(),
+                &config,
+                monospace_font,
+            ),
+        };
+        undo.track(&text, typed);
+
+        if let Some(result) = proofreading {
+            let height = match spellcheck {
+                Some(_) => pane_height,
+                None => -status_height,
+            };
+            ui.child_window("proofread")
+                .size([0.0, height])
+                .border(true)
+                .build(|| proofread.draw(ui, &mut text, result, spellcheck.as_ref()));
+        }
+
+        if let Some(spellcheck) = &mut spellcheck {
+            ui.child_window("spelling")
+                .size([0.0, -status_height])
+                .border(true)
+                .build(|| spellcheck.draw(ui, &mut text));
+        }
+
+        if status.draw_bar(ui) {
+            logging.open = true;
+            logging.errors_only = true;
+        }
+
+        if about_open {
+            about.draw(ui, &mut config, &mut status, &mut about_open);
+        }
+
+        if let Some(level) = logging.draw(ui, config.log_level, &mut status) {
+            config.log_level = level;
+            logging.set_level(level);
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+
+        /* the correction is only applied once the diff is accepted */
+        if let Some(job) = &mut panels.cleanup {
+            job.poll();
+            let mut open = true;
+            if let Some(corrected) = job.draw(ui, &mut open) {
+                text = corrected;
+                cursor = None;
+            }
+            if !open {
+                panels.cleanup = None;
+            }
+        }
+
+        if let Some(job) = &mut batch {
+            let mut open = true;
+            job.draw(ui, &mut open);
+            if !open {
+                batch = None;
+            }
+        }
+
+        /* the camera stops as soon as its window is closed */
+        #[cfg(feature = "camera")]
+        if camera_open {
+            let action = scanner.draw(
+                ui,
+                &mut renderer,
+                &mut config,
+                &mut status,
+                &mut camera_open,
+            );
+            match action {
+                Some(ScannerAction::Snap(frame)) => match transcribe_region(
+                    backend.as_ref(),
+                    frame,
+                    &config,
+                    &clipboard,
+                    &mut renderer,
+                    &mut history,
+                    &mut secret_guard,
+                ) {
+                    Ok((result, result_transcription, result_pages)) => {
+                        place_text(&mut text, &mut cursor, &config, &result);
+                        transcription = result_transcription;
+                        pages = result_pages;
+                    }
+                    Err(err) => status.error("Error extracting text from camera", err),
+                },
+                Some(ScannerAction::Read(read)) => {
+                    place_text(&mut text, &mut cursor, &config, &read);
+                    transcription = None;
+                    pages.clear();
+                }
+                None => {}
+            }
+            if !camera_open {
+                scanner.close(&renderer);
+            }
+        }
+
+        // a color picked from the preview filters recognition to text of it
+        if let Some(color) = preview
+            .as_mut()
+            .and_then(|preview| preview.take_text_color())
+        {
+            let filter = &mut config.preprocessing.text_color;
+            match filter {
+                Some(filter) => filter.color = color,
+                None => *filter = Some(ColorFilter::new(color)),
+            }
+            if let Err(err) = config.save() {
+                error!("Error saving config: {}", err);
+            }
+        }
+
+        if let Some(region) = preview.as_mut().and_then(|preview| {
+            preview.draw(
+                ui,
+                &mut renderer,
+                &clipboard,
+                &config.preprocessing,
+                &mut status,
+            )
+        }) {
+            match transcribe_region(
+                backend.as_ref(),
+                region,
+                &config,
+                &clipboard,
+                &mut renderer,
+                &mut history,
+                &mut secret_guard,
+            ) {
+                Ok((result, result_transcription, result_pages)) => {
+                    place_text(&mut text, &mut cursor, &config, &result);
+                    transcription = result_transcription;
+                    pages = result_pages;
+                }
+                Err(err) => status.error("Error extracting text from image", err),
+            }
+        }
+
+        /* the setup wizard writes the config once finished or skipped */
+        if let Some(wizard) = &mut onboarding {
+            match wizard.draw(ui, &mut config) {
+                Some(OnboardingAction::Appearance) => appearance_changed = true,
+                // a cloud backend has no models to load, only the client
+                Some(OnboardingAction::ReloadModels)
+                    if CLOUD_BACKENDS.contains(&config.backend.as_str()) =>
+                {
+                    match load_backend(&config, &cache) {
+                        Ok(loaded) => {
+                            backend = loaded;
+                            models_dialog = None;
+                            wizard.models_loaded(Ok(()));
+                        }
+                        Err(err) => {
+                            wizard.models_loaded(Err(format!("Error loading OCR models: {}", err)))
+                        }
+                    }
+                }
+                Some(OnboardingAction::ReloadModels) => {
+                    wizard.load(spawn_model_load(&config, &cache))
+                }
+                Some(OnboardingAction::Loaded(factory)) => match factory() {
+                    Ok(loaded) => {
+                        backend = loaded;
+                        models_dialog = None;
+                        wizard.models_loaded(Ok(()));
+                    }
+                    Err(err) => {
+                        wizard.models_loaded(Err(format!("Error loading OCR models: {}", err)))
+                    }
+                },
+                Some(OnboardingAction::Hotkey) => {
+                    if let (Ok(manager), Some(old)) = (&hotkey_manager, hotkey) {
+                        if let Err(err) = manager.unregister(old) {
+                            error!("Error unregistering hotkey: {}", err);
+                        }
+                    }
+                    hotkey = register(&config.hotkey);
+                    wizard.hotkey_registered(hotkey.is_some());
+                }
+                Some(OnboardingAction::Test) => wizard.test(spawn_sample_test(&config)),
+                Some(OnboardingAction::Finish) => {
+                    models_dialog = wizard.models_error().map(ModelsDialog::new);
+                    onboarding = None;
+                    if let Err(err) = config.save() {
+                        error!("Error saving config: {}", err);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        /* the models can be picked in the window when they were not found at startup */
+        if let (Some(dialog), None) = (&mut models_dialog, &onboarding) {
+            if dialog.draw(ui, &mut config) {
+                match load_backend(&config, &cache) {
+                    Ok(loaded) => {
+                        backend = loaded;
+                        models_dialog = None;
+                        if let Err(err) = config.save() {
+                            error!("Error saving config: {}", err);
+                        }
+                    }
+                    Err(err) => dialog.failed(format!("Error loading OCR models: {}", err)),
+                }
+            }
+        }
+
+        if translating {
+            panels
+                .translation
+                .draw(ui, &mut config, &mut text, &mut translating);
+        }
+
+        if extracting {
+            extraction.draw(ui, &mut config, &text, &mut extracting);
+        }
+
+        if statistics_open {
+            statistics.draw(ui, &text, &mut statistics_open);
+        }
+
+        if anonymizing {
+            anonymization.draw(ui, &text, &clipboard, &mut status, &mut anonymizing);
+        }
+
+        if links_open {
+            link_panel.draw(ui, &clipboard, &mut status, &mut links_open);
+        }
+
+        if lines_open {
+            line_panel.update(&text);
+            line_panel.draw(ui, &clipboard, &mut status, &mut lines_open);
+        }
+
+        if codes_open && config.check_codes {
+            code_panel.draw(
+                ui,
+                Editor {
+                    text: &mut text,
+                    cursor: &mut cursor,
+                    transcription: &mut transcription,
+                    pages: &mut pages,
+                    undo: &mut undo,
+                    panels: &mut panels,
+                },
+                &clipboard,
+                &mut secret_guard,
+                &mut status,
+                &mut codes_open,
+            );
+        }
+
+        if cheat_sheet_open {
+            let mut hotkeys = vec![
+                (
+                    "Transcribe the clipboard".to_string(),
+                    config.hotkey.clone(),
+                ),
+                (
+                    "Transcribe the active window".to_string(),
+                    config.window_hotkey.clone(),
+                ),
+                (
+                    "Start or stop the live overlay".to_string(),
+                    config.overlay_hotkey.clone(),
+                ),
+            ];
+            for (monitor, shortcut) in config.monitor_hotkeys.iter().enumerate() {
+                hotkeys.push((
+                    format!("Transcribe monitor {}", monitor + 1),
+                    shortcut.clone(),
+                ));
+            }
+            keymap::draw_cheat_sheet(ui, &config.keymap, &hotkeys, &mut cheat_sheet_open);
+        }
+
+        if book_open {
+            book.draw(ui, &mut config, &text, &mut status, &mut book_open);
+        }
+
+        if compare_open {
+            compare.draw(
+                ui,
+                &mut compare_open,
+                &config,
+                &history,
+                &text,
+                &mut status,
+                load_uncached,
+            );
+        }
+
+        /* the composed entry goes in as one, the way captions do */
+        if compose_open {
+            match compose.draw(
+                ui,
+                &mut compose_open,
+                &mut config,
+                &clipboard,
+                &mut secret_guard,
+                &mut status,
+            ) {
+                Some(ComposeAction::AddRegion) => compose_region = true,
+                Some(ComposeAction::Finish(result)) => {
+                    place_text(&mut text, &mut cursor, &config, &result);
+                    transcription = None;
+                    pages.clear();
+                    if config.auto_copy {
+                        let copied = config.templated(&result, Some("compose"), None);
+                        if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                    remember(
+                        &mut history,
+                        &mut renderer,
+                        HistoryEntry {
+                            text: result,
+                            transcription: None,
+                            thumbnail: None,
+                            thumbnail_image: None,
+                        },
+                        None,
+                        Some("compose"),
+                    );
+                }
+                None => {}
+            }
+        }
+
+        secret_guard.draw(ui, &clipboard, &mut status);
+
+        /* side panel with past transcriptions */
+        let [display_width, display_height] = ui.io().display_size;
+        let mut opened_entry = None;
+        ui.window(format!("{}###History", tr("History")))
+            .position([display_width - 320.0, 0.0], Condition::FirstUseEver)
+            .size([320.0, display_height], Condition::FirstUseEver)
+            .collapsible(true)
+            .build(|| {
+                #[cfg(feature = "archive")]
+                if let Some(archive) = history.archive() {
+                    if let Some(found) = archive_search.draw(ui, archive, &clipboard, &mut status) {
+                        text = found;
+                        transcription = None;
+                        pages.clear();
+                    }
+                    if archive_search.is_searching() {
+                        return;
+                    }
+                    ui.separator();
+                }
+                if history.is_empty() {
+                    ui.text_disabled(tr("No transcriptions yet"));
+                }
+                for (index, entry) in history.entries().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    if let Some(thumbnail) = &entry.thumbnail {
+                        imgui::Image::new(thumbnail.id, thumbnail.size).build(ui);
+                    }
+                    ui.text_wrapped(entry.text.as_str());
+                    if let Some(name) = entry
+                        .transcription
+                        .as_ref()
+                        .and_then(|transcription| transcription.backend.as_deref())
+                    {
+                        ui.text_disabled(format!("{} {}", tr("Read by"), name));
+                    }
+                    if ui.button(tr("Copy")) {
+                        if let Err(err) = secret_guard.copy(&clipboard, &entry.text) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                    ui.same_line();
+                    if ui.button(tr("Open")) {
+                        text = entry.text.clone();
+                        transcription = entry.transcription.clone();
+                        pages.clear();
+                        opened_entry = Some(index);
+                    }
+                    ui.separator();
+                }
+            });
+        // only the newest entry was read from the image kept for proofreading
+        if opened_entry.is_some_and(|index| index > 0) {
+            history.set_last_image(None);
+        }
+
+        /* the frames of a scrolling capture are stitched until it is finished */
+        let mut picked = None;
+        let mut finished = false;
+        if let Some(capture) = &mut scroll_capture {
+            capture.poll();
+            match capture.draw(ui) {
+                Some(ScrollAction::Finish(image)) => {
+                    picked = Some(image);
+                    finished = true;
+                }
+                Some(ScrollAction::Cancel) => finished = true,
+                None => (),
+            }
+        }
+        if finished {
+            scroll_capture = None;
+        }
+
+        /* the snipping overlay covers everything but the toasts */
+        let mut composed = None;
+        if let Some(overlay) = &mut overlay {
+            if let Some(region) = overlay.draw(ui) {
+                match (snip_purpose, overlay.selected_rect()) {
+                    (SnipPurpose::Scrolling, Some(rect)) => {
+                        scroll_capture = Some(ScrollCapture::start(overlay.desktop, rect, &region))
+                    }
+                    (SnipPurpose::LiveOverlay, Some(rect)) => {
+                        live_overlay.start(overlay.desktop, rect, &config, overlay_hotkey.is_some())
+                    }
+                    (SnipPurpose::Pin, Some(rect)) => {
+                        pinned = Some(PinnedRegion::start(
+                            overlay.desktop,
+                            rect,
+                            false,
+                            tabs.active_id(),
+                        ))
+                    }
+                    (SnipPurpose::Captions, Some(rect)) => {
+                        pinned = Some(PinnedRegion::start(
+                            overlay.desktop,
+                            rect,
+                            true,
+                            tabs.active_id(),
+                        ))
+                    }
+                    (SnipPurpose::Compose, _) => composed = Some(region),
+                    _ => picked = Some(region),
+                }
+            }
+        }
+
+        /* the live overlay reads its region again every few seconds, once it covers it */
+        live_overlay.frame(ui, &mut window, &config, overlay.is_some());
+
+        /* a pinned region is read again every few seconds on the pool, its new text appended
+        to the tab it was pinned from and kept in the history */
+        if let Some(pin) = &mut pinned {
+            if let Some(frame) = pin.poll(&config) {
+                match ensure_pool(&mut worker_pool, &config, &cache) {
+                    Ok(pool) => {
+                        let (sender, job) = mpsc::channel();
+                        let preprocessing = config.preprocessing.clone();
+                        pool.submit(move |backend| {
+                            let result = backend.map_err(str::to_string).and_then(|backend| {
+                                ocr::transcribe_preprocessed(backend, &frame, &preprocessing)
+                                    .map_err(|err| err.to_string())
+                            });
+                            let _ = sender.send(result);
+                        });
+                        pin.read(job);
+                    }
+                    Err(err) => pin.failed(err.to_string()),
+                }
+            }
+            match pin.finished() {
+                Some(Ok(result)) => {
+                    if let Some(added) = pin.new_text(&config, &config.render(&result)) {
+                        let active = Editor {
+                            text: &mut text,
+                            cursor: &mut cursor,
+                            transcription: &mut transcription,
+                            pages: &mut pages,
+                            undo: &mut undo,
+                            panels: &mut panels,
+                        };
+                        if let Some(Editor { text: target, .. }) = tabs.editor(pin.tab, active) {
+                            if !target.is_empty() && !target.ends_with('\n') {
+                                target.push('\n');
+                            }
+                            target.push_str(&added);
+                            target.push('\n');
+                        }
+                        remember(
+                            &mut history,
+                            &mut renderer,
+                            HistoryEntry {
+                                text: added,
+                                transcription: None,
+                                thumbnail: None,
+                                thumbnail_image: None,
+                            },
+                            None,
+                            Some("pinned region"),
+                        );
+                    }
+                }
+                Some(Err(err)) => pin.failed(err),
+                None => {}
+            }
+            if !pin.draw(ui, &mut config) {
+                let tab = pin.tab;
+                let cues = pinned.take().and_then(PinnedRegion::finish);
+                if let Some(cues) = cues.filter(|cues| !cues.is_empty()) {
+                    let result = subtitle::timestamped_text(&cues);
+                    placements.push(Placement {
+                        tab,
+                        result: result.clone(),
+                        before: None,
+                        read: Some((None, Vec::new())),
+                        merge: false,
+                    });
+                    if config.auto_copy {
+                        let copied = config.templated(&result, Some("captions"), None);
+                        if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                            status.error("Error setting text to clipboard", err);
+                        }
+                    }
+                    remember(
+                        &mut history,
+                        &mut renderer,
+                        HistoryEntry {
+                            text: result,
+                            transcription: None,
+                            thumbnail: None,
+                            thumbnail_image: None,
+                        },
+                        None,
+                        Some("captions"),
+                    );
+                    subtitle_cues = Some((cues, "captions"));
+                }
+            }
+        }
+
+        /* a region snipped for the compose window only becomes one of its sections */
+        if let Some(region) = composed {
+            match ensure_pool(&mut worker_pool, &config, &cache) {
+                Ok(pool) => {
+                    let (sender, job) = mpsc::channel();
+                    let preprocessing = config.preprocessing.clone();
+                    pool.submit(move |backend| {
+                        let result = backend.map_err(str::to_string).and_then(|backend| {
+                            ocr::transcribe_preprocessed(backend, &region, &preprocessing)
+                                .map_err(|err| err.to_string())
+                        });
+                        let _ = sender.send(result);
+                    });
+                    compose.read(job);
+                }
+                Err(err) => status.error("Error loading OCR models", err),
+            }
+        }
+
+        if let Some(region) = picked {
+            match transcribe_region(
+                backend.as_ref(),
+                region,
+                &config,
+                &clipboard,
+                &mut renderer,
+                &mut history,
+                &mut secret_guard,
+            ) {
+                Ok((result, result_transcription, result_pages)) => {
+                    place_text(&mut text, &mut cursor, &config, &result);
+                    transcription = result_transcription;
+                    pages = result_pages;
+                }
+                Err(err) => status.error("Error extracting text from screen", err),
+            }
+        }
+
+        /* formulas read as LaTeX take the place of their lines once the model answers,
+        unless another result came in meanwhile */
+        let finished = formula_job
+            .as_mut()
+            .and_then(|(pushed, tab, job)| Some((*pushed, *tab, job.poll()?)));
+        if let Some((pushed, tab, result)) = finished {
+            formula_job = None;
+            let active = Editor {
+                text: &mut text,
+                cursor: &mut cursor,
+                transcription: &mut transcription,
+                pages: &mut pages,
+                undo: &mut undo,
+                panels: &mut panels,
+            };
+            let editor = tabs.editor(tab, active);
+            match result {
+                Ok(Some(converted)) if history.pushed() == pushed => {
+                    if let Some(entry) = history.latest_mut() {
+                        let before = entry
+                            .transcription
+                            .as_ref()
+                            .map(|read| config.render(read))
+                            .unwrap_or_default();
+                        let after = config.render(&converted);
+                        entry.text = entry.text.replacen(&before, &after, 1);
+                        let found = editor
+                            .filter(|_| !before.is_empty())
+                            .and_then(|editor| Some((editor.text.rfind(&before)?, editor)));
+                        if let Some((at, editor)) = found {
+                            editor.text.replace_range(at..at + before.len(), &after);
+                            if let Some(position) =
+                                editor.cursor.as_mut().filter(|position| **position > at)
+                            {
+                                *position = (*position + after.len())
+                                    .saturating_sub(before.len())
+                                    .max(at);
+                            }
+                            *editor.transcription = Some(converted.clone());
+                        }
+                        // the text copied before the formulas were read is copied again
+                        let source = converted.source.as_deref();
+                        let copied =
+                            config.templated(&before, source, entry.transcription.as_ref());
+                        if clipboard.get_text().is_ok_and(|current| current == copied) {
+                            let copied = config.templated(&after, source, Some(&converted));
+                            if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                                status.error("Error setting text to clipboard", err);
+                            }
+                        }
+                        entry.transcription = Some(history::kept(converted));
+                    }
+                }
+                Ok(_) => {}
+                // the formulas are kept as recognized
+                Err(err) => warn!("Error reading formulas: {}", err),
+            }
+        }
+
+        /* every new result is mirrored to screen readers and handed to the plugins and webhook,
+        and told of in a notification while the window is out of sight */
+        if history.pushed() != announced {
+            announced = history.pushed();
+            if let Some(entry) = history.entries().next() {
+                if let Some(job) = entry
+                    .transcription
+                    .as_ref()
+                    .and_then(|read| spawn_formulas(&config, read))
+                {
+                    let tab = placed.unwrap_or(tabs.active_id());
+                    formula_job = Some((announced, tab, job));
+                }
+                if let Some(reader) = &mut screen_reader {
+                    reader.announce(&entry.text);
+                }
+                if let Some(timings) = entry.transcription.as_ref().and_then(|t| t.timings) {
+                    perf_hud.record(timings);
+                }
+                if config.notify_in_background
+                    && (window_hidden || window.is_minimized())
+                    && !entry.text.trim().is_empty()
+                {
+                    notifier.show(&entry.text, &secret_guard);
+                }
+                plugin_runner.run(
+                    &config.plugins,
+                    &config.enabled_plugins,
+                    &entry.text,
+                    entry.transcription.as_ref(),
+                );
+                if !config.webhook_url.trim().is_empty() {
+                    webhook.post(
+                        config.webhook_url.trim(),
+                        &config.webhook_authorization,
+                        &entry.text,
+                        entry.transcription.as_ref(),
+                    );
+                }
+            }
+        }
+        for (name, err) in plugin_runner.failures() {
+            status.error(&format!("Error running plugin {}", name), err);
+        }
+        for err in webhook.failures() {
+            status.error("Error posting to the webhook", err);
+        }
+        for err in notifier.failures() {
+            status.error("Error showing a notification", err);
+        }
+        for copied in notifier.copies() {
+            if let Err(err) = secret_guard.copy(&clipboard, &copied) {
+                status.error("Error setting text to clipboard", err);
+            }
+        }
+        for (name, err) in delivery.failures() {
+            status.error("Error sending text", format!("{}: {}", name, err));
+        }
+        for err in script_errors.failures() {
+            status.error("Error running script", err);
+        }
+
+        /* put back what was autosaved before the crash, or let it go */
+        if let Some((_, saved_at)) = &recovery {
+            let title = format!("{}###recover", tr("Recover unsaved work"));
+            if !recovery_offered {
+                ui.open_popup(&title);
+                recovery_offered = true;
+            }
+            let saved_at = chrono::DateTime::<chrono::Local>::from(*saved_at);
+            let mut choice = None;
+            ui.modal_popup_config(&title)
+                .always_auto_resize(true)
+                .build(|| {
+                    ui.text(tr("Transcribe Wizard did not close properly last time."));
+                    ui.text(format!(
+                        "{} {}",
+                        tr("Its tabs and history were autosaved at"),
+                        saved_at.format("%Y-%m-%d %H:%M:%S")
+                    ));
+                    if ui.button(tr("Recover")) {
+                        choice = Some(true);
+                    }
+                    ui.same_line();
+                    if ui.button(tr("Discard")) {
+                        choice = Some(false);
+                    }
+                });
+            match choice {
+                Some(true) => {
+                    if let Some((mut session, _)) = recovery.take() {
+                        for entry in history.clear() {
+                            if let Some(texture) = entry.thumbnail {
+                                texture.destroy(&renderer);
+                            }
+                        }
+                        session.restore_history(&mut history, &mut renderer);
+                        // the layout is taken in the next frame, this one is
+                        // being built
+                        recovered_layout = Some(mem::take(&mut session.layout));
+                        if let Some((x, y, width, height)) = session.window {
+                            window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+                            if let Err(err) = window.set_size(width, height) {
+                                error!("Error restoring window size: {}", err);
+                            }
+                        }
+                        tabs = Tabs::restore(
+                            mem::take(&mut session.tabs),
+                            session.active,
+                            Editor {
+                                text: &mut text,
+                                cursor: &mut cursor,
+                                transcription: &mut transcription,
+                                pages: &mut pages,
+                                undo: &mut undo,
+                                panels: &mut panels,
+                            },
+                        );
+                        announced = history.pushed();
+                    }
+                }
+                Some(false) => {
+                    recovery = None;
+                    if let Err(err) = Session::discard_autosave() {
+                        error!("Error discarding the autosave: {}", err);
+                    }
+                }
+                None => (),
+            }
+        }
+
+        if config.perf_hud {
+            perf_hud.draw(ui);
+        }
+
+        /* last so it stays on top of the other windows */
+        status.draw_toast(ui);
+
+        /* render */
+        let draw_data = imgui.render();
+
+        unsafe { renderer.gl_context().clear(glow::COLOR_BUFFER_BIT) };
+        if let Err(err) = renderer.render(draw_data) {
+            error!("Error rendering: {}", err);
+        }
+
+        window.gl_swap_window();
+
+        /* save the tabs and history aside now and then, not while the last autosave is unanswered */
+        if let Some((fingerprint, receiver)) = &autosaving {
+            match receiver.try_recv() {
+                Ok(Ok(())) => {
+                    autosaved = Some(*fingerprint);
+                    autosaving = None;
+                }
+                Ok(Err(err)) => {
+                    error!("Error autosaving: {}", err);
+                    autosaving = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => autosaving = None,
+            }
+        }
+        let interval = Duration::from_secs(config.autosave_interval as u64);
+        if config.autosave_interval > 0
+            && recovery.is_none()
+            && autosaving.is_none()
+            && autosaved_at.elapsed() >= interval
+        {
+            autosaved_at = Instant::now();
+            let editor = Editor {
+                text: &mut text,
+                cursor: &mut cursor,
+                transcription: &mut transcription,
+                pages: &mut pages,
+                undo: &mut undo,
+                panels: &mut panels,
+            };
+            // the layout too when the session is kept, so a crash does not
+            // lose what saving it on exit would have kept
+            let (layout, placement) = if config.restore_session {
+                let mut layout = String::new();
+                imgui.save_ini_settings(&mut layout);
+                let ((x, y), (width, height)) =
+                    snip_window.unwrap_or_else(|| (window.position(), window.size()));
+                (layout, Some((x, y, width, height)))
+            } else {
+                (String::new(), None)
+            };
+            let session = Session::new(tabs.save(&editor), layout, placement);
+            let fingerprint = session.fingerprint(&history);
+            if autosaved != Some(fingerprint) {
+                autosaving = Some((fingerprint, session.autosave(&history)));
+            }
+        }
+    }
+
+    /* keep the text, history and layout for the next run */
+    if config.restore_session {
+        let mut layout = String::new();
+        imgui.save_ini_settings(&mut layout);
+        // not the desktop the window covers while snipping
+        let ((x, y), (width, height)) =
+            snip_window.unwrap_or_else(|| (window.position(), window.size()));
+        let editor = Editor {
+            text: &mut text,
+            cursor: &mut cursor,
+            transcription: &mut transcription,
+            pages: &mut pages,
+            undo: &mut undo,
+            panels: &mut panels,
+        };
+        let session = Session::new(tabs.save(&editor), layout, Some((x, y, width, height)));
+        if let Err(err) = session.save(&history) {
+            error!("Error saving session: {}", err);
+        }
+    }
+    // a clean exit leaves nothing to recover, unless recovering was never answered.
+    // An autosave still being written is waited for so it is not left behind
+    if let Some((_, receiver)) = autosaving {
+        let _ = receiver.recv();
+    }
+    if recovery.is_none() {
+        if let Err(err) = Session::discard_autosave() {
+            error!("Error discarding the autosave: {}", err);
+        }
+    }
+
+    /* keep the cached results for the next run */
+    if let (true, Some(cache), Some(path)) = (config.persist_cache, &cache, Config::cache_path()) {
+        if let Ok(cache) = cache.lock() {
+            if let Err(err) = cache.save(&path) {
+                error!("Error saving result cache: {}", err);
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
 }
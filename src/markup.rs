@@ -0,0 +1,593 @@
+// Turn rich clipboard content, HTML and RTF, into plain text or Markdown
+
+// elements whose content is never shown
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "template", "noscript"];
+
+// elements that start on a new line, and ones separated by a blank line
+const LINE_ELEMENTS: &[&str] = &[
+    "div",
+    "li",
+    "tr",
+    "dt",
+    "dd",
+    "section",
+    "article",
+    "header",
+    "footer",
+    "nav",
+    "aside",
+    "main",
+    "figure",
+    "figcaption",
+    "address",
+    "form",
+    "fieldset",
+];
+const PARAGRAPH_ELEMENTS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "dl",
+    "table",
+    "blockquote",
+    "pre",
+    "hr",
+];
+
+// Writes text while collapsing whitespace the way a browser would
+struct Writer {
+    markdown: bool,
+    out: String,
+    // whitespace seen since the last character written
+    space: bool,
+    // whitespace right after an opening marker like ** would break it
+    glued: bool,
+    newlines: usize,
+    pre: bool,
+    quote_depth: usize,
+    // counter of every open list, None for unordered ones
+    lists: Vec<Option<usize>>,
+    links: Vec<Option<String>>,
+    cells: usize,
+    rows: usize,
+}
+
+impl Writer {
+    fn new(markdown: bool) -> Self {
+        Writer {
+            markdown,
+            out: String::new(),
+            space: false,
+            glued: false,
+            newlines: 0,
+            pre: false,
+            quote_depth: 0,
+            lists: Vec::new(),
+            links: Vec::new(),
+            cells: 0,
+            rows: 0,
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    // end the current line, with `count` - 1 blank lines after it
+    fn block(&mut self, count: usize) {
+        self.newlines = self.newlines.max(count);
+        self.space = false;
+    }
+
+    // write the line breaks and quote markers due before the next character
+    fn flush(&mut self) {
+        if self.newlines > 0 && !self.out.is_empty() {
+            while self.out.ends_with(' ') {
+                self.out.pop();
+            }
+            let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+            for _ in existing..self.newlines {
+                self.out.push('\n');
+            }
+            self.space = false;
+        }
+        self.newlines = 0;
+        if self.markdown && self.quote_depth > 0 && self.at_line_start() {
+            self.out.push_str(&"> ".repeat(self.quote_depth));
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.pre {
+            self.flush();
+            self.out.push_str(text);
+            return;
+        }
+        for character in text.chars() {
+            if character.is_whitespace() {
+                self.space = !self.glued;
+                continue;
+            }
+            self.flush();
+            if self.space && !self.at_line_start() {
+                self.out.push(' ');
+            }
+            self.space = false;
+            self.glued = false;
+            self.out.push(character);
+        }
+    }
+
+    // markup that opens a span, like the ** before bold text
+    fn open(&mut self, marker: &str) {
+        self.flush();
+        if self.space && !self.at_line_start() {
+            self.out.push(' ');
+        }
+        self.space = false;
+        self.glued = true;
+        self.out.push_str(marker);
+    }
+
+    // markup that closes a span, whitespace before it moves after it
+    fn close(&mut self, marker: &str) {
+        self.glued = false;
+        self.out.push_str(marker);
+    }
+
+    fn tag(&mut self, name: &str, closing: bool, attributes: &str) {
+        let markdown = self.markdown;
+        match (name, closing) {
+            ("br", _) => {
+                self.flush();
+                self.out.push_str(if markdown { "  \n" } else { "\n" });
+                self.space = false;
+            }
+            ("hr", false) => {
+                self.block(2);
+                if markdown {
+                    self.open("---");
+                }
+                self.block(2);
+            }
+            ("li", false) => {
+                self.block(1);
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}{}. ", indent, number)
+                    }
+                    _ => format!("{}- ", indent),
+                };
+                self.open(&marker);
+            }
+            ("ul", false) => self.open_list(None),
+            ("ol", false) => self.open_list(Some(0)),
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                self.block(if self.lists.is_empty() { 2 } else { 1 });
+            }
+            ("blockquote", closing) => {
+                self.block(2);
+                if closing {
+                    self.quote_depth = self.quote_depth.saturating_sub(1);
+                } else {
+                    self.quote_depth += 1;
+                }
+            }
+            ("pre", false) => {
+                self.block(2);
+                if markdown {
+                    self.open("```");
+                    self.out.push('\n');
+                }
+                self.pre = true;
+            }
+            ("pre", true) => {
+                self.pre = false;
+                if markdown {
+                    if !self.at_line_start() {
+                        self.out.push('\n');
+                    }
+                    self.out.push_str("```");
+                }
+                self.block(2);
+            }
+            ("table", false) => self.rows = 0,
+            ("tr", false) => {
+                self.block(1);
+                self.cells = 0;
+            }
+            ("tr", true) => {
+                if markdown && self.cells > 0 {
+                    self.close(" |");
+                    if self.rows == 0 {
+                        self.block(1);
+                        self.open(&format!("|{}", " --- |".repeat(self.cells)));
+                    }
+                }
+                self.rows += 1;
+                self.block(1);
+            }
+            ("td" | "th", false) => {
+                if markdown {
+                    self.open(if self.cells == 0 { "| " } else { " | " });
+                } else if self.cells > 0 {
+                    self.close("\t");
+                    self.space = false;
+                }
+                self.cells += 1;
+            }
+            ("a", false) => {
+                let href = attribute(attributes, "href").filter(|href| !href.is_empty());
+                if markdown && href.is_some() {
+                    self.open("[");
+                }
+                self.links.push(href);
+            }
+            ("a", true) => {
+                if let Some(Some(href)) = self.links.pop() {
+                    if markdown {
+                        self.close(&format!("]({})", href));
+                    }
+                }
+            }
+            (heading, false) if markdown && is_heading(heading) => {
+                self.block(2);
+                let level = heading[1..].parse().unwrap_or(1);
+                self.open(&format!("{} ", "#".repeat(level)));
+            }
+            ("b" | "strong", false) if markdown => self.open("**"),
+            ("b" | "strong", true) if markdown => self.close("**"),
+            ("i" | "em", false) if markdown => self.open("*"),
+            ("i" | "em", true) if markdown => self.close("*"),
+            ("s" | "del" | "strike", false) if markdown => self.open("~~"),
+            ("s" | "del" | "strike", true) if markdown => self.close("~~"),
+            ("code", false) if markdown && !self.pre => self.open("`"),
+            ("code", true) if markdown && !self.pre => self.close("`"),
+            (name, _) if PARAGRAPH_ELEMENTS.contains(&name) => self.block(2),
+            (name, _) if LINE_ELEMENTS.contains(&name) => self.block(1),
+            _ => (),
+        }
+    }
+
+    fn open_list(&mut self, counter: Option<usize>) {
+        self.block(if self.lists.is_empty() { 2 } else { 1 });
+        self.lists.push(counter);
+    }
+
+    fn finish(self) -> String {
+        self.out.trim().to_string()
+    }
+}
+
+fn is_heading(name: &str) -> bool {
+    name.len() == 2 && name.starts_with('h') && name.as_bytes()[1].is_ascii_digit()
+}
+
+// value of an attribute in the inside of a tag, quoted or not
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let lowercase = attributes.to_ascii_lowercase();
+    let mut search = 0;
+    while let Some(found) = lowercase[search..].find(name) {
+        let start = search + found;
+        search = start + name.len();
+        let before = lowercase[..start].chars().next_back();
+        if before.is_some_and(|character| !character.is_whitespace()) {
+            continue;
+        }
+        let rest = attributes[search..].trim_start();
+        let Some(value) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or(""),
+            _ => value.split_whitespace().next().unwrap_or(""),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+// replace character references like &amp; and &#233;
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| {
+                let name = &rest[1..end + 1];
+                let character = match name {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "ndash" => Some('–'),
+                    "mdash" => Some('—'),
+                    "hellip" => Some('…'),
+                    "bull" => Some('•'),
+                    "lsquo" => Some('‘'),
+                    "rsquo" => Some('’'),
+                    "ldquo" => Some('“'),
+                    "rdquo" => Some('”'),
+                    "laquo" => Some('«'),
+                    "raquo" => Some('»'),
+                    "copy" => Some('©'),
+                    "reg" => Some('®'),
+                    "trade" => Some('™'),
+                    "euro" => Some('€'),
+                    _ => {
+                        let number = name.strip_prefix('#')?;
+                        let code = match number.strip_prefix(['x', 'X']) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                            None => number.parse().ok()?,
+                        };
+                        char::from_u32(code)
+                    }
+                }?;
+                Some((character, end + 2))
+            });
+        match decoded {
+            Some((character, length)) => {
+                out.push(character);
+                rest = &rest[length..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Convert HTML to plain text, or to Markdown keeping headings, emphasis, links,
+// lists, quotes, code and tables
+pub fn html_to_text(html: &str, markdown: bool) -> String {
+    let mut writer = Writer::new(markdown);
+    // the Windows clipboard prefixes HTML with a header of byte offsets
+    let mut rest = match html.find('<') {
+        Some(start) if html.starts_with("Version:") => &html[start..],
+        _ => html,
+    };
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            writer.text(&decode_entities(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+
+        let end = rest.find('>').unwrap_or(rest.len());
+        let inside = rest[1..end].trim();
+        rest = rest.get(end + 1..).unwrap_or("");
+        let (closing, inside) = match inside.strip_prefix('/') {
+            Some(inside) => (true, inside),
+            None => (false, inside),
+        };
+        // doctype, processing instructions and the like
+        if inside.starts_with(['!', '?']) {
+            continue;
+        }
+        let name_end = inside
+            .find(|character: char| character.is_whitespace() || character == '/')
+            .unwrap_or(inside.len());
+        let name = inside[..name_end].to_ascii_lowercase();
+
+        if !closing && HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            let end_tag = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&end_tag) {
+                Some(start) => rest[start..]
+                    .find('>')
+                    .map_or("", |end| &rest[start + end + 1..]),
+                None => "",
+            };
+            continue;
+        }
+        writer.tag(&name, closing, &inside[name_end..]);
+    }
+
+    writer.finish()
+}
+
+// RTF groups holding data rather than document text
+const RTF_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "info",
+    "pict",
+    "header",
+    "headerl",
+    "headerr",
+    "footer",
+    "footerl",
+    "footerr",
+    "listtable",
+    "listoverridetable",
+    "rsidtbl",
+    "generator",
+    "xmlnstbl",
+    "themedata",
+    "colorschememapping",
+    "latentstyles",
+    "datastore",
+    "object",
+    "fldinst",
+    "filetbl",
+    "revtbl",
+];
+
+// Windows-1252 characters that differ from Latin-1, for the bytes 0x80 to 0x9f
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+fn windows_1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9f => WINDOWS_1252[byte as usize - 0x80],
+        _ => byte as char,
+    }
+}
+
+// State of an RTF group, restored when it closes
+#[derive(Clone, Copy)]
+struct RtfGroup {
+    hidden: bool,
+    // fallback characters following every \u escape
+    unicode_skip: usize,
+}
+
+// Convert RTF to plain text
+pub fn rtf_to_text(rtf: &str) -> String {
+    let bytes = rtf.as_bytes();
+    let mut out = String::new();
+    let mut groups = vec![RtfGroup {
+        hidden: false,
+        unicode_skip: 1,
+    }];
+    let mut skip = 0;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let byte = bytes[index];
+        index += 1;
+        let group = *groups.last().unwrap_or(&RtfGroup {
+            hidden: false,
+            unicode_skip: 1,
+        });
+        let push = |out: &mut String, character: char, skip: &mut usize| {
+            if *skip > 0 {
+                *skip -= 1;
+            } else if !group.hidden {
+                out.push(character);
+            }
+        };
+
+        match byte {
+            b'{' => {
+                groups.push(group);
+                // \* marks a destination readers may ignore
+                if bytes[index..].starts_with(b"\\*") {
+                    if let Some(last) = groups.last_mut() {
+                        last.hidden = true;
+                    }
+                }
+            }
+            b'}' => {
+                if groups.len() > 1 {
+                    groups.pop();
+                }
+            }
+            b'\\' => {
+                let Some(&next) = bytes.get(index) else {
+                    break;
+                };
+                if !next.is_ascii_alphabetic() {
+                    index += 1;
+                    match next {
+                        b'\'' => {
+                            let hex = rtf.get(index..index + 2).unwrap_or("");
+                            index += hex.len();
+                            if let Ok(value) = u8::from_str_radix(hex, 16) {
+                                push(&mut out, windows_1252(value), &mut skip);
+                            }
+                        }
+                        b'~' => push(&mut out, ' ', &mut skip),
+                        b'-' | b'_' => (),
+                        b'\n' | b'\r' => push(&mut out, '\n', &mut skip),
+                        other if other.is_ascii() => push(&mut out, other as char, &mut skip),
+                        _ => (),
+                    }
+                    continue;
+                }
+
+                let word_start = index;
+                while index < bytes.len() && bytes[index].is_ascii_alphabetic() {
+                    index += 1;
+                }
+                let word = &rtf[word_start..index];
+                let number_start = index;
+                if index < bytes.len() && bytes[index] == b'-' {
+                    index += 1;
+                }
+                while index < bytes.len() && bytes[index].is_ascii_digit() {
+                    index += 1;
+                }
+                let parameter: Option<i32> = rtf[number_start..index].parse().ok();
+                // a space ends the control word and is part of it
+                if index < bytes.len() && bytes[index] == b' ' {
+                    index += 1;
+                }
+
+                if RTF_DESTINATIONS.contains(&word) {
+                    if let Some(last) = groups.last_mut() {
+                        last.hidden = true;
+                    }
+                    continue;
+                }
+                match word {
+                    "par" | "line" | "row" | "sect" | "page" => push(&mut out, '\n', &mut skip),
+                    "tab" | "cell" => push(&mut out, '\t', &mut skip),
+                    "emdash" => push(&mut out, '—', &mut skip),
+                    "endash" => push(&mut out, '–', &mut skip),
+                    "bullet" => push(&mut out, '•', &mut skip),
+                    "lquote" => push(&mut out, '‘', &mut skip),
+                    "rquote" => push(&mut out, '’', &mut skip),
+                    "ldblquote" => push(&mut out, '“', &mut skip),
+                    "rdblquote" => push(&mut out, '”', &mut skip),
+                    "uc" => {
+                        if let Some(last) = groups.last_mut() {
+                            last.unicode_skip = parameter.unwrap_or(1).max(0) as usize;
+                        }
+                    }
+                    "u" => {
+                        // negative values wrap around 16 bits
+                        let code = parameter.unwrap_or(0) as i16 as u16 as u32;
+                        if let Some(character) = char::from_u32(code) {
+                            push(&mut out, character, &mut skip);
+                        }
+                        skip = group.unicode_skip;
+                    }
+                    _ => (),
+                }
+            }
+            b'\r' | b'\n' => (),
+            // multi-byte characters only appear in RTF that is not strictly 7-bit
+            _ => {
+                if let Some(character) = rtf.get(index - 1..).and_then(|rest| rest.chars().next()) {
+                    push(&mut out, character, &mut skip);
+                    index += character.len_utf8() - 1;
+                }
+            }
+        }
+    }
+
+    out.lines()
+        .map(str::trim_end)
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
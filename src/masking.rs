@@ -0,0 +1,121 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    config::SecretHandling,
+    locale::tr,
+    status::Status,
+};
+use imgui::{Condition, Ui};
+use tracing::info;
+use transcribewizard::secrets::{self, Secret};
+
+// characters of a secret shown in the prompt, enough to tell which one it is
+const SHOWN_CHARS: usize = 6;
+
+// Puts results on the clipboard, holding back those with secrets in them
+// until the user picks whether to copy them masked
+pub struct SecretGuard {
+    pub handling: SecretHandling,
+    pending: Option<(String, Vec<Secret>)>,
+}
+
+impl SecretGuard {
+    pub fn new(handling: SecretHandling) -> Self {
+        SecretGuard {
+            handling,
+            pending: None,
+        }
+    }
+
+    // a result waits for the user to decide
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    // copy `text` the way the handling says, a text held back replaces the
+    // one held before
+    pub fn copy(&mut self, clipboard: &SystemClipboard, text: &str) -> Result<(), String> {
+        let found = match self.handling {
+            SecretHandling::Ignore => Vec::new(),
+            _ => secrets::find(text),
+        };
+        if found.is_empty() {
+            return clipboard
+                .set_text(text.to_string())
+                .map_err(|err| err.to_string());
+        }
+        info!("Found {} in the result", secrets::summary(&found));
+        match self.handling {
+            SecretHandling::Mask => clipboard
+                .set_text(secrets::mask(text, &found))
+                .map_err(|err| err.to_string()),
+            _ => {
+                self.pending = Some((text.to_string(), found));
+                Ok(())
+            }
+        }
+    }
+
+    // `text` with its secrets masked, unless the handling copies them as they
+    // are, for what shows it outside the window
+    pub fn masked(&self, text: &str) -> String {
+        match self.handling {
+            SecretHandling::Ignore => text.to_string(),
+            _ => secrets::mask(text, &secrets::find(text)),
+        }
+    }
+
+    // the prompt for the text held back, if any
+    pub fn draw(&mut self, ui: &Ui, clipboard: &SystemClipboard, status: &mut Status) {
+        let Some((text, found)) = &self.pending else {
+            return;
+        };
+        let mut copied = None;
+        let mut dismissed = false;
+        let mut open = true;
+        let [display_width, display_height] = ui.io().display_size;
+        ui.window(format!("{}###Secrets found", tr("Secrets found")))
+            .position(
+                [display_width / 2.0, display_height / 2.0],
+                Condition::Appearing,
+            )
+            .position_pivot([0.5, 0.5])
+            .size([420.0, 0.0], Condition::FirstUseEver)
+            .opened(&mut open)
+            .collapsible(false)
+            .focused(true)
+            .build(|| {
+                ui.text_wrapped(format!(
+                    "The result contains {}, it was not copied yet.",
+                    secrets::summary(found)
+                ));
+                for secret in found {
+                    let value: String = text[secret.range.clone()]
+                        .chars()
+                        .take(SHOWN_CHARS)
+                        .collect();
+                    ui.bullet_text(format!("{}: {}...", secret.kind.label(), value));
+                }
+                ui.separator();
+                if ui.button(tr("Copy masked")) {
+                    copied = Some(secrets::mask(text, found));
+                }
+                ui.same_line();
+                if ui.button(tr("Copy as is")) {
+                    copied = Some(text.clone());
+                }
+                ui.same_line();
+                if ui.button(tr("Don't copy")) {
+                    dismissed = true;
+                }
+            });
+        if let Some(copied) = copied {
+            if let Err(err) = clipboard.set_text(copied) {
+                status.error("Error setting text to clipboard", err);
+            }
+            dismissed = true;
+        }
+        if dismissed || !open {
+            self.pending = None;
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// words either side may have beyond the overlap, a line cut off at the edge of
+// a scrolled screenshot or a status bar
+const EDGE_WORDS: usize = 12;
+
+// shorter runs of the same words are likely chance, such as a repeated heading
+const MIN_OVERLAP_WORDS: usize = 6;
+
+// words at the end of the earlier text searched for the overlap
+const SEARCH_WORDS: usize = 2000;
+
+// the words of a text with their byte ranges
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(from)) => {
+                words.push((from, index));
+                start = None;
+            }
+            (false, None) => start = Some(index),
+            _ => (),
+        }
+    }
+    if let Some(from) = start {
+        words.push((from, text.len()));
+    }
+    words
+}
+
+// what two readings of the same word have in common, OCR of the same text in
+// two screenshots differs in case and punctuation now and then
+fn normalized(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Join `next` onto `previous` where the start of `next` repeats the end of
+/// `previous`, as consecutive screenshots of a page being scrolled do.
+///
+/// The longest run of words the two have in common is looked for near the end
+/// of `previous` and the start of `next`, words are compared ignoring case and
+/// punctuation. The result is `previous` up to the end of the run followed by
+/// the rest of `next`, `previous` alone when `next` adds nothing. `None` when
+/// they do not overlap.
+pub fn merge(previous: &str, next: &str) -> Option<String> {
+    let previous_words = words(previous);
+    let previous_words = &previous_words[previous_words.len().saturating_sub(SEARCH_WORDS)..];
+    let next_words = words(next);
+    let a: Vec<String> = previous_words
+        .iter()
+        .map(|&(start, end)| normalized(&previous[start..end]))
+        .collect();
+    let b: Vec<String> = next_words
+        .iter()
+        .map(|&(start, end)| normalized(&next[start..end]))
+        .collect();
+
+    // runs[j] is the length of the common run ending at word i of a and j of b
+    let mut runs = vec![0; b.len() + 1];
+    // end in a, end in b and length of the best run
+    let mut best = (0, 0, 0);
+    for i in 0..a.len() {
+        let mut diagonal = 0;
+        for j in 0..b.len() {
+            let above = runs[j + 1];
+            runs[j + 1] = if a[i] == b[j] && !a[i].is_empty() {
+                diagonal + 1
+            } else {
+                0
+            };
+            diagonal = above;
+            let length = runs[j + 1];
+            let near_end = a.len() - (i + 1) <= EDGE_WORDS;
+            let near_start = j + 1 - length <= EDGE_WORDS;
+            if length > best.2 && near_end && near_start {
+                best = (i + 1, j + 1, length);
+            }
+        }
+    }
+
+    let (end_a, end_b, length) = best;
+    // a short text repeated whole counts, it adds nothing either way
+    if length == 0 || length < MIN_OVERLAP_WORDS && length < b.len() {
+        return None;
+    }
+    let mut merged = previous[..previous_words[end_a - 1].1].to_string();
+    if let Some(&(start, _)) = next_words.get(end_b) {
+        // with the whitespace that followed the run, a space or a line break
+        merged.push_str(&next[next_words[end_b - 1].1..start]);
+        merged.push_str(&next[start..]);
+    }
+    Some(merged)
+}
@@ -0,0 +1,46 @@
+use exif::{In, Reader, Tag, Value};
+
+// ResolutionUnit of resolutions given per centimeter, they are per inch otherwise
+const PER_CENTIMETER: u16 = 3;
+
+/// What a photo or scan records about itself besides its pixels.
+#[derive(Clone, Default)]
+pub struct ImageMetadata {
+    /// Pixels per inch across and down, as the camera or scanner recorded them.
+    pub dpi: Option<(f32, f32)>,
+    /// When the photo was taken as the camera has it, e.g. `2024-05-01 12:30:00`.
+    pub taken: Option<String>,
+}
+
+fn rational(exif: &exif::Exif, tag: Tag) -> Option<f32> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(values) => values
+            .first()
+            .filter(|value| value.denom != 0)
+            .map(|value| value.to_f64() as f32),
+        _ => None,
+    }
+}
+
+/// The metadata in raw EXIF data, as [`image::ImageDecoder::exif_metadata`]
+/// hands it out. Whatever is missing or unreadable is left out.
+pub fn from_exif(data: &[u8]) -> ImageMetadata {
+    let Ok(exif) = Reader::new().read_raw(data.to_vec()) else {
+        return ImageMetadata::default();
+    };
+    let per_centimeter = exif
+        .get_field(Tag::ResolutionUnit, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        == Some(PER_CENTIMETER as u32);
+    let dpi = rational(&exif, Tag::XResolution)
+        .zip(rational(&exif, Tag::YResolution))
+        .map(|(x, y)| match per_centimeter {
+            true => (x * 2.54, y * 2.54),
+            false => (x, y),
+        });
+    let taken = [Tag::DateTimeOriginal, Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| exif.get_field(tag, In::PRIMARY))
+        .map(|field| field.display_value().to_string());
+    ImageMetadata { dpi, taken }
+}
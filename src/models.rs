@@ -0,0 +1,89 @@
+use crate::{
+    config::{Config, MODEL_DIR_VAR},
+    locale::tr,
+};
+use imgui::{Condition, Ui};
+use std::path::{Path, PathBuf};
+
+// Window asking for the OCR model files when they could not be loaded, instead
+// of quitting with nothing to transcribe with
+pub struct ModelsDialog {
+    error: String,
+}
+
+// ask for an rten model file
+pub fn pick_model() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("rten models", &["rten"])
+        .pick_file()
+}
+
+// the path of a model with a button to browse for another, true when clicked
+pub fn model_row(ui: &Ui, label: &str, path: &Path) -> bool {
+    ui.text(format!("{}: {}", label, path.display()));
+    if !path.exists() {
+        ui.same_line();
+        ui.text_colored([0.9, 0.3, 0.3, 1.0], tr("missing"));
+    }
+    ui.same_line();
+    ui.button(format!("{}##{}", tr("Browse..."), label))
+}
+
+impl ModelsDialog {
+    pub fn new(error: String) -> Self {
+        ModelsDialog { error }
+    }
+
+    // the models picked still did not load
+    pub fn failed(&mut self, error: String) {
+        self.error = error;
+    }
+
+    // returns true when the models should be loaded again, the picked files are
+    // kept in the config
+    pub fn draw(&mut self, ui: &Ui, config: &mut Config) -> bool {
+        let mut retry = false;
+        let [display_width, display_height] = ui.io().display_size;
+        ui.window(format!("{}###OCR models", tr("OCR models")))
+            .position(
+                [display_width / 2.0, display_height / 2.0],
+                Condition::FirstUseEver,
+            )
+            .position_pivot([0.5, 0.5])
+            .size([560.0, 0.0], Condition::FirstUseEver)
+            .collapsible(false)
+            .build(|| {
+                ui.text_wrapped(&self.error);
+                ui.separator();
+                ui.text_wrapped(format!(
+                    "Put the models in the working directory, set {} to the folder \
+                     holding them or pick the files here.",
+                    MODEL_DIR_VAR
+                ));
+
+                if model_row(ui, tr("Detection model"), &config.detection_model_path()) {
+                    if let Some(path) = pick_model() {
+                        config.detection_model = path;
+                        config.detection_override = None;
+                        retry = true;
+                    }
+                }
+                if let Some((model, _)) = config.recognition_model_path() {
+                    if model_row(ui, tr("Recognition model"), &model) {
+                        if let Some(path) = pick_model() {
+                            if let Some(language) = config.recognition_language_mut() {
+                                language.model = path;
+                            }
+                            config.recognition_override = None;
+                            retry = true;
+                        }
+                    }
+                }
+
+                if ui.button(tr("Retry")) {
+                    retry = true;
+                }
+            });
+        retry
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{locale::tr, masking::SecretGuard};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+// characters of the text shown in a notification, the rest is there to copy
+const PREVIEW_CHARS: usize = 200;
+
+// what one notification shows, and the whole text its Copy action copies
+struct Note {
+    summary: String,
+    body: String,
+    text: String,
+    copy: String,
+}
+
+// Desktop notifications of results read while the window is hidden, such as
+// in watch mode or from the hotkey, so background OCR does not go unnoticed.
+// The Copy action, where the desktop offers one, puts the whole text on the
+// clipboard through `copies`
+pub struct Notifier {
+    #[cfg(target_os = "linux")]
+    notes: Option<Sender<Note>>,
+    copies: (Sender<String>, Receiver<String>),
+    failures: (Sender<String>, Receiver<String>),
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Notifier {
+            #[cfg(target_os = "linux")]
+            notes: None,
+            copies: mpsc::channel(),
+            failures: mpsc::channel(),
+        }
+    }
+}
+
+impl Notifier {
+    // tell the user `text` was recognized, with the start of it masked the
+    // way `guard` copies it, as notifications stay on screen and in the
+    // history of the desktop
+    pub fn show(&mut self, text: &str, guard: &SecretGuard) {
+        let mut body: String = guard
+            .masked(text)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if body.chars().count() > PREVIEW_CHARS {
+            body = body.chars().take(PREVIEW_CHARS).collect::<String>() + "…";
+        }
+        let note = Note {
+            summary: tr("Text recognized").to_string(),
+            body,
+            text: text.to_string(),
+            copy: tr("Copy").to_string(),
+        };
+        self.send(note);
+    }
+
+    // the texts whose Copy action was clicked since the last call
+    pub fn copies(&self) -> Vec<String> {
+        self.copies.1.try_iter().collect()
+    }
+
+    // what went wrong showing notifications since the last call
+    pub fn failures(&self) -> Vec<String> {
+        self.failures.1.try_iter().collect()
+    }
+
+    // one connection to the notification server for all notifications, each
+    // replacing the one before so watch mode does not pile them up. Once the
+    // connection fails, which is reported, notifications are off until the
+    // next start rather than failing again for every result
+    #[cfg(target_os = "linux")]
+    fn send(&mut self, note: Note) {
+        match &self.notes {
+            Some(notes) => {
+                let _ = notes.send(note);
+            }
+            None => self.start(note),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn start(&mut self, note: Note) {
+        let (sender, notes) = mpsc::channel();
+        let _ = sender.send(note);
+        self.notes = Some(sender);
+        let (copies, failures) = (self.copies.0.clone(), self.failures.0.clone());
+        thread::spawn(move || {
+            if let Err(err) = freedesktop::run(notes, copies) {
+                let _ = failures.send(err.to_string());
+            }
+        });
+    }
+
+    // run the program that shows the notification off the UI thread, telling
+    // of it failing through `failures`
+    #[cfg(any(target_os = "macos", windows))]
+    fn spawn(&self, name: &'static str, mut command: std::process::Command) {
+        let failures = self.failures.0.clone();
+        thread::spawn(move || match command.status() {
+            Ok(status) if status.success() => (),
+            Ok(status) => {
+                let _ = failures.send(format!("{} exited with {}", name, status));
+            }
+            Err(err) => {
+                let _ = failures.send(format!("Failed to run {}: {}", name, err));
+            }
+        });
+    }
+
+    #[cfg(target_os = "macos")]
+    fn send(&mut self, note: Note) {
+        // AppleScript string literals escape quotes and backslashes
+        let quoted =
+            |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "display notification {} with title \"Transcribe Wizard\" subtitle {}",
+            quoted(&note.body),
+            quoted(&note.summary)
+        );
+        let mut command = std::process::Command::new("osascript");
+        command.args(["-e", &script]);
+        self.spawn("osascript", command);
+    }
+
+    // a toast through PowerShell, which has the application identity Windows
+    // asks of toasts, the texts go through the environment to need no quoting
+    #[cfg(windows)]
+    fn send(&mut self, note: Note) {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        const SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$toast = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$lines = $toast.GetElementsByTagName('text')
+$lines.Item(0).AppendChild($toast.CreateTextNode($env:TW_SUMMARY)) | Out-Null
+$lines.Item(1).AppendChild($toast.CreateTextNode($env:TW_BODY)) | Out-Null
+$app = '{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe'
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($app).Show([Windows.UI.Notifications.ToastNotification]::new($toast))
+"#;
+        let mut command = std::process::Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+            .env("TW_SUMMARY", &note.summary)
+            .env("TW_BODY", &note.body)
+            .creation_flags(CREATE_NO_WINDOW);
+        self.spawn("PowerShell", command);
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    fn send(&mut self, _note: Note) {
+        let _ = self
+            .failures
+            .0
+            .send("Notifications are not supported on this platform".into());
+    }
+}
+
+// notifications over D-Bus, as GNOME, KDE and the notification daemons of
+// other desktops take them
+#[cfg(target_os = "linux")]
+mod freedesktop {
+    use super::Note;
+    use std::{
+        collections::HashMap,
+        error::Error,
+        sync::{mpsc::Receiver, mpsc::Sender, Arc, Mutex},
+        thread,
+    };
+    use zbus::{
+        blocking::{Connection, Proxy},
+        zvariant::Value,
+    };
+
+    // the body of a notification is markup on most servers
+    fn escaped(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    // show every note that comes in until the window goes away
+    pub fn run(notes: Receiver<Note>, copies: Sender<String>) -> Result<(), Box<dyn Error>> {
+        let connection = Connection::session()?;
+        let server = Proxy::new(
+            &connection,
+            "org.freedesktop.Notifications",
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.Notifications",
+        )?;
+        // the notification shown last and its whole text
+        let shown = Arc::new(Mutex::new((0u32, String::new())));
+        let actions = server.receive_signal("ActionInvoked")?;
+        let clicked = shown.clone();
+        thread::spawn(move || {
+            for message in actions {
+                let Ok((id, action)) = message.body().deserialize::<(u32, String)>() else {
+                    continue;
+                };
+                let Ok(clicked) = clicked.lock() else {
+                    break;
+                };
+                if action == "copy" && id == clicked.0 && copies.send(clicked.1.clone()).is_err() {
+                    break;
+                }
+            }
+        });
+        for note in notes {
+            let replaces = shown.lock().map_or(0, |shown| shown.0);
+            let hints: HashMap<&str, Value> = HashMap::new();
+            let id: u32 = server.call(
+                "Notify",
+                &(
+                    "Transcribe Wizard",
+                    replaces,
+                    "",
+                    note.summary.as_str(),
+                    escaped(&note.body),
+                    vec!["copy", note.copy.as_str()],
+                    hints,
+                    -1i32,
+                ),
+            )?;
+            if let Ok(mut shown) = shown.lock() {
+                *shown = (id, note.text);
+            }
+        }
+        Ok(())
+    }
+}
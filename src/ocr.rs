@@ -0,0 +1,709 @@
+use crate::{
+    backend::OcrBackend, document, layout, orientation, preprocess::Preprocessing, TranscribeError,
+};
+use image::{imageops::FilterType, DynamicImage};
+use ocrs::{DecodeMethod, ImageSource, OcrEngine, OcrEngineParams, TextItem};
+use rten::Model;
+use rten_imageproc::{BoundingRect, Rect, RotatedRect};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+/// Tuning of the ocrs engine beyond its models.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineOptions {
+    /// Beam search width, 0 decodes greedily which is faster and rarely worse.
+    pub beam_width: u32,
+    /// Characters recognition may produce, the whole alphabet when empty.
+    pub allowed_chars: String,
+    /// Have the engine log what it does to stderr.
+    pub debug: bool,
+}
+
+/// Named sets of characters for [`EngineOptions::allowed_chars`], for inputs
+/// such as serial numbers and codes that only ever use a few.
+pub const CHARACTER_SETS: &[(&str, &str)] = &[
+    ("Digits", "0123456789"),
+    ("Numbers", "0123456789.,-+"),
+    ("Hexadecimal", "0123456789ABCDEFabcdef"),
+    ("License plates", "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-"),
+    ("Serial numbers", "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-/."),
+];
+
+/// Load the detection and recognition models into a new engine.
+///
+/// `alphabet` must match the recognition model, `None` is the alphabet of the
+/// stock ocrs models.
+pub fn load_engine(
+    detection_model: &Path,
+    recognition_model: &Path,
+    alphabet: Option<String>,
+    options: &EngineOptions,
+) -> Result<OcrEngine, TranscribeError> {
+    let load = |path: &Path, kind: &str| {
+        if !path.exists() {
+            return Err(TranscribeError::ModelMissing(path.to_path_buf()));
+        }
+        Model::load_file(path).map_err(|err| {
+            TranscribeError::Model(format!(
+                "Failed to load {} model {}: {}",
+                kind,
+                path.display(),
+                err
+            ))
+        })
+    };
+    let detection = load(detection_model, "detection")?;
+    let recognition = load(recognition_model, "recognition")?;
+    OcrEngine::new(OcrEngineParams {
+        detection_model: Some(detection),
+        recognition_model: Some(recognition),
+        alphabet,
+        debug: options.debug,
+        decode_method: match options.beam_width {
+            0 => DecodeMethod::Greedy,
+            width => DecodeMethod::BeamSearch { width },
+        },
+        allowed_chars: (!options.allowed_chars.is_empty()).then(|| options.allowed_chars.clone()),
+    })
+    .map_err(|err| TranscribeError::Model(format!("Failed to create OCR engine: {}", err)))
+}
+
+/// Axis aligned box in image pixel coordinates.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Bounds {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl From<Rect> for Bounds {
+    fn from(rect: Rect) -> Self {
+        Bounds {
+            left: rect.left(),
+            top: rect.top(),
+            right: rect.right(),
+            bottom: rect.bottom(),
+        }
+    }
+}
+
+/// Oriented box around an item, `angle` is the clockwise rotation in degrees.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct RotatedBounds {
+    pub center_x: f32,
+    pub center_y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub angle: f32,
+}
+
+impl Bounds {
+    fn scaled(self, x: f32, y: f32) -> Self {
+        Bounds {
+            left: (self.left as f32 * x).round() as i32,
+            top: (self.top as f32 * y).round() as i32,
+            right: (self.right as f32 * x).round() as i32,
+            bottom: (self.bottom as f32 * y).round() as i32,
+        }
+    }
+}
+
+impl From<RotatedRect> for RotatedBounds {
+    fn from(rect: RotatedRect) -> Self {
+        let center = rect.center();
+        let up = rect.up_axis();
+        RotatedBounds {
+            center_x: center.x,
+            center_y: center.y,
+            width: rect.width(),
+            height: rect.height(),
+            angle: up.x.atan2(-up.y).to_degrees(),
+        }
+    }
+}
+
+impl From<Bounds> for RotatedBounds {
+    fn from(bounds: Bounds) -> Self {
+        let width = (bounds.right - bounds.left) as f32;
+        let height = (bounds.bottom - bounds.top) as f32;
+        RotatedBounds {
+            center_x: bounds.left as f32 + width / 2.0,
+            center_y: bounds.top as f32 + height / 2.0,
+            width,
+            height,
+            angle: 0.0,
+        }
+    }
+}
+
+impl RotatedBounds {
+    fn scaled(self, x: f32, y: f32) -> Self {
+        RotatedBounds {
+            center_x: self.center_x * x,
+            center_y: self.center_y * y,
+            width: self.width * x,
+            height: self.height * y,
+            angle: self.angle,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub bounds: Bounds,
+    pub rotated_bounds: RotatedBounds,
+    /// In `0.0..=1.0`, `None` when the engine does not report one.
+    pub confidence: Option<f32>,
+    /// Other readings the engine considered, most likely first. Empty when the
+    /// engine does not report any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Line {
+    pub text: String,
+    pub bounds: Bounds,
+    pub rotated_bounds: RotatedBounds,
+    /// In `0.0..=1.0`, `None` when the engine does not report one.
+    pub confidence: Option<f32>,
+    pub words: Vec<Word>,
+}
+
+/// Everything the engine recognized in an image, lines are in reading order.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TranscriptionResult {
+    pub width: u32,
+    pub height: u32,
+    pub lines: Vec<Line>,
+    /// Name of the backend that read it, as [`OcrBackend::name`] has it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// How long reading it took, not saved with the result.
+    #[serde(skip)]
+    pub timings: Option<Timings>,
+    /// The image it was read from, for callers that keep the two together to
+    /// draw or save the result over it. Left empty here and not saved.
+    #[serde(skip)]
+    pub image: Option<Arc<DynamicImage>>,
+    /// Where the image came from, such as its file name, for naming what is
+    /// saved of the result. Left empty here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// How long the steps of reading an image took.
+#[derive(Clone, Copy, Default)]
+pub struct Timings {
+    /// Turning and cleaning up the image before recognition.
+    pub preprocessing: Duration,
+    /// Finding the words and lines, for engines that tell it apart from
+    /// reading them.
+    pub detection: Option<Duration>,
+    pub recognition: Option<Duration>,
+    /// The whole transcription, preprocessing included.
+    pub total: Duration,
+    /// The result came from the cache and was not read again.
+    pub cached: bool,
+}
+
+/// The average of the confidences there are, `None` when there are none.
+pub fn average_confidence(confidences: impl IntoIterator<Item = Option<f32>>) -> Option<f32> {
+    let confidences: Vec<f32> = confidences.into_iter().flatten().collect();
+    (!confidences.is_empty()).then(|| confidences.iter().sum::<f32>() / confidences.len() as f32)
+}
+
+impl Line {
+    /// Average confidence of the words, `None` when the engine reports none.
+    pub fn word_confidence(&self) -> Option<f32> {
+        average_confidence(self.words.iter().map(|word| word.confidence))
+    }
+}
+
+impl TranscriptionResult {
+    /// The recognized lines flattened into a single string.
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    /// Average confidence of the words, `None` when the engine reports none.
+    pub fn confidence(&self) -> Option<f32> {
+        average_confidence(
+            self.lines
+                .iter()
+                .flat_map(|line| &line.words)
+                .map(|word| word.confidence),
+        )
+    }
+
+    /// Map every box onto an image of a different size.
+    pub fn rescale(&mut self, width: u32, height: u32) {
+        let x = width as f32 / self.width as f32;
+        let y = height as f32 / self.height as f32;
+        for line in &mut self.lines {
+            line.bounds = line.bounds.scaled(x, y);
+            line.rotated_bounds = line.rotated_bounds.scaled(x, y);
+            for word in &mut line.words {
+                word.bounds = word.bounds.scaled(x, y);
+                word.rotated_bounds = word.rotated_bounds.scaled(x, y);
+            }
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Map every box onto an image of `width` by `height` through `point`,
+    /// which takes a point of this image to where it is on the other one.
+    /// Boxes stay boxes, for mappings that bend straight lines too.
+    pub fn map_points(&mut self, width: u32, height: u32, point: impl Fn(f32, f32) -> (f32, f32)) {
+        let map = |bounds: Bounds| {
+            let corners = [
+                (bounds.left, bounds.top),
+                (bounds.right, bounds.top),
+                (bounds.right, bounds.bottom),
+                (bounds.left, bounds.bottom),
+            ]
+            .map(|(x, y)| point(x as f32, y as f32));
+            let along = |axis: fn(&(f32, f32)) -> f32| {
+                let values = corners.iter().map(axis);
+                let min = values.clone().fold(f32::INFINITY, f32::min);
+                (min, values.fold(f32::NEG_INFINITY, f32::max))
+            };
+            let (left, right) = along(|corner| corner.0);
+            let (top, bottom) = along(|corner| corner.1);
+            Bounds {
+                left: left.floor() as i32,
+                top: top.floor() as i32,
+                right: right.ceil() as i32,
+                bottom: bottom.ceil() as i32,
+            }
+        };
+        // the center and the middles of two sides, which also give the new angle
+        let map_rotated = |rotated: RotatedBounds| {
+            let (sin, cos) = rotated.angle.to_radians().sin_cos();
+            let (x, y) = (rotated.center_x, rotated.center_y);
+            let center = point(x, y);
+            let right = point(x + cos * rotated.width / 2.0, y + sin * rotated.width / 2.0);
+            let down = point(
+                x - sin * rotated.height / 2.0,
+                y + cos * rotated.height / 2.0,
+            );
+            RotatedBounds {
+                center_x: center.0,
+                center_y: center.1,
+                width: 2.0 * (right.0 - center.0).hypot(right.1 - center.1),
+                height: 2.0 * (down.0 - center.0).hypot(down.1 - center.1),
+                angle: (right.1 - center.1).atan2(right.0 - center.0).to_degrees(),
+            }
+        };
+        for line in &mut self.lines {
+            line.bounds = map(line.bounds);
+            line.rotated_bounds = map_rotated(line.rotated_bounds);
+            for word in &mut line.words {
+                word.bounds = map(word.bounds);
+                word.rotated_bounds = map_rotated(word.rotated_bounds);
+            }
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Map every box onto the image turned a quarter clockwise.
+    pub fn turn_clockwise(&mut self) {
+        let height = self.height as i32;
+        let turn = |bounds: Bounds| Bounds {
+            left: height - bounds.bottom,
+            top: bounds.left,
+            right: height - bounds.top,
+            bottom: bounds.right,
+        };
+        let turn_rotated = |rotated: RotatedBounds| RotatedBounds {
+            center_x: height as f32 - rotated.center_y,
+            center_y: rotated.center_x,
+            angle: rotated.angle + 90.0,
+            ..rotated
+        };
+        for line in &mut self.lines {
+            line.bounds = turn(line.bounds);
+            line.rotated_bounds = turn_rotated(line.rotated_bounds);
+            for word in &mut line.words {
+                word.bounds = turn(word.bounds);
+                word.rotated_bounds = turn_rotated(word.rotated_bounds);
+            }
+        }
+        std::mem::swap(&mut self.width, &mut self.height);
+    }
+}
+
+/// Lines recognized at a time by [`transcribe_streaming`], few enough that the
+/// first show up quickly and enough to keep recognition batched.
+const STREAMED_LINES: usize = 12;
+
+/// How far a streamed transcription got, see [`OcrBackend::transcribe_streaming`].
+pub enum Progress {
+    /// Where the lines are, found before any of them is read. The lines have no
+    /// text or words yet.
+    Detected(TranscriptionResult),
+    /// The lines read since the last update, in reading order. Only those, so
+    /// that a large image is not copied over and over.
+    Read(TranscriptionResult),
+}
+
+impl Progress {
+    fn result_mut(&mut self) -> &mut TranscriptionResult {
+        match self {
+            Progress::Detected(result) | Progress::Read(result) => result,
+        }
+    }
+}
+
+// the lines recognized, what is noise among them is for a filter::LineFilter
+// to tell
+fn recognized_lines(line_texts: Vec<Option<ocrs::TextLine>>) -> impl Iterator<Item = Line> {
+    line_texts.into_iter().flatten().map(|line| Line {
+        text: line.to_string(),
+        bounds: line.bounding_rect().into(),
+        rotated_bounds: line.rotated_rect().into(),
+        confidence: None,
+        words: line
+            .words()
+            .map(|word| Word {
+                text: word.to_string(),
+                bounds: word.bounding_rect().into(),
+                rotated_bounds: word.rotated_rect().into(),
+                confidence: None,
+                alternatives: Vec::new(),
+            })
+            .collect(),
+    })
+}
+
+/// Detect and recognize the text in an image.
+pub fn transcribe(
+    engine: &OcrEngine,
+    image: &DynamicImage,
+) -> Result<TranscriptionResult, Box<dyn Error>> {
+    let start = Instant::now();
+    let image_rgb = image.to_rgb8();
+    let image_source = ImageSource::from_bytes(image_rgb.as_raw(), image_rgb.dimensions())?;
+    let ocr_input = engine.prepare_input(image_source)?;
+    let word_rects = engine.detect_words(&ocr_input)?;
+    let line_rects = engine.find_text_lines(&ocr_input, &word_rects);
+    let detection = start.elapsed();
+    let line_texts = engine.recognize_text(&ocr_input, &line_rects)?;
+
+    Ok(TranscriptionResult {
+        width: image_rgb.width(),
+        height: image_rgb.height(),
+        lines: recognized_lines(line_texts).collect(),
+        backend: None,
+        timings: Some(Timings {
+            detection: Some(detection),
+            recognition: Some(start.elapsed() - detection),
+            ..Timings::default()
+        }),
+        image: None,
+        source: None,
+    })
+}
+
+/// Like [`transcribe`], telling `progress` where the lines are once they are
+/// found and then every few lines the ones just read.
+pub fn transcribe_streaming(
+    engine: &OcrEngine,
+    image: &DynamicImage,
+    progress: &mut dyn FnMut(Progress),
+) -> Result<TranscriptionResult, Box<dyn Error>> {
+    let start = Instant::now();
+    let image_rgb = image.to_rgb8();
+    let image_source = ImageSource::from_bytes(image_rgb.as_raw(), image_rgb.dimensions())?;
+    let ocr_input = engine.prepare_input(image_source)?;
+    let word_rects = engine.detect_words(&ocr_input)?;
+    let line_rects = engine.find_text_lines(&ocr_input, &word_rects);
+    let detection = start.elapsed();
+
+    let mut result = TranscriptionResult {
+        width: image_rgb.width(),
+        height: image_rgb.height(),
+        lines: Vec::new(),
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    };
+    let detected = line_rects
+        .iter()
+        .filter_map(|words| {
+            let bounds = words
+                .iter()
+                .map(|word| word.bounding_rect())
+                .reduce(|union, rect| union.union(rect))?;
+            let bounds = Bounds::from(bounds.integral_bounding_rect());
+            Some(Line {
+                text: String::new(),
+                bounds,
+                rotated_bounds: bounds.into(),
+                confidence: None,
+                words: Vec::new(),
+            })
+        })
+        .collect();
+    progress(Progress::Detected(TranscriptionResult {
+        lines: detected,
+        ..result.clone()
+    }));
+    for chunk in line_rects.chunks(STREAMED_LINES) {
+        let line_texts = engine.recognize_text(&ocr_input, chunk)?;
+        let read: Vec<Line> = recognized_lines(line_texts).collect();
+        progress(Progress::Read(TranscriptionResult {
+            width: result.width,
+            height: result.height,
+            lines: read.clone(),
+            backend: None,
+            timings: None,
+            image: None,
+            source: None,
+        }));
+        result.lines.extend(read);
+    }
+    result.timings = Some(Timings {
+        detection: Some(detection),
+        recognition: Some(start.elapsed() - detection),
+        ..Timings::default()
+    });
+    Ok(result)
+}
+
+/// Lines lower than this many pixels, going by the middle one, are too small
+/// for the engine to read well.
+pub const MIN_TEXT_HEIGHT: f32 = 16.0;
+
+// about the height the engine reads text at its best
+const TARGET_TEXT_HEIGHT: f32 = 32.0;
+
+/// How many times larger the image of `result` should be read again for its
+/// text to be high enough, `None` when the text is high enough already.
+pub fn upscale_factor(result: &TranscriptionResult) -> Option<u32> {
+    let mut heights: Vec<f32> = result
+        .lines
+        .iter()
+        .map(|line| line.rotated_bounds.height)
+        .collect();
+    heights.sort_by(f32::total_cmp);
+    let median = *heights.get(heights.len() / 2)?;
+    (median > 0.0 && median < MIN_TEXT_HEIGHT)
+        .then(|| (TARGET_TEXT_HEIGHT / median).ceil().clamp(2.0, 3.0) as u32)
+}
+
+/// Transcribe an image with any backend, cleaning the image up first and
+/// putting the lines of several columns in reading order after when asked to.
+///
+/// Vertical text is read by turning the image a quarter counter-clockwise, so
+/// that its columns become lines in reading order, unless the backend reads
+/// vertical text by itself.
+///
+/// Boxes are mapped back onto `image`, also after turning it upright or
+/// flattening the page in it, except that after deskewing they are relative
+/// to the straightened image.
+pub fn transcribe_preprocessed(
+    backend: &dyn OcrBackend,
+    image: &DynamicImage,
+    preprocessing: &Preprocessing,
+) -> Result<TranscriptionResult, Box<dyn Error>> {
+    transcribe_preprocessed_streaming(backend, image, preprocessing, &mut |_| ())
+}
+
+/// Like [`transcribe_preprocessed`], with the [`Progress`] of backends that
+/// stream it mapped onto `image` the way the result is.
+pub fn transcribe_preprocessed_streaming(
+    backend: &dyn OcrBackend,
+    image: &DynamicImage,
+    preprocessing: &Preprocessing,
+    progress: &mut dyn FnMut(Progress),
+) -> Result<TranscriptionResult, Box<dyn Error>> {
+    let start = Instant::now();
+    // cloud engines would bill every guess, they are left to read it as it is
+    let quarters = match backend.local() {
+        Some(local) if preprocessing.auto_rotate => orientation::detect(local, image)?,
+        _ => 0,
+    };
+    let upright;
+    let image = if quarters != 0 {
+        info!("Turning image {} degrees to make it upright", quarters * 90);
+        upright = orientation::rotate(image, quarters);
+        &upright
+    } else {
+        image
+    };
+    let vertical = preprocessing.vertical && !backend.reads_vertical();
+    let sideways;
+    let image = if vertical {
+        sideways = orientation::rotate(image, 3);
+        &sideways
+    } else {
+        image
+    };
+    // the page is flattened here rather than with the other steps, so that
+    // the boxes can be mapped back off it
+    let page = match preprocessing.flatten_page {
+        true => document::find_page(image)
+            .and_then(|corners| Some((document::flatten(image, corners)?, corners))),
+        false => None,
+    };
+    let flat = page.as_ref().map_or(image, |(flat, _)| flat);
+    let preprocessing = &Preprocessing {
+        flatten_page: false,
+        ..preprocessing.clone()
+    };
+    // what is done to the result below, for the progress as much as the result
+    let map = |result: &mut TranscriptionResult| {
+        if preprocessing.is_enabled() {
+            result.rescale(flat.width(), flat.height());
+        }
+        if preprocessing.columns {
+            layout::reading_order(result);
+        }
+        if let Some((_, corners)) = page {
+            document::unflatten(result, corners, image.width(), image.height());
+        }
+        if vertical {
+            result.turn_clockwise();
+        }
+        // three quarters clockwise undo one
+        for _ in 0..quarters * 3 % 4 {
+            result.turn_clockwise();
+        }
+    };
+    let mut mapped = |mut update: Progress| {
+        map(update.result_mut());
+        progress(update);
+    };
+    let processed;
+    let read = if preprocessing.is_enabled() {
+        processed = preprocessing.apply(flat);
+        &processed
+    } else {
+        flat
+    };
+    let preprocessed = start.elapsed();
+    let mut result = backend.transcribe_streaming(read, &mut mapped)?;
+    if preprocessing.auto_upscale {
+        if let Some(factor) = upscale_factor(&result) {
+            info!("Text is small, reading the image again at {}x", factor);
+            let larger = read.resize_exact(
+                read.width() * factor,
+                read.height() * factor,
+                FilterType::Lanczos3,
+            );
+            let mut larger_result = backend.transcribe(&larger)?;
+            larger_result.rescale(read.width(), read.height());
+            if !larger_result.lines.is_empty() {
+                result = larger_result;
+            }
+        }
+    }
+    map(&mut result);
+    let timings = result.timings.get_or_insert_with(Timings::default);
+    timings.preprocessing = preprocessed;
+    timings.total = start.elapsed();
+    let name = result
+        .backend
+        .get_or_insert_with(|| backend.name().to_string());
+    info!(
+        "{} transcribed {}x{} image into {} lines in {} ms",
+        name,
+        image.width(),
+        image.height(),
+        result.lines.len(),
+        start.elapsed().as_millis()
+    );
+    Ok(result)
+}
+
+// Words, lines and results for the tests of the modules that work on them,
+// with nothing known about them the tests don't set
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::{Bounds, Line, TranscriptionResult, Word};
+
+    pub fn bounds(left: i32, top: i32, right: i32, bottom: i32) -> Bounds {
+        Bounds {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    // a word of `text` over `bounds`, with no confidence or other readings
+    pub fn word(text: &str, bounds: Bounds) -> Word {
+        Word {
+            text: text.to_string(),
+            bounds,
+            rotated_bounds: bounds.into(),
+            confidence: None,
+            alternatives: Vec::new(),
+        }
+    }
+
+    // a line of `text` over `bounds`, for engines that only read lines
+    pub fn line(text: &str, bounds: Bounds) -> Line {
+        Line {
+            text: text.to_string(),
+            bounds,
+            rotated_bounds: bounds.into(),
+            confidence: None,
+            words: Vec::new(),
+        }
+    }
+
+    // a line of `words`, its text and box the ones of the words together
+    pub fn line_of(words: Vec<Word>) -> Line {
+        let text = words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let bounds = words
+            .iter()
+            .map(|word| word.bounds)
+            .reduce(|a, b| {
+                bounds(
+                    a.left.min(b.left),
+                    a.top.min(b.top),
+                    a.right.max(b.right),
+                    a.bottom.max(b.bottom),
+                )
+            })
+            .unwrap_or(bounds(0, 0, 0, 0));
+        Line {
+            words,
+            ..line(&text, bounds)
+        }
+    }
+
+    pub fn transcription(width: u32, height: u32, lines: Vec<Line>) -> TranscriptionResult {
+        TranscriptionResult {
+            width,
+            height,
+            lines,
+            backend: None,
+            timings: None,
+            image: None,
+            source: None,
+        }
+    }
+}
@@ -0,0 +1,460 @@
+use crate::{
+    config::Config,
+    locale::{self, tr, UiLanguage},
+    models::{model_row, pick_model},
+    theme::Theme,
+};
+use ab_glyph::{FontRef, PxScale};
+use global_hotkey::hotkey::HotKey;
+use image::{DynamicImage, Rgb, RgbImage};
+use imageproc::drawing::draw_text_mut;
+use imgui::{Condition, Ui};
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+use transcribewizard::{evaluate, pool::BackendFactory, update};
+
+// the text of the sample image the last step reads
+const SAMPLE_LINES: [&str; 3] = [
+    "Transcribe Wizard reads the text",
+    "of screenshots, photos and scans.",
+    "The quick brown fox jumps 12 times.",
+];
+const SAMPLE_SIZE: f32 = 36.0;
+
+const STEPS: [&str; 4] = ["Language and theme", "Models", "Hotkey", "Test"];
+
+// where the missing detection and recognition models were downloaded to
+type Downloaded = (Option<PathBuf>, Option<PathBuf>);
+
+// what reading the sample image gave, the text and how long it took
+type Tested = Result<(String, Duration), String>;
+
+// the first job of `job` to be done, None while it still runs
+fn finished<T>(job: &Option<Receiver<Result<T, String>>>) -> Option<Result<T, String>> {
+    match job.as_ref()?.try_recv() {
+        Ok(result) => Some(result),
+        Err(TryRecvError::Empty) => None,
+        Err(TryRecvError::Disconnected) => Some(Err("worker stopped".into())),
+    }
+}
+
+// an image of the sample text drawn with the bundled font, black on white as
+// a clean screenshot has it
+pub fn sample_image() -> DynamicImage {
+    let font = FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)
+        .expect("the bundled font is valid");
+    let line_height = (SAMPLE_SIZE * 1.5) as i32;
+    let mut canvas = RgbImage::from_pixel(
+        720,
+        (line_height * SAMPLE_LINES.len() as i32 + line_height) as u32,
+        Rgb([255, 255, 255]),
+    );
+    for (index, line) in SAMPLE_LINES.iter().enumerate() {
+        draw_text_mut(
+            &mut canvas,
+            Rgb([0, 0, 0]),
+            24,
+            line_height / 2 + index as i32 * line_height,
+            PxScale::from(SAMPLE_SIZE),
+            &font,
+            line,
+        );
+    }
+    DynamicImage::ImageRgb8(canvas)
+}
+
+pub enum OnboardingAction {
+    // the theme changed, the style is to be built again
+    Appearance,
+    // the model files are in place, to be loaded on a worker handed over with `load`
+    ReloadModels,
+    // the models loaded, the backend is to be made with this
+    Loaded(BackendFactory),
+    // the hotkey in the config is to be registered in place of the old one
+    Hotkey,
+    // the sample image is to be read on a worker handed over with `test`
+    Test,
+    // the wizard was finished or skipped, the config is to be saved
+    Finish,
+}
+
+// Window walking through the setup on the first start, in place of the models
+// dialog: the interface language and theme, getting the model files, the
+// hotkey and reading a sample image to see it all works. It can be run again
+// from the settings
+pub struct Onboarding {
+    step: usize,
+    // why the models did not load, None once they did
+    models_error: Option<String>,
+    // the models downloaded once it is done
+    download: Option<Receiver<Result<Downloaded, String>>>,
+    download_error: Option<String>,
+    // the models loading on a worker
+    loading: Option<Receiver<Result<BackendFactory, String>>>,
+    // the hotkey as typed, whether it is valid and whether it was registered
+    hotkey: String,
+    hotkey_error: Option<String>,
+    registered: Option<bool>,
+    // the sample image being read on a worker, and what reading it gave
+    testing: Option<Receiver<Tested>>,
+    test: Option<Tested>,
+}
+
+impl Onboarding {
+    pub fn new(config: &Config, models_error: Option<String>) -> Self {
+        Onboarding {
+            step: 0,
+            models_error,
+            download: None,
+            download_error: None,
+            loading: None,
+            hotkey: config.hotkey.clone(),
+            hotkey_error: None,
+            registered: None,
+            testing: None,
+            test: None,
+        }
+    }
+
+    // load the models with `job` after a ReloadModels
+    pub fn load(&mut self, job: Receiver<Result<BackendFactory, String>>) {
+        self.loading = Some(job);
+    }
+
+    // how making the backend went after a Loaded, or loading it after a
+    // ReloadModels that needed no worker
+    pub fn models_loaded(&mut self, result: Result<(), String>) {
+        self.models_error = result.err();
+        self.test = None;
+    }
+
+    // why the models are still not loaded, for the models dialog once the
+    // wizard is done
+    pub fn models_error(&self) -> Option<String> {
+        self.models_error.clone()
+    }
+
+    // whether the hotkey could be registered after a Hotkey
+    pub fn hotkey_registered(&mut self, registered: bool) {
+        self.registered = Some(registered);
+    }
+
+    // read the sample image with `job` after a Test
+    pub fn test(&mut self, job: Receiver<Tested>) {
+        self.testing = Some(job);
+        self.test = None;
+    }
+
+    // download the missing detection and recognition models on a worker
+    // thread, from where ocrs publishes them to the models directory of the
+    // user, as the working directory may be anywhere
+    fn download(&mut self, detection: Option<PathBuf>, recognition: Option<PathBuf>) {
+        let (sender, job) = mpsc::channel();
+        thread::spawn(move || {
+            let fetch = |missing: Option<PathBuf>| -> Result<Option<PathBuf>, String> {
+                let Some(missing) = missing else {
+                    return Ok(None);
+                };
+                let name = missing.file_name().unwrap_or_default().to_string_lossy();
+                let path = Config::models_dir()
+                    .ok_or("No data directory to download to")?
+                    .join(name.as_ref());
+                update::download_ocrs_model(&name, &path).map_err(|err| err.to_string())?;
+                Ok(Some(path))
+            };
+            let _ = sender.send(fetch(detection).and_then(|detection| {
+                fetch(recognition).map(|recognition| (detection, recognition))
+            }));
+        });
+        self.download = Some(job);
+        self.download_error = None;
+    }
+
+    // pick up the finished jobs, the downloaded models go in the config and
+    // are loaded, the loaded ones make the backend
+    fn poll(&mut self, config: &mut Config) -> Option<OnboardingAction> {
+        if let Some(result) = finished(&self.testing) {
+            self.testing = None;
+            self.test = Some(result);
+        }
+        if let Some(result) = finished(&self.loading) {
+            self.loading = None;
+            match result {
+                Ok(factory) => return Some(OnboardingAction::Loaded(factory)),
+                Err(err) => self.models_error = Some(err),
+            }
+        }
+        let result = finished(&self.download)?;
+        self.download = None;
+        match result {
+            Ok((detection, recognition)) => {
+                if let Some(path) = detection {
+                    config.detection_model = path;
+                    config.detection_override = None;
+                }
+                if let Some(path) = recognition {
+                    if let Some(language) = config.recognition_language_mut() {
+                        language.model = path;
+                    }
+                    config.recognition_override = None;
+                }
+                Some(OnboardingAction::ReloadModels)
+            }
+            Err(err) => {
+                self.download_error = Some(err);
+                None
+            }
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, config: &mut Config) -> Option<OnboardingAction> {
+        let mut action = self.poll(config);
+        let mut open = true;
+        let [display_width, display_height] = ui.io().display_size;
+        ui.window(format!(
+            "{}###Onboarding",
+            tr("Welcome to Transcribe Wizard")
+        ))
+        .position(
+            [display_width / 2.0, display_height / 2.0],
+            Condition::FirstUseEver,
+        )
+        .position_pivot([0.5, 0.5])
+        .size([560.0, 0.0], Condition::FirstUseEver)
+        .collapsible(false)
+        .opened(&mut open)
+        .build(|| {
+            ui.text_disabled(format!(
+                "{} {}/{}: {}",
+                tr("Step"),
+                self.step + 1,
+                STEPS.len(),
+                tr(STEPS[self.step])
+            ));
+            ui.separator();
+            match self.step {
+                0 => {
+                    if self.draw_appearance(ui, config) {
+                        action = Some(OnboardingAction::Appearance);
+                    }
+                }
+                1 => {
+                    if self.draw_models(ui, config) {
+                        action = Some(OnboardingAction::ReloadModels);
+                    }
+                }
+                2 => {
+                    if self.draw_hotkey(ui, config) {
+                        action = Some(OnboardingAction::Hotkey);
+                    }
+                }
+                _ => {
+                    if self.draw_test(ui) {
+                        action = Some(OnboardingAction::Test);
+                    }
+                }
+            }
+
+            ui.separator();
+            if self.step > 0 && ui.button(tr("Back")) {
+                self.step -= 1;
+            }
+            ui.same_line();
+            if self.step + 1 < STEPS.len() {
+                if ui.button(tr("Next")) {
+                    self.step += 1;
+                }
+                ui.same_line();
+                if ui.button(tr("Skip setup")) {
+                    action = Some(OnboardingAction::Finish);
+                }
+            } else if ui.button(tr("Finish")) {
+                action = Some(OnboardingAction::Finish);
+            }
+        });
+        if !open {
+            action = Some(OnboardingAction::Finish);
+        }
+        action
+    }
+
+    // whether the theme changed
+    fn draw_appearance(&mut self, ui: &Ui, config: &mut Config) -> bool {
+        ui.text_wrapped(tr(
+            "Pick the language of the interface and how it looks, both can be changed later under Appearance in Settings.",
+        ));
+        let mut language_index = UiLanguage::ALL
+            .iter()
+            .position(|language| *language == config.ui_language)
+            .unwrap_or(0);
+        let language_labels = UiLanguage::ALL.map(UiLanguage::label);
+        if ui.combo_simple_string(
+            tr("Interface language"),
+            &mut language_index,
+            &language_labels,
+        ) {
+            config.ui_language = UiLanguage::ALL[language_index];
+            locale::set(config.ui_language);
+        }
+        let mut theme_index = Theme::ALL
+            .iter()
+            .position(|theme| *theme == config.theme)
+            .unwrap_or(0);
+        let theme_labels = Theme::ALL.map(Theme::label);
+        let changed = ui.combo_simple_string(tr("Theme"), &mut theme_index, &theme_labels);
+        config.theme = Theme::ALL[theme_index];
+        changed
+    }
+
+    // whether the models are to be loaded again
+    fn draw_models(&mut self, ui: &Ui, config: &mut Config) -> bool {
+        let mut reload = false;
+        ui.text_wrapped(tr(
+            "Transcribe Wizard needs a detection and a recognition model to read text. They can be downloaded from the releases on GitHub or picked from disk.",
+        ));
+        let detection = config.detection_model_path();
+        let recognition = config.recognition_model_path().map(|(model, _)| model);
+        if model_row(ui, tr("Detection model"), &detection) {
+            if let Some(path) = pick_model() {
+                config.detection_model = path;
+                config.detection_override = None;
+                reload = true;
+            }
+        }
+        if let Some(model) = &recognition {
+            if model_row(ui, tr("Recognition model"), model) {
+                if let Some(path) = pick_model() {
+                    if let Some(language) = config.recognition_language_mut() {
+                        language.model = path;
+                    }
+                    config.recognition_override = None;
+                    reload = true;
+                }
+            }
+        }
+
+        let detection = Some(detection).filter(|path| !path.exists());
+        let recognition = recognition.filter(|path| !path.exists());
+        let missing = detection.iter().chain(&recognition).count();
+        if missing > 0 {
+            let _disabled = ui.begin_disabled(self.download.is_some());
+            if ui.button(format!("{} ({})", tr("Download models"), missing)) {
+                self.download(detection, recognition);
+            }
+        }
+        if self.download.is_some() {
+            ui.same_line();
+            ui.text_disabled(tr("Downloading..."));
+        }
+        if let Some(err) = &self.download_error {
+            ui.text_colored(
+                [0.9, 0.3, 0.3, 1.0],
+                format!("{}: {}", tr("Error downloading models"), err),
+            );
+        }
+        match &self.models_error {
+            _ if self.loading.is_some() => ui.text_disabled(tr("Loading models...")),
+            None => ui.text_colored([0.3, 0.8, 0.3, 1.0], tr("The models are loaded")),
+            Some(err) => {
+                ui.text_wrapped(err);
+                if ui.button(tr("Retry")) {
+                    reload = true;
+                }
+            }
+        }
+        reload && self.loading.is_none()
+    }
+
+    // whether the hotkey is to be registered again
+    fn draw_hotkey(&mut self, ui: &Ui, config: &mut Config) -> bool {
+        ui.text_wrapped(tr(
+            "Pressed anywhere, the hotkey transcribes the image on the clipboard and puts the text back in its place, without switching to this window.",
+        ));
+        ui.input_text(tr("Hotkey"), &mut self.hotkey)
+            .hint("Ctrl+Shift+O")
+            .build();
+        let mut apply = ui.is_item_deactivated_after_edit();
+        ui.same_line();
+        apply |= ui.button(tr("Apply"));
+        if apply && self.hotkey.trim() != config.hotkey {
+            match self.hotkey.trim().parse::<HotKey>() {
+                Ok(_) => {
+                    config.hotkey = self.hotkey.trim().to_string();
+                    self.hotkey_error = None;
+                    self.registered = None;
+                    return true;
+                }
+                Err(err) => self.hotkey_error = Some(err.to_string()),
+            }
+        }
+        if let Some(err) = &self.hotkey_error {
+            ui.text_colored(
+                [0.9, 0.3, 0.3, 1.0],
+                format!("{}: {}", tr("Not a valid shortcut"), err),
+            );
+        } else if self.registered == Some(false) {
+            ui.text_colored(
+                [0.9, 0.3, 0.3, 1.0],
+                tr("The hotkey could not be registered, another app may be using it"),
+            );
+        } else if self.registered == Some(true) {
+            ui.text_colored([0.3, 0.8, 0.3, 1.0], tr("The hotkey is ready"));
+        }
+        false
+    }
+
+    // whether the sample image is to be read
+    fn draw_test(&mut self, ui: &Ui) -> bool {
+        ui.text_wrapped(tr(
+            "Read a sample image to check that recognition works. It shows this text:",
+        ));
+        for line in SAMPLE_LINES {
+            ui.text_disabled(line);
+        }
+        let ready = self.models_error.is_none() && self.loading.is_none();
+        let run = {
+            let _disabled = ui.begin_disabled(!ready || self.testing.is_some());
+            ui.button(tr("Run test"))
+        };
+        if !ready {
+            ui.same_line();
+            ui.text_disabled(tr("The models are not loaded yet"));
+        }
+        match &self.test {
+            None if self.testing.is_some() => ui.text_disabled(tr("Reading...")),
+            None => {}
+            Some(Ok((text, latency))) => {
+                let sample = evaluate::score(
+                    PathBuf::from("sample"),
+                    *latency,
+                    text,
+                    &SAMPLE_LINES.join("\n"),
+                );
+                let accuracy = (1.0 - sample.cer()).max(0.0) * 100.0;
+                let color = match accuracy >= 90.0 {
+                    true => [0.3, 0.8, 0.3, 1.0],
+                    false => [0.9, 0.6, 0.2, 1.0],
+                };
+                ui.text_colored(
+                    color,
+                    format!(
+                        "{}: {:.0}%, {} ms",
+                        tr("Accuracy"),
+                        accuracy,
+                        latency.as_millis()
+                    ),
+                );
+                ui.text_wrapped(text);
+            }
+            Some(Err(err)) => ui.text_colored(
+                [0.9, 0.3, 0.3, 1.0],
+                format!("{}: {}", tr("Error reading the sample"), err),
+            ),
+        }
+        run
+    }
+}
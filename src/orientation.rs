@@ -0,0 +1,68 @@
+use crate::backend::OcrBackend;
+use image::{imageops::FilterType, DynamicImage};
+use std::{borrow::Cow, error::Error};
+use tracing::debug;
+
+/// Longest side of the copies recognized while guessing the orientation.
+const PROBE_SIZE: u32 = 1024;
+
+/// Turn an image clockwise by `quarters` quarter turns.
+pub fn rotate(image: &DynamicImage, quarters: u32) -> DynamicImage {
+    match quarters % 4 {
+        1 => image.rotate90(),
+        2 => image.rotate180(),
+        3 => image.rotate270(),
+        _ => image.clone(),
+    }
+}
+
+/// Quarter turns clockwise that make the text in `image` upright.
+///
+/// Every orientation is transcribed with `backend`, best a local engine
+/// without a cache, and the one producing the most text that looks like words
+/// wins, sideways or upside down text comes out as scattered symbols. Costs
+/// four transcriptions of a smaller copy.
+pub fn detect(backend: &dyn OcrBackend, image: &DynamicImage) -> Result<u32, Box<dyn Error>> {
+    let probe = if image.width().max(image.height()) > PROBE_SIZE {
+        Cow::Owned(image.resize(PROBE_SIZE, PROBE_SIZE, FilterType::Triangle))
+    } else {
+        Cow::Borrowed(image)
+    };
+    let mut best = (0, 0.0);
+    for quarters in 0..4 {
+        let result = match quarters {
+            0 => backend.transcribe(&probe)?,
+            _ => backend.transcribe(&rotate(&probe, quarters))?,
+        };
+        let score: f32 = result
+            .lines
+            .iter()
+            .flat_map(|line| &line.words)
+            .filter(|word| looks_like_word(&word.text))
+            .map(|word| word.text.chars().count() as f32 * word.confidence.unwrap_or(1.0))
+            .sum();
+        debug!("{} quarter turns score {}", quarters, score);
+        if score > best.1 {
+            best = (quarters, score);
+        }
+    }
+    Ok(best.0)
+}
+
+// letters with at most a leading capital or a number, optionally followed by
+// punctuation, what misread text rarely manages
+fn looks_like_word(text: &str) -> bool {
+    let text = text.trim_end_matches(|c: char| ".,;:!?)\"'".contains(c));
+    let text = text.trim_start_matches(|c: char| "(\"'".contains(c));
+    let mut chars = text.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    let rest = chars.as_str();
+    if first.is_numeric() {
+        return rest.chars().all(|c| c.is_numeric() || ".,:".contains(c));
+    }
+    first.is_alphabetic()
+        && !rest.is_empty()
+        && (rest.chars().all(char::is_lowercase) || rest.chars().all(char::is_uppercase))
+}
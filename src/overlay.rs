@@ -0,0 +1,254 @@
+use crate::{
+    capture::{self, Area, Shot},
+    texture::Texture,
+};
+use image::DynamicImage;
+use imgui::{Condition, ImColor32, Key, MouseButton, StyleVar, Ui, WindowFlags};
+use imgui_glow_renderer::AutoRenderer;
+
+// regions smaller than this many pixels either way count as a click, not a selection
+const MIN_SELECTION: f32 = 4.0;
+
+// desktop pixels an arrow key moves or resizes the selection, with Ctrl held
+const KEY_STEP: f32 = 1.0;
+const KEY_STEP_LARGE: f32 = 10.0;
+
+// size of the selection the arrow keys start from when nothing is selected
+const KEY_SELECTION: [f32; 2] = [320.0, 120.0];
+
+const SELECTION_COLOR: [u8; 3] = [255, 200, 0];
+
+const SHADE: ImColor32 = ImColor32::from_rgba(0, 0, 0, 128);
+
+const HELP: &str = "Drag to select, arrows move the selection, Shift+arrows resize it, \
+                    Ctrl for larger steps. Enter transcribes, Esc cancels.";
+
+// The screenshots of every monitor shown across the whole desktop, dimmed
+// outside the region being selected, the way the Windows Snipping Tool does
+pub struct Overlay {
+    pub desktop: Area,
+    shots: Vec<(Shot, Texture)>,
+    // the corners of the selection in desktop coordinates, as they were dragged
+    selection: Option<([f32; 2], [f32; 2])>,
+    // false once picked or cancelled, the window is then put back
+    pub open: bool,
+}
+
+impl Overlay {
+    pub fn new(renderer: &mut AutoRenderer, shots: Vec<Shot>) -> Result<Self, String> {
+        let desktop =
+            Area::union(shots.iter().map(|shot| shot.area)).ok_or("No monitors captured")?;
+        let mut uploaded = Vec::new();
+        for shot in shots {
+            match Texture::upload(renderer, &shot.image) {
+                Ok(texture) => uploaded.push((shot, texture)),
+                Err(err) => {
+                    for (_, texture) in uploaded {
+                        texture.destroy(renderer);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(Overlay {
+            desktop,
+            shots: uploaded,
+            selection: None,
+            open: true,
+        })
+    }
+
+    // release the textures, only while no frame is drawing them
+    pub fn destroy(self, renderer: &AutoRenderer) {
+        for (_, texture) in self.shots {
+            texture.destroy(renderer);
+        }
+    }
+
+    // the selection as left, top, right and bottom, None when nothing is selected
+    pub fn selected_rect(&self) -> Option<[f32; 4]> {
+        let ([x0, y0], [x1, y1]) = self.selection?;
+        let rect = [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)];
+        (rect[2] - rect[0] >= MIN_SELECTION && rect[3] - rect[1] >= MIN_SELECTION).then_some(rect)
+    }
+
+    // the selected region cut from the monitor showing most of it
+    fn region(&self) -> Option<DynamicImage> {
+        capture::cut(
+            self.shots.iter().map(|(shot, _)| shot),
+            self.selected_rect()?,
+        )
+    }
+
+    // move the selection with the arrow keys, or resize it with Shift held
+    fn keyboard(&mut self, ui: &Ui) {
+        let io = ui.io();
+        let step = if io.key_ctrl {
+            KEY_STEP_LARGE
+        } else {
+            KEY_STEP
+        };
+        let mut delta = [0.0, 0.0];
+        if ui.is_key_pressed(Key::LeftArrow) {
+            delta[0] -= step;
+        }
+        if ui.is_key_pressed(Key::RightArrow) {
+            delta[0] += step;
+        }
+        if ui.is_key_pressed(Key::UpArrow) {
+            delta[1] -= step;
+        }
+        if ui.is_key_pressed(Key::DownArrow) {
+            delta[1] += step;
+        }
+        if delta == [0.0, 0.0] {
+            return;
+        }
+
+        let desktop = self.desktop;
+        let [left, top, right, bottom] = self.selected_rect().unwrap_or_else(|| {
+            let center_x = desktop.x as f32 + desktop.width as f32 / 2.0;
+            let center_y = desktop.y as f32 + desktop.height as f32 / 2.0;
+            let [width, height] = KEY_SELECTION;
+            [
+                center_x - width / 2.0,
+                center_y - height / 2.0,
+                center_x + width / 2.0,
+                center_y + height / 2.0,
+            ]
+        });
+        let (start, end) = if io.key_shift {
+            let right = (right + delta[0]).max(left + MIN_SELECTION);
+            let bottom = (bottom + delta[1]).max(top + MIN_SELECTION);
+            ([left, top], [right, bottom])
+        } else {
+            // keep the whole selection on the desktop
+            let x = delta[0].clamp(desktop.x as f32 - left, desktop.right() as f32 - right);
+            let y = delta[1].clamp(desktop.y as f32 - top, desktop.bottom() as f32 - bottom);
+            ([left + x, top + y], [right + x, bottom + y])
+        };
+        let clamp = |[x, y]: [f32; 2]| {
+            [
+                x.clamp(desktop.x as f32, desktop.right() as f32),
+                y.clamp(desktop.y as f32, desktop.bottom() as f32),
+            ]
+        };
+        self.selection = Some((clamp(start), clamp(end)));
+    }
+
+    // the overlay across the whole window, which covers the desktop, returns
+    // the region to transcribe once picked
+    pub fn draw(&mut self, ui: &Ui) -> Option<DynamicImage> {
+        let mut picked = None;
+        let display_size = ui.io().display_size;
+        let desktop = self.desktop;
+        let scale = [
+            display_size[0] / desktop.width as f32,
+            display_size[1] / desktop.height as f32,
+        ];
+        let to_screen = |[x, y]: [f32; 2]| {
+            [
+                (x - desktop.x as f32) * scale[0],
+                (y - desktop.y as f32) * scale[1],
+            ]
+        };
+        let to_desktop = |[x, y]: [f32; 2]| {
+            [
+                (x / scale[0] + desktop.x as f32).clamp(desktop.x as f32, desktop.right() as f32),
+                (y / scale[1] + desktop.y as f32).clamp(desktop.y as f32, desktop.bottom() as f32),
+            ]
+        };
+
+        let _padding = ui.push_style_var(StyleVar::WindowPadding([0.0, 0.0]));
+        let _border = ui.push_style_var(StyleVar::WindowBorderSize(0.0));
+        ui.window("##snip")
+            .position([0.0, 0.0], Condition::Always)
+            .size(display_size, Condition::Always)
+            .flags(
+                WindowFlags::NO_DECORATION | WindowFlags::NO_MOVE | WindowFlags::NO_SAVED_SETTINGS,
+            )
+            .focused(true)
+            .build(|| {
+                let draw_list = ui.get_window_draw_list();
+                for (shot, texture) in &self.shots {
+                    let area = shot.area;
+                    draw_list
+                        .add_image(
+                            texture.id,
+                            to_screen([area.x as f32, area.y as f32]),
+                            to_screen([area.right() as f32, area.bottom() as f32]),
+                        )
+                        .build();
+                }
+
+                // a button over the screenshots takes the drag
+                ui.set_cursor_screen_pos([0.0, 0.0]);
+                ui.invisible_button("selection", display_size);
+                let mouse = to_desktop(ui.io().mouse_pos);
+                if ui.is_item_activated() {
+                    self.selection = Some((mouse, mouse));
+                } else if ui.is_item_active() {
+                    if let Some((_, end)) = &mut self.selection {
+                        *end = mouse;
+                    }
+                }
+                self.keyboard(ui);
+
+                // shade everything around the selection
+                match self.selected_rect() {
+                    Some([left, top, right, bottom]) => {
+                        let [x0, y0] = to_screen([left, top]);
+                        let [x1, y1] = to_screen([right, bottom]);
+                        let [width, height] = display_size;
+                        for (min, max) in [
+                            ([0.0, 0.0], [width, y0]),
+                            ([0.0, y1], [width, height]),
+                            ([0.0, y0], [x0, y1]),
+                            ([x1, y0], [width, y1]),
+                        ] {
+                            draw_list.add_rect(min, max, SHADE).filled(true).build();
+                        }
+                        let [red, green, blue] = SELECTION_COLOR;
+                        draw_list
+                            .add_rect([x0, y0], [x1, y1], ImColor32::from_rgb(red, green, blue))
+                            .thickness(2.0)
+                            .build();
+                        let label = format!("{}x{}", (right - left) as u32, (bottom - top) as u32);
+                        draw_list.add_text(
+                            [x0, (y0 - ui.text_line_height() - 4.0).max(0.0)],
+                            ImColor32::WHITE,
+                            label,
+                        );
+                    }
+                    None => draw_list
+                        .add_rect([0.0, 0.0], display_size, SHADE)
+                        .filled(true)
+                        .build(),
+                }
+
+                let help_width = ui.calc_text_size(HELP)[0];
+                let help_pos = [(display_size[0] - help_width) / 2.0, 16.0];
+                draw_list
+                    .add_rect(
+                        [help_pos[0] - 8.0, help_pos[1] - 4.0],
+                        [
+                            help_pos[0] + help_width + 8.0,
+                            help_pos[1] + ui.text_line_height() + 4.0,
+                        ],
+                        SHADE,
+                    )
+                    .filled(true)
+                    .rounding(4.0)
+                    .build();
+                draw_list.add_text(help_pos, ImColor32::WHITE, HELP);
+
+                if ui.is_key_pressed(Key::Escape) || ui.is_mouse_clicked(MouseButton::Right) {
+                    self.open = false;
+                } else if ui.is_key_pressed(Key::Enter) || ui.is_key_pressed(Key::KeypadEnter) {
+                    picked = self.region();
+                    self.open = picked.is_none();
+                }
+            });
+        picked
+    }
+}
@@ -0,0 +1,327 @@
+use crate::ocr::{Bounds, Line, TranscriptionResult};
+use image::{codecs::jpeg::JpegEncoder, DynamicImage};
+use std::{collections::HashMap, error::Error, fmt::Write, fs, path::Path};
+
+// quality of the page images, enough for scans to stay readable
+const JPEG_QUALITY: u8 = 85;
+
+// width of every glyph in thousandths of the font size, text is stretched to
+// its box with horizontal scaling anyway
+const GLYPH_WIDTH: f32 = 500.0;
+
+// how far below the baseline the box of a word starts, as a share of its height
+const DESCENT: f32 = 0.2;
+
+// characters a font can hold, the codes of a simple font are a single byte
+const FONT_SIZE: usize = 256;
+
+// bfchar entries in a single block of a ToUnicode map, as the format allows
+const CMAP_BLOCK: usize = 100;
+
+/// Write `pages` of images and what was read from them as a searchable PDF,
+/// each image a page with the text over it invisible, where it is in the
+/// image, so it can be searched, selected and copied.
+///
+/// `dpi` is the resolution of the images, which sets the size of the pages.
+pub fn save(
+    path: &Path,
+    pages: &[(DynamicImage, TranscriptionResult)],
+    dpi: f32,
+) -> Result<(), Box<dyn Error>> {
+    let pdf = render(pages, dpi)?;
+    fs::write(path, pdf)
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err).into())
+}
+
+/// The searchable PDF [`save`] writes.
+///
+/// The text is drawn in glyphless fonts of Type 3 whose ToUnicode maps say
+/// which character each code is, so every script is searchable without
+/// embedding a real font.
+pub fn render(
+    pages: &[(DynamicImage, TranscriptionResult)],
+    dpi: f32,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    if pages.is_empty() {
+        return Err("A PDF needs at least one page".into());
+    }
+    let characters = Characters::of(pages);
+    let mut pdf = Writer::default();
+    let catalog = pdf.reserve();
+    let page_tree = pdf.reserve();
+
+    /* one font for every 256 characters, shared by all pages */
+    let mut fonts = String::new();
+    for (index, chunk) in characters.list.chunks(FONT_SIZE).enumerate() {
+        let glyph = pdf.add_stream("", format!("{} 0 d0\n", GLYPH_WIDTH).as_bytes());
+        let to_unicode = pdf.add_stream("", to_unicode(chunk).as_bytes());
+        let widths = vec![GLYPH_WIDTH.to_string(); chunk.len()].join(" ");
+        let font = pdf.add(&format!(
+            "<< /Type /Font /Subtype /Type3 /FontBBox [0 {} {} {}] \
+             /FontMatrix [0.001 0 0 0.001 0 0] /CharProcs << /g {} 0 R >> \
+             /Encoding << /Type /Encoding /Differences [0 {}] >> \
+             /FirstChar 0 /LastChar {} /Widths [{}] /ToUnicode {} 0 R /Resources << >> >>",
+            -(DESCENT * 1000.0),
+            GLYPH_WIDTH,
+            (1.0 - DESCENT) * 1000.0,
+            glyph,
+            vec!["/g"; chunk.len()].join(" "),
+            chunk.len() - 1,
+            widths,
+            to_unicode
+        ));
+        write!(fonts, "/F{} {} 0 R ", index, font)?;
+    }
+
+    let mut kids = Vec::new();
+    for (image, transcription) in pages {
+        let points = 72.0 / dpi;
+        let (width, height) = (
+            image.width() as f32 * points,
+            image.height() as f32 * points,
+        );
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(&image.to_rgb8())?;
+        let picture = pdf.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+                 /BitsPerComponent 8 /Filter /DCTDecode",
+                image.width(),
+                image.height()
+            ),
+            &jpeg,
+        );
+        let mut content = format!("q {} 0 0 {} 0 0 cm /Im0 Do Q\n", width, height);
+        // the boxes are where the recognized image had them, which may have been scaled
+        let scale_x = width / transcription.width.max(1) as f32;
+        let scale_y = height / transcription.height.max(1) as f32;
+        let to_page = |bounds: Bounds| {
+            (
+                bounds.left as f32 * scale_x,
+                height - bounds.bottom as f32 * scale_y,
+                (bounds.right - bounds.left).max(1) as f32 * scale_x,
+                (bounds.bottom - bounds.top).max(1) as f32 * scale_y,
+            )
+        };
+        content.push_str("BT 3 Tr\n");
+        for line in &transcription.lines {
+            let drawn = drawn(line);
+            let last = drawn.len() - 1;
+            for (index, (bounds, text)) in drawn.into_iter().enumerate() {
+                characters.place(&mut content, to_page(bounds), text, index < last);
+            }
+        }
+        content.push_str("ET\n");
+        let contents = pdf.add_stream("", content.as_bytes());
+        kids.push(pdf.add(&format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Contents {} 0 R \
+             /Resources << /XObject << /Im0 {} 0 R >> /Font << {}>> >> >>",
+            page_tree, width, height, contents, picture, fonts
+        )));
+    }
+
+    let kids: Vec<String> = kids.iter().map(|kid| format!("{} 0 R", kid)).collect();
+    pdf.set(
+        page_tree,
+        &format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        ),
+    );
+    pdf.set(
+        catalog,
+        &format!("<< /Type /Catalog /Pages {} 0 R >>", page_tree),
+    );
+    Ok(pdf.finish(catalog))
+}
+
+// the boxes and strings drawn of `line`, its words or the whole line for
+// engines that only read lines
+fn drawn(line: &Line) -> Vec<(Bounds, &str)> {
+    match line.words.is_empty() {
+        true => vec![(line.bounds, line.text.as_str())],
+        false => line
+            .words
+            .iter()
+            .map(|word| (word.bounds, word.text.as_str()))
+            .collect(),
+    }
+}
+
+// Every character of the text and the font and code it is drawn with
+struct Characters {
+    list: Vec<char>,
+    codes: HashMap<char, (usize, u8)>,
+}
+
+impl Characters {
+    fn of(pages: &[(DynamicImage, TranscriptionResult)]) -> Self {
+        let mut list = vec![' '];
+        let mut codes = HashMap::from([(' ', (0, 0))]);
+        let text = pages
+            .iter()
+            .flat_map(|(_, transcription)| &transcription.lines)
+            .flat_map(drawn);
+        for character in text.flat_map(|(_, text)| text.chars()) {
+            if character.is_control() || codes.contains_key(&character) {
+                continue;
+            }
+            let index = list.len();
+            codes.insert(character, (index / FONT_SIZE, (index % FONT_SIZE) as u8));
+            list.push(character);
+        }
+        Characters { list, codes }
+    }
+
+    // draw `text` stretched over the box at left, bottom, width and height,
+    // with a space after it that falls just outside the box
+    fn place(
+        &self,
+        content: &mut String,
+        (left, bottom, width, height): (f32, f32, f32, f32),
+        text: &str,
+        space: bool,
+    ) {
+        let text = text.trim();
+        let count = text
+            .chars()
+            .filter(|character| self.codes.contains_key(character))
+            .count();
+        if count == 0 {
+            return;
+        }
+        let size = height;
+        let natural = count as f32 * GLYPH_WIDTH / 1000.0 * size;
+        let stretch = width / natural * 100.0;
+        let _ = writeln!(
+            content,
+            "{:.2} Tz 1 0 0 1 {:.2} {:.2} Tm",
+            stretch,
+            left,
+            bottom + size * DESCENT
+        );
+        let mut font = None;
+        let mut run = String::new();
+        let characters = text.chars().chain(space.then_some(' '));
+        for character in characters {
+            let Some(&(index, code)) = self.codes.get(&character) else {
+                continue;
+            };
+            if font != Some(index) {
+                if let Some(previous) = font {
+                    let _ = writeln!(content, "/F{} {:.2} Tf <{}> Tj", previous, size, run);
+                }
+                font = Some(index);
+                run.clear();
+            }
+            let _ = write!(run, "{:02X}", code);
+        }
+        if let Some(font) = font {
+            let _ = writeln!(content, "/F{} {:.2} Tf <{}> Tj", font, size, run);
+        }
+    }
+}
+
+// the map from the codes of a font to the characters they stand for
+fn to_unicode(characters: &[char]) -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<00> <FF>\nendcodespacerange\n",
+    );
+    for (block, chunk) in characters.chunks(CMAP_BLOCK).enumerate() {
+        let _ = writeln!(cmap, "{} beginbfchar", chunk.len());
+        for (offset, character) in chunk.iter().enumerate() {
+            let mut utf16 = [0; 2];
+            let hex: String = character
+                .encode_utf16(&mut utf16)
+                .iter()
+                .map(|unit| format!("{:04X}", unit))
+                .collect();
+            let _ = writeln!(cmap, "<{:02X}> <{}>", block * CMAP_BLOCK + offset, hex);
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+// Objects of a PDF being written, numbered from 1, with the cross-reference
+// table built when it is finished
+#[derive(Default)]
+struct Writer {
+    objects: Vec<Option<Vec<u8>>>,
+}
+
+impl Writer {
+    // a number for an object written later with `set`
+    fn reserve(&mut self) -> usize {
+        self.objects.push(None);
+        self.objects.len()
+    }
+
+    fn set(&mut self, number: usize, dictionary: &str) {
+        self.objects[number - 1] = Some(dictionary.as_bytes().to_vec());
+    }
+
+    fn add(&mut self, dictionary: &str) -> usize {
+        let number = self.reserve();
+        self.set(number, dictionary);
+        number
+    }
+
+    // a stream with `entries` added to its dictionary
+    fn add_stream(&mut self, entries: &str, data: &[u8]) -> usize {
+        let mut object = format!("<< {} /Length {} >>\nstream\n", entries, data.len()).into_bytes();
+        object.extend_from_slice(data);
+        object.extend_from_slice(b"\nendstream");
+        let number = self.reserve();
+        self.objects[number - 1] = Some(object);
+        number
+    }
+
+    fn finish(self, root: usize) -> Vec<u8> {
+        // the binary comment tells tools the file is not plain text
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let size = self.objects.len() + 1;
+        let mut offsets = Vec::with_capacity(size);
+        for (index, object) in self.objects.into_iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            pdf.extend_from_slice(&object.unwrap_or_else(|| b"null".to_vec()));
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", size);
+        for offset in offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            size, root, xref
+        );
+        pdf.extend_from_slice(table.as_bytes());
+        pdf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::fixtures::{bounds, line, line_of, transcription, word};
+
+    #[test]
+    fn characters_come_from_the_words_drawn() {
+        // a corrected word the line text was not updated for
+        let mut corrected = line_of(vec![word("café", bounds(0, 0, 40, 10))]);
+        corrected.text = "cafe".to_string();
+        let lines = vec![corrected, line("naïve", bounds(0, 20, 50, 30))];
+        let pages = [(DynamicImage::new_rgb8(1, 1), transcription(100, 100, lines))];
+        let characters = Characters::of(&pages);
+        assert!(characters.codes.contains_key(&'é'));
+        assert!(characters.codes.contains_key(&'ï'));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::locale::tr;
+use imgui::{Condition, Ui, WindowFlags};
+use std::{collections::VecDeque, time::Duration};
+use transcribewizard::ocr::Timings;
+
+// captures the averages go over
+const ROLLING: usize = 20;
+
+const MEGABYTE: f64 = 1024.0 * 1024.0;
+
+// how long a step of reading took, None where the engine does not tell
+type Step = fn(&Timings) -> Option<f64>;
+
+const STEPS: [(&str, Step); 4] = [
+    ("Preprocessing", |timings| seconds(timings.preprocessing)),
+    ("Detection", |timings| timings.detection.and_then(seconds)),
+    ("Recognition", |timings| {
+        timings.recognition.and_then(seconds)
+    }),
+    ("Total", |timings| seconds(timings.total)),
+];
+
+fn seconds(duration: Duration) -> Option<f64> {
+    Some(duration.as_secs_f64())
+}
+
+// the mean of the values some captures have, None when none has one
+fn mean(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    let values: Vec<f64> = values.flatten().collect();
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+// forget the highest memory use so far, so the next peak is that of the
+// captures read after
+#[cfg(target_os = "linux")]
+fn reset_peak_memory() {
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reset_peak_memory() {}
+
+// highest memory use since the reset, the high water mark of the resident set
+#[cfg(target_os = "linux")]
+fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_memory() -> Option<u64> {
+    None
+}
+
+// how long a capture took to read, and the peak memory of the process since
+// the capture before, which covers all of its reading, decoding and cleanup
+#[derive(Clone, Copy)]
+struct Measured {
+    timings: Timings,
+    peak_memory: Option<u64>,
+}
+
+// A small window over the top right corner with how long the last capture
+// took to read and where the time went, next to the averages of the last few,
+// for comparing backends and settings
+#[derive(Default)]
+pub struct PerfHud {
+    last: Option<Measured>,
+    // the captures read lately, cached results are left out as they would
+    // only make the engine look fast
+    recent: VecDeque<Measured>,
+}
+
+impl PerfHud {
+    pub fn record(&mut self, timings: Timings) {
+        let measured = Measured {
+            timings,
+            peak_memory: peak_memory(),
+        };
+        reset_peak_memory();
+        self.last = Some(measured);
+        if timings.cached {
+            return;
+        }
+        if self.recent.len() == ROLLING {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(measured);
+    }
+
+    pub fn draw(&self, ui: &Ui) {
+        let width = ui.io().display_size[0];
+        let margin = 10.0;
+        ui.window("##performance")
+            .position(
+                [width - margin, margin + ui.frame_height()],
+                Condition::Always,
+            )
+            .position_pivot([1.0, 0.0])
+            .bg_alpha(0.7)
+            .flags(
+                WindowFlags::NO_DECORATION
+                    | WindowFlags::ALWAYS_AUTO_RESIZE
+                    | WindowFlags::NO_SAVED_SETTINGS
+                    | WindowFlags::NO_FOCUS_ON_APPEARING
+                    | WindowFlags::NO_NAV
+                    | WindowFlags::NO_MOVE
+                    | WindowFlags::NO_INPUTS,
+            )
+            .build(|| {
+                let Some(last) = self.last else {
+                    ui.text_disabled(tr("No capture read yet"));
+                    return;
+                };
+                if last.timings.cached {
+                    ui.text_disabled(tr("Last result came from the cache"));
+                }
+                // the columns line up at widths that fit the longest label
+                let column = ui.current_font_size() * 8.0;
+                let row = |label: &str, last: Option<String>, average: Option<String>| {
+                    ui.text(label);
+                    for (index, value) in [last, average].into_iter().enumerate() {
+                        ui.same_line_with_pos(column * (index + 1) as f32);
+                        match value {
+                            Some(value) => ui.text(value),
+                            None => ui.text_disabled("-"),
+                        }
+                    }
+                };
+                ui.text("");
+                ui.same_line_with_pos(column);
+                ui.text_disabled(tr("Last"));
+                ui.same_line_with_pos(column * 2.0);
+                ui.text_disabled(format!("{} {}", tr("Average of"), self.recent.len()));
+                let shown = |seconds: f64| format!("{:.0} ms", seconds * 1000.0);
+                for (label, value) in STEPS {
+                    row(
+                        tr(label),
+                        (!last.timings.cached)
+                            .then(|| value(&last.timings))
+                            .flatten()
+                            .map(shown),
+                        mean(self.recent.iter().map(|measured| value(&measured.timings)))
+                            .map(shown),
+                    );
+                }
+                let memory = |measured: &Measured| measured.peak_memory.map(|bytes| bytes as f64);
+                let megabytes = |bytes: f64| format!("{:.0} MB", bytes / MEGABYTE);
+                row(
+                    tr("Peak memory"),
+                    memory(&last).map(megabytes),
+                    mean(self.recent.iter().map(memory)).map(megabytes),
+                );
+            });
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    capture::{self, Area},
+    config::Config,
+    locale::tr,
+};
+use chrono::Local;
+use image::DynamicImage;
+use imgui::{Condition, Ui};
+use std::{
+    sync::mpsc::{Receiver, TryRecvError},
+    time::{Duration, Instant},
+};
+use tracing::error;
+use transcribewizard::{
+    captions::CaptionTracker,
+    digest::ImageDigest,
+    ocr::TranscriptionResult,
+    subtitle::{self, Cue},
+};
+
+const ERROR_COLOR: [f32; 4] = [1.0, 0.45, 0.4, 1.0];
+
+// A region of the screen captured again every few seconds, whatever new text
+// it shows appended to the transcript, for dashboards and streamed captions.
+// Recording captions, every caption line becomes a timed cue instead
+pub struct PinnedRegion {
+    // the region as left, top, right and bottom in desktop coordinates
+    rect: [f32; 4],
+    desktop: Area,
+    capture: Option<Receiver<Result<DynamicImage, String>>>,
+    // the frame captured last being read on the pool
+    reading: Option<Receiver<Result<TranscriptionResult, String>>>,
+    next_capture: Instant,
+    // the tab new text is appended to, the one active when it was pinned
+    pub tab: usize,
+    // the frame read last, the region is not read again until it changes
+    last_frame: Option<ImageDigest>,
+    // the lines read last, lines still in view are not appended twice
+    last_lines: Vec<String>,
+    captions: Option<CaptionTracker>,
+    // the captions so far as timestamped lines, shown in the window
+    transcript: String,
+    started: Instant,
+    // when the frame being captured was asked for, from the start
+    captured_at: Duration,
+    // when the frame read last was captured, from the start
+    read_at: Duration,
+    captures: usize,
+    changes: usize,
+    paused: bool,
+    error: Option<String>,
+}
+
+impl PinnedRegion {
+    // pin `rect` of the desktop, recording its captions when `captions`
+    pub fn start(desktop: Area, rect: [f32; 4], captions: bool, tab: usize) -> Self {
+        PinnedRegion {
+            rect,
+            desktop,
+            capture: None,
+            reading: None,
+            next_capture: Instant::now(),
+            tab,
+            last_frame: None,
+            last_lines: Vec::new(),
+            captions: captions.then(CaptionTracker::new),
+            transcript: String::new(),
+            started: Instant::now(),
+            captured_at: Duration::ZERO,
+            read_at: Duration::ZERO,
+            captures: 0,
+            changes: 0,
+            paused: false,
+            error: None,
+        }
+    }
+
+    // captions change every second or two, dashboards far more slowly
+    fn interval(&self, config: &Config) -> Duration {
+        Duration::from_secs_f32(match self.captions {
+            Some(_) => config.caption_interval.max(0.5),
+            None => config.pin_interval.max(1.0),
+        })
+    }
+
+    // capture the region now, with no delay so the frame shows it as it is
+    // when asked for
+    fn spawn_capture(&mut self) {
+        self.captured_at = self.started.elapsed();
+        self.capture = Some(capture::spawn_region(
+            self.desktop,
+            self.rect,
+            Duration::ZERO,
+        ));
+    }
+
+    // capture the region when it is time, returns the frame captured when it
+    // changed since the last one. Nothing is captured while a frame is read
+    pub fn poll(&mut self, config: &Config) -> Option<DynamicImage> {
+        let Some(job) = &self.capture else {
+            if !self.paused && self.reading.is_none() && Instant::now() >= self.next_capture {
+                self.spawn_capture();
+            }
+            return None;
+        };
+        let captured = match job.try_recv() {
+            Ok(captured) => captured,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("worker stopped".to_string()),
+        };
+        self.capture = None;
+        let interval = self.interval(config);
+        self.next_capture = Instant::now() + interval;
+        self.read_at = self.captured_at;
+        self.captures += 1;
+        match captured {
+            Ok(frame) => {
+                let digest = ImageDigest::of(&frame);
+                if self
+                    .last_frame
+                    .as_ref()
+                    .is_some_and(|last| last.is_same(&digest))
+                {
+                    if let Some(captions) = &mut self.captions {
+                        captions.hold(self.read_at + interval);
+                    }
+                    return None;
+                }
+                self.last_frame = Some(digest);
+                self.error = None;
+                Some(frame)
+            }
+            Err(err) => {
+                self.error = Some(format!("Error capturing the screen: {}", err));
+                None
+            }
+        }
+    }
+
+    // read the frame `poll` returned through `job`
+    pub fn read(&mut self, job: Receiver<Result<TranscriptionResult, String>>) {
+        self.reading = Some(job);
+    }
+
+    // what the frame being read recognized once it is done
+    pub fn finished(&mut self) -> Option<Result<TranscriptionResult, String>> {
+        let result = match self.reading.as_ref()?.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("worker stopped".to_string()),
+        };
+        self.reading = None;
+        Some(result)
+    }
+
+    // the part of `text` read from the latest frame to append, None when it
+    // shows nothing new. Lines that scrolled up from the bottom, as captions
+    // do, were appended last time and only the lines after them are new.
+    // Recorded captions go to their cues instead and are never appended
+    pub fn new_text(&mut self, config: &Config, text: &str) -> Option<String> {
+        let until = self.read_at + self.interval(config);
+        if let Some(captions) = &mut self.captions {
+            if captions.add(self.read_at, until, text) {
+                self.changes += 1;
+                self.transcript = subtitle::timestamped_text(captions.cues());
+            }
+            return None;
+        }
+        let lines: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        let overlap = (0..=lines.len().min(self.last_lines.len()))
+            .rev()
+            .find(|count| self.last_lines[self.last_lines.len() - count..] == lines[..*count])
+            .unwrap_or(0);
+        let added = lines[overlap..].join("\n");
+        self.last_lines = lines;
+        if added.is_empty() {
+            return None;
+        }
+        self.changes += 1;
+        Some(if config.pin_timestamps {
+            format!("[{}] {}", Local::now().format("%H:%M:%S"), added)
+        } else {
+            added
+        })
+    }
+
+    // the recorded captions, None when only pinned
+    pub fn finish(self) -> Option<Vec<Cue>> {
+        self.captions.map(CaptionTracker::into_cues)
+    }
+
+    pub fn failed(&mut self, err: String) {
+        self.error = Some(format!("Error extracting text from screen: {}", err));
+    }
+
+    // the window with the counts and controls, returns false once unpinned
+    pub fn draw(&mut self, ui: &Ui, config: &mut Config) -> bool {
+        let mut open = true;
+        let mut unpinned = false;
+        let title = match self.captions {
+            Some(_) => tr("Recording captions"),
+            None => tr("Pinned region"),
+        };
+        ui.window(format!("{}###Pinned region", title))
+            .size([420.0, 0.0], Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                let [left, top, right, bottom] = self.rect;
+                ui.text(format!(
+                    "{}x{} at {}, {}",
+                    (right - left) as u32,
+                    (bottom - top) as u32,
+                    left as i32,
+                    top as i32
+                ));
+                let changes = match &self.captions {
+                    Some(captions) => format!("{} {}", captions.cues().len(), tr("captions")),
+                    None => format!("{} {}", self.changes, tr("changes appended")),
+                };
+                ui.text_disabled(format!("{} {}, {}", self.captures, tr("captures"), changes));
+                if let Some(err) = &self.error {
+                    ui.text_colored(ERROR_COLOR, err);
+                }
+                let mut changed = false;
+                // saved once the field is left, not on every keystroke
+                if self.captions.is_some() {
+                    if ui
+                        .input_float(tr("Interval (seconds)"), &mut config.caption_interval)
+                        .step(0.5)
+                        .build()
+                    {
+                        config.caption_interval = config.caption_interval.max(0.5);
+                    }
+                    changed |= ui.is_item_deactivated_after_edit();
+                } else {
+                    if ui
+                        .input_float(tr("Interval (seconds)"), &mut config.pin_interval)
+                        .step(1.0)
+                        .build()
+                    {
+                        config.pin_interval = config.pin_interval.max(1.0);
+                    }
+                    changed |= ui.is_item_deactivated_after_edit();
+                    changed |= ui.checkbox(tr("Timestamps"), &mut config.pin_timestamps);
+                }
+                if changed {
+                    if let Err(err) = config.save() {
+                        error!("Error saving config: {}", err);
+                    }
+                }
+                let label = if self.paused {
+                    tr("Resume")
+                } else {
+                    tr("Pause")
+                };
+                if ui.button(label) {
+                    self.paused = !self.paused;
+                }
+                ui.same_line();
+                if ui.button(tr("Capture now")) && self.capture.is_none() && self.reading.is_none()
+                {
+                    self.spawn_capture();
+                }
+                ui.same_line();
+                unpinned = match self.captions {
+                    Some(_) => ui.button(tr("Stop recording")),
+                    None => ui.button(tr("Unpin")),
+                };
+                if self.captions.is_some() {
+                    ui.input_text_multiline("##captions", &mut self.transcript, [-1.0, 200.0])
+                        .read_only(true)
+                        .build();
+                }
+            });
+        open && !unpinned
+    }
+}
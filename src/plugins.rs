@@ -0,0 +1,178 @@
+use crate::{jobs::Background, locale::tr};
+use imgui::Ui;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+use transcribewizard::{
+    export::{self, ExportFormat},
+    TranscriptionResult,
+};
+
+// An output action outside the app, a shell command that gets every new
+// result as the JSON of "Save as" on its standard input
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Plugin {
+    pub name: String,
+    // run by sh on Unix and cmd on Windows, e.g. `curl -d @- https://...`
+    pub command: String,
+}
+
+impl Plugin {
+    fn run(&self, json: &str) -> Result<(), String> {
+        #[cfg(windows)]
+        let mut command = {
+            let mut command = Command::new("cmd");
+            command.args(["/C", &self.command]);
+            command
+        };
+        #[cfg(not(windows))]
+        let mut command = {
+            let mut command = Command::new("sh");
+            command.args(["-c", &self.command]);
+            command
+        };
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| err.to_string())?;
+        // the input is written on a thread of its own while the output is read,
+        // a command that fills its stderr before it reads all of the input
+        // would otherwise wait on this one for good
+        let output = thread::scope(|scope| {
+            if let Some(mut stdin) = child.stdin.take() {
+                scope.spawn(move || {
+                    // a command that does not read its input is fine too
+                    let _ = stdin.write_all(json.as_bytes());
+                });
+            }
+            child.wait_with_output()
+        })
+        .map_err(|err| err.to_string())?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(match stderr.trim() {
+            "" => output.status.to_string(),
+            message => format!("{}, {}", output.status, message),
+        })
+    }
+}
+
+// Runs the enabled plugins on their own threads, so a slow webhook does not
+// hold up the window, and collects what went wrong
+#[derive(Default)]
+pub struct PluginRunner {
+    background: Background<(String, String)>,
+}
+
+impl PluginRunner {
+    // hand a new result to every plugin whose command is in `enabled`
+    pub fn run(
+        &self,
+        plugins: &[Plugin],
+        enabled: &[String],
+        text: &str,
+        transcription: Option<&TranscriptionResult>,
+    ) {
+        let plugins: Vec<Plugin> = plugins
+            .iter()
+            .filter(|plugin| enabled.contains(&plugin.command) && !plugin.command.trim().is_empty())
+            .cloned()
+            .collect();
+        if plugins.is_empty() {
+            return;
+        }
+        let json = match export::render(ExportFormat::Json, text, transcription) {
+            Ok(json) => json,
+            Err(err) => return self.background.fail(("plugins".into(), err.to_string())),
+        };
+        for plugin in plugins {
+            let json = json.clone();
+            self.background
+                .spawn(move || plugin.run(&json).map_err(|err| (plugin.name, err)));
+        }
+    }
+
+    // the plugins that failed since the last call, with why
+    pub fn failures(&self) -> Vec<(String, String)> {
+        self.background.failures()
+    }
+}
+
+// the plugin editors of the settings window, true once something changed
+pub fn edit(ui: &Ui, plugins: &mut Vec<Plugin>, enabled: &mut Vec<String>) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+    for (index, plugin) in plugins.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(index);
+        let mut on = enabled.contains(&plugin.command);
+        if ui.checkbox("##enabled", &mut on) {
+            enabled.retain(|command| *command != plugin.command);
+            if on {
+                enabled.push(plugin.command.clone());
+            }
+            changed = true;
+        }
+        ui.same_line();
+        ui.set_next_item_width(120.0);
+        ui.input_text("##name", &mut plugin.name)
+            .hint(tr("Name"))
+            .build();
+        changed |= ui.is_item_deactivated_after_edit();
+        ui.same_line();
+        ui.set_next_item_width(-60.0);
+        let previous = plugin.command.clone();
+        if ui
+            .input_text("##command", &mut plugin.command)
+            .hint(tr("Command"))
+            .build()
+        {
+            // keep it enabled with its new command
+            for command in enabled.iter_mut().filter(|command| **command == previous) {
+                *command = plugin.command.clone();
+            }
+        }
+        changed |= ui.is_item_deactivated_after_edit();
+        ui.same_line();
+        if ui.small_button(tr("Delete")) {
+            removed = Some(index);
+        }
+    }
+    if let Some(index) = removed {
+        let plugin = plugins.remove(index);
+        enabled.retain(|command| *command != plugin.command);
+        changed = true;
+    }
+    if ui.button(tr("Add plugin")) {
+        plugins.push(Plugin {
+            name: format!("Plugin {}", plugins.len() + 1),
+            ..Plugin::default()
+        });
+        changed = true;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_that_fill_stderr_before_reading_their_input_finish() {
+        let plugin = Plugin {
+            name: "noisy".to_string(),
+            command: "head -c 1000000 /dev/zero >&2; cat >/dev/null; exit 3".to_string(),
+        };
+        let json = "x".repeat(1_000_000);
+        let err = plugin.run(&json).unwrap_err();
+        assert!(err.starts_with("exit status: 3"));
+    }
+}
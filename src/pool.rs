@@ -0,0 +1,69 @@
+use crate::backend::OcrBackend;
+use std::{
+    error::Error,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// Makes the backend of a worker. Backends that can share their models, like
+/// ocrs, hand out clones of one loaded engine, others load one per call.
+pub type BackendFactory =
+    Arc<dyn Fn() -> Result<Box<dyn OcrBackend>, Box<dyn Error>> + Send + Sync>;
+
+/// Work for a pool thread, given the backend of the thread or why it could not
+/// be made.
+pub type Job = Box<dyn FnOnce(Result<&dyn OcrBackend, &str>) + Send>;
+
+/// A fixed number of threads running OCR jobs in the order they are submitted.
+///
+/// Every thread makes its backend once when it starts. Dropping the pool lets
+/// the threads finish the queued jobs and exit, without waiting for them.
+pub struct WorkerPool {
+    jobs: Sender<Job>,
+    threads: usize,
+}
+
+impl WorkerPool {
+    /// `threads` of 0 starts one per CPU core.
+    pub fn new(threads: usize, factory: BackendFactory) -> Self {
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, |count| count.get()),
+            threads => threads,
+        };
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..threads {
+            let (receiver, factory) = (receiver.clone(), factory.clone());
+            thread::spawn(move || {
+                let backend = factory().map_err(|err| err.to_string());
+                loop {
+                    // the lock is only held while waiting, jobs run unlocked
+                    let job = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    let Ok(job) = job else {
+                        return;
+                    };
+                    match &backend {
+                        Ok(backend) => job(Ok(backend.as_ref())),
+                        Err(err) => job(Err(err)),
+                    }
+                }
+            });
+        }
+        WorkerPool { jobs, threads }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn submit(&self, job: impl FnOnce(Result<&dyn OcrBackend, &str>) + Send + 'static) {
+        // the threads only stop once the pool is gone
+        let _ = self.jobs.send(Box::new(job));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::links::regex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+// a word broken over a line with a hyphen, the second half starting in lower case
+// so that hyphenated names and line-leading dashes stay
+const HYPHENATED: &str = r"(\p{L})-[ \t]*\n[ \t]*(\p{Ll})";
+
+// patterns compiled since the cache was last cleared, the text is rendered
+// again every time it is read and the patterns change only while edited
+const COMPILED_PATTERNS: usize = 64;
+
+/// What a [`Transform`] does to the text.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformKind {
+    /// Replace matches of the regular expression `pattern` with `replacement`,
+    /// which may refer to groups as `$1` or `${name}`.
+    Replace,
+    /// Collapse runs of spaces within lines and of blank lines, and trim the
+    /// ends of lines and of the text. Indentation is kept.
+    Whitespace,
+    /// Turn curly quotes and primes into straight ASCII ones.
+    StraightQuotes,
+    /// Join words hyphenated across a line break.
+    JoinHyphenated,
+}
+
+impl TransformKind {
+    pub const ALL: [TransformKind; 4] = [
+        TransformKind::Replace,
+        TransformKind::Whitespace,
+        TransformKind::StraightQuotes,
+        TransformKind::JoinHyphenated,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TransformKind::Replace => "Regex replace",
+            TransformKind::Whitespace => "Normalize whitespace",
+            TransformKind::StraightQuotes => "Straighten quotes",
+            TransformKind::JoinHyphenated => "Join hyphenated words",
+        }
+    }
+}
+
+/// One step of cleaning up recognized text, see [`apply`].
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub kind: TransformKind,
+    pub enabled: bool,
+    /// Used by [`TransformKind::Replace`] only.
+    pub pattern: String,
+    pub replacement: String,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            kind: TransformKind::Replace,
+            enabled: true,
+            pattern: String::new(),
+            replacement: String::new(),
+        }
+    }
+}
+
+impl Transform {
+    pub fn new(kind: TransformKind) -> Self {
+        Transform {
+            kind,
+            ..Transform::default()
+        }
+    }
+
+    /// Why the transform cannot run, an invalid or empty pattern.
+    pub fn check(&self) -> Result<(), String> {
+        match self.kind {
+            TransformKind::Replace => self.regex().map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    fn regex(&self) -> Result<Regex, String> {
+        if self.pattern.is_empty() {
+            return Err("Empty pattern".into());
+        }
+        static COMPILED: OnceLock<Mutex<HashMap<String, Result<Regex, String>>>> = OnceLock::new();
+        let mut compiled = COMPILED
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // typing a pattern compiles every version of it on the way
+        if compiled.len() >= COMPILED_PATTERNS && !compiled.contains_key(&self.pattern) {
+            compiled.clear();
+        }
+        compiled
+            .entry(self.pattern.clone())
+            .or_insert_with(|| Regex::new(&self.pattern).map_err(|err| err.to_string()))
+            .clone()
+    }
+
+    fn run(&self, text: &str) -> Result<String, String> {
+        Ok(match self.kind {
+            TransformKind::Replace => self
+                .regex()?
+                .replace_all(text, self.replacement.as_str())
+                .into_owned(),
+            TransformKind::Whitespace => normalize_whitespace(text),
+            TransformKind::StraightQuotes => straighten_quotes(text),
+            TransformKind::JoinHyphenated => {
+                static HYPHENATED_WORDS: OnceLock<Regex> = OnceLock::new();
+                regex(&HYPHENATED_WORDS, HYPHENATED)
+                    .replace_all(text, "$1$2")
+                    .into_owned()
+            }
+        })
+    }
+}
+
+/// The built-in cleanups, off until enabled.
+pub fn default_transforms() -> Vec<Transform> {
+    [
+        TransformKind::JoinHyphenated,
+        TransformKind::StraightQuotes,
+        TransformKind::Whitespace,
+    ]
+    .map(|kind| Transform {
+        enabled: false,
+        ..Transform::new(kind)
+    })
+    .into()
+}
+
+/// `text` put through every enabled transform in order. Replacements whose
+/// pattern does not compile are skipped, [`Transform::check`] tells which.
+pub fn apply(transforms: &[Transform], text: &str) -> String {
+    let mut text = text.to_string();
+    for transform in transforms.iter().filter(|transform| transform.enabled) {
+        if let Ok(transformed) = transform.run(&text) {
+            text = transformed;
+        }
+    }
+    text
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let content = line.trim_start();
+        let indent = &line[..line.len() - content.len()];
+        let collapsed = content.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if collapsed.is_empty() {
+            // one blank line between paragraphs, none at the start
+            if lines.last().is_some_and(|last: &String| !last.is_empty()) {
+                lines.push(String::new());
+            }
+        } else {
+            lines.push(format!("{}{}", indent, collapsed));
+        }
+    }
+    if lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+fn straighten_quotes(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => '\'',
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => '"',
+            c => c,
+        })
+        .collect()
+}
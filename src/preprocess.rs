@@ -0,0 +1,252 @@
+use crate::document;
+use image::{imageops::FilterType, DynamicImage, GrayImage, Luma, RgbImage};
+use imageproc::{
+    contrast::{otsu_level, stretch_contrast, threshold, ThresholdType},
+    filter::median_filter,
+    geometric_transformations::{rotate_about_center, Border, Interpolation},
+};
+use serde::{Deserialize, Serialize};
+
+// skew angles tried when deskewing, in degrees either way
+const MAX_SKEW: f32 = 15.0;
+const SKEW_STEP: f32 = 0.5;
+
+// text pixels sampled when estimating skew, more only costs time
+const SKEW_SAMPLES: usize = 20_000;
+
+/// How far from the text color a pixel may be by default, enough for the
+/// antialiased edges of letters.
+pub const DEFAULT_COLOR_TOLERANCE: u8 = 60;
+
+/// Text of one color picked out of a busy background, such as subtitles over
+/// video or the text of a game's interface.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorFilter {
+    pub color: [u8; 3],
+    /// How far from `color` a pixel may be and still count as text, as the
+    /// distance between the two in RGB.
+    pub tolerance: u8,
+}
+
+impl ColorFilter {
+    pub fn new(color: [u8; 3]) -> Self {
+        ColorFilter {
+            color,
+            tolerance: DEFAULT_COLOR_TOLERANCE,
+        }
+    }
+
+    /// `image` with the pixels of the text color black and every other one
+    /// white, the dark text on a light background recognition reads best.
+    pub fn apply(&self, image: &DynamicImage) -> GrayImage {
+        let rgb = image.to_rgb8();
+        let limit = self.tolerance as i32 * self.tolerance as i32;
+        GrayImage::from_fn(rgb.width(), rgb.height(), |x, y| {
+            let pixel = rgb.get_pixel(x, y).0;
+            let distance: i32 = pixel
+                .iter()
+                .zip(self.color)
+                .map(|(channel, wanted)| (*channel as i32 - wanted as i32).pow(2))
+                .sum();
+            Luma([if distance <= limit { 0 } else { 255 }])
+        })
+    }
+}
+
+/// Optional image cleanup applied before recognition, steps run in field order.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preprocessing {
+    /// Turn sideways and upside down images upright, needs a backend so it is
+    /// left to [`crate::ocr::transcribe_preprocessed`].
+    pub auto_rotate: bool,
+    /// Find the page in a photo of one and flatten it, undoing the keystone of
+    /// a photo taken at an angle.
+    pub flatten_page: bool,
+    /// Keep only the text of this color, for text over busy backgrounds.
+    pub text_color: Option<ColorFilter>,
+    pub grayscale: bool,
+    pub denoise: bool,
+    pub contrast_stretch: bool,
+    /// Otsu thresholding, implies grayscale.
+    pub binarize: bool,
+    pub deskew: bool,
+    /// Double the image size, small text recognizes much better.
+    pub upscale: bool,
+    /// Read the image again at two or three times the size when its text turns
+    /// out to be too small, which needs the text found first so it is left to
+    /// [`crate::ocr::transcribe_preprocessed`].
+    pub auto_upscale: bool,
+    /// Read multi-column pages column by column, this reorders the result so it
+    /// is left to [`crate::ocr::transcribe_preprocessed`] too.
+    pub columns: bool,
+    /// The text runs in columns from top to bottom, read from right to left as
+    /// in Japanese, also left to [`crate::ocr::transcribe_preprocessed`].
+    pub vertical: bool,
+}
+
+impl Preprocessing {
+    /// Whether [`Preprocessing::apply`] changes the image.
+    pub fn is_enabled(&self) -> bool {
+        self.flatten_page
+            || self.text_color.is_some()
+            || self.grayscale
+            || self.denoise
+            || self.contrast_stretch
+            || self.binarize
+            || self.deskew
+            || self.upscale
+    }
+
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        let flat;
+        let image = if self.flatten_page {
+            flat = document::flatten_page(image);
+            &flat
+        } else {
+            image
+        };
+        let mut image = if let Some(filter) = &self.text_color {
+            DynamicImage::ImageLuma8(filter.apply(image))
+        } else if self.grayscale || self.binarize {
+            DynamicImage::ImageLuma8(image.to_luma8())
+        } else {
+            DynamicImage::ImageRgb8(image.to_rgb8())
+        };
+
+        if self.denoise {
+            image = map_image(
+                image,
+                |gray| median_filter(gray, 1, 1),
+                |rgb| median_filter(rgb, 1, 1),
+            );
+        }
+
+        if self.contrast_stretch {
+            if let Some((lower, upper)) = contrast_range(&image.to_luma8()) {
+                image = map_image(
+                    image,
+                    |gray| stretch_contrast(gray, lower, upper, 0, 255),
+                    |rgb| stretch_contrast(rgb, lower, upper, 0, 255),
+                );
+            }
+        }
+
+        if self.binarize {
+            let gray = image.to_luma8();
+            let level = otsu_level(&gray);
+            image = DynamicImage::ImageLuma8(threshold(&gray, level, ThresholdType::Binary));
+        }
+
+        if self.deskew {
+            let angle = skew_angle(&image.to_luma8());
+            if angle != 0.0 {
+                let theta = -angle.to_radians();
+                image = map_image(
+                    image,
+                    |gray| {
+                        rotate_about_center(gray, theta, Interpolation::Bilinear, Border::Replicate)
+                    },
+                    |rgb| {
+                        rotate_about_center(rgb, theta, Interpolation::Bilinear, Border::Replicate)
+                    },
+                );
+            }
+        }
+
+        if self.upscale {
+            image = image.resize_exact(
+                image.width() * 2,
+                image.height() * 2,
+                FilterType::CatmullRom,
+            );
+        }
+
+        image
+    }
+}
+
+// run a grayscale or color version of an operation depending on the image
+fn map_image(
+    image: DynamicImage,
+    gray: impl FnOnce(&GrayImage) -> GrayImage,
+    rgb: impl FnOnce(&RgbImage) -> RgbImage,
+) -> DynamicImage {
+    match image {
+        DynamicImage::ImageLuma8(image) => DynamicImage::ImageLuma8(gray(&image)),
+        image => DynamicImage::ImageRgb8(rgb(&image.to_rgb8())),
+    }
+}
+
+// luminance range holding all but the darkest and brightest percent of pixels
+fn contrast_range(gray: &GrayImage) -> Option<(u8, u8)> {
+    let mut histogram = [0usize; 256];
+    for pixel in gray.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    let total: usize = histogram.iter().sum();
+    let cutoff = total / 100;
+
+    let mut lower = 0;
+    let mut count = 0;
+    for (value, amount) in histogram.iter().enumerate() {
+        count += amount;
+        if count > cutoff {
+            lower = value;
+            break;
+        }
+    }
+    let mut upper = 255;
+    count = 0;
+    for (value, amount) in histogram.iter().enumerate().rev() {
+        count += amount;
+        if count > cutoff {
+            upper = value;
+            break;
+        }
+    }
+
+    (upper > lower).then_some((lower as u8, upper as u8))
+}
+
+// Estimate the clockwise skew of text lines in degrees, by finding the angle at
+// which the text pixels pile up into the sharpest row profile
+fn skew_angle(gray: &GrayImage) -> f32 {
+    let level = otsu_level(gray);
+    let total = gray.pixels().len();
+    let dark = gray.pixels().filter(|pixel| pixel.0[0] <= level).count();
+    // text is whichever class is rarer, handles light text on dark backgrounds
+    let text_is_dark = dark * 2 <= total;
+
+    let step = (dark.min(total - dark) / SKEW_SAMPLES).max(1);
+    let points: Vec<(f32, f32)> = gray
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| (pixel.0[0] <= level) == text_is_dark)
+        .step_by(step)
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    if points.is_empty() {
+        return 0.0;
+    }
+
+    let diagonal = (gray.width() as f32).hypot(gray.height() as f32);
+    let mut rows = vec![0u32; 2 * diagonal as usize + 1];
+    let mut best_angle = 0.0;
+    let mut best_score = 0u64;
+    let steps = (MAX_SKEW / SKEW_STEP) as i32;
+    for index in -steps..=steps {
+        let angle = index as f32 * SKEW_STEP;
+        let (sin, cos) = angle.to_radians().sin_cos();
+        rows.iter_mut().for_each(|row| *row = 0);
+        for (x, y) in &points {
+            let row = y * cos - x * sin + diagonal;
+            rows[row as usize] += 1;
+        }
+        let score = rows.iter().map(|&row| row as u64 * row as u64).sum();
+        if score > best_score {
+            best_score = score;
+            best_angle = angle;
+        }
+    }
+    best_angle
+}
@@ -0,0 +1,62 @@
+use crate::locale::tr;
+use imgui::Ui;
+use transcribewizard::preprocess::{ColorFilter, Preprocessing};
+
+// The checkboxes of every preprocessing step, shared by Settings and the
+// setups in Compare, true once one was changed. The color tolerance counts
+// as changed when its slider is let go
+pub fn draw(ui: &Ui, preprocessing: &mut Preprocessing) -> bool {
+    let mut changed = ui.checkbox(tr("Detect orientation"), &mut preprocessing.auto_rotate);
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr(
+            "Recognizes every image four times to find which way is up",
+        ));
+    }
+    changed |= ui.checkbox(
+        tr("Flatten photographed pages"),
+        &mut preprocessing.flatten_page,
+    );
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr(
+            "Finds the page in a photo and undoes the keystone of shooting it at an angle",
+        ));
+    }
+    let mut filtering = preprocessing.text_color.is_some();
+    if ui.checkbox(tr("Filter by text color"), &mut filtering) {
+        preprocessing.text_color = filtering.then(|| ColorFilter::new([255, 255, 255]));
+        changed = true;
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr(
+            "Keeps only text of one color, for subtitles and text over busy backgrounds. Use Pick text color in the preview to take it from the image",
+        ));
+    }
+    if let Some(filter) = &mut preprocessing.text_color {
+        let mut color = filter.color.map(|channel| channel as f32 / 255.0);
+        if ui.color_edit3(tr("Text color"), &mut color) {
+            filter.color = color.map(|channel| (channel * 255.0).round() as u8);
+            changed = true;
+        }
+        let mut tolerance = filter.tolerance as i32;
+        ui.slider_config(tr("Color tolerance"), 5, 200)
+            .build(&mut tolerance);
+        filter.tolerance = tolerance as u8;
+        changed |= ui.is_item_deactivated_after_edit();
+    }
+    changed |= ui.checkbox(tr("Grayscale"), &mut preprocessing.grayscale);
+    changed |= ui.checkbox(tr("Denoise"), &mut preprocessing.denoise);
+    changed |= ui.checkbox(tr("Stretch contrast"), &mut preprocessing.contrast_stretch);
+    changed |= ui.checkbox(tr("Binarize (Otsu)"), &mut preprocessing.binarize);
+    changed |= ui.checkbox(tr("Deskew"), &mut preprocessing.deskew);
+    changed |= ui.checkbox(tr("Upscale 2x"), &mut preprocessing.upscale);
+    changed |= ui.checkbox(tr("Upscale small text"), &mut preprocessing.auto_upscale);
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr(
+            "Reads the image again at 2 or 3x when its text is only a few pixels high",
+        ));
+    }
+    changed |= ui.checkbox(tr("Detect columns"), &mut preprocessing.columns);
+    // columns read top to bottom and right to left, as in manga
+    changed |= ui.checkbox(tr("Vertical text"), &mut preprocessing.vertical);
+    changed
+}
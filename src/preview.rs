@@ -0,0 +1,501 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    locale::tr,
+    status::Status,
+    texture::Texture,
+};
+use clipboard_rs::{common::RustImage, RustImageData};
+use image::{imageops, DynamicImage, GenericImageView, Pixel, Rgba};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_hollow_rect_mut},
+    filter::gaussian_blur_f32,
+    rect::Rect,
+};
+use imgui::{Condition, ImColor32, Ui};
+use imgui_glow_renderer::AutoRenderer;
+use std::mem;
+use transcribewizard::{
+    metadata::ImageMetadata,
+    ocr::{Bounds, Progress},
+    orientation,
+    preprocess::Preprocessing,
+};
+
+// regions smaller than this many pixels either way count as a click, not a selection
+const MIN_SELECTION: f32 = 4.0;
+
+const SELECTION_COLOR: [u8; 3] = [255, 200, 0];
+// lines found in a streamed transcription, and filled in once read
+const DETECTED_COLOR: [u8; 3] = [80, 160, 255];
+
+const RECTANGLE_COLOR: Rgba<u8> = Rgba([230, 40, 40, 255]);
+const RECTANGLE_THICKNESS: u32 = 3;
+const HIGHLIGHT_COLOR: Rgba<u8> = Rgba([255, 230, 0, 96]);
+const REDACT_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+// What dragging over the image does
+#[derive(Clone, Copy, PartialEq)]
+enum Tool {
+    Select,
+    Rectangle,
+    Highlight,
+    // a black box
+    Redact,
+    // the region blurred past reading, for recognition it is blacked out too
+    Blur,
+    // a click takes the color of the text to filter recognition by
+    Pick,
+}
+
+impl Tool {
+    const ALL: [Tool; 6] = [
+        Tool::Select,
+        Tool::Rectangle,
+        Tool::Highlight,
+        Tool::Redact,
+        Tool::Blur,
+        Tool::Pick,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Tool::Select => "Select",
+            Tool::Rectangle => "Rectangle",
+            Tool::Highlight => "Highlight",
+            Tool::Redact => "Redact",
+            Tool::Blur => "Blur",
+            Tool::Pick => "Pick text color",
+        }
+    }
+
+    fn hides_text(self) -> bool {
+        matches!(self, Tool::Redact | Tool::Blur)
+    }
+}
+
+// A mark drawn over the image, its region in image pixels as left, top, width
+// and height
+struct Annotation {
+    tool: Tool,
+    rect: (u32, u32, u32, u32),
+}
+
+// An image waiting to be transcribed, a region of which can be picked by
+// dragging over it so window chrome and the like stay out of the result.
+// Annotations drawn over it go along when it is copied, and redacted regions
+// are left out of recognition unless asked otherwise
+pub struct Preview {
+    image: DynamicImage,
+    texture: Texture,
+    // the corners of the selection in image pixels, as they were dragged
+    selection: Option<([f32; 2], [f32; 2])>,
+    tool: Tool,
+    annotations: Vec<Annotation>,
+    // the corners of the annotation being dragged
+    drawing: Option<([f32; 2], [f32; 2])>,
+    // recognize the image with its redacted regions blacked out
+    exclude_redacted: bool,
+    // the texture does not show the annotations as they are yet
+    stale: bool,
+    // the lines found and read so far while the image is streamed
+    detected: Vec<Bounds>,
+    read: Vec<Bounds>,
+    // what the file the image came from says about it
+    metadata: ImageMetadata,
+    // the text color clicked with the picker, until it is taken
+    picked_color: Option<[u8; 3]>,
+    pub open: bool,
+}
+
+// the rectangle between two dragged corners as left, top, width and height, None
+// when too small to be meant
+fn rect_between([x0, y0]: [f32; 2], [x1, y1]: [f32; 2]) -> Option<(u32, u32, u32, u32)> {
+    let (left, right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+    (right - left >= MIN_SELECTION && bottom - top >= MIN_SELECTION).then_some((
+        left as u32,
+        top as u32,
+        (right - left) as u32,
+        (bottom - top) as u32,
+    ))
+}
+
+impl Preview {
+    pub fn new(renderer: &mut AutoRenderer, image: DynamicImage) -> Result<Self, String> {
+        let texture = Texture::upload(renderer, &image.to_rgba8())?;
+        Ok(Preview {
+            image,
+            texture,
+            selection: None,
+            tool: Tool::Select,
+            annotations: Vec::new(),
+            drawing: None,
+            exclude_redacted: true,
+            stale: false,
+            detected: Vec::new(),
+            read: Vec::new(),
+            metadata: ImageMetadata::default(),
+            picked_color: None,
+            open: true,
+        })
+    }
+
+    pub fn set_metadata(&mut self, metadata: ImageMetadata) {
+        self.metadata = metadata;
+    }
+
+    // show how far reading the image got, boxes around the lines found that
+    // fill in as they are read
+    pub fn show_progress(&mut self, progress: &Progress) {
+        match progress {
+            Progress::Detected(result) => {
+                self.detected = result.lines.iter().map(|line| line.bounds).collect();
+                self.read.clear();
+            }
+            Progress::Read(result) => self
+                .read
+                .extend(result.lines.iter().map(|line| line.bounds)),
+        }
+    }
+
+    // the image is read, the boxes would only be in the way
+    pub fn clear_progress(&mut self) {
+        self.detected.clear();
+        self.read.clear();
+    }
+
+    // the color last picked from the image as the color of its text
+    pub fn take_text_color(&mut self) -> Option<[u8; 3]> {
+        self.picked_color.take()
+    }
+
+    // release the texture, only while no frame is drawing it
+    pub fn destroy(self, renderer: &AutoRenderer) {
+        self.texture.destroy(renderer);
+    }
+
+    // the selection as left, top, width and height, None when nothing is selected
+    fn selected_rect(&self) -> Option<(u32, u32, u32, u32)> {
+        let (start, end) = self.selection?;
+        rect_between(start, end)
+    }
+
+    // the selected part of `image`, all of it without a selection
+    fn crop(&self, image: DynamicImage) -> DynamicImage {
+        match self.selected_rect() {
+            Some((left, top, width, height)) => image.crop_imm(left, top, width, height),
+            None => image,
+        }
+    }
+
+    // the image with the annotations drawn in, or for recognition with only the
+    // redacted regions blacked out
+    fn annotated(&self, for_recognition: bool) -> DynamicImage {
+        if self.annotations.is_empty() {
+            return self.image.clone();
+        }
+        let mut canvas = self.image.to_rgba8();
+        for annotation in &self.annotations {
+            let (left, top, width, height) = annotation.rect;
+            let width = width.min(canvas.width().saturating_sub(left));
+            let height = height.min(canvas.height().saturating_sub(top));
+            if width == 0 || height == 0 || for_recognition && !annotation.tool.hides_text() {
+                continue;
+            }
+            let rect = Rect::at(left as i32, top as i32).of_size(width, height);
+            match annotation.tool {
+                Tool::Select | Tool::Pick => (),
+                Tool::Redact => draw_filled_rect_mut(&mut canvas, rect, REDACT_COLOR),
+                Tool::Blur if for_recognition => {
+                    draw_filled_rect_mut(&mut canvas, rect, REDACT_COLOR)
+                }
+                Tool::Blur => {
+                    let region = imageops::crop_imm(&canvas, left, top, width, height).to_image();
+                    let sigma = (width.min(height) as f32 / 4.0).max(4.0);
+                    let blurred = gaussian_blur_f32(&region, sigma);
+                    imageops::replace(&mut canvas, &blurred, left as i64, top as i64);
+                }
+                Tool::Rectangle => {
+                    for inset in 0..RECTANGLE_THICKNESS.min(width / 2).min(height / 2) {
+                        let rect = Rect::at((left + inset) as i32, (top + inset) as i32)
+                            .of_size(width - inset * 2, height - inset * 2);
+                        draw_hollow_rect_mut(&mut canvas, rect, RECTANGLE_COLOR);
+                    }
+                }
+                Tool::Highlight => {
+                    for y in top..top + height {
+                        for x in left..left + width {
+                            canvas.get_pixel_mut(x, y).blend(&HIGHLIGHT_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+        DynamicImage::ImageRgba8(canvas)
+    }
+
+    // the region to transcribe
+    fn region(&self) -> DynamicImage {
+        self.crop(self.annotated(self.exclude_redacted))
+    }
+
+    // the region as recognition sees it, cleaned up by the preprocessing steps
+    fn processed(&self, preprocessing: &Preprocessing) -> DynamicImage {
+        let region = self.region();
+        if preprocessing.is_enabled() {
+            preprocessing.apply(&region)
+        } else {
+            region
+        }
+    }
+
+    // show the annotations as they are now, before the image is drawn this frame
+    fn refresh(&mut self, renderer: &mut AutoRenderer) -> Result<(), String> {
+        let texture = Texture::upload(renderer, &self.annotated(false).to_rgba8())?;
+        mem::replace(&mut self.texture, texture).destroy(renderer);
+        self.stale = false;
+        Ok(())
+    }
+
+    // turn the image by quarter turns clockwise, before it is drawn this frame
+    fn rotate(&mut self, renderer: &mut AutoRenderer, quarters: u32) -> Result<(), String> {
+        let image = orientation::rotate(&self.image, quarters);
+        let texture = Texture::upload(renderer, &image.to_rgba8())?;
+        mem::replace(&mut self.texture, texture).destroy(renderer);
+        self.image = image;
+        self.selection = None;
+        // they would have to turn along
+        self.annotations.clear();
+        self.clear_progress();
+        self.stale = false;
+        Ok(())
+    }
+
+    // the Preview window, returns the image to transcribe once asked to
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        renderer: &mut AutoRenderer,
+        clipboard: &SystemClipboard,
+        preprocessing: &Preprocessing,
+        status: &mut Status,
+    ) -> Option<DynamicImage> {
+        let mut picked = None;
+        let mut open = self.open;
+        ui.window(format!("{}###Preview", tr("Preview")))
+            .size([640.0, 480.0], Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                if self.stale {
+                    if let Err(err) = self.refresh(renderer) {
+                        status.error("Error showing annotations", err);
+                        self.stale = false;
+                    }
+                }
+                let selected = self.selected_rect();
+                let label = if selected.is_some() {
+                    "Transcribe selection"
+                } else {
+                    "Transcribe image"
+                };
+                if ui.button(label) {
+                    picked = Some(self.region());
+                }
+                ui.same_line();
+                if ui.button(tr("Copy processed image")) {
+                    let image = RustImageData::from_dynamic_image(self.processed(preprocessing));
+                    if let Err(err) = clipboard.set_image(image) {
+                        status.error("Error setting image to clipboard", err);
+                    }
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr(
+                        "The selection cleaned up by the preprocessing steps, as it is recognized",
+                    ));
+                }
+                ui.same_line();
+                {
+                    let _disabled = ui.begin_disabled(selected.is_none());
+                    if ui.button(tr("Clear selection")) {
+                        self.selection = None;
+                    }
+                }
+                ui.same_line();
+                let mut quarters = 0;
+                if ui.button(tr("Rotate left")) {
+                    quarters = 3;
+                }
+                ui.same_line();
+                if ui.button(tr("Rotate right")) {
+                    quarters = 1;
+                }
+                if quarters != 0 {
+                    if let Err(err) = self.rotate(renderer, quarters) {
+                        status.error("Error rotating image", err);
+                    }
+                }
+                ui.same_line();
+                let (width, height) = self.image.dimensions();
+                match selected {
+                    Some((left, top, selected_width, selected_height)) => {
+                        ui.text_disabled(format!(
+                            "{}x{} at {}, {}",
+                            selected_width, selected_height, left, top
+                        ))
+                    }
+                    None => ui.text_disabled(format!(
+                        "{}x{}, drag over the image to select a region",
+                        width, height
+                    )),
+                }
+                if let Some((x, y)) = self.metadata.dpi {
+                    ui.same_line();
+                    if (x - y).abs() < 0.5 {
+                        ui.text_disabled(format!("{:.0} dpi", x));
+                    } else {
+                        ui.text_disabled(format!("{:.0}x{:.0} dpi", x, y));
+                    }
+                }
+                if let Some(taken) = &self.metadata.taken {
+                    ui.same_line();
+                    ui.text_disabled(format!("{} {}", tr("taken"), taken));
+                }
+
+                for tool in Tool::ALL {
+                    if ui.radio_button_bool(tool.label(), self.tool == tool) {
+                        self.tool = tool;
+                    }
+                    ui.same_line();
+                }
+                {
+                    let _disabled = ui.begin_disabled(self.annotations.is_empty());
+                    if ui.button(tr("Undo mark")) {
+                        self.annotations.pop();
+                        self.stale = true;
+                    }
+                    ui.same_line();
+                    if ui.button(tr("Clear marks")) {
+                        self.annotations.clear();
+                        self.stale = true;
+                    }
+                }
+                ui.same_line();
+                if ui.button(tr("Copy image")) {
+                    let image = RustImageData::from_dynamic_image(self.crop(self.annotated(false)));
+                    if let Err(err) = clipboard.set_image(image) {
+                        status.error("Error setting image to clipboard", err);
+                    }
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr("The selection with the marks drawn in, ready to share"));
+                }
+                ui.same_line();
+                ui.checkbox(
+                    tr("Leave redactions out of OCR"),
+                    &mut self.exclude_redacted,
+                );
+
+                ui.child_window("image")
+                    .horizontal_scrollbar(true)
+                    .build(|| self.draw_image(ui));
+            });
+        self.open = open;
+        picked
+    }
+
+    // the image shrunk to fit the width of the window, with the selection on top
+    fn draw_image(&mut self, ui: &Ui) {
+        let [texture_width, texture_height] = self.texture.size;
+        let available = ui.content_region_avail()[0];
+        let scale = (available / texture_width).clamp(0.05, 1.0);
+        let size = [texture_width * scale, texture_height * scale];
+        let origin = ui.cursor_screen_pos();
+        imgui::Image::new(self.texture.id, size).build(ui);
+
+        // a button over the image takes the drag, which would otherwise move the window
+        ui.set_cursor_screen_pos(origin);
+        ui.invisible_button("selection", size);
+        let [mouse_x, mouse_y] = ui.io().mouse_pos;
+        let mouse = [
+            ((mouse_x - origin[0]) / scale).clamp(0.0, texture_width),
+            ((mouse_y - origin[1]) / scale).clamp(0.0, texture_height),
+        ];
+        let dragged = if self.tool == Tool::Select {
+            &mut self.selection
+        } else {
+            &mut self.drawing
+        };
+        if self.tool == Tool::Pick {
+            if ui.is_item_activated() {
+                let x = (mouse[0] as u32).min(self.image.width().saturating_sub(1));
+                let y = (mouse[1] as u32).min(self.image.height().saturating_sub(1));
+                self.picked_color = Some(self.image.get_pixel(x, y).to_rgb().0);
+            }
+        } else if ui.is_item_activated() {
+            *dragged = Some((mouse, mouse));
+        } else if ui.is_item_active() {
+            if let Some((_, end)) = dragged {
+                *end = mouse;
+            }
+        }
+        if ui.is_item_deactivated() && self.tool != Tool::Select {
+            let rect = self
+                .drawing
+                .take()
+                .and_then(|(start, end)| rect_between(start, end));
+            if let Some(rect) = rect {
+                self.annotations.push(Annotation {
+                    tool: self.tool,
+                    rect,
+                });
+                self.stale = true;
+            }
+        }
+
+        let to_screen = |[x, y]: [f32; 2]| [origin[0] + x * scale, origin[1] + y * scale];
+        let draw_list = ui.get_window_draw_list();
+        let [red, green, blue] = DETECTED_COLOR;
+        for bounds in &self.detected {
+            let start = to_screen([bounds.left as f32, bounds.top as f32]);
+            let end = to_screen([bounds.right as f32, bounds.bottom as f32]);
+            draw_list
+                .add_rect(start, end, ImColor32::from_rgb(red, green, blue))
+                .thickness(1.0)
+                .build();
+        }
+        for bounds in &self.read {
+            let start = to_screen([bounds.left as f32, bounds.top as f32]);
+            let end = to_screen([bounds.right as f32, bounds.bottom as f32]);
+            draw_list
+                .add_rect(start, end, ImColor32::from_rgba(red, green, blue, 64))
+                .filled(true)
+                .build();
+        }
+        if let Some((start, end)) = self.drawing {
+            draw_list
+                .add_rect(to_screen(start), to_screen(end), ImColor32::WHITE)
+                .thickness(1.0)
+                .build();
+        }
+        let Some((start, end)) = self.selection else {
+            return;
+        };
+        let [red, green, blue] = SELECTION_COLOR;
+        draw_list
+            .add_rect(
+                to_screen(start),
+                to_screen(end),
+                ImColor32::from_rgba(red, green, blue, 48),
+            )
+            .filled(true)
+            .build();
+        draw_list
+            .add_rect(
+                to_screen(start),
+                to_screen(end),
+                ImColor32::from_rgb(red, green, blue),
+            )
+            .thickness(2.0)
+            .build();
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    config::{self, Config, Profile, Project},
+    history::History,
+    locale::tr,
+    session::Session,
+    status::Status,
+};
+use imgui::Ui;
+use imgui_glow_renderer::AutoRenderer;
+use tracing::error;
+
+// a dialog saving `name`.`extension`, or with a project open a file in its
+// folder named the way it names its files
+pub fn save_dialog(
+    config: &Config,
+    name: &str,
+    extension: &str,
+    source: Option<&str>,
+) -> rfd::FileDialog {
+    let dialog = rfd::FileDialog::new();
+    let Some(project) = config.current_project() else {
+        return dialog.set_file_name(format!("{}.{}", name, extension));
+    };
+    let dialog = dialog.set_file_name(format!("{}.{}", project.file_name(source), extension));
+    match project.output_dir.as_os_str().is_empty() {
+        true => dialog,
+        false => dialog.set_directory(&project.output_dir),
+    }
+}
+
+// a dialog to pick a file with, in the folder of the open project if it has one
+pub fn open_dialog(config: &Config) -> rfd::FileDialog {
+    let dialog = rfd::FileDialog::new();
+    match config.current_project() {
+        Some(project) if !project.output_dir.as_os_str().is_empty() => {
+            dialog.set_directory(&project.output_dir)
+        }
+        _ => dialog,
+    }
+}
+
+// the dropdown of the projects in the main window, returns the one picked
+// when it is not the one open, an empty name for no project
+pub fn pick(ui: &Ui, config: &Config) -> Option<String> {
+    if config.projects.is_empty() {
+        return None;
+    }
+    let mut opened = None;
+    let preview_value = if config.project.is_empty() {
+        tr("No project")
+    } else {
+        config.project.as_str()
+    };
+    if let Some(_combo) = ui.begin_combo(tr("Project"), preview_value) {
+        if ui
+            .selectable_config(tr("No project"))
+            .selected(config.project.is_empty())
+            .build()
+        {
+            opened = Some(String::new());
+        }
+        for project in &config.projects {
+            if ui
+                .selectable_config(&project.name)
+                .selected(project.name == config.project)
+                .build()
+            {
+                opened = Some(project.name.clone());
+            }
+        }
+    }
+    opened.filter(|name| *name != config.project)
+}
+
+// make `name` the open project, with its own history in place of the one of
+// the project left. Returns the profile it uses, for the caller to apply,
+// the config is saved here when there is none
+pub fn open(
+    name: String,
+    config: &mut Config,
+    history: &mut History,
+    renderer: &mut AutoRenderer,
+    status: &mut Status,
+) -> Option<Profile> {
+    /* the history of the project left is kept aside for when it is opened again */
+    if let Err(err) = Session::save_project(&config.project, history) {
+        status.error("Error saving project history", err);
+    }
+    for entry in history.clear() {
+        if let Some(texture) = entry.thumbnail {
+            texture.destroy(renderer);
+        }
+    }
+    history.set_last_image(None);
+    match Session::load_project(&name) {
+        Ok(Some(mut session)) => session.restore_history(history, renderer),
+        Ok(None) => {}
+        Err(err) => status.error("Error loading project history", err),
+    }
+    config.project = name;
+    history.set_journal(config.journal_dir());
+    let profile = config
+        .current_project()
+        .and_then(|project| {
+            config
+                .profiles
+                .iter()
+                .find(|profile| profile.name == project.profile)
+        })
+        .cloned();
+    if profile.is_none() {
+        if let Err(err) = config.save() {
+            error!("Error saving config: {}", err);
+        }
+    }
+    profile
+}
+
+// The projects under "Projects" in Settings, with the name of one to add
+#[derive(Default)]
+pub struct ProjectSettings {
+    name: String,
+}
+
+impl ProjectSettings {
+    // rows of the projects to edit or delete and a field to add one, returns
+    // whether any changed
+    pub fn draw(&mut self, ui: &Ui, config: &mut Config) -> bool {
+        let mut changed = false;
+        let mut removed = None;
+        let mut profiles = vec![tr("None").to_string()];
+        profiles.extend(config.profiles.iter().map(|profile| profile.name.clone()));
+        for (index, project) in config.projects.iter_mut().enumerate() {
+            let _id = ui.push_id_usize(index);
+            let Some(_node) = ui.tree_node(&project.name) else {
+                continue;
+            };
+            let mut dir = project.output_dir.display().to_string();
+            if ui
+                .input_text(tr("Output folder"), &mut dir)
+                .hint(tr("Usual places"))
+                .enter_returns_true(true)
+                .build()
+            {
+                project.output_dir = dir.into();
+                changed = true;
+            }
+            ui.same_line();
+            if ui.button(tr("Browse...")) {
+                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                    project.output_dir = dir;
+                    changed = true;
+                }
+            }
+            let mut profile_index = profiles
+                .iter()
+                .skip(1)
+                .position(|name| *name == project.profile)
+                .map_or(0, |index| index + 1);
+            if ui.combo_simple_string(tr("Profile"), &mut profile_index, &profiles) {
+                project.profile = match profile_index {
+                    0 => String::new(),
+                    index => profiles[index].clone(),
+                };
+                changed = true;
+            }
+            ui.input_text(tr("File names"), &mut project.file_name)
+                .build();
+            changed |= ui.is_item_deactivated_after_edit();
+            if ui.is_item_hovered() {
+                let placeholders: Vec<String> = config::FILE_NAME_PLACEHOLDERS
+                    .iter()
+                    .map(|name| format!("{{{}}}", name))
+                    .collect();
+                ui.tooltip_text(format!(
+                    "{} {}",
+                    tr("What saved files are called, with"),
+                    placeholders.join(", ")
+                ));
+            }
+            if ui.small_button(tr("Delete")) {
+                removed = Some(index);
+            }
+        }
+        if let Some(index) = removed {
+            /* its history stays on disk and comes back with a project of the same name */
+            let project = config.projects.remove(index);
+            if project.name == config.project {
+                config.project.clear();
+            }
+            changed = true;
+        }
+        ui.input_text(format!("{}##project", tr("Name")), &mut self.name)
+            .build();
+        ui.same_line();
+        let name = self.name.trim();
+        let exists = config.projects.iter().any(|project| project.name == name);
+        let _disabled = ui.begin_disabled(name.is_empty() || exists);
+        if ui.button(tr("Add project")) {
+            config.projects.push(Project {
+                name: name.to_string(),
+                ..Project::default()
+            });
+            self.name.clear();
+            changed = true;
+        }
+        changed
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{flow::Flow, locale::tr, spellcheck::SpellcheckView};
+use imgui::{MouseButton, Ui};
+use std::ops::Range;
+use transcribewizard::{ocr::Word, TranscriptionResult};
+
+// words recognized with less confidence than this are worth a second look
+const SUSPICIOUS_CONFIDENCE: f32 = 0.8;
+
+const SUSPICIOUS: [f32; 4] = [0.95, 0.65, 0.2, 1.0];
+
+const POPUP: &str = "word_alternatives";
+
+// Suspicious word the popup is open for
+struct Target {
+    range: Range<usize>,
+    word: String,
+    confidence: Option<f32>,
+    candidates: Vec<String>,
+}
+
+// Read-only view of the text with the words the engine was unsure about
+// highlighted, clicking one offers the other readings to replace it with
+#[derive(Default)]
+pub struct ProofreadView {
+    target: Option<Target>,
+}
+
+fn is_suspicious(word: &Word) -> bool {
+    !word.alternatives.is_empty()
+        || word
+            .confidence
+            .is_some_and(|confidence| confidence < SUSPICIOUS_CONFIDENCE)
+}
+
+// where the suspicious words of the transcription are in the text, in order,
+// words edited away since are skipped
+fn locate<'a>(text: &str, transcription: &'a TranscriptionResult) -> Vec<(Range<usize>, &'a Word)> {
+    let mut located = Vec::new();
+    let mut from = 0;
+    for word in transcription.lines.iter().flat_map(|line| &line.words) {
+        if word.text.is_empty() {
+            continue;
+        }
+        let Some(offset) = text[from..].find(&word.text) else {
+            continue;
+        };
+        let range = from + offset..from + offset + word.text.len();
+        from = range.end;
+        if is_suspicious(word) {
+            located.push((range, word));
+        }
+    }
+    located
+}
+
+impl ProofreadView {
+    // draw the text wrapped to the window, `spellcheck` adds its suggestions to
+    // the engine's when the word is misspelled too
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        text: &mut String,
+        transcription: &TranscriptionResult,
+        spellcheck: Option<&SpellcheckView>,
+    ) {
+        let located = locate(text, transcription);
+        let mut flow = Flow::new(ui);
+
+        let mut line_start = 0;
+        for line in text.split('\n') {
+            for chunk in line.split(' ').filter(|chunk| !chunk.is_empty()) {
+                let chunk_start = line_start + (chunk.as_ptr() as usize - line.as_ptr() as usize);
+                let chunk_end = chunk_start + chunk.len();
+                flow.place(ui, chunk);
+                let found = located
+                    .iter()
+                    .find(|(range, _)| range.start >= chunk_start && range.end <= chunk_end);
+                let Some((range, word)) = found else {
+                    ui.text(chunk);
+                    continue;
+                };
+                ui.text_colored(SUSPICIOUS, chunk);
+                if ui.is_item_hovered() {
+                    ui.set_mouse_cursor(Some(imgui::MouseCursor::Hand));
+                }
+                if ui.is_item_clicked_with_button(MouseButton::Left) {
+                    let mut candidates = word.alternatives.clone();
+                    if let Some(spellcheck) = spellcheck {
+                        for suggestion in spellcheck.suggestions(&word.text) {
+                            if !candidates.contains(&suggestion) {
+                                candidates.push(suggestion);
+                            }
+                        }
+                    }
+                    self.target = Some(Target {
+                        range: range.clone(),
+                        word: word.text.clone(),
+                        confidence: word.confidence,
+                        candidates,
+                    });
+                    ui.open_popup(POPUP);
+                }
+            }
+            flow.break_line(ui);
+            line_start += line.len() + 1;
+        }
+
+        ui.popup(POPUP, || {
+            let Some(target) = &self.target else {
+                return;
+            };
+            // the text may have changed since the popup opened
+            if text.get(target.range.clone()) != Some(target.word.as_str()) {
+                ui.close_current_popup();
+                return;
+            }
+            match target.confidence {
+                Some(confidence) => ui.text_disabled(format!(
+                    "{}, {:.0}% confident",
+                    target.word,
+                    confidence * 100.0
+                )),
+                None => ui.text_disabled(&target.word),
+            }
+            ui.separator();
+            if target.candidates.is_empty() {
+                ui.text_disabled(tr("No alternatives"));
+            }
+            for candidate in &target.candidates {
+                if ui.selectable(candidate) {
+                    text.replace_range(target.range.clone(), candidate);
+                }
+            }
+        });
+    }
+}
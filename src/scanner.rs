@@ -0,0 +1,294 @@
+use crate::{config::Config, locale::tr, status::Status, texture::Texture, PoolSettings};
+use image::DynamicImage;
+use imgui::{Condition, ImColor32, Ui};
+use imgui_glow_renderer::AutoRenderer;
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::error;
+use transcribewizard::{
+    camera::{self, CameraFeed},
+    document::{self, Quad},
+    ocr,
+    pool::WorkerPool,
+};
+
+// time between the frames read while reading continuously, each read takes
+// about as long on a laptop
+const READ_INTERVAL: Duration = Duration::from_secs(1);
+
+// time between looking for the page to outline, every frame would hold up the UI
+const OUTLINE_INTERVAL: Duration = Duration::from_millis(200);
+
+const PAGE_COLOR: [u8; 3] = [80, 220, 120];
+
+pub enum ScannerAction {
+    // a frame to transcribe like any other capture
+    Snap(DynamicImage),
+    // the text of the latest frame read continuously, when it is not what the
+    // frame before read
+    Read(String),
+}
+
+// The Camera window, a webcam turned into a document scanner that reads a
+// snapped frame or every frame in turn, cut down to the page in it
+#[derive(Default)]
+pub struct Scanner {
+    feed: Option<CameraFeed>,
+    // the camera being opened, which can take a few seconds
+    opening: Option<Receiver<Result<CameraFeed, String>>>,
+    // why the camera could not be opened, not retried until asked to
+    failed: Option<String>,
+    cameras: Option<Vec<String>>,
+    frame: Option<DynamicImage>,
+    texture: Option<Texture>,
+    page: Option<Quad>,
+    outlined_at: Option<Instant>,
+    continuous: bool,
+    reading: Option<Receiver<Result<String, String>>>,
+    read_at: Option<Instant>,
+    last_read: Option<String>,
+    // frames are read on a pool of their own without the result cache, which
+    // the nearly same frames of a camera would only fill up
+    pool: Option<(WorkerPool, PoolSettings)>,
+}
+
+impl Scanner {
+    // the frame as it gets read, only the page outlined in it flattened when
+    // asked to, it is not looked for again
+    fn snapped(&self, config: &Config) -> Option<DynamicImage> {
+        let frame = self.frame.as_ref()?;
+        let page = self.page.filter(|_| config.camera_crop);
+        Some(
+            page.and_then(|page| document::flatten(frame, page))
+                .unwrap_or_else(|| frame.clone()),
+        )
+    }
+
+    // open the camera on a worker thread, the feed comes back through `opening`
+    fn open(&mut self, config: &Config) {
+        let (sender, receiver) = mpsc::channel();
+        let index = config.camera_index;
+        thread::spawn(move || {
+            // a feed nobody waits for anymore is dropped here, off the UI thread
+            let _ = sender.send(CameraFeed::open(index).map_err(|err| err.to_string()));
+        });
+        self.opening = Some(receiver);
+    }
+
+    // pick up the camera once opened
+    fn poll_opening(&mut self) {
+        let Some(opening) = &self.opening else {
+            return;
+        };
+        match opening.try_recv() {
+            Ok(Ok(feed)) => self.feed = Some(feed),
+            Ok(Err(err)) => self.failed = Some(err),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => self.failed = Some("worker stopped".into()),
+        }
+        self.opening = None;
+    }
+
+    // stop the camera on a worker thread, it waits for the frame being captured
+    fn stop(&mut self) {
+        if let Some(feed) = self.feed.take() {
+            thread::spawn(move || drop(feed));
+        }
+    }
+
+    // stop the camera and let go of the last frame, the window was closed
+    pub fn close(&mut self, renderer: &AutoRenderer) {
+        if let Some(texture) = self.texture.take() {
+            texture.destroy(renderer);
+        }
+        self.stop();
+        *self = Scanner::default();
+    }
+
+    // show the newest frame of the camera, a new texture only when its size changed
+    fn refresh(&mut self, renderer: &mut AutoRenderer, config: &Config) -> Result<(), String> {
+        let Some(feed) = &self.feed else {
+            return Ok(());
+        };
+        if let Some(err) = feed.error() {
+            self.stop();
+            self.failed = Some(err.clone());
+            return Err(err);
+        }
+        let Some(frame) = feed.frame() else {
+            return Ok(());
+        };
+        let frame = DynamicImage::ImageRgb8(frame);
+        let rgba = frame.to_rgba8();
+        match &self.texture {
+            Some(texture) if texture.size == [rgba.width() as f32, rgba.height() as f32] => {
+                texture.update(renderer, &rgba)
+            }
+            _ => {
+                let texture = Texture::upload(renderer, &rgba)?;
+                if let Some(previous) = self.texture.replace(texture) {
+                    previous.destroy(renderer);
+                }
+            }
+        }
+        if !config.camera_crop {
+            self.page = None;
+        } else if self
+            .outlined_at
+            .is_none_or(|at| at.elapsed() >= OUTLINE_INTERVAL)
+        {
+            self.page = document::find_page(&frame);
+            self.outlined_at = Some(Instant::now());
+        }
+        self.frame = Some(frame);
+        Ok(())
+    }
+
+    // read a frame on the pool, the text comes back through the receiver
+    fn read(
+        pool: &WorkerPool,
+        image: DynamicImage,
+        config: &Config,
+    ) -> Receiver<Result<String, String>> {
+        let (sender, receiver) = mpsc::channel();
+        let config = config.clone();
+        pool.submit(move |backend| {
+            let result = backend.map_err(str::to_string).and_then(|backend| {
+                ocr::transcribe_preprocessed(backend, &image, &config.preprocessing)
+                    .map(|transcription| config.render(&transcription))
+                    .map_err(|err| err.to_string())
+            });
+            let _ = sender.send(result);
+        });
+        receiver
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        renderer: &mut AutoRenderer,
+        config: &mut Config,
+        status: &mut Status,
+        open: &mut bool,
+    ) -> Option<ScannerAction> {
+        if self.feed.is_none() && self.opening.is_none() && self.failed.is_none() {
+            self.open(config);
+        }
+        self.poll_opening();
+        if let Err(err) = self.refresh(renderer, config) {
+            status.error("Error capturing from camera", err);
+        }
+
+        let mut action = None;
+        if let Some(reading) = &self.reading {
+            match reading.try_recv() {
+                Ok(Ok(text)) => {
+                    if self.last_read.as_ref() != Some(&text) {
+                        self.last_read = Some(text.clone());
+                        action = Some(ScannerAction::Read(text));
+                    }
+                    self.reading = None;
+                }
+                Ok(Err(err)) => {
+                    status.error("Error reading camera frame", err);
+                    self.continuous = false;
+                    self.reading = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.reading = None,
+            }
+        }
+        let due = self.read_at.is_none_or(|at| at.elapsed() >= READ_INTERVAL);
+        if self.continuous && self.reading.is_none() && due {
+            if let Some(image) = self.snapped(config) {
+                match crate::ensure_pool(&mut self.pool, config, &None) {
+                    Ok(pool) => {
+                        self.reading = Some(Self::read(pool, image, config));
+                        self.read_at = Some(Instant::now());
+                    }
+                    Err(err) => {
+                        status.error("Error starting OCR workers", err);
+                        self.continuous = false;
+                    }
+                }
+            }
+        }
+
+        ui.window(format!("{}###Camera", tr("Camera")))
+            .size([640.0, 520.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                let cameras = self
+                    .cameras
+                    .get_or_insert_with(|| camera::cameras().unwrap_or_default());
+                if cameras.len() > 1 {
+                    let mut selected = (config.camera_index as usize).min(cameras.len() - 1);
+                    ui.set_next_item_width(200.0);
+                    if ui.combo_simple_string(tr("Camera"), &mut selected, cameras) {
+                        config.camera_index = selected as u32;
+                        self.stop();
+                        self.opening = None;
+                        self.failed = None;
+                        if let Err(err) = config.save() {
+                            error!("Error saving config: {}", err);
+                        }
+                    }
+                }
+                if let Some(err) = &self.failed {
+                    ui.text_wrapped(err);
+                    if ui.button(tr("Retry")) {
+                        self.failed = None;
+                    }
+                    return;
+                }
+
+                {
+                    let _disabled = ui.begin_disabled(self.frame.is_none());
+                    if ui.button(tr("Snap")) {
+                        action = self.snapped(config).map(ScannerAction::Snap);
+                    }
+                }
+                ui.same_line();
+                ui.checkbox(tr("Read continuously"), &mut self.continuous);
+                ui.same_line();
+                if ui.checkbox(tr("Crop to page"), &mut config.camera_crop) {
+                    if let Err(err) = config.save() {
+                        error!("Error saving config: {}", err);
+                    }
+                }
+                if self.continuous && self.reading.is_some() {
+                    ui.same_line();
+                    ui.text_disabled(tr("Reading..."));
+                }
+
+                let Some(texture) = &self.texture else {
+                    ui.text_disabled(tr("Waiting for the camera..."));
+                    return;
+                };
+                let [width, height] = texture.size;
+                let scale = (ui.content_region_avail()[0] / width).clamp(0.05, 1.0);
+                let origin = ui.cursor_screen_pos();
+                imgui::Image::new(texture.id, [width * scale, height * scale]).build(ui);
+                if let Some(page) = self.page {
+                    let to_screen =
+                        |(x, y): (f32, f32)| [origin[0] + x * scale, origin[1] + y * scale];
+                    let [red, green, blue] = PAGE_COLOR;
+                    let draw_list = ui.get_window_draw_list();
+                    for (index, &corner) in page.iter().enumerate() {
+                        draw_list
+                            .add_line(
+                                to_screen(corner),
+                                to_screen(page[(index + 1) % page.len()]),
+                                ImColor32::from_rgb(red, green, blue),
+                            )
+                            .thickness(2.0)
+                            .build();
+                    }
+                }
+            });
+        action
+    }
+}
@@ -0,0 +1,97 @@
+use image::DynamicImage;
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, Receiver},
+    },
+    thread,
+};
+use tracing::warn;
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+};
+
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+// screenshots asked for so far, every request needs a token of its own
+static REQUESTS: AtomicU32 = AtomicU32::new(0);
+
+// Ask the desktop portal for a screenshot of the whole desktop, or of the area
+// the user picks the way their desktop does it when `interactive`, which works
+// under GNOME, KDE and wlroots alike. Blocks until it is taken and returns the
+// image, None when the user cancelled.
+pub fn screenshot(interactive: bool) -> Result<Option<DynamicImage>, Box<dyn Error>> {
+    let Some(uri) = screenshot_uri(interactive)? else {
+        return Ok(None);
+    };
+    let path = crate::path_from_uri(&uri);
+    let image = image::open(&path);
+    // the desktop saves every screenshot, these are only needed in memory
+    if let Err(err) = std::fs::remove_file(&path) {
+        warn!("Error removing {}: {}", path.display(), err);
+    }
+    Ok(Some(image?))
+}
+
+// the URI of the screenshot the portal saved
+fn screenshot_uri(interactive: bool) -> Result<Option<String>, Box<dyn Error>> {
+    let connection = Connection::session()?;
+    let token = format!(
+        "transcribewizard{}_{}",
+        std::process::id(),
+        REQUESTS.fetch_add(1, Ordering::Relaxed)
+    );
+
+    // listen for the response before asking, it may come before the call returns
+    let sender = connection
+        .unique_name()
+        .ok_or("No name on the session bus")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request_path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+    let request = Proxy::new(
+        &connection,
+        PORTAL,
+        request_path.as_str(),
+        "org.freedesktop.portal.Request",
+    )?;
+    let mut responses = request.receive_signal("Response")?;
+
+    let portal = Proxy::new(
+        &connection,
+        PORTAL,
+        PORTAL_PATH,
+        "org.freedesktop.portal.Screenshot",
+    )?;
+    let mut options: HashMap<&str, Value> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+    options.insert("interactive", Value::from(interactive));
+    let _: OwnedObjectPath = portal.call("Screenshot", &("", options))?;
+
+    let message = responses
+        .next()
+        .ok_or("The portal went away without a response")?;
+    let (response, results): (u32, HashMap<String, OwnedValue>) = message.body().deserialize()?;
+    match response {
+        0 => (),
+        1 => return Ok(None),
+        _ => return Err("The portal could not take a screenshot".into()),
+    }
+    let uri = results
+        .get("uri")
+        .ok_or("The portal returned no screenshot")?;
+    Ok(Some(uri.downcast_ref::<&str>()?.to_string()))
+}
+
+// take the screenshot on a worker thread, the portal waits for the user
+pub fn spawn() -> Receiver<Result<Option<DynamicImage>, String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(screenshot(true).map_err(|err| err.to_string()));
+    });
+    receiver
+}
@@ -0,0 +1,113 @@
+use crate::TranscriptionResult;
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+use tracing::info;
+
+// steps a script may take before it is stopped, enough for any text and few
+// enough that an endless loop ends within a second or so
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+// the engine every script runs on, it holds no state of its own between runs
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| info!("Script: {}", text));
+        engine.on_debug(|text, _, position| info!("Script at {}: {}", position, text));
+        engine
+    })
+}
+
+/// A [Rhai](https://rhai.rs) script that turns a transcription into text.
+///
+/// It gets the text as the settings rendered it as `text`, and the result the
+/// engine read as `result`: its `width` and `height`, the `backend` that read
+/// it and its `lines`, each with its `text`, `confidence`, `bounds` as
+/// `left`, `top`, `right` and `bottom`, and `words` with their own `text`,
+/// `confidence`, `bounds` and `alternatives`. Confidences are `()` for
+/// engines that report none. What the script ends with is the new text, a
+/// string, an array of lines or `()` to keep the text as it is.
+pub struct Script {
+    ast: AST,
+}
+
+impl Script {
+    pub fn compile(source: &str) -> Result<Self, Box<dyn Error>> {
+        let ast = engine()
+            .compile(source)
+            .map_err(|err| format!("Failed to compile script: {}", err))?;
+        Ok(Script { ast })
+    }
+
+    /// Run the script over `result`, read as `text`.
+    pub fn run(&self, result: &TranscriptionResult, text: &str) -> Result<String, Box<dyn Error>> {
+        let mut scope = Scope::new();
+        scope.push("text", text.to_string());
+        scope.push_dynamic("result", rhai::serde::to_dynamic(result)?);
+        let returned: Dynamic = engine()
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|err| format!("Script failed: {}", err))?;
+        if returned.is_unit() {
+            return Ok(text.to_string());
+        }
+        if returned.is_array() {
+            let lines: Vec<String> = returned
+                .into_array()?
+                .into_iter()
+                .map(|line| line.to_string())
+                .collect();
+            return Ok(lines.join("\n"));
+        }
+        let type_name = returned.type_name();
+        returned
+            .into_string()
+            .map_err(|_| format!("The script returned {}, not text", type_name).into())
+    }
+}
+
+// the scripts compiled so far with the time their file was changed
+type Compiled = HashMap<PathBuf, (SystemTime, Arc<Script>)>;
+
+/// Run the script in the file at `path` as [`Script::run`] does. Scripts are
+/// compiled once and again only after their file changed, so an edit takes
+/// effect with the next result.
+pub fn run_file(
+    path: &Path,
+    result: &TranscriptionResult,
+    text: &str,
+) -> Result<String, Box<dyn Error>> {
+    static COMPILED: OnceLock<Mutex<Compiled>> = OnceLock::new();
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+    let compiled = COMPILED.get_or_init(Default::default);
+    let cached = compiled.lock().ok().and_then(|compiled| {
+        compiled
+            .get(path)
+            .filter(|(time, _)| *time == modified)
+            .map(|(_, script)| script.clone())
+    });
+    let script = match cached {
+        Some(script) => script,
+        None => {
+            let source = fs::read_to_string(path)
+                .map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+            let script = Arc::new(
+                Script::compile(&source).map_err(|err| format!("{}: {}", path.display(), err))?,
+            );
+            if let Ok(mut compiled) = compiled.lock() {
+                compiled.insert(path.to_path_buf(), (modified, script.clone()));
+            }
+            script
+        }
+    };
+    script.run(result, text)
+}
@@ -0,0 +1,143 @@
+use crate::{
+    capture::{self, Area},
+    locale::tr,
+};
+use image::{DynamicImage, RgbaImage};
+use imgui::{Condition, Ui};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use transcribewizard::stitch::{Added, Stitcher};
+
+// shortest time between the starts of two captures of the region while it
+// scrolls, the desktop portal on Linux takes about as long to answer and
+// its answer is waited on instead
+const CAPTURE_INTERVAL: Duration = Duration::from_millis(400);
+
+// What the user asked of the scrolling capture window
+pub enum ScrollAction {
+    // transcribe what was stitched so far
+    Finish(DynamicImage),
+    Cancel,
+}
+
+// A region of the screen captured over and over while its content is scrolled,
+// the frames stitched into one tall image
+pub struct ScrollCapture {
+    frames: Receiver<Result<RgbaImage, String>>,
+    stop: Arc<AtomicBool>,
+    stitcher: Stitcher,
+    // frames that did not line up with the one before, scrolled too fast
+    lost: usize,
+    error: Option<String>,
+}
+
+impl ScrollCapture {
+    // start capturing the `rect` of the desktop, given as left, top, right and
+    // bottom, with `first` the region as it was picked
+    pub fn start(desktop: Area, rect: [f32; 4], first: &DynamicImage) -> Self {
+        let mut stitcher = Stitcher::new();
+        stitcher.add(&first.to_rgba8());
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, frames) = mpsc::channel();
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                let started = Instant::now();
+                let frame = capture::capture_monitors(desktop)
+                    .map_err(|err| err.to_string())
+                    .and_then(|shots| {
+                        capture::cut(&shots, rect).ok_or_else(|| "Region is off screen".into())
+                    })
+                    .map(|region| region.to_rgba8());
+                if sender.send(frame).is_err() {
+                    break;
+                }
+                thread::sleep(CAPTURE_INTERVAL.saturating_sub(started.elapsed()));
+            }
+        });
+        ScrollCapture {
+            frames,
+            stop,
+            stitcher,
+            lost: 0,
+            error: None,
+        }
+    }
+
+    // stitch the frames captured since the last call
+    pub fn poll(&mut self) {
+        loop {
+            match self.frames.try_recv() {
+                Ok(Ok(frame)) => {
+                    if self.stitcher.add(&frame) == Added::Lost {
+                        self.lost += 1;
+                    }
+                }
+                Ok(Err(err)) => self.error = Some(err),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.error.get_or_insert_with(|| "Capture stopped".into());
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui) -> Option<ScrollAction> {
+        let mut action = None;
+        let mut open = true;
+        ui.window(format!("{}###Scrolling capture", tr("Scrolling capture")))
+            .size([360.0, 0.0], Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                ui.text_wrapped(tr(
+                    "Scroll the region slowly, it is captured every moment and stitched as \
+                     it moves.",
+                ));
+                ui.text(format!(
+                    "{} frames, {} pixels tall",
+                    self.stitcher.frames(),
+                    self.stitcher.height()
+                ));
+                if self.lost > 0 {
+                    ui.text_colored(
+                        [0.95, 0.65, 0.2, 1.0],
+                        format!(
+                            "{} frames did not line up, scroll back a little and slower",
+                            self.lost
+                        ),
+                    );
+                }
+                if let Some(err) = &self.error {
+                    ui.text_colored([0.9, 0.3, 0.3, 1.0], err);
+                }
+                if ui.button(tr("Finish and transcribe")) {
+                    action = self
+                        .stitcher
+                        .image()
+                        .map(|image| ScrollAction::Finish(DynamicImage::ImageRgba8(image)));
+                }
+                ui.same_line();
+                if ui.button(tr("Cancel")) {
+                    action = Some(ScrollAction::Cancel);
+                }
+            });
+        if !open {
+            action = Some(ScrollAction::Cancel);
+        }
+        action
+    }
+}
+
+impl Drop for ScrollCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    locale::tr,
+    status::Status,
+};
+use imgui::Ui;
+use transcribewizard::archive::{Archive, Archived};
+
+// most transcriptions listed for a search, the best matches come first
+const MAX_RESULTS: usize = 50;
+
+// Search box over the archive of every transcription, shown atop the history
+#[derive(Default)]
+pub struct ArchiveSearch {
+    query: String,
+    // the query the results are for, searched again once it changed
+    searched: String,
+    results: Vec<Archived>,
+}
+
+impl ArchiveSearch {
+    // true while a search is shown in place of the history
+    pub fn is_searching(&self) -> bool {
+        !self.query.trim().is_empty()
+    }
+
+    // draw the box and the results, returning the text of the one opened
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        archive: &Archive,
+        clipboard: &SystemClipboard,
+        status: &mut Status,
+    ) -> Option<String> {
+        ui.set_next_item_width(-1.0);
+        ui.input_text("##search", &mut self.query)
+            .hint(tr("Search all transcriptions"))
+            .build();
+        if self.query != self.searched {
+            self.search(archive, status);
+        }
+        if !self.is_searching() {
+            return None;
+        }
+
+        let mut opened = None;
+        let mut forgotten = None;
+        if self.results.is_empty() {
+            ui.text_disabled(tr("Nothing found"));
+        }
+        for found in &self.results {
+            let _id = ui.push_id(found.id.to_string());
+            ui.text_disabled(&found.created);
+            ui.text_wrapped(&found.snippet);
+            if ui.button(tr("Copy")) {
+                if let Err(err) = clipboard.set_text(found.text.clone()) {
+                    status.error("Error setting text to clipboard", err);
+                }
+            }
+            ui.same_line();
+            if ui.button(tr("Open")) {
+                opened = Some(found.text.clone());
+            }
+            ui.same_line();
+            if ui.button(tr("Forget")) {
+                forgotten = Some(found.id);
+            }
+            ui.separator();
+        }
+        if let Some(id) = forgotten {
+            match archive.remove(id) {
+                Ok(()) => self.search(archive, status),
+                Err(err) => status.error("Error removing transcription", err),
+            }
+        }
+        opened
+    }
+
+    fn search(&mut self, archive: &Archive, status: &mut Status) {
+        self.searched = self.query.clone();
+        self.results = match archive.search(&self.query, MAX_RESULTS) {
+            Ok(results) => results,
+            Err(err) => {
+                status.error("Error searching transcriptions", err);
+                Vec::new()
+            }
+        };
+    }
+}
@@ -0,0 +1,160 @@
+use crate::links;
+use regex::Regex;
+use std::ops::Range;
+
+// patterns of the secrets looked for, the whole match is the secret
+const PATTERNS: &[(SecretKind, &str)] = &[
+    (
+        SecretKind::PrivateKey,
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?(?:-----END [A-Z ]*PRIVATE KEY-----|\z)",
+    ),
+    // AWS access key ids
+    (SecretKind::ApiKey, r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    // GitHub tokens, classic and fine-grained
+    (
+        SecretKind::ApiKey,
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{40,})\b",
+    ),
+    // OpenAI, Anthropic and Stripe style keys
+    (
+        SecretKind::ApiKey,
+        r"\b(?:sk|pk|rk)[-_](?:live[-_]|test[-_]|proj[-_]|ant[-_])?[A-Za-z0-9_-]{20,}",
+    ),
+    // Slack tokens
+    (SecretKind::ApiKey, r"\bxox[abposr]-[A-Za-z0-9-]{10,}"),
+    // Google API keys
+    (SecretKind::ApiKey, r"\bAIza[0-9A-Za-z_-]{35}"),
+    // JSON web tokens, header, payload and signature
+    (
+        SecretKind::Token,
+        r"\beyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+    ),
+    (SecretKind::Email, links::EMAIL),
+    // 13 to 19 digits in groups as cards are printed, checked with Luhn below
+    (SecretKind::CardNumber, r"\b\d(?:[ -]?\d){12,18}\b"),
+];
+
+/// What a [`Secret`] looks like it is.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SecretKind {
+    PrivateKey,
+    ApiKey,
+    /// A JSON web token.
+    Token,
+    CardNumber,
+    Email,
+}
+
+impl SecretKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SecretKind::PrivateKey => "private key",
+            SecretKind::ApiKey => "API key",
+            SecretKind::Token => "access token",
+            SecretKind::CardNumber => "card number",
+            SecretKind::Email => "email address",
+        }
+    }
+
+    /// What the secret is replaced with by [`mask`].
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            SecretKind::PrivateKey => "[private key]",
+            SecretKind::ApiKey => "[API key]",
+            SecretKind::Token => "[token]",
+            SecretKind::CardNumber => "[card number]",
+            SecretKind::Email => "[email]",
+        }
+    }
+}
+
+/// Something in a text that should not be pasted around.
+#[derive(Clone)]
+pub struct Secret {
+    pub kind: SecretKind,
+    /// Byte range in the text.
+    pub range: Range<usize>,
+}
+
+// the Luhn checksum every payment card number passes, which the numbers in
+// receipts and order ids mostly do not
+fn luhn(digits: &str) -> bool {
+    let mut sum = 0;
+    for (index, digit) in digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+    {
+        sum += if index % 2 == 1 {
+            let doubled = digit * 2;
+            if doubled > 9 {
+                doubled - 9
+            } else {
+                doubled
+            }
+        } else {
+            digit
+        };
+    }
+    sum % 10 == 0
+}
+
+/// The secrets in `text` in the order they appear, where patterns overlap
+/// the one listed first wins.
+pub fn find(text: &str) -> Vec<Secret> {
+    let mut found: Vec<Secret> = Vec::new();
+    for (kind, pattern) in PATTERNS {
+        let Ok(regex) = Regex::new(pattern) else {
+            continue;
+        };
+        for matched in regex.find_iter(text) {
+            if *kind == SecretKind::CardNumber && !luhn(matched.as_str()) {
+                continue;
+            }
+            let range = matched.range();
+            let overlaps = found
+                .iter()
+                .any(|secret| secret.range.start < range.end && range.start < secret.range.end);
+            if !overlaps {
+                found.push(Secret { kind: *kind, range });
+            }
+        }
+    }
+    found.sort_by_key(|secret| secret.range.start);
+    found
+}
+
+/// `text` with every one of `secrets` replaced by its placeholder. `secrets`
+/// must come from [`find`] on the same text.
+pub fn mask(text: &str, secrets: &[Secret]) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let mut from = 0;
+    for secret in secrets {
+        masked.push_str(&text[from..secret.range.start]);
+        masked.push_str(secret.kind.placeholder());
+        from = secret.range.end;
+    }
+    masked.push_str(&text[from..]);
+    masked
+}
+
+/// How many secrets of each kind there are, e.g. "2 email addresses, 1 API key".
+pub fn summary(secrets: &[Secret]) -> String {
+    let mut kinds: Vec<SecretKind> = secrets.iter().map(|secret| secret.kind).collect();
+    kinds.sort();
+    kinds.dedup();
+    kinds
+        .into_iter()
+        .map(|kind| {
+            let count = secrets.iter().filter(|secret| secret.kind == kind).count();
+            let label = kind.label();
+            match count {
+                1 => format!("1 {}", label),
+                count if label.ends_with('s') => format!("{} {}es", count, label),
+                count => format!("{} {}s", count, label),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -0,0 +1,185 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const NOTION_PAGES: &str = "https://api.notion.com/v1/pages";
+const NOTION_VERSION: &str = "2022-06-28";
+// Notion takes at most 2000 characters per text and 100 blocks per request
+const NOTION_TEXT_LIMIT: usize = 2000;
+const NOTION_BLOCK_LIMIT: usize = 100;
+
+/// Where a [`SendTarget`] puts the text.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SendKind {
+    /// Append to one Markdown file, such as a note in an Obsidian vault.
+    AppendFile,
+    /// Create a page in Notion under a parent page.
+    Notion,
+    /// Write to a path made from a template with date placeholders, appending
+    /// when the file already exists.
+    PathTemplate,
+}
+
+impl SendKind {
+    pub const ALL: [SendKind; 3] = [
+        SendKind::AppendFile,
+        SendKind::Notion,
+        SendKind::PathTemplate,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SendKind::AppendFile => "Append to file",
+            SendKind::Notion => "Notion page",
+            SendKind::PathTemplate => "File from template",
+        }
+    }
+}
+
+/// A "Send to" destination for the text.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SendTarget {
+    pub kind: SendKind,
+    pub name: String,
+    /// The file of [`SendKind::AppendFile`], the template of
+    /// [`SendKind::PathTemplate`].
+    pub path: String,
+    /// Token of a Notion integration the parent page is shared with.
+    pub notion_token: String,
+    /// ID of the Notion page new pages go under, as at the end of its URL.
+    pub notion_parent: String,
+}
+
+impl Default for SendTarget {
+    fn default() -> Self {
+        SendTarget {
+            kind: SendKind::AppendFile,
+            name: String::new(),
+            path: String::new(),
+            notion_token: String::new(),
+            notion_parent: String::new(),
+        }
+    }
+}
+
+impl SendTarget {
+    /// Send `text`, returning the path or URL it ended up at.
+    pub fn send(&self, text: &str) -> Result<String, Box<dyn Error>> {
+        let now = Local::now();
+        match self.kind {
+            // a plain file is a template without placeholders
+            SendKind::AppendFile | SendKind::PathTemplate => {
+                let path = expand_template(self.path.trim(), &now);
+                if path.as_os_str().is_empty() {
+                    return Err("No file to write to".into());
+                }
+                append(&path, text)?;
+                Ok(path.display().to_string())
+            }
+            SendKind::Notion => self.create_notion_page(text, &now),
+        }
+    }
+
+    // a page titled with the first line, the text below it in paragraphs
+    fn create_notion_page(
+        &self,
+        text: &str,
+        now: &DateTime<Local>,
+    ) -> Result<String, Box<dyn Error>> {
+        if self.notion_token.is_empty() || self.notion_parent.is_empty() {
+            return Err("Notion needs a token and a parent page".into());
+        }
+        let title: String = match text.lines().map(str::trim).find(|line| !line.is_empty()) {
+            Some(line) => line.chars().take(100).collect(),
+            None => format!("Transcription {}", now.format("%Y-%m-%d %H:%M")),
+        };
+        let children: Vec<Value> = paragraphs(text)
+            .into_iter()
+            .take(NOTION_BLOCK_LIMIT)
+            .map(|paragraph| {
+                json!({
+                    "object": "block",
+                    "type": "paragraph",
+                    "paragraph": { "rich_text": [{ "type": "text", "text": { "content": paragraph } }] },
+                })
+            })
+            .collect();
+        let page: Value = ureq::post(NOTION_PAGES)
+            .header("Authorization", &format!("Bearer {}", self.notion_token))
+            .header("Notion-Version", NOTION_VERSION)
+            .send_json(json!({
+                "parent": { "page_id": self.notion_parent.trim() },
+                "properties": { "title": { "title": [{ "text": { "content": title } }] } },
+                "children": children,
+            }))
+            .map_err(|err| format!("Request to Notion failed: {}", err))?
+            .into_body()
+            .read_json()
+            .map_err(|err| format!("Unexpected response from Notion: {}", err))?;
+        Ok(page["url"].as_str().unwrap_or(NOTION_PAGES).to_string())
+    }
+}
+
+/// `template` with `{date}`, `{time}`, `{year}`, `{month}`, `{day}`, `{hour}`,
+/// `{minute}` and `{second}` filled in from `now`, e.g. "~/Notes/{date}.md".
+/// A leading `~` stands for the home directory.
+pub fn expand_template(template: &str, now: &DateTime<Local>) -> PathBuf {
+    let mut expanded = template.to_string();
+    for (placeholder, format) in [
+        ("{date}", "%Y-%m-%d"),
+        ("{time}", "%H-%M-%S"),
+        ("{year}", "%Y"),
+        ("{month}", "%m"),
+        ("{day}", "%d"),
+        ("{hour}", "%H"),
+        ("{minute}", "%M"),
+        ("{second}", "%S"),
+    ] {
+        expanded = expanded.replace(placeholder, &now.format(format).to_string());
+    }
+    match expanded.strip_prefix('~') {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+            None => PathBuf::from(expanded),
+        },
+        None => PathBuf::from(expanded),
+    }
+}
+
+// add `text` to the end of the file, a blank line after what it held
+fn append(path: &Path, text: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let separator = match fs::metadata(path) {
+        Ok(metadata) if metadata.len() > 0 => "\n",
+        _ => "",
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+    file.write_all(format!("{}{}\n", separator, text.trim_end()).as_bytes())?;
+    Ok(())
+}
+
+// the blank line separated paragraphs of the text, long ones split to fit
+fn paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = paragraph.chars().collect();
+        for chunk in chars.chunks(NOTION_TEXT_LIMIT) {
+            paragraphs.push(chunk.iter().collect());
+        }
+    }
+    paragraphs
+}
@@ -0,0 +1,164 @@
+use crate::{backend_factory, config::Config, load_cache};
+use serde_json::json;
+use std::{error::Error, io::Read, net::ToSocketAddrs};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{error, info};
+use transcribewizard::{
+    export::{self, ExportFormat},
+    files, ocr,
+    pool::WorkerPool,
+    postprocess,
+};
+
+// largest image body accepted, bigger uploads are more likely a mistake
+const MAX_BODY: u64 = 64 * 1024 * 1024;
+
+// `:8080` listens on the local machine only, other machines are let in by
+// naming an interface such as 0.0.0.0
+fn bind_address(address: &str) -> String {
+    match address.strip_prefix(':') {
+        Some(port) => format!("127.0.0.1:{}", port),
+        None => address.to_string(),
+    }
+}
+
+// whether every address `address` stands for is of this machine
+fn is_local(address: &str) -> bool {
+    address
+        .to_socket_addrs()
+        .is_ok_and(|mut addresses| addresses.all(|address| address.ip().is_loopback()))
+}
+
+// whether the request carries the token, compared in constant time so the time
+// an answer takes tells nothing about it
+fn authorized(request: &Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    request
+        .headers()
+        .iter()
+        .filter(|header| header.field.equiv("Authorization"))
+        .any(|header| {
+            let sent = header.value.as_str().trim().as_bytes();
+            sent.len() == expected.len()
+                && sent
+                    .iter()
+                    .zip(expected.as_bytes())
+                    .fold(0, |differ, (a, b)| differ | (a ^ b))
+                    == 0
+        })
+}
+
+fn respond_json(request: Request, status: u16, body: String) {
+    let header = Header::from_bytes("Content-Type", "application/json").ok();
+    let mut response = Response::from_string(body).with_status_code(status);
+    if let Some(header) = header {
+        response = response.with_header(header);
+    }
+    if let Err(err) = request.respond(response) {
+        error!("Error sending response: {}", err);
+    }
+}
+
+fn respond_error(request: Request, status: u16, message: &str) {
+    respond_json(request, status, json!({ "error": message }).to_string());
+}
+
+// Serve the OCR engine over HTTP until the process is stopped, without a window
+//
+// `POST /ocr` takes an image as the body and answers with the text and boxes
+// as the JSON export has them, `GET /health` tells whether the server is up.
+// Requests are recognized on the OCR threads of the config. With a token set
+// every request has to carry it, and without one only the local machine may
+// be listened on.
+pub fn run(address: &str, config: &Config) -> Result<(), Box<dyn Error>> {
+    let address = bind_address(address);
+    let token = config.serve_token.trim().to_string();
+    if token.is_empty() && !is_local(&address) {
+        return Err(format!(
+            "Listening on {} lets other machines in, set serve_token in the config first",
+            address
+        )
+        .into());
+    }
+    let cache = load_cache(config);
+    let factory = backend_factory(config, &cache)?;
+    let pool = WorkerPool::new(config.ocr_threads, factory);
+    let server = Server::http(&address)
+        .map_err(|err| format!("Failed to listen on {}: {}", address, err))?;
+    info!(
+        "Serving {} on http://{} with {} OCR threads",
+        config.backend,
+        address,
+        pool.threads()
+    );
+
+    for mut request in server.incoming_requests() {
+        let path = request
+            .url()
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        info!(
+            "{} {} from {:?}",
+            request.method(),
+            path,
+            request.remote_addr()
+        );
+        if !token.is_empty() && !authorized(&request, &token) {
+            respond_error(request, 401, "Missing or wrong token");
+            continue;
+        }
+        match (request.method(), path.as_str()) {
+            (Method::Get, "/health") => respond_json(
+                request,
+                200,
+                json!({ "status": "ok", "backend": config.backend }).to_string(),
+            ),
+            (Method::Post, "/ocr") => {
+                let mut body = Vec::new();
+                if let Err(err) = request
+                    .as_reader()
+                    .take(MAX_BODY + 1)
+                    .read_to_end(&mut body)
+                {
+                    respond_error(request, 400, &format!("Failed to read body: {}", err));
+                    continue;
+                }
+                if body.len() as u64 > MAX_BODY {
+                    respond_error(request, 413, "Image too large");
+                    continue;
+                }
+                let image = match files::decode(&body, config.conversion()) {
+                    Ok(image) => image,
+                    Err(err) => {
+                        respond_error(request, 400, &format!("Unsupported image: {}", err));
+                        continue;
+                    }
+                };
+                let (preprocessing, layout) = (config.preprocessing.clone(), config.text_layout);
+                let transforms = config.transforms.clone();
+                pool.submit(move |backend| {
+                    let result = backend.map_err(str::to_string).and_then(|backend| {
+                        let transcription =
+                            ocr::transcribe_preprocessed(backend, &image, &preprocessing)
+                                .map_err(|err| err.to_string())?;
+                        export::render(
+                            ExportFormat::Json,
+                            &postprocess::apply(&transforms, &layout.render(&transcription)),
+                            Some(&transcription),
+                        )
+                        .map_err(|err| err.to_string())
+                    });
+                    match result {
+                        Ok(body) => respond_json(request, 200, body),
+                        Err(err) => respond_error(request, 500, &err),
+                    }
+                });
+            }
+            (_, "/health" | "/ocr") => respond_error(request, 405, "Method not allowed"),
+            _ => respond_error(request, 404, "Not found"),
+        }
+    }
+    Ok(())
+}
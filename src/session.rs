@@ -0,0 +1,355 @@
+use crate::{
+    history::{History, HistoryEntry},
+    tabs::SavedTab,
+    texture::Texture,
+};
+use image::RgbaImage;
+use imgui_glow_renderer::AutoRenderer;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    error::Error,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::SystemTime,
+};
+use tracing::error;
+use transcribewizard::TranscriptionResult;
+
+// remove `dir` and everything in it, if it is there
+fn remove_dir(dir: &Path) -> Result<(), Box<dyn Error>> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    fs::remove_dir_all(dir)
+        .map_err(|err| format!("Failed to remove {}: {}", dir.display(), err).into())
+}
+
+// `dir` with `.suffix` added to its name, the whole name kept even when a
+// project's has dots of its own
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    dir.with_file_name(name)
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedEntry {
+    text: String,
+    transcription: Option<TranscriptionResult>,
+    // name of the PNG in the session directory
+    thumbnail: Option<String>,
+}
+
+// What was on screen when the app was closed, restored on the next start
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub tabs: Vec<SavedTab>,
+    // index of the tab that was open
+    pub active: usize,
+    // newest first, like the history
+    history: Vec<SavedEntry>,
+    // imgui's window positions and sizes in its ini format
+    pub layout: String,
+    // position and size of the main window
+    pub window: Option<(i32, i32, u32, u32)>,
+    // the directory it was loaded from, where its thumbnails are
+    #[serde(skip)]
+    from: PathBuf,
+}
+
+// A session taken from the history with its thumbnails, written by `write` on
+// any thread
+pub struct Prepared {
+    session: Session,
+    // each thumbnail under the name its entry refers to it by
+    thumbnails: Vec<(String, RgbaImage)>,
+}
+
+impl Session {
+    pub fn new(
+        (tabs, active): (Vec<SavedTab>, usize),
+        layout: String,
+        window: Option<(i32, i32, u32, u32)>,
+    ) -> Self {
+        Session {
+            tabs,
+            active,
+            history: Vec::new(),
+            layout,
+            window,
+            from: PathBuf::new(),
+        }
+    }
+
+    // directory of the session file and the history thumbnails, "session" for
+    // the one saved on exit and "autosave" for the one kept while running
+    fn dir(name: &str) -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("transcribewizard").join(name))
+    }
+
+    // the last session, None when there is none
+    pub fn load() -> Result<Option<Self>, Box<dyn Error>> {
+        Self::load_from("session")
+    }
+
+    // what was autosaved and when, left behind only when the app did not exit
+    // cleanly
+    pub fn load_autosave() -> Result<Option<(Self, SystemTime)>, Box<dyn Error>> {
+        let Some(session) = Self::load_from("autosave")? else {
+            return Ok(None);
+        };
+        let saved_at = fs::metadata(session.from.join("session.json"))
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or_else(|_| SystemTime::now());
+        Ok(Some((session, saved_at)))
+    }
+
+    // from the previous save when a crash came between moving it aside and
+    // moving the new one in place
+    fn load_from(name: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        let Some(target) = Self::dir(name) else {
+            return Ok(None);
+        };
+        let Some(dir) = [target.clone(), sibling(&target, "old")]
+            .into_iter()
+            .find(|dir| dir.join("session.json").exists())
+        else {
+            return Ok(None);
+        };
+        let path = dir.join("session.json");
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let mut session: Session = serde_json::from_str(&contents)
+            .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?;
+        session.from = dir;
+        Ok(Some(session))
+    }
+
+    // the directory the history of `project` is kept in while another one is
+    // open, the history of no project at all included. The name is made safe
+    // for a file system and a hash of it keeps apart the names that end up
+    // the same, "a/b" and "a_b"
+    fn project_dir(project: &str) -> String {
+        if project.is_empty() {
+            return "projects/.none".into();
+        }
+        let hash = blake3::hash(project.as_bytes()).to_hex();
+        format!(
+            "projects/{}-{}",
+            crate::config::file_safe(project),
+            &hash[..16]
+        )
+    }
+
+    // the history kept for `project`, None when it has none yet
+    pub fn load_project(project: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        Self::load_from(&Self::project_dir(project))
+    }
+
+    // keep `history` as that of `project`, for when it is opened again
+    pub fn save_project(project: &str, history: &History) -> Result<(), Box<dyn Error>> {
+        Session::default().save_to(&Self::project_dir(project), history)
+    }
+
+    // store the session, replacing the previous one and its thumbnails
+    pub fn save(self, history: &History) -> Result<(), Box<dyn Error>> {
+        self.save_to("session", history)
+    }
+
+    // store the session where it is recovered from after a crash, on a thread
+    // of its own so encoding the thumbnails does not hold up a frame
+    pub fn autosave(self, history: &History) -> Receiver<Result<(), String>> {
+        let prepared = self.prepare(history);
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(prepared.write("autosave").map_err(|err| err.to_string()));
+        });
+        receiver
+    }
+
+    // forget the autosaved session, on a clean exit or when it was not wanted
+    pub fn discard_autosave() -> Result<(), Box<dyn Error>> {
+        let Some(dir) = Self::dir("autosave") else {
+            return Ok(());
+        };
+        remove_dir(&dir)?;
+        remove_dir(&sibling(&dir, "old"))
+    }
+
+    // sums up the tabs, history and layout, autosaving skips a session summed up the
+    // same as the last one it saved
+    pub fn fingerprint(&self, history: &History) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for tab in &self.tabs {
+            tab.title.hash(&mut hasher);
+            tab.text.hash(&mut hasher);
+        }
+        self.active.hash(&mut hasher);
+        self.layout.hash(&mut hasher);
+        self.window.hash(&mut hasher);
+        // the entries themselves, so removing or clearing them counts too
+        for entry in history.entries() {
+            entry.text.hash(&mut hasher);
+            entry.thumbnail_image.is_some().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn save_to(self, name: &str, history: &History) -> Result<(), Box<dyn Error>> {
+        self.prepare(history).write(name)
+    }
+
+    // the entries of `history` to save, each thumbnail named after a hash of
+    // its pixels so one saved before is not encoded again
+    fn prepare(mut self, history: &History) -> Prepared {
+        let mut thumbnails = Vec::new();
+        self.history = history
+            .entries()
+            .map(|entry| {
+                let thumbnail = entry.thumbnail_image.as_ref().map(|image| {
+                    let mut hasher = blake3::Hasher::new();
+                    hasher.update(&image.width().to_le_bytes());
+                    hasher.update(&image.height().to_le_bytes());
+                    hasher.update(image.as_raw());
+                    let name = format!("thumbnail-{}.png", &hasher.finalize().to_hex()[..16]);
+                    thumbnails.push((name.clone(), image.clone()));
+                    name
+                });
+                SavedEntry {
+                    text: entry.text.clone(),
+                    transcription: entry.transcription.clone(),
+                    thumbnail,
+                }
+            })
+            .collect();
+        Prepared {
+            session: self,
+            thumbnails,
+        }
+    }
+}
+
+impl Prepared {
+    // written to a directory of its own, then the previous one is moved aside
+    // before this one is moved in its place and only then removed, so a crash
+    // at any point leaves one whole to load
+    fn write(self, name: &str) -> Result<(), Box<dyn Error>> {
+        let target = Session::dir(name).ok_or("No data directory available")?;
+        let dir = sibling(&target, "partial");
+        let old = sibling(&target, "old");
+        remove_dir(&dir)?;
+        fs::create_dir_all(&dir)
+            .map_err(|err| format!("Failed to create {}: {}", dir.display(), err))?;
+
+        let mut session = self.session;
+        let mut failed = Vec::new();
+        for (name, image) in &self.thumbnails {
+            let path = dir.join(name);
+            if path.exists() {
+                continue;
+            }
+            // linked from the previous save when it has it, copied where links
+            // are not supported
+            let previous = target.join(name);
+            let written = if previous.exists() {
+                fs::hard_link(&previous, &path)
+                    .or_else(|_| fs::copy(&previous, &path).map(|_| ()))
+                    .map_err(|err| err.to_string())
+            } else {
+                image.save(&path).map_err(|err| err.to_string())
+            };
+            if let Err(err) = written {
+                error!("Error saving history thumbnail: {}", err);
+                failed.push(name.clone());
+            }
+        }
+        for entry in &mut session.history {
+            if entry
+                .thumbnail
+                .as_ref()
+                .is_some_and(|name| failed.contains(name))
+            {
+                entry.thumbnail = None;
+            }
+        }
+
+        let path = dir.join("session.json");
+        fs::write(&path, serde_json::to_string(&session)?)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+        remove_dir(&old)?;
+        if target.exists() {
+            fs::rename(&target, &old)
+                .map_err(|err| format!("Failed to move {}: {}", target.display(), err))?;
+        }
+        fs::rename(&dir, &target)
+            .map_err(|err| format!("Failed to move {}: {}", dir.display(), err))?;
+        remove_dir(&old)
+    }
+}
+
+impl Session {
+    // put the saved transcriptions back into the history, uploading their thumbnails
+    pub fn restore_history(&mut self, history: &mut History, renderer: &mut AutoRenderer) {
+        // pushed oldest first so the newest ends up on top again
+        for saved in self.history.drain(..).rev() {
+            let thumbnail_image: Option<RgbaImage> =
+                saved
+                    .thumbnail
+                    .and_then(|name| match image::open(self.from.join(name)) {
+                        Ok(image) => Some(image.to_rgba8()),
+                        Err(err) => {
+                            error!("Error loading history thumbnail: {}", err);
+                            None
+                        }
+                    });
+            let thumbnail =
+                thumbnail_image
+                    .as_ref()
+                    .and_then(|image| match Texture::upload(renderer, image) {
+                        Ok(texture) => Some(texture),
+                        Err(err) => {
+                            error!("Error creating history thumbnail: {}", err);
+                            None
+                        }
+                    });
+            let entry = HistoryEntry {
+                text: saved.text,
+                transcription: saved.transcription,
+                thumbnail,
+                thumbnail_image,
+            };
+            if let Some(texture) = history.push(entry).and_then(|entry| entry.thumbnail) {
+                texture.destroy(renderer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dotted_project_names_keep_their_backups_apart() {
+        let first = PathBuf::from(Session::project_dir("Client v1.2"));
+        let second = PathBuf::from(Session::project_dir("Client v1.5"));
+        for suffix in ["old", "partial"] {
+            let (first, second) = (sibling(&first, suffix), sibling(&second, suffix));
+            assert_ne!(first, second);
+            assert!(first.to_string_lossy().starts_with("projects/Client v1.2-"));
+            assert!(first.to_string_lossy().ends_with(&format!(".{}", suffix)));
+        }
+    }
+
+    #[test]
+    fn siblings_of_the_hidden_no_project_directory_are_appended() {
+        let none = PathBuf::from(Session::project_dir(""));
+        assert_eq!(sibling(&none, "old"), PathBuf::from("projects/.none.old"));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::texture::Texture;
+use image::DynamicImage;
+use imgui::{ImColor32, MouseButton, Ui};
+use imgui_glow_renderer::AutoRenderer;
+use std::sync::{Arc, Weak};
+use tracing::error;
+use transcribewizard::TranscriptionResult;
+
+const LINE_COLOR: [u8; 3] = [80, 160, 255];
+const FOCUSED_COLOR: [u8; 3] = [255, 200, 0];
+
+// Proofreading layout with the image the text was read from on the left and
+// the text line by line on the right. Scrolling either side scrolls the other
+// to the same line, and clicking a line on the image puts the cursor in it
+// while the line being edited is marked on the image
+#[derive(Default)]
+pub struct SideBySide {
+    texture: Option<Texture>,
+    // the image the texture was made from
+    shown: Weak<DynamicImage>,
+    // the text line being edited
+    focused: Option<usize>,
+    // the text line to put the cursor in next frame
+    jump: Option<usize>,
+    // the pane to scroll next frame and the text line to scroll it to
+    follow: Option<(Pane, usize)>,
+    // where the image and the text were scrolled to last frame
+    scroll: [f32; 2],
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Image,
+    Text,
+}
+
+impl Pane {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// the line of `text` each line of the transcription is in, found in order so
+// editing text around them moves them along, None for lines edited away
+fn line_map(text: &str, transcription: &TranscriptionResult) -> Vec<Option<usize>> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut from = 0;
+    transcription
+        .lines
+        .iter()
+        .map(|line| {
+            let wanted = line.text.trim();
+            if wanted.is_empty() {
+                return None;
+            }
+            // several recognized lines can share one, as in paragraphs and tables
+            let found = (from..lines.len()).find(|&index| lines[index].contains(wanted))?;
+            from = found;
+            Some(found)
+        })
+        .collect()
+}
+
+impl SideBySide {
+    // keep the texture showing the image the transcription was read from
+    pub fn update(
+        &mut self,
+        renderer: &mut AutoRenderer,
+        transcription: Option<&TranscriptionResult>,
+    ) {
+        let Some(image) = transcription.and_then(|transcription| transcription.image.as_ref())
+        else {
+            if let Some(texture) = self.texture.take() {
+                texture.destroy(renderer);
+            }
+            self.shown = Weak::new();
+            return;
+        };
+        if self.texture.is_some() && Weak::ptr_eq(&self.shown, &Arc::downgrade(image)) {
+            return;
+        }
+        self.shown = Arc::downgrade(image);
+        self.focused = None;
+        match Texture::upload(renderer, &image.to_rgba8()) {
+            Ok(texture) => {
+                if let Some(previous) = self.texture.replace(texture) {
+                    previous.destroy(renderer);
+                }
+            }
+            Err(err) => error!("Error showing the image side by side: {}", err),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.texture.is_some()
+    }
+
+    // draw both panes `height` high, returns whether the text was edited
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        text: &mut String,
+        transcription: &TranscriptionResult,
+        height: f32,
+    ) -> bool {
+        let Some((texture_id, [texture_width, texture_height])) = self
+            .texture
+            .as_ref()
+            .map(|texture| (texture.id, texture.size))
+        else {
+            return false;
+        };
+        let map = line_map(text, transcription);
+        let row_height = ui.frame_height_with_spacing();
+        let width = ui.content_region_avail()[0] / 2.0;
+        let follow = self.follow.take();
+        let mut scrolled = None;
+        let mut clicked = None;
+
+        ui.child_window("side_by_side_image")
+            .size([width, height])
+            .border(true)
+            .horizontal_scrollbar(true)
+            .build(|| {
+                let scale = (ui.content_region_avail()[0] / texture_width).clamp(0.05, 1.0);
+                // the boxes are where the recognized image had them, which
+                // may have been scaled unevenly
+                let to_image = [
+                    texture_width / transcription.width.max(1) as f32 * scale,
+                    texture_height / transcription.height.max(1) as f32 * scale,
+                ];
+                let origin = ui.cursor_screen_pos();
+                let size = [texture_width * scale, texture_height * scale];
+                imgui::Image::new(texture_id, size).build(ui);
+                ui.set_cursor_screen_pos(origin);
+                ui.invisible_button("lines", size);
+                let [mouse_x, mouse_y] = ui.io().mouse_pos;
+                let mouse = [
+                    (mouse_x - origin[0]) / to_image[0],
+                    (mouse_y - origin[1]) / to_image[1],
+                ];
+
+                let draw_list = ui.get_window_draw_list();
+                for (line, index) in transcription.lines.iter().zip(&map) {
+                    let Some(index) = *index else {
+                        continue;
+                    };
+                    let bounds = line.bounds;
+                    let start = [
+                        origin[0] + bounds.left as f32 * to_image[0],
+                        origin[1] + bounds.top as f32 * to_image[1],
+                    ];
+                    let end = [
+                        origin[0] + bounds.right as f32 * to_image[0],
+                        origin[1] + bounds.bottom as f32 * to_image[1],
+                    ];
+                    let [red, green, blue] = if self.focused == Some(index) {
+                        FOCUSED_COLOR
+                    } else {
+                        LINE_COLOR
+                    };
+                    draw_list
+                        .add_rect(start, end, ImColor32::from_rgb(red, green, blue))
+                        .thickness(if self.focused == Some(index) {
+                            2.0
+                        } else {
+                            1.0
+                        })
+                        .build();
+                    let inside = (bounds.left as f32..=bounds.right as f32).contains(&mouse[0])
+                        && (bounds.top as f32..=bounds.bottom as f32).contains(&mouse[1]);
+                    if inside && ui.is_item_clicked_with_button(MouseButton::Left) {
+                        clicked = Some(index);
+                    }
+                }
+
+                let top = ui.scroll_y() / to_image[1];
+                match follow {
+                    Some((Pane::Image, index)) => {
+                        let line = map.iter().position(|mapped| *mapped == Some(index));
+                        if let Some(line) = line {
+                            let top = transcription.lines[line].bounds.top as f32 * to_image[1];
+                            ui.set_scroll_y(top);
+                        }
+                    }
+                    _ if self.moved(ui, Pane::Image) => {
+                        // the first line still in sight leads the text
+                        scrolled = transcription
+                            .lines
+                            .iter()
+                            .zip(&map)
+                            .find(|(line, _)| line.bounds.bottom as f32 > top)
+                            .and_then(|(_, index)| *index)
+                            .map(|index| (Pane::Text, index));
+                    }
+                    _ => (),
+                }
+                self.scroll[Pane::Image.index()] = ui.scroll_y();
+            });
+
+        ui.same_line();
+        let mut edited = false;
+        ui.child_window("side_by_side_text")
+            .size([0.0, height])
+            .border(true)
+            .build(|| {
+                let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+                for (index, line) in lines.iter_mut().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    if self.jump == Some(index) {
+                        ui.set_keyboard_focus_here();
+                    }
+                    ui.set_next_item_width(-1.0);
+                    edited |= ui.input_text("##line", line).build();
+                    if ui.is_item_active() {
+                        self.focused = Some(index);
+                    }
+                    if self.jump == Some(index) {
+                        ui.set_scroll_here_y_with_ratio(0.25);
+                    }
+                }
+                self.jump = None;
+                if edited {
+                    *text = lines.join("\n");
+                }
+
+                match follow {
+                    Some((Pane::Text, index)) => ui.set_scroll_y(index as f32 * row_height),
+                    _ if self.moved(ui, Pane::Text) => {
+                        let top = (ui.scroll_y() / row_height) as usize;
+                        scrolled = Some((Pane::Image, top));
+                    }
+                    _ => (),
+                }
+                self.scroll[Pane::Text.index()] = ui.scroll_y();
+            });
+
+        if let Some(index) = clicked {
+            self.jump = Some(index);
+            self.focused = Some(index);
+        }
+        self.follow = scrolled;
+        edited
+    }
+
+    // whether the user scrolled the pane being drawn since last frame, a pane
+    // scrolled to follow the other is not under the mouse
+    fn moved(&self, ui: &Ui, pane: Pane) -> bool {
+        ui.is_window_hovered() && (ui.scroll_y() - self.scroll[pane.index()]).abs() > 0.5
+    }
+}
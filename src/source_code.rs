@@ -0,0 +1,561 @@
+use quick_xml::{events::Event, Reader};
+use serde::{Deserialize, Serialize};
+
+// typographic characters OCR puts in code where the source has plain ASCII
+const TYPOGRAPHIC: &[(char, &str)] = &[
+    ('\u{2018}', "'"),
+    ('\u{2019}', "'"),
+    ('\u{201A}', "'"),
+    ('\u{2032}', "'"),
+    ('\u{201C}', "\""),
+    ('\u{201D}', "\""),
+    ('\u{201E}', "\""),
+    ('\u{2033}', "\""),
+    ('\u{2013}', "-"),
+    ('\u{2014}', "-"),
+    ('\u{2212}', "-"),
+    ('\u{2026}', "..."),
+    ('\u{00A0}', " "),
+    ('\u{2009}', " "),
+    ('\u{202F}', " "),
+];
+
+// letters OCR reads in place of the digits of a number
+const DIGIT_LOOKALIKES: &[(char, char)] = &[('O', '0'), ('o', '0'), ('l', '1'), ('I', '1')];
+
+// keywords of the usual languages, a word in keyword position that is one
+// with `l` and `o` read as `1` and `0` is taken for it
+const KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "bool",
+    "break",
+    "class",
+    "const",
+    "continue",
+    "def",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "elif",
+    "else",
+    "enum",
+    "false",
+    "False",
+    "final",
+    "float",
+    "for",
+    "from",
+    "func",
+    "function",
+    "global",
+    "if",
+    "impl",
+    "import",
+    "in",
+    "interface",
+    "is",
+    "lambda",
+    "let",
+    "long",
+    "loop",
+    "match",
+    "mod",
+    "module",
+    "mut",
+    "new",
+    "None",
+    "nonlocal",
+    "not",
+    "null",
+    "or",
+    "package",
+    "private",
+    "protected",
+    "pub",
+    "public",
+    "raise",
+    "return",
+    "self",
+    "static",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "trait",
+    "true",
+    "True",
+    "try",
+    "type",
+    "use",
+    "var",
+    "void",
+    "volatile",
+    "where",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Turn what OCR commonly misreads in source code back into it: smart quotes,
+/// dashes, ellipses and odd spaces become ASCII, letters in a number such as
+/// `1O0` become digits, and a keyword with `1` or `0` in place of `l` or `o`,
+/// such as `pub1ic` or `f0r`, is spelled right where a space, a colon or an
+/// opening bracket follows it. Identifiers such as `l0` or `d0` are left alone, and so
+/// are indentation and line breaks.
+pub fn to_ascii(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars() {
+        match TYPOGRAPHIC
+            .iter()
+            .find(|(typographic, _)| *typographic == c)
+        {
+            Some((_, replacement)) => ascii.push_str(replacement),
+            None => ascii.push(c),
+        }
+    }
+
+    let chars: Vec<char> = ascii.chars().collect();
+    let word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut fixed = String::with_capacity(ascii.len());
+    let mut start = 0;
+    while start < chars.len() {
+        if !word(chars[start]) {
+            fixed.push(chars[start]);
+            start += 1;
+            continue;
+        }
+        let end = (start..chars.len())
+            .find(|&index| !word(chars[index]))
+            .unwrap_or(chars.len());
+        // a keyword is followed by a space or what it opens and is no field
+        // or method, identifiers that look like one are used in other places
+        let keyword_position = matches!(chars.get(end), Some(' ' | '\t' | '(' | '{' | ':'))
+            && (start == 0 || chars[start - 1] != '.');
+        fixed.push_str(&fix_word(&chars[start..end], keyword_position));
+        start = end;
+    }
+    fixed
+}
+
+// a word of letters, digits and underscores with its lookalikes fixed, spelled
+// as a keyword only in `keyword_position`
+fn fix_word(word: &[char], keyword_position: bool) -> String {
+    let lookalike = |c: &char| DIGIT_LOOKALIKES.iter().any(|(letter, _)| letter == c);
+    // a number with some of its digits misread, which starts with a digit as
+    // identifiers can't, hex and binary ones are left alone as their letters
+    // are digits
+    let number = word.first().is_some_and(char::is_ascii_digit)
+        && word.iter().all(|c| c.is_ascii_digit() || lookalike(c))
+        && !matches!(word, ['0', 'x' | 'b' | 'o', ..]);
+    if number {
+        return word
+            .iter()
+            .map(|c| {
+                DIGIT_LOOKALIKES
+                    .iter()
+                    .find(|(letter, _)| letter == c)
+                    .map_or(*c, |(_, digit)| *digit)
+            })
+            .collect();
+    }
+    let spelled: String = word
+        .iter()
+        .map(|c| match c {
+            '1' => 'l',
+            '0' => 'o',
+            c => *c,
+        })
+        .collect();
+    let word: String = word.iter().collect();
+    match keyword_position && spelled != word && KEYWORDS.contains(&spelled.as_str()) {
+        true => spelled,
+        false => word,
+    }
+}
+
+/// The language a transcription of code is checked as.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeLanguage {
+    /// Not checked.
+    None,
+    Rust,
+    Python,
+    Json,
+    Toml,
+    Xml,
+    /// Any language with C-like brackets, strings and comments, of which only
+    /// those are checked.
+    Other,
+}
+
+impl CodeLanguage {
+    pub const ALL: [CodeLanguage; 7] = [
+        CodeLanguage::None,
+        CodeLanguage::Rust,
+        CodeLanguage::Python,
+        CodeLanguage::Json,
+        CodeLanguage::Toml,
+        CodeLanguage::Xml,
+        CodeLanguage::Other,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CodeLanguage::None => "Do not check",
+            CodeLanguage::Rust => "Rust",
+            CodeLanguage::Python => "Python",
+            CodeLanguage::Json => "JSON",
+            CodeLanguage::Toml => "TOML",
+            CodeLanguage::Xml => "XML",
+            CodeLanguage::Other => "Other (brackets only)",
+        }
+    }
+
+    /// Check that `text` is valid code, or a snippet of it for Rust, where the
+    /// statements of a function body pass too. The error names the line it
+    /// was found on.
+    pub fn check(self, text: &str) -> Result<(), String> {
+        match self {
+            CodeLanguage::None => Ok(()),
+            CodeLanguage::Rust => check_rust(text),
+            CodeLanguage::Python => check_python(text),
+            CodeLanguage::Json => serde_json::from_str::<serde_json::Value>(text)
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+            CodeLanguage::Toml => toml::from_str::<toml::Table>(text)
+                .map(|_| ())
+                .map_err(|err| {
+                    let line = err.span().map_or(1, |span| line_at(text, span.start));
+                    format!("line {}: {}", line, err.message())
+                }),
+            CodeLanguage::Xml => check_xml(text),
+            CodeLanguage::Other => scan(text, false).map(|_| ()),
+        }
+    }
+}
+
+// the line, from 1, the byte at `offset` is on
+fn line_at(text: &str, offset: usize) -> usize {
+    text.as_bytes()[..offset.min(text.len())]
+        .iter()
+        .filter(|byte| **byte == b'\n')
+        .count()
+        + 1
+}
+
+fn check_rust(text: &str) -> Result<(), String> {
+    // unclosed brackets and strings, before syn sees them and tells no line
+    if let Err(err) = text.parse::<proc_macro2::TokenStream>() {
+        return Err(format!(
+            "line {}: a bracket or string is not closed or closed twice",
+            err.span().start().line
+        ));
+    }
+    let file = match syn::parse_file(text) {
+        Ok(_) => return Ok(()),
+        Err(err) => err,
+    };
+    // the brace put before the snippet is on its first line, the lines stay
+    let block = match syn::parse_str::<syn::Block>(&format!("{{{}\n}}", text)) {
+        Ok(_) => return Ok(()),
+        Err(err) => err,
+    };
+    // the reading that got further is more likely what the snippet is
+    let position = |err: &syn::Error| {
+        let start = err.span().start();
+        (start.line, start.column)
+    };
+    let err = match position(&block) > position(&file) {
+        true => block,
+        false => file,
+    };
+    Err(format!("line {}: {}", err.span().start().line, err))
+}
+
+fn check_xml(text: &str) -> Result<(), String> {
+    let mut reader = Reader::from_str(text);
+    let mut open = Vec::new();
+    loop {
+        let event = reader.read_event().map_err(|err| {
+            let line = line_at(text, reader.error_position() as usize);
+            format!("line {}: {}", line, err)
+        })?;
+        match event {
+            Event::Start(element) => {
+                let line = line_at(text, reader.buffer_position() as usize);
+                open.push((element.name().into_inner().to_string(), line));
+            }
+            Event::End(_) => {
+                open.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    match open.pop() {
+        Some((name, line)) => Err(format!("line {}: <{}> is never closed", line, name)),
+        None => Ok(()),
+    }
+}
+
+// what the scan found of a line that matters to indentation
+struct LineState {
+    // inside brackets, a string or after a backslash where it starts, so its
+    // indentation means nothing
+    continued: bool,
+    // the last character outside strings and comments
+    last: Option<char>,
+}
+
+// check the brackets and strings of `text` are closed in the order they were
+// opened, with Python's comments and strings or C-like ones
+fn scan(text: &str, python: bool) -> Result<Vec<LineState>, String> {
+    let mut brackets: Vec<(char, usize)> = Vec::new();
+    // the quote of the string the scan is in, whether it is tripled and the
+    // line it started on
+    let mut string: Option<(char, bool, usize)> = None;
+    let mut block_comment: Option<usize> = None;
+    let mut states = Vec::new();
+    let mut continued = false;
+    for (index, line) in text.split('\n').enumerate() {
+        let number = index + 1;
+        let chars: Vec<char> = line.chars().collect();
+        let mut state = LineState {
+            continued: continued || !brackets.is_empty() || string.is_some(),
+            last: None,
+        };
+        let mut at = 0;
+        while at < chars.len() {
+            let c = chars[at];
+            let next = chars.get(at + 1).copied();
+            if block_comment.is_some() {
+                if c == '*' && next == Some('/') {
+                    block_comment = None;
+                    at += 1;
+                }
+                at += 1;
+                continue;
+            }
+            if let Some((quote, triple, _)) = string {
+                if c == '\\' {
+                    at += 2;
+                    continue;
+                }
+                let tripled = chars.get(at..at + 3) == Some(&[quote; 3][..]);
+                if c == quote && (!triple || tripled) {
+                    string = None;
+                    at += if triple { 3 } else { 1 };
+                    state.last = Some(quote);
+                    continue;
+                }
+                at += 1;
+                continue;
+            }
+            match c {
+                '#' if python => break,
+                '/' if !python && next == Some('/') => break,
+                '/' if !python && next == Some('*') => {
+                    block_comment = Some(number);
+                    at += 1;
+                }
+                '"' | '\'' | '`' if !python || c != '`' => {
+                    let triple = python && chars.get(at..at + 3) == Some(&[c; 3][..]);
+                    string = Some((c, triple, number));
+                    if triple {
+                        at += 2;
+                    }
+                }
+                '(' | '[' | '{' => brackets.push((c, number)),
+                ')' | ']' | '}' => {
+                    let opening = match c {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    match brackets.pop() {
+                        Some((open, _)) if open == opening => {}
+                        Some((open, line)) => {
+                            return Err(format!(
+                                "line {}: `{}` closes the `{}` opened on line {}",
+                                number, c, open, line
+                            ))
+                        }
+                        None => return Err(format!("line {}: `{}` closes nothing", number, c)),
+                    }
+                }
+                _ => {}
+            }
+            if !c.is_whitespace() {
+                state.last = Some(c);
+            }
+            at += 1;
+        }
+        // only triple quoted strings and template literals run over lines, a
+        // backslash at the end continues the others
+        if let Some((quote, triple, started)) = string {
+            if !triple && quote != '`' && !line.ends_with('\\') {
+                return Err(format!("line {}: the string is not closed", started));
+            }
+        }
+        continued = state.last == Some('\\');
+        states.push(state);
+    }
+    if let Some((_, _, line)) = string {
+        return Err(format!("line {}: the string is never closed", line));
+    }
+    if let Some(line) = block_comment {
+        return Err(format!("line {}: the comment is never closed", line));
+    }
+    if let Some((open, line)) = brackets.pop() {
+        return Err(format!("line {}: `{}` is never closed", line, open));
+    }
+    Ok(states)
+}
+
+// the brackets and strings, then that blocks are indented after a colon and
+// dedented back to a level used before
+fn check_python(text: &str) -> Result<(), String> {
+    let states = scan(text, true)?;
+    let mut levels = vec![0];
+    let mut opens_block = false;
+    for (index, (line, state)) in text.split('\n').zip(&states).enumerate() {
+        let number = index + 1;
+        if state.continued {
+            if state.last.is_some() {
+                opens_block = state.last == Some(':');
+            }
+            continue;
+        }
+        // blank lines and comments are not indented
+        if state.last.is_none() {
+            continue;
+        }
+        let indent: usize = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 8 } else { 1 })
+            .sum();
+        let level = *levels.last().unwrap_or(&0);
+        if opens_block {
+            if indent <= level {
+                return Err(format!("line {}: an indented block is expected", number));
+            }
+            levels.push(indent);
+        } else if indent > level {
+            return Err(format!("line {}: the indent is unexpected", number));
+        } else {
+            while levels.last().is_some_and(|level| *level > indent) {
+                levels.pop();
+            }
+            if levels.last() != Some(&indent) {
+                return Err(format!(
+                    "line {}: the dedent matches no outer indentation",
+                    number
+                ));
+            }
+        }
+        opens_block = state.last == Some(':');
+    }
+    if opens_block {
+        return Err(format!(
+            "line {}: an indented block is expected",
+            states.len()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typography_becomes_ascii() {
+        assert_eq!(
+            to_ascii("print(\u{201C}a \u{2014} b\u{2026}\u{201D})"),
+            "print(\"a - b...\")"
+        );
+    }
+
+    #[test]
+    fn numbers_and_keywords_are_fixed() {
+        assert_eq!(to_ascii("x = 1O0 + 2l"), "x = 100 + 21");
+        assert_eq!(to_ascii("pub1ic c1ass A {"), "public class A {");
+        assert_eq!(to_ascii("f0r i in range(3):"), "for i in range(3):");
+        assert_eq!(to_ascii("e1se:"), "else:");
+        assert_eq!(to_ascii("whi1e(x)"), "while(x)");
+    }
+
+    #[test]
+    fn identifiers_are_left_alone() {
+        assert_eq!(to_ascii("l0 = O1 + d0;"), "l0 = O1 + d0;");
+        assert_eq!(to_ascii("f(d0, l0)"), "f(d0, l0)");
+        assert_eq!(to_ascii("x.d0()"), "x.d0()");
+        assert_eq!(to_ascii("0x1F + 0b10"), "0x1F + 0b10");
+    }
+
+    #[test]
+    fn python_indentation_is_checked() {
+        let check = |text| CodeLanguage::Python.check(text);
+        assert!(check("def f(x):\n    if x:\n        return 1\n    return 2\n").is_ok());
+        assert!(check("x = [\n  1,\n      2]\n").is_ok());
+        assert_eq!(
+            check("def f():\nreturn 1"),
+            Err("line 2: an indented block is expected".into())
+        );
+        assert_eq!(
+            check("if x:\n    a\n  b"),
+            Err("line 3: the dedent matches no outer indentation".into())
+        );
+        assert_eq!(
+            check("a = 1\n    b = 2"),
+            Err("line 2: the indent is unexpected".into())
+        );
+        assert_eq!(
+            check("s = '''\n# not a comment (\n'''\nt = 'open"),
+            Err("line 4: the string is not closed".into())
+        );
+    }
+
+    #[test]
+    fn rust_snippets_pass() {
+        let check = |text| CodeLanguage::Rust.check(text);
+        assert!(check("fn main() {\n    println!(\"hi\");\n}").is_ok());
+        assert!(check("let x = 1;\nx + 1").is_ok());
+        assert!(check("fn main() {\n    let x = 1 +;\n}")
+            .unwrap_err()
+            .starts_with("line 2:"));
+        assert!(check("fn main() {\n    let x = (1;\n}")
+            .unwrap_err()
+            .contains("is not closed"));
+    }
+
+    #[test]
+    fn brackets_are_matched() {
+        let check = |text| CodeLanguage::Other.check(text);
+        assert!(check("f(a[1], \"(\") // )").is_ok());
+        assert_eq!(
+            check("f(a]\n"),
+            Err("line 1: `]` closes the `(` opened on line 1".into())
+        );
+        assert_eq!(
+            check("/* {\n}"),
+            Err("line 1: the comment is never closed".into())
+        );
+        assert_eq!(check("{\n\n"), Err("line 1: `{` is never closed".into()));
+    }
+
+    #[test]
+    fn xml_elements_are_closed() {
+        let check = |text| CodeLanguage::Xml.check(text);
+        assert!(check("<a>\n  <b x=\"1\"/>\n</a>").is_ok());
+        assert_eq!(
+            check("<a>\n  <b>\n</b>"),
+            Err("line 1: <a> is never closed".into())
+        );
+        assert!(check("<a>\n</b>").unwrap_err().starts_with("line 2:"));
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    error::Error,
+    sync::mpsc::{self, Receiver},
+};
+use tracing::error;
+use tts::Tts;
+
+// Reads text aloud a sentence at a time, speech engines cannot pause mid
+// utterance so pausing stops and resuming starts the sentence over
+pub struct Reader {
+    tts: Tts,
+    // a message for every utterance that finished playing
+    finished: Receiver<()>,
+    sentences: Vec<String>,
+    // sentence being spoken, or to speak on resuming
+    current: usize,
+    paused: bool,
+}
+
+// split after sentence ending punctuation and at line breaks, OCR text often
+// has lines without any
+fn sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut characters = text.char_indices().peekable();
+    while let Some((index, character)) = characters.next() {
+        let ends = match character {
+            '\n' => true,
+            '.' | '!' | '?' => characters
+                .peek()
+                .is_none_or(|(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if ends {
+            let end = index + character.len_utf8();
+            sentences.push(text[start..end].trim().to_string());
+            start = end;
+        }
+    }
+    sentences.push(text[start..].trim().to_string());
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+impl Reader {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let tts =
+            Tts::default().map_err(|err| format!("Failed to start text to speech: {}", err))?;
+        let (sender, finished) = mpsc::channel();
+        tts.on_utterance_end(Some(Box::new(move |_| {
+            let _ = sender.send(());
+        })))
+        .map_err(|err| format!("Text to speech cannot report progress: {}", err))?;
+        Ok(Reader {
+            tts,
+            finished,
+            sentences: Vec::new(),
+            current: 0,
+            paused: false,
+        })
+    }
+
+    // `rate` is relative to the normal speed of the engine
+    pub fn set_rate(&mut self, rate: f32) {
+        let rate = (self.tts.normal_rate() * rate).clamp(self.tts.min_rate(), self.tts.max_rate());
+        if let Err(err) = self.tts.set_rate(rate) {
+            error!("Error setting speech rate: {}", err);
+        }
+    }
+
+    fn speak_current(&mut self) -> Result<(), Box<dyn Error>> {
+        let sentence = self.sentences[self.current].as_str();
+        self.tts
+            .speak(sentence, true)
+            .map_err(|err| format!("Failed to speak: {}", err))?;
+        Ok(())
+    }
+
+    pub fn read(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        self.stop();
+        self.sentences = sentences(text);
+        if self.sentences.is_empty() {
+            return Ok(());
+        }
+        self.speak_current()
+    }
+
+    // reading, paused or not
+    pub fn is_reading(&self) -> bool {
+        !self.sentences.is_empty()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        if let Err(err) = self.tts.stop() {
+            error!("Error pausing speech: {}", err);
+        }
+    }
+
+    pub fn resume(&mut self) -> Result<(), Box<dyn Error>> {
+        self.paused = false;
+        self.speak_current()
+    }
+
+    pub fn stop(&mut self) {
+        if self.is_reading() {
+            if let Err(err) = self.tts.stop() {
+                error!("Error stopping speech: {}", err);
+            }
+        }
+        self.sentences.clear();
+        self.current = 0;
+        self.paused = false;
+    }
+
+    // move on to the next sentence once the last one was spoken, call every frame
+    pub fn update(&mut self) -> Result<(), Box<dyn Error>> {
+        while self.finished.try_recv().is_ok() {
+            if self.paused || !self.is_reading() {
+                continue;
+            }
+            self.current += 1;
+            if self.current == self.sentences.len() {
+                self.stop();
+            } else {
+                self.speak_current()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{flow::Flow, locale::tr};
+use imgui::{MouseButton, Ui};
+use std::{collections::HashSet, ops::Range};
+use transcribewizard::spelling::{self, SpellChecker};
+
+// underline color of misspelled words
+const UNDERLINE: [f32; 4] = [0.9, 0.2, 0.2, 1.0];
+
+const POPUP: &str = "spelling_suggestions";
+
+// Misspelled word the suggestion popup is open for
+struct Target {
+    range: Range<usize>,
+    word: String,
+    suggestions: Vec<String>,
+}
+
+// Read-only view of the text with misspelled words underlined
+pub struct SpellcheckView {
+    checker: SpellChecker,
+    // words the user told to leave alone this session
+    ignored: HashSet<String>,
+    target: Option<Target>,
+}
+
+impl SpellcheckView {
+    pub fn new(checker: SpellChecker) -> Self {
+        SpellcheckView {
+            checker,
+            ignored: HashSet::new(),
+            target: None,
+        }
+    }
+
+    fn is_misspelled(&self, word: &str) -> bool {
+        !self.ignored.contains(word) && !self.checker.check(word)
+    }
+
+    // corrections for a word, none when it is spelled right
+    pub fn suggestions(&self, word: &str) -> Vec<String> {
+        if self.is_misspelled(word) {
+            self.checker.suggest(word)
+        } else {
+            Vec::new()
+        }
+    }
+
+    // draw the text wrapped to the window, right-clicking a misspelled word offers
+    // corrections that are written back into `text`
+    pub fn draw(&mut self, ui: &Ui, text: &mut String) {
+        let mut flow = Flow::new(ui);
+        let draw_list = ui.get_window_draw_list();
+
+        let mut line_start = 0;
+        for line in text.split('\n') {
+            for chunk in line.split(' ').filter(|chunk| !chunk.is_empty()) {
+                let chunk_start = line_start + (chunk.as_ptr() as usize - line.as_ptr() as usize);
+                flow.place(ui, chunk);
+                ui.text(chunk);
+                let misspelled: Vec<Range<usize>> = spelling::words(chunk)
+                    .into_iter()
+                    .filter(|word| self.is_misspelled(&chunk[word.clone()]))
+                    .collect();
+                if misspelled.is_empty() {
+                    continue;
+                }
+
+                let [left, _] = ui.item_rect_min();
+                let bottom = ui.item_rect_max()[1];
+                let mouse = ui.io().mouse_pos[0];
+                // the misspelled word under the mouse, chunks like "teh,wrold" hold several
+                let mut hovered = misspelled[0].clone();
+                for word in &misspelled {
+                    let start = left + ui.calc_text_size(&chunk[..word.start])[0];
+                    let end = left + ui.calc_text_size(&chunk[..word.end])[0];
+                    draw_list
+                        .add_line([start, bottom], [end, bottom], UNDERLINE)
+                        .build();
+                    if mouse >= start {
+                        hovered = word.clone();
+                    }
+                }
+                if ui.is_item_clicked_with_button(MouseButton::Right) {
+                    let word = hovered;
+                    let range = chunk_start + word.start..chunk_start + word.end;
+                    let word = chunk[word].to_string();
+                    self.target = Some(Target {
+                        suggestions: self.checker.suggest(&word),
+                        range,
+                        word,
+                    });
+                    ui.open_popup(POPUP);
+                }
+            }
+            flow.break_line(ui);
+            line_start += line.len() + 1;
+        }
+
+        ui.popup(POPUP, || {
+            let Some(target) = &self.target else {
+                return;
+            };
+            // the text may have changed since the popup opened
+            if text.get(target.range.clone()) != Some(target.word.as_str()) {
+                ui.close_current_popup();
+                return;
+            }
+            if target.suggestions.is_empty() {
+                ui.text_disabled(tr("No suggestions"));
+            }
+            for suggestion in &target.suggestions {
+                if ui.selectable(suggestion) {
+                    text.replace_range(target.range.clone(), suggestion);
+                }
+            }
+            ui.separator();
+            if ui.selectable(tr("Ignore")) {
+                self.ignored.insert(target.word.clone());
+            }
+        });
+    }
+}
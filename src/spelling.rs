@@ -0,0 +1,63 @@
+use spellbook::Dictionary;
+use std::{error::Error, fs, ops::Range, path::Path};
+
+/// A Hunspell dictionary, the `.dic` file with its `.aff` file next to it.
+pub struct SpellChecker {
+    dictionary: Dictionary,
+}
+
+impl SpellChecker {
+    /// Load `dic` and the `.aff` file of the same name.
+    pub fn load(dic: &Path) -> Result<Self, Box<dyn Error>> {
+        let aff = dic.with_extension("aff");
+        let read = |path: &Path| {
+            fs::read_to_string(path)
+                .map_err(|err| format!("Failed to read dictionary {}: {}", path.display(), err))
+        };
+        let dictionary = Dictionary::new(&read(&aff)?, &read(dic)?)
+            .map_err(|err| format!("Failed to parse dictionary {}: {}", dic.display(), err))?;
+        Ok(SpellChecker { dictionary })
+    }
+
+    pub fn check(&self, word: &str) -> bool {
+        self.dictionary.check(word)
+    }
+
+    /// Corrections for a misspelled word, most likely first.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        self.dictionary.suggest(word, &mut suggestions);
+        suggestions
+    }
+}
+
+/// Byte ranges of the words in `text` worth checking.
+///
+/// Words are runs of letters with apostrophes inside them, single letters are
+/// left out since OCR output is full of stray ones that are not typos.
+pub fn words(text: &str) -> Vec<Range<usize>> {
+    let is_apostrophe = |character: char| character == '\'' || character == '’';
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut characters = text.char_indices().peekable();
+    while let Some((index, character)) = characters.next() {
+        let next_is_letter = characters
+            .peek()
+            .is_some_and(|(_, next)| next.is_alphabetic());
+        let in_word = character.is_alphabetic()
+            || (start.is_some() && is_apostrophe(character) && next_is_letter);
+        match (in_word, start) {
+            (true, None) => start = Some(index),
+            (false, Some(word_start)) => {
+                words.push(word_start..index);
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(word_start) = start {
+        words.push(word_start..text.len());
+    }
+    words.retain(|word| text[word.clone()].chars().nth(1).is_some());
+    words
+}
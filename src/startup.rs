@@ -0,0 +1,100 @@
+use sdl2::messagebox::{show_simple_message_box, MessageBoxFlag};
+use std::{fmt::Display, process::ExitCode};
+use tracing::{error, warn};
+
+// The parts of the app brought up at start, each reported with what usually
+// gets it working when it fails
+#[derive(Clone, Copy)]
+pub enum Subsystem {
+    Sdl,
+    Video,
+    Window,
+    Renderer,
+    Events,
+    Clipboard,
+    PrimarySelection,
+}
+
+impl Subsystem {
+    fn name(self) -> &'static str {
+        match self {
+            Subsystem::Sdl => "sdl",
+            Subsystem::Video => "video",
+            Subsystem::Window => "window",
+            Subsystem::Renderer => "renderer",
+            Subsystem::Events => "events",
+            Subsystem::Clipboard => "clipboard",
+            Subsystem::PrimarySelection => "primary-selection",
+        }
+    }
+
+    fn what(self) -> &'static str {
+        match self {
+            Subsystem::Sdl => "Failed to initialize SDL",
+            Subsystem::Video => "Failed to initialize video",
+            Subsystem::Window => "Failed to create the window",
+            Subsystem::Renderer => "Failed to set up rendering",
+            Subsystem::Events => "Failed to read input events",
+            Subsystem::Clipboard => "Failed to open the clipboard",
+            Subsystem::PrimarySelection => "Failed to open the primary selection",
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            Subsystem::Sdl | Subsystem::Video | Subsystem::Events => {
+                "No display could be reached. On Linux, check that DISPLAY or WAYLAND_DISPLAY \
+                 is set and the SDL2 library is installed. Without a display, --serve, --stdin \
+                 and --bench still work."
+            }
+            Subsystem::Window | Subsystem::Renderer => {
+                "No OpenGL 2.1 or newer is available. Updating the graphics driver usually \
+                 helps. In virtual machines, enable 3D acceleration or run with --software to \
+                 render on the CPU with Mesa's llvmpipe."
+            }
+            Subsystem::Clipboard => {
+                "The app keeps working without copying and pasting. On Linux the clipboard \
+                 needs an X server, or XWayland under Wayland."
+            }
+            Subsystem::PrimarySelection => {
+                "Only the clipboard can be read and copied to. On Wayland the primary selection \
+                 needs a compositor with the data control protocol."
+            }
+        }
+    }
+}
+
+// report a subsystem the app can do without, the log on standard error gets
+// its name and hint as fields of their own
+pub fn degraded(subsystem: Subsystem, err: impl Display) {
+    warn!(
+        subsystem = subsystem.name(),
+        hint = subsystem.hint(),
+        "{}: {}",
+        subsystem.what(),
+        err
+    );
+}
+
+// report why the app cannot start in the log and in a dialog, there is no
+// window to show it in. Main returns what it gives back, so what was set up
+// before, the log file among it, is dropped and flushed
+pub fn fail(subsystem: Subsystem, err: impl Display) -> ExitCode {
+    error!(
+        subsystem = subsystem.name(),
+        hint = subsystem.hint(),
+        "{}: {}",
+        subsystem.what(),
+        err
+    );
+    let message = format!("{}: {}\n\n{}", subsystem.what(), err, subsystem.hint());
+    if let Err(err) = show_simple_message_box(
+        MessageBoxFlag::ERROR,
+        "Transcribe Wizard cannot start",
+        &message,
+        None,
+    ) {
+        error!("Error showing message box: {}", err);
+    }
+    ExitCode::FAILURE
+}
@@ -0,0 +1,56 @@
+use crate::locale::tr;
+use imgui::{Condition, Ui};
+use transcribewizard::stats::TextStats;
+
+// Small window with the counts of the text, kept up to date as it is edited
+#[derive(Default)]
+pub struct StatisticsPanel {
+    // the text the stats were counted for, recounted only once it changed
+    counted: String,
+    stats: TextStats,
+}
+
+// "3 min 20 s", or only seconds under a minute
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{} s", seconds),
+        _ => format!("{} min {} s", seconds / 60, seconds % 60),
+    }
+}
+
+impl StatisticsPanel {
+    pub fn draw(&mut self, ui: &Ui, text: &str, open: &mut bool) {
+        if self.counted != text {
+            self.counted = text.to_string();
+            self.stats = TextStats::of(text);
+        }
+        let stats = &self.stats;
+        ui.window(format!("{}###Statistics", tr("Statistics")))
+            .size([260.0, 0.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                ui.text(format!("{}: {}", tr("Characters"), stats.characters));
+                ui.text(format!(
+                    "{}: {}",
+                    tr("Characters without spaces"),
+                    stats.characters_no_spaces
+                ));
+                ui.text(format!("{}: {}", tr("Words"), stats.words));
+                ui.text(format!("{}: {}", tr("Lines"), stats.lines));
+                ui.text(format!(
+                    "{}: {}",
+                    tr("Reading time"),
+                    format_duration(stats.reading_time.as_secs())
+                ));
+                match stats.language {
+                    Some(language) => ui.text(format!(
+                        "{}: {} ({})",
+                        tr("Language"),
+                        language.name,
+                        language.script.name()
+                    )),
+                    None => ui.text(format!("{}: {}", tr("Language"), tr("unknown"))),
+                }
+            });
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+use whatlang::{Lang, Script};
+
+// average silent reading speed of adults, in words per minute
+const READING_SPEED: f32 = 238.0;
+
+// shorter texts are too little to tell the language from
+const MIN_LANGUAGE_CHARS: usize = 20;
+
+/// The language a text is written in and its script.
+#[derive(Clone, Copy, PartialEq)]
+pub struct DetectedLanguage {
+    /// English name, as [`crate::translate::LANGUAGES`] has it.
+    pub name: &'static str,
+    pub script: Script,
+}
+
+/// The language `text` is most likely in, `None` when it is too short or too
+/// mixed to tell.
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    if text.chars().filter(|c| !c.is_whitespace()).count() < MIN_LANGUAGE_CHARS {
+        return None;
+    }
+    let info = whatlang::detect(text).filter(|info| info.is_reliable())?;
+    let name = match info.lang() {
+        // the name the translation services know it by
+        Lang::Cmn => "Chinese",
+        lang => lang.eng_name(),
+    };
+    Some(DetectedLanguage {
+        name,
+        script: info.script(),
+    })
+}
+
+/// Counts of a text, as word-limited submissions and the like count them.
+#[derive(Clone, Default, PartialEq)]
+pub struct TextStats {
+    pub characters: usize,
+    /// Characters other than whitespace.
+    pub characters_no_spaces: usize,
+    /// Runs of characters between whitespace.
+    pub words: usize,
+    /// Lines with anything on them.
+    pub lines: usize,
+    pub reading_time: Duration,
+    /// See [`detect_language`].
+    pub language: Option<DetectedLanguage>,
+}
+
+impl TextStats {
+    pub fn of(text: &str) -> Self {
+        let characters = text.chars().count();
+        let characters_no_spaces = text.chars().filter(|c| !c.is_whitespace()).count();
+        let words = text.split_whitespace().count();
+        let lines = text.lines().filter(|line| !line.trim().is_empty()).count();
+        TextStats {
+            characters,
+            characters_no_spaces,
+            words,
+            lines,
+            reading_time: Duration::from_secs_f32(words as f32 / READING_SPEED * 60.0),
+            language: detect_language(text),
+        }
+    }
+}
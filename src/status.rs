@@ -0,0 +1,110 @@
+use crate::locale::tr;
+use imgui::{Condition, StyleColor, Ui, WindowFlags};
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+use tracing::error;
+
+// how long an error stays up in the corner of the window
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+const ERROR_COLOR: [f32; 4] = [1.0, 0.4, 0.4, 1.0];
+
+struct Report {
+    message: String,
+    at: Instant,
+}
+
+// Errors of everything the app does in the background or on a click, shown in
+// a status bar below the editor and as a toast, so they never take the place
+// of the text. Every one is logged too, for the Log window
+#[derive(Default)]
+pub struct Status {
+    last: Option<Report>,
+    // errors since the start or the last clear
+    count: usize,
+    // the last report is shown as a toast until then
+    toast_until: Option<Instant>,
+}
+
+impl Status {
+    // report a failure of `what`, e.g. "Error getting text from clipboard", in
+    // the language of the interface, the log keeps it in English
+    pub fn error(&mut self, what: &str, err: impl Display) {
+        error!("{}: {}", what, err);
+        let message = format!("{}: {}", tr(what), err);
+        let at = Instant::now();
+        self.last = Some(Report { message, at });
+        self.count += 1;
+        self.toast_until = Some(at + TOAST_DURATION);
+    }
+
+    // forget the errors, once the log they are in was cleared
+    pub fn clear(&mut self) {
+        *self = Status::default();
+    }
+
+    // the line at the bottom of the main window with the last error, returns
+    // true when the button to see them all in the log was clicked
+    pub fn draw_bar(&mut self, ui: &Ui) -> bool {
+        let label = format!("{} ({})", tr("Errors"), self.count);
+        match &self.last {
+            Some(report) => {
+                ui.text_colored(ERROR_COLOR, &report.message);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(ago(report.at));
+                }
+            }
+            None => ui.text_disabled(tr("No errors")),
+        }
+        let width = ui.calc_text_size(&label)[0] + ui.clone_style().frame_padding[0] * 2.0;
+        ui.same_line_with_pos((ui.window_content_region_max()[0] - width).max(0.0));
+        ui.button(format!("{}###errors", label))
+    }
+
+    // the last error over the bottom right corner, until it times out or is clicked
+    pub fn draw_toast(&mut self, ui: &Ui) {
+        let Some(until) = self.toast_until else {
+            return;
+        };
+        let (Some(report), true) = (&self.last, Instant::now() < until) else {
+            self.toast_until = None;
+            return;
+        };
+        let [width, height] = ui.io().display_size;
+        let margin = 10.0;
+        let mut dismissed = false;
+        ui.window("##toast")
+            .position([width - margin, height - margin], Condition::Always)
+            .position_pivot([1.0, 1.0])
+            .bg_alpha(0.9)
+            .flags(
+                WindowFlags::NO_DECORATION
+                    | WindowFlags::ALWAYS_AUTO_RESIZE
+                    | WindowFlags::NO_SAVED_SETTINGS
+                    | WindowFlags::NO_FOCUS_ON_APPEARING
+                    | WindowFlags::NO_NAV
+                    | WindowFlags::NO_MOVE,
+            )
+            .build(|| {
+                let _wrap = ui.push_text_wrap_pos_with_pos(width / 2.0);
+                let _color = ui.push_style_color(StyleColor::Text, ERROR_COLOR);
+                ui.text(&report.message);
+                dismissed = ui.is_window_hovered() && ui.is_mouse_clicked(imgui::MouseButton::Left);
+            });
+        if dismissed {
+            self.toast_until = None;
+        }
+    }
+}
+
+// how long ago something happened, roughly
+fn ago(at: Instant) -> String {
+    let seconds = at.elapsed().as_secs();
+    match seconds {
+        0..=59 => format!("{}s ago", seconds),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        _ => format!("{}h ago", seconds / 3600),
+    }
+}
@@ -0,0 +1,174 @@
+use image::RgbaImage;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+// rows of a new frame that must land where the scroll offset puts them, fewer
+// are more likely an accident of blank or repeated lines
+const MIN_MATCHING_ROWS: usize = 8;
+
+/// What became of a frame given to [`Stitcher::add`].
+#[derive(Clone, Copy, PartialEq)]
+pub enum Added {
+    /// The frame showed more of the content, which was appended.
+    Grew,
+    /// Nothing scrolled since the last frame.
+    Unchanged,
+    /// The frame does not overlap the last one, scrolled too far at once, or
+    /// differs in size. It is left out.
+    Lost,
+}
+
+/// Joins frames of a region captured while its content scrolls down into one
+/// tall image.
+///
+/// Each frame is lined up with the previous one by the rows they share: rows
+/// are compared exactly, which holds for text rendered again after scrolling,
+/// and the offset most rows agree on is how far the content moved. Rows that
+/// stay put at the bottom, such as a status bar or a message input, are kept
+/// out of the image.
+pub struct Stitcher {
+    width: u32,
+    // the image so far, RGBA rows one after the other
+    pixels: Vec<u8>,
+    last: Option<RgbaImage>,
+    // rows at the bottom of every frame that never scroll
+    footer: u32,
+    frames: usize,
+}
+
+fn row_hash(image: &RgbaImage, y: u32) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    row(image, y).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn row(image: &RgbaImage, y: u32) -> &[u8] {
+    let stride = image.width() as usize * 4;
+    &image.as_raw()[y as usize * stride..(y as usize + 1) * stride]
+}
+
+// a row of one colour, which matches every other blank row and says nothing
+// about the offset
+fn is_blank(row: &[u8]) -> bool {
+    row.chunks_exact(4).all(|pixel| pixel == &row[..4])
+}
+
+impl Default for Stitcher {
+    fn default() -> Self {
+        Stitcher::new()
+    }
+}
+
+impl Stitcher {
+    pub fn new() -> Self {
+        Stitcher {
+            width: 0,
+            pixels: Vec::new(),
+            last: None,
+            footer: 0,
+            frames: 0,
+        }
+    }
+
+    /// Frames that went into the image.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Height of the image so far.
+    pub fn height(&self) -> u32 {
+        match self.width {
+            0 => 0,
+            width => (self.pixels.len() / (width as usize * 4)) as u32,
+        }
+    }
+
+    /// How far the content of `next` moved up from `last`, `None` when the
+    /// two do not line up.
+    fn offset(last: &RgbaImage, next: &RgbaImage) -> Option<u32> {
+        let mut rows: HashMap<u64, Vec<u32>> = HashMap::new();
+        for y in 0..last.height() {
+            if !is_blank(row(last, y)) {
+                rows.entry(row_hash(last, y)).or_default().push(y);
+            }
+        }
+        let mut votes: HashMap<u32, usize> = HashMap::new();
+        let mut content_rows = 0;
+        for y in 0..next.height() {
+            if is_blank(row(next, y)) {
+                continue;
+            }
+            content_rows += 1;
+            for &last_y in rows.get(&row_hash(next, y)).into_iter().flatten() {
+                if last_y >= y {
+                    *votes.entry(last_y - y).or_default() += 1;
+                }
+            }
+        }
+        // rows that did not move vote for 0, such as a header that stays put
+        let unchanged = votes.get(&0).copied().unwrap_or(0);
+        let moved = votes
+            .into_iter()
+            .filter(|&(offset, count)| offset > 0 && count >= MIN_MATCHING_ROWS)
+            .max_by_key(|&(offset, count)| (count, std::cmp::Reverse(offset)))
+            .map(|(offset, _)| offset);
+        match moved {
+            Some(offset) => Some(offset),
+            // most of it still in place, rather than replaced by what lay far below
+            None if unchanged * 2 >= content_rows => Some(0),
+            None => None,
+        }
+    }
+
+    // rows at the bottom that are the same in both frames
+    fn fixed_bottom(last: &RgbaImage, next: &RgbaImage) -> u32 {
+        (0..next.height())
+            .rev()
+            .take_while(|&y| row(last, y) == row(next, y))
+            .count() as u32
+    }
+
+    pub fn add(&mut self, frame: &RgbaImage) -> Added {
+        let Some(last) = &self.last else {
+            self.width = frame.width();
+            self.pixels = frame.as_raw().clone();
+            self.last = Some(frame.clone());
+            self.frames = 1;
+            return Added::Grew;
+        };
+        if frame.dimensions() != last.dimensions() {
+            return Added::Lost;
+        }
+        let Some(offset) = Stitcher::offset(last, frame) else {
+            return Added::Lost;
+        };
+        if offset == 0 {
+            return Added::Unchanged;
+        }
+
+        let height = frame.height();
+        let footer = Stitcher::fixed_bottom(last, frame).min(height - offset);
+        if footer > self.footer {
+            // rows appended before the footer was known, the first frame went in whole
+            let stride = self.width as usize * 4;
+            let extra = (footer - self.footer) as usize * stride;
+            self.pixels
+                .truncate(self.pixels.len().saturating_sub(extra));
+            self.footer = footer;
+        }
+        for y in height - offset - self.footer.min(height - offset)..height - self.footer {
+            self.pixels.extend_from_slice(row(frame, y));
+        }
+        self.last = Some(frame.clone());
+        self.frames += 1;
+        Added::Grew
+    }
+
+    /// The stitched image, `None` before the first frame.
+    pub fn image(&self) -> Option<RgbaImage> {
+        RgbaImage::from_raw(self.width, self.height(), self.pixels.clone())
+            .filter(|_| self.last.is_some())
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    clipboard_frames, clipboard_gif_format, clipboard_image,
+    config::Config,
+    ensure_pool, frames_capture,
+    history::History,
+    instance::Reply,
+    masking::SecretGuard,
+    open_preview,
+    preview::Preview,
+    record_capture,
+    status::Status,
+    Capture, FileJob, Placement, PoolSettings, SharedCache,
+};
+use clipboard_rs::ContentFormat;
+use image::DynamicImage;
+use imgui_glow_renderer::AutoRenderer;
+use std::{
+    error::Error,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
+};
+use tracing::info;
+use transcribewizard::{
+    ocr::{self, Progress},
+    pool::WorkerPool,
+    preprocess::Preprocessing,
+    TranscribeError, TranscriptionResult,
+};
+
+// images with at least this many pixels are streamed, about a screenshot or a
+// page scanned at 150 dpi, smaller ones are read quickly enough in one go
+pub const STREAM_MIN_PIXELS: u64 = 2_000_000;
+
+pub enum StreamUpdate {
+    Progress(Progress),
+    Done(Result<TranscriptionResult, String>),
+}
+
+// A large clipboard image being read on the pool, whose lines show up in the
+// preview and the text as they are recognized instead of all at the end
+pub struct StreamJob {
+    receiver: Receiver<StreamUpdate>,
+    // shared with the worker reading it, which lets go of it once done
+    pub image: Arc<DynamicImage>,
+    // the result goes on the clipboard, as it would have when read in one go
+    pub copy: bool,
+    // the tab it was started from, which gets the lines whichever tab is open
+    pub tab: usize,
+    // the text and cursor from before, the lines read so far are placed into them
+    pub before: (String, Option<usize>),
+    // the lines read so far, the updates only bring the new ones
+    read: Option<TranscriptionResult>,
+}
+
+impl StreamJob {
+    pub fn start(
+        pool: &WorkerPool,
+        image: DynamicImage,
+        preprocessing: Preprocessing,
+        copy: bool,
+        tab: usize,
+        before: (String, Option<usize>),
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let image = Arc::new(image);
+        let streamed = image.clone();
+        pool.submit(move |backend| {
+            let result = backend.map_err(str::to_string).and_then(|backend| {
+                let progress_sender = sender.clone();
+                let mut progress = |progress| {
+                    let _ = progress_sender.send(StreamUpdate::Progress(progress));
+                };
+                ocr::transcribe_preprocessed_streaming(
+                    backend,
+                    &streamed,
+                    &preprocessing,
+                    &mut progress,
+                )
+                .map_err(|err| err.to_string())
+            });
+            let _ = sender.send(StreamUpdate::Done(result));
+        });
+        StreamJob {
+            receiver,
+            image,
+            copy,
+            tab,
+            before,
+            read: None,
+        }
+    }
+
+    // what came in since the last call, in order
+    pub fn updates(&self) -> Vec<StreamUpdate> {
+        self.receiver.try_iter().collect()
+    }
+
+    // the lines read so far, with those of a Progress::Read update added
+    pub fn gather(&mut self, read: &TranscriptionResult) -> &TranscriptionResult {
+        let gathered = self.read.get_or_insert_with(|| TranscriptionResult {
+            lines: Vec::new(),
+            ..read.clone()
+        });
+        gathered.lines.extend(read.lines.iter().cloned());
+        gathered
+    }
+}
+
+// What stream_clipboard made of the clipboard
+pub enum Streamed {
+    Job(StreamJob),
+    // the frames of an animated GIF, read on the pool
+    Frames(FileJob),
+    // left to be read in one go, along with the image when it was read already
+    Unstreamed(Option<DynamicImage>),
+}
+
+// stream the clipboard image when it is what gets transcribed and large enough
+// to be worth it
+pub fn stream_clipboard(
+    clipboard_context: &SystemClipboard,
+    config: &Config,
+    cache: &SharedCache,
+    pool: &mut Option<(WorkerPool, PoolSettings)>,
+    copy: bool,
+    tab: usize,
+    before: (&str, Option<usize>),
+) -> Result<Streamed, Box<dyn Error>> {
+    // files and text win over an image in clipboard_capture
+    if clipboard_context.has(ContentFormat::Files) || clipboard_context.has(ContentFormat::Text) {
+        return Ok(Streamed::Unstreamed(None));
+    }
+    /* every frame of an animated GIF is read, the platform gives only the first */
+    if clipboard_gif_format(clipboard_context).is_some() {
+        let mut frames = clipboard_frames(clipboard_context, config)?;
+        if frames.len() < 2 {
+            return Ok(Streamed::Unstreamed(frames.pop()));
+        }
+        info!("Read GIF with {} frames from clipboard", frames.len());
+        let pool = ensure_pool(pool, config, cache)?;
+        let (sender, job) = mpsc::channel();
+        let config = config.clone();
+        pool.submit(move |backend| {
+            let _ = sender.send(
+                backend
+                    .map_err(str::to_string)
+                    .and_then(|backend| frames_capture(backend, frames, &config)),
+            );
+        });
+        return Ok(Streamed::Frames(job));
+    }
+    if !config.stream_results || !clipboard_context.has(ContentFormat::Image) {
+        return Ok(Streamed::Unstreamed(None));
+    }
+    let image = clipboard_image(clipboard_context, config)?;
+    if (image.width() as u64 * image.height() as u64) < STREAM_MIN_PIXELS {
+        return Ok(Streamed::Unstreamed(Some(image)));
+    }
+    info!(
+        "Streaming {}x{} image from clipboard",
+        image.width(),
+        image.height()
+    );
+    let pool = ensure_pool(pool, config, cache)?;
+    let (text, cursor) = before;
+    Ok(Streamed::Job(StreamJob::start(
+        pool,
+        image,
+        config.preprocessing.clone(),
+        copy,
+        tab,
+        (text.to_string(), cursor),
+    )))
+}
+
+// What a streamed read touches while its lines come in, borrowed from where
+// the main loop keeps it
+pub struct StreamTargets<'a> {
+    pub config: &'a Config,
+    pub clipboard: &'a SystemClipboard,
+    pub renderer: &'a mut AutoRenderer,
+    pub history: &'a mut History,
+    pub preview: &'a mut Option<Preview>,
+    pub secret_guard: &'a mut SecretGuard,
+    pub status: &'a mut Status,
+    // the preview is not opened while the window is hidden
+    pub window_hidden: bool,
+}
+
+// The clipboard image being streamed, one at a time, with the instance
+// waiting for its text when a command started it
+#[derive(Default)]
+pub struct Streaming {
+    job: Option<StreamJob>,
+    reply: Option<Reply>,
+}
+
+impl Streaming {
+    pub fn is_reading(&self) -> bool {
+        self.job.is_some()
+    }
+
+    pub fn start(&mut self, job: StreamJob, reply: Option<Reply>) {
+        self.job = Some(job);
+        self.reply = reply;
+    }
+
+    // show the lines read since the last call, and record the result like any
+    // other once it is done. Returns the texts to place in the tab it was
+    // started from
+    pub fn poll(&mut self, targets: StreamTargets) -> Vec<Placement> {
+        let mut placements = Vec::new();
+        let updates = self
+            .job
+            .as_ref()
+            .map(StreamJob::updates)
+            .unwrap_or_default();
+        for update in updates {
+            let Some(job) = &mut self.job else {
+                break;
+            };
+            match update {
+                StreamUpdate::Progress(progress) => {
+                    if let Progress::Detected(_) = progress {
+                        if !targets.window_hidden {
+                            if let Err(err) = open_preview(
+                                targets.preview,
+                                targets.renderer,
+                                (*job.image).clone(),
+                            ) {
+                                targets
+                                    .status
+                                    .error("Error previewing clipboard image", err);
+                            }
+                        }
+                    }
+                    if let Progress::Read(read) = &progress {
+                        placements.push(Placement {
+                            tab: job.tab,
+                            result: targets.config.render(job.gather(read)),
+                            before: Some(job.before.clone()),
+                            read: None,
+                            merge: false,
+                        });
+                    }
+                    if let Some(preview) = targets.preview.as_mut() {
+                        preview.show_progress(&progress);
+                    }
+                }
+                StreamUpdate::Done(result) => {
+                    let Some(job) = self.job.take() else {
+                        break;
+                    };
+                    if let Some(preview) = targets.preview.as_mut() {
+                        preview.clear_progress();
+                    }
+                    let recorded =
+                        result
+                            .map_err(TranscribeError::Ocr)
+                            .and_then(|result_transcription| {
+                                let capture = Capture {
+                                    text: targets.config.render(&result_transcription),
+                                    image: Some(Arc::unwrap_or_clone(job.image)),
+                                    transcription: Some(result_transcription),
+                                    pages: Vec::new(),
+                                    note: Some("clipboard image".into()),
+                                };
+                                record_capture(
+                                    capture,
+                                    targets.config,
+                                    targets.clipboard,
+                                    targets.renderer,
+                                    targets.history,
+                                    job.copy.then_some(&mut *targets.secret_guard),
+                                )
+                            });
+                    if let Some(reply) = self.reply.take() {
+                        reply.answer(match &recorded {
+                            Ok((result, ..)) => Ok(result.clone()),
+                            Err(err) => Err(err.to_string()),
+                        });
+                    }
+                    match recorded {
+                        Ok((result, result_transcription, result_pages)) => {
+                            placements.push(Placement {
+                                tab: job.tab,
+                                result,
+                                before: Some(job.before),
+                                read: Some((result_transcription, result_pages)),
+                                merge: false,
+                            })
+                        }
+                        Err(err) => {
+                            // the lines read so far are taken back out
+                            placements.push(Placement {
+                                tab: job.tab,
+                                result: String::new(),
+                                before: Some(job.before),
+                                read: None,
+                                merge: false,
+                            });
+                            targets
+                                .status
+                                .error("Error getting text from clipboard", err)
+                        }
+                    }
+                }
+            }
+        }
+        placements
+    }
+}
@@ -0,0 +1,155 @@
+use std::{mem, path::Path, time::Duration};
+
+/// Text shown or spoken over a stretch of time.
+#[derive(Clone)]
+pub struct Cue {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+    /// Who says it, numbered from 1, None when unknown or for text on screen.
+    pub speaker: Option<usize>,
+}
+
+// the text of a cue after the name of its speaker
+fn spoken(cue: &Cue) -> String {
+    match cue.speaker {
+        Some(speaker) => format!("Speaker {}: {}", speaker, cue.text),
+        None => cue.text.clone(),
+    }
+}
+
+// format a duration as hours:minutes:seconds with milliseconds after `separator`
+fn timestamp(duration: Duration, separator: char) -> String {
+    let millis = duration.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+/// One line per cue, prefixed with its time range.
+pub fn timestamped_text(cues: &[Cue]) -> String {
+    cues.iter()
+        .map(|cue| {
+            format!(
+                "[{} --> {}] {}",
+                timestamp(cue.start, '.'),
+                timestamp(cue.end, '.'),
+                spoken(cue).replace('\n', " ")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Render cues as a SubRip subtitle file.
+pub fn srt(cues: &[Cue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(index, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                index + 1,
+                timestamp(cue.start, ','),
+                timestamp(cue.end, ','),
+                spoken(cue)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// WebVTT reads markup in cue text, so the characters starting it are escaped
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Render cues as a WebVTT subtitle file, the format browsers play.
+pub fn vtt(cues: &[Cue]) -> String {
+    let cues: Vec<String> = cues
+        .iter()
+        .map(|cue| {
+            format!(
+                "{} --> {}\n{}\n",
+                timestamp(cue.start, '.'),
+                timestamp(cue.end, '.'),
+                match cue.speaker {
+                    // a voice tag, which players can show or style
+                    Some(speaker) => format!("<v Speaker {}>{}", speaker, escape_vtt(&cue.text)),
+                    None => escape_vtt(&cue.text),
+                }
+            )
+        })
+        .collect();
+    format!("WEBVTT\n\n{}", cues.join("\n"))
+}
+
+/// Break a cue text into lines of at most `max_line` characters at spaces,
+/// words longer than that get a line of their own. 0 leaves it as it is.
+pub fn wrap(text: &str, max_line: usize) -> String {
+    if max_line == 0 {
+        return text.to_string();
+    }
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_line {
+            lines.push(mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Render cues as JSON, an array of objects with the start and end in seconds,
+/// the speaker and the text.
+pub fn json(cues: &[Cue]) -> String {
+    let cues: Vec<serde_json::Value> = cues
+        .iter()
+        .map(|cue| {
+            serde_json::json!({
+                "start": cue.start.as_secs_f64(),
+                "end": cue.end.as_secs_f64(),
+                "speaker": cue.speaker,
+                "text": cue.text,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&cues).unwrap_or_default()
+}
+
+/// Render cues as a file of the format `path` ends in: WebVTT for `.vtt`,
+/// JSON for `.json`, timestamped lines for `.txt` and SubRip otherwise.
+/// Subtitle lines are wrapped at `max_line`.
+pub fn render_for(path: &Path, cues: &[Cue], max_line: usize) -> String {
+    let wrapped: Vec<Cue> = cues
+        .iter()
+        .map(|cue| Cue {
+            text: wrap(&cue.text, max_line),
+            ..cue.clone()
+        })
+        .collect();
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "vtt" => vtt(&wrapped),
+        "json" => json(cues),
+        "txt" => timestamped_text(cues),
+        _ => srt(&wrapped),
+    }
+}
@@ -0,0 +1,179 @@
+use crate::ocr::{Bounds, TranscriptionResult};
+
+// gaps wider than this many word heights separate cells, spaces inside a cell
+// are around half a height
+const CELL_GAP: f32 = 1.2;
+
+/// Rows of cells reconstructed from the word boxes, every row has one cell per
+/// column and empty cells are empty strings.
+pub struct Table {
+    pub rows: Vec<Vec<String>>,
+}
+
+struct Cell {
+    text: String,
+    bounds: Bounds,
+}
+
+fn height(bounds: &Bounds) -> i32 {
+    bounds.bottom - bounds.top
+}
+
+fn center_x(bounds: &Bounds) -> i32 {
+    (bounds.left + bounds.right) / 2
+}
+
+/// Find a grid in the word layout, `None` when the words do not line up in at
+/// least two rows of two columns.
+pub fn detect(transcription: &TranscriptionResult) -> Option<Table> {
+    let mut words: Vec<_> = transcription
+        .lines
+        .iter()
+        .flat_map(|line| &line.words)
+        .filter(|word| !word.text.trim().is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    words.sort_by_key(|word| word.bounds.top + word.bounds.bottom);
+
+    // rows are words overlapping vertically by at least half the smaller height
+    let mut rows: Vec<(Bounds, Vec<_>)> = Vec::new();
+    for word in words {
+        let bounds = word.bounds;
+        let row = rows.last_mut().filter(|(row, _)| {
+            let overlap = row.bottom.min(bounds.bottom) - row.top.max(bounds.top);
+            overlap * 2 >= height(row).min(height(&bounds))
+        });
+        match row {
+            Some((row, row_words)) => {
+                row.top = row.top.min(bounds.top);
+                row.bottom = row.bottom.max(bounds.bottom);
+                row_words.push(word);
+            }
+            None => rows.push((bounds, vec![word])),
+        }
+    }
+
+    // words of a row left to right, grouped into cells at wide gaps
+    let rows: Vec<Vec<Cell>> = rows
+        .into_iter()
+        .map(|(_, mut row_words)| {
+            row_words.sort_by_key(|word| word.bounds.left);
+            let mut heights: Vec<i32> = row_words.iter().map(|word| height(&word.bounds)).collect();
+            heights.sort_unstable();
+            let gap = heights[heights.len() / 2] as f32 * CELL_GAP;
+
+            let mut cells: Vec<Cell> = Vec::new();
+            for word in row_words {
+                match cells.last_mut() {
+                    Some(cell) if ((word.bounds.left - cell.bounds.right) as f32) < gap => {
+                        cell.text.push(' ');
+                        cell.text.push_str(word.text.trim());
+                        cell.bounds.right = cell.bounds.right.max(word.bounds.right);
+                    }
+                    _ => cells.push(Cell {
+                        text: word.text.trim().to_string(),
+                        bounds: word.bounds,
+                    }),
+                }
+            }
+            cells
+        })
+        .collect();
+
+    // columns are the horizontal spans the cells of multi-cell rows overlap in,
+    // single cells such as titles would merge every column into one
+    let mut spans: Vec<(i32, i32)> = rows
+        .iter()
+        .filter(|cells| cells.len() > 1)
+        .flatten()
+        .map(|cell| (cell.bounds.left, cell.bounds.right))
+        .collect();
+    spans.sort_unstable();
+    let mut columns: Vec<(i32, i32)> = Vec::new();
+    for (left, right) in spans {
+        match columns.last_mut() {
+            Some(column) if left <= column.1 => column.1 = column.1.max(right),
+            _ => columns.push((left, right)),
+        }
+    }
+    let multi_cell_rows = rows.iter().filter(|cells| cells.len() > 1).count();
+    if columns.len() < 2 || multi_cell_rows < 2 {
+        return None;
+    }
+
+    // titles above and notes below the grid are not part of it
+    let first = rows.iter().position(|cells| cells.len() > 1)?;
+    let last = rows.iter().rposition(|cells| cells.len() > 1)?;
+
+    let rows = rows
+        .into_iter()
+        .skip(first)
+        .take(last + 1 - first)
+        .map(|cells| {
+            let mut row = vec![String::new(); columns.len()];
+            for cell in cells {
+                // the column the cell is centered in, or the closest one in a gutter
+                let center = center_x(&cell.bounds);
+                let column = columns
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, (left, right))| {
+                        if center < *left {
+                            left - center
+                        } else {
+                            (center - right).max(0)
+                        }
+                    })
+                    .map_or(0, |(index, _)| index);
+                if !row[column].is_empty() {
+                    row[column].push(' ');
+                }
+                row[column].push_str(&cell.text);
+            }
+            row
+        })
+        .collect();
+    Some(Table { rows })
+}
+
+impl Table {
+    /// Comma separated values, quoting cells as RFC 4180 describes.
+    pub fn to_csv(&self) -> String {
+        let mut output = String::new();
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| {
+                    if cell.contains([',', '"', '\n']) {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell.clone()
+                    }
+                })
+                .collect();
+            output.push_str(&cells.join(","));
+            output.push_str("\r\n");
+        }
+        output
+    }
+
+    /// A Markdown table with the first row as header.
+    pub fn to_markdown(&self) -> String {
+        let format_row = |row: &[String]| {
+            let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+        let mut output = String::new();
+        let mut rows = self.rows.iter();
+        if let Some(header) = rows.next() {
+            output.push_str(&format_row(header));
+            output.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+        }
+        for row in rows {
+            output.push_str(&format_row(row));
+        }
+        output
+    }
+}
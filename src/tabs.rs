@@ -0,0 +1,274 @@
+use crate::{
+    cleanup::Cleanup,
+    find::FindBar,
+    locale::tr,
+    translation::TranslationPanel,
+    undo::{UndoStack, UNDO_CAPACITY},
+};
+use imgui::{TabBarFlags, TabItemFlags, Ui};
+use serde::{Deserialize, Serialize};
+use std::mem;
+use transcribewizard::TranscriptionResult;
+
+// The document in the editor, borrowed from where the main loop keeps it
+pub struct Editor<'a> {
+    pub text: &'a mut String,
+    pub cursor: &'a mut Option<usize>,
+    pub transcription: &'a mut Option<TranscriptionResult>,
+    pub pages: &'a mut Vec<TranscriptionResult>,
+    pub undo: &'a mut UndoStack,
+    pub panels: &'a mut Panels,
+}
+
+// What is open on a document besides its text, kept with it while another
+// tab is picked
+#[derive(Default)]
+pub struct Panels {
+    pub find: FindBar,
+    pub translation: TranslationPanel,
+    // the AI cleanup of its text, from the request until it is dismissed
+    pub cleanup: Option<Cleanup>,
+}
+
+// A tab as it is kept in the session
+#[derive(Serialize, Deserialize)]
+pub struct SavedTab {
+    pub title: String,
+    pub text: String,
+    pub transcription: Option<TranscriptionResult>,
+}
+
+struct Document {
+    title: String,
+    // unique for the run, keeps the imgui id of a tab stable
+    id: usize,
+    text: String,
+    cursor: Option<usize>,
+    transcription: Option<TranscriptionResult>,
+    pages: Vec<TranscriptionResult>,
+    undo: UndoStack,
+    panels: Panels,
+}
+
+impl Document {
+    fn new(title: String, id: usize, text: String) -> Self {
+        Document {
+            undo: UndoStack::new(UNDO_CAPACITY, &text),
+            title,
+            id,
+            text,
+            cursor: None,
+            transcription: None,
+            pages: Vec::new(),
+            panels: Panels::default(),
+        }
+    }
+
+    // trade contents with the editor
+    fn exchange(&mut self, editor: &mut Editor) {
+        mem::swap(editor.text, &mut self.text);
+        mem::swap(editor.cursor, &mut self.cursor);
+        mem::swap(editor.transcription, &mut self.transcription);
+        mem::swap(editor.pages, &mut self.pages);
+        mem::swap(editor.undo, &mut self.undo);
+        mem::swap(editor.panels, &mut self.panels);
+    }
+}
+
+// Documents worked on side by side, each with its own text, OCR result and
+// undo history
+//
+// The active one lives in the editor, its entry here only holds the title
+// until another tab is picked and the two trade places.
+pub struct Tabs {
+    documents: Vec<Document>,
+    active: usize,
+    next_id: usize,
+    // the tab imgui has to be told to select on the next frame
+    select: Option<usize>,
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Tabs {
+            documents: vec![Document::new("Document 1".into(), 0, String::new())],
+            active: 0,
+            next_id: 1,
+            select: None,
+        }
+    }
+}
+
+impl Tabs {
+    // the id of the tab in the editor, which stays its own while it is open
+    pub fn active_id(&self) -> usize {
+        self.documents[self.active].id
+    }
+
+    // the document of tab `id`, for a job started in it to put its result
+    // into: `active` while it is the tab in the editor, None once it is closed
+    pub fn editor<'a>(&'a mut self, id: usize, active: Editor<'a>) -> Option<Editor<'a>> {
+        if id == self.active_id() {
+            return Some(active);
+        }
+        let index = self.active;
+        self.documents
+            .iter_mut()
+            .enumerate()
+            .find(|(other, document)| *other != index && document.id == id)
+            .map(|(_, document)| Editor {
+                text: &mut document.text,
+                cursor: &mut document.cursor,
+                transcription: &mut document.transcription,
+                pages: &mut document.pages,
+                undo: &mut document.undo,
+                panels: &mut document.panels,
+            })
+    }
+
+    // tabs of a saved session, the active one is put into the editor
+    pub fn restore(saved: Vec<SavedTab>, active: usize, mut editor: Editor) -> Self {
+        let mut tabs = Tabs::default();
+        if saved.is_empty() {
+            return tabs;
+        }
+        tabs.documents = saved
+            .into_iter()
+            .enumerate()
+            .map(|(id, saved)| {
+                let mut document = Document::new(saved.title, id, saved.text);
+                document.transcription = saved.transcription;
+                document
+            })
+            .collect();
+        tabs.next_id = tabs.documents.len();
+        tabs.active = active.min(tabs.documents.len() - 1);
+        tabs.select = Some(tabs.active);
+        tabs.documents[tabs.active].exchange(&mut editor);
+        tabs
+    }
+
+    // every tab for the session, the active one taken from the editor
+    pub fn save(&self, editor: &Editor) -> (Vec<SavedTab>, usize) {
+        let saved = self
+            .documents
+            .iter()
+            .enumerate()
+            .map(|(index, document)| {
+                let (text, transcription) = if index == self.active {
+                    (editor.text.clone(), editor.transcription.clone())
+                } else {
+                    (document.text.clone(), document.transcription.clone())
+                };
+                SavedTab {
+                    title: document.title.clone(),
+                    text,
+                    transcription,
+                }
+            })
+            .collect();
+        (saved, self.active)
+    }
+
+    fn switch(&mut self, index: usize, editor: &mut Editor) {
+        if index == self.active {
+            return;
+        }
+        self.documents[self.active].exchange(editor);
+        self.documents[index].exchange(editor);
+        self.active = index;
+    }
+
+    pub fn add(&mut self, editor: &mut Editor) {
+        let title = format!("Document {}", self.next_id + 1);
+        self.documents
+            .push(Document::new(title, self.next_id, String::new()));
+        self.next_id += 1;
+        let index = self.documents.len() - 1;
+        self.switch(index, editor);
+        self.select = Some(index);
+    }
+
+    // move to the tab `step` places on from the active one, wrapping around
+    pub fn cycle(&mut self, step: isize, editor: &mut Editor) {
+        let index = (self.active as isize + step).rem_euclid(self.documents.len() as isize);
+        self.switch(index as usize, editor);
+        self.select = Some(index as usize);
+    }
+
+    pub fn close_active(&mut self, editor: &mut Editor) {
+        self.close(self.active, editor);
+    }
+
+    fn close(&mut self, index: usize, editor: &mut Editor) {
+        if self.documents.len() == 1 {
+            // the last tab is emptied rather than closed
+            *editor.text = String::new();
+            *editor.cursor = None;
+            *editor.transcription = None;
+            editor.pages.clear();
+            *editor.panels = Panels::default();
+            return;
+        }
+        if index == self.active {
+            let neighbour = if index + 1 < self.documents.len() {
+                index + 1
+            } else {
+                index - 1
+            };
+            self.switch(neighbour, editor);
+            self.select = Some(neighbour);
+        }
+        self.documents.remove(index);
+        if self.active > index {
+            self.active -= 1;
+        }
+        if let Some(select) = &mut self.select {
+            if *select > index {
+                *select -= 1;
+            }
+        }
+    }
+
+    // the "New tab" button and the tab bar above the editor
+    pub fn draw(&mut self, ui: &Ui, mut editor: Editor) {
+        if ui.button(tr("New tab")) {
+            self.add(&mut editor);
+        }
+        ui.same_line();
+        let Some(_bar) = ui.tab_bar_with_flags(
+            "documents",
+            TabBarFlags::FITTING_POLICY_SCROLL | TabBarFlags::TAB_LIST_POPUP_BUTTON,
+        ) else {
+            return;
+        };
+        let mut picked = None;
+        let mut closed = None;
+        let select = self.select.take();
+        for (index, document) in self.documents.iter().enumerate() {
+            let mut open = true;
+            let flags = if select == Some(index) {
+                TabItemFlags::SET_SELECTED
+            } else {
+                TabItemFlags::empty()
+            };
+            let label = format!("{}###document{}", document.title, document.id);
+            if ui
+                .tab_item_with_flags(&label, Some(&mut open), flags)
+                .is_some()
+            {
+                picked = Some(index);
+            }
+            if !open {
+                closed = Some(index);
+            }
+        }
+        // imgui reports the tab it shows, which lags one frame behind a selection
+        if let (Some(index), None) = (picked, select) {
+            self.switch(index, &mut editor);
+        }
+        if let Some(index) = closed {
+            self.close(index, &mut editor);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::Local;
+
+/// Placeholders [`fill`] knows, each written in braces such as `{text}`.
+pub const PLACEHOLDERS: [&str; 4] = ["text", "date", "source", "confidence"];
+
+/// What the placeholders of a template stand for.
+pub struct Fields<'a> {
+    pub text: &'a str,
+    /// Where the text was recognized from, such as "screen" or a file name.
+    pub source: Option<&'a str>,
+    /// In `0.0..=1.0`, as [`crate::TranscriptionResult::confidence`] has it.
+    pub confidence: Option<f32>,
+}
+
+/// Put `fields` into `template`, which wraps the text for copying or saving,
+/// such as `> {text}\n\n- {source}, {date}`.
+///
+/// `{date}` is today's date and `{confidence}` a percentage, the source and
+/// confidence are left empty when unknown. Braces around anything else are
+/// kept as they are, and an empty template leaves the text as it is.
+pub fn fill(template: &str, fields: &Fields) -> String {
+    if template.is_empty() {
+        return fields.text.to_string();
+    }
+    let mut filled = String::with_capacity(template.len() + fields.text.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name = after.find('}').map(|end| &after[..end]);
+        let value = match name {
+            Some("text") => Some(fields.text.to_string()),
+            Some("date") => Some(Local::now().format("%Y-%m-%d").to_string()),
+            Some("source") => Some(fields.source.unwrap_or_default().to_string()),
+            Some("confidence") => Some(
+                fields
+                    .confidence
+                    .map(|confidence| format!("{:.0}%", confidence * 100.0))
+                    .unwrap_or_default(),
+            ),
+            _ => None,
+        };
+        match (value, name) {
+            (Some(value), Some(name)) => {
+                filled.push_str(&value);
+                rest = &after[name.len() + 1..];
+            }
+            _ => {
+                filled.push('{');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(source: Option<&'static str>, confidence: Option<f32>) -> Fields<'static> {
+        Fields {
+            text: "Hello",
+            source,
+            confidence,
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled() {
+        let filled = fill(
+            "> {text}\n- {source}, {confidence}",
+            &fields(Some("screen"), Some(0.873)),
+        );
+        assert_eq!(filled, "> Hello\n- screen, 87%");
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(fill("{date}", &fields(None, None)), today);
+    }
+
+    #[test]
+    fn unknown_fields_are_left_empty() {
+        assert_eq!(
+            fill("{text} ({source}{confidence})", &fields(None, None)),
+            "Hello ()"
+        );
+    }
+
+    #[test]
+    fn other_braces_are_kept() {
+        let filled = fill("{name} {text} {", &fields(None, None));
+        assert_eq!(filled, "{name} Hello {");
+        assert_eq!(fill("{{text}}", &fields(None, None)), "{Hello}");
+    }
+
+    #[test]
+    fn an_empty_template_keeps_the_text() {
+        assert_eq!(fill("", &fields(Some("screen"), None)), "Hello");
+    }
+}
@@ -0,0 +1,399 @@
+use crate::config::Config;
+use imgui::{
+    FontId, InputTextCallbackHandler, InputTextMultilineCallback, Key, StyleColor,
+    TextCallbackData, Ui,
+};
+use std::mem;
+
+// The text field of the main window. imgui's own multiline input does not wrap
+// lines, so a wrapped line is shown with a break in place of the space it
+// wraps at and turned back into a space in the text. Both are one byte, so a
+// position in the text is the same in what is shown, as the cursor is
+#[derive(Default)]
+pub struct TextArea {
+    // the text as shown, with the soft breaks
+    shown: String,
+    // the positions of the soft breaks in `shown`, in order
+    breaks: Vec<usize>,
+    // the buffer as the callback last saw it, to tell where an edit was made
+    seen: String,
+    // the text `shown` was made from and the width it was wrapped to, None
+    // when it is not wrapped
+    plain: String,
+    wrapped: Option<f32>,
+    // whether the field had focus last frame, imgui then keeps its own copy of
+    // the text and ignores changes to `shown` unless the callback makes them
+    active: bool,
+    // `shown` changed while the field had focus, for the callback to put in
+    reload: bool,
+    // the cursor last frame, to scroll to it once it moved
+    cursor: Option<usize>,
+    // the selection in the field as the callback last saw it, in bytes
+    selection: (i32, i32),
+}
+
+impl TextArea {
+    // draw the field `height` tall with the wrapping, line numbers and font
+    // the config asks for, whether the text was edited
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        text: &mut String,
+        cursor: &mut Option<usize>,
+        height: f32,
+        read_only: bool,
+        config: &Config,
+        monospace: Option<FontId>,
+    ) -> bool {
+        let _font = monospace
+            .filter(|_| config.monospace)
+            .map(|font| ui.push_font(font));
+        let style = ui.clone_style();
+        if !config.line_numbers {
+            let width =
+                ui.content_region_avail()[0] - style.frame_padding[0] * 2.0 - style.scrollbar_size;
+            let wrap = config.word_wrap.then_some(width);
+            return self.draw_field(ui, text, cursor, [-1.0, height], wrap, read_only);
+        }
+
+        /* the field is as tall as its text and scrolls with the numbers beside it */
+        let mut edited = false;
+        ui.child_window("text_area").size([0.0, height]).build(|| {
+            let line_height = ui.text_line_height();
+            let lines = self.shown.matches('\n').count() + 1;
+            let digits = (lines - self.breaks.len()).to_string().len().max(2);
+            let gutter = ui.calc_text_size("0")[0] * digits as f32 + style.item_spacing[0];
+            let [available, visible] = ui.content_region_avail();
+            let width = available - gutter - style.frame_padding[0] * 2.0;
+            let wrap = config.word_wrap.then_some(width);
+            let origin = ui.cursor_screen_pos();
+            let scroll = ui.scroll_y();
+
+            ui.set_cursor_pos([gutter, ui.cursor_pos()[1]]);
+            let field_height = (lines + 1) as f32 * line_height + style.frame_padding[1] * 2.0;
+            edited = self.draw_field(
+                ui,
+                text,
+                cursor,
+                [-1.0, field_height.max(visible)],
+                wrap,
+                read_only,
+            );
+
+            // only the hard lines are numbered, on the first row they are shown on
+            let draw_list = ui.get_window_draw_list();
+            let color = style[StyleColor::TextDisabled];
+            let mut number = 0;
+            let mut start = 0;
+            for (row, line) in self.shown.split('\n').enumerate() {
+                let hard = row == 0 || self.breaks.binary_search(&(start - 1)).is_err();
+                start += line.len() + 1;
+                if !hard {
+                    continue;
+                }
+                number += 1;
+                let y = style.frame_padding[1] + row as f32 * line_height;
+                if y + line_height < scroll || y > scroll + visible {
+                    continue;
+                }
+                let label = number.to_string();
+                let x = gutter - style.item_spacing[0] - ui.calc_text_size(&label)[0];
+                draw_list.add_text([origin[0] + x, origin[1] + y], color, label);
+            }
+
+            // the field does not scroll itself, follow the cursor as it moves
+            if self.active && self.cursor != *cursor {
+                if let Some(position) = *cursor {
+                    let end = position.min(self.shown.len());
+                    let row = self.shown.as_bytes()[..end]
+                        .iter()
+                        .filter(|byte| **byte == b'\n')
+                        .count();
+                    let y = style.frame_padding[1] + row as f32 * line_height;
+                    if y < scroll {
+                        ui.set_scroll_y(y);
+                    } else if y + line_height + style.frame_padding[1] > scroll + visible {
+                        ui.set_scroll_y(y + line_height + style.frame_padding[1] - visible);
+                    }
+                }
+            }
+            self.cursor = *cursor;
+        });
+        edited
+    }
+
+    // whether the field has focus, Ctrl+Z is then for the app's undo as well
+    pub fn has_focus(&self) -> bool {
+        self.active
+    }
+
+    fn draw_field(
+        &mut self,
+        ui: &Ui,
+        text: &mut String,
+        cursor: &mut Option<usize>,
+        size: [f32; 2],
+        wrap: Option<f32>,
+        read_only: bool,
+    ) -> bool {
+        // a change made outside the field, such as an undo or a new result, goes
+        // in even while it has focus, only the rewrapping is left to the callback
+        if *text != self.plain || (!self.active && wrap != self.wrapped) {
+            self.breaks = wrap.map_or_else(Vec::new, |width| {
+                wrap_points(text, width, |text| ui.calc_text_size(text)[0])
+            });
+            self.shown = replace_at(text, &self.breaks, b'\n');
+            self.seen = self.shown.clone();
+            self.plain = text.clone();
+            self.wrapped = wrap;
+            self.reload = self.active;
+        }
+        // the selection before this frame's keys, which a copy is made of
+        let selection = self.selection;
+        let handler = Rewrap {
+            ui,
+            width: wrap,
+            breaks: &mut self.breaks,
+            seen: &mut self.seen,
+            cursor,
+            selection: &mut self.selection,
+            reload: mem::take(&mut self.reload).then(|| self.shown.clone()),
+        };
+        let changed = ui
+            .input_text_multiline("##text", &mut self.shown, size)
+            // the lines of a streamed image are still coming in
+            .read_only(read_only)
+            // tabs typed or pasted are kept, as in code they are indentation
+            .allow_tab_input(true)
+            // Ctrl+Z undoes with the app's own history, which knows every change
+            .no_undo_redo(true)
+            .callback(InputTextMultilineCallback::ALWAYS, handler)
+            .build();
+        // imgui copies the soft breaks along, the text copied is put on the
+        // clipboard again with spaces in their place, as it is in `plain`
+        if self.active && copied(ui) {
+            let (start, end) = (selection.0.min(selection.1), selection.0.max(selection.1));
+            let (start, end) = (start.max(0) as usize, end.max(0) as usize);
+            if let Some(copied) = self
+                .plain
+                .get(start..end)
+                .filter(|copied| !copied.is_empty())
+            {
+                ui.set_clipboard_text(copied);
+            }
+        }
+        self.active = ui.is_item_active();
+        self.wrapped = wrap;
+        if !changed {
+            return false;
+        }
+        // an edit the callback did not see, such as one undone with escape
+        if self.seen != self.shown {
+            shift_breaks(&mut self.breaks, &self.seen, &self.shown);
+            self.seen = self.shown.clone();
+        }
+        let edited = replace_at(&self.shown, &self.breaks, b' ');
+        if edited == self.plain {
+            return false;
+        }
+        self.plain = edited.clone();
+        *text = edited;
+        true
+    }
+}
+
+// Keeps the soft breaks where they belong while the field is edited, and
+// rewraps the text each frame as imgui only lets its buffer be changed here
+struct Rewrap<'a> {
+    ui: &'a Ui,
+    width: Option<f32>,
+    breaks: &'a mut Vec<usize>,
+    seen: &'a mut String,
+    cursor: &'a mut Option<usize>,
+    selection: &'a mut (i32, i32),
+    // the text to replace the buffer with, changed outside the field
+    reload: Option<String>,
+}
+
+impl InputTextCallbackHandler for Rewrap<'_> {
+    fn on_always(&mut self, mut data: TextCallbackData) {
+        if let Some(shown) = self.reload.take() {
+            let cursor = data.cursor_pos().min(shown.len());
+            let cursor = (0..=cursor)
+                .rev()
+                .find(|&at| shown.is_char_boundary(at))
+                .unwrap_or(0);
+            data.clear();
+            data.insert_chars(0, &shown);
+            data.set_cursor_pos(cursor);
+            *self.cursor = Some(cursor);
+            *self.seen = shown;
+            return;
+        }
+        *self.cursor = Some(data.cursor_pos());
+        *self.selection = (*data.selection_start_mut(), *data.selection_end_mut());
+        if data.str() != self.seen.as_str() {
+            shift_breaks(self.breaks, self.seen, data.str());
+            *self.seen = data.str().to_string();
+        }
+        let plain = replace_at(self.seen, self.breaks, b' ');
+        let wanted = self.width.map_or_else(Vec::new, |width| {
+            wrap_points(&plain, width, |text| self.ui.calc_text_size(text)[0])
+        });
+        if wanted == *self.breaks {
+            return;
+        }
+
+        // swapping a byte moves the cursor and selection, put them back after
+        let cursor = data.cursor_pos();
+        let selection = (*data.selection_start_mut(), *data.selection_end_mut());
+        for (from, to, byte) in [
+            (&*self.breaks, &wanted, " "),
+            (&wanted, &*self.breaks, "\n"),
+        ] {
+            for &position in from
+                .iter()
+                .filter(|position| to.binary_search(position).is_err())
+            {
+                data.remove_chars(position, 1);
+                data.insert_chars(position, byte);
+            }
+        }
+        data.set_cursor_pos(cursor);
+        *data.selection_start_mut() = selection.0;
+        *data.selection_end_mut() = selection.1;
+        *self.breaks = wanted;
+        *self.seen = data.str().to_string();
+    }
+}
+
+// whether a key that copies or cuts the selection of a text field was pressed
+fn copied(ui: &Ui) -> bool {
+    let io = ui.io();
+    let command = io.key_ctrl || io.key_super;
+    (command && (ui.is_key_pressed(Key::C) || ui.is_key_pressed(Key::X)))
+        || (io.key_ctrl && ui.is_key_pressed(Key::Insert))
+        || (io.key_shift && ui.is_key_pressed(Key::Delete))
+}
+
+// keep the breaks before and after the part of `before` an edit replaced to
+// make `after`, those inside it went with the text
+fn shift_breaks(breaks: &mut Vec<usize>, before: &str, after: &str) {
+    let (before, after) = (before.as_bytes(), after.as_bytes());
+    let prefix = before
+        .iter()
+        .zip(after)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let (old_end, new_end) = (before.len() - suffix, after.len() - suffix);
+    breaks.retain_mut(|position| {
+        if *position < prefix {
+            return true;
+        }
+        if *position >= old_end {
+            *position = *position - old_end + new_end;
+            return true;
+        }
+        false
+    });
+}
+
+// `text` with the spaces or breaks at `positions` set to `byte`
+fn replace_at(text: &str, positions: &[usize], byte: u8) -> String {
+    let mut bytes = text.as_bytes().to_vec();
+    for &position in positions {
+        if let Some(old) = bytes
+            .get_mut(position)
+            .filter(|old| matches!(old, b' ' | b'\n'))
+        {
+            *old = byte;
+        }
+    }
+    String::from_utf8(bytes).unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into())
+}
+
+// the spaces to break the lines of `text` at so they fit in `width`, the
+// space before each word that would run past it, as wide as `measure` says.
+// Indentation is never broken and a word wider than the field stays on its
+// own line
+fn wrap_points(text: &str, width: f32, measure: impl Fn(&str) -> f32) -> Vec<usize> {
+    let space = measure(" ");
+    let mut breaks = Vec::new();
+    let mut position = 0;
+    for line in text.split('\n') {
+        let mut x = 0.0;
+        let mut content = false;
+        for (index, word) in line.split(' ').enumerate() {
+            let word_width = match word.is_empty() {
+                true => 0.0,
+                false => measure(word),
+            };
+            if index > 0 {
+                if content && !word.is_empty() && x + space + word_width > width {
+                    breaks.push(position - 1);
+                    x = 0.0;
+                } else {
+                    x += space;
+                }
+            }
+            x += word_width;
+            content |= !word.is_empty();
+            position += word.len() + 1;
+        }
+    }
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every character one wide, as in a monospace font
+    fn wrap(text: &str, width: f32) -> Vec<usize> {
+        wrap_points(text, width, |text| text.chars().count() as f32)
+    }
+
+    #[test]
+    fn lines_break_at_the_space_before_the_word_that_runs_over() {
+        assert_eq!(wrap("one two three four", 9.0), [7, 13]);
+        assert_eq!(wrap("short\none two three", 8.0), [13]);
+        assert!(wrap("fits", 10.0).is_empty());
+    }
+
+    #[test]
+    fn indentation_and_long_words_are_not_broken() {
+        assert_eq!(wrap("        indented words", 10.0), [16]);
+        assert_eq!(wrap("a verylongword b", 4.0), [1, 14]);
+    }
+
+    #[test]
+    fn breaks_are_swapped_only_at_spaces_and_breaks() {
+        assert_eq!(
+            replace_at("one two three", &[3, 7], b'\n'),
+            "one\ntwo\nthree"
+        );
+        assert_eq!(replace_at("one\ntwo", &[3], b' '), "one two");
+        assert_eq!(replace_at("one", &[1, 10], b'\n'), "one");
+    }
+
+    #[test]
+    fn breaks_move_with_the_text_after_an_edit() {
+        // "one two\nthree four" with a break after "two", a word typed before it
+        let mut breaks = vec![7];
+        shift_breaks(&mut breaks, "one two\nthree", "one big two\nthree");
+        assert_eq!(breaks, [11]);
+        // text deleted after the break leaves it in place
+        shift_breaks(&mut breaks, "one big two\nthree", "one big two\nthr");
+        assert_eq!(breaks, [11]);
+        // a break inside the text replaced goes with it
+        shift_breaks(&mut breaks, "one big two\nthr", "one thr");
+        assert!(breaks.is_empty());
+    }
+}
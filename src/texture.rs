@@ -0,0 +1,91 @@
+use image::RgbaImage;
+use imgui::TextureId;
+use imgui_glow_renderer::{
+    glow::{self, HasContext},
+    AutoRenderer, TextureMap,
+};
+
+// An image uploaded to the GPU so imgui can draw it
+pub struct Texture {
+    pub id: TextureId,
+    pub size: [f32; 2],
+    texture: glow::Texture,
+}
+
+impl Texture {
+    // upload an image as a new OpenGL texture and register it with the renderer
+    pub fn upload(renderer: &mut AutoRenderer, image: &RgbaImage) -> Result<Self, String> {
+        let (width, height) = image.dimensions();
+        let gl = renderer.gl_context();
+
+        let texture = unsafe {
+            let texture = gl.create_texture()?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAG_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                Some(image.as_raw()),
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+            texture
+        };
+
+        match renderer.texture_map_mut().register(texture) {
+            Some(id) => Ok(Texture {
+                id,
+                size: [width as f32, height as f32],
+                texture,
+            }),
+            None => {
+                unsafe { renderer.gl_context().delete_texture(texture) };
+                Err("Failed to register texture with the renderer".into())
+            }
+        }
+    }
+
+    // replace the pixels with those of an image of the same size, cheaper than
+    // a new texture for every frame of a camera
+    #[cfg(feature = "camera")]
+    pub fn update(&self, renderer: &AutoRenderer, image: &RgbaImage) {
+        let (width, height) = image.dimensions();
+        let gl = renderer.gl_context();
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(image.as_raw()),
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+    }
+
+    // release the OpenGL texture
+    pub fn destroy(self, renderer: &AutoRenderer) {
+        unsafe { renderer.gl_context().delete_texture(self.texture) };
+    }
+}
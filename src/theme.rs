@@ -0,0 +1,251 @@
+use crate::{config::Config, texture::Texture};
+use image::RgbaImage;
+use imgui::{
+    Context, FontAtlas, FontConfig, FontGlyphRanges, FontId, FontSource, Style, StyleColor,
+};
+use imgui_glow_renderer::AutoRenderer;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tracing::error;
+
+// used when no font is configured, Hack covers Latin, Greek and Cyrillic
+// along with most punctuation and symbols
+const BUNDLED_FONT: &[u8] = epaint_default_fonts::HACK_REGULAR;
+
+// pairs of first and last code point, ending in 0: Latin and its extensions,
+// IPA, Greek, Cyrillic, Latin extended additional, punctuation, currencies,
+// letterlike symbols to math operators, box drawing to geometric shapes
+const GLYPH_RANGES: &[u32] = &[
+    0x0020, 0x02FF, 0x0370, 0x03FF, 0x0400, 0x052F, 0x1E00, 0x1EFF, 0x2000, 0x206F, 0x20A0, 0x20CF,
+    0x2100, 0x22FF, 0x2500, 0x25FF, 0,
+];
+
+// what the fallback fonts contribute: Greek, Cyrillic, Hebrew, Arabic, Thai,
+// CJK punctuation, kana, Hangul, Arabic presentation forms and full width
+// forms, characters the main font has are kept from it. Of the CJK ideographs
+// only the common ones of Chinese and Japanese are added, all twenty thousand
+// of them would make an atlas too large to upload at the bigger sizes
+const FALLBACK_GLYPH_RANGES: &[u32] = &[
+    0x0370, 0x03FF, 0x0400, 0x052F, 0x0590, 0x05FF, 0x0600, 0x06FF, 0x0750, 0x077F, 0x0E00, 0x0E7F,
+    0x3000, 0x30FF, 0x3130, 0x318F, 0x31F0, 0x31FF, 0xAC00, 0xD7A3, 0xFB50, 0xFDFF, 0xFE70, 0xFEFF,
+    0xFF00, 0xFFEF, 0,
+];
+
+// the font atlas is this wide, and at most as tall as the texture size nearly
+// every GPU takes, beyond that the fallbacks are left out so the interface
+// still shows
+const ATLAS_WIDTH: u32 = 4096;
+const MAX_ATLAS_HEIGHT: u32 = 8192;
+
+// Color preset of the interface
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Dark,
+    Light,
+    // white on black with bordered frames
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::HighContrast];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::HighContrast => "High contrast",
+        }
+    }
+
+    fn apply(self, style: &mut Style) {
+        match self {
+            Theme::Dark => {
+                style.use_dark_colors();
+            }
+            Theme::Light => {
+                style.use_light_colors();
+                style.frame_border_size = 1.0;
+            }
+            Theme::HighContrast => {
+                style.use_dark_colors();
+                style.frame_border_size = 1.0;
+                style.window_border_size = 1.0;
+                let white = [1.0, 1.0, 1.0, 1.0];
+                let black = [0.0, 0.0, 0.0, 1.0];
+                let yellow = [1.0, 0.9, 0.0, 1.0];
+                let blue = [0.0, 0.25, 0.7, 1.0];
+                for color in [StyleColor::Text, StyleColor::Border, StyleColor::Separator] {
+                    style[color] = white;
+                }
+                style[StyleColor::TextDisabled] = [0.75, 0.75, 0.75, 1.0];
+                for color in [
+                    StyleColor::WindowBg,
+                    StyleColor::ChildBg,
+                    StyleColor::PopupBg,
+                    StyleColor::FrameBg,
+                    StyleColor::TitleBg,
+                    StyleColor::TitleBgCollapsed,
+                    StyleColor::MenuBarBg,
+                    StyleColor::ScrollbarBg,
+                    StyleColor::Button,
+                    StyleColor::Header,
+                ] {
+                    style[color] = black;
+                }
+                for color in [
+                    StyleColor::FrameBgHovered,
+                    StyleColor::ButtonHovered,
+                    StyleColor::HeaderHovered,
+                    StyleColor::TitleBgActive,
+                    StyleColor::TextSelectedBg,
+                ] {
+                    style[color] = blue;
+                }
+                for color in [
+                    StyleColor::FrameBgActive,
+                    StyleColor::ButtonActive,
+                    StyleColor::HeaderActive,
+                    StyleColor::CheckMark,
+                    StyleColor::SliderGrab,
+                    StyleColor::SliderGrabActive,
+                    StyleColor::ScrollbarGrab,
+                    StyleColor::ScrollbarGrabHovered,
+                    StyleColor::ScrollbarGrabActive,
+                ] {
+                    style[color] = yellow;
+                }
+            }
+        }
+    }
+}
+
+// Reset the style to `base`, the one imgui starts with, then color it with
+// the theme and scale its paddings, spacings and roundings
+pub fn apply_style(style: &mut Style, base: &Style, theme: Theme, scale: f32) {
+    *style = *base;
+    theme.apply(style);
+    style.scale_all_sizes(scale);
+}
+
+// Load the configured font, or the bundled one when there is none or it cannot
+// be read, with the fallback fonts merged in, rasterized at the font size
+// times the UI scale. With the editor in monospace the first monospace font
+// found is added after it with the same fallbacks, its id is returned. When
+// the fallbacks make the atlas taller than MAX_ATLAS_HEIGHT they are dropped
+pub fn add_fonts(fonts: &mut FontAtlas, config: &Config) -> Option<FontId> {
+    let size = config.font_size * config.ui_scale;
+    let font = if config.font.as_os_str().is_empty() {
+        None
+    } else {
+        match fs::read(&config.font) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                error!("Error loading font {}: {}", config.font.display(), err);
+                None
+            }
+        }
+    };
+    // fallbacks are there to cover scripts, a missing one only loses those
+    let fallbacks: Vec<Vec<u8>> = config
+        .fallback_fonts
+        .iter()
+        .filter_map(|path| fs::read(path).ok())
+        .collect();
+
+    let data = font.as_deref().unwrap_or(BUNDLED_FONT);
+    let monospace = if config.monospace {
+        let monospace = config
+            .monospace_fonts
+            .iter()
+            .find_map(|path| fs::read(path).ok());
+        if monospace.is_none() {
+            error!("None of the monospace fonts could be loaded");
+        }
+        monospace
+    } else {
+        None
+    };
+
+    fonts.tex_desired_width = ATLAS_WIDTH as i32;
+    let id = add_sources(fonts, data, monospace.as_deref(), &fallbacks, size);
+    if fallbacks.is_empty() || fonts.build_rgba32_texture().height <= MAX_ATLAS_HEIGHT {
+        return id;
+    }
+    error!(
+        "The fallback fonts make the font atlas too large at size {}, leaving them out",
+        size
+    );
+    fonts.clear();
+    add_sources(fonts, data, monospace.as_deref(), &[], size)
+}
+
+// the main font and the monospace one when there is one, returns the id of
+// the latter
+fn add_sources(
+    fonts: &mut FontAtlas,
+    data: &[u8],
+    monospace: Option<&[u8]>,
+    fallbacks: &[Vec<u8>],
+    size: f32,
+) -> Option<FontId> {
+    fonts.add_font(&font_sources(data, fallbacks, size));
+    monospace.map(|data| fonts.add_font(&font_sources(data, fallbacks, size)))
+}
+
+// `data` at `size` with the fallbacks merged in
+fn font_sources<'a>(data: &'a [u8], fallbacks: &'a [Vec<u8>], size: f32) -> Vec<FontSource<'a>> {
+    let mut sources = vec![FontSource::TtfData {
+        data,
+        size_pixels: size,
+        config: Some(FontConfig {
+            glyph_ranges: FontGlyphRanges::from_slice(GLYPH_RANGES),
+            // crisper at the small sizes the interface mostly uses
+            oversample_h: 2,
+            ..FontConfig::default()
+        }),
+    }];
+    for data in fallbacks {
+        let ranges = [
+            FontGlyphRanges::from_slice(FALLBACK_GLYPH_RANGES),
+            FontGlyphRanges::chinese_simplified_common(),
+            FontGlyphRanges::japanese(),
+        ];
+        sources.extend(ranges.into_iter().map(|glyph_ranges| FontSource::TtfData {
+            data,
+            size_pixels: size,
+            config: Some(FontConfig {
+                glyph_ranges,
+                // thousands of ideographs, oversampling would double the atlas
+                oversample_h: 1,
+                ..FontConfig::default()
+            }),
+        }));
+    }
+    sources
+}
+
+// Replace the fonts with the ones `config` asks for and upload their atlas in
+// place of `texture`, which holds the previous replacement if there was one
+//
+// Has to run between frames, the current one still draws with the old atlas.
+pub fn rebuild_fonts(
+    imgui: &mut Context,
+    renderer: &mut AutoRenderer,
+    texture: &mut Option<Texture>,
+    config: &Config,
+) -> Result<Option<FontId>, String> {
+    let fonts = imgui.fonts();
+    fonts.clear();
+    let monospace = add_fonts(fonts, config);
+    let atlas = fonts.build_rgba32_texture();
+    let image = RgbaImage::from_raw(atlas.width, atlas.height, atlas.data.to_vec())
+        .ok_or("Font atlas has an unexpected size")?;
+
+    let uploaded = Texture::upload(renderer, &image)?;
+    imgui.fonts().tex_id = uploaded.id;
+    if let Some(previous) = texture.replace(uploaded) {
+        previous.destroy(renderer);
+    }
+    Ok(monospace)
+}
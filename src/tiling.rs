@@ -0,0 +1,421 @@
+use crate::ocr::{Bounds, Line, Progress, RotatedBounds, TranscriptionResult};
+use image::DynamicImage;
+use rayon::prelude::*;
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+};
+use tracing::info;
+
+/// Images with a side longer than this are read in tiles, larger inputs make
+/// the engine run out of memory.
+pub const TILE_ABOVE: u32 = 4096;
+
+/// Side of a tile, which the engine reads at full resolution.
+pub const TILE_SIZE: u32 = 2048;
+
+/// How far neighbouring tiles reach into each other. A few lines of text, so
+/// every word cut at the edge of one tile is whole in the next.
+pub const TILE_OVERLAP: u32 = 256;
+
+/// A part of an image that is read on its own, in image pixels.
+#[derive(Clone, Copy)]
+pub struct Tile {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub row: usize,
+    pub column: usize,
+    // the part of the tile whose words it keeps, up to the middle of the
+    // overlap with each neighbour, the cores of all tiles cover the image once
+    core: Bounds,
+}
+
+// where tiles of `size` start along a side of `length`, evenly spread so that
+// neighbours overlap by at least `overlap`, with the middle of each overlap
+fn spans(length: u32, size: u32, overlap: u32) -> Vec<(u32, u32, u32, u32)> {
+    if length <= size {
+        return vec![(0, length, 0, length)];
+    }
+    let step = size.saturating_sub(overlap).max(1);
+    let count = (length - overlap).div_ceil(step) as usize;
+    let starts: Vec<u32> = (0..count)
+        .map(|index| ((length - size) as u64 * index as u64 / (count - 1) as u64) as u32)
+        .collect();
+    (0..count)
+        .map(|index| {
+            let start = starts[index];
+            let core_start = match index {
+                0 => 0,
+                _ => (start + starts[index - 1] + size) / 2,
+            };
+            let core_end = match starts.get(index + 1) {
+                Some(next) => (next + start + size) / 2,
+                None => length,
+            };
+            (start, size, core_start, core_end)
+        })
+        .collect()
+}
+
+/// Whether an image is too large to be read in one go.
+pub fn needs_tiling(width: u32, height: u32) -> bool {
+    width > TILE_ABOVE || height > TILE_ABOVE
+}
+
+/// Overlapping tiles of `size` covering an image, row by row.
+pub fn tiles(width: u32, height: u32, size: u32, overlap: u32) -> Vec<Tile> {
+    let columns = spans(width, size, overlap);
+    let mut tiles = Vec::new();
+    for (row, (top, tile_height, core_top, core_bottom)) in
+        spans(height, size, overlap).into_iter().enumerate()
+    {
+        for (column, &(left, tile_width, core_left, core_right)) in columns.iter().enumerate() {
+            tiles.push(Tile {
+                left,
+                top,
+                width: tile_width,
+                height: tile_height,
+                row,
+                column,
+                core: Bounds {
+                    left: core_left as i32,
+                    top: core_top as i32,
+                    right: core_right as i32,
+                    bottom: core_bottom as i32,
+                },
+            });
+        }
+    }
+    tiles
+}
+
+fn shifted(bounds: Bounds, x: i32, y: i32) -> Bounds {
+    Bounds {
+        left: bounds.left + x,
+        top: bounds.top + y,
+        right: bounds.right + x,
+        bottom: bounds.bottom + y,
+    }
+}
+
+fn shifted_rotated(bounds: RotatedBounds, x: i32, y: i32) -> RotatedBounds {
+    RotatedBounds {
+        center_x: bounds.center_x + x as f32,
+        center_y: bounds.center_y + y as f32,
+        ..bounds
+    }
+}
+
+fn union(a: Bounds, b: Bounds) -> Bounds {
+    Bounds {
+        left: a.left.min(b.left),
+        top: a.top.min(b.top),
+        right: a.right.max(b.right),
+        bottom: a.bottom.max(b.bottom),
+    }
+}
+
+fn contains_center(core: Bounds, bounds: Bounds) -> bool {
+    let x = (bounds.left + bounds.right) / 2;
+    let y = (bounds.top + bounds.bottom) / 2;
+    (core.left..core.right).contains(&x) && (core.top..core.bottom).contains(&y)
+}
+
+// the line rebuilt from its words, after some were dropped or joined on
+fn rebuilt(mut line: Line) -> Line {
+    if let Some(bounds) = line.words.iter().map(|word| word.bounds).reduce(union) {
+        line.bounds = bounds;
+        line.rotated_bounds = bounds.into();
+    }
+    line.text = line
+        .words
+        .iter()
+        .map(|word| word.text.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    line.confidence = line.word_confidence();
+    line
+}
+
+// whether `next` carries on `line` across the edge between two tiles side by side
+fn continues(line: &Line, next: &Line) -> bool {
+    let overlap = line.bounds.bottom.min(next.bounds.bottom) - line.bounds.top.max(next.bounds.top);
+    let height = (line.bounds.bottom - line.bounds.top).min(next.bounds.bottom - next.bounds.top);
+    let gap = next.bounds.left - line.bounds.right;
+    overlap * 2 >= height && gap < height * 2 && next.bounds.right > line.bounds.right
+}
+
+// the lines of `result` in image pixels, with only the words that lie in the
+// core of `tile` so text in the overlaps is not read twice
+fn kept(tile: Tile, result: TranscriptionResult) -> Vec<Line> {
+    let (x, y) = (tile.left as i32, tile.top as i32);
+    let mut kept = Vec::new();
+    for mut line in result.lines {
+        line.bounds = shifted(line.bounds, x, y);
+        line.rotated_bounds = shifted_rotated(line.rotated_bounds, x, y);
+        for word in &mut line.words {
+            word.bounds = shifted(word.bounds, x, y);
+            word.rotated_bounds = shifted_rotated(word.rotated_bounds, x, y);
+        }
+        if line.words.is_empty() {
+            // engines without word boxes, the line goes by its own
+            if contains_center(tile.core, line.bounds) {
+                kept.push(line);
+            }
+            continue;
+        }
+        let count = line.words.len();
+        line.words
+            .retain(|word| contains_center(tile.core, word.bounds));
+        match line.words.len() {
+            0 => {}
+            kept_words if kept_words == count => kept.push(line),
+            _ => kept.push(rebuilt(line)),
+        }
+    }
+    kept
+}
+
+// whether `line` goes on below `last` in the same column of text, overlapping
+// it across most of the narrower of the two and not far under it
+fn same_column(last: &Line, line: &Line) -> bool {
+    let overlap = last.bounds.right.min(line.bounds.right) - last.bounds.left.max(line.bounds.left);
+    let width = (last.bounds.right - last.bounds.left).min(line.bounds.right - line.bounds.left);
+    let height = (last.bounds.bottom - last.bounds.top).max(1);
+    overlap * 2 >= width && line.bounds.top - last.bounds.bottom < height * 3
+}
+
+// the lines top to bottom within each column of text and one column after the
+// other, columns side by side would be interleaved sorted by their tops alone
+fn reading_order(mut lines: Vec<Line>) -> Vec<Line> {
+    lines.sort_by_key(|line| (line.bounds.top, line.bounds.left));
+    // started in the order of their first lines
+    let mut columns: Vec<Vec<Line>> = Vec::new();
+    for line in lines {
+        let column = columns
+            .iter_mut()
+            .filter(|column| column.last().is_some_and(|last| same_column(last, &line)))
+            .min_by_key(|column| {
+                column
+                    .last()
+                    .map_or(0, |last| line.bounds.top - last.bounds.bottom)
+            });
+        match column {
+            Some(column) => column.push(line),
+            None => columns.push(vec![line]),
+        }
+    }
+    columns.into_iter().flatten().collect()
+}
+
+// the kept lines of each tile put together, lines cut at the edge between two
+// tiles side by side joined again
+fn joined(width: u32, height: u32, tiles: Vec<(Tile, Vec<Line>)>) -> TranscriptionResult {
+    // the lines kept so far, with the tile each of them ends in
+    let mut lines: Vec<(Line, Tile)> = Vec::new();
+    for (tile, kept) in tiles {
+        for line in kept {
+            let carried = lines.iter_mut().rev().find(|(previous, ends_in)| {
+                ends_in.row == tile.row
+                    && ends_in.column + 1 == tile.column
+                    && continues(previous, &line)
+            });
+            match carried {
+                Some((previous, ends_in)) if !line.words.is_empty() => {
+                    previous.words.extend(line.words);
+                    *previous = rebuilt(previous.clone());
+                    *ends_in = tile;
+                }
+                _ => lines.push((line, tile)),
+            }
+        }
+    }
+    TranscriptionResult {
+        width,
+        height,
+        lines: reading_order(lines.into_iter().map(|(line, _)| line).collect()),
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    }
+}
+
+/// Put the results of the tiles of a `width` by `height` image together, in
+/// image pixels and reading order. Every word is taken from the tile it lies
+/// in the core of, so text in the overlaps is not read twice, and lines cut at
+/// the edge between two tiles side by side are joined again.
+pub fn merge(
+    width: u32,
+    height: u32,
+    results: Vec<(Tile, TranscriptionResult)>,
+) -> TranscriptionResult {
+    let tiles = results
+        .into_iter()
+        .map(|(tile, result)| (tile, kept(tile, result)))
+        .collect();
+    joined(width, height, tiles)
+}
+
+/// Read a large image in overlapping tiles with `transcribe` and put the
+/// results together with [`merge`]. The tiles are read on rayon's pool, which
+/// the engine reads on too, so images read at once share the CPU cores rather
+/// than each taking all of them. `progress` is told the lines of each tile once
+/// it and those before it are read.
+pub fn transcribe_tiled(
+    image: &DynamicImage,
+    transcribe: impl Fn(&DynamicImage) -> Result<TranscriptionResult, Box<dyn Error>> + Sync,
+    progress: &mut dyn FnMut(Progress),
+) -> Result<TranscriptionResult, Box<dyn Error>> {
+    let (width, height) = (image.width(), image.height());
+    let tiles = tiles(width, height, TILE_SIZE, TILE_OVERLAP);
+    info!(
+        "Reading {}x{} image in {} tiles",
+        width,
+        height,
+        tiles.len()
+    );
+    let empty = TranscriptionResult {
+        width,
+        height,
+        lines: Vec::new(),
+        backend: None,
+        timings: None,
+        image: None,
+        source: None,
+    };
+    // where the lines are is only known once a tile is read
+    progress(Progress::Detected(empty.clone()));
+
+    let transcribe = &transcribe;
+    // set once a tile failed, the tiles not started yet are left unread
+    let failed = AtomicBool::new(false);
+    let mut read: Vec<Option<Vec<Line>>> = vec![None; tiles.len()];
+    let mut reported = 0;
+    let mut error = None;
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let (tiles, failed) = (&tiles, &failed);
+        scope.spawn(move || {
+            tiles
+                .par_iter()
+                .enumerate()
+                .for_each_with(sender, |sender, (index, tile)| {
+                    if failed.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let part = image.crop_imm(tile.left, tile.top, tile.width, tile.height);
+                    let result = transcribe(&part).map_err(|err| err.to_string());
+                    let _ = sender.send((index, result));
+                });
+        });
+        for (index, result) in receiver {
+            match result {
+                Ok(result) => read[index] = Some(kept(tiles[index], result)),
+                Err(err) => {
+                    failed.store(true, Ordering::Relaxed);
+                    error.get_or_insert(err);
+                }
+            }
+            while let Some(Some(lines)) = read.get(reported) {
+                progress(Progress::Read(TranscriptionResult {
+                    lines: lines.clone(),
+                    ..empty.clone()
+                }));
+                reported += 1;
+            }
+        }
+    });
+    if let Some(err) = error {
+        return Err(err.into());
+    }
+    let tiles = tiles.into_iter().zip(read.into_iter().flatten()).collect();
+    Ok(joined(width, height, tiles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::fixtures::{bounds, line, line_of, transcription, word};
+
+    // a line of words side by side, each `width` wide, starting at `left`
+    fn words(text: &str, left: i32, top: i32, width: i32) -> Line {
+        let words = text
+            .split(' ')
+            .enumerate()
+            .map(|(index, text)| {
+                let left = left + index as i32 * (width + 10);
+                word(text, bounds(left, top, left + width, top + 20))
+            })
+            .collect();
+        line_of(words)
+    }
+
+    fn texts(result: &TranscriptionResult) -> Vec<&str> {
+        result.lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn tiles_cover_the_image_once() {
+        let tiles = tiles(5000, 3000, 2048, 256);
+        assert_eq!(tiles.len(), 3 * 2);
+        for tile in &tiles {
+            assert!(tile.left + tile.width <= 5000 && tile.top + tile.height <= 3000);
+        }
+        // every pixel is in the core of exactly one tile
+        for (x, y) in [(0, 0), (1900, 100), (2048, 1500), (4999, 2999)] {
+            let cores = tiles
+                .iter()
+                .filter(|tile| {
+                    (tile.core.left..tile.core.right).contains(&x)
+                        && (tile.core.top..tile.core.bottom).contains(&y)
+                })
+                .count();
+            assert_eq!(cores, 1, "pixel {},{}", x, y);
+        }
+    }
+
+    #[test]
+    fn columns_are_read_one_after_the_other() {
+        let tile = tiles(1000, 1000, 1000, 0)[0];
+        let lines = vec![
+            line("title", bounds(100, 0, 900, 30)),
+            line("left one", bounds(100, 50, 450, 70)),
+            line("right one", bounds(550, 50, 900, 70)),
+            line("left two", bounds(100, 80, 450, 100)),
+            line("right two", bounds(550, 80, 900, 100)),
+        ];
+        let merged = merge(1000, 1000, vec![(tile, transcription(1000, 1000, lines))]);
+        assert_eq!(
+            texts(&merged),
+            ["title", "left one", "left two", "right one", "right two"]
+        );
+    }
+
+    #[test]
+    fn overlaps_are_read_once_and_cut_lines_joined() {
+        let tiles = tiles(3000, 1000, 2048, 256);
+        assert_eq!(tiles.len(), 2);
+        let (first, second) = (tiles[0], tiles[1]);
+        // "b" lies in both tiles, in the core of the first one only
+        let shared = first.core.right - 100;
+        let left = transcription(
+            first.width,
+            first.height,
+            vec![words("a b", shared - 90, 100, 80)],
+        );
+        let right = transcription(
+            second.width,
+            second.height,
+            vec![words("b c", shared - second.left as i32, 100, 80)],
+        );
+        let merged = merge(3000, 1000, vec![(first, left), (second, right)]);
+        assert_eq!(texts(&merged), ["a b c"]);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::locale::tr;
+use imgui::Ui;
+use transcribewizard::postprocess::{Transform, TransformKind};
+
+// rows of the transforms in the order they run, each switched on and off,
+// moved and removed on its own, returns whether anything changed
+pub fn edit_transforms(ui: &Ui, transforms: &mut Vec<Transform>) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+    let mut moved = None;
+    let count = transforms.len();
+    for (index, transform) in transforms.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(index);
+        changed |= ui.checkbox(transform.kind.label(), &mut transform.enabled);
+        ui.same_line();
+        if index > 0 && ui.small_button(tr("Up")) {
+            moved = Some((index, index - 1));
+        }
+        ui.same_line();
+        if index + 1 < count && ui.small_button(tr("Down")) {
+            moved = Some((index, index + 1));
+        }
+        ui.same_line();
+        if ui.small_button("x") {
+            removed = Some(index);
+        }
+        if transform.kind == TransformKind::Replace {
+            ui.indent();
+            ui.set_next_item_width(160.0);
+            changed |= ui
+                .input_text("##pattern", &mut transform.pattern)
+                .hint(tr("Pattern"))
+                .build();
+            ui.same_line();
+            ui.set_next_item_width(160.0);
+            changed |= ui
+                .input_text("##replacement", &mut transform.replacement)
+                .hint(tr("Replacement, $1 for groups"))
+                .build();
+            if let Err(err) = transform.check() {
+                ui.text_colored([0.9, 0.3, 0.3, 1.0], err);
+            }
+            ui.unindent();
+        }
+    }
+    if let Some((from, to)) = moved {
+        transforms.swap(from, to);
+        changed = true;
+    }
+    if let Some(index) = removed {
+        transforms.remove(index);
+        changed = true;
+    }
+
+    let mut added = None;
+    if let Some(_combo) = ui.begin_combo("##add", tr("Add transform")) {
+        for kind in TransformKind::ALL {
+            if ui.selectable(kind.label()) {
+                added = Some(kind);
+            }
+        }
+    }
+    if let Some(kind) = added {
+        transforms.push(Transform::new(kind));
+        changed = true;
+    }
+    changed
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::error::Error;
+
+/// Languages offered for translation as code and name, both services know them.
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("zh", "Chinese"),
+    ("cs", "Czech"),
+    ("nl", "Dutch"),
+    ("en", "English"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("hu", "Hungarian"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("es", "Spanish"),
+    ("sv", "Swedish"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+];
+
+/// Source language code that has the service detect the language.
+pub const AUTO: &str = "auto";
+
+/// Code of the language [`LANGUAGES`] names `name`.
+pub fn language_code(name: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(_, language)| *language == name)
+        .map(|(code, _)| *code)
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationService {
+    LibreTranslate,
+    DeepL,
+}
+
+impl TranslationService {
+    pub const ALL: [TranslationService; 2] = [
+        TranslationService::LibreTranslate,
+        TranslationService::DeepL,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TranslationService::LibreTranslate => "LibreTranslate",
+            TranslationService::DeepL => "DeepL",
+        }
+    }
+
+    /// A local LibreTranslate server, or the free DeepL API.
+    pub fn default_url(self) -> &'static str {
+        match self {
+            TranslationService::LibreTranslate => "http://localhost:5000",
+            TranslationService::DeepL => "https://api-free.deepl.com",
+        }
+    }
+}
+
+/// A translation API to send text through.
+pub struct Translator {
+    pub service: TranslationService,
+    /// Base URL of the API, `None` is [`TranslationService::default_url`].
+    pub url: Option<String>,
+    /// Required by DeepL, LibreTranslate servers may do without.
+    pub api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LibreTranslation {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+impl Translator {
+    /// Translate `text` between language codes, `source` may be [`AUTO`].
+    pub fn translate(
+        &self,
+        text: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let base = self
+            .url
+            .as_deref()
+            .unwrap_or(self.service.default_url())
+            .trim_end_matches('/');
+        match self.service {
+            TranslationService::LibreTranslate => {
+                let url = format!("{}/translate", base);
+                let mut body = json!({
+                    "q": text,
+                    "source": source,
+                    "target": target,
+                    "format": "text",
+                });
+                if let Some(api_key) = &self.api_key {
+                    body["api_key"] = api_key.as_str().into();
+                }
+                let translation: LibreTranslation = ureq::post(&url)
+                    .send_json(body)
+                    .map_err(|err| format!("Request to {} failed: {}", url, err))?
+                    .into_body()
+                    .read_json()
+                    .map_err(|err| format!("Unexpected response from {}: {}", url, err))?;
+                Ok(translation.translated_text)
+            }
+            TranslationService::DeepL => {
+                let url = format!("{}/v2/translate", base);
+                let api_key = self.api_key.as_deref().ok_or("DeepL needs an API key")?;
+                // DeepL detects the language when no source is given
+                let mut body = json!({
+                    "text": [text],
+                    "target_lang": target.to_uppercase(),
+                });
+                if source != AUTO {
+                    body["source_lang"] = source.to_uppercase().into();
+                }
+                let response: DeepLResponse = ureq::post(&url)
+                    .header("Authorization", &format!("DeepL-Auth-Key {}", api_key))
+                    .send_json(body)
+                    .map_err(|err| format!("Request to {} failed: {}", url, err))?
+                    .into_body()
+                    .read_json()
+                    .map_err(|err| format!("Unexpected response from {}: {}", url, err))?;
+                response
+                    .translations
+                    .into_iter()
+                    .next()
+                    .map(|translation| translation.text)
+                    .ok_or_else(|| format!("Empty response from {}", url).into())
+            }
+        }
+    }
+}
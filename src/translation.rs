@@ -0,0 +1,162 @@
+use crate::{config::Config, locale::tr};
+use imgui::{Condition, Ui};
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+use tracing::error;
+use transcribewizard::{
+    stats,
+    translate::{self, TranslationService, Translator, AUTO, LANGUAGES},
+};
+
+// translate `text` the way the settings say on a worker thread, the services
+// can take a few seconds
+pub fn spawn(config: &Config, text: &str) -> Receiver<Result<String, String>> {
+    let translator = Translator {
+        service: config.translation_service,
+        url: (!config.translation_url.is_empty()).then(|| config.translation_url.clone()),
+        api_key: (!config.translation_api_key.is_empty())
+            .then(|| config.translation_api_key.clone()),
+    };
+    let mut source = config.translate_from.clone();
+    if source == AUTO && config.translate_detected {
+        if let Some(code) = stats::detect_language(text)
+            .and_then(|language| translate::language_code(language.name))
+        {
+            source = code.to_string();
+        }
+    }
+    let target = config.translate_to.clone();
+    let text = text.to_string();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let result = translator
+            .translate(&text, &source, &target)
+            .map_err(|err| err.to_string());
+        let _ = sender.send(result);
+    });
+    receiver
+}
+
+// Window showing the text next to its translation
+#[derive(Default)]
+pub struct TranslationPanel {
+    job: Option<Receiver<Result<String, String>>>,
+    translation: String,
+}
+
+impl TranslationPanel {
+    fn poll(&mut self) {
+        let Some(job) = &self.job else {
+            return;
+        };
+        match job.try_recv() {
+            Ok(Ok(translation)) => self.translation = translation,
+            Ok(Err(err)) => self.translation = format!("Error translating text: {}", err),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.translation = "Error translating text: worker stopped".into()
+            }
+        }
+        self.job = None;
+    }
+
+    pub fn draw(&mut self, ui: &Ui, config: &mut Config, text: &mut String, open: &mut bool) {
+        self.poll();
+        let [display_width, display_height] = ui.io().display_size;
+        ui.window(format!("{}###Translate", tr("Translate")))
+            .position(
+                [display_width / 2.0, display_height / 2.0],
+                Condition::FirstUseEver,
+            )
+            .position_pivot([0.5, 0.5])
+            .size([700.0, 450.0], Condition::FirstUseEver)
+            .opened(open)
+            .build(|| {
+                let mut changed = false;
+
+                let mut service_index = TranslationService::ALL
+                    .iter()
+                    .position(|service| *service == config.translation_service)
+                    .unwrap_or(0);
+                let service_labels = TranslationService::ALL.map(TranslationService::label);
+                if ui.combo_simple_string(tr("Service"), &mut service_index, &service_labels) {
+                    config.translation_service = TranslationService::ALL[service_index];
+                    changed = true;
+                }
+                changed |= ui
+                    .input_text(tr("API URL"), &mut config.translation_url)
+                    .hint(config.translation_service.default_url())
+                    .build();
+                changed |= ui
+                    .input_text(tr("API key"), &mut config.translation_api_key)
+                    .password(true)
+                    .build();
+
+                let names: Vec<&str> = LANGUAGES.iter().map(|(_, name)| *name).collect();
+                // the source combo starts with automatic detection
+                let source_names: Vec<&str> = ["Detect language"]
+                    .into_iter()
+                    .chain(names.clone())
+                    .collect();
+                let mut source_index = LANGUAGES
+                    .iter()
+                    .position(|(code, _)| *code == config.translate_from)
+                    .map_or(0, |index| index + 1);
+                if ui.combo_simple_string(tr("From"), &mut source_index, &source_names) {
+                    config.translate_from = match source_index {
+                        0 => AUTO.into(),
+                        index => LANGUAGES[index - 1].0.into(),
+                    };
+                    changed = true;
+                }
+                if source_index == 0 {
+                    changed |= ui.checkbox(
+                        tr("Detect the language here"),
+                        &mut config.translate_detected,
+                    );
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(
+                            tr("Send the language whatlang detects instead of leaving it to the service"),
+                        );
+                    }
+                }
+                let mut target_index = LANGUAGES
+                    .iter()
+                    .position(|(code, _)| *code == config.translate_to)
+                    .unwrap_or(0);
+                if ui.combo_simple_string(tr("To"), &mut target_index, &names) {
+                    config.translate_to = LANGUAGES[target_index].0.into();
+                    changed = true;
+                }
+
+                if changed {
+                    if let Err(err) = config.save() {
+                        error!("Error saving config: {}", err);
+                    }
+                }
+
+                {
+                    let _disabled = ui.begin_disabled(self.job.is_some() || text.trim().is_empty());
+                    if ui.button(tr("Translate")) {
+                        self.job = Some(spawn(config, text));
+                    }
+                }
+                if self.job.is_some() {
+                    ui.same_line();
+                    ui.text(tr("Translating..."));
+                }
+
+                ui.columns(2, "side_by_side", false);
+                ui.input_text_multiline("##original", text, [-1.0, -1.0])
+                    .read_only(true)
+                    .build();
+                ui.next_column();
+                ui.input_text_multiline("##translation", &mut self.translation, [-1.0, -1.0])
+                    .read_only(true)
+                    .build();
+                ui.columns(1, "side_by_side", false);
+            });
+    }
+}
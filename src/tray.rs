@@ -0,0 +1,95 @@
+use image::{Rgba, RgbaImage};
+use std::error::Error;
+use tray_icon::{
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    Icon, TrayIcon, TrayIconBuilder,
+};
+
+// side of the generated tray icon in pixels
+const ICON_SIZE: u32 = 32;
+
+pub enum TrayCommand {
+    Transcribe,
+    ToggleWatch,
+    ShowWindow,
+    Quit,
+}
+
+// Tray icon with quick actions, so the app can keep running in the background
+pub struct Tray {
+    _icon: TrayIcon,
+    transcribe: MenuItem,
+    watch: CheckMenuItem,
+    show: MenuItem,
+    quit: MenuItem,
+}
+
+impl Tray {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let transcribe = MenuItem::new("OCR clipboard", true, None);
+        let watch = CheckMenuItem::new("Toggle watch mode", true, false, None);
+        let show = MenuItem::new("Show window", true, None);
+        let quit = MenuItem::new("Quit", true, None);
+
+        let menu = Menu::new();
+        menu.append_items(&[
+            &transcribe,
+            &watch,
+            &show,
+            &PredefinedMenuItem::separator(),
+            &quit,
+        ])?;
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Transcribe Wizard")
+            .with_icon(Icon::from_rgba(icon_rgba(), ICON_SIZE, ICON_SIZE)?)
+            .build()?;
+
+        Ok(Tray {
+            _icon: icon,
+            transcribe,
+            watch,
+            show,
+            quit,
+        })
+    }
+
+    // next menu action picked by the user, if any
+    pub fn poll(&self) -> Option<TrayCommand> {
+        loop {
+            let event = MenuEvent::receiver().try_recv().ok()?;
+            if event.id == *self.transcribe.id() {
+                return Some(TrayCommand::Transcribe);
+            }
+            if event.id == *self.watch.id() {
+                return Some(TrayCommand::ToggleWatch);
+            }
+            if event.id == *self.show.id() {
+                return Some(TrayCommand::ShowWindow);
+            }
+            if event.id == *self.quit.id() {
+                return Some(TrayCommand::Quit);
+            }
+        }
+    }
+
+    // keep the watch mode checkmark in sync with the app
+    pub fn set_watching(&self, watching: bool) {
+        self.watch.set_checked(watching);
+    }
+}
+
+// draw a "T" on a square so no image asset has to be shipped
+fn icon_rgba() -> Vec<u8> {
+    RgbaImage::from_fn(ICON_SIZE, ICON_SIZE, |x, y| {
+        let bar = (6..26).contains(&x) && (6..11).contains(&y);
+        let stem = (13..19).contains(&x) && (6..27).contains(&y);
+        if bar || stem {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([88, 60, 170, 255])
+        }
+    })
+    .into_raw()
+}
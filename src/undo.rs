@@ -0,0 +1,85 @@
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+// number of versions of the text that can be undone
+pub const UNDO_CAPACITY: usize = 100;
+
+// typing with shorter pauses than this is undone in one step
+const TYPING_PAUSE: Duration = Duration::from_secs(1);
+
+// Past versions of the text, recorded whenever it is seen to have changed so
+// new results, find and replace, AI cleanup and typing can all be undone
+pub struct UndoStack {
+    undo: Vec<String>,
+    redo: Vec<String>,
+    // the text when it was last tracked
+    current: String,
+    capacity: usize,
+    // when the editor was last typed in, so a run of keystrokes joins one step
+    typed_at: Option<Instant>,
+}
+
+impl UndoStack {
+    // start from `text`, which cannot be undone itself
+    pub fn new(capacity: usize, text: &str) -> Self {
+        UndoStack {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            current: text.to_string(),
+            capacity,
+            typed_at: None,
+        }
+    }
+
+    // record the text if it changed since the last call, `typing` when the
+    // change was made in the editor
+    pub fn track(&mut self, text: &str, typing: bool) {
+        if text == self.current {
+            return;
+        }
+        let now = Instant::now();
+        let joins_typing = typing
+            && self
+                .typed_at
+                .is_some_and(|at| now.duration_since(at) < TYPING_PAUSE);
+        if !joins_typing {
+            self.undo.push(mem::take(&mut self.current));
+            if self.undo.len() > self.capacity {
+                self.undo.remove(0);
+            }
+        }
+        self.current = text.to_string();
+        self.typed_at = typing.then_some(now);
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // put the previous version into `text`
+    pub fn undo(&mut self, text: &mut String) {
+        self.track(text, false);
+        if let Some(previous) = self.undo.pop() {
+            self.redo.push(mem::replace(&mut self.current, previous));
+            text.clone_from(&self.current);
+            self.typed_at = None;
+        }
+    }
+
+    // put the version the last undo replaced back into `text`
+    pub fn redo(&mut self, text: &mut String) {
+        self.track(text, false);
+        if let Some(next) = self.redo.pop() {
+            self.undo.push(mem::replace(&mut self.current, next));
+            text.clone_from(&self.current);
+            self.typed_at = None;
+        }
+    }
+}
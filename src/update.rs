@@ -0,0 +1,259 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The GitHub repository releases are looked for in.
+pub const REPOSITORY: &str = "CondeSaheki/Transcribe-Wizard";
+
+/// Where the ocrs project publishes its detection and recognition models, by
+/// file name.
+pub const OCRS_MODELS: [(&str, &str); 2] = [
+    (
+        "text-detection.rten",
+        "https://ocrs-models.s3-accelerate.amazonaws.com/text-detection.rten",
+    ),
+    (
+        "text-recognition.rten",
+        "https://ocrs-models.s3-accelerate.amazonaws.com/text-recognition.rten",
+    ),
+];
+
+/// The version of this build.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The newest release of the app on GitHub.
+#[derive(Deserialize)]
+pub struct Release {
+    #[serde(rename = "tag_name")]
+    pub tag: String,
+    /// The release page.
+    #[serde(rename = "html_url")]
+    pub url: String,
+    pub assets: Vec<Asset>,
+}
+
+/// A file attached to a release.
+#[derive(Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub size: u64,
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+    /// When the file was uploaded, in RFC 3339 as GitHub has it.
+    #[serde(rename = "updated_at")]
+    pub updated: String,
+    /// The hash GitHub computed of the file, such as "sha256:...", missing
+    /// for files uploaded before it did.
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+/// Fetch the newest release from GitHub.
+pub fn latest_release() -> Result<Release, Box<dyn Error>> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        REPOSITORY
+    );
+    ureq::get(&url)
+        // GitHub turns down requests without one
+        .header("User-Agent", &format!("transcribewizard/{}", VERSION))
+        .header("Accept", "application/vnd.github+json")
+        .call()
+        .map_err(|err| format!("Request to {} failed: {}", url, err))?
+        .into_body()
+        .read_json()
+        .map_err(|err| format!("Unexpected response from {}: {}", url, err).into())
+}
+
+// the numbers of a version such as "v1.2.10", a suffix such as "-beta" is
+// left out
+fn numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|number| number.parse().unwrap_or(0))
+        .collect()
+}
+
+impl Release {
+    /// Whether the release is of a version after this build, compared number
+    /// by number.
+    pub fn is_newer(&self) -> bool {
+        numbers(&self.tag) > numbers(VERSION)
+    }
+
+    /// The model files of the release that are newer than those of `models`
+    /// with the same name, each with the file it would replace. Models that
+    /// are missing are counted as out of date.
+    pub fn model_updates(&self, models: &[PathBuf]) -> Vec<(Asset, PathBuf)> {
+        self.assets
+            .iter()
+            .filter(|asset| asset.name.ends_with(".rten"))
+            .filter_map(|asset| {
+                let model = models
+                    .iter()
+                    .find(|model| model.file_name().is_some_and(|name| *name == *asset.name))?;
+                is_outdated(model, asset).then(|| (asset.clone(), model.clone()))
+            })
+            .collect()
+    }
+}
+
+// whether `asset` differs from the file at `path` in size or was uploaded
+// after it was written
+fn is_outdated(path: &Path, asset: &Asset) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return true;
+    };
+    if metadata.len() != asset.size {
+        return true;
+    }
+    let uploaded = chrono::DateTime::parse_from_rfc3339(&asset.updated)
+        .map(|uploaded| SystemTime::from(uploaded.to_utc()));
+    match (uploaded, metadata.modified()) {
+        (Ok(uploaded), Ok(modified)) => uploaded > modified,
+        _ => false,
+    }
+}
+
+/// Download `asset` to `path`. It is written next to it first and moved in
+/// place once complete and matching the digest published with it, so a
+/// failed or altered download keeps the file that was there. Assets without
+/// a SHA-256 digest are not downloaded.
+pub fn download(asset: &Asset, path: &Path) -> Result<(), Box<dyn Error>> {
+    let digest = asset
+        .digest
+        .as_deref()
+        .and_then(|digest| digest.strip_prefix("sha256:"))
+        .ok_or_else(|| format!("{} was published without a SHA-256 digest", asset.name))?;
+    fetch(&asset.url, path, Some((asset.size, digest)))
+}
+
+/// Download the ocrs model named `name`, one of [`OCRS_MODELS`], to `path`,
+/// making its directory when missing.
+pub fn download_ocrs_model(name: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    let (_, url) = OCRS_MODELS
+        .iter()
+        .find(|(model, _)| *model == name)
+        .ok_or_else(|| format!("ocrs publishes no model named {}", name))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("Failed to create {}: {}", dir.display(), err))?;
+    }
+    fetch(url, path, None)
+}
+
+// `bytes` in lowercase hex, as digests are written
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// write what `url` answers with to `path` through a file next to it, checking
+// it came to the size and SHA-256 digest in hex of `expected` when known
+fn fetch(url: &str, path: &Path, expected: Option<(u64, &str)>) -> Result<(), Box<dyn Error>> {
+    let partial = path.with_extension("partial");
+    let mut body = ureq::get(url)
+        .header("User-Agent", &format!("transcribewizard/{}", VERSION))
+        .call()
+        .map_err(|err| format!("Request to {} failed: {}", url, err))?
+        .into_body();
+    let mut hasher = Sha256::new();
+    let written = fs::File::create(&partial).and_then(|mut file| {
+        let mut reader = body.as_reader();
+        let mut buffer = vec![0; 64 * 1024];
+        let mut written = 0;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(written);
+            }
+            hasher.update(&buffer[..read]);
+            file.write_all(&buffer[..read])?;
+            written += read as u64;
+        }
+    });
+    let written =
+        written.map_err(|err| format!("Failed to write {}: {}", partial.display(), err))?;
+    if let Some((size, digest)) = expected {
+        let error = if written != size {
+            Some(format!(
+                "Download of {} stopped after {} of {} bytes",
+                url, written, size
+            ))
+        } else if !hex(&hasher.finalize()).eq_ignore_ascii_case(digest) {
+            Some(format!("Download of {} does not match its digest", url))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            let _ = fs::remove_file(&partial);
+            return Err(error.into());
+        }
+    }
+    fs::rename(&partial, path)
+        .map_err(|err| format!("Failed to move {}: {}", partial.display(), err).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn release(tag: &str) -> Release {
+        Release {
+            tag: tag.into(),
+            url: String::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    fn asset(size: u64, updated: SystemTime) -> Asset {
+        Asset {
+            name: "text-recognition.rten".into(),
+            size,
+            url: String::new(),
+            updated: chrono::DateTime::<chrono::Utc>::from(updated).to_rfc3339(),
+            digest: None,
+        }
+    }
+
+    #[test]
+    fn numbers_leave_out_prefix_and_suffix() {
+        assert_eq!(numbers("v1.2.10"), [1, 2, 10]);
+        assert_eq!(numbers("0.3.0-beta.2"), [0, 3, 0]);
+        assert_eq!(numbers("2.0+build"), [2, 0]);
+        assert_eq!(numbers("v1.x"), [1, 0]);
+    }
+
+    #[test]
+    fn newer_compares_number_by_number() {
+        assert!(numbers("v1.10.0") > numbers("v1.9.3"));
+        assert!(release("v9999.0.0").is_newer());
+        assert!(!release(&format!("v{}", VERSION)).is_newer());
+        assert!(!release("v0.0.0").is_newer());
+    }
+
+    #[test]
+    fn outdated_by_size_or_upload_date() {
+        let path = std::env::temp_dir().join(format!(
+            "transcribewizard-update-{}.rten",
+            std::process::id()
+        ));
+        assert!(is_outdated(&path, &asset(4, UNIX_EPOCH)));
+        fs::write(&path, b"rten").unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert!(!is_outdated(&path, &asset(4, UNIX_EPOCH)));
+        assert!(is_outdated(&path, &asset(5, UNIX_EPOCH)));
+        assert!(is_outdated(&path, &asset(4, SystemTime::now() + day)));
+        fs::remove_file(&path).unwrap();
+    }
+}
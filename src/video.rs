@@ -0,0 +1,170 @@
+use crate::{backend::OcrBackend, job::JobControl, ocr, preprocess::Preprocessing, subtitle::Cue};
+use image::{DynamicImage, RgbImage};
+use std::{
+    error::Error,
+    io::{ErrorKind, Read},
+    path::Path,
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// File extensions offered when picking a video, ffmpeg reads many more.
+pub const EXTENSIONS: &[&str] = &["mp4", "mkv", "webm", "mov", "avi"];
+
+// Size and length of the first video stream, read with ffprobe
+struct VideoInfo {
+    width: u32,
+    height: u32,
+    duration: Option<Duration>,
+}
+
+fn probe(path: &Path) -> Result<VideoInfo, Box<dyn Error>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height:format=duration"])
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(path)
+        .output()
+        .map_err(|err| format!("Failed to run ffprobe, is ffmpeg installed? {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to read video {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    let mut width = None;
+    let mut height = None;
+    let mut duration = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line.split_once('=') {
+            Some(("width", value)) => width = value.parse().ok(),
+            Some(("height", value)) => height = value.parse().ok(),
+            Some(("duration", value)) => {
+                duration = value
+                    .parse()
+                    .ok()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            }
+            _ => (),
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => Ok(VideoInfo {
+            width,
+            height,
+            duration,
+        }),
+        _ => Err(format!("No video stream in {}", path.display()).into()),
+    }
+}
+
+// The ffmpeg process decoding the frames, stopped when reading them ends early
+struct Decoder(Child);
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        // fails harmlessly once it exited by itself
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// OCR a frame every `interval` of a video and collect the on-screen text.
+///
+/// Frames showing the same text as the one before extend the previous cue, so
+/// a subtitle shown for five seconds is one cue rather than five. Frames come
+/// from the `ffmpeg` command, `on_progress` gets the fraction done after each.
+/// `control` pauses and cancels the job between frames.
+pub fn transcribe_video(
+    backend: &dyn OcrBackend,
+    path: &Path,
+    interval: Duration,
+    preprocessing: &Preprocessing,
+    control: &JobControl,
+    mut on_progress: impl FnMut(f32),
+) -> Result<Vec<Cue>, Box<dyn Error>> {
+    let info = probe(path)?;
+    let interval = interval.max(Duration::from_millis(40));
+
+    // rotation metadata is ignored so frames keep the size ffprobe reported
+    let mut ffmpeg = Decoder(
+        Command::new("ffmpeg")
+            .args(["-v", "error", "-noautorotate", "-i"])
+            .arg(path)
+            .args(["-vf", &format!("fps=1/{}", interval.as_secs_f64())])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Failed to run ffmpeg, is it installed? {}", err))?,
+    );
+    let mut frames = ffmpeg
+        .0
+        .stdout
+        .take()
+        .ok_or("Failed to read ffmpeg output")?;
+    // read along on a thread, ffmpeg would stop once the pipe is full
+    let errors = ffmpeg.0.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut errors = String::new();
+            let _ = stderr.read_to_string(&mut errors);
+            errors
+        })
+    });
+
+    let mut cues: Vec<Cue> = Vec::new();
+    let mut previous = String::new();
+    let mut buffer = vec![0; info.width as usize * info.height as usize * 3];
+    for index in 0.. {
+        control.checkpoint()?;
+        match frames.read_exact(&mut buffer) {
+            Ok(()) => (),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(format!("Failed to read video frame: {}", err).into()),
+        }
+        let start = interval * index;
+        let frame = RgbImage::from_raw(info.width, info.height, buffer.clone())
+            .ok_or("Video frame has the wrong size")?;
+        let transcription =
+            ocr::transcribe_preprocessed(backend, &DynamicImage::ImageRgb8(frame), preprocessing)?;
+
+        let text = transcription
+            .lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let normalized = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        match cues.last_mut() {
+            Some(cue) if !normalized.is_empty() && normalized == previous => {
+                cue.end = start + interval
+            }
+            _ if !normalized.is_empty() => cues.push(Cue {
+                start,
+                end: start + interval,
+                text,
+                speaker: None,
+            }),
+            _ => (),
+        }
+        previous = normalized;
+
+        if let Some(duration) = info.duration {
+            on_progress(((start + interval).as_secs_f32() / duration.as_secs_f32()).min(1.0));
+        }
+    }
+
+    let status = ffmpeg.0.wait()?;
+    let errors = errors
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    if !status.success() {
+        return Err(format!("ffmpeg failed: {}", errors.trim()).into());
+    }
+    Ok(cues)
+}
@@ -0,0 +1,272 @@
+use crate::{
+    clipboard::{ClipboardProvider, SystemClipboard},
+    config::Selection,
+};
+use clipboard_rs::{
+    common::RustImage, ClipboardHandler, ClipboardWatcher, ClipboardWatcherContext, ContentFormat,
+    WatcherShutdown,
+};
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+use tracing::info;
+#[cfg(target_os = "linux")]
+use wl_clipboard_rs::{
+    paste::Seat,
+    watch::{CancelHandle, ClipboardType, Watcher},
+};
+
+struct ChangeHandler(Sender<()>);
+
+impl ClipboardHandler for ChangeHandler {
+    fn on_clipboard_change(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+// A watch of the clipboard on a background thread for watch mode, stopped once
+// dropped. The system tells of changes where it can: the data control
+// protocols of Wayland, XFixes on X11 for the clipboard and the primary
+// selection alike and the clipboard listener of Windows, while macOS is asked
+// for its change count. Elsewhere the clipboard is read every `interval` and
+// compared with what it held before
+pub struct ClipboardWatch {
+    changes: Receiver<()>,
+    // what stops the thread, taken when the watch is dropped
+    stop: Option<Stop>,
+}
+
+enum Stop {
+    #[cfg(target_os = "linux")]
+    Wayland(CancelHandle),
+    // the connection and the window the thread waits on events of
+    #[cfg(target_os = "linux")]
+    X11(x11::PrimaryWatch),
+    Native(WatcherShutdown),
+    Polling(Sender<()>),
+}
+
+impl ClipboardWatch {
+    pub fn start(selection: Selection, interval: Duration) -> Self {
+        let (sender, changes) = mpsc::channel();
+        #[cfg(target_os = "linux")]
+        match watch_wayland(selection) {
+            Ok(mut watcher) => {
+                info!("Watching the clipboard through Wayland data control");
+                let stop = Stop::Wayland(watcher.cancel_handle());
+                thread::spawn(move || {
+                    // the first event is the selection as it already was
+                    let _ = watcher.next_event();
+                    while let Ok(Some(_)) = watcher.next_event() {
+                        if sender.send(()).is_err() {
+                            break;
+                        }
+                    }
+                });
+                return ClipboardWatch {
+                    changes,
+                    stop: Some(stop),
+                };
+            }
+            Err(err) => info!("Not watching the clipboard through Wayland: {}", err),
+        }
+        #[cfg(target_os = "linux")]
+        if selection == Selection::Primary {
+            match x11::PrimaryWatch::start(sender.clone()) {
+                Ok(watch) => {
+                    info!("Watching the primary selection through XFixes");
+                    return ClipboardWatch {
+                        changes,
+                        stop: Some(Stop::X11(watch)),
+                    };
+                }
+                Err(err) => info!("Not watching the primary selection through X11: {}", err),
+            }
+        }
+        if selection == Selection::Clipboard {
+            match ClipboardWatcherContext::new() {
+                Ok(mut watcher) => {
+                    info!("Watching the clipboard through system notifications");
+                    let stop = Stop::Native(watcher.get_shutdown_channel());
+                    thread::spawn(move || watcher.add_handler(ChangeHandler(sender)).start_watch());
+                    return ClipboardWatch {
+                        changes,
+                        stop: Some(stop),
+                    };
+                }
+                Err(err) => info!("Not watching the clipboard through the system: {}", err),
+            }
+        }
+        info!(
+            "Reading the clipboard every {:.1}s to notice changes",
+            interval.as_secs_f32()
+        );
+        let (stop, stopped) = mpsc::channel();
+        thread::spawn(move || poll(selection, interval, sender, stopped));
+        ClipboardWatch {
+            changes,
+            stop: Some(Stop::Polling(stop)),
+        }
+    }
+
+    // whether the clipboard changed since the last call
+    pub fn changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+
+    // whether the clipboard is read every interval, the other watches have
+    // no use for it
+    pub fn polls(&self) -> bool {
+        matches!(self.stop, Some(Stop::Polling(_)))
+    }
+}
+
+impl Drop for ClipboardWatch {
+    fn drop(&mut self) {
+        match self.stop.take() {
+            #[cfg(target_os = "linux")]
+            Some(Stop::Wayland(cancel)) => cancel.cancel(),
+            #[cfg(target_os = "linux")]
+            Some(Stop::X11(watch)) => watch.stop(),
+            Some(Stop::Native(shutdown)) => shutdown.stop(),
+            // the thread notices its end of the channel is gone
+            Some(Stop::Polling(stop)) => drop(stop),
+            None => {}
+        }
+    }
+}
+
+// a watcher of the selection over the data control protocol the compositor
+// speaks, wlr or ext, when this is a Wayland session
+#[cfg(target_os = "linux")]
+fn watch_wayland(selection: Selection) -> Result<Watcher, Box<dyn std::error::Error>> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Err("not a Wayland session".into());
+    }
+    let clipboard = match selection {
+        Selection::Clipboard => ClipboardType::Regular,
+        Selection::Primary => ClipboardType::Primary,
+    };
+    Ok(Watcher::new(clipboard, Seat::Unspecified)?)
+}
+
+// read the clipboard every `interval` and tell when it holds an image that is
+// not the one read before, until the other end of `stopped` is dropped. The
+// formats on offer are compared first and the encoded image after, so an
+// unchanged clipboard is never decoded; only where the image can't be read
+// undecoded are its pixels hashed
+fn poll(selection: Selection, interval: Duration, sender: Sender<()>, stopped: Receiver<()>) {
+    let clipboard = SystemClipboard::open(selection, selection);
+    let fingerprint = || {
+        let formats = clipboard.available_formats().unwrap_or_default();
+        if !clipboard.has(ContentFormat::Image) {
+            return None;
+        }
+        let encoded = formats
+            .iter()
+            .filter(|format| {
+                let format = format.to_lowercase();
+                ["png", "image", "bitmap", "dib", "tiff"]
+                    .iter()
+                    .any(|name| format.contains(name))
+            })
+            .find_map(|format| clipboard.get_buffer(format).ok());
+        let hash = match encoded {
+            Some(bytes) => blake3::hash(&bytes),
+            None => blake3::hash(clipboard.get_image().ok()?.to_rgba8().ok()?.as_raw()),
+        };
+        Some((formats, hash))
+    };
+    let mut last = fingerprint();
+    while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+        let current = fingerprint();
+        if current.is_some() && current != last && sender.send(()).is_err() {
+            return;
+        }
+        last = current;
+    }
+}
+
+// the primary selection of X11, which the clipboard watcher leaves out, told
+// of through XFixes as its owner changes
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::{error::Error, sync::mpsc::Sender, sync::Arc, thread};
+    use x11rb::{
+        connection::Connection,
+        protocol::{
+            xfixes::{ConnectionExt as _, SelectionEventMask},
+            xproto::{
+                AtomEnum, ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask,
+                WindowClass,
+            },
+            Event,
+        },
+        rust_connection::RustConnection,
+        COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT,
+    };
+
+    pub struct PrimaryWatch {
+        connection: Arc<RustConnection>,
+        // the window selection events go to, a message to it ends the thread
+        window: u32,
+    }
+
+    impl PrimaryWatch {
+        pub fn start(sender: Sender<()>) -> Result<Self, Box<dyn Error>> {
+            if std::env::var_os("DISPLAY").is_none() {
+                return Err("not an X11 session".into());
+            }
+            let (connection, screen) = x11rb::connect(None)?;
+            connection.xfixes_query_version(5, 0)?.reply()?;
+            let root = connection.setup().roots[screen].root;
+            let window = connection.generate_id()?;
+            connection.create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                COPY_FROM_PARENT,
+                &CreateWindowAux::new(),
+            )?;
+            connection.xfixes_select_selection_input(
+                window,
+                AtomEnum::PRIMARY.into(),
+                SelectionEventMask::SET_SELECTION_OWNER,
+            )?;
+            connection.flush()?;
+            let connection = Arc::new(connection);
+            let events = connection.clone();
+            thread::spawn(move || {
+                while let Ok(event) = events.wait_for_event() {
+                    match event {
+                        Event::XfixesSelectionNotify(_) if sender.send(()).is_err() => break,
+                        Event::ClientMessage(message) if message.window == window => break,
+                        _ => {}
+                    }
+                }
+            });
+            Ok(PrimaryWatch { connection, window })
+        }
+
+        // wake the thread with a message to its window, which it stops at
+        pub fn stop(self) {
+            let message = ClientMessageEvent::new(32, self.window, AtomEnum::NONE, [0u32; 5]);
+            let sent = self
+                .connection
+                .send_event(false, self.window, EventMask::NO_EVENT, message)
+                .and_then(|_| self.connection.destroy_window(self.window))
+                .map(|_| ());
+            if sent.is_ok() {
+                let _ = self.connection.flush();
+            }
+        }
+    }
+}
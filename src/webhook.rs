@@ -0,0 +1,46 @@
+use crate::jobs::Background;
+use transcribewizard::{
+    export::{self, ExportFormat},
+    TranscriptionResult,
+};
+
+// Posts every new result as JSON to the configured URL on a thread of its own,
+// so a slow server does not hold up the window, and collects what went wrong
+#[derive(Default)]
+pub struct Webhook {
+    background: Background<String>,
+}
+
+impl Webhook {
+    // send the result as the JSON of "Save as", with `authorization` as the
+    // Authorization header unless it is empty
+    pub fn post(
+        &self,
+        url: &str,
+        authorization: &str,
+        text: &str,
+        transcription: Option<&TranscriptionResult>,
+    ) {
+        let json = match export::render(ExportFormat::Json, text, transcription) {
+            Ok(json) => json,
+            Err(err) => return self.background.fail(err.to_string()),
+        };
+        let url = url.to_string();
+        let authorization = authorization.to_string();
+        self.background.spawn(move || {
+            let mut request = ureq::post(&url).header("Content-Type", "application/json");
+            if !authorization.is_empty() {
+                request = request.header("Authorization", &authorization);
+            }
+            request
+                .send(json)
+                .map(|_| ())
+                .map_err(|err| format!("Request to {} failed: {}", url, err))
+        });
+    }
+
+    // the requests that failed since the last call
+    pub fn failures(&self) -> Vec<String> {
+        self.background.failures()
+    }
+}